@@ -0,0 +1,225 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+/// The access control list of an object, as returned by [`ObjectStore::get_acl`]
+///
+/// Stores model access control differently, and so this is a best-effort normalization
+/// of the store's representation:
+///
+/// * S3 returns the [S3 object ACL], with the canonical user ID of the owner
+/// * GCS returns the [GCS object ACL], which is empty for buckets with uniform bucket-level
+///   access, and the entity of the owner
+/// * Azure returns the [POSIX ACL] of the blob, available only for storage accounts with a
+///   hierarchical namespace, and the object ID of the owning user
+///
+/// [`ObjectStore::get_acl`]: crate::ObjectStore::get_acl
+/// [S3 object ACL]: https://docs.aws.amazon.com/AmazonS3/latest/userguide/acl-overview.html
+/// [GCS object ACL]: https://cloud.google.com/storage/docs/access-control/lists
+/// [POSIX ACL]: https://learn.microsoft.com/en-us/azure/storage/blobs/data-lake-storage-access-control
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Acl {
+    /// The owner of the object, if reported by the store
+    pub owner: Option<String>,
+    /// The grants of the object
+    pub grants: Vec<AclGrant>,
+}
+
+/// A permission granted to an [`AclGrantee`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AclGrant {
+    /// The grantee
+    pub grantee: AclGrantee,
+    /// The permission granted
+    pub permission: AclPermission,
+}
+
+impl AclGrant {
+    /// Create a new [`AclGrant`] of `permission` to `grantee`
+    pub fn new(grantee: AclGrantee, permission: AclPermission) -> Self {
+        Self {
+            grantee,
+            permission,
+        }
+    }
+}
+
+/// The recipient of an [`AclGrant`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AclGrantee {
+    /// A user, identified by a store-specific ID, such as an S3 canonical user ID,
+    /// an email address or an Azure object ID
+    User(String),
+    /// A group of users, identified by a store-specific ID, such as an S3 group URI
+    /// or a GCS group, domain or project entity
+    Group(String),
+    /// Any user, including anonymous users
+    AllUsers,
+    /// Any authenticated user
+    AuthenticatedUsers,
+}
+
+/// The permission of an [`AclGrant`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AclPermission {
+    /// Read the object
+    Read,
+    /// Write the object
+    Write,
+    /// Read the ACL of the object
+    ReadAcl,
+    /// Write the ACL of the object
+    WriteAcl,
+    /// All of the above, i.e. the S3 `FULL_CONTROL` permission or GCS `OWNER` role
+    FullControl,
+}
+
+/// A predefined ("canned") ACL to apply to an object when it is written
+///
+/// See [`PutOptions::acl`] and the [S3 canned ACLs] and [GCS predefined ACLs] for the
+/// grants applied by each. Not all are supported by every store, with
+/// [`CannedAcl::PublicReadWrite`] and [`CannedAcl::AwsExecRead`] specific to S3, and
+/// [`CannedAcl::ProjectPrivate`] specific to GCS. Writes specifying a canned ACL not
+/// supported by the store return [`Error::NotSupported`]
+///
+/// Azure does not support object ACLs, instead controlling anonymous access with the
+/// [public access level] of the container, and so ignores this
+///
+/// [`PutOptions::acl`]: crate::PutOptions::acl
+/// [`Error::NotSupported`]: crate::Error::NotSupported
+/// [S3 canned ACLs]: https://docs.aws.amazon.com/AmazonS3/latest/userguide/acl-overview.html#canned-acl
+/// [GCS predefined ACLs]: https://cloud.google.com/storage/docs/access-control/lists#predefined-acl
+/// [public access level]: https://learn.microsoft.com/en-us/azure/storage/blobs/anonymous-read-access-configure
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CannedAcl {
+    /// The owner has full control, and no one else has access
+    Private,
+    /// The owner has full control, and anyone can read
+    PublicRead,
+    /// The owner has full control, and anyone can read and write
+    PublicReadWrite,
+    /// The owner has full control, and any authenticated user can read
+    AuthenticatedRead,
+    /// The owner has full control, and EC2 can read AMI bundles
+    AwsExecRead,
+    /// The owner has full control, and the bucket owner can read
+    BucketOwnerRead,
+    /// Both the owner and the bucket owner have full control
+    BucketOwnerFullControl,
+    /// The owner has full control, and project members can access according to their roles
+    ProjectPrivate,
+}
+
+impl CannedAcl {
+    /// Returns the name of this ACL, as used by the `x-amz-acl` and `x-goog-acl` headers
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Private => "private",
+            Self::PublicRead => "public-read",
+            Self::PublicReadWrite => "public-read-write",
+            Self::AuthenticatedRead => "authenticated-read",
+            Self::AwsExecRead => "aws-exec-read",
+            Self::BucketOwnerRead => "bucket-owner-read",
+            Self::BucketOwnerFullControl => "bucket-owner-full-control",
+            Self::ProjectPrivate => "project-private",
+        }
+    }
+
+    /// Returns an error if this ACL is not supported by `store`, which supports `supported`
+    #[cfg(any(feature = "aws", feature = "gcp"))]
+    pub(crate) fn check(self, store: &'static str, supported: &[Self]) -> crate::Result<Self> {
+        match supported.contains(&self) {
+            true => Ok(self),
+            false => Err(crate::Error::NotSupported {
+                source: format!("canned ACL \"{self}\" is not supported by {store}").into(),
+            }),
+        }
+    }
+}
+
+impl std::fmt::Display for CannedAcl {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for CannedAcl {
+    type Err = ();
+
+    /// Parses either the header name, e.g. `public-read`, or the name used by the
+    /// `predefinedAcl` parameter of the GCS JSON API, e.g. `publicRead`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "private" => Ok(Self::Private),
+            "public-read" | "publicRead" => Ok(Self::PublicRead),
+            "public-read-write" => Ok(Self::PublicReadWrite),
+            "authenticated-read" | "authenticatedRead" => Ok(Self::AuthenticatedRead),
+            "aws-exec-read" => Ok(Self::AwsExecRead),
+            "bucket-owner-read" | "bucketOwnerRead" => Ok(Self::BucketOwnerRead),
+            "bucket-owner-full-control" | "bucketOwnerFullControl" => {
+                Ok(Self::BucketOwnerFullControl)
+            }
+            "project-private" | "projectPrivate" => Ok(Self::ProjectPrivate),
+            _ => Err(()),
+        }
+    }
+}
+
+#[cfg(feature = "cloud")]
+impl crate::config::Parse for CannedAcl {
+    fn parse(v: &str) -> crate::Result<Self> {
+        v.parse().map_err(|_| crate::Error::Generic {
+            store: "Config",
+            source: format!("\"{v}\" is not a valid canned ACL").into(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_canned_acl() {
+        let all = [
+            CannedAcl::Private,
+            CannedAcl::PublicRead,
+            CannedAcl::PublicReadWrite,
+            CannedAcl::AuthenticatedRead,
+            CannedAcl::AwsExecRead,
+            CannedAcl::BucketOwnerRead,
+            CannedAcl::BucketOwnerFullControl,
+            CannedAcl::ProjectPrivate,
+        ];
+        for acl in all {
+            assert_eq!(acl.as_str().parse::<CannedAcl>().unwrap(), acl);
+        }
+        assert_eq!("publicRead".parse::<CannedAcl>(), Ok(CannedAcl::PublicRead));
+        assert!("public".parse::<CannedAcl>().is_err());
+        assert!("Public-Read".parse::<CannedAcl>().is_err());
+    }
+
+    #[cfg(any(feature = "aws", feature = "gcp"))]
+    #[test]
+    fn test_canned_acl_check() {
+        let supported = [CannedAcl::Private, CannedAcl::PublicRead];
+        CannedAcl::PublicRead.check("S3", &supported).unwrap();
+        let err = CannedAcl::ProjectPrivate
+            .check("S3", &supported)
+            .unwrap_err();
+        assert!(matches!(err, crate::Error::NotSupported { .. }), "{err}");
+    }
+}
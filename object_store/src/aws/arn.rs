@@ -0,0 +1,180 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use crate::aws::STORE;
+use snafu::Snafu;
+
+#[derive(Debug, Snafu)]
+enum Error {
+    #[snafu(display("Invalid access point ARN '{}': {}", arn, reason))]
+    InvalidArn { arn: String, reason: &'static str },
+
+    #[snafu(display("Unsupported partition '{}' in access point ARN '{}'", partition, arn))]
+    UnsupportedPartition { arn: String, partition: String },
+}
+
+impl From<Error> for crate::Error {
+    fn from(source: Error) -> Self {
+        Self::Generic {
+            store: STORE,
+            source: Box::new(source),
+        }
+    }
+}
+
+/// An S3 access point ARN provided in place of a bucket name
+///
+/// <https://docs.aws.amazon.com/AmazonS3/latest/userguide/using-access-points.html>
+/// <https://docs.aws.amazon.com/AmazonS3/latest/userguide/MultiRegionAccessPoints.html>
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct AccessPointArn {
+    /// The domain suffix of the partition, e.g. `amazonaws.com`
+    dns_suffix: &'static str,
+    /// The region of the access point, or `None` for a multi-region access point
+    region: Option<String>,
+    account: String,
+    name: String,
+}
+
+impl AccessPointArn {
+    /// Returns true if `bucket` should be interpreted as an ARN
+    pub(crate) fn is_arn(bucket: &str) -> bool {
+        bucket.starts_with("arn:")
+    }
+
+    /// Parse an access point ARN of the form
+    /// `arn:{partition}:s3:{region}:{account}:accesspoint/{name}`
+    ///
+    /// Multi-region access points have an empty region, and are named by their alias
+    pub(crate) fn parse(arn: &str) -> crate::Result<Self> {
+        let invalid = |reason| Error::InvalidArn {
+            arn: arn.to_string(),
+            reason,
+        };
+
+        let mut parts = arn.splitn(6, ':');
+        let (prefix, partition, service, region, account, resource) = match (
+            parts.next(),
+            parts.next(),
+            parts.next(),
+            parts.next(),
+            parts.next(),
+            parts.next(),
+        ) {
+            (Some(a), Some(b), Some(c), Some(d), Some(e), Some(f)) => (a, b, c, d, e, f),
+            _ => return Err(invalid("expected 6 colon-separated components").into()),
+        };
+
+        if prefix != "arn" {
+            return Err(invalid("must start with 'arn'").into());
+        }
+        if service != "s3" {
+            return Err(invalid("only S3 access points are supported").into());
+        }
+
+        let name = resource
+            .strip_prefix("accesspoint/")
+            .or_else(|| resource.strip_prefix("accesspoint:"))
+            .ok_or_else(|| invalid("resource must be an access point"))?;
+        if name.is_empty() || name.contains(['/', ':']) {
+            return Err(invalid("invalid access point name").into());
+        }
+        if account.is_empty() || !account.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(invalid("account must be numeric").into());
+        }
+
+        let dns_suffix = match partition {
+            "aws" | "aws-us-gov" => "amazonaws.com",
+            "aws-cn" => "amazonaws.com.cn",
+            _ => {
+                return Err(Error::UnsupportedPartition {
+                    arn: arn.to_string(),
+                    partition: partition.to_string(),
+                }
+                .into())
+            }
+        };
+
+        Ok(Self {
+            dns_suffix,
+            region: (!region.is_empty()).then(|| region.to_string()),
+            account: account.to_string(),
+            name: name.to_string(),
+        })
+    }
+
+    /// The region requests to this access point must be signed for, or `None` if
+    /// this is a multi-region access point, requiring SigV4A
+    pub(crate) fn region(&self) -> Option<&str> {
+        self.region.as_deref()
+    }
+
+    /// The virtual-hosted style endpoint of this access point
+    pub(crate) fn endpoint(&self) -> String {
+        match &self.region {
+            Some(region) => format!(
+                "https://{}-{}.s3-accesspoint.{}.{}",
+                self.name, self.account, region, self.dns_suffix
+            ),
+            None => format!(
+                "https://{}.accesspoint.s3-global.{}",
+                self.name, self.dns_suffix
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_access_point_arn() {
+        let arn =
+            AccessPointArn::parse("arn:aws:s3:us-west-2:123456789012:accesspoint/my-ap").unwrap();
+        assert_eq!(arn.region(), Some("us-west-2"));
+        assert_eq!(
+            arn.endpoint(),
+            "https://my-ap-123456789012.s3-accesspoint.us-west-2.amazonaws.com"
+        );
+
+        let arn =
+            AccessPointArn::parse("arn:aws-cn:s3:cn-north-1:123456789012:accesspoint:ap").unwrap();
+        assert_eq!(
+            arn.endpoint(),
+            "https://ap-123456789012.s3-accesspoint.cn-north-1.amazonaws.com.cn"
+        );
+
+        let arn = AccessPointArn::parse("arn:aws:s3::123456789012:accesspoint/abc.mrap").unwrap();
+        assert_eq!(arn.region(), None);
+        assert_eq!(
+            arn.endpoint(),
+            "https://abc.mrap.accesspoint.s3-global.amazonaws.com"
+        );
+
+        for invalid in [
+            "arn:aws:s3:us-west-2:123456789012",
+            "arn:aws:s3:us-west-2:123456789012:bucket/foo",
+            "arn:aws:s3:us-west-2:account:accesspoint/foo",
+            "arn:aws:s3:us-west-2:123456789012:accesspoint/",
+            "arn:aws:s3-outposts:us-west-2:123456789012:outpost/op-1/accesspoint/foo",
+            "arn:aws-iso:s3:us-west-2:123456789012:accesspoint/foo",
+        ] {
+            AccessPointArn::parse(invalid).unwrap_err();
+        }
+    }
+}
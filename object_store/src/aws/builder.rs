@@ -15,7 +15,8 @@
 // specific language governing permissions and limitations
 // under the License.
 
-use crate::aws::client::{S3Client, S3Config};
+use crate::aws::arn::AccessPointArn;
+use crate::aws::client::{S3Client, S3Config, CANNED_ACLS};
 use crate::aws::credential::{
     InstanceCredentialProvider, SessionProvider, TaskCredentialProvider, WebIdentityProvider,
 };
@@ -24,12 +25,18 @@ use crate::aws::{
     STORE,
 };
 use crate::client::TokenCredentialProvider;
-use crate::config::ConfigValue;
-use crate::{ClientConfigKey, ClientOptions, Result, RetryConfig, StaticCredentialProvider};
+use crate::config::{config_snapshot, ByteSize, ConfigValue};
+use crate::multipart::{
+    validate_multipart_config, DEFAULT_CONCURRENCY, DEFAULT_PART_SIZE, MAX_PART_SIZE, MIN_PART_SIZE,
+};
+use crate::{
+    CannedAcl, ClientConfigKey, ClientOptions, Result, RetryConfig, StaticCredentialProvider,
+};
 use itertools::Itertools;
 use reqwest::header::{HeaderMap, HeaderValue};
 use serde::{Deserialize, Serialize};
 use snafu::{OptionExt, ResultExt, Snafu};
+use std::collections::BTreeMap;
 use std::str::FromStr;
 use std::sync::Arc;
 use std::time::Duration;
@@ -39,6 +46,9 @@ use url::Url;
 /// Default metadata endpoint
 static DEFAULT_METADATA_ENDPOINT: &str = "http://169.254.169.254";
 
+/// Default region
+static DEFAULT_REGION: &str = "us-east-1";
+
 /// A specialized `Error` for object store-related errors
 #[derive(Debug, Snafu)]
 #[allow(missing_docs)]
@@ -178,6 +188,18 @@ pub struct AmazonS3Builder {
     encryption_type: Option<ConfigValue<S3EncryptionType>>,
     encryption_kms_key_id: Option<String>,
     encryption_bucket_key_enabled: Option<ConfigValue<bool>>,
+    /// Size of the parts uploaded by multipart uploads
+    multipart_part_size: Option<ConfigValue<ByteSize>>,
+    /// Number of parts uploaded concurrently by multipart uploads
+    multipart_concurrency: Option<ConfigValue<usize>>,
+    /// Grow the size of parts uploaded by multipart uploads
+    multipart_part_size_scaling: ConfigValue<bool>,
+    /// Detect the region of the bucket from redirect responses
+    region_auto_detect: ConfigValue<bool>,
+    /// List objects with ListObjects (V1)
+    list_objects_v1: ConfigValue<bool>,
+    /// The canned ACL applied to written objects
+    canned_acl: Option<ConfigValue<CannedAcl>>,
 }
 
 /// Configuration keys for [`AmazonS3Builder`]
@@ -338,6 +360,65 @@ pub enum AmazonS3ConfigKey {
     /// - `s3_express`
     S3Express,
 
+    /// The size of the parts uploaded by [`ObjectStore::put_multipart`]
+    ///
+    /// Accepts a number of bytes with an optional unit, e.g. `16MiB`.
+    /// See [`AmazonS3Builder::with_multipart_part_size`] for details
+    ///
+    /// Supported keys:
+    /// - `aws_multipart_part_size`
+    /// - `multipart_part_size`
+    ///
+    /// [`ObjectStore::put_multipart`]: crate::ObjectStore::put_multipart
+    MultipartPartSize,
+
+    /// The number of parts uploaded concurrently by [`ObjectStore::put_multipart`]
+    ///
+    /// Supported keys:
+    /// - `aws_multipart_concurrency`
+    /// - `multipart_concurrency`
+    ///
+    /// [`ObjectStore::put_multipart`]: crate::ObjectStore::put_multipart
+    MultipartConcurrency,
+
+    /// Grow the size of the parts uploaded by [`ObjectStore::put_multipart`]
+    ///
+    /// See [`AmazonS3Builder::with_multipart_part_size_scaling`] for details
+    ///
+    /// Supported keys:
+    /// - `aws_multipart_part_size_scaling`
+    /// - `multipart_part_size_scaling`
+    ///
+    /// [`ObjectStore::put_multipart`]: crate::ObjectStore::put_multipart
+    MultipartPartSizeScaling,
+
+    /// Detect the region of the bucket when requests are redirected
+    ///
+    /// See [`AmazonS3Builder::with_region_auto_detect`] for details
+    ///
+    /// Supported keys:
+    /// - `aws_region_auto_detect`
+    /// - `region_auto_detect`
+    RegionAutoDetect,
+
+    /// List objects with the legacy ListObjects (V1) API
+    ///
+    /// See [`AmazonS3Builder::with_list_objects_v1`] for details
+    ///
+    /// Supported keys:
+    /// - `aws_list_objects_v1`
+    /// - `list_objects_v1`
+    ListObjectsV1,
+
+    /// The canned ACL applied to written objects
+    ///
+    /// See [`AmazonS3Builder::with_canned_acl`] for details
+    ///
+    /// Supported keys:
+    /// - `aws_canned_acl`
+    /// - `canned_acl`
+    CannedAcl,
+
     /// Client options
     Client(ClientConfigKey),
 
@@ -345,6 +426,44 @@ pub enum AmazonS3ConfigKey {
     Encryption(S3EncryptionConfigKey),
 }
 
+impl AmazonS3ConfigKey {
+    /// All variants of [`AmazonS3ConfigKey`] other than [`AmazonS3ConfigKey::Client`]
+    /// and the alias [`AmazonS3ConfigKey::DefaultRegion`]
+    const KEYS: &'static [Self] = &[
+        Self::AccessKeyId,
+        Self::SecretAccessKey,
+        Self::Region,
+        Self::Bucket,
+        Self::Endpoint,
+        Self::Token,
+        Self::ImdsV1Fallback,
+        Self::VirtualHostedStyleRequest,
+        Self::UnsignedPayload,
+        Self::Checksum,
+        Self::MetadataEndpoint,
+        Self::ContainerCredentialsRelativeUri,
+        Self::CopyIfNotExists,
+        Self::ConditionalPut,
+        Self::SkipSignature,
+        Self::DisableTagging,
+        Self::S3Express,
+        Self::MultipartPartSize,
+        Self::MultipartConcurrency,
+        Self::MultipartPartSizeScaling,
+        Self::RegionAutoDetect,
+        Self::ListObjectsV1,
+        Self::CannedAcl,
+        Self::Encryption(S3EncryptionConfigKey::ServerSideEncryption),
+        Self::Encryption(S3EncryptionConfigKey::KmsKeyId),
+        Self::Encryption(S3EncryptionConfigKey::BucketKeyEnabled),
+    ];
+
+    /// Returns true if the value of this key should not be disclosed
+    fn is_secret(&self) -> bool {
+        matches!(self, Self::SecretAccessKey | Self::Token)
+    }
+}
+
 impl AsRef<str> for AmazonS3ConfigKey {
     fn as_ref(&self) -> &str {
         match self {
@@ -366,6 +485,12 @@ impl AsRef<str> for AmazonS3ConfigKey {
             Self::CopyIfNotExists => "aws_copy_if_not_exists",
             Self::ConditionalPut => "aws_conditional_put",
             Self::DisableTagging => "aws_disable_tagging",
+            Self::MultipartPartSize => "aws_multipart_part_size",
+            Self::MultipartConcurrency => "aws_multipart_concurrency",
+            Self::MultipartPartSizeScaling => "aws_multipart_part_size_scaling",
+            Self::RegionAutoDetect => "aws_region_auto_detect",
+            Self::ListObjectsV1 => "aws_list_objects_v1",
+            Self::CannedAcl => "aws_canned_acl",
             Self::Client(opt) => opt.as_ref(),
             Self::Encryption(opt) => opt.as_ref(),
         }
@@ -397,6 +522,14 @@ impl FromStr for AmazonS3ConfigKey {
             "aws_copy_if_not_exists" | "copy_if_not_exists" => Ok(Self::CopyIfNotExists),
             "aws_conditional_put" | "conditional_put" => Ok(Self::ConditionalPut),
             "aws_disable_tagging" | "disable_tagging" => Ok(Self::DisableTagging),
+            "aws_multipart_part_size" | "multipart_part_size" => Ok(Self::MultipartPartSize),
+            "aws_multipart_concurrency" | "multipart_concurrency" => Ok(Self::MultipartConcurrency),
+            "aws_multipart_part_size_scaling" | "multipart_part_size_scaling" => {
+                Ok(Self::MultipartPartSizeScaling)
+            }
+            "aws_region_auto_detect" | "region_auto_detect" => Ok(Self::RegionAutoDetect),
+            "aws_list_objects_v1" | "list_objects_v1" => Ok(Self::ListObjectsV1),
+            "aws_canned_acl" | "canned_acl" => Ok(Self::CannedAcl),
             // Backwards compatibility
             "aws_allow_http" => Ok(Self::Client(ClientConfigKey::AllowHttp)),
             "aws_server_side_encryption" => Ok(Self::Encryption(
@@ -515,6 +648,20 @@ impl AmazonS3Builder {
             AmazonS3ConfigKey::ConditionalPut => {
                 self.conditional_put = Some(ConfigValue::Deferred(value.into()))
             }
+            AmazonS3ConfigKey::MultipartPartSize => {
+                self.multipart_part_size = Some(ConfigValue::Deferred(value.into()))
+            }
+            AmazonS3ConfigKey::MultipartConcurrency => {
+                self.multipart_concurrency = Some(ConfigValue::Deferred(value.into()))
+            }
+            AmazonS3ConfigKey::MultipartPartSizeScaling => {
+                self.multipart_part_size_scaling.parse(value)
+            }
+            AmazonS3ConfigKey::RegionAutoDetect => self.region_auto_detect.parse(value),
+            AmazonS3ConfigKey::ListObjectsV1 => self.list_objects_v1.parse(value),
+            AmazonS3ConfigKey::CannedAcl => {
+                self.canned_acl = Some(ConfigValue::Deferred(value.into()))
+            }
             AmazonS3ConfigKey::Encryption(key) => match key {
                 S3EncryptionConfigKey::ServerSideEncryption => {
                     self.encryption_type = Some(ConfigValue::Deferred(value.into()))
@@ -569,6 +716,18 @@ impl AmazonS3Builder {
                 self.conditional_put.as_ref().map(ToString::to_string)
             }
             AmazonS3ConfigKey::DisableTagging => Some(self.disable_tagging.to_string()),
+            AmazonS3ConfigKey::MultipartPartSize => {
+                self.multipart_part_size.as_ref().map(ToString::to_string)
+            }
+            AmazonS3ConfigKey::MultipartConcurrency => {
+                self.multipart_concurrency.as_ref().map(ToString::to_string)
+            }
+            AmazonS3ConfigKey::MultipartPartSizeScaling => {
+                Some(self.multipart_part_size_scaling.to_string())
+            }
+            AmazonS3ConfigKey::RegionAutoDetect => Some(self.region_auto_detect.to_string()),
+            AmazonS3ConfigKey::ListObjectsV1 => Some(self.list_objects_v1.to_string()),
+            AmazonS3ConfigKey::CannedAcl => self.canned_acl.as_ref().map(ToString::to_string),
             AmazonS3ConfigKey::Encryption(key) => match key {
                 S3EncryptionConfigKey::ServerSideEncryption => {
                     self.encryption_type.as_ref().map(ToString::to_string)
@@ -582,6 +741,54 @@ impl AmazonS3Builder {
         }
     }
 
+    /// Returns the configuration of this builder as key - value pairs
+    ///
+    /// This includes any values sourced from the environment by [`Self::from_env`], along
+    /// with the defaults of options that have not been set, and can be used to diagnose
+    /// why a store is not configured as expected. Keys without a value are omitted.
+    ///
+    /// Secrets, such as the secret access key and session token, are redacted,
+    /// see [`Self::config_snapshot_with_secrets`] to include them.
+    ///
+    /// # Example
+    /// ```
+    /// use object_store::aws::AmazonS3Builder;
+    ///
+    /// let builder = AmazonS3Builder::new()
+    ///     .with_bucket_name("foo")
+    ///     .with_secret_access_key("secret");
+    /// let snapshot = builder.config_snapshot();
+    /// assert_eq!(snapshot["aws_bucket"], "foo");
+    /// assert_eq!(snapshot["aws_region"], "us-east-1");
+    /// assert_ne!(snapshot["aws_secret_access_key"], "secret");
+    /// ```
+    pub fn config_snapshot(&self) -> BTreeMap<String, String> {
+        self.snapshot(false)
+    }
+
+    /// Returns the configuration of this builder as key - value pairs, including secrets
+    ///
+    /// See [`Self::config_snapshot`]
+    pub fn config_snapshot_with_secrets(&self) -> BTreeMap<String, String> {
+        self.snapshot(true)
+    }
+
+    fn snapshot(&self, include_secrets: bool) -> BTreeMap<String, String> {
+        let client_keys = ClientConfigKey::ALL
+            .iter()
+            .map(|k| AmazonS3ConfigKey::Client(*k));
+        let mut snapshot = config_snapshot(
+            AmazonS3ConfigKey::KEYS.iter().copied().chain(client_keys),
+            include_secrets,
+            AmazonS3ConfigKey::is_secret,
+            |k| self.get_config_value(k),
+        );
+        snapshot
+            .entry(AmazonS3ConfigKey::Region.as_ref().to_string())
+            .or_insert_with(|| DEFAULT_REGION.to_string());
+        snapshot
+    }
+
     /// Sets properties on this builder based on a URL
     ///
     /// This is a separate member function to allow fallible computation to
@@ -645,7 +852,68 @@ impl AmazonS3Builder {
         self
     }
 
+    /// Detect the region of the bucket if requests are redirected, defaults to false
+    ///
+    /// If the configured region does not match that of the bucket, S3 responds with a
+    /// redirect carrying the bucket's region in the `x-amz-bucket-region` header. When
+    /// enabled, the request is transparently retried once against that region, and the
+    /// region is used for all subsequent requests made by this store
+    ///
+    /// The endpoint is only rewritten for the default AWS endpoints, requests to a
+    /// custom endpoint are retried against the same endpoint signed for the new region
+    ///
+    /// See also [`resolve_bucket_region`](crate::aws::resolve_bucket_region)
+    pub fn with_region_auto_detect(mut self, enabled: bool) -> Self {
+        self.region_auto_detect = enabled.into();
+        self
+    }
+
+    /// List objects with the legacy [ListObjects] (V1) API, defaults to false
+    ///
+    /// Some older S3-compatible stores do not support [ListObjectsV2]. Regardless of
+    /// this setting, if the first request of a listing is rejected with a `501 Not
+    /// Implemented` response, or a `400 Bad Request` response with the error code
+    /// `NotImplemented` or identifying the `list-type` parameter, it is retried with V1,
+    /// and if that succeeds, V1 is used for all subsequent listings made by the store. Enabling this avoids the
+    /// initial failed request, and is required for stores that instead return an error
+    /// that cannot be distinguished from other failures.
+    ///
+    /// V1 paginates with the greatest key of the previous page, and so the pagination
+    /// tokens of V1 and V2 listings are not interchangeable
+    ///
+    /// [ListObjects]: https://docs.aws.amazon.com/AmazonS3/latest/API/API_ListObjects.html
+    /// [ListObjectsV2]: https://docs.aws.amazon.com/AmazonS3/latest/API/API_ListObjectsV2.html
+    pub fn with_list_objects_v1(mut self, enabled: bool) -> Self {
+        self.list_objects_v1 = enabled.into();
+        self
+    }
+
+    /// Apply the [`CannedAcl`] to written objects, sent as the `x-amz-acl` header
+    ///
+    /// This applies to puts without [`PutOptions::acl`], and to multipart uploads,
+    /// which have no per-request options. Building the store fails if the ACL is not
+    /// supported by S3, such as [`CannedAcl::ProjectPrivate`]
+    ///
+    /// Buckets with [Object Ownership] set to `BucketOwnerEnforced`, the default for new
+    /// buckets, have ACLs disabled and reject writes specifying an ACL other than
+    /// [`CannedAcl::BucketOwnerFullControl`]
+    ///
+    /// [`PutOptions::acl`]: crate::PutOptions::acl
+    /// [Object Ownership]: https://docs.aws.amazon.com/AmazonS3/latest/userguide/about-object-ownership.html
+    pub fn with_canned_acl(mut self, acl: CannedAcl) -> Self {
+        self.canned_acl = Some(acl.into());
+        self
+    }
+
     /// Set the bucket_name (required)
+    ///
+    /// This may also be the ARN of an [access point], e.g.
+    /// `arn:aws:s3:us-west-2:123456789012:accesspoint/my-access-point`, in which case
+    /// requests are sent to the access point's endpoint, and signed for its region.
+    /// Requests to a [multi-region access point] are signed with SigV4A.
+    ///
+    /// [access point]: https://docs.aws.amazon.com/AmazonS3/latest/userguide/using-access-points.html
+    /// [multi-region access point]: https://docs.aws.amazon.com/AmazonS3/latest/userguide/MultiRegionAccessPoints.html
     pub fn with_bucket_name(mut self, bucket_name: impl Into<String>) -> Self {
         self.bucket_name = Some(bucket_name.into());
         self
@@ -738,7 +1006,9 @@ impl AmazonS3Builder {
 
     /// If enabled, [`AmazonS3`] will not fetch credentials and will not sign requests
     ///
-    /// This can be useful when interacting with public S3 buckets that deny authorized requests
+    /// This takes precedence over any configured credentials, with requests sent without
+    /// an `Authorization` header. This can be useful when interacting with public S3 buckets
+    /// that deny authorized requests
     pub fn with_skip_signature(mut self, skip_signature: bool) -> Self {
         self.skip_signature = skip_signature.into();
         self
@@ -789,6 +1059,13 @@ impl AmazonS3Builder {
         self
     }
 
+    /// Sets the [`reqwest::Client`] used to make requests, sharing its connection pool
+    /// with other stores, see [`ClientOptions::with_http_client`]
+    pub fn with_http_client(mut self, client: reqwest::Client) -> Self {
+        self.client_options = self.client_options.with_http_client(client);
+        self
+    }
+
     /// Configure how to provide `copy_if_not_exists`
     pub fn with_copy_if_not_exists(mut self, config: S3CopyIfNotExists) -> Self {
         self.copy_if_not_exists = Some(config.into());
@@ -836,6 +1113,46 @@ impl AmazonS3Builder {
         self
     }
 
+    /// Sets the size in bytes of the parts uploaded by [`ObjectStore::put_multipart`],
+    /// defaults to 10 MiB
+    ///
+    /// This must be at least 5 MiB and at most 5 GiB. Larger parts reduce the number of requests, at the
+    /// cost of greater memory usage, and are necessary for objects with more than
+    /// 10,000 parts
+    ///
+    /// [`ObjectStore::put_multipart`]: crate::ObjectStore::put_multipart
+    pub fn with_multipart_part_size(mut self, part_size: usize) -> Self {
+        self.multipart_part_size = Some(ByteSize(part_size).into());
+        self
+    }
+
+    /// Sets the number of parts uploaded concurrently by [`ObjectStore::put_multipart`],
+    /// defaults to 8
+    ///
+    /// [`ObjectStore::put_multipart`]: crate::ObjectStore::put_multipart
+    pub fn with_multipart_concurrency(mut self, concurrency: usize) -> Self {
+        self.multipart_concurrency = Some(concurrency.into());
+        self
+    }
+
+    /// Grow the size of the parts uploaded by [`ObjectStore::put_multipart`] as the upload
+    /// progresses, allowing uploads of up to roughly 10 TiB with the default part size
+    /// whilst still using small parts for small uploads, defaults to false
+    ///
+    /// The part size starts at [`Self::with_multipart_part_size`] and doubles after every
+    /// 1,000 parts, up to the maximum part size of 5 GiB, see
+    /// [`WriteMultiPart::with_part_size_scaling`]
+    ///
+    /// This should not be enabled for S3-compatible stores that require all parts to
+    /// be the same size, such as Cloudflare R2
+    ///
+    /// [`ObjectStore::put_multipart`]: crate::ObjectStore::put_multipart
+    /// [`WriteMultiPart::with_part_size_scaling`]: crate::multipart::WriteMultiPart::with_part_size_scaling
+    pub fn with_multipart_part_size_scaling(mut self, scaling: bool) -> Self {
+        self.multipart_part_size_scaling = scaling.into();
+        self
+    }
+
     /// Create a [`AmazonS3`] instance from the provided values,
     /// consuming `self`.
     pub fn build(mut self) -> Result<AmazonS3> {
@@ -844,7 +1161,20 @@ impl AmazonS3Builder {
         }
 
         let bucket = self.bucket_name.context(MissingBucketNameSnafu)?;
-        let region = self.region.unwrap_or_else(|| "us-east-1".to_string());
+        let access_point = AccessPointArn::is_arn(&bucket)
+            .then(|| AccessPointArn::parse(&bucket))
+            .transpose()?;
+
+        // Requests to an access point must be signed for its region, with requests
+        // to multi-region access points signed with SigV4A for all regions
+        let region = match access_point.as_ref().and_then(|x| x.region()) {
+            Some(region) => region.to_string(),
+            None => self.region.unwrap_or_else(|| DEFAULT_REGION.to_string()),
+        };
+        let sigv4a_region_set = access_point
+            .as_ref()
+            .filter(|x| x.region().is_none())
+            .map(|_| "*".to_string());
         let checksum = self.checksum_algorithm.map(|x| x.get()).transpose()?;
         let copy_if_not_exists = self.copy_if_not_exists.map(|x| x.get()).transpose()?;
         let put_precondition = self.conditional_put.map(|x| x.get()).transpose()?;
@@ -952,11 +1282,16 @@ impl AmazonS3Builder {
         // For example, if `virtual_hosted_style_request` is true then `endpoint` should have bucket name included.
         let virtual_hosted = self.virtual_hosted_style_request.get()?;
         let bucket_endpoint = match (&self.endpoint, zonal_endpoint, virtual_hosted) {
+            // Access points only support virtual-hosted style requests
+            (Some(endpoint), _, _) if access_point.is_some() => endpoint.clone(),
             (Some(endpoint), _, true) => endpoint.clone(),
             (Some(endpoint), _, false) => format!("{endpoint}/{bucket}"),
             (None, Some(endpoint), _) => endpoint,
-            (None, None, true) => format!("https://{bucket}.s3.{region}.amazonaws.com"),
-            (None, None, false) => format!("https://s3.{region}.amazonaws.com/{bucket}"),
+            (None, None, virtual_hosted) => match &access_point {
+                Some(arn) => arn.endpoint(),
+                None if virtual_hosted => format!("https://{bucket}.s3.{region}.amazonaws.com"),
+                None => format!("https://s3.{region}.amazonaws.com/{bucket}"),
+            },
         };
 
         let encryption_headers = if let Some(encryption_type) = self.encryption_type {
@@ -971,6 +1306,27 @@ impl AmazonS3Builder {
             S3EncryptionHeaders::default()
         };
 
+        let multipart_part_size = match self.multipart_part_size {
+            Some(size) => size.get()?.0,
+            None => DEFAULT_PART_SIZE,
+        };
+        let multipart_concurrency = match self.multipart_concurrency {
+            Some(concurrency) => concurrency.get()?,
+            None => DEFAULT_CONCURRENCY,
+        };
+        validate_multipart_config(
+            STORE,
+            multipart_part_size,
+            MIN_PART_SIZE,
+            MAX_PART_SIZE,
+            multipart_concurrency,
+        )?;
+
+        let canned_acl = match self.canned_acl {
+            Some(acl) => Some(acl.get()?.check(STORE, CANNED_ACLS)?),
+            None => None,
+        };
+
         let config = S3Config {
             region,
             endpoint: self.endpoint,
@@ -987,6 +1343,15 @@ impl AmazonS3Builder {
             copy_if_not_exists,
             conditional_put: put_precondition,
             encryption_headers,
+            multipart_part_size,
+            multipart_concurrency,
+            multipart_part_size_scaling: self.multipart_part_size_scaling.get()?,
+            sigv4a_region_set,
+            region_auto_detect: self.region_auto_detect.get()?,
+            detected_region: Default::default(),
+            list_objects_v1: self.list_objects_v1.get()?,
+            list_v1_detected: Default::default(),
+            canned_acl,
         };
 
         let client = Arc::new(S3Client::new(config)?);
@@ -1231,6 +1596,34 @@ mod tests {
         );
     }
 
+    #[test]
+    fn s3_test_config_snapshot() {
+        let builder = AmazonS3Builder::new()
+            .with_access_key_id("key_id")
+            .with_secret_access_key("secret_key")
+            .with_token("token")
+            .with_bucket_name("bucket")
+            .with_endpoint("http://localhost:4566");
+
+        let snapshot = builder.config_snapshot();
+        assert_eq!(snapshot["aws_access_key_id"], "key_id");
+        assert_eq!(snapshot["aws_bucket"], "bucket");
+        assert_eq!(snapshot["aws_endpoint"], "http://localhost:4566");
+        assert_eq!(snapshot["aws_region"], "us-east-1");
+        assert_eq!(snapshot["aws_skip_signature"], "false");
+        assert_eq!(snapshot["timeout"], "30s");
+        assert_eq!(snapshot["aws_secret_access_key"], crate::config::REDACTED);
+        assert_eq!(snapshot["aws_session_token"], crate::config::REDACTED);
+        assert!(!snapshot.contains_key("aws_metadata_endpoint"));
+
+        let snapshot = builder
+            .with_region("eu-west-1")
+            .config_snapshot_with_secrets();
+        assert_eq!(snapshot["aws_region"], "eu-west-1");
+        assert_eq!(snapshot["aws_secret_access_key"], "secret_key");
+        assert_eq!(snapshot["aws_session_token"], "token");
+    }
+
     #[test]
     fn s3_default_region() {
         let builder = AmazonS3Builder::new()
@@ -1240,6 +1633,117 @@ mod tests {
         assert_eq!(builder.client.config.region, "us-east-1");
     }
 
+    #[tokio::test]
+    async fn s3_test_access_point_arn() {
+        use crate::client::mock_server::MockServer;
+        use crate::{path::Path, ObjectStore};
+        use hyper::{Body, Response};
+
+        let arn = "arn:aws:s3:us-west-2:123456789012:accesspoint/my-ap";
+        let builder = AmazonS3Builder::new()
+            .with_bucket_name(arn)
+            .with_region("us-east-1")
+            .with_access_key_id("key")
+            .with_secret_access_key("secret");
+
+        let store = builder.clone().build().unwrap();
+        let config = &store.client.config;
+        assert_eq!(config.region, "us-west-2");
+        assert_eq!(
+            config.bucket_endpoint,
+            "https://my-ap-123456789012.s3-accesspoint.us-west-2.amazonaws.com"
+        );
+        assert_eq!(
+            config.copy_source(&Path::from("a/b")),
+            format!("{arn}/object/a/b")
+        );
+
+        // Requests are signed for the region of the access point
+        let server = MockServer::new();
+        let store = builder.with_endpoint(server.url()).build().unwrap();
+        assert_eq!(store.client.config.bucket_endpoint, server.url());
+        server.push_fn(|req| {
+            let auth = req
+                .headers()
+                .get("authorization")
+                .unwrap()
+                .to_str()
+                .unwrap();
+            assert!(auth.contains("/us-west-2/s3/aws4_request"), "{auth}");
+            assert_eq!(req.uri().path(), "/file");
+            Response::new(Body::empty())
+        });
+        store.head(&Path::from("file")).await.unwrap_err();
+        server.shutdown().await;
+
+        let mrap = "arn:aws:s3::123456789012:accesspoint/abc.mrap";
+        let store = AmazonS3Builder::new()
+            .with_bucket_name(mrap)
+            .build()
+            .unwrap();
+        let config = &store.client.config;
+        assert_eq!(config.sigv4a_region_set.as_deref(), Some("*"));
+        assert_eq!(
+            config.bucket_endpoint,
+            "https://abc.mrap.accesspoint.s3-global.amazonaws.com"
+        );
+    }
+
+    #[test]
+    fn s3_test_multipart_config() {
+        let builder = AmazonS3Builder::new()
+            .with_bucket_name("bucket")
+            .with_region("us-east-1");
+
+        let store = builder.clone().build().unwrap();
+        assert_eq!(store.client.config.multipart_part_size, 10 * 1024 * 1024);
+        assert_eq!(store.client.config.multipart_concurrency, 8);
+
+        let store = builder
+            .clone()
+            .with_config("aws_multipart_part_size".parse().unwrap(), "16MiB")
+            .with_config("multipart_concurrency".parse().unwrap(), "4")
+            .build()
+            .unwrap();
+        assert_eq!(store.client.config.multipart_part_size, 16 * 1024 * 1024);
+        assert_eq!(store.client.config.multipart_concurrency, 4);
+
+        let store = builder
+            .clone()
+            .with_config(AmazonS3ConfigKey::MultipartPartSize, "5MiB")
+            .build()
+            .unwrap();
+        assert_eq!(store.client.config.multipart_part_size, 5 * 1024 * 1024);
+        assert!(!store.client.config.multipart_part_size_scaling);
+
+        let store = builder
+            .clone()
+            .with_config(AmazonS3ConfigKey::MultipartPartSizeScaling, "true")
+            .build()
+            .unwrap();
+        assert!(store.client.config.multipart_part_size_scaling);
+
+        let store = builder
+            .clone()
+            .with_config(AmazonS3ConfigKey::MultipartPartSize, "5GiB")
+            .build()
+            .unwrap();
+        assert_eq!(
+            store.client.config.multipart_part_size,
+            5 * 1024 * 1024 * 1024
+        );
+
+        // 5MB is less than the minimum of 5MiB, and 5121MiB more than the maximum of 5GiB
+        for size in ["5MB", "0", "big", "5121MiB"] {
+            let b = builder
+                .clone()
+                .with_config(AmazonS3ConfigKey::MultipartPartSize, size);
+            assert!(b.build().is_err(), "{size}");
+        }
+        let b = builder.with_multipart_concurrency(0);
+        assert!(b.build().is_err());
+    }
+
     #[test]
     fn s3_test_urls() {
         let mut builder = AmazonS3Builder::new();
@@ -16,7 +16,7 @@
 // under the License.
 
 use crate::config::Parse;
-use ring::digest::{self, digest as ring_digest};
+use ring::digest;
 use std::str::FromStr;
 
 #[allow(non_camel_case_types)]
@@ -25,26 +25,88 @@ use std::str::FromStr;
 pub enum Checksum {
     /// SHA-256 algorithm.
     SHA256,
+    /// CRC-32C (Castagnoli) algorithm.
+    CRC32C,
 }
 
 impl Checksum {
     pub(super) fn digest(&self, bytes: &[u8]) -> Vec<u8> {
+        let mut hasher = self.hasher();
+        hasher.update(bytes);
+        hasher.finish()
+    }
+
+    /// Returns a [`ChecksumHasher`] to compute the checksum incrementally
+    pub(super) fn hasher(&self) -> ChecksumHasher {
         match self {
-            Self::SHA256 => ring_digest(&digest::SHA256, bytes).as_ref().to_owned(),
+            Self::SHA256 => ChecksumHasher::SHA256(Box::new(digest::Context::new(&digest::SHA256))),
+            Self::CRC32C => ChecksumHasher::CRC32C(0),
         }
     }
 
     pub(super) fn header_name(&self) -> &'static str {
         match self {
             Self::SHA256 => "x-amz-checksum-sha256",
+            Self::CRC32C => "x-amz-checksum-crc32c",
+        }
+    }
+}
+
+/// Incrementally computes a [`Checksum`]
+pub(super) enum ChecksumHasher {
+    SHA256(Box<digest::Context>),
+    CRC32C(u32),
+}
+
+impl ChecksumHasher {
+    pub(super) fn update(&mut self, bytes: &[u8]) {
+        match self {
+            Self::SHA256(ctx) => ctx.update(bytes),
+            Self::CRC32C(crc) => *crc = crc32c(*crc, bytes),
+        }
+    }
+
+    pub(super) fn finish(self) -> Vec<u8> {
+        match self {
+            Self::SHA256(ctx) => ctx.finish().as_ref().to_owned(),
+            Self::CRC32C(crc) => crc.to_be_bytes().to_vec(),
+        }
+    }
+}
+
+/// Lookup table for the reflected CRC-32C polynomial `0x82F63B78`
+const CRC32C_TABLE: [u32; 256] = {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut j = 0;
+        while j < 8 {
+            crc = match crc & 1 {
+                1 => (crc >> 1) ^ 0x82F63B78,
+                _ => crc >> 1,
+            };
+            j += 1;
         }
+        table[i] = crc;
+        i += 1;
     }
+    table
+};
+
+/// Extends the CRC-32C `crc` of some preceding data with `bytes`
+fn crc32c(crc: u32, bytes: &[u8]) -> u32 {
+    let crc = bytes.iter().fold(!crc, |crc, b| {
+        CRC32C_TABLE[((crc ^ *b as u32) & 0xFF) as usize] ^ (crc >> 8)
+    });
+    !crc
 }
 
 impl std::fmt::Display for Checksum {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match &self {
             Self::SHA256 => write!(f, "sha256"),
+            Self::CRC32C => write!(f, "crc32c"),
         }
     }
 }
@@ -55,6 +117,7 @@ impl FromStr for Checksum {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s.to_lowercase().as_str() {
             "sha256" => Ok(Self::SHA256),
+            "crc32c" => Ok(Self::CRC32C),
             _ => Err(()),
         }
     }
@@ -76,3 +139,21 @@ impl Parse for Checksum {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crc32c() {
+        assert_eq!(crc32c(0, b""), 0);
+        assert_eq!(crc32c(0, b"123456789"), 0xE3069283);
+
+        for checksum in [Checksum::CRC32C, Checksum::SHA256] {
+            let mut hasher = checksum.hasher();
+            hasher.update(b"1234");
+            hasher.update(b"56789");
+            assert_eq!(hasher.finish(), checksum.digest(b"123456789"));
+        }
+    }
+}
@@ -15,32 +15,42 @@
 // specific language governing permissions and limitations
 // under the License.
 
+use crate::aws::arn::AccessPointArn;
 use crate::aws::builder::S3EncryptionHeaders;
 use crate::aws::checksum::Checksum;
-use crate::aws::credential::{AwsCredential, CredentialExt};
+use crate::aws::credential::{
+    AwsCredential, CredentialExt, STREAMING_UNSIGNED_PAYLOAD_TRAILER, TRAILER_HEADER,
+};
+use crate::aws::streaming;
 use crate::aws::{
     AwsAuthorizer, AwsCredentialProvider, S3ConditionalPut, S3CopyIfNotExists, STORE,
-    STRICT_PATH_ENCODE_SET,
+    STRICT_PATH_ENCODE_SET, TAGS_HEADER,
 };
 use crate::client::get::GetClient;
 use crate::client::header::{get_etag, HeaderConfig};
 use crate::client::header::{get_put_result, get_version};
 use crate::client::list::ListClient;
-use crate::client::retry::RetryExt;
+use crate::client::retry::{self, RetryExt};
 use crate::client::s3::{
-    CompleteMultipartUpload, CompleteMultipartUploadResult, InitiateMultipartUploadResult,
-    ListResponse,
+    CompleteMultipartUpload, CompleteMultipartUploadResult, CopyPartResult,
+    InitiateMultipartUploadResult, ListMultipartUploadsResult, ListResponse, Tagging,
 };
-use crate::client::GetOptionsExt;
-use crate::multipart::PartId;
+use crate::client::{GetOptionsExt, HedgeConfig};
+use crate::multipart::{validate_parts, PartId, UploadLimiter};
 use crate::path::DELIMITER;
 use crate::{
-    ClientOptions, GetOptions, ListResult, MultipartId, Path, PutResult, Result, RetryConfig,
+    Acl, AclGrant, AclGrantee, AclPermission, CannedAcl, ClientOptions, CopyOptions, GetOptions,
+    ListDeduplication, ListResult, MultipartId, Path, PutResult, Result, Retention, RetentionMode,
+    RetryConfig, TagSet,
 };
 use async_trait::async_trait;
 use base64::prelude::BASE64_STANDARD;
 use base64::Engine;
 use bytes::{Buf, Bytes};
+use chrono::SecondsFormat;
+use futures::future::BoxFuture;
+use futures::stream::BoxStream;
+use futures::FutureExt;
 use hyper::http;
 use hyper::http::HeaderName;
 use itertools::Itertools;
@@ -48,15 +58,99 @@ use md5::{Digest, Md5};
 use percent_encoding::{utf8_percent_encode, PercentEncode};
 use quick_xml::events::{self as xml_events};
 use reqwest::{
-    header::{CONTENT_LENGTH, CONTENT_TYPE},
-    Client as ReqwestClient, Method, RequestBuilder, Response,
+    header::{
+        HeaderMap, HeaderValue, CACHE_CONTROL, CONTENT_DISPOSITION, CONTENT_ENCODING,
+        CONTENT_LANGUAGE, CONTENT_LENGTH, CONTENT_TYPE, EXPIRES, IF_MATCH, IF_NONE_MATCH,
+    },
+    Client as ReqwestClient, Method, RequestBuilder, Response, StatusCode,
 };
 use serde::{Deserialize, Serialize};
 use snafu::{ResultExt, Snafu};
-use std::sync::Arc;
+use std::borrow::Cow;
+use std::sync::atomic::{AtomicBool, Ordering::Relaxed};
+use std::sync::{Arc, RwLock};
+use url::Url;
 
 const VERSION_HEADER: &str = "x-amz-version-id";
 
+pub(crate) static ACL_HEADER: HeaderName = HeaderName::from_static("x-amz-acl");
+
+/// The [`CannedAcl`] supported by S3 for objects
+///
+/// <https://docs.aws.amazon.com/AmazonS3/latest/userguide/acl-overview.html#canned-acl>
+pub(crate) const CANNED_ACLS: &[CannedAcl] = &[
+    CannedAcl::Private,
+    CannedAcl::PublicRead,
+    CannedAcl::PublicReadWrite,
+    CannedAcl::AuthenticatedRead,
+    CannedAcl::AwsExecRead,
+    CannedAcl::BucketOwnerRead,
+    CannedAcl::BucketOwnerFullControl,
+];
+
+static COPY_SOURCE_IF_MATCH: HeaderName = HeaderName::from_static("x-amz-copy-source-if-match");
+static TAGGING_COUNT_HEADER: HeaderName = HeaderName::from_static("x-amz-tagging-count");
+
+/// The prefix of the headers containing user-defined object metadata
+const USER_METADATA_PREFIX: &str = "x-amz-meta-";
+
+/// The headers of an object that are preserved by a CopyObject request, and must
+/// therefore be provided to CreateMultipartUpload to preserve them on a multipart copy
+const COPIED_HEADERS: &[HeaderName] = &[
+    CACHE_CONTROL,
+    CONTENT_DISPOSITION,
+    CONTENT_ENCODING,
+    CONTENT_LANGUAGE,
+    CONTENT_TYPE,
+    EXPIRES,
+];
+static COPY_SOURCE_IF_UNMODIFIED_SINCE: HeaderName =
+    HeaderName::from_static("x-amz-copy-source-if-unmodified-since");
+
+/// The base64 encoded MD5 digest of `body`, as required by the `Content-MD5` header
+pub(crate) fn content_md5(body: &[u8]) -> String {
+    let mut hasher = Md5::new();
+    hasher.update(body);
+    BASE64_STANDARD.encode(hasher.finalize())
+}
+
+/// The S3 representation of a [`RetentionMode`]
+pub(crate) fn retention_mode(mode: RetentionMode) -> &'static str {
+    match mode {
+        RetentionMode::Governance => "GOVERNANCE",
+        RetentionMode::Compliance => "COMPLIANCE",
+    }
+}
+
+/// The S3 representation of [`Retention::retain_until`]
+pub(crate) fn retain_until_date(retention: &Retention) -> String {
+    retention
+        .retain_until
+        .to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+/// S3 returns `403 Forbidden`, with a message referencing Object Lock, for
+/// operations blocked by a retention period or legal hold
+fn is_object_lock_error(e: &crate::client::retry::Error) -> bool {
+    e.status() == Some(StatusCode::FORBIDDEN)
+        && e.body()
+            .map(|b| b.to_ascii_lowercase().contains("object lock"))
+            .unwrap_or_default()
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename = "Retention", rename_all = "PascalCase")]
+struct ObjectLockRetention {
+    mode: &'static str,
+    retain_until_date: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename = "LegalHold", rename_all = "PascalCase")]
+struct ObjectLockLegalHold {
+    status: &'static str,
+}
+
 /// A specialized `Error` for object store-related errors
 #[derive(Debug, Snafu)]
 #[allow(missing_docs)]
@@ -96,6 +190,12 @@ pub(crate) enum Error {
     #[snafu(display("Error getting list response body: {}", source))]
     ListResponseBody { source: reqwest::Error },
 
+    #[snafu(display("Error performing list multipart uploads request: {}", source))]
+    ListMultipartUploadsRequest { source: crate::client::retry::Error },
+
+    #[snafu(display("Error getting list multipart uploads response body: {}", source))]
+    ListMultipartUploadsResponseBody { source: reqwest::Error },
+
     #[snafu(display("Error performing create multipart request: {}", source))]
     CreateMultipartRequest { source: crate::client::retry::Error },
 
@@ -108,16 +208,34 @@ pub(crate) enum Error {
     #[snafu(display("Error getting complete multipart response body: {}", source))]
     CompleteMultipartResponseBody { source: reqwest::Error },
 
+    #[snafu(display("Error getting copy part response body: {}", source))]
+    CopyPartResponseBody { source: reqwest::Error },
+
+    #[snafu(display("Error getting tagging response body: {}", source))]
+    TaggingResponseBody { source: reqwest::Error },
+
+    #[snafu(display("Got invalid tagging response: {}", source))]
+    InvalidTaggingResponse { source: quick_xml::de::DeError },
+
     #[snafu(display("Got invalid list response: {}", source))]
     InvalidListResponse { source: quick_xml::de::DeError },
 
     #[snafu(display("Got invalid multipart response: {}", source))]
     InvalidMultipartResponse { source: quick_xml::de::DeError },
 
+    #[snafu(display("Error getting ACL response body: {}", source))]
+    AclResponseBody { source: reqwest::Error },
+
+    #[snafu(display("Got invalid ACL response: {}", source))]
+    InvalidAclResponse { source: quick_xml::de::DeError },
+
     #[snafu(display("Unable to extract metadata from headers: {}", source))]
     Metadata {
         source: crate::client::header::Error,
     },
+
+    #[snafu(display("Generating signed URLs while skipping signatures is not supported"))]
+    SignedUrlWithSkipSignature,
 }
 
 impl From<Error> for crate::Error {
@@ -167,6 +285,84 @@ impl From<DeleteError> for Error {
     }
 }
 
+const ALL_USERS_URI: &str = "http://acs.amazonaws.com/groups/global/AllUsers";
+const AUTHENTICATED_USERS_URI: &str = "http://acs.amazonaws.com/groups/global/AuthenticatedUsers";
+
+/// <https://docs.aws.amazon.com/AmazonS3/latest/API/API_GetObjectAcl.html#API_GetObjectAcl_ResponseSyntax>
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct AccessControlPolicy {
+    owner: Option<AclOwner>,
+    #[serde(default)]
+    access_control_list: AccessControlList,
+}
+
+#[derive(Debug, Deserialize)]
+struct AclOwner {
+    #[serde(rename = "ID")]
+    id: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct AccessControlList {
+    #[serde(default, rename = "Grant")]
+    grants: Vec<S3Grant>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct S3Grant {
+    grantee: S3Grantee,
+    permission: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct S3Grantee {
+    #[serde(rename = "ID")]
+    id: Option<String>,
+    #[serde(rename = "URI")]
+    uri: Option<String>,
+    email_address: Option<String>,
+}
+
+impl From<AccessControlPolicy> for Acl {
+    fn from(value: AccessControlPolicy) -> Self {
+        let grants = value
+            .access_control_list
+            .grants
+            .into_iter()
+            .filter_map(|grant| {
+                let permission = match grant.permission.as_str() {
+                    "READ" => AclPermission::Read,
+                    "WRITE" => AclPermission::Write,
+                    "READ_ACP" => AclPermission::ReadAcl,
+                    "WRITE_ACP" => AclPermission::WriteAcl,
+                    "FULL_CONTROL" => AclPermission::FullControl,
+                    _ => return None,
+                };
+                let g = grant.grantee;
+                let grantee = match (g.id, g.uri, g.email_address) {
+                    (Some(id), _, _) => AclGrantee::User(id),
+                    (_, Some(uri), _) if uri == ALL_USERS_URI => AclGrantee::AllUsers,
+                    (_, Some(uri), _) if uri == AUTHENTICATED_USERS_URI => {
+                        AclGrantee::AuthenticatedUsers
+                    }
+                    (_, Some(uri), _) => AclGrantee::Group(uri),
+                    (_, _, Some(email)) => AclGrantee::User(email),
+                    _ => return None,
+                };
+                Some(AclGrant::new(grantee, permission))
+            })
+            .collect();
+
+        Self {
+            owner: value.owner.map(|o| o.id),
+            grants,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct S3Config {
     pub region: String,
@@ -184,11 +380,100 @@ pub struct S3Config {
     pub copy_if_not_exists: Option<S3CopyIfNotExists>,
     pub conditional_put: Option<S3ConditionalPut>,
     pub encryption_headers: S3EncryptionHeaders,
+    pub multipart_part_size: usize,
+    pub multipart_concurrency: usize,
+    pub multipart_part_size_scaling: bool,
+    /// If set, requests are signed with SigV4A for this region set
+    pub sigv4a_region_set: Option<String>,
+    /// If set, requests redirected to the bucket's region are retried against that region
+    pub region_auto_detect: bool,
+    /// The region discovered by [`Self::region_auto_detect`], if any
+    pub detected_region: RwLock<Option<DetectedRegion>>,
+    /// If set, objects are listed with ListObjects (V1) instead of ListObjectsV2
+    pub list_objects_v1: bool,
+    /// Set if the server reported ListObjectsV2 as unsupported, and a ListObjects (V1)
+    /// request then succeeded
+    pub list_v1_detected: AtomicBool,
+    /// The canned ACL applied to objects written without [`PutOptions::acl`]
+    ///
+    /// [`PutOptions::acl`]: crate::PutOptions::acl
+    pub canned_acl: Option<CannedAcl>,
+}
+
+/// A bucket region discovered from a redirect response
+#[derive(Debug, Clone)]
+pub(crate) struct DetectedRegion {
+    region: String,
+    bucket_endpoint: String,
 }
 
 impl S3Config {
     pub(crate) fn path_url(&self, path: &Path) -> String {
-        format!("{}/{}", self.bucket_endpoint, encode_path(path))
+        format!("{}/{}", self.bucket_endpoint(), encode_path(path))
+    }
+
+    /// The region requests are signed for, taking into account any detected region
+    pub(crate) fn region(&self) -> Cow<'_, str> {
+        match self.detected() {
+            Some(detected) => Cow::Owned(detected.region),
+            None => Cow::Borrowed(&self.region),
+        }
+    }
+
+    /// The endpoint of the bucket, taking into account any detected region
+    pub(crate) fn bucket_endpoint(&self) -> Cow<'_, str> {
+        match self.detected() {
+            Some(detected) => Cow::Owned(detected.bucket_endpoint),
+            None => Cow::Borrowed(&self.bucket_endpoint),
+        }
+    }
+
+    fn detected(&self) -> Option<DetectedRegion> {
+        match self.region_auto_detect {
+            true => self.detected_region.read().unwrap().clone(),
+            false => None,
+        }
+    }
+
+    /// Records the bucket region reported by a redirect `error`
+    ///
+    /// Returns the endpoint the request was sent to and the newly detected region,
+    /// or `None` if the request should not be retried
+    pub(crate) fn detect_region(&self, error: &retry::Error) -> Option<(String, DetectedRegion)> {
+        let region = match error {
+            retry::Error::BareRedirect {
+                region: Some(region),
+            } if self.region_auto_detect => region,
+            _ => return None,
+        };
+
+        let current = self.bucket_endpoint().into_owned();
+        let bucket_endpoint = match (&self.endpoint, self.is_s3_express()) {
+            // Custom and zonal endpoints are region-specific, only the signing region changes
+            (Some(_), _) | (None, true) => self.bucket_endpoint.clone(),
+            (None, false) => self.bucket_endpoint.replacen(
+                &format!(".{}.amazonaws.com", self.region),
+                &format!(".{region}.amazonaws.com"),
+                1,
+            ),
+        };
+
+        let detected = DetectedRegion {
+            region: region.clone(),
+            bucket_endpoint,
+        };
+        *self.detected_region.write().unwrap() = Some(detected.clone());
+        Some((current, detected))
+    }
+
+    /// The value of the `x-amz-copy-source` header to copy from `path`
+    ///
+    /// Objects accessed through an access point are identified by `{arn}/object/{key}`
+    pub(crate) fn copy_source(&self, path: &Path) -> String {
+        match AccessPointArn::is_arn(&self.bucket) {
+            true => format!("{}/object/{}", self.bucket, encode_path(path)),
+            false => format!("{}/{}", self.bucket, encode_path(path)),
+        }
     }
 
     async fn get_session_credential(&self) -> Result<SessionCredential<'_>> {
@@ -203,10 +488,19 @@ impl S3Config {
         Ok(SessionCredential {
             credential,
             session_token: self.session_provider.is_some(),
+            region: self.region(),
             config: self,
         })
     }
 
+    /// Discard any cached credentials, including the session credentials if `session`
+    async fn invalidate_credential(&self, session: bool) {
+        self.credentials.invalidate().await;
+        if let Some(provider) = self.session_provider.as_ref().filter(|_| session) {
+            provider.invalidate().await;
+        }
+    }
+
     pub(crate) async fn get_credential(&self) -> Result<Option<Arc<AwsCredential>>> {
         Ok(match self.skip_signature {
             false => Some(self.credentials.get_credential().await?),
@@ -223,14 +517,18 @@ impl S3Config {
 struct SessionCredential<'a> {
     credential: Option<Arc<AwsCredential>>,
     session_token: bool,
+    region: Cow<'a, str>,
     config: &'a S3Config,
 }
 
 impl<'a> SessionCredential<'a> {
     fn authorizer(&self) -> Option<AwsAuthorizer<'_>> {
-        let mut authorizer =
-            AwsAuthorizer::new(self.credential.as_deref()?, "s3", &self.config.region)
-                .with_sign_payload(self.config.sign_payload);
+        let mut authorizer = AwsAuthorizer::new(self.credential.as_deref()?, "s3", &self.region)
+            .with_sign_payload(self.config.sign_payload);
+
+        if let Some(region_set) = &self.config.sigv4a_region_set {
+            authorizer = authorizer.with_sigv4a(region_set);
+        }
 
         if self.session_token {
             let token = HeaderName::from_static("x-amz-s3session-token");
@@ -241,6 +539,93 @@ impl<'a> SessionCredential<'a> {
     }
 }
 
+trait SignedRequestExt {
+    /// Sign the request with `credential` and dispatch it, retrying once against the
+    /// bucket's region if S3 redirects it and [`S3Config::region_auto_detect`] is set,
+    /// and once with refreshed credentials if they are rejected and
+    /// [`RetryConfig::refresh_credentials`] is set
+    fn send_signed<'a>(
+        self,
+        credential: &'a SessionCredential<'_>,
+        payload_sha256: Option<&'a [u8]>,
+    ) -> BoxFuture<'a, Result<Response, retry::Error>>;
+}
+
+impl SignedRequestExt for RequestBuilder {
+    fn send_signed<'a>(
+        self,
+        credential: &'a SessionCredential<'_>,
+        payload_sha256: Option<&'a [u8]>,
+    ) -> BoxFuture<'a, Result<Response, retry::Error>> {
+        let config = credential.config;
+        let refresh = config.retry_config.refresh_credentials && credential.credential.is_some();
+        let retry = (config.region_auto_detect || refresh)
+            .then(|| self.try_clone())
+            .flatten();
+        let response = self
+            .with_aws_sigv4(credential.authorizer(), payload_sha256)
+            .send_retry(&config.retry_config);
+
+        async move {
+            let (error, builder) = match (response.await, retry) {
+                (Err(e), Some(builder)) => (e, builder),
+                (result, _) => return result,
+            };
+
+            if refresh && error.is_auth_failure() {
+                config.invalidate_credential(credential.session_token).await;
+                let refreshed = match credential.session_token {
+                    true => config.get_session_credential().await,
+                    false => config.get_credential().await.map(|c| SessionCredential {
+                        credential: c,
+                        session_token: false,
+                        region: config.region(),
+                        config,
+                    }),
+                };
+                let refreshed = match refreshed {
+                    Ok(refreshed) => SessionCredential {
+                        region: Cow::Owned(credential.region.to_string()),
+                        ..refreshed
+                    },
+                    Err(_) => return Err(error),
+                };
+                return builder
+                    .with_aws_sigv4(refreshed.authorizer(), payload_sha256)
+                    .send_retry(&config.retry_config)
+                    .await;
+            }
+
+            if !config.region_auto_detect {
+                return Err(error);
+            }
+            let (endpoint, detected) = match config.detect_region(&error) {
+                Some(x) => x,
+                None => return Err(error),
+            };
+
+            let (client, request) = builder.build_split();
+            let mut request = request.expect("request valid");
+            if let Some(path) = request.url().as_str().strip_prefix(endpoint.as_str()) {
+                let url = format!("{}{path}", detected.bucket_endpoint);
+                *request.url_mut() = Url::parse(&url).expect("bucket endpoint valid");
+            }
+
+            let credential = SessionCredential {
+                credential: credential.credential.clone(),
+                session_token: credential.session_token,
+                region: Cow::Owned(detected.region),
+                config,
+            };
+            Self::from_parts(client, request)
+                .with_aws_sigv4(credential.authorizer(), payload_sha256)
+                .send_retry(&config.retry_config)
+                .await
+        }
+        .boxed()
+    }
+}
+
 #[derive(Debug, Snafu)]
 pub enum RequestError {
     #[snafu(context(false))]
@@ -290,34 +675,61 @@ impl<'a> Request<'a> {
             false => SessionCredential {
                 credential: self.config.get_credential().await?,
                 session_token: false,
+                region: self.config.region(),
                 config: self.config,
             },
         };
 
         let path = self.path.as_ref();
         self.builder
-            .with_aws_sigv4(credential.authorizer(), self.payload_sha256.as_deref())
-            .send_retry(&self.config.retry_config)
+            .send_signed(&credential, self.payload_sha256.as_deref())
             .await
             .context(RetrySnafu { path })
     }
 
+    /// Apply the conditions on the source of a copy in `opts`
+    pub fn copy_source_conditions(mut self, opts: &CopyOptions) -> Self {
+        if let Some(etag) = &opts.if_match {
+            self = self.header(&COPY_SOURCE_IF_MATCH, etag);
+        }
+        if let Some(date) = opts.if_unmodified_since {
+            let date = date.format(crate::util::RFC1123_FMT).to_string();
+            self = self.header(&COPY_SOURCE_IF_UNMODIFIED_SINCE, &date);
+        }
+        self
+    }
+
     pub async fn do_put(self) -> Result<PutResult> {
         let response = self.send().await?;
         Ok(get_put_result(response.headers(), VERSION_HEADER).context(MetadataSnafu)?)
     }
 }
 
+/// How to handle an existing object when completing a multipart upload
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum CompleteMultipartMode {
+    /// Overwrite any existing object
+    Overwrite,
+    /// Fail with [`crate::Error::AlreadyExists`] if an object exists, using `If-None-Match: *`
+    Create,
+}
+
 #[derive(Debug)]
 pub(crate) struct S3Client {
     pub config: S3Config,
     pub client: ReqwestClient,
+    pub upload_limiter: Option<UploadLimiter>,
 }
 
 impl S3Client {
     pub fn new(config: S3Config) -> Result<Self> {
-        let client = config.client_options.client()?;
-        Ok(Self { config, client })
+        let client = config.client_options.store_client()?;
+        let upload_limiter = config.client_options.upload_limiter()?;
+        Ok(Self {
+            config,
+            client,
+            upload_limiter,
+        })
     }
 
     /// Make an S3 PUT request <https://docs.aws.amazon.com/AmazonS3/latest/API/API_PutObject.html>
@@ -362,22 +774,78 @@ impl S3Client {
         }
     }
 
+    /// Make an S3 PUT request streaming the `len` bytes of `body` with `aws-chunked`
+    /// content encoding and a trailing `checksum`
+    ///
+    /// As the body can't be replayed, the request is not retried
+    ///
+    /// <https://docs.aws.amazon.com/AmazonS3/latest/API/sigv4-streaming-trailers.html>
+    pub fn put_stream_request<'a>(
+        &'a self,
+        path: &'a Path,
+        len: u64,
+        body: BoxStream<'static, Result<Bytes>>,
+        checksum: Checksum,
+    ) -> Request<'a> {
+        let url = self.config.path_url(path);
+        let encoded_len = streaming::encoded_len(len, checksum);
+
+        let mut builder = self
+            .client
+            .request(Method::PUT, url)
+            .headers(self.config.encryption_headers.clone().into())
+            .header(CONTENT_ENCODING, "aws-chunked")
+            .header(CONTENT_LENGTH, encoded_len)
+            .header("x-amz-decoded-content-length", len)
+            .header("x-amz-content-sha256", STREAMING_UNSIGNED_PAYLOAD_TRAILER)
+            .header(
+                "x-amz-sdk-checksum-algorithm",
+                checksum.to_string().to_uppercase(),
+            )
+            .header(&TRAILER_HEADER, checksum.header_name());
+
+        if let Some(value) = self.config.client_options.get_content_type(path) {
+            builder = builder.header(CONTENT_TYPE, value);
+        }
+
+        let body = streaming::encode(body, len, checksum);
+        Request {
+            path,
+            builder: builder.body(reqwest::Body::wrap_stream(body)),
+            payload_sha256: None,
+            config: &self.config,
+            use_session_creds: true,
+        }
+    }
+
     /// Make an S3 Delete request <https://docs.aws.amazon.com/AmazonS3/latest/API/API_DeleteObject.html>
+    ///
+    /// If `if_match` is provided the delete is conditional on the object's ETag
     pub async fn delete_request<T: Serialize + ?Sized + Sync>(
         &self,
         path: &Path,
         query: &T,
+        if_match: Option<&str>,
     ) -> Result<()> {
         let credential = self.config.get_session_credential().await?;
         let url = self.config.path_url(path);
 
-        self.client
-            .request(Method::DELETE, url)
+        let mut builder = self.client.request(Method::DELETE, url);
+        if let Some(e_tag) = if_match {
+            builder = builder.header(IF_MATCH, e_tag);
+        }
+
+        builder
             .query(query)
-            .with_aws_sigv4(credential.authorizer(), None)
-            .send_retry(&self.config.retry_config)
+            .send_signed(&credential, None)
             .await
-            .map_err(|e| e.error(STORE, path.to_string()))?;
+            .map_err(|e| match is_object_lock_error(&e) {
+                true => crate::Error::Retained {
+                    path: path.to_string(),
+                    source: Box::new(e),
+                },
+                false => e.error(STORE, path.to_string()),
+            })?;
 
         Ok(())
     }
@@ -395,7 +863,7 @@ impl S3Client {
         }
 
         let credential = self.config.get_session_credential().await?;
-        let url = format!("{}?delete", self.config.bucket_endpoint);
+        let url = format!("{}?delete", self.config.bucket_endpoint());
 
         let mut buffer = Vec::new();
         let mut writer = quick_xml::Writer::new(&mut buffer);
@@ -453,15 +921,12 @@ impl S3Client {
         // https://docs.aws.amazon.com/AmazonS3/latest/API/API_DeleteObjects.html
         // >   "The Content-MD5 request header is required for all Multi-Object Delete requests"
         // Some platforms, like MinIO, enforce this requirement and fail requests without the header.
-        let mut hasher = Md5::new();
-        hasher.update(&body);
-        builder = builder.header("Content-MD5", BASE64_STANDARD.encode(hasher.finalize()));
+        builder = builder.header("Content-MD5", content_md5(&body));
 
         let response = builder
             .header(CONTENT_TYPE, "application/xml")
             .body(body)
-            .with_aws_sigv4(credential.authorizer(), payload_sha256.as_deref())
-            .send_retry(&self.config.retry_config)
+            .send_signed(&credential, payload_sha256.as_deref())
             .await
             .context(DeleteObjectsRequestSnafu {})?
             .bytes()
@@ -492,10 +957,40 @@ impl S3Client {
         Ok(results)
     }
 
+    /// Make an S3 HeadBucket request <https://docs.aws.amazon.com/AmazonS3/latest/API/API_HeadBucket.html>
+    pub async fn head_bucket(&self) -> Result<()> {
+        let credential = self.config.get_session_credential().await?;
+        self.client
+            .request(Method::HEAD, self.config.bucket_endpoint().as_ref())
+            .send_signed(&credential, None)
+            .await
+            .map_err(|e| e.error(STORE, self.config.bucket.clone()))?;
+        Ok(())
+    }
+
+    /// Make an S3 GetObjectAcl request <https://docs.aws.amazon.com/AmazonS3/latest/API/API_GetObjectAcl.html>
+    pub async fn get_acl(&self, path: &Path) -> Result<Acl> {
+        let credential = self.config.get_session_credential().await?;
+        let response = self
+            .client
+            .request(Method::GET, self.config.path_url(path))
+            .query(&[("acl", "")])
+            .send_signed(&credential, None)
+            .await
+            .map_err(|e| e.error(STORE, path.to_string()))?
+            .bytes()
+            .await
+            .context(AclResponseBodySnafu)?;
+
+        let policy: AccessControlPolicy =
+            quick_xml::de::from_reader(response.reader()).context(InvalidAclResponseSnafu)?;
+        Ok(policy.into())
+    }
+
     /// Make an S3 Copy request <https://docs.aws.amazon.com/AmazonS3/latest/API/API_CopyObject.html>
     pub fn copy_request<'a>(&'a self, from: &'a Path, to: &Path) -> Request<'a> {
         let url = self.config.path_url(to);
-        let source = format!("{}/{}", self.config.bucket, encode_path(from));
+        let source = self.config.copy_source(from);
 
         let builder = self
             .client
@@ -513,15 +1008,31 @@ impl S3Client {
     }
 
     pub async fn create_multipart(&self, location: &Path) -> Result<MultipartId> {
+        self.create_multipart_with_headers(location, HeaderMap::new())
+            .await
+    }
+
+    /// Make an S3 CreateMultipartUpload request, setting the additional `headers` on
+    /// the created object, e.g. its content type and user-defined metadata
+    async fn create_multipart_with_headers(
+        &self,
+        location: &Path,
+        headers: HeaderMap,
+    ) -> Result<MultipartId> {
         let credential = self.config.get_session_credential().await?;
         let url = format!("{}?uploads=", self.config.path_url(location),);
 
-        let response = self
+        let mut builder = self
             .client
             .request(Method::POST, url)
             .headers(self.config.encryption_headers.clone().into())
-            .with_aws_sigv4(credential.authorizer(), None)
-            .send_retry(&self.config.retry_config)
+            .headers(headers);
+        if let Some(acl) = self.config.canned_acl {
+            builder = builder.header(&ACL_HEADER, acl.as_str());
+        }
+
+        let response = builder
+            .send_signed(&credential, None)
             .await
             .context(CreateMultipartRequestSnafu)?
             .bytes()
@@ -534,6 +1045,40 @@ impl S3Client {
         Ok(response.upload_id)
     }
 
+    /// Make an S3 ListMultipartUploads request <https://docs.aws.amazon.com/AmazonS3/latest/API/API_ListMultipartUploads.html>
+    ///
+    /// `markers` are the key and upload ID markers from which to resume the listing
+    pub async fn list_multipart_uploads(
+        &self,
+        prefix: Option<&str>,
+        markers: Option<(String, String)>,
+    ) -> Result<ListMultipartUploadsResult> {
+        let credential = self.config.get_session_credential().await?;
+        let url = self.config.bucket_endpoint().into_owned();
+
+        let mut query = vec![("uploads", "")];
+        if let Some(prefix) = prefix {
+            query.push(("prefix", prefix))
+        }
+        if let Some((key, upload_id)) = &markers {
+            query.push(("key-marker", key));
+            query.push(("upload-id-marker", upload_id));
+        }
+
+        let response = self
+            .client
+            .request(Method::GET, &url)
+            .query(&query)
+            .send_signed(&credential, None)
+            .await
+            .context(ListMultipartUploadsRequestSnafu)?
+            .bytes()
+            .await
+            .context(ListMultipartUploadsResponseBodySnafu)?;
+
+        Ok(quick_xml::de::from_reader(response.reader()).context(InvalidMultipartResponseSnafu)?)
+    }
+
     pub async fn put_part(
         &self,
         path: &Path,
@@ -550,7 +1095,109 @@ impl S3Client {
             .await?;
 
         let content_id = get_etag(response.headers()).context(MetadataSnafu)?;
-        Ok(PartId { content_id })
+        Ok(PartId::new(content_id, part_idx))
+    }
+
+    /// Make an S3 UploadPartCopy request <https://docs.aws.amazon.com/AmazonS3/latest/API/API_UploadPartCopy.html>
+    pub async fn put_part_copy(
+        &self,
+        from: &Path,
+        to: &Path,
+        upload_id: &MultipartId,
+        part_idx: usize,
+        opts: &CopyOptions,
+    ) -> Result<PartId> {
+        let part = (part_idx + 1).to_string();
+        let builder = self
+            .client
+            .request(Method::PUT, self.config.path_url(to))
+            .header("x-amz-copy-source", self.config.copy_source(from))
+            .query(&[("partNumber", &part), ("uploadId", upload_id)]);
+
+        let request = Request {
+            builder,
+            path: from,
+            config: &self.config,
+            payload_sha256: None,
+            use_session_creds: false,
+        };
+
+        let data = request
+            .copy_source_conditions(opts)
+            .send()
+            .await?
+            .bytes()
+            .await
+            .context(CopyPartResponseBodySnafu)?;
+
+        let response: CopyPartResult =
+            quick_xml::de::from_reader(data.reader()).context(InvalidMultipartResponseSnafu)?;
+
+        Ok(PartId::new(response.e_tag, part_idx))
+    }
+
+    /// Returns the headers of the object at `from` to set on a multipart copy of it, so
+    /// as to preserve its content headers, user-defined metadata and tags as CopyObject
+    /// would
+    async fn copy_source_headers(&self, from: &Path) -> Result<HeaderMap> {
+        let options = GetOptions {
+            head: true,
+            ..Default::default()
+        };
+        let response = self.get_request(from, options).await?;
+        let source = response.headers();
+
+        let mut headers = HeaderMap::new();
+        for (name, value) in source {
+            if COPIED_HEADERS.contains(name) || name.as_str().starts_with(USER_METADATA_PREFIX) {
+                headers.append(name.clone(), value.clone());
+            }
+        }
+
+        let tag_count = source
+            .get(&TAGGING_COUNT_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<usize>().ok())
+            .unwrap_or_default();
+        if tag_count > 0 && !self.config.disable_tagging {
+            let tags = self.get_object_tags(from).await?;
+            if let Ok(value) = HeaderValue::from_str(tags.encoded()) {
+                headers.insert(&TAGS_HEADER, value);
+            }
+        }
+        Ok(headers)
+    }
+
+    /// Copy `from` to `to` if no object exists at `to`, as a multipart upload of a single
+    /// UploadPartCopy completed with `If-None-Match: *`
+    ///
+    /// As UploadPartCopy only copies the data of the source, its content headers,
+    /// user-defined metadata and tags are first read, and set when creating the upload.
+    ///
+    /// This is limited to sources of at most 5 GiB, the maximum size of a part
+    pub async fn copy_multipart_if_not_exists(
+        &self,
+        from: &Path,
+        to: &Path,
+        opts: &CopyOptions,
+    ) -> Result<()> {
+        let headers = self.copy_source_headers(from).await?;
+        let upload_id = self.create_multipart_with_headers(to, headers).await?;
+        let result = match self.put_part_copy(from, to, &upload_id, 0, opts).await {
+            Ok(part) => self
+                .complete_multipart(to, &upload_id, vec![part], CompleteMultipartMode::Create)
+                .await
+                .map(|_| ()),
+            Err(e) => Err(e),
+        };
+
+        if result.is_err() {
+            // The original error is more useful than any failure aborting the upload
+            let _ = self
+                .delete_request(to, &[("uploadId", &upload_id)], None)
+                .await;
+        }
+        result
     }
 
     pub async fn complete_multipart(
@@ -558,6 +1205,7 @@ impl S3Client {
         location: &Path,
         upload_id: &str,
         parts: Vec<PartId>,
+        mode: CompleteMultipartMode,
     ) -> Result<PutResult> {
         let parts = if parts.is_empty() {
             // If no parts were uploaded, upload an empty part
@@ -569,21 +1217,41 @@ impl S3Client {
         } else {
             parts
         };
+        validate_parts(STORE, &parts)?;
         let request = CompleteMultipartUpload::from(parts);
         let body = quick_xml::se::to_string(&request).unwrap();
 
         let credential = self.config.get_session_credential().await?;
         let url = self.config.path_url(location);
 
-        let response = self
+        let mut builder = self
             .client
             .request(Method::POST, url)
             .query(&[("uploadId", upload_id)])
-            .body(body)
-            .with_aws_sigv4(credential.authorizer(), None)
-            .send_retry(&self.config.retry_config)
+            .body(body);
+
+        if let Some(timeout) = self.config.client_options.multipart_complete_timeout()? {
+            builder = builder.timeout(timeout);
+        }
+
+        if mode == CompleteMultipartMode::Create {
+            builder = builder.header(IF_NONE_MATCH, "*");
+        }
+
+        let response = builder
+            .send_signed(&credential, None)
             .await
-            .context(CompleteMultipartRequestSnafu)?;
+            .map_err(|source| match (mode, source.status()) {
+                // S3 returns 409 if a conflicting conditional write is in progress
+                (
+                    CompleteMultipartMode::Create,
+                    Some(StatusCode::PRECONDITION_FAILED | StatusCode::CONFLICT),
+                ) => crate::Error::AlreadyExists {
+                    path: location.to_string(),
+                    source: Box::new(source),
+                },
+                _ => Error::CompleteMultipartRequest { source }.into(),
+            })?;
 
         let version = get_version(response.headers(), VERSION_HEADER).context(MetadataSnafu)?;
 
@@ -601,15 +1269,79 @@ impl S3Client {
         })
     }
 
-    #[cfg(test)]
+    /// Make an S3 PutObjectRetention request <https://docs.aws.amazon.com/AmazonS3/latest/API/API_PutObjectRetention.html>
+    pub async fn put_object_retention(&self, path: &Path, retention: &Retention) -> Result<()> {
+        let body = ObjectLockRetention {
+            mode: retention_mode(retention.mode),
+            retain_until_date: retain_until_date(retention),
+        };
+        self.put_object_lock_config(path, "retention", quick_xml::se::to_string(&body).unwrap())
+            .await
+    }
+
+    /// Make an S3 PutObjectLegalHold request <https://docs.aws.amazon.com/AmazonS3/latest/API/API_PutObjectLegalHold.html>
+    pub async fn put_object_legal_hold(&self, path: &Path, hold: bool) -> Result<()> {
+        let status = match hold {
+            true => "ON",
+            false => "OFF",
+        };
+        let body = ObjectLockLegalHold { status };
+        self.put_object_lock_config(path, "legal-hold", quick_xml::se::to_string(&body).unwrap())
+            .await
+    }
+
+    /// Object Lock configuration requests *require* a `Content-MD5` header
+    async fn put_object_lock_config(
+        &self,
+        path: &Path,
+        subresource: &str,
+        body: String,
+    ) -> Result<()> {
+        let credential = self.config.get_session_credential().await?;
+        let url = format!("{}?{subresource}", self.config.path_url(path));
+        self.client
+            .request(Method::PUT, url)
+            .header("Content-MD5", content_md5(body.as_bytes()))
+            .header(CONTENT_TYPE, "application/xml")
+            .body(body)
+            .send_signed(&credential, None)
+            .await
+            .map_err(|e| match is_object_lock_error(&e) {
+                true => crate::Error::Retained {
+                    path: path.to_string(),
+                    source: Box::new(e),
+                },
+                false => e.error(STORE, path.to_string()),
+            })?;
+        Ok(())
+    }
+
+    /// Make an S3 GetObjectTagging request <https://docs.aws.amazon.com/AmazonS3/latest/API/API_GetObjectTagging.html>
+    async fn get_object_tags(&self, path: &Path) -> Result<TagSet> {
+        let data = self
+            .get_object_tagging(path)
+            .await?
+            .bytes()
+            .await
+            .context(TaggingResponseBodySnafu)?;
+
+        let response: Tagging =
+            quick_xml::de::from_reader(data.reader()).context(InvalidTaggingResponseSnafu)?;
+
+        let mut tags = TagSet::default();
+        for tag in &response.tag_set.tag {
+            tags.push(&tag.key, &tag.value);
+        }
+        Ok(tags)
+    }
+
     pub async fn get_object_tagging(&self, path: &Path) -> Result<Response> {
         let credential = self.config.get_session_credential().await?;
         let url = format!("{}?tagging", self.config.path_url(path));
         let response = self
             .client
             .request(Method::GET, url)
-            .with_aws_sigv4(credential.authorizer(), None)
-            .send_retry(&self.config.retry_config)
+            .send_signed(&credential, None)
             .await
             .map_err(|e| e.error(STORE, path.to_string()))?;
         Ok(response)
@@ -626,6 +1358,14 @@ impl GetClient for S3Client {
         version_header: Some(VERSION_HEADER),
     };
 
+    fn retry_config(&self) -> &RetryConfig {
+        &self.config.retry_config
+    }
+
+    fn hedge_config(&self) -> Option<&HedgeConfig> {
+        self.config.client_options.hedge_config()
+    }
+
     /// Make an S3 GET request <https://docs.aws.amazon.com/AmazonS3/latest/API/API_GetObject.html>
     async fn get_request(&self, path: &Path, options: GetOptions) -> Result<Response> {
         let credential = self.config.get_session_credential().await?;
@@ -641,10 +1381,15 @@ impl GetClient for S3Client {
             builder = builder.query(&[("versionId", v)])
         }
 
+        // Checksums are only returned if requested, and so are always requested by
+        // a head request, populating ObjectMeta::checksum
+        if options.verify_checksum || options.head {
+            builder = builder.header("x-amz-checksum-mode", "ENABLED");
+        }
+
         let response = builder
             .with_get_options(options)
-            .with_aws_sigv4(credential.authorizer(), None)
-            .send_retry(&self.config.retry_config)
+            .send_signed(&credential, None)
             .await
             .map_err(|e| e.error(STORE, path.to_string()))?;
 
@@ -654,7 +1399,15 @@ impl GetClient for S3Client {
 
 #[async_trait]
 impl ListClient for S3Client {
+    fn list_deduplication(&self) -> Result<ListDeduplication> {
+        self.config.client_options.list_deduplication()
+    }
+
     /// Make an S3 List request <https://docs.aws.amazon.com/AmazonS3/latest/API/API_ListObjectsV2.html>
+    ///
+    /// Falls back to [`Self::list_v1_request`] if [`S3Config::list_objects_v1`] is set,
+    /// or if the server reports V2 as unsupported, see [`list_v2_unsupported`], and V1
+    /// then succeeds
     async fn list_request(
         &self,
         prefix: Option<&str>,
@@ -663,8 +1416,72 @@ impl ListClient for S3Client {
         offset: Option<&str>,
     ) -> Result<(ListResult, Option<String>)> {
         let credential = self.config.get_session_credential().await?;
-        let url = self.config.bucket_endpoint.clone();
+        let c = &credential;
+        let v1 = self.config.list_objects_v1 || self.config.list_v1_detected.load(Relaxed);
+        let (mut response, token) = match v1 {
+            true => {
+                self.list_v1_request(c, prefix, delimiter, token.or(offset))
+                    .await?
+            }
+            false => match self
+                .list_v2_request(c, prefix, delimiter, token, offset)
+                .await
+            {
+                Err(e) if token.is_none() && list_v2_unsupported(&e) => {
+                    match self.list_v1_request(c, prefix, delimiter, offset).await {
+                        Ok(r) => {
+                            self.config.list_v1_detected.store(true, Relaxed);
+                            r
+                        }
+                        Err(_) => return Err(e.into()),
+                    }
+                }
+                r => r?,
+            },
+        };
 
+        if self.config.client_options.hide_directory_markers()? {
+            response.remove_directory_markers();
+        }
+        Ok((response.try_into()?, token))
+    }
+}
+
+/// Returns true if `e`, the error of a ListObjectsV2 request, indicates that the
+/// server only supports ListObjects (V1)
+///
+/// A 400 response is only considered to do so if it reports the request as not
+/// implemented, or identifies the `list-type` parameter, as it may otherwise be caused
+/// by the request itself, e.g. an invalid prefix
+fn list_v2_unsupported(e: &Error) -> bool {
+    let source = match e {
+        Error::ListRequest { source } => source,
+        _ => return false,
+    };
+    match source.status() {
+        Some(StatusCode::NOT_IMPLEMENTED) => true,
+        Some(StatusCode::BAD_REQUEST) => {
+            let details = source.details();
+            let code = details.and_then(|d| d.code.as_deref());
+            let message = details.and_then(|d| d.message.as_deref());
+            code == Some("NotImplemented")
+                || message.map_or(false, |m| m.contains("list-type"))
+                || source.body().map_or(false, |b| b.contains("list-type"))
+        }
+        _ => false,
+    }
+}
+
+impl S3Client {
+    /// Make an S3 ListObjectsV2 request, returning the response and continuation token
+    async fn list_v2_request(
+        &self,
+        credential: &SessionCredential<'_>,
+        prefix: Option<&str>,
+        delimiter: bool,
+        token: Option<&str>,
+        offset: Option<&str>,
+    ) -> Result<(ListResponse, Option<String>), Error> {
         let mut query = Vec::with_capacity(4);
 
         if let Some(token) = token {
@@ -685,23 +1502,60 @@ impl ListClient for S3Client {
             query.push(("start-after", offset))
         }
 
+        let mut response = self.send_list_request(credential, &query).await?;
+        let token = response.next_continuation_token.take();
+        Ok((response, token))
+    }
+
+    /// Make an S3 [ListObjects] (V1) request, returning the response and the marker of
+    /// the next page, for servers that do not support ListObjectsV2
+    ///
+    /// [ListObjects]: https://docs.aws.amazon.com/AmazonS3/latest/API/API_ListObjects.html
+    async fn list_v1_request(
+        &self,
+        credential: &SessionCredential<'_>,
+        prefix: Option<&str>,
+        delimiter: bool,
+        marker: Option<&str>,
+    ) -> Result<(ListResponse, Option<String>), Error> {
+        let mut query = Vec::with_capacity(3);
+
+        if delimiter {
+            query.push(("delimiter", DELIMITER))
+        }
+
+        if let Some(marker) = marker {
+            query.push(("marker", marker))
+        }
+
+        if let Some(prefix) = prefix {
+            query.push(("prefix", prefix))
+        }
+
+        let mut response = self.send_list_request(credential, &query).await?;
+        let marker = response.next_marker();
+        Ok((response, marker))
+    }
+
+    async fn send_list_request(
+        &self,
+        credential: &SessionCredential<'_>,
+        query: &[(&str, &str)],
+    ) -> Result<ListResponse, Error> {
+        let url = self.config.bucket_endpoint().into_owned();
+
         let response = self
             .client
             .request(Method::GET, &url)
-            .query(&query)
-            .with_aws_sigv4(credential.authorizer(), None)
-            .send_retry(&self.config.retry_config)
+            .query(query)
+            .send_signed(credential, None)
             .await
             .context(ListRequestSnafu)?
             .bytes()
             .await
             .context(ListResponseBodySnafu)?;
 
-        let mut response: ListResponse =
-            quick_xml::de::from_reader(response.reader()).context(InvalidListResponseSnafu)?;
-        let token = response.next_continuation_token.take();
-
-        Ok((response.try_into()?, token))
+        quick_xml::de::from_reader(response.reader()).context(InvalidListResponseSnafu)
     }
 }
 
@@ -33,27 +33,40 @@
 
 use async_trait::async_trait;
 use bytes::Bytes;
+use chrono::{DateTime, Utc};
 use futures::stream::BoxStream;
 use futures::{StreamExt, TryStreamExt};
-use reqwest::header::{HeaderName, IF_MATCH, IF_NONE_MATCH};
+use reqwest::header::{HeaderName, HeaderValue, IF_MATCH, IF_NONE_MATCH};
 use reqwest::{Method, StatusCode};
 use std::{sync::Arc, time::Duration};
 use tokio::io::AsyncWrite;
 use url::Url;
 
-use crate::aws::client::{RequestError, S3Client};
+use crate::aws::client::{
+    content_md5, retain_until_date, retention_mode, CompleteMultipartMode, Request, RequestError,
+    S3Client, ACL_HEADER, CANNED_ACLS,
+};
 use crate::client::get::GetClientExt;
-use crate::client::list::ListClientExt;
+use crate::client::list::{list_prefix, ListClientExt};
+use crate::client::metadata::MetadataLimits;
+use crate::client::s3::abort_incomplete_uploads;
 use crate::client::CredentialProvider;
-use crate::multipart::{MultiPartStore, PartId, PutPart, WriteMultiPart};
+use crate::multipart::{MultiPartStore, PartId, PutPart, WriteMultiPart, MAX_PART_SIZE};
 use crate::signer::Signer;
+use crate::util::{self, unsupported_path, url_with_path};
 use crate::{
-    Error, GetOptions, GetResult, ListResult, MultipartId, ObjectMeta, ObjectStore, Path, PutMode,
-    PutOptions, PutResult, Result,
+    Acl, CopyMode, CopyOptions, DeleteOptions, Error, GetOptions, GetResult, ListPage, ListResult,
+    MultipartId, ObjectMeta, ObjectStore, Path, PutMode, PutOptions, PutResult, Result, Retention,
 };
 
 static TAGS_HEADER: HeaderName = HeaderName::from_static("x-amz-tagging");
+static OBJECT_LOCK_MODE_HEADER: HeaderName = HeaderName::from_static("x-amz-object-lock-mode");
+static OBJECT_LOCK_RETAIN_UNTIL_HEADER: HeaderName =
+    HeaderName::from_static("x-amz-object-lock-retain-until-date");
+static OBJECT_LOCK_LEGAL_HOLD_HEADER: HeaderName =
+    HeaderName::from_static("x-amz-object-lock-legal-hold");
 
+mod arn;
 mod builder;
 mod checksum;
 mod client;
@@ -61,6 +74,8 @@ mod credential;
 mod dynamo;
 mod precondition;
 mod resolve;
+mod sigv4a;
+mod streaming;
 
 pub use builder::{AmazonS3Builder, AmazonS3ConfigKey, S3EncryptionHeaders};
 pub use checksum::Checksum;
@@ -83,6 +98,23 @@ const STRICT_PATH_ENCODE_SET: percent_encoding::AsciiSet = STRICT_ENCODE_SET.rem
 
 const STORE: &str = "S3";
 
+/// The maximum length of an object key in bytes
+const MAX_KEY_BYTES: usize = 1024;
+
+/// The maximum number of parts in a multipart upload
+///
+/// <https://docs.aws.amazon.com/AmazonS3/latest/userguide/qfacts.html>
+const MAX_PARTS: usize = 10_000;
+
+/// The limits on user-defined metadata
+///
+/// <https://docs.aws.amazon.com/AmazonS3/latest/userguide/UsingMetadata.html#UserMetadata>
+const METADATA_LIMITS: MetadataLimits = MetadataLimits {
+    header_prefix: "x-amz-meta-",
+    max_size: 2 * 1024,
+    identifier_keys: false,
+};
+
 /// [`CredentialProvider`] for [`AmazonS3`]
 pub type AwsCredentialProvider = Arc<dyn CredentialProvider<Credential = AwsCredential>>;
 pub use credential::{AwsAuthorizer, AwsCredential};
@@ -105,6 +137,227 @@ impl AmazonS3 {
         &self.client.config.credentials
     }
 
+    /// Copy an object from one path to another, storing the copy with `storage_class`,
+    /// e.g. `STANDARD_IA` or `GLACIER`
+    ///
+    /// This performs the copy and storage class transition in a single request,
+    /// without the copy first being written to the default storage class.
+    ///
+    /// <https://docs.aws.amazon.com/AmazonS3/latest/userguide/storage-class-intro.html>
+    pub async fn copy_with_storage_class(
+        &self,
+        from: &Path,
+        to: &Path,
+        storage_class: &str,
+    ) -> Result<()> {
+        self.validate_path(to)?;
+        if HeaderValue::from_str(storage_class).is_err() {
+            return Err(Error::Generic {
+                store: STORE,
+                source: format!("invalid storage class {storage_class:?}").into(),
+            });
+        }
+        self.client
+            .copy_request(from, to)
+            .header("x-amz-storage-class", storage_class)
+            .send()
+            .await?;
+        Ok(())
+    }
+
+    /// Upload the `len` bytes yielded by `stream` to `location` in a single request,
+    /// sending a checksum computed whilst streaming in the trailer of the request
+    ///
+    /// This allows S3 to verify the integrity of large objects, without first buffering
+    /// them in memory to compute a checksum for the request headers. The algorithm is
+    /// that configured with [`AmazonS3Builder::with_checksum_algorithm`], or
+    /// [`Checksum::CRC32C`] if none.
+    ///
+    /// Returns an error if `stream` does not yield exactly `len` bytes, which must not
+    /// exceed the 5 GiB limit of a single S3 PUT. As `stream` is consumed by the request,
+    /// it is not retried on failure.
+    pub async fn put_stream(
+        &self,
+        location: &Path,
+        len: u64,
+        stream: BoxStream<'static, Result<Bytes>>,
+    ) -> Result<PutResult> {
+        self.put_stream_opts(location, len, stream, PutOptions::default())
+            .await
+    }
+
+    /// Perform [`Self::put_stream`] with the provided [`PutOptions`]
+    ///
+    /// As the checksum in the trailer of the request provides the integrity check
+    /// required by Object Lock, no `Content-MD5` is sent for [`PutOptions::retention`]
+    /// or [`PutOptions::legal_hold`]
+    pub async fn put_stream_opts(
+        &self,
+        location: &Path,
+        len: u64,
+        stream: BoxStream<'static, Result<Bytes>>,
+        opts: PutOptions,
+    ) -> Result<PutResult> {
+        self.validate_path(location)?;
+        let checksum = self.client.config.checksum.unwrap_or(Checksum::CRC32C);
+        let request = self
+            .client
+            .put_stream_request(location, len, stream, checksum);
+        self.put_request_opts(location, request, opts).await
+    }
+
+    /// Apply the headers and [`PutMode`] of `opts` to the PUT `request` and send it
+    async fn put_request_opts(
+        &self,
+        location: &Path,
+        mut request: Request<'_>,
+        opts: PutOptions,
+    ) -> Result<PutResult> {
+        let metadata = METADATA_LIMITS.headers(STORE, &opts.metadata)?;
+        let acl = opts
+            .acl
+            .map(|acl| acl.check(STORE, CANNED_ACLS))
+            .transpose()?;
+
+        let tags = opts.tags.encoded();
+        if !tags.is_empty() && !self.client.config.disable_tagging {
+            request = request.header(&TAGS_HEADER, tags);
+        }
+        if let Some(retention) = &opts.retention {
+            request = request
+                .header(&OBJECT_LOCK_MODE_HEADER, retention_mode(retention.mode))
+                .header(
+                    &OBJECT_LOCK_RETAIN_UNTIL_HEADER,
+                    &retain_until_date(retention),
+                );
+        }
+        if opts.legal_hold {
+            request = request.header(&OBJECT_LOCK_LEGAL_HOLD_HEADER, "ON");
+        }
+        if let Some(acl) = acl.or(self.client.config.canned_acl) {
+            request = request.header(&ACL_HEADER, acl.as_str());
+        }
+        for (name, value) in metadata {
+            request = request.header(name, value);
+        }
+
+        match (opts.mode, &self.client.config.conditional_put) {
+            (PutMode::Overwrite, _) => request.do_put().await,
+            (PutMode::Create | PutMode::Update(_), None) => Err(Error::NotImplemented),
+            (PutMode::Create, Some(S3ConditionalPut::ETagMatch)) => {
+                match request.header(&IF_NONE_MATCH, "*").do_put().await {
+                    // Technically If-None-Match should return NotModified but some stores,
+                    // such as R2, instead return PreconditionFailed
+                    // https://developers.cloudflare.com/r2/api/s3/extensions/#conditional-operations-in-putobject
+                    Err(e @ Error::NotModified { .. } | e @ Error::Precondition { .. }) => {
+                        Err(Error::AlreadyExists {
+                            path: location.to_string(),
+                            source: Box::new(e),
+                        })
+                    }
+                    r => r,
+                }
+            }
+            (PutMode::Create, Some(S3ConditionalPut::Dynamo(d))) => {
+                d.conditional_op(&self.client, location, None, move || request.do_put())
+                    .await
+            }
+            (PutMode::Update(v), Some(put)) => {
+                let etag = v.e_tag.ok_or_else(|| Error::Generic {
+                    store: STORE,
+                    source: "ETag required for conditional put".to_string().into(),
+                })?;
+                self.client
+                    .config
+                    .client_options
+                    .check_conditional_etag(&etag)?;
+                match put {
+                    S3ConditionalPut::ETagMatch => {
+                        request.header(&IF_MATCH, etag.as_str()).do_put().await
+                    }
+                    S3ConditionalPut::Dynamo(d) => {
+                        d.conditional_op(&self.client, location, Some(&etag), move || {
+                            request.do_put()
+                        })
+                        .await
+                    }
+                }
+            }
+        }
+    }
+
+    /// Perform [`ObjectStore::copy_if_not_exists`] with the source conditions of `opts`
+    async fn copy_if_not_exists_opts(
+        &self,
+        from: &Path,
+        to: &Path,
+        opts: &CopyOptions,
+    ) -> Result<()> {
+        let (k, v, status) = match &self.client.config.copy_if_not_exists {
+            Some(S3CopyIfNotExists::Header(k, v)) => (k, v, StatusCode::PRECONDITION_FAILED),
+            Some(S3CopyIfNotExists::HeaderWithStatus(k, v, status)) => (k, v, *status),
+            Some(S3CopyIfNotExists::Multipart) => {
+                return self
+                    .client
+                    .copy_multipart_if_not_exists(from, to, opts)
+                    .await
+            }
+            Some(S3CopyIfNotExists::Dynamo(lock)) => {
+                // The lock is only taken for the destination
+                util::check_copy_source(self, from, opts.clone()).await?;
+                return lock.copy_if_not_exists(&self.client, from, to).await;
+            }
+            None => {
+                return Err(Error::NotSupported {
+                    source: "S3 does not support copy-if-not-exists".to_string().into(),
+                })
+            }
+        };
+
+        let req = self
+            .client
+            .copy_request(from, to)
+            .copy_source_conditions(opts);
+        match req.header(k, v).send().await {
+            Err(RequestError::Retry { source, path }) if source.status() == Some(status) => {
+                Err(Error::AlreadyExists {
+                    source: Box::new(source),
+                    path,
+                })
+            }
+            Err(e) => Err(e.into()),
+            Ok(_) => Ok(()),
+        }
+    }
+
+    /// Set the [`Retention`] of the object at `location`, preventing it from being
+    /// overwritten or deleted until it expires
+    ///
+    /// Requires the bucket to have S3 Object Lock enabled. A retention in
+    /// [`RetentionMode::Compliance`] can't be shortened or removed, whilst one in
+    /// [`RetentionMode::Governance`] can only be by callers with the
+    /// `s3:BypassGovernanceRetention` permission.
+    ///
+    /// Operations blocked by a retention return [`Error::Retained`]
+    ///
+    /// [`RetentionMode::Compliance`]: crate::RetentionMode::Compliance
+    /// [`RetentionMode::Governance`]: crate::RetentionMode::Governance
+    ///
+    /// <https://docs.aws.amazon.com/AmazonS3/latest/userguide/object-lock.html>
+    pub async fn put_object_retention(&self, location: &Path, retention: &Retention) -> Result<()> {
+        self.client.put_object_retention(location, retention).await
+    }
+
+    /// Place, or remove, a legal hold on the object at `location`
+    ///
+    /// Whilst a legal hold is in place the object can't be overwritten or deleted,
+    /// regardless of any [`Retention`]. Requires the bucket to have S3 Object Lock enabled.
+    ///
+    /// <https://docs.aws.amazon.com/AmazonS3/latest/userguide/object-lock.html#object-lock-legal-holds>
+    pub async fn put_object_legal_hold(&self, location: &Path, hold: bool) -> Result<()> {
+        self.client.put_object_legal_hold(location, hold).await
+    }
+
     /// Create a full URL to the resource specified by `path` with this instance's configuration.
     fn path_url(&self, path: &Path) -> String {
         self.client.config.path_url(path)
@@ -146,8 +399,17 @@ impl Signer for AmazonS3 {
     /// # }
     /// ```
     async fn signed_url(&self, method: Method, path: &Path, expires_in: Duration) -> Result<Url> {
+        let config = &self.client.config;
+        if config.skip_signature {
+            return Err(client::Error::SignedUrlWithSkipSignature.into());
+        }
+
         let credential = self.credentials().get_credential().await?;
-        let authorizer = AwsAuthorizer::new(&credential, "s3", &self.client.config.region);
+        let region = config.region();
+        let mut authorizer = AwsAuthorizer::new(&credential, "s3", &region);
+        if let Some(region_set) = &config.sigv4a_region_set {
+            authorizer = authorizer.with_sigv4a(region_set);
+        }
 
         let path_url = self.path_url(path);
         let mut url = Url::parse(&path_url).map_err(|e| crate::Error::Generic {
@@ -164,57 +426,24 @@ impl Signer for AmazonS3 {
 #[async_trait]
 impl ObjectStore for AmazonS3 {
     async fn put_opts(&self, location: &Path, bytes: Bytes, opts: PutOptions) -> Result<PutResult> {
-        let mut request = self.client.put_request(location, bytes, true);
-        let tags = opts.tags.encoded();
-        if !tags.is_empty() && !self.client.config.disable_tagging {
-            request = request.header(&TAGS_HEADER, tags);
-        }
+        self.validate_path(location)?;
+        let object_lock = opts.retention.is_some() || opts.legal_hold;
+        // Object Lock requires an integrity check, defaulting to Content-MD5
+        let md5 =
+            (object_lock && self.client.config.checksum.is_none()).then(|| content_md5(&bytes));
 
-        match (opts.mode, &self.client.config.conditional_put) {
-            (PutMode::Overwrite, _) => request.do_put().await,
-            (PutMode::Create | PutMode::Update(_), None) => Err(Error::NotImplemented),
-            (PutMode::Create, Some(S3ConditionalPut::ETagMatch)) => {
-                match request.header(&IF_NONE_MATCH, "*").do_put().await {
-                    // Technically If-None-Match should return NotModified but some stores,
-                    // such as R2, instead return PreconditionFailed
-                    // https://developers.cloudflare.com/r2/api/s3/extensions/#conditional-operations-in-putobject
-                    Err(e @ Error::NotModified { .. } | e @ Error::Precondition { .. }) => {
-                        Err(Error::AlreadyExists {
-                            path: location.to_string(),
-                            source: Box::new(e),
-                        })
-                    }
-                    r => r,
-                }
-            }
-            (PutMode::Create, Some(S3ConditionalPut::Dynamo(d))) => {
-                d.conditional_op(&self.client, location, None, move || request.do_put())
-                    .await
-            }
-            (PutMode::Update(v), Some(put)) => {
-                let etag = v.e_tag.ok_or_else(|| Error::Generic {
-                    store: STORE,
-                    source: "ETag required for conditional put".to_string().into(),
-                })?;
-                match put {
-                    S3ConditionalPut::ETagMatch => {
-                        request.header(&IF_MATCH, etag.as_str()).do_put().await
-                    }
-                    S3ConditionalPut::Dynamo(d) => {
-                        d.conditional_op(&self.client, location, Some(&etag), move || {
-                            request.do_put()
-                        })
-                        .await
-                    }
-                }
-            }
+        let mut request = self.client.put_request(location, bytes, true);
+        if let Some(md5) = &md5 {
+            request = request.header("Content-MD5", md5);
         }
+        self.put_request_opts(location, request, opts).await
     }
 
     async fn put_multipart(
         &self,
         location: &Path,
     ) -> Result<(MultipartId, Box<dyn AsyncWrite + Unpin + Send>)> {
+        self.validate_path(location)?;
         let id = self.client.create_multipart(location).await?;
 
         let upload = S3MultiPartUpload {
@@ -223,12 +452,26 @@ impl ObjectStore for AmazonS3 {
             client: Arc::clone(&self.client),
         };
 
-        Ok((id, Box::new(WriteMultiPart::new(upload, 8))))
+        let config = &self.client.config;
+        let writer = WriteMultiPart::new(upload, config.multipart_concurrency)
+            .with_part_size(config.multipart_part_size);
+        let writer = match config.multipart_part_size_scaling {
+            true => {
+                let max_part_size = usize::try_from(MAX_PART_SIZE).unwrap_or(usize::MAX);
+                writer.with_part_size_scaling(MAX_PARTS, max_part_size)
+            }
+            false => writer,
+        };
+        let writer = match &self.client.upload_limiter {
+            Some(limiter) => writer.with_upload_limiter(limiter.clone()),
+            None => writer,
+        };
+        Ok((id, Box::new(writer)))
     }
 
     async fn abort_multipart(&self, location: &Path, multipart_id: &MultipartId) -> Result<()> {
         self.client
-            .delete_request(location, &[("uploadId", multipart_id)])
+            .delete_request(location, &[("uploadId", multipart_id)], None)
             .await
     }
 
@@ -237,7 +480,18 @@ impl ObjectStore for AmazonS3 {
     }
 
     async fn delete(&self, location: &Path) -> Result<()> {
-        self.client.delete_request(location, &()).await
+        self.client.delete_request(location, &(), None).await
+    }
+
+    async fn delete_opts(&self, location: &Path, opts: DeleteOptions) -> Result<()> {
+        let if_match = opts.if_match.as_deref();
+        match &opts.version {
+            Some(v) => {
+                let query = &[("versionId", v)];
+                self.client.delete_request(location, query, if_match).await
+            }
+            None => self.client.delete_request(location, &(), if_match).await,
+        }
     }
 
     fn delete_stream<'a>(
@@ -282,40 +536,92 @@ impl ObjectStore for AmazonS3 {
         self.client.list_with_offset(prefix, offset)
     }
 
+    fn list_range(&self, start: &Path, end: &Path) -> BoxStream<'_, Result<ObjectMeta>> {
+        if self.client.config.is_s3_express() {
+            let (start, end) = (start.clone(), end.clone());
+            // S3 Express does not list objects in lexicographic order
+            return self
+                .client
+                .list(None)
+                .try_filter(move |f| {
+                    futures::future::ready(f.location >= start && f.location < end)
+                })
+                .boxed();
+        }
+
+        let location = start.clone();
+        let head = Box::pin(async move { self.head(&location).await });
+        util::list_range(start, end, head, self.client.list_with_offset(None, start))
+    }
+
     async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
         self.client.list_with_delimiter(prefix).await
     }
 
+    fn list_with_delimiter_stream(
+        &self,
+        prefix: Option<&Path>,
+    ) -> BoxStream<'_, Result<ListResult>> {
+        self.client.list_paginated(prefix, true, None)
+    }
+
+    fn list_from_token(
+        &self,
+        prefix: Option<&Path>,
+        token: Option<&str>,
+    ) -> BoxStream<'_, Result<ListPage>> {
+        self.client.list_from_token(prefix, token)
+    }
+
+    async fn probe(&self) -> Result<()> {
+        self.client.head_bucket().await
+    }
+
+    async fn get_acl(&self, location: &Path) -> Result<Acl> {
+        self.client.get_acl(location).await
+    }
+
     async fn copy(&self, from: &Path, to: &Path) -> Result<()> {
+        self.validate_path(to)?;
         self.client.copy_request(from, to).send().await?;
         Ok(())
     }
 
-    async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
-        let (k, v, status) = match &self.client.config.copy_if_not_exists {
-            Some(S3CopyIfNotExists::Header(k, v)) => (k, v, StatusCode::PRECONDITION_FAILED),
-            Some(S3CopyIfNotExists::HeaderWithStatus(k, v, status)) => (k, v, *status),
-            Some(S3CopyIfNotExists::Dynamo(lock)) => {
-                return lock.copy_if_not_exists(&self.client, from, to).await
-            }
-            None => {
-                return Err(Error::NotSupported {
-                    source: "S3 does not support copy-if-not-exists".to_string().into(),
-                })
+    async fn copy_opts(&self, from: &Path, to: &Path, opts: CopyOptions) -> Result<()> {
+        self.validate_path(to)?;
+        match opts.mode {
+            CopyMode::Overwrite => {
+                let request = self.client.copy_request(from, to);
+                request.copy_source_conditions(&opts).send().await?;
+                Ok(())
             }
-        };
+            CopyMode::Create => self.copy_if_not_exists_opts(from, to, &opts).await,
+        }
+    }
 
-        let req = self.client.copy_request(from, to);
-        match req.header(k, v).send().await {
-            Err(RequestError::Retry { source, path }) if source.status() == Some(status) => {
-                Err(Error::AlreadyExists {
-                    source: Box::new(source),
-                    path,
-                })
-            }
-            Err(e) => Err(e.into()),
-            Ok(_) => Ok(()),
+    async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        self.validate_path(to)?;
+        self.copy_if_not_exists_opts(from, to, &CopyOptions::default())
+            .await
+    }
+
+    fn object_url(&self, location: &Path) -> Result<Url> {
+        let bucket = &self.client.config.bucket;
+        let url =
+            Url::parse(&format!("s3://{bucket}")).map_err(|source| crate::Error::Generic {
+                store: STORE,
+                source: Box::new(source),
+            })?;
+        Ok(url_with_path(url, location))
+    }
+
+    fn validate_path(&self, location: &Path) -> Result<()> {
+        // https://docs.aws.amazon.com/AmazonS3/latest/userguide/object-keys.html
+        if location.as_ref().len() > MAX_KEY_BYTES {
+            let reason = format!("key exceeds {MAX_KEY_BYTES} bytes");
+            return Err(unsupported_path(location, STORE, reason));
         }
+        Ok(())
     }
 }
 
@@ -335,10 +641,21 @@ impl PutPart for S3MultiPartUpload {
 
     async fn complete(&self, completed_parts: Vec<PartId>) -> Result<()> {
         self.client
-            .complete_multipart(&self.location, &self.upload_id, completed_parts)
+            .complete_multipart(
+                &self.location,
+                &self.upload_id,
+                completed_parts,
+                CompleteMultipartMode::Overwrite,
+            )
             .await?;
         Ok(())
     }
+
+    async fn abort(&self) -> Result<()> {
+        self.client
+            .delete_request(&self.location, &[("uploadId", &self.upload_id)], None)
+            .await
+    }
 }
 
 #[async_trait]
@@ -363,11 +680,31 @@ impl MultiPartStore for AmazonS3 {
         id: &MultipartId,
         parts: Vec<PartId>,
     ) -> Result<PutResult> {
-        self.client.complete_multipart(path, id, parts).await
+        self.client
+            .complete_multipart(path, id, parts, CompleteMultipartMode::Overwrite)
+            .await
     }
 
     async fn abort_multipart(&self, path: &Path, id: &MultipartId) -> Result<()> {
-        self.client.delete_request(path, &[("uploadId", id)]).await
+        self.client
+            .delete_request(path, &[("uploadId", id)], None)
+            .await
+    }
+
+    async fn abort_incomplete_uploads(
+        &self,
+        prefix: Option<&Path>,
+        older_than: DateTime<Utc>,
+    ) -> Result<usize> {
+        let prefix = list_prefix(prefix);
+        let list = |markers| {
+            self.client
+                .list_multipart_uploads(prefix.as_deref(), markers)
+        };
+        let abort = |path: Path, id: MultipartId| async move {
+            MultiPartStore::abort_multipart(self, &path, &id).await
+        };
+        abort_incomplete_uploads(list, abort, older_than).await
     }
 }
 
@@ -415,6 +752,7 @@ mod tests {
         }
         if test_conditional_put {
             put_opts(&integration, true).await;
+            delete_opts(&integration).await;
         }
 
         // run integration test with unsigned payload enabled
@@ -434,28 +772,189 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn s3_test_get_nonexistent_location() {
+    async fn s3_test_put_stream() {
         crate::test_util::maybe_skip_integration!();
-        let integration = AmazonS3Builder::from_env().build().unwrap();
+        let data: Bytes = (0..3_000_000_u32).map(|x| x as u8).collect();
+        let stream = || {
+            let chunks: Vec<_> = data.chunks(100_000).map(Bytes::copy_from_slice).collect();
+            futures::stream::iter(chunks.into_iter().map(Ok)).boxed()
+        };
 
-        let location = Path::from_iter([NON_EXISTENT_NAME]);
+        for checksum in [Checksum::CRC32C, Checksum::SHA256] {
+            let builder = AmazonS3Builder::from_env().with_checksum_algorithm(checksum);
+            let integration = builder.build().unwrap();
+            let location = Path::from("test_put_stream");
+            let len = data.len() as u64;
+            integration
+                .put_stream(&location, len, stream())
+                .await
+                .unwrap();
 
-        let err = get_nonexistent_object(&integration, Some(location))
-            .await
-            .unwrap_err();
-        assert!(matches!(err, crate::Error::NotFound { .. }), "{}", err);
+            let read = integration.get(&location).await.unwrap().bytes().await;
+            assert_eq!(read.unwrap(), data);
+            integration.delete(&location).await.unwrap();
+        }
     }
 
     #[tokio::test]
-    async fn s3_test_get_nonexistent_bucket() {
-        crate::test_util::maybe_skip_integration!();
-        let config = AmazonS3Builder::from_env().with_bucket_name(NON_EXISTENT_NAME);
-        let integration = config.build().unwrap();
-
-        let location = Path::from_iter([NON_EXISTENT_NAME]);
-
-        let err = integration.get(&location).await.unwrap_err();
-        assert!(matches!(err, crate::Error::NotFound { .. }), "{}", err);
+    async fn s3_test_put_stream_trailer() {
+        use crate::client::mock_server::MockServer;
+        use base64::prelude::{Engine, BASE64_STANDARD};
+        use hyper::{Body, Response};
+
+        let data: Bytes = (0..100_000_u32).map(|x| x as u8).collect();
+        let expected = data.clone();
+
+        let server = MockServer::new();
+        server.push_async_fn(|req| async move {
+            let headers = req.headers().clone();
+            assert_eq!(headers["Content-Encoding"], "aws-chunked");
+            assert_eq!(headers["x-amz-decoded-content-length"], "100000");
+            assert_eq!(headers["x-amz-trailer"], "x-amz-checksum-crc32c");
+            assert_eq!(
+                headers["x-amz-content-sha256"],
+                "STREAMING-UNSIGNED-PAYLOAD-TRAILER"
+            );
+            let auth = headers["Authorization"].to_str().unwrap();
+            assert!(auth.contains("x-amz-trailer"), "{auth}");
+
+            let body = hyper::body::to_bytes(req.into_body()).await.unwrap();
+            assert_eq!(headers["Content-Length"], body.len().to_string().as_str());
+
+            // Decode the chunks
+            let mut decoded = Vec::new();
+            let mut remaining = body.as_ref();
+            loop {
+                let idx = remaining.windows(2).position(|x| x == b"\r\n").unwrap();
+                let size = std::str::from_utf8(&remaining[..idx]).unwrap();
+                let size = usize::from_str_radix(size, 16).unwrap();
+                remaining = &remaining[idx + 2..];
+                if size == 0 {
+                    break;
+                }
+                decoded.extend_from_slice(&remaining[..size]);
+                assert_eq!(&remaining[size..size + 2], b"\r\n");
+                remaining = &remaining[size + 2..];
+            }
+            assert_eq!(decoded, expected);
+
+            let crc = BASE64_STANDARD.encode(Checksum::CRC32C.digest(&expected));
+            let trailer = format!("x-amz-checksum-crc32c:{crc}\r\n\r\n");
+            assert_eq!(remaining, trailer.as_bytes());
+
+            Response::builder()
+                .header("ETag", "\"1\"")
+                .body(Body::empty())
+                .unwrap()
+        });
+
+        let store = AmazonS3Builder::new()
+            .with_bucket_name("bucket")
+            .with_region("us-east-1")
+            .with_access_key_id("key")
+            .with_secret_access_key("secret")
+            .with_endpoint(server.url())
+            .with_allow_http(true)
+            .build()
+            .unwrap();
+
+        let chunks: Vec<_> = data.chunks(30_000).map(Bytes::copy_from_slice).collect();
+        let stream = futures::stream::iter(chunks.into_iter().map(Ok)).boxed();
+        let location = Path::from("foo");
+        let result = store.put_stream(&location, 100_000, stream).await.unwrap();
+        assert_eq!(result.e_tag.as_deref(), Some("\"1\""));
+
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn s3_test_put_stream_opts() {
+        use crate::client::mock_server::MockServer;
+        use hyper::{Body, Response, StatusCode};
+
+        let server = MockServer::new();
+        let store = AmazonS3Builder::new()
+            .with_bucket_name("bucket")
+            .with_region("us-east-1")
+            .with_access_key_id("key")
+            .with_secret_access_key("secret")
+            .with_endpoint(server.url())
+            .with_allow_http(true)
+            .with_conditional_put(S3ConditionalPut::ETagMatch)
+            .build()
+            .unwrap();
+
+        let stream = || futures::stream::iter([Ok(Bytes::from("data"))]).boxed();
+        let location = Path::from("foo");
+
+        // Applies the headers of the options
+        server.push_fn(|req| {
+            assert_eq!(req.headers()["x-amz-tagging"], "team=a");
+            assert_eq!(req.headers()["x-amz-meta-author"], "alice");
+            assert_eq!(req.headers()["If-None-Match"], "*");
+            Response::builder()
+                .header("ETag", "\"1\"")
+                .body(Body::empty())
+                .unwrap()
+        });
+        let mut opts = PutOptions::from(PutMode::Create);
+        opts.tags.push("team", "a");
+        opts.metadata
+            .insert("author".to_string(), "alice".to_string());
+        let result = store
+            .put_stream_opts(&location, 4, stream(), opts)
+            .await
+            .unwrap();
+        assert_eq!(result.e_tag.as_deref(), Some("\"1\""));
+
+        // Maps errors by status
+        server.push(
+            Response::builder()
+                .status(StatusCode::PRECONDITION_FAILED)
+                .body(Body::empty())
+                .unwrap(),
+        );
+        let err = store
+            .put_stream_opts(&location, 4, stream(), PutMode::Create.into())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::AlreadyExists { .. }), "{err}");
+
+        server.push(
+            Response::builder()
+                .status(StatusCode::FORBIDDEN)
+                .body(Body::empty())
+                .unwrap(),
+        );
+        let err = store.put_stream(&location, 4, stream()).await.unwrap_err();
+        assert!(matches!(err, Error::PermissionDenied { .. }), "{err}");
+
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn s3_test_get_nonexistent_location() {
+        crate::test_util::maybe_skip_integration!();
+        let integration = AmazonS3Builder::from_env().build().unwrap();
+
+        let location = Path::from_iter([NON_EXISTENT_NAME]);
+
+        let err = get_nonexistent_object(&integration, Some(location))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, crate::Error::NotFound { .. }), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn s3_test_get_nonexistent_bucket() {
+        crate::test_util::maybe_skip_integration!();
+        let config = AmazonS3Builder::from_env().with_bucket_name(NON_EXISTENT_NAME);
+        let integration = config.build().unwrap();
+
+        let location = Path::from_iter([NON_EXISTENT_NAME]);
+
+        let err = integration.get(&location).await.unwrap_err();
+        assert!(matches!(err, crate::Error::NotFound { .. }), "{}", err);
     }
 
     #[tokio::test]
@@ -493,6 +992,1460 @@ mod tests {
         assert!(matches!(err, crate::Error::NotFound { .. }), "{}", err);
     }
 
+    #[tokio::test]
+    async fn s3_test_skip_signature() {
+        use crate::client::mock_server::MockServer;
+        use crate::signer::Signer;
+        use hyper::{Body, Response};
+
+        let server = MockServer::new();
+        // Skipping signatures takes precedence over configured credentials
+        let store = AmazonS3Builder::new()
+            .with_bucket_name("bucket")
+            .with_endpoint(server.url())
+            .with_allow_http(true)
+            .with_access_key_id("key")
+            .with_secret_access_key("secret")
+            .with_skip_signature(true)
+            .build()
+            .unwrap();
+
+        server.push_fn(|req| {
+            assert!(req.headers().get("authorization").is_none());
+            assert!(req.headers().get("x-amz-date").is_none());
+            Response::builder()
+                .header("etag", "\"1\"")
+                .header("last-modified", "Thu, 01 Jan 1970 00:00:00 GMT")
+                .body(Body::from("data"))
+                .unwrap()
+        });
+        let path = Path::from("file");
+        let data = store.get(&path).await.unwrap().bytes().await.unwrap();
+        assert_eq!(data.as_ref(), b"data");
+        server.shutdown().await;
+
+        let err = store
+            .signed_url(Method::GET, &path, Duration::from_secs(60))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("skipping signatures"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn s3_test_abort_multipart() {
+        use crate::client::mock_server::MockServer;
+        use crate::multipart::WriteMultiPart;
+        use hyper::{Body, Response};
+
+        let server = MockServer::new();
+        let store = AmazonS3Builder::new()
+            .with_bucket_name("bucket")
+            .with_endpoint(server.url())
+            .with_allow_http(true)
+            .with_skip_signature(true)
+            .build()
+            .unwrap();
+
+        let upload = S3MultiPartUpload {
+            location: Path::from("file"),
+            upload_id: "upload".to_string(),
+            client: Arc::clone(&store.client),
+        };
+        let mut writer = WriteMultiPart::new(upload, 8);
+        writer.write_all(b"data").await.unwrap();
+
+        server.push_fn(|req| {
+            assert_eq!(req.method(), "DELETE");
+            assert_eq!(req.uri().path(), "/bucket/file");
+            assert_eq!(req.uri().query(), Some("uploadId=upload"));
+            Response::new(Body::empty())
+        });
+        writer.abort().await.unwrap();
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn s3_test_hide_directory_markers() {
+        use crate::client::mock_server::MockServer;
+        use hyper::{Body, Response};
+
+        // A listing of prefix `foo/` containing the `foo/` directory marker
+        const LIST: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<ListBucketResult>
+    <Contents>
+        <Key>foo/</Key>
+        <LastModified>2023-01-01T00:00:00.000Z</LastModified>
+        <Size>0</Size>
+    </Contents>
+    <Contents>
+        <Key>foo/bar</Key>
+        <LastModified>2023-01-01T00:00:00.000Z</LastModified>
+        <Size>3</Size>
+    </Contents>
+</ListBucketResult>"#;
+
+        let server = MockServer::new();
+        let builder = AmazonS3Builder::new()
+            .with_bucket_name("bucket")
+            .with_endpoint(server.url())
+            .with_allow_http(true)
+            .with_skip_signature(true);
+
+        let options = crate::ClientOptions::new()
+            .with_allow_http(true)
+            .with_hide_directory_markers(true);
+        let cases = [
+            (builder.clone().build().unwrap(), vec!["foo", "foo/bar"]),
+            (
+                builder.with_client_options(options).build().unwrap(),
+                vec!["foo/bar"],
+            ),
+        ];
+
+        let prefix = Path::from("foo");
+        for (store, expected) in cases {
+            server.push(Response::new(Body::from(LIST)));
+            let r = store.list_with_delimiter(Some(&prefix)).await.unwrap();
+            let locations: Vec<_> = r.objects.iter().map(|o| o.location.as_ref()).collect();
+            assert_eq!(locations, expected);
+        }
+
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn s3_test_get_version() {
+        use crate::client::mock_server::MockServer;
+        use hyper::{Body, Response};
+
+        let server = MockServer::new();
+        let store = AmazonS3Builder::new()
+            .with_bucket_name("bucket")
+            .with_endpoint(server.url())
+            .with_allow_http(true)
+            .with_skip_signature(true)
+            .build()
+            .unwrap();
+
+        for method in ["GET", "HEAD"] {
+            server.push_fn(move |req| {
+                assert_eq!(req.method(), method);
+                assert_eq!(req.uri().query(), Some("versionId=v1"));
+                Response::builder()
+                    .header("x-amz-version-id", "v1")
+                    .body(Body::from("old"))
+                    .unwrap()
+            });
+        }
+
+        let path = Path::from("file");
+        let options = GetOptions {
+            version: Some("v1".to_string()),
+            ..Default::default()
+        };
+        let result = store.get_opts(&path, options.clone()).await.unwrap();
+        assert_eq!(result.meta.version.as_deref(), Some("v1"));
+        assert_eq!(result.bytes().await.unwrap().as_ref(), b"old");
+
+        let head = GetOptions {
+            head: true,
+            ..options
+        };
+        let meta = store.get_opts(&path, head).await.unwrap().meta;
+        assert_eq!(meta.version.as_deref(), Some("v1"));
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn s3_test_checksum() {
+        use crate::client::mock_server::MockServer;
+        use crate::{ChecksumAlgorithm, ObjectChecksum};
+        use hyper::{Body, Response};
+
+        let server = MockServer::new();
+        let store = AmazonS3Builder::new()
+            .with_bucket_name("bucket")
+            .with_endpoint(server.url())
+            .with_allow_http(true)
+            .with_skip_signature(true)
+            .build()
+            .unwrap();
+
+        server.push_fn(|req| {
+            assert_eq!(req.method(), "HEAD");
+            // Checksums are only returned if requested
+            let mode = req.headers().get("x-amz-checksum-mode").unwrap();
+            assert_eq!(mode, "ENABLED");
+            Response::builder()
+                .header("content-length", "9")
+                .header("x-amz-checksum-crc32c", "4waSgw==")
+                .body(Body::empty())
+                .unwrap()
+        });
+        let meta = store.head(&Path::from("file")).await.unwrap();
+        let expected = ObjectChecksum {
+            algorithm: ChecksumAlgorithm::Crc32c,
+            digest: 0xE3069283_u32.to_be_bytes().to_vec(),
+        };
+        assert_eq!(meta.checksum, Some(expected));
+
+        // The checksum of a multipart object can't be recomputed and so is omitted
+        server.push_fn(|_| {
+            Response::builder()
+                .header("content-length", "9")
+                .header("x-amz-checksum-crc32c", "4waSgw==-2")
+                .body(Body::empty())
+                .unwrap()
+        });
+        let meta = store.head(&Path::from("file")).await.unwrap();
+        assert_eq!(meta.checksum, None);
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn s3_test_put_if_changed() {
+        use crate::client::mock_server::MockServer;
+        use hyper::{Body, Response, StatusCode};
+
+        let server = MockServer::new();
+        let store = AmazonS3Builder::new()
+            .with_bucket_name("bucket")
+            .with_endpoint(server.url())
+            .with_allow_http(true)
+            .with_skip_signature(true)
+            .build()
+            .unwrap();
+        let path = Path::from("file");
+        let head = |req: hyper::Request<Body>| {
+            assert_eq!(req.method(), "HEAD");
+            Response::builder()
+                .header("content-length", "9")
+                .header("x-amz-checksum-crc32c", "4waSgw==")
+                .body(Body::empty())
+                .unwrap()
+        };
+        let put = |req: hyper::Request<Body>| {
+            assert_eq!(req.method(), "PUT");
+            Response::builder()
+                .header("etag", "\"1\"")
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        // A missing object is written
+        server.push_fn(|req| {
+            assert_eq!(req.method(), "HEAD");
+            Response::builder()
+                .status(StatusCode::NOT_FOUND)
+                .body(Body::empty())
+                .unwrap()
+        });
+        server.push_fn(put);
+        let written = crate::put_if_changed(&store, &path, "123456789".into())
+            .await
+            .unwrap();
+        assert!(written);
+
+        // The second identical put is skipped
+        server.push_fn(head);
+        let written = crate::put_if_changed(&store, &path, "123456789".into())
+            .await
+            .unwrap();
+        assert!(!written);
+
+        // Different contents of the same size are written
+        server.push_fn(head);
+        server.push_fn(put);
+        let written = crate::put_if_changed(&store, &path, "987654321".into())
+            .await
+            .unwrap();
+        assert!(written);
+
+        // As are objects without a checksum
+        server.push_fn(|_| {
+            Response::builder()
+                .header("content-length", "9")
+                .body(Body::empty())
+                .unwrap()
+        });
+        server.push_fn(put);
+        let written = crate::put_if_changed(&store, &path, "123456789".into())
+            .await
+            .unwrap();
+        assert!(written);
+
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn s3_test_delete_version() {
+        use crate::client::mock_server::MockServer;
+        use hyper::{Body, Response, StatusCode};
+
+        let server = MockServer::new();
+        let store = AmazonS3Builder::new()
+            .with_bucket_name("bucket")
+            .with_endpoint(server.url())
+            .with_allow_http(true)
+            .with_skip_signature(true)
+            .build()
+            .unwrap();
+
+        let path = Path::from("file");
+        let mut versions = vec![];
+        for version in ["v1", "v2"] {
+            server.push_fn(move |req| {
+                assert_eq!(req.method(), "PUT");
+                Response::builder()
+                    .header("etag", format!("\"{version}\""))
+                    .header("x-amz-version-id", version)
+                    .body(Body::empty())
+                    .unwrap()
+            });
+            let r = store.put(&path, version.into()).await.unwrap();
+            versions.push(r.version.unwrap());
+        }
+        assert_eq!(versions, ["v1", "v2"]);
+
+        // Permanently delete the older version, leaving the current version intact
+        server.push_fn(|req| {
+            assert_eq!(req.method(), "DELETE");
+            assert_eq!(req.uri().path(), "/bucket/file");
+            assert_eq!(req.uri().query(), Some("versionId=v1"));
+            Response::builder()
+                .status(StatusCode::NO_CONTENT)
+                .header("x-amz-version-id", "v1")
+                .body(Body::empty())
+                .unwrap()
+        });
+        let options = DeleteOptions {
+            version: Some(versions[0].clone()),
+            ..Default::default()
+        };
+        store.delete_opts(&path, options).await.unwrap();
+
+        server.push_fn(|req| {
+            assert_eq!(req.uri().query(), Some("versionId=v1"));
+            Response::builder()
+                .status(StatusCode::NOT_FOUND)
+                .body(Body::empty())
+                .unwrap()
+        });
+        let options = GetOptions {
+            version: Some(versions[0].clone()),
+            ..Default::default()
+        };
+        let err = store.get_opts(&path, options).await.unwrap_err();
+        assert!(matches!(err, crate::Error::NotFound { .. }), "{err}");
+
+        // A delete without a version does not specify one, adding a delete marker
+        server.push_fn(|req| {
+            assert_eq!(req.method(), "DELETE");
+            assert_eq!(req.uri().query(), None);
+            Response::builder()
+                .status(StatusCode::NO_CONTENT)
+                .header("x-amz-delete-marker", "true")
+                .body(Body::empty())
+                .unwrap()
+        });
+        store.delete(&path).await.unwrap();
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn s3_test_metadata() {
+        use crate::client::mock_server::MockServer;
+        use hyper::{Body, Response};
+
+        let server = MockServer::new();
+        let store = AmazonS3Builder::new()
+            .with_bucket_name("bucket")
+            .with_endpoint(server.url())
+            .with_allow_http(true)
+            .with_skip_signature(true)
+            .build()
+            .unwrap();
+
+        let path = Path::from("file");
+        server.push_fn(|req| {
+            assert_eq!(req.headers()["x-amz-meta-author"], "alice");
+            Response::builder()
+                .header("etag", "\"1\"")
+                .body(Body::empty())
+                .unwrap()
+        });
+        let mut opts = PutOptions::default();
+        opts.metadata
+            .insert("author".to_string(), "alice".to_string());
+        store
+            .put_opts(&path, "data".into(), opts.clone())
+            .await
+            .unwrap();
+
+        // Oversized metadata is rejected without making a request
+        opts.metadata.insert("notes".to_string(), "a".repeat(2048));
+        let err = store
+            .put_opts(&path, "data".into(), opts)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::MetadataTooLarge { .. }), "{err}");
+        assert!(err.to_string().contains("S3 limit of 2048 bytes"), "{err}");
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn s3_test_copy_opts() {
+        use crate::client::mock_server::MockServer;
+        use hyper::{Body, Response, StatusCode};
+
+        let server = MockServer::new();
+        let store = AmazonS3Builder::new()
+            .with_bucket_name("bucket")
+            .with_endpoint(server.url())
+            .with_allow_http(true)
+            .with_skip_signature(true)
+            .build()
+            .unwrap();
+
+        server.push_fn(|req| {
+            assert_eq!(req.headers()["x-amz-copy-source-if-match"], "\"stale\"");
+            Response::builder()
+                .status(StatusCode::PRECONDITION_FAILED)
+                .body(Body::empty())
+                .unwrap()
+        });
+
+        let opts = CopyOptions {
+            if_match: Some("\"stale\"".to_string()),
+            ..Default::default()
+        };
+        let err = store
+            .copy_opts(&Path::from("a"), &Path::from("b"), opts)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::Precondition { .. }), "{err}");
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn s3_test_copy_if_not_exists_multipart() {
+        use crate::client::mock_server::MockServer;
+        use hyper::{Body, Response, StatusCode};
+
+        let server = MockServer::new();
+        let store = AmazonS3Builder::new()
+            .with_bucket_name("bucket")
+            .with_endpoint(server.url())
+            .with_allow_http(true)
+            .with_skip_signature(true)
+            .with_config(AmazonS3ConfigKey::CopyIfNotExists, "multipart")
+            .build()
+            .unwrap();
+
+        for exists in [false, true] {
+            // The content headers, metadata and tags of the source are preserved
+            server.push_fn(|req| {
+                assert_eq!(req.method(), "HEAD");
+                assert_eq!(req.uri().path(), "/bucket/a");
+                Response::builder()
+                    .header("Content-Type", "text/plain")
+                    .header("Content-Length", "3")
+                    .header("Last-Modified", "Thu, 01 Jan 2024 00:00:00 GMT")
+                    .header("x-amz-meta-owner", "me")
+                    .header("x-amz-tagging-count", "1")
+                    .body(Body::empty())
+                    .unwrap()
+            });
+            server.push_fn(|req| {
+                assert_eq!(req.method(), "GET");
+                assert_eq!(req.uri().query(), Some("tagging"));
+                let body = "<Tagging><TagSet><Tag><Key>team</Key><Value>a b</Value></Tag></TagSet></Tagging>";
+                Response::new(Body::from(body))
+            });
+            server.push_fn(|req| {
+                assert_eq!(req.method(), "POST");
+                assert_eq!(req.uri().path(), "/bucket/b");
+                assert_eq!(req.headers()["Content-Type"], "text/plain");
+                assert_eq!(req.headers()["x-amz-meta-owner"], "me");
+                assert_eq!(req.headers()["x-amz-tagging"], "team=a+b");
+                let body = "<InitiateMultipartUploadResult><UploadId>1</UploadId></InitiateMultipartUploadResult>";
+                Response::new(Body::from(body))
+            });
+            server.push_fn(|req| {
+                assert_eq!(req.method(), "PUT");
+                assert_eq!(req.uri().query(), Some("partNumber=1&uploadId=1"));
+                assert_eq!(req.headers()["x-amz-copy-source"], "bucket/a");
+                let body = "<CopyPartResult><ETag>\"1\"</ETag></CopyPartResult>";
+                Response::new(Body::from(body))
+            });
+            server.push_fn(move |req| {
+                assert_eq!(req.method(), "POST");
+                assert_eq!(req.headers()["If-None-Match"], "*");
+                match exists {
+                    true => Response::builder()
+                        .status(StatusCode::PRECONDITION_FAILED)
+                        .body(Body::empty())
+                        .unwrap(),
+                    false => {
+                        let body = "<CompleteMultipartUploadResult><ETag>\"2\"</ETag></CompleteMultipartUploadResult>";
+                        Response::new(Body::from(body))
+                    }
+                }
+            });
+
+            if exists {
+                // The upload is aborted
+                server.push_fn(|req| {
+                    assert_eq!(req.method(), "DELETE");
+                    assert_eq!(req.uri().query(), Some("uploadId=1"));
+                    Response::new(Body::empty())
+                });
+            }
+
+            let result = store
+                .copy_if_not_exists(&Path::from("a"), &Path::from("b"))
+                .await;
+            match exists {
+                true => {
+                    let err = result.unwrap_err();
+                    assert!(matches!(err, Error::AlreadyExists { .. }), "{err}");
+                }
+                false => result.unwrap(),
+            }
+        }
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn s3_test_probe() {
+        use crate::client::mock_server::MockServer;
+        use hyper::{Body, Response, StatusCode};
+
+        let server = MockServer::new();
+        let builder = AmazonS3Builder::new()
+            .with_bucket_name("bucket")
+            .with_endpoint(server.url())
+            .with_retry(crate::RetryConfig {
+                max_retries: 0,
+                ..Default::default()
+            })
+            .with_allow_http(true)
+            .with_skip_signature(true);
+        let store = builder.clone().build().unwrap();
+
+        server.push_fn(|req| {
+            assert_eq!(req.method(), "HEAD");
+            assert_eq!(req.uri().path(), "/bucket");
+            Response::new(Body::empty())
+        });
+        store.probe().await.unwrap();
+
+        for (status, expected) in [
+            (StatusCode::UNAUTHORIZED, "Unauthenticated"),
+            (StatusCode::FORBIDDEN, "PermissionDenied"),
+        ] {
+            server.push(
+                Response::builder()
+                    .status(status)
+                    .body(Body::empty())
+                    .unwrap(),
+            );
+            let err = store.probe().await.unwrap_err();
+            let matched = match &err {
+                Error::Unauthenticated { .. } => "Unauthenticated",
+                Error::PermissionDenied { .. } => "PermissionDenied",
+                _ => "other",
+            };
+            assert_eq!(matched, expected, "{err}");
+        }
+        server.shutdown().await;
+
+        // Connectivity failures are not reported as credential failures
+        let err = builder.build().unwrap().probe().await.unwrap_err();
+        assert!(matches!(err, Error::Generic { .. }), "{err}");
+    }
+
+    #[tokio::test]
+    async fn s3_test_multipart_complete_timeout() {
+        use crate::client::mock_server::MockServer;
+        use crate::multipart::PartId;
+        use hyper::{Body, Response};
+
+        async fn delayed(body: &'static str) -> Response<Body> {
+            tokio::time::sleep(Duration::from_millis(500)).await;
+            Response::builder()
+                .header("etag", "\"1\"")
+                .body(Body::from(body))
+                .unwrap()
+        }
+
+        let server = MockServer::new();
+        let options = crate::ClientOptions::new()
+            .with_allow_http(true)
+            .with_timeout(Duration::from_millis(100))
+            .with_multipart_complete_timeout(Duration::from_secs(5));
+        let store = AmazonS3Builder::new()
+            .with_bucket_name("bucket")
+            .with_endpoint(server.url())
+            .with_client_options(options)
+            .with_retry(crate::RetryConfig {
+                max_retries: 0,
+                ..Default::default()
+            })
+            .with_skip_signature(true)
+            .build()
+            .unwrap();
+        let path = Path::from("file");
+
+        // Part uploads use the request timeout
+        server.push_async_fn(|_| delayed(""));
+        let err = store
+            .client
+            .put_part(&path, &"upload".to_string(), 0, "data".into())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("timed out"), "{err}");
+
+        // Whereas the complete request uses the longer multipart complete timeout
+        server.push_async_fn(|req| {
+            assert_eq!(req.method(), "POST");
+            delayed(
+                "<CompleteMultipartUploadResult><ETag>\"2\"</ETag></CompleteMultipartUploadResult>",
+            )
+        });
+        let part = PartId::new("\"1\"".to_string(), 0);
+        let mode = CompleteMultipartMode::Overwrite;
+        let result = store
+            .client
+            .complete_multipart(&path, "upload", vec![part], mode)
+            .await
+            .unwrap();
+        assert_eq!(result.e_tag.as_deref(), Some("\"2\""));
+
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn s3_test_list_range() {
+        use crate::client::mock_server::MockServer;
+        use hyper::{Body, Response, StatusCode};
+
+        let server = MockServer::new();
+        let store = AmazonS3Builder::new()
+            .with_bucket_name("bucket")
+            .with_endpoint(server.url())
+            .with_allow_http(true)
+            .with_skip_signature(true)
+            .build()
+            .unwrap();
+
+        // The object at start is fetched with a head request
+        server.push_fn(|req| {
+            assert_eq!(req.method(), "HEAD");
+            assert_eq!(req.uri().path(), "/bucket/b");
+            Response::builder()
+                .header("etag", "\"1\"")
+                .header("content-length", "1")
+                .header("last-modified", "Thu, 01 Jan 1970 00:00:00 GMT")
+                .body(Body::empty())
+                .unwrap()
+        });
+        // With the remainder listed after it, stopping once a key reaches end without
+        // requesting the next page
+        server.push_fn(|req| {
+            let query = req.uri().query().unwrap();
+            assert!(query.contains("start-after=b"), "{query}");
+            let contents: String = ["b/x", "b0", "c", "d"]
+                .iter()
+                .map(|k| {
+                    format!(
+                        "<Contents><Key>{k}</Key><Size>1</Size>\
+                         <LastModified>2023-01-01T00:00:00.000Z</LastModified></Contents>"
+                    )
+                })
+                .collect();
+            let body = format!(
+                "<ListBucketResult><IsTruncated>true</IsTruncated>{contents}\
+                 <NextContinuationToken>token</NextContinuationToken></ListBucketResult>"
+            );
+            Response::new(Body::from(body))
+        });
+
+        let s = store.list_range(&Path::from("b"), &Path::from("c"));
+        let actual: Vec<_> = s.map_ok(|x| x.location).try_collect().await.unwrap();
+        assert_eq!(actual, vec!["b".into(), "b/x".into(), "b0".into()]);
+
+        // A missing start object is skipped
+        server.push(
+            Response::builder()
+                .status(StatusCode::NOT_FOUND)
+                .body(Body::empty())
+                .unwrap(),
+        );
+        server.push(Response::new(Body::from(
+            "<ListBucketResult><IsTruncated>false</IsTruncated></ListBucketResult>",
+        )));
+        let s = store.list_range(&Path::from("b"), &Path::from("c"));
+        let actual: Vec<_> = s.try_collect().await.unwrap();
+        assert!(actual.is_empty());
+
+        // An empty range makes no requests
+        let s = store.list_range(&Path::from("c"), &Path::from("b"));
+        let actual: Vec<_> = s.try_collect().await.unwrap();
+        assert!(actual.is_empty());
+
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn s3_test_list_objects_v1_fallback() {
+        use crate::client::mock_server::MockServer;
+        use hyper::{Body, Response, StatusCode};
+
+        fn page(keys: &[&str], truncated: bool) -> Response<Body> {
+            let contents: String = keys
+                .iter()
+                .map(|k| {
+                    format!(
+                        "<Contents><Key>{k}</Key><Size>1</Size>\
+                         <LastModified>2023-01-01T00:00:00.000Z</LastModified></Contents>"
+                    )
+                })
+                .collect();
+            let body = format!(
+                "<ListBucketResult><IsTruncated>{truncated}</IsTruncated>{contents}</ListBucketResult>"
+            );
+            Response::new(Body::from(body))
+        }
+
+        fn query(req: &hyper::Request<Body>) -> std::collections::HashMap<String, String> {
+            let query = req.uri().query().unwrap_or_default();
+            url::form_urlencoded::parse(query.as_bytes())
+                .into_owned()
+                .collect()
+        }
+
+        let server = MockServer::new();
+        let store = AmazonS3Builder::new()
+            .with_bucket_name("bucket")
+            .with_endpoint(server.url())
+            .with_allow_http(true)
+            .with_skip_signature(true)
+            .build()
+            .unwrap();
+
+        // A bad request unrelated to ListObjectsV2 support does not fall back to V1
+        server.push_fn(|req| {
+            assert_eq!(query(&req)["list-type"], "2");
+            Response::builder()
+                .status(StatusCode::BAD_REQUEST)
+                .body(Body::from(
+                    "<Error><Code>InvalidArgument</Code><Message>Invalid prefix</Message></Error>",
+                ))
+                .unwrap()
+        });
+        let err = store.list(None).try_collect::<Vec<_>>().await.unwrap_err();
+        assert!(err.to_string().contains("Invalid prefix"), "{err}");
+
+        // ListObjectsV2 is rejected
+        server.push_fn(|req| {
+            assert_eq!(query(&req)["list-type"], "2");
+            Response::builder()
+                .status(StatusCode::BAD_REQUEST)
+                .body(Body::from(
+                    "<Error><Code>InvalidArgument</Code><Message>Unsupported parameter list-type</Message></Error>",
+                ))
+                .unwrap()
+        });
+        // And so the listing is retried with V1, paginating by the last key
+        server.push_fn(|req| {
+            let query = query(&req);
+            assert!(!query.contains_key("list-type"));
+            assert_eq!(query["prefix"], "a/");
+            assert!(!query.contains_key("marker"));
+            page(&["a/1", "a/2"], true)
+        });
+        server.push_fn(|req| {
+            assert_eq!(query(&req)["marker"], "a/2");
+            page(&["a/3"], false)
+        });
+        let prefix = Path::from("a");
+        let listed: Vec<_> = store.list(Some(&prefix)).try_collect().await.unwrap();
+        let locations: Vec<_> = listed.iter().map(|x| x.location.as_ref()).collect();
+        assert_eq!(locations, ["a/1", "a/2", "a/3"]);
+
+        // Subsequent listings use V1 directly, with the offset as the marker
+        server.push_fn(|req| {
+            let query = query(&req);
+            assert!(!query.contains_key("list-type"));
+            assert_eq!(query["marker"], "a/1");
+            page(&["a/2"], false)
+        });
+        let offset = Path::from("a/1");
+        let listed: Vec<_> = store
+            .list_with_offset(Some(&prefix), &offset)
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(listed.len(), 1);
+
+        // Errors other than an unsupported V2 request are returned as is
+        let store = AmazonS3Builder::new()
+            .with_bucket_name("bucket")
+            .with_endpoint(server.url())
+            .with_allow_http(true)
+            .with_skip_signature(true)
+            .with_retry(crate::RetryConfig {
+                max_retries: 0,
+                ..Default::default()
+            })
+            .build()
+            .unwrap();
+        server.push(
+            Response::builder()
+                .status(StatusCode::FORBIDDEN)
+                .body(Body::empty())
+                .unwrap(),
+        );
+        store.list(None).try_collect::<Vec<_>>().await.unwrap_err();
+
+        // Enabling V1 skips the V2 request
+        let store = AmazonS3Builder::new()
+            .with_bucket_name("bucket")
+            .with_endpoint(server.url())
+            .with_allow_http(true)
+            .with_skip_signature(true)
+            .with_config(AmazonS3ConfigKey::ListObjectsV1, "true")
+            .build()
+            .unwrap();
+        server.push_fn(|req| {
+            assert!(!query(&req).contains_key("list-type"));
+            page(&["b"], false)
+        });
+        let listed: Vec<_> = store.list(None).try_collect().await.unwrap();
+        assert_eq!(listed.len(), 1);
+
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn s3_test_shared_http_client() {
+        use crate::client::mock_server::MockServer;
+        use hyper::{Body, Response};
+
+        let server = MockServer::new();
+        let builder = |client: Option<reqwest::Client>| {
+            let builder = AmazonS3Builder::new()
+                .with_bucket_name("bucket")
+                .with_endpoint(server.url())
+                .with_allow_http(true)
+                .with_skip_signature(true);
+            match client {
+                Some(client) => builder.with_http_client(client),
+                None => builder,
+            }
+            .build()
+            .unwrap()
+        };
+        let delete = || Response::new(Body::empty());
+
+        // Stores sharing a client reuse its connections
+        let client = reqwest::Client::new();
+        let a = builder(Some(client.clone()));
+        let b = builder(Some(client));
+        for store in [&a, &b, &a] {
+            server.push(delete());
+            store.delete(&Path::from("file")).await.unwrap();
+        }
+        assert_eq!(server.connections(), 1);
+
+        // Whereas stores with their own clients do not
+        let c = builder(None);
+        let d = builder(None);
+        for store in [&c, &d] {
+            server.push(delete());
+            store.delete(&Path::from("file")).await.unwrap();
+        }
+        assert_eq!(server.connections(), 3);
+
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn s3_test_abort_incomplete_uploads() {
+        use crate::client::mock_server::MockServer;
+        use hyper::{Body, Method, Response};
+
+        fn query(req: &hyper::Request<Body>) -> std::collections::HashMap<String, String> {
+            let query = req.uri().query().unwrap_or_default();
+            url::form_urlencoded::parse(query.as_bytes())
+                .into_owned()
+                .collect()
+        }
+
+        let server = MockServer::new();
+        let store = AmazonS3Builder::new()
+            .with_bucket_name("bucket")
+            .with_endpoint(server.url())
+            .with_allow_http(true)
+            .with_skip_signature(true)
+            .build()
+            .unwrap();
+
+        let now = Utc::now();
+        let old = (now - chrono::Duration::days(2)).to_rfc3339();
+        let recent = (now - chrono::Duration::minutes(5)).to_rfc3339();
+
+        // The first page contains an old upload and a recent one
+        server.push_fn(move |req| {
+            let query = query(&req);
+            assert_eq!(req.method(), Method::GET);
+            assert!(query.contains_key("uploads"));
+            assert_eq!(query["prefix"], "data/");
+            assert!(!query.contains_key("key-marker"));
+            let body = format!(
+                "<ListMultipartUploadsResult>\
+                 <IsTruncated>true</IsTruncated>\
+                 <NextKeyMarker>data/b</NextKeyMarker>\
+                 <NextUploadIdMarker>2</NextUploadIdMarker>\
+                 <Upload><Key>data/a</Key><UploadId>1</UploadId><Initiated>{old}</Initiated></Upload>\
+                 <Upload><Key>data/b</Key><UploadId>2</UploadId><Initiated>{recent}</Initiated></Upload>\
+                 </ListMultipartUploadsResult>"
+            );
+            Response::new(Body::from(body))
+        });
+        // Only the old upload is aborted
+        server.push_fn(|req| {
+            assert_eq!(req.method(), Method::DELETE);
+            assert_eq!(req.uri().path(), "/bucket/data/a");
+            assert_eq!(query(&req)["uploadId"], "1");
+            Response::new(Body::empty())
+        });
+        // The second page is requested from the markers of the first
+        server.push_fn(|req| {
+            let query = query(&req);
+            assert_eq!(query["key-marker"], "data/b");
+            assert_eq!(query["upload-id-marker"], "2");
+            let body = "<ListMultipartUploadsResult><IsTruncated>false</IsTruncated>\
+                        </ListMultipartUploadsResult>";
+            Response::new(Body::from(body))
+        });
+
+        let prefix = Path::from("data");
+        let older_than = now - chrono::Duration::days(1);
+        let aborted = store
+            .abort_incomplete_uploads(Some(&prefix), older_than)
+            .await
+            .unwrap();
+        assert_eq!(aborted, 1);
+
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn s3_test_get_acl() {
+        use crate::client::mock_server::MockServer;
+        use crate::{AclGrant, AclGrantee, AclPermission};
+        use hyper::{Body, Response};
+
+        let server = MockServer::new();
+        let store = AmazonS3Builder::new()
+            .with_bucket_name("bucket")
+            .with_endpoint(server.url())
+            .with_allow_http(true)
+            .with_skip_signature(true)
+            .build()
+            .unwrap();
+
+        server.push_fn(|req| {
+            assert_eq!(req.uri().path(), "/bucket/file");
+            assert_eq!(req.uri().query(), Some("acl="));
+            let body = r#"<?xml version="1.0" encoding="UTF-8"?>
+<AccessControlPolicy xmlns="http://s3.amazonaws.com/doc/2006-03-01/">
+  <Owner><ID>owner-id</ID><DisplayName>owner</DisplayName></Owner>
+  <AccessControlList>
+    <Grant>
+      <Grantee xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" xsi:type="CanonicalUser">
+        <ID>owner-id</ID><DisplayName>owner</DisplayName>
+      </Grantee>
+      <Permission>FULL_CONTROL</Permission>
+    </Grant>
+    <Grant>
+      <Grantee xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" xsi:type="Group">
+        <URI>http://acs.amazonaws.com/groups/global/AllUsers</URI>
+      </Grantee>
+      <Permission>READ</Permission>
+    </Grant>
+  </AccessControlList>
+</AccessControlPolicy>"#;
+            Response::new(Body::from(body))
+        });
+
+        let acl = store.get_acl(&Path::from("file")).await.unwrap();
+        assert_eq!(acl.owner.as_deref(), Some("owner-id"));
+        assert_eq!(
+            acl.grants,
+            vec![
+                AclGrant::new(
+                    AclGrantee::User("owner-id".to_string()),
+                    AclPermission::FullControl
+                ),
+                AclGrant::new(AclGrantee::AllUsers, AclPermission::Read),
+            ]
+        );
+
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn s3_test_canned_acl() {
+        use crate::client::mock_server::MockServer;
+        use crate::CannedAcl;
+        use hyper::{Body, Response};
+
+        let server = MockServer::new();
+        let builder = AmazonS3Builder::new()
+            .with_bucket_name("bucket")
+            .with_endpoint(server.url())
+            .with_allow_http(true)
+            .with_skip_signature(true);
+
+        let err = builder
+            .clone()
+            .with_config("canned_acl".parse().unwrap(), "project-private")
+            .build()
+            .unwrap_err();
+        assert!(matches!(err, Error::NotSupported { .. }), "{err}");
+
+        let store = builder
+            .with_config("canned_acl".parse().unwrap(), "private")
+            .build()
+            .unwrap();
+        let path = Path::from("file");
+
+        server.push_fn(|req| {
+            assert_eq!(req.method(), "PUT");
+            assert_eq!(req.headers()["x-amz-acl"], "public-read");
+            Response::builder()
+                .header("etag", "\"1\"")
+                .body(Body::empty())
+                .unwrap()
+        });
+        let opts = PutOptions {
+            acl: Some(CannedAcl::PublicRead),
+            ..Default::default()
+        };
+        store.put_opts(&path, "data".into(), opts).await.unwrap();
+
+        // Falls back to the configured ACL
+        server.push_fn(|req| {
+            assert_eq!(req.headers()["x-amz-acl"], "private");
+            Response::builder()
+                .header("etag", "\"2\"")
+                .body(Body::empty())
+                .unwrap()
+        });
+        store.put(&path, "data".into()).await.unwrap();
+
+        server.push_fn(|req| {
+            assert_eq!(req.method(), "POST");
+            assert_eq!(req.uri().query(), Some("uploads="));
+            assert_eq!(req.headers()["x-amz-acl"], "private");
+            let body = "<InitiateMultipartUploadResult><UploadId>upload</UploadId></InitiateMultipartUploadResult>";
+            Response::new(Body::from(body))
+        });
+        let id = MultiPartStore::create_multipart(&store, &path)
+            .await
+            .unwrap();
+        assert_eq!(id, "upload");
+
+        let opts = PutOptions {
+            acl: Some(CannedAcl::ProjectPrivate),
+            ..Default::default()
+        };
+        let err = store
+            .put_opts(&path, "data".into(), opts)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::NotSupported { .. }), "{err}");
+
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn s3_test_refresh_credentials() {
+        use crate::client::mock_server::MockServer;
+        use hyper::{Body, Response, StatusCode};
+        use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+        #[derive(Debug, Default)]
+        struct CountingProvider {
+            fetches: AtomicUsize,
+            invalidated: AtomicBool,
+        }
+
+        #[async_trait]
+        impl CredentialProvider for CountingProvider {
+            type Credential = AwsCredential;
+
+            async fn get_credential(&self) -> Result<Arc<AwsCredential>> {
+                if self.invalidated.swap(false, Ordering::SeqCst)
+                    || self.fetches.load(Ordering::SeqCst) == 0
+                {
+                    self.fetches.fetch_add(1, Ordering::SeqCst);
+                }
+                Ok(Arc::new(AwsCredential {
+                    key_id: format!("key{}", self.fetches.load(Ordering::SeqCst)),
+                    secret_key: "secret".to_string(),
+                    token: None,
+                }))
+            }
+
+            async fn invalidate(&self) {
+                self.invalidated.store(true, Ordering::SeqCst)
+            }
+        }
+
+        fn expired(req: hyper::Request<Body>, key: &str) -> Response<Body> {
+            let auth = req.headers()["authorization"].to_str().unwrap();
+            assert!(auth.contains(&format!("Credential={key}/")), "{auth}");
+            let body = "<Error><Code>ExpiredToken</Code><Message>expired</Message></Error>";
+            Response::builder()
+                .status(StatusCode::UNAUTHORIZED)
+                .body(Body::from(body))
+                .unwrap()
+        }
+
+        let server = MockServer::new();
+        let provider = Arc::new(CountingProvider::default());
+        let store = AmazonS3Builder::new()
+            .with_bucket_name("bucket")
+            .with_region("us-east-1")
+            .with_endpoint(server.url())
+            .with_allow_http(true)
+            .with_credentials(Arc::clone(&provider) as AwsCredentialProvider)
+            .build()
+            .unwrap();
+        let path = Path::from("file");
+
+        // An expired token is refreshed and the request retried
+        server.push_fn(|req| expired(req, "key1"));
+        server.push_fn(|req| {
+            let auth = req.headers()["authorization"].to_str().unwrap();
+            assert!(auth.contains("Credential=key2/"), "{auth}");
+            Response::builder()
+                .header("etag", "\"1\"")
+                .body(Body::empty())
+                .unwrap()
+        });
+        store.put(&path, "data".into()).await.unwrap();
+        assert_eq!(provider.fetches.load(Ordering::SeqCst), 2);
+
+        // Credentials are only refreshed once per request
+        server.push_fn(|req| expired(req, "key2"));
+        server.push_fn(|req| expired(req, "key3"));
+        let err = store.put(&path, "data".into()).await.unwrap_err();
+        assert!(err.to_string().contains("ExpiredToken"), "{err}");
+        assert_eq!(provider.fetches.load(Ordering::SeqCst), 3);
+
+        // A 403 denied by policy does not refresh credentials
+        server.push(
+            Response::builder()
+                .status(StatusCode::FORBIDDEN)
+                .body(Body::from("<Error><Code>AccessDenied</Code></Error>"))
+                .unwrap(),
+        );
+        store.put(&path, "data".into()).await.unwrap_err();
+        assert_eq!(provider.fetches.load(Ordering::SeqCst), 3);
+
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn s3_test_region_auto_detect() {
+        use crate::client::mock_server::MockServer;
+        use hyper::{Body, Response, StatusCode};
+
+        fn assert_region(req: &hyper::Request<Body>, region: &str) {
+            let auth = req.headers()["Authorization"].to_str().unwrap();
+            assert!(
+                auth.contains(&format!("/{region}/s3/aws4_request")),
+                "{auth}"
+            );
+        }
+
+        fn redirect() -> Response<Body> {
+            Response::builder()
+                .status(StatusCode::MOVED_PERMANENTLY)
+                .header("x-amz-bucket-region", "eu-west-1")
+                .body(Body::empty())
+                .unwrap()
+        }
+
+        let server = MockServer::new();
+        let builder = AmazonS3Builder::new()
+            .with_bucket_name("bucket")
+            .with_region("us-east-1")
+            .with_access_key_id("key")
+            .with_secret_access_key("secret")
+            .with_endpoint(server.url())
+            .with_allow_http(true);
+
+        // Without auto-detection the redirect is returned as an error
+        let store = builder.clone().build().unwrap();
+        server.push(redirect());
+        let err = store.get(&Path::from("foo")).await.unwrap_err();
+        assert!(
+            err.to_string().contains("incorrectly configured region"),
+            "{err}"
+        );
+
+        let store = builder
+            .with_config(AmazonS3ConfigKey::RegionAutoDetect, "true")
+            .build()
+            .unwrap();
+
+        server.push_fn(|req| {
+            assert_region(&req, "us-east-1");
+            redirect()
+        });
+        server.push_fn(|req| {
+            assert_eq!(req.uri().path(), "/bucket/foo");
+            assert_region(&req, "eu-west-1");
+            let mut response = Response::new(Body::empty());
+            response
+                .headers_mut()
+                .insert("ETag", "\"1\"".parse().unwrap());
+            response
+        });
+        store.put(&Path::from("foo"), "data".into()).await.unwrap();
+
+        // The detected region is used for subsequent requests
+        server.push_fn(|req| {
+            assert_region(&req, "eu-west-1");
+            Response::builder()
+                .header("ETag", "\"1\"")
+                .header("Content-Length", 4)
+                .body(Body::from("data"))
+                .unwrap()
+        });
+        let data = store.get(&Path::from("foo")).await.unwrap();
+        assert_eq!(data.bytes().await.unwrap().as_ref(), b"data");
+
+        server.shutdown().await;
+
+        // The default AWS endpoint is rewritten for the detected region
+        let store = AmazonS3Builder::new()
+            .with_bucket_name("bucket")
+            .with_region("us-east-1")
+            .with_region_auto_detect(true)
+            .build()
+            .unwrap();
+        let error = crate::client::retry::Error::BareRedirect {
+            region: Some("eu-west-1".to_string()),
+        };
+        let config = &store.client.config;
+        let (endpoint, _) = config.detect_region(&error).unwrap();
+        assert_eq!(endpoint, "https://s3.us-east-1.amazonaws.com/bucket");
+        assert_eq!(
+            config.bucket_endpoint(),
+            "https://s3.eu-west-1.amazonaws.com/bucket"
+        );
+        assert_eq!(config.region(), "eu-west-1");
+    }
+
+    #[tokio::test]
+    async fn s3_test_object_lock() {
+        use crate::client::mock_server::MockServer;
+        use crate::RetentionMode;
+        use chrono::{TimeZone, Utc};
+        use hyper::{Body, Response, StatusCode};
+
+        let server = MockServer::new();
+        let store = AmazonS3Builder::new()
+            .with_bucket_name("bucket")
+            .with_endpoint(server.url())
+            .with_allow_http(true)
+            .with_skip_signature(true)
+            .build()
+            .unwrap();
+
+        let path = Path::from("file");
+        let retain_until = Utc.with_ymd_and_hms(2030, 1, 1, 0, 0, 0).unwrap();
+        let retention = Retention::new(RetentionMode::Governance, retain_until);
+
+        server.push_fn(|req| {
+            assert_eq!(req.method(), "PUT");
+            let headers = req.headers();
+            assert_eq!(headers.get("x-amz-object-lock-mode").unwrap(), "GOVERNANCE");
+            assert_eq!(
+                headers.get("x-amz-object-lock-retain-until-date").unwrap(),
+                "2030-01-01T00:00:00.000Z"
+            );
+            assert_eq!(headers.get("x-amz-object-lock-legal-hold").unwrap(), "ON");
+            // MD5 of "data"
+            assert_eq!(
+                headers.get("Content-MD5").unwrap(),
+                "jXd/OF09/siBXSD3SWAm3A=="
+            );
+            Response::builder()
+                .header("etag", "\"1\"")
+                .body(Body::empty())
+                .unwrap()
+        });
+        let opts = PutOptions {
+            retention: Some(retention),
+            legal_hold: true,
+            ..Default::default()
+        };
+        store.put_opts(&path, "data".into(), opts).await.unwrap();
+
+        // Object Lock headers are only sent when configured
+        server.push_fn(|req| {
+            let headers = req.headers();
+            assert!(headers.get("x-amz-object-lock-mode").is_none());
+            assert!(headers.get("x-amz-object-lock-legal-hold").is_none());
+            assert!(headers.get("Content-MD5").is_none());
+            Response::builder()
+                .header("etag", "\"2\"")
+                .body(Body::empty())
+                .unwrap()
+        });
+        store.put(&path, "data".into()).await.unwrap();
+
+        server.push_async_fn(|req| async move {
+            assert_eq!(req.method(), "PUT");
+            assert_eq!(req.uri().query(), Some("retention"));
+            assert!(req.headers().get("Content-MD5").is_some());
+            let body = hyper::body::to_bytes(req.into_body()).await.unwrap();
+            assert_eq!(
+                std::str::from_utf8(&body).unwrap(),
+                "<Retention><Mode>GOVERNANCE</Mode><RetainUntilDate>2030-01-01T00:00:00.000Z</RetainUntilDate></Retention>"
+            );
+            Response::new(Body::empty())
+        });
+        store.put_object_retention(&path, &retention).await.unwrap();
+
+        server.push_async_fn(|req| async move {
+            assert_eq!(req.uri().query(), Some("legal-hold"));
+            let body = hyper::body::to_bytes(req.into_body()).await.unwrap();
+            assert_eq!(
+                std::str::from_utf8(&body).unwrap(),
+                "<LegalHold><Status>OFF</Status></LegalHold>"
+            );
+            Response::new(Body::empty())
+        });
+        store.put_object_legal_hold(&path, false).await.unwrap();
+
+        // Deleting a retained object version returns a typed error
+        server.push_fn(|req| {
+            assert_eq!(req.method(), "DELETE");
+            Response::builder()
+                .status(StatusCode::FORBIDDEN)
+                .body(Body::from(
+                    "<Error><Code>AccessDenied</Code><Message>Access Denied because object protected by object lock.</Message></Error>",
+                ))
+                .unwrap()
+        });
+        let opts = DeleteOptions {
+            version: Some("v1".to_string()),
+            ..Default::default()
+        };
+        let err = store.delete_opts(&path, opts).await.unwrap_err();
+        assert!(matches!(err, Error::Retained { .. }), "{err}");
+
+        // Other permission errors are not
+        server.push_fn(|_| {
+            Response::builder()
+                .status(StatusCode::FORBIDDEN)
+                .body(Body::from("<Error><Code>AccessDenied</Code></Error>"))
+                .unwrap()
+        });
+        let err = store.delete(&path).await.unwrap_err();
+        assert!(matches!(err, Error::PermissionDenied { .. }), "{err}");
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn s3_test_content_type_inference() {
+        use crate::client::mock_server::MockServer;
+        use hyper::{Body, Response};
+
+        let server = MockServer::new();
+        let options = crate::ClientOptions::new().with_content_type_inference(true);
+        let store = AmazonS3Builder::new()
+            .with_client_options(options)
+            .with_bucket_name("bucket")
+            .with_endpoint(server.url())
+            .with_allow_http(true)
+            .with_skip_signature(true)
+            .build()
+            .unwrap();
+
+        for (path, expected) in [
+            ("data.csv", "text/csv"),
+            ("data.parquet", "application/octet-stream"),
+        ] {
+            server.push_fn(move |req| {
+                assert_eq!(req.headers().get("content-type").unwrap(), expected);
+                Response::builder()
+                    .header("etag", "\"1\"")
+                    .body(Body::empty())
+                    .unwrap()
+            });
+            store.put(&Path::from(path), "a,b".into()).await.unwrap();
+        }
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn s3_test_copy_with_storage_class() {
+        use crate::client::mock_server::MockServer;
+        use hyper::{Body, Response};
+
+        let server = MockServer::new();
+        let store = AmazonS3Builder::new()
+            .with_bucket_name("bucket")
+            .with_endpoint(server.url())
+            .with_allow_http(true)
+            .with_skip_signature(true)
+            .build()
+            .unwrap();
+
+        // The copy and storage class transition are performed in a single request
+        server.push_fn(|req| {
+            assert_eq!(req.method(), "PUT");
+            assert_eq!(req.uri().path(), "/bucket/to");
+            let headers = req.headers();
+            assert_eq!(headers.get("x-amz-copy-source").unwrap(), "bucket/from");
+            assert_eq!(headers.get("x-amz-storage-class").unwrap(), "STANDARD_IA");
+            Response::new(Body::empty())
+        });
+
+        let from = Path::from("from");
+        let to = Path::from("to");
+        store
+            .copy_with_storage_class(&from, &to, "STANDARD_IA")
+            .await
+            .unwrap();
+
+        // A plain copy does not specify a storage class
+        server.push_fn(|req| {
+            assert!(req.headers().get("x-amz-storage-class").is_none());
+            Response::new(Body::empty())
+        });
+        store.copy(&from, &to).await.unwrap();
+
+        // An invalid storage class is rejected without making a request
+        let err = store
+            .copy_with_storage_class(&from, &to, "STANDARD\n")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::Generic { .. }), "{err}");
+        assert!(err.to_string().contains("invalid storage class"), "{err}");
+        server.shutdown().await;
+    }
+
     #[tokio::test]
     #[ignore = "Tests shouldn't call use remote services by default"]
     async fn test_disable_creds() {
@@ -519,6 +2472,34 @@ mod tests {
         v2.list_with_delimiter(Some(&prefix)).await.unwrap();
     }
 
+    #[tokio::test]
+    async fn s3_test_validate_path() {
+        let store = AmazonS3Builder::new()
+            .with_bucket_name("bucket")
+            .build()
+            .unwrap();
+
+        store.validate_path(&Path::from("foo/bar.parquet")).unwrap();
+        store.validate_path(&Path::from("a".repeat(1024))).unwrap();
+
+        let err = store
+            .validate_path(&Path::from("a".repeat(1025)))
+            .unwrap_err();
+        assert!(err.to_string().contains("key exceeds 1024 bytes"), "{err}");
+
+        // A deeply nested key is rejected before making any request
+        let deep = Path::from_iter(vec!["a"; 900]);
+        let err = store.validate_path(&deep).unwrap_err();
+        assert!(err.to_string().contains("key exceeds 1024 bytes"), "{err}");
+
+        // As is the destination of a copy or rename
+        let from = Path::from("foo");
+        let err = store.copy(&from, &deep).await.unwrap_err();
+        assert!(matches!(err, Error::InvalidPath { .. }), "{err}");
+        let err = store.rename_if_not_exists(&from, &deep).await.unwrap_err();
+        assert!(matches!(err, Error::InvalidPath { .. }), "{err}");
+    }
+
     async fn s3_encryption(store: &AmazonS3) {
         crate::test_util::maybe_skip_integration!();
 
@@ -46,6 +46,18 @@ pub enum S3CopyIfNotExists {
     ///
     /// Encoded as `header-with-status:<HEADER_NAME>:<HEADER_VALUE>:<STATUS>` ignoring whitespace
     HeaderWithStatus(String, String, reqwest::StatusCode),
+    /// Copy using a multipart upload of a single UploadPartCopy, completed with
+    /// `If-None-Match: *` so that it fails atomically if the destination exists
+    ///
+    /// This uses the [conditional writes] supported by AWS S3, and is limited to sources
+    /// of at most 5 GiB. The content headers, user-defined metadata and tags of the
+    /// source are read with an additional request, and set on the destination as by
+    /// CopyObject
+    ///
+    /// Encoded as `multipart` ignoring whitespace
+    ///
+    /// [conditional writes]: https://docs.aws.amazon.com/AmazonS3/latest/userguide/conditional-writes.html
+    Multipart,
     /// The name of a DynamoDB table to use for coordination
     ///
     /// Encoded as either `dynamo:<TABLE_NAME>` or `dynamo:<TABLE_NAME>:<TIMEOUT_MILLIS>`
@@ -64,6 +76,7 @@ impl std::fmt::Display for S3CopyIfNotExists {
             Self::HeaderWithStatus(k, v, code) => {
                 write!(f, "header-with-status: {k}: {v}: {}", code.as_u16())
             }
+            Self::Multipart => f.write_str("multipart"),
             Self::Dynamo(lock) => write!(f, "dynamo: {}", lock.table_name()),
         }
     }
@@ -71,6 +84,9 @@ impl std::fmt::Display for S3CopyIfNotExists {
 
 impl S3CopyIfNotExists {
     fn from_str(s: &str) -> Option<Self> {
+        if s.trim() == "multipart" {
+            return Some(Self::Multipart);
+        }
         let (variant, value) = s.split_once(':')?;
         match variant.trim() {
             "header" => {
@@ -196,6 +212,13 @@ mod tests {
         assert_eq!(expected, S3CopyIfNotExists::from_str(input));
     }
 
+    #[test]
+    fn parse_s3_copy_if_not_exists_multipart() {
+        let expected = Some(S3CopyIfNotExists::Multipart);
+        assert_eq!(expected, S3CopyIfNotExists::from_str(" multipart "));
+        assert_eq!(S3CopyIfNotExists::Multipart.to_string(), "multipart");
+    }
+
     #[test]
     fn parse_s3_condition_put_dynamo() {
         let input = "dynamo: table:1300";
@@ -0,0 +1,185 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Key derivation and signing for [AWS SigV4A]
+//!
+//! SigV4A signs requests with an ECDSA P-256 key derived from the secret access key,
+//! allowing a single signature to be valid across a set of regions.
+//!
+//! [`ring`] can sign with a P-256 key, but requires the corresponding public key to
+//! construct it, which is computed with [`p256`].
+//!
+//! [AWS SigV4A]: https://docs.aws.amazon.com/IAM/latest/UserGuide/reference_sigv-create-signed-request.html
+
+use crate::aws::credential::hex_encode;
+use crate::aws::AwsCredential;
+use p256::elliptic_curve::sec1::ToEncodedPoint;
+use p256::elliptic_curve::PrimeField;
+use p256::{NonZeroScalar, PublicKey, Scalar};
+use parking_lot::Mutex;
+use ring::rand::SystemRandom;
+use ring::signature::{EcdsaKeyPair, ECDSA_P256_SHA256_ASN1_SIGNING};
+use ring::{digest, hmac};
+use std::sync::Arc;
+
+/// The SigV4A signing algorithm
+pub(crate) const ALGORITHM: &str = "AWS4-ECDSA-P256-SHA256";
+
+/// The maximum number of derived keys to cache
+const CACHE_SIZE: usize = 8;
+
+/// Derived keys, keyed by a digest of the access key id and secret, see [`cache_key`]
+static KEY_CACHE: Mutex<Vec<([u8; 32], Arc<SigningKey>)>> = parking_lot::const_mutex(Vec::new());
+
+/// Returns the SHA-256 digest identifying `credential` in [`KEY_CACHE`], so that the
+/// cache does not retain the secret access key
+fn cache_key(credential: &AwsCredential) -> [u8; 32] {
+    let mut ctx = digest::Context::new(&digest::SHA256);
+    ctx.update(&(credential.key_id.len() as u64).to_be_bytes());
+    ctx.update(credential.key_id.as_bytes());
+    ctx.update(credential.secret_key.as_bytes());
+    ctx.finish().as_ref().try_into().unwrap()
+}
+
+/// An ECDSA P-256 key derived from an [`AwsCredential`]
+pub(crate) struct SigningKey {
+    key_pair: EcdsaKeyPair,
+    rng: SystemRandom,
+}
+
+impl std::fmt::Debug for SigningKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SigningKey").finish_non_exhaustive()
+    }
+}
+
+impl SigningKey {
+    /// Returns the [`SigningKey`] for `credential`, deriving it if not already cached
+    pub(crate) fn for_credential(credential: &AwsCredential) -> Arc<Self> {
+        let cache_key = cache_key(credential);
+        let mut cache = KEY_CACHE.lock();
+        if let Some((_, key)) = cache.iter().find(|(k, _)| k == &cache_key) {
+            return Arc::clone(key);
+        }
+
+        let key = Arc::new(Self::derive(&credential.key_id, &credential.secret_key));
+        if cache.len() >= CACHE_SIZE {
+            cache.remove(0);
+        }
+        cache.push((cache_key, Arc::clone(&key)));
+        key
+    }
+
+    /// Derive the signing key using the NIST SP 800-108 counter mode KDF, with HMAC-SHA256
+    fn derive(key_id: &str, secret_key: &str) -> Self {
+        let input_key = hmac::Key::new(hmac::HMAC_SHA256, format!("AWS4A{secret_key}").as_bytes());
+        // The probability of needing more than one iteration is ~2^-32
+        for counter in 1..=u8::MAX {
+            let mut input = Vec::with_capacity(4 + ALGORITHM.len() + key_id.len() + 6);
+            input.extend_from_slice(&1_u32.to_be_bytes());
+            input.extend_from_slice(ALGORITHM.as_bytes());
+            input.push(0);
+            input.extend_from_slice(key_id.as_bytes());
+            input.push(counter);
+            input.extend_from_slice(&256_u32.to_be_bytes());
+
+            // The key is k0 + 1, provided k0 <= n - 2, i.e. k0 + 1 is a non-zero scalar
+            let tag = hmac::sign(&input_key, &input);
+            let k0 = p256::FieldBytes::clone_from_slice(tag.as_ref());
+            let d = Option::<Scalar>::from(Scalar::from_repr(k0))
+                .map(|k0| k0 + Scalar::ONE)
+                .and_then(|d| Option::<NonZeroScalar>::from(NonZeroScalar::new(d)));
+            if let Some(d) = d {
+                return Self::from_private_key(&d);
+            }
+        }
+        unreachable!("failed to derive SigV4A key")
+    }
+
+    fn from_private_key(d: &NonZeroScalar) -> Self {
+        let public_key = PublicKey::from_secret_scalar(d).to_encoded_point(false);
+        let rng = SystemRandom::new();
+        let key_pair = EcdsaKeyPair::from_private_key_and_public_key(
+            &ECDSA_P256_SHA256_ASN1_SIGNING,
+            &d.to_repr(),
+            public_key.as_bytes(),
+            &rng,
+        )
+        .expect("derived SigV4A key pair is valid");
+        Self { key_pair, rng }
+    }
+
+    /// Returns the hex-encoded DER signature of `string_to_sign`
+    pub(crate) fn sign(&self, string_to_sign: &str) -> String {
+        let signature = self
+            .key_pair
+            .sign(&self.rng, string_to_sign.as_bytes())
+            .expect("ECDSA signing succeeds");
+        hex_encode(signature.as_ref())
+    }
+
+    #[cfg(test)]
+    fn public_key(&self) -> &[u8] {
+        use ring::signature::KeyPair;
+        self.key_pair.public_key().as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ring::signature::{UnparsedPublicKey, ECDSA_P256_SHA256_ASN1};
+
+    #[test]
+    fn test_derive_key() {
+        // https://github.com/awslabs/aws-c-auth/tree/main/tests/aws-signing-test-suite/v4a
+        let key = SigningKey::derive("AKIDEXAMPLE", "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY");
+        let expected = concat!(
+            "04",
+            "b6618f6a65740a99e650b33b6b4b5bd0d43b176d721a3edfea7e7d2d56d936b1",
+            "865ed22a7eadc9c5cb9d2cbaca1b3699139fedc5043dc6661864218330c8e518"
+        );
+        assert_eq!(hex_encode(key.public_key()), expected);
+
+        let signature = key.sign("string to sign");
+        let signature = (0..signature.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&signature[i..i + 2], 16).unwrap())
+            .collect::<Vec<_>>();
+        UnparsedPublicKey::new(&ECDSA_P256_SHA256_ASN1, key.public_key())
+            .verify(b"string to sign", &signature)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_key_cache() {
+        let credential = |key_id: &str, secret_key: &str| AwsCredential {
+            key_id: key_id.to_string(),
+            secret_key: secret_key.to_string(),
+            token: None,
+        };
+        let a = SigningKey::for_credential(&credential("cache_id", "secret"));
+        let b = SigningKey::for_credential(&credential("cache_id", "secret"));
+        assert!(Arc::ptr_eq(&a, &b));
+
+        // Changing the secret, or moving bytes between the id and secret, is a new key
+        let c = SigningKey::for_credential(&credential("cache_id", "secret2"));
+        assert_ne!(a.public_key(), c.public_key());
+        let d = SigningKey::for_credential(&credential("cache_ids", "ecret"));
+        assert_ne!(a.public_key(), d.public_key());
+    }
+}
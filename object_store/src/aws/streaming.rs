@@ -0,0 +1,206 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! [`aws-chunked`] encoding of request bodies with a trailing checksum
+//!
+//! [`aws-chunked`]: https://docs.aws.amazon.com/AmazonS3/latest/API/sigv4-streaming-trailers.html
+
+use crate::aws::checksum::{Checksum, ChecksumHasher};
+use crate::aws::STORE;
+use crate::Result;
+use base64::prelude::BASE64_STANDARD;
+use base64::Engine;
+use bytes::{BufMut, Bytes, BytesMut};
+use futures::stream::BoxStream;
+use futures::StreamExt;
+use snafu::Snafu;
+
+/// The size of the chunks the body is split into
+///
+/// Chunks are a fixed size, so that the length of the encoded body can be
+/// determined upfront to set the `Content-Length` header
+const CHUNK_SIZE: usize = 64 * 1024;
+
+#[derive(Debug, Snafu)]
+enum Error {
+    #[snafu(display("Expected stream of {expected} bytes, got at least {actual} bytes"))]
+    TooLong { expected: u64, actual: u64 },
+
+    #[snafu(display("Expected stream of {expected} bytes, got {actual} bytes"))]
+    TooShort { expected: u64, actual: u64 },
+}
+
+impl From<Error> for crate::Error {
+    fn from(source: Error) -> Self {
+        Self::Generic {
+            store: STORE,
+            source: Box::new(source),
+        }
+    }
+}
+
+/// Returns the length of the `aws-chunked` encoding of `len` bytes with a trailing `checksum`
+pub(crate) fn encoded_len(len: u64, checksum: Checksum) -> u64 {
+    // `{hex length}\r\n{data}\r\n`
+    let chunk = |n: u64| format!("{n:x}").len() as u64 + 4 + n;
+    let full = len / CHUNK_SIZE as u64;
+    let remainder = len % CHUNK_SIZE as u64;
+
+    let mut total = full * chunk(CHUNK_SIZE as u64);
+    if remainder != 0 {
+        total += chunk(remainder);
+    }
+    total + trailer(checksum, checksum.hasher()).len() as u64
+}
+
+/// Returns the final zero-length chunk, followed by the trailing checksum
+fn trailer(checksum: Checksum, hasher: ChecksumHasher) -> Bytes {
+    let value = BASE64_STANDARD.encode(hasher.finish());
+    format!("0\r\n{}:{value}\r\n\r\n", checksum.header_name()).into()
+}
+
+fn chunk(data: &[u8]) -> Bytes {
+    let mut out = BytesMut::with_capacity(data.len() + 16);
+    out.put(format!("{:x}\r\n", data.len()).as_bytes());
+    out.put(data);
+    out.put(&b"\r\n"[..]);
+    out.freeze()
+}
+
+struct EncodeState {
+    input: BoxStream<'static, Result<Bytes>>,
+    buffer: BytesMut,
+    hasher: Option<ChecksumHasher>,
+    checksum: Checksum,
+    expected: u64,
+    read: u64,
+}
+
+/// Encode the `len` bytes of `input` with `aws-chunked`, computing `checksum` as the
+/// data is streamed and sending it in the trailer
+///
+/// Returns an error if `input` does not yield exactly `len` bytes
+pub(crate) fn encode(
+    input: BoxStream<'static, Result<Bytes>>,
+    len: u64,
+    checksum: Checksum,
+) -> BoxStream<'static, Result<Bytes>> {
+    let state = EncodeState {
+        input,
+        buffer: BytesMut::new(),
+        hasher: Some(checksum.hasher()),
+        checksum,
+        expected: len,
+        read: 0,
+    };
+
+    futures::stream::unfold(state, |mut s| async move {
+        loop {
+            if s.buffer.len() >= CHUNK_SIZE {
+                let data = s.buffer.split_to(CHUNK_SIZE);
+                return Some((Ok(chunk(&data)), s));
+            }
+
+            let hasher = s.hasher.as_mut()?;
+            match s.input.next().await {
+                Some(Ok(bytes)) => {
+                    s.read += bytes.len() as u64;
+                    if s.read > s.expected {
+                        s.hasher = None;
+                        let (expected, actual) = (s.expected, s.read);
+                        return Some((Err(Error::TooLong { expected, actual }.into()), s));
+                    }
+                    hasher.update(&bytes);
+                    s.buffer.extend_from_slice(&bytes);
+                }
+                Some(Err(e)) => {
+                    s.hasher = None;
+                    return Some((Err(e), s));
+                }
+                None if s.read != s.expected => {
+                    s.hasher = None;
+                    let (expected, actual) = (s.expected, s.read);
+                    return Some((Err(Error::TooShort { expected, actual }.into()), s));
+                }
+                None if !s.buffer.is_empty() => {
+                    let data = s.buffer.split();
+                    return Some((Ok(chunk(&data)), s));
+                }
+                None => {
+                    let hasher = s.hasher.take().unwrap();
+                    return Some((Ok(trailer(s.checksum, hasher)), s));
+                }
+            }
+        }
+    })
+    .boxed()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::TryStreamExt;
+
+    async fn encode_all(data: &[u8], splits: usize, checksum: Checksum) -> Bytes {
+        let parts: Vec<_> = data
+            .chunks(data.len() / splits + 1)
+            .map(|x| Ok(Bytes::copy_from_slice(x)))
+            .collect();
+        let input = futures::stream::iter(parts).boxed();
+        let encoded: Vec<_> = encode(input, data.len() as _, checksum)
+            .try_collect()
+            .await
+            .unwrap();
+        encoded.concat().into()
+    }
+
+    #[tokio::test]
+    async fn test_encode() {
+        let data: Vec<u8> = (0..CHUNK_SIZE * 2 + 10).map(|x| x as u8).collect();
+
+        for len in [0, 1, CHUNK_SIZE, CHUNK_SIZE + 1, data.len()] {
+            for checksum in [Checksum::CRC32C, Checksum::SHA256] {
+                let data = &data[..len];
+                let encoded = encode_all(data, 3, checksum).await;
+                assert_eq!(encoded.len() as u64, encoded_len(len as _, checksum));
+
+                let checksum_value = BASE64_STANDARD.encode(checksum.digest(data));
+                let expected_trailer =
+                    format!("0\r\n{}:{checksum_value}\r\n\r\n", checksum.header_name());
+                assert!(encoded.ends_with(expected_trailer.as_bytes()));
+            }
+        }
+
+        let encoded = encode_all(b"hello world", 4, Checksum::CRC32C).await;
+        assert_eq!(
+            encoded.as_ref(),
+            b"b\r\nhello world\r\n0\r\nx-amz-checksum-crc32c:yZRlqg==\r\n\r\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_encode_length_mismatch() {
+        for len in [5, 20] {
+            let input = futures::stream::iter([Ok(Bytes::from("hello world"))]).boxed();
+            let err = encode(input, len, Checksum::CRC32C)
+                .try_collect::<Vec<_>>()
+                .await
+                .unwrap_err();
+            assert!(err.to_string().contains("Expected stream of"), "{err}");
+        }
+    }
+}
@@ -0,0 +1,89 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Reading the POSIX access control of blobs in storage accounts with a hierarchical namespace
+//!
+//! <https://learn.microsoft.com/en-us/azure/storage/blobs/data-lake-storage-access-control>
+
+use crate::{Acl, AclGrant, AclGrantee, AclPermission};
+use reqwest::header::HeaderMap;
+
+const OWNER_HEADER: &str = "x-ms-owner";
+const GROUP_HEADER: &str = "x-ms-group";
+const PERMISSIONS_HEADER: &str = "x-ms-permissions";
+const ACL_HEADER: &str = "x-ms-acl";
+
+/// Returns the [`Acl`] described by the headers of a Get Blob Properties response
+///
+/// The full ACL is read from `x-ms-acl`, falling back to the owner, group and other
+/// permissions of `x-ms-permissions`. Accounts without a hierarchical namespace return
+/// neither, in which case the returned [`Acl`] is empty.
+///
+/// The `mask` entry, limiting the permissions of named users and groups, and `default`
+/// entries, which apply to the children of a directory, are ignored.
+pub(crate) fn acl_from_headers(headers: &HeaderMap) -> Acl {
+    let header = |name| headers.get(name).and_then(|v| v.to_str().ok());
+    let owner = header(OWNER_HEADER).map(ToString::to_string);
+    let group = header(GROUP_HEADER);
+
+    // Entries of the form `[scope:]type:[id]:permissions`
+    let entries: Vec<(&str, &str, &str)> = match (header(ACL_HEADER), header(PERMISSIONS_HEADER)) {
+        (Some(acl), _) => acl
+            .split(',')
+            .filter_map(|entry| {
+                let mut parts = entry.trim().rsplitn(3, ':');
+                let permissions = parts.next()?;
+                let id = parts.next()?;
+                let kind = parts.next()?;
+                (!kind.contains(':')).then_some((kind, id, permissions))
+            })
+            .collect(),
+        (None, Some(permissions)) if permissions.len() >= 9 => vec![
+            ("user", "", &permissions[0..3]),
+            ("group", "", &permissions[3..6]),
+            ("other", "", &permissions[6..9]),
+        ],
+        _ => vec![],
+    };
+
+    let mut grants = Vec::with_capacity(entries.len() * 2);
+    for (kind, id, permissions) in entries {
+        let grantee = match (kind, id) {
+            ("user", "") => match &owner {
+                Some(owner) => AclGrantee::User(owner.clone()),
+                None => continue,
+            },
+            ("user", id) => AclGrantee::User(id.to_string()),
+            ("group", "") => match group {
+                Some(group) => AclGrantee::Group(group.to_string()),
+                None => continue,
+            },
+            ("group", id) => AclGrantee::Group(id.to_string()),
+            // Any other principal authorized to access the account
+            ("other", _) => AclGrantee::AuthenticatedUsers,
+            _ => continue,
+        };
+        if permissions.contains('r') {
+            grants.push(AclGrant::new(grantee.clone(), AclPermission::Read));
+        }
+        if permissions.contains('w') {
+            grants.push(AclGrant::new(grantee, AclPermission::Write));
+        }
+    }
+
+    Acl { owner, grants }
+}
@@ -17,18 +17,22 @@
 
 use crate::azure::client::{AzureClient, AzureConfig};
 use crate::azure::credential::{
-    AzureAccessKey, AzureCliCredential, ClientSecretOAuthProvider, ImdsManagedIdentityProvider,
-    WorkloadIdentityOAuthProvider,
+    authority_hosts, AzureAccessKey, AzureCliCredential, ClientSecretOAuthProvider,
+    ImdsManagedIdentityProvider, WorkloadIdentityOAuthProvider, AZURE_VERSION,
 };
-use crate::azure::{AzureCredential, AzureCredentialProvider, MicrosoftAzure, STORE};
+use crate::azure::{AccessTier, AzureCredential, AzureCredentialProvider, MicrosoftAzure, STORE};
 use crate::client::TokenCredentialProvider;
-use crate::config::ConfigValue;
+use crate::config::{config_snapshot, ByteSize, ConfigValue};
+use crate::multipart::{validate_multipart_config, DEFAULT_CONCURRENCY, DEFAULT_PART_SIZE};
 use crate::{ClientConfigKey, ClientOptions, Result, RetryConfig, StaticCredentialProvider};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
 use percent_encoding::percent_decode_str;
 use serde::{Deserialize, Serialize};
 use snafu::{OptionExt, ResultExt, Snafu};
+use std::collections::BTreeMap;
 use std::str::FromStr;
 use std::sync::Arc;
+use tracing::warn;
 use url::Url;
 
 /// The well-known account used by Azurite and the legacy Azure Storage Emulator.
@@ -36,6 +40,9 @@ use url::Url;
 /// <https://docs.microsoft.com/azure/storage/common/storage-use-azurite#well-known-storage-account-and-key>
 const EMULATOR_ACCOUNT: &str = "devstoreaccount1";
 
+/// The storage endpoint suffix of the Azure public cloud
+const DEFAULT_ENDPOINT_SUFFIX: &str = "core.windows.net";
+
 /// The well-known account key used by Azurite and the legacy Azure Storage Emulator.
 ///
 /// <https://docs.microsoft.com/azure/storage/common/storage-use-azurite#well-known-storage-account-and-key>
@@ -44,6 +51,11 @@ const EMULATOR_ACCOUNT_KEY: &str =
 
 const MSI_ENDPOINT_ENV_KEY: &str = "IDENTITY_ENDPOINT";
 
+/// The maximum size of a block of a block blob
+///
+/// <https://learn.microsoft.com/en-us/rest/api/storageservices/put-block#remarks>
+const MAX_BLOCK_SIZE: u64 = 4000 * 1024 * 1024;
+
 /// A specialized `Error` for Azure builder-related errors
 #[derive(Debug, Snafu)]
 #[allow(missing_docs)]
@@ -87,6 +99,18 @@ enum Error {
     #[snafu(display("Missing component in SAS query pair"))]
     MissingSasComponent {},
 
+    #[snafu(display("SAS token expired at {}", expiry))]
+    SasTokenExpired { expiry: String },
+
+    #[snafu(display(
+        "Invalid storage service version '{}', expected a date of the form YYYY-MM-DD",
+        version
+    ))]
+    InvalidAzureVersion { version: String },
+
+    #[snafu(display("Invalid connection string, expected segments of the form 'key=value'"))]
+    InvalidConnectionString {},
+
     #[snafu(display("Configuration key: '{}' is not known.", key))]
     UnknownConfigurationKey { key: String },
 
@@ -149,10 +173,16 @@ pub struct MicrosoftAzureBuilder {
     authority_host: Option<String>,
     /// Url
     url: Option<String>,
+    /// Connection string
+    connection_string: Option<String>,
     /// When set to true, azurite storage emulator has to be used
-    use_emulator: ConfigValue<bool>,
+    use_emulator: Option<ConfigValue<bool>>,
     /// Storage endpoint
     endpoint: Option<String>,
+    /// Storage endpoint DNS suffix
+    endpoint_suffix: Option<String>,
+    /// Storage service version sent in the `x-ms-version` header
+    azure_version: Option<String>,
     /// Msi endpoint for acquiring managed identity token
     msi_endpoint: Option<String>,
     /// Object id for use with managed identity authentication
@@ -177,6 +207,12 @@ pub struct MicrosoftAzureBuilder {
     use_fabric_endpoint: ConfigValue<bool>,
     /// When set to true, skips tagging objects
     disable_tagging: ConfigValue<bool>,
+    /// Access tier of uploaded blobs
+    access_tier: Option<ConfigValue<AccessTier>>,
+    /// Size of the blocks uploaded by multipart uploads
+    multipart_block_size: Option<ConfigValue<ByteSize>>,
+    /// Number of blocks uploaded concurrently by multipart uploads
+    multipart_concurrency: Option<ConfigValue<usize>>,
 }
 
 /// Configuration keys for [`MicrosoftAzureBuilder`]
@@ -274,6 +310,25 @@ pub enum AzureConfigKey {
     /// - `endpoint`
     Endpoint,
 
+    /// The DNS suffix of the storage endpoint, used to target sovereign clouds
+    ///
+    /// See [`MicrosoftAzureBuilder::with_endpoint_suffix`] for details
+    ///
+    /// Supported keys:
+    /// - `azure_storage_endpoint_suffix`
+    /// - `azure_endpoint_suffix`
+    /// - `endpoint_suffix`
+    EndpointSuffix,
+
+    /// The storage service version sent in the `x-ms-version` header
+    ///
+    /// See [`MicrosoftAzureBuilder::with_azure_version`] for details
+    ///
+    /// Supported keys:
+    /// - `azure_storage_version`
+    /// - `azure_version`
+    AzureVersion,
+
     /// Use object store with url scheme account.dfs.fabric.microsoft.com
     ///
     /// Supported keys:
@@ -341,10 +396,78 @@ pub enum AzureConfigKey {
     /// - `disable_tagging`
     DisableTagging,
 
+    /// The access tier of uploaded blobs
+    ///
+    /// See [`MicrosoftAzureBuilder::with_access_tier`] for details
+    ///
+    /// Supported keys:
+    /// - `azure_storage_access_tier`
+    /// - `azure_access_tier`
+    /// - `access_tier`
+    AccessTier,
+
+    /// The size of the blocks uploaded by [`ObjectStore::put_multipart`]
+    ///
+    /// Accepts a number of bytes with an optional unit, e.g. `16MiB`.
+    /// See [`MicrosoftAzureBuilder::with_multipart_block_size`] for details
+    ///
+    /// Supported keys:
+    /// - `azure_multipart_block_size`
+    /// - `multipart_block_size`
+    ///
+    /// [`ObjectStore::put_multipart`]: crate::ObjectStore::put_multipart
+    MultipartBlockSize,
+
+    /// The number of blocks uploaded concurrently by [`ObjectStore::put_multipart`]
+    ///
+    /// Supported keys:
+    /// - `azure_multipart_concurrency`
+    /// - `multipart_concurrency`
+    ///
+    /// [`ObjectStore::put_multipart`]: crate::ObjectStore::put_multipart
+    MultipartConcurrency,
+
     /// Client options
     Client(ClientConfigKey),
 }
 
+impl AzureConfigKey {
+    /// All variants of [`AzureConfigKey`] other than [`AzureConfigKey::Client`]
+    const KEYS: &'static [Self] = &[
+        Self::AccountName,
+        Self::AccessKey,
+        Self::ClientId,
+        Self::ClientSecret,
+        Self::AuthorityId,
+        Self::SasKey,
+        Self::Token,
+        Self::UseEmulator,
+        Self::Endpoint,
+        Self::EndpointSuffix,
+        Self::AzureVersion,
+        Self::UseFabricEndpoint,
+        Self::MsiEndpoint,
+        Self::ObjectId,
+        Self::MsiResourceId,
+        Self::FederatedTokenFile,
+        Self::UseAzureCli,
+        Self::SkipSignature,
+        Self::ContainerName,
+        Self::DisableTagging,
+        Self::AccessTier,
+        Self::MultipartBlockSize,
+        Self::MultipartConcurrency,
+    ];
+
+    /// Returns true if the value of this key should not be disclosed
+    fn is_secret(&self) -> bool {
+        matches!(
+            self,
+            Self::AccessKey | Self::ClientSecret | Self::SasKey | Self::Token
+        )
+    }
+}
+
 impl AsRef<str> for AzureConfigKey {
     fn as_ref(&self) -> &str {
         match self {
@@ -358,6 +481,8 @@ impl AsRef<str> for AzureConfigKey {
             Self::UseEmulator => "azure_storage_use_emulator",
             Self::UseFabricEndpoint => "azure_use_fabric_endpoint",
             Self::Endpoint => "azure_storage_endpoint",
+            Self::EndpointSuffix => "azure_storage_endpoint_suffix",
+            Self::AzureVersion => "azure_storage_version",
             Self::MsiEndpoint => "azure_msi_endpoint",
             Self::ObjectId => "azure_object_id",
             Self::MsiResourceId => "azure_msi_resource_id",
@@ -366,6 +491,9 @@ impl AsRef<str> for AzureConfigKey {
             Self::SkipSignature => "azure_skip_signature",
             Self::ContainerName => "azure_container_name",
             Self::DisableTagging => "azure_disable_tagging",
+            Self::AccessTier => "azure_storage_access_tier",
+            Self::MultipartBlockSize => "azure_multipart_block_size",
+            Self::MultipartConcurrency => "azure_multipart_concurrency",
             Self::Client(key) => key.as_ref(),
         }
     }
@@ -406,11 +534,22 @@ impl FromStr for AzureConfigKey {
             "azure_object_id" | "object_id" => Ok(Self::ObjectId),
             "azure_msi_resource_id" | "msi_resource_id" => Ok(Self::MsiResourceId),
             "azure_federated_token_file" | "federated_token_file" => Ok(Self::FederatedTokenFile),
+            "azure_storage_endpoint_suffix" | "azure_endpoint_suffix" | "endpoint_suffix" => {
+                Ok(Self::EndpointSuffix)
+            }
+            "azure_storage_version" | "azure_version" => Ok(Self::AzureVersion),
             "azure_use_fabric_endpoint" | "use_fabric_endpoint" => Ok(Self::UseFabricEndpoint),
             "azure_use_azure_cli" | "use_azure_cli" => Ok(Self::UseAzureCli),
             "azure_skip_signature" | "skip_signature" => Ok(Self::SkipSignature),
             "azure_container_name" | "container_name" => Ok(Self::ContainerName),
             "azure_disable_tagging" | "disable_tagging" => Ok(Self::DisableTagging),
+            "azure_storage_access_tier" | "azure_access_tier" | "access_tier" => {
+                Ok(Self::AccessTier)
+            }
+            "azure_multipart_block_size" | "multipart_block_size" => Ok(Self::MultipartBlockSize),
+            "azure_multipart_concurrency" | "multipart_concurrency" => {
+                Ok(Self::MultipartConcurrency)
+            }
             // Backwards compatibility
             "azure_allow_http" => Ok(Self::Client(ClientConfigKey::AllowHttp)),
             _ => match s.parse() {
@@ -522,14 +661,27 @@ impl MicrosoftAzureBuilder {
             AzureConfigKey::FederatedTokenFile => self.federated_token_file = Some(value.into()),
             AzureConfigKey::UseAzureCli => self.use_azure_cli.parse(value),
             AzureConfigKey::SkipSignature => self.skip_signature.parse(value),
-            AzureConfigKey::UseEmulator => self.use_emulator.parse(value),
+            AzureConfigKey::UseEmulator => {
+                self.use_emulator = Some(ConfigValue::Deferred(value.into()))
+            }
             AzureConfigKey::Endpoint => self.endpoint = Some(value.into()),
+            AzureConfigKey::EndpointSuffix => self.endpoint_suffix = Some(value.into()),
+            AzureConfigKey::AzureVersion => self.azure_version = Some(value.into()),
             AzureConfigKey::UseFabricEndpoint => self.use_fabric_endpoint.parse(value),
             AzureConfigKey::Client(key) => {
                 self.client_options = self.client_options.with_config(key, value)
             }
             AzureConfigKey::ContainerName => self.container_name = Some(value.into()),
             AzureConfigKey::DisableTagging => self.disable_tagging.parse(value),
+            AzureConfigKey::AccessTier => {
+                self.access_tier = Some(ConfigValue::Deferred(value.into()))
+            }
+            AzureConfigKey::MultipartBlockSize => {
+                self.multipart_block_size = Some(ConfigValue::Deferred(value.into()))
+            }
+            AzureConfigKey::MultipartConcurrency => {
+                self.multipart_concurrency = Some(ConfigValue::Deferred(value.into()))
+            }
         };
         self
     }
@@ -554,9 +706,15 @@ impl MicrosoftAzureBuilder {
             AzureConfigKey::AuthorityId => self.tenant_id.clone(),
             AzureConfigKey::SasKey => self.sas_key.clone(),
             AzureConfigKey::Token => self.bearer_token.clone(),
-            AzureConfigKey::UseEmulator => Some(self.use_emulator.to_string()),
+            AzureConfigKey::UseEmulator => Some(
+                self.use_emulator
+                    .as_ref()
+                    .map_or_else(|| false.to_string(), ToString::to_string),
+            ),
             AzureConfigKey::UseFabricEndpoint => Some(self.use_fabric_endpoint.to_string()),
             AzureConfigKey::Endpoint => self.endpoint.clone(),
+            AzureConfigKey::EndpointSuffix => self.endpoint_suffix.clone(),
+            AzureConfigKey::AzureVersion => self.azure_version.clone(),
             AzureConfigKey::MsiEndpoint => self.msi_endpoint.clone(),
             AzureConfigKey::ObjectId => self.object_id.clone(),
             AzureConfigKey::MsiResourceId => self.msi_resource_id.clone(),
@@ -566,9 +724,59 @@ impl MicrosoftAzureBuilder {
             AzureConfigKey::Client(key) => self.client_options.get_config_value(key),
             AzureConfigKey::ContainerName => self.container_name.clone(),
             AzureConfigKey::DisableTagging => Some(self.disable_tagging.to_string()),
+            AzureConfigKey::AccessTier => self.access_tier.as_ref().map(ToString::to_string),
+            AzureConfigKey::MultipartBlockSize => {
+                self.multipart_block_size.as_ref().map(ToString::to_string)
+            }
+            AzureConfigKey::MultipartConcurrency => {
+                self.multipart_concurrency.as_ref().map(ToString::to_string)
+            }
         }
     }
 
+    /// Returns the configuration of this builder as key - value pairs
+    ///
+    /// This includes any values sourced from the environment by [`Self::from_env`], along
+    /// with the defaults of options that have not been set, and can be used to diagnose
+    /// why a store is not configured as expected. Keys without a value are omitted.
+    ///
+    /// Secrets, such as access keys, client secrets, SAS keys and bearer tokens, are redacted,
+    /// see [`Self::config_snapshot_with_secrets`] to include them.
+    ///
+    /// # Example
+    /// ```
+    /// use object_store::azure::MicrosoftAzureBuilder;
+    ///
+    /// let builder = MicrosoftAzureBuilder::new()
+    ///     .with_account("foo")
+    ///     .with_access_key("secret");
+    /// let snapshot = builder.config_snapshot();
+    /// assert_eq!(snapshot["azure_storage_account_name"], "foo");
+    /// assert_ne!(snapshot["azure_storage_account_key"], "secret");
+    /// ```
+    pub fn config_snapshot(&self) -> BTreeMap<String, String> {
+        self.snapshot(false)
+    }
+
+    /// Returns the configuration of this builder as key - value pairs, including secrets
+    ///
+    /// See [`Self::config_snapshot`]
+    pub fn config_snapshot_with_secrets(&self) -> BTreeMap<String, String> {
+        self.snapshot(true)
+    }
+
+    fn snapshot(&self, include_secrets: bool) -> BTreeMap<String, String> {
+        let client_keys = ClientConfigKey::ALL
+            .iter()
+            .map(|k| AzureConfigKey::Client(*k));
+        config_snapshot(
+            AzureConfigKey::KEYS.iter().copied().chain(client_keys),
+            include_secrets,
+            AzureConfigKey::is_secret,
+            |k| self.get_config_value(k),
+        )
+    }
+
     /// Sets properties on this builder based on a URL
     ///
     /// This is a separate member function to allow fallible computation to
@@ -628,6 +836,65 @@ impl MicrosoftAzureBuilder {
         Ok(())
     }
 
+    /// Applies the options of `connection_string` not already set on this builder
+    fn parse_connection_string(&mut self, connection_string: &str) -> Result<(), Error> {
+        let mut protocol = None;
+        let mut suffix = None;
+        let mut blob_endpoint = None;
+
+        let segments = connection_string.split(';').map(str::trim);
+        for segment in segments.filter(|s| !s.is_empty()) {
+            let (key, value) = segment
+                .split_once('=')
+                .ok_or(Error::InvalidConnectionString {})?;
+
+            match key {
+                "AccountName" => {
+                    self.account_name.get_or_insert_with(|| value.to_string());
+                }
+                "AccountKey" => {
+                    self.access_key.get_or_insert_with(|| value.to_string());
+                }
+                "SharedAccessSignature" => {
+                    self.sas_key.get_or_insert_with(|| value.to_string());
+                }
+                "BlobEndpoint" => blob_endpoint = Some(value),
+                "DefaultEndpointsProtocol" => protocol = Some(value),
+                "EndpointSuffix" => suffix = Some(value),
+                "UseDevelopmentStorage" => {
+                    if value.eq_ignore_ascii_case("true") {
+                        self.use_emulator.get_or_insert_with(|| true.into());
+                    }
+                }
+                // Endpoints for other storage services
+                "QueueEndpoint" | "TableEndpoint" | "FileEndpoint" => {}
+                _ => warn!("Ignoring unknown connection string key \"{key}\""),
+            }
+        }
+
+        if let Some(suffix) = suffix {
+            self.endpoint_suffix
+                .get_or_insert_with(|| suffix.to_string());
+        }
+
+        if protocol == Some("http") {
+            self.client_options = self.client_options.clone().with_allow_http(true);
+        }
+
+        if self.endpoint.is_none() {
+            self.endpoint = match (blob_endpoint, &self.account_name, protocol) {
+                (Some(endpoint), _, _) => Some(endpoint.to_string()),
+                (None, Some(account), Some("http")) => {
+                    let suffix = self.endpoint_suffix.as_deref();
+                    let suffix = suffix.unwrap_or(DEFAULT_ENDPOINT_SUFFIX);
+                    Some(format!("http://{account}.blob.{suffix}"))
+                }
+                _ => None,
+            };
+        }
+        Ok(())
+    }
+
     /// Set the Azure Account (required)
     pub fn with_account(mut self, account: impl Into<String>) -> Self {
         self.account_name = Some(account.into());
@@ -689,6 +956,42 @@ impl MicrosoftAzureBuilder {
         self
     }
 
+    /// Set a percent-encoded shared access signature token, such as a container or
+    /// account SAS generated in the Azure portal, appended to the url of every request
+    ///
+    /// The leading `?` is optional. If the token contains a parseable signed expiry (`se`),
+    /// [`MicrosoftAzureBuilder::build`] will return an error if it has already expired.
+    ///
+    /// See [`AzureConfigKey::SasKey`]
+    pub fn with_sas_token(mut self, token: impl Into<String>) -> Self {
+        self.sas_key = Some(token.into());
+        self
+    }
+
+    /// Configure the account, credentials and endpoint from a connection string, such as
+    /// those provided by the Azure portal
+    ///
+    /// For example `DefaultEndpointsProtocol=https;AccountName=account;AccountKey=key;EndpointSuffix=core.windows.net`,
+    /// or `UseDevelopmentStorage=true` to use the storage emulator.
+    ///
+    /// The connection string is parsed by [`MicrosoftAzureBuilder::build`], with any options
+    /// set explicitly on the builder taking precedence. The following keys are supported,
+    /// with any other keys ignored:
+    ///
+    /// - `AccountName`
+    /// - `AccountKey`
+    /// - `SharedAccessSignature`
+    /// - `BlobEndpoint`
+    /// - `DefaultEndpointsProtocol`
+    /// - `EndpointSuffix`
+    /// - `UseDevelopmentStorage`
+    ///
+    /// <https://learn.microsoft.com/en-us/azure/storage/common/storage-configure-connection-string>
+    pub fn with_connection_string(mut self, connection_string: impl Into<String>) -> Self {
+        self.connection_string = Some(connection_string.into());
+        self
+    }
+
     /// Set the credential provider overriding any other options
     pub fn with_credentials(mut self, credentials: AzureCredentialProvider) -> Self {
         self.credentials = Some(credentials);
@@ -697,7 +1000,7 @@ impl MicrosoftAzureBuilder {
 
     /// Set if the Azure emulator should be used (defaults to false)
     pub fn with_use_emulator(mut self, use_emulator: bool) -> Self {
-        self.use_emulator = use_emulator.into();
+        self.use_emulator = Some(use_emulator.into());
         self
     }
 
@@ -712,6 +1015,36 @@ impl MicrosoftAzureBuilder {
         self
     }
 
+    /// Set the DNS suffix of the storage endpoint, defaults to `core.windows.net`
+    ///
+    /// The blob endpoint is then `https://{account}.blob.{suffix}`, which can be used to
+    /// target sovereign clouds, for example `core.usgovcloudapi.net` for Azure US Government
+    /// or `core.chinacloudapi.cn` for Azure China.
+    ///
+    /// If the suffix belongs to a known sovereign cloud and no authority host has been set
+    /// with [`Self::with_authority_host`], the matching authority host from
+    /// [authority_hosts](crate::azure::authority_hosts) will be used for OAuth
+    /// based authorization.
+    ///
+    /// Note: [`Self::with_endpoint`] will take precedence over this option
+    pub fn with_endpoint_suffix(mut self, suffix: impl Into<String>) -> Self {
+        self.endpoint_suffix = Some(suffix.into());
+        self
+    }
+
+    /// Sets the storage service version sent in the `x-ms-version` header of all requests
+    ///
+    /// This must be a date of the form `YYYY-MM-DD`, and defaults to the version
+    /// this crate has been tested against. Newer versions may be required to use
+    /// recently added features, whilst older versions may be necessary for
+    /// compatibility with Azure Stack or older releases of the Azurite emulator.
+    ///
+    /// See <https://learn.microsoft.com/en-us/rest/api/storageservices/versioning-for-the-azure-storage-services>
+    pub fn with_azure_version(mut self, version: impl Into<String>) -> Self {
+        self.azure_version = Some(version.into());
+        self
+    }
+
     /// Set if Microsoft Fabric url scheme should be used (defaults to false)
     ///
     /// When disabled the url scheme used is `https://{account}.blob.core.windows.net`
@@ -775,6 +1108,13 @@ impl MicrosoftAzureBuilder {
         self
     }
 
+    /// Sets the [`reqwest::Client`] used to make requests, sharing its connection pool
+    /// with other stores, see [`ClientOptions::with_http_client`]
+    pub fn with_http_client(mut self, client: reqwest::Client) -> Self {
+        self.client_options = self.client_options.with_http_client(client);
+        self
+    }
+
     /// Sets the endpoint for acquiring managed identity token
     pub fn with_msi_endpoint(mut self, msi_endpoint: impl Into<String>) -> Self {
         self.msi_endpoint = Some(msi_endpoint.into());
@@ -799,7 +1139,8 @@ impl MicrosoftAzureBuilder {
 
     /// If enabled, [`MicrosoftAzure`] will not fetch credentials and will not sign requests
     ///
-    /// This can be useful when interacting with public containers
+    /// This takes precedence over any configured credentials, with requests sent without
+    /// an `Authorization` header. This can be useful when interacting with public containers
     pub fn with_skip_signature(mut self, skip_signature: bool) -> Self {
         self.skip_signature = skip_signature.into();
         self
@@ -811,19 +1152,62 @@ impl MicrosoftAzureBuilder {
         self
     }
 
+    /// Sets the [`AccessTier`] of blobs written by [`ObjectStore::put`] and
+    /// [`ObjectStore::put_multipart`], otherwise the account's default tier is used
+    ///
+    /// [`AccessTier::Cold`] requires a storage service version of `2021-12-02` or later,
+    /// see [`Self::with_azure_version`]
+    ///
+    /// [`ObjectStore::put`]: crate::ObjectStore::put
+    /// [`ObjectStore::put_multipart`]: crate::ObjectStore::put_multipart
+    pub fn with_access_tier(mut self, tier: AccessTier) -> Self {
+        self.access_tier = Some(tier.into());
+        self
+    }
+
+    /// Sets the size in bytes of the blocks uploaded by [`ObjectStore::put_multipart`],
+    /// defaults to 10 MiB
+    ///
+    /// This must be at most 4000 MiB. Larger blocks reduce the number of requests, at the
+    /// cost of greater memory usage, and are necessary for blobs with more than 50,000
+    /// blocks
+    ///
+    /// [`ObjectStore::put_multipart`]: crate::ObjectStore::put_multipart
+    pub fn with_multipart_block_size(mut self, block_size: usize) -> Self {
+        self.multipart_block_size = Some(ByteSize(block_size).into());
+        self
+    }
+
+    /// Sets the number of blocks uploaded concurrently by [`ObjectStore::put_multipart`],
+    /// defaults to 8
+    ///
+    /// [`ObjectStore::put_multipart`]: crate::ObjectStore::put_multipart
+    pub fn with_multipart_concurrency(mut self, concurrency: usize) -> Self {
+        self.multipart_concurrency = Some(concurrency.into());
+        self
+    }
+
     /// Configure a connection to container with given name on Microsoft Azure Blob store.
     pub fn build(mut self) -> Result<MicrosoftAzure> {
         if let Some(url) = self.url.take() {
             self.parse_url(&url)?;
         }
 
+        if let Some(connection_string) = self.connection_string.take() {
+            self.parse_connection_string(&connection_string)?;
+        }
+
         let container = self.container_name.ok_or(Error::MissingContainerName {})?;
 
         let static_creds = |credential: AzureCredential| -> AzureCredentialProvider {
             Arc::new(StaticCredentialProvider::new(credential))
         };
 
-        let (is_emulator, storage_url, auth, account) = if self.use_emulator.get()? {
+        let use_emulator = match &self.use_emulator {
+            Some(use_emulator) => use_emulator.get()?,
+            None => false,
+        };
+        let (is_emulator, storage_url, auth, account) = if use_emulator {
             let account_name = self
                 .account_name
                 .unwrap_or_else(|| EMULATOR_ACCOUNT.to_string());
@@ -835,9 +1219,9 @@ impl MicrosoftAzureBuilder {
             } else if let Some(bearer_token) = self.bearer_token {
                 AzureCredential::BearerToken(bearer_token)
             } else if let Some(query_pairs) = self.sas_query_pairs {
-                AzureCredential::SASToken(query_pairs)
+                AzureCredential::SASToken(check_sas_expiry(query_pairs)?)
             } else if let Some(sas) = self.sas_key {
-                AzureCredential::SASToken(split_sas(&sas)?)
+                AzureCredential::SASToken(check_sas_expiry(split_sas(&sas)?)?)
             } else {
                 AzureCredential::AccessKey(AzureAccessKey::try_new(EMULATOR_ACCOUNT_KEY)?)
             };
@@ -852,10 +1236,19 @@ impl MicrosoftAzureBuilder {
                     true => {
                         format!("https://{}.blob.fabric.microsoft.com", &account_name)
                     }
-                    false => format!("https://{}.blob.core.windows.net", &account_name),
+                    false => {
+                        let suffix = self.endpoint_suffix.as_deref();
+                        let suffix = suffix.unwrap_or(DEFAULT_ENDPOINT_SUFFIX);
+                        format!("https://{}.blob.{}", &account_name, suffix)
+                    }
                 },
             };
 
+            let authority_host = self.authority_host.or_else(|| {
+                let suffix = self.endpoint_suffix.as_deref()?;
+                Some(authority_host_for_suffix(suffix)?.to_string())
+            });
+
             let url =
                 Url::parse(&account_url).context(UnableToParseUrlSnafu { url: account_url })?;
 
@@ -873,7 +1266,7 @@ impl MicrosoftAzureBuilder {
                     client_id,
                     federated_token_file,
                     tenant_id,
-                    self.authority_host,
+                    authority_host.clone(),
                 );
                 Arc::new(TokenCredentialProvider::new(
                     client_credential,
@@ -887,7 +1280,7 @@ impl MicrosoftAzureBuilder {
                     client_id.clone(),
                     client_secret,
                     tenant_id,
-                    self.authority_host,
+                    authority_host,
                 );
                 Arc::new(TokenCredentialProvider::new(
                     client_credential,
@@ -895,9 +1288,10 @@ impl MicrosoftAzureBuilder {
                     self.retry_config.clone(),
                 )) as _
             } else if let Some(query_pairs) = self.sas_query_pairs {
-                static_creds(AzureCredential::SASToken(query_pairs))
+                static_creds(AzureCredential::SASToken(check_sas_expiry(query_pairs)?))
             } else if let Some(sas) = self.sas_key {
-                static_creds(AzureCredential::SASToken(split_sas(&sas)?))
+                let query_pairs = check_sas_expiry(split_sas(&sas)?)?;
+                static_creds(AzureCredential::SASToken(query_pairs))
             } else if self.use_azure_cli.get()? {
                 Arc::new(AzureCliCredential::new()) as _
             } else {
@@ -916,12 +1310,45 @@ impl MicrosoftAzureBuilder {
             (false, url, credential, account_name)
         };
 
+        let version = match self.azure_version {
+            Some(version) => {
+                validate_azure_version(&version)?;
+                version
+            }
+            None => AZURE_VERSION.to_string(),
+        };
+
+        let multipart_block_size = match self.multipart_block_size {
+            Some(size) => size.get()?.0,
+            None => DEFAULT_PART_SIZE,
+        };
+        let multipart_concurrency = match self.multipart_concurrency {
+            Some(concurrency) => concurrency.get()?,
+            None => DEFAULT_CONCURRENCY,
+        };
+        validate_multipart_config(
+            STORE,
+            multipart_block_size,
+            1,
+            MAX_BLOCK_SIZE,
+            multipart_concurrency,
+        )?;
+
+        let access_tier = self.access_tier.map(|x| x.get()).transpose()?;
+        if let Some(tier) = &access_tier {
+            tier.check_version(&version)?;
+        }
+
         let config = AzureConfig {
             account,
+            version,
             is_emulator,
             skip_signature: self.skip_signature.get()?,
             container,
             disable_tagging: self.disable_tagging.get()?,
+            access_tier,
+            multipart_block_size,
+            multipart_concurrency,
             retry_config: self.retry_config,
             client_options: self.client_options,
             service: storage_url,
@@ -934,6 +1361,27 @@ impl MicrosoftAzureBuilder {
     }
 }
 
+/// Checks that `version` is a storage service version of the form `YYYY-MM-DD`
+fn validate_azure_version(version: &str) -> Result<(), Error> {
+    match version.len() == 10 && NaiveDate::parse_from_str(version, "%Y-%m-%d").is_ok() {
+        true => Ok(()),
+        false => Err(Error::InvalidAzureVersion {
+            version: version.to_string(),
+        }),
+    }
+}
+
+/// Returns the authority host of the sovereign cloud with the storage endpoint `suffix`
+fn authority_host_for_suffix(suffix: &str) -> Option<&'static str> {
+    match suffix.trim_start_matches('.') {
+        "core.windows.net" => Some(authority_hosts::AZURE_PUBLIC_CLOUD),
+        "core.usgovcloudapi.net" => Some(authority_hosts::AZURE_GOVERNMENT),
+        "core.chinacloudapi.cn" => Some(authority_hosts::AZURE_CHINA),
+        "core.cloudapi.de" => Some(authority_hosts::AZURE_GERMANY),
+        _ => None,
+    }
+}
+
 /// Parses the contents of the environment variable `env_name` as a URL
 /// if present, otherwise falls back to default_url
 fn url_from_env(env_name: &str, default_url: &str) -> Result<Url> {
@@ -966,6 +1414,40 @@ fn split_sas(sas: &str) -> Result<Vec<(String, String)>, Error> {
     Ok(pairs)
 }
 
+/// Returns an error if the signed expiry (`se`) of the SAS `pairs` has passed
+///
+/// Logs a warning if the expiry cannot be parsed
+fn check_sas_expiry(pairs: Vec<(String, String)>) -> Result<Vec<(String, String)>, Error> {
+    let expiry = match pairs.iter().find(|(k, _)| k == "se") {
+        Some((_, v)) => v,
+        None => return Ok(pairs),
+    };
+
+    // The expiry may be specified as a date or as a UTC date and time, with
+    // optional seconds - https://learn.microsoft.com/en-us/rest/api/storageservices/formatting-datetime-values
+    let parsed = DateTime::parse_from_rfc3339(expiry)
+        .map(|x| x.with_timezone(&Utc))
+        .or_else(|_| {
+            NaiveDateTime::parse_from_str(expiry, "%Y-%m-%dT%H:%MZ")
+                .map(|x| Utc.from_utc_datetime(&x))
+        })
+        .or_else(|_| {
+            NaiveDate::parse_from_str(expiry, "%Y-%m-%d")
+                .map(|x| Utc.from_utc_datetime(&x.and_time(NaiveTime::MIN)))
+        });
+
+    match parsed {
+        Ok(parsed) if parsed <= Utc::now() => Err(Error::SasTokenExpired {
+            expiry: expiry.clone(),
+        }),
+        Ok(_) => Ok(pairs),
+        Err(e) => {
+            warn!("Unable to parse SAS token expiry \"{expiry}\": {e}");
+            Ok(pairs)
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1091,6 +1573,38 @@ mod tests {
         assert_eq!(builder.bearer_token.unwrap(), azure_storage_token);
     }
 
+    #[test]
+    fn azure_test_config_snapshot() {
+        let builder = MicrosoftAzureBuilder::new()
+            .with_account("account")
+            .with_access_key("access_key")
+            .with_bearer_token_authorization("token")
+            .with_config(AzureConfigKey::ClientSecret, "client_secret")
+            .with_config(AzureConfigKey::SasKey, "sas")
+            .with_endpoint("http://localhost:10000".to_string());
+
+        let snapshot = builder.config_snapshot();
+        assert_eq!(snapshot["azure_storage_account_name"], "account");
+        assert_eq!(snapshot["azure_storage_endpoint"], "http://localhost:10000");
+        assert_eq!(snapshot["azure_storage_use_emulator"], "false");
+        assert_eq!(snapshot["allow_http"], "false");
+        assert!(!snapshot.contains_key("azure_storage_client_id"));
+        for key in [
+            "azure_storage_account_key",
+            "azure_storage_token",
+            "azure_storage_client_secret",
+            "azure_storage_sas_key",
+        ] {
+            assert_eq!(snapshot[key], crate::config::REDACTED);
+        }
+
+        let snapshot = builder.config_snapshot_with_secrets();
+        assert_eq!(snapshot["azure_storage_account_key"], "access_key");
+        assert_eq!(snapshot["azure_storage_token"], "token");
+        assert_eq!(snapshot["azure_storage_client_secret"], "client_secret");
+        assert_eq!(snapshot["azure_storage_sas_key"], "sas");
+    }
+
     #[test]
     fn azure_test_split_sas() {
         let raw_sas = "?sv=2021-10-04&st=2023-01-04T17%3A48%3A57Z&se=2023-01-04T18%3A15%3A00Z&sr=c&sp=rcwl&sig=C7%2BZeEOWbrxPA3R0Cw%2Fw1EZz0%2B4KBvQexeKZKe%2BB6h0%3D";
@@ -1108,4 +1622,219 @@ mod tests {
         let pairs = split_sas(raw_sas).unwrap();
         assert_eq!(expected, pairs);
     }
+
+    #[test]
+    fn azure_test_sas_expiry() {
+        let build = |sas: &str| {
+            MicrosoftAzureBuilder::new()
+                .with_account("account")
+                .with_container_name("container")
+                .with_sas_token(sas)
+                .build()
+        };
+
+        let err = build("sv=2021-10-04&se=2023-01-04T18%3A15%3A00Z&sig=abc")
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains("SAS token expired at 2023-01-04T18:15:00Z"),
+            "{err}"
+        );
+        let err = build("?se=2023-01-04&sig=abc").unwrap_err().to_string();
+        assert!(err.contains("SAS token expired at 2023-01-04"), "{err}");
+        let err = build("se=2023-01-04T18%3A15Z&sig=abc")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("SAS token expired"), "{err}");
+
+        build("sv=2021-10-04&se=2999-01-01T00%3A00%3A00Z&sig=abc").unwrap();
+        build("sv=2021-10-04&sig=abc").unwrap();
+        // Unparseable expiry is not an error
+        build("se=invalid&sig=abc").unwrap();
+    }
+
+    #[test]
+    fn azure_test_endpoint_suffix() {
+        let store = MicrosoftAzureBuilder::new()
+            .with_account("account")
+            .with_container_name("container")
+            .with_access_key("")
+            .with_endpoint_suffix("core.usgovcloudapi.net")
+            .build()
+            .unwrap();
+        assert_eq!(
+            store.client.config().service.as_str(),
+            "https://account.blob.core.usgovcloudapi.net/"
+        );
+
+        let builder = MicrosoftAzureBuilder::new()
+            .with_config("azure_storage_endpoint_suffix".parse().unwrap(), "suffix");
+        assert_eq!(
+            builder.get_config_value(&AzureConfigKey::EndpointSuffix),
+            Some("suffix".to_string())
+        );
+
+        assert_eq!(
+            authority_host_for_suffix("core.usgovcloudapi.net"),
+            Some(authority_hosts::AZURE_GOVERNMENT)
+        );
+        assert_eq!(
+            authority_host_for_suffix("core.chinacloudapi.cn"),
+            Some(authority_hosts::AZURE_CHINA)
+        );
+        assert_eq!(authority_host_for_suffix("example.com"), None);
+    }
+
+    #[test]
+    fn azure_test_multipart_config() {
+        let builder = MicrosoftAzureBuilder::new()
+            .with_account("account")
+            .with_container_name("container")
+            .with_access_key("");
+
+        let store = builder
+            .clone()
+            .with_config("azure_multipart_block_size".parse().unwrap(), "4MB")
+            .with_config("multipart_concurrency".parse().unwrap(), "16")
+            .build()
+            .unwrap();
+        assert_eq!(store.client.config().multipart_block_size, 4_000_000);
+        assert_eq!(store.client.config().multipart_concurrency, 16);
+        assert_eq!(
+            builder
+                .clone()
+                .with_config(AzureConfigKey::MultipartBlockSize, "4MB")
+                .get_config_value(&AzureConfigKey::MultipartBlockSize)
+                .as_deref(),
+            Some("4MB")
+        );
+
+        let store = builder.clone().with_multipart_block_size(1024).build();
+        assert_eq!(store.unwrap().client.config().multipart_block_size, 1024);
+
+        let store = builder
+            .clone()
+            .with_config(AzureConfigKey::MultipartBlockSize, "4000MiB")
+            .build()
+            .unwrap();
+        assert_eq!(
+            store.client.config().multipart_block_size,
+            4000 * 1024 * 1024
+        );
+
+        // 4001MiB exceeds the maximum block size of 4000MiB
+        for size in ["0", "4XB", "4001MiB"] {
+            let b = builder
+                .clone()
+                .with_config(AzureConfigKey::MultipartBlockSize, size);
+            assert!(b.build().is_err(), "{size}");
+        }
+    }
+
+    #[test]
+    fn azure_test_version() {
+        let builder = MicrosoftAzureBuilder::new()
+            .with_account("account")
+            .with_container_name("container")
+            .with_access_key("");
+
+        let store = builder.clone().build().unwrap();
+        assert_eq!(store.client.config().version, AZURE_VERSION);
+
+        let store = builder
+            .clone()
+            .with_config(AzureConfigKey::AzureVersion, "2021-08-06")
+            .build()
+            .unwrap();
+        assert_eq!(store.client.config().version, "2021-08-06");
+
+        for version in ["latest", "2021-8-6", "2021-13-01", "2021-08-06 "] {
+            let err = builder.clone().with_azure_version(version).build();
+            assert!(err.is_err(), "{version}");
+        }
+    }
+
+    #[test]
+    fn azure_test_connection_string() {
+        let key = "ZmFrZWtleQ==";
+        let connection_string = format!(
+            "DefaultEndpointsProtocol=https;AccountName=account;AccountKey={key};EndpointSuffix=core.usgovcloudapi.net;Unknown=1"
+        );
+        let mut builder = MicrosoftAzureBuilder::new()
+            .with_container_name("container")
+            .with_connection_string(&connection_string);
+        let s = builder.connection_string.take().unwrap();
+        builder.parse_connection_string(&s).unwrap();
+        assert_eq!(builder.account_name.as_deref(), Some("account"));
+        assert_eq!(builder.access_key.as_deref(), Some(key));
+        assert_eq!(builder.endpoint, None);
+        assert_eq!(
+            builder.endpoint_suffix.as_deref(),
+            Some("core.usgovcloudapi.net")
+        );
+
+        // Explicit options take precedence
+        let mut builder = MicrosoftAzureBuilder::new()
+            .with_account("other")
+            .with_connection_string(&connection_string);
+        let s = builder.connection_string.take().unwrap();
+        builder.parse_connection_string(&s).unwrap();
+        assert_eq!(builder.account_name.as_deref(), Some("other"));
+
+        let mut builder = MicrosoftAzureBuilder::new().with_connection_string(
+            "DefaultEndpointsProtocol=http;AccountName=account;EndpointSuffix=example.com",
+        );
+        let s = builder.connection_string.take().unwrap();
+        builder.parse_connection_string(&s).unwrap();
+        assert_eq!(
+            builder.endpoint.as_deref(),
+            Some("http://account.blob.example.com")
+        );
+
+        let mut builder = MicrosoftAzureBuilder::new().with_connection_string(
+            "BlobEndpoint=https://account.example.com;SharedAccessSignature=sv=2021-10-04&sig=abc",
+        );
+        let s = builder.connection_string.take().unwrap();
+        builder.parse_connection_string(&s).unwrap();
+        assert_eq!(
+            builder.endpoint.as_deref(),
+            Some("https://account.example.com")
+        );
+        assert_eq!(builder.sas_key.as_deref(), Some("sv=2021-10-04&sig=abc"));
+
+        let mut builder = MicrosoftAzureBuilder::new().with_connection_string("invalid");
+        let err = builder.parse_connection_string("invalid").unwrap_err();
+        assert!(matches!(err, Error::InvalidConnectionString { .. }));
+        builder
+            .with_container_name("container")
+            .build()
+            .unwrap_err();
+
+        // Emulator shorthand
+        let store = MicrosoftAzureBuilder::new()
+            .with_container_name("container")
+            .with_connection_string("UseDevelopmentStorage=true")
+            .build()
+            .unwrap();
+        assert_eq!(store.client.config().account, EMULATOR_ACCOUNT);
+        assert!(store.client.config().is_emulator);
+
+        // Explicitly disabling the emulator takes precedence
+        let store = MicrosoftAzureBuilder::new()
+            .with_container_name("container")
+            .with_connection_string("UseDevelopmentStorage=true;AccountName=account")
+            .with_use_emulator(false)
+            .build()
+            .unwrap();
+        assert_eq!(store.client.config().account, "account");
+        assert!(!store.client.config().is_emulator);
+
+        let store = MicrosoftAzureBuilder::new()
+            .with_container_name("container")
+            .with_config(AzureConfigKey::UseEmulator, "false")
+            .with_connection_string("UseDevelopmentStorage=true;AccountName=account")
+            .build()
+            .unwrap();
+        assert!(!store.client.config().is_emulator);
+    }
 }
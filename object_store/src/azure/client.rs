@@ -16,19 +16,21 @@
 // under the License.
 
 use super::credential::AzureCredential;
+use crate::azure::acl::acl_from_headers;
 use crate::azure::credential::*;
-use crate::azure::{AzureCredentialProvider, STORE};
+use crate::azure::{AccessTier, AzureCredentialProvider, ExpiryOption, STORE};
 use crate::client::get::GetClient;
 use crate::client::header::{get_put_result, HeaderConfig};
 use crate::client::list::ListClient;
+use crate::client::metadata::MetadataLimits;
 use crate::client::retry::RetryExt;
-use crate::client::GetOptionsExt;
-use crate::multipart::PartId;
+use crate::client::{GetOptionsExt, HedgeConfig};
+use crate::multipart::{validate_parts, PartId, UploadLimiter};
 use crate::path::DELIMITER;
 use crate::util::{deserialize_rfc1123, GetRange};
 use crate::{
-    ClientOptions, GetOptions, ListResult, ObjectMeta, Path, PutMode, PutOptions, PutResult,
-    Result, RetryConfig,
+    Acl, ChecksumAlgorithm, ClientOptions, CopyOptions, GetOptions, ListDeduplication, ListResult,
+    ObjectChecksum, ObjectMeta, Path, PutMode, PutOptions, PutResult, Result, RetryConfig,
 };
 use async_trait::async_trait;
 use base64::prelude::BASE64_STANDARD;
@@ -52,6 +54,21 @@ use url::Url;
 const VERSION_HEADER: &str = "x-ms-version-id";
 
 static TAGS_HEADER: HeaderName = HeaderName::from_static("x-ms-tags");
+static ACCESS_TIER_HEADER: HeaderName = HeaderName::from_static("x-ms-access-tier");
+static SOURCE_IF_MATCH: HeaderName = HeaderName::from_static("x-ms-source-if-match");
+static SOURCE_IF_UNMODIFIED_SINCE: HeaderName =
+    HeaderName::from_static("x-ms-source-if-unmodified-since");
+static EXPIRY_OPTION_HEADER: HeaderName = HeaderName::from_static("x-ms-expiry-option");
+static EXPIRY_TIME_HEADER: HeaderName = HeaderName::from_static("x-ms-expiry-time");
+
+/// The limits on user-defined metadata
+///
+/// <https://learn.microsoft.com/en-us/rest/api/storageservices/setting-and-retrieving-properties-and-metadata-for-blob-resources>
+const METADATA_LIMITS: MetadataLimits = MetadataLimits {
+    header_prefix: "x-ms-meta-",
+    max_size: 8 * 1024,
+    identifier_keys: true,
+};
 
 /// A specialized `Error` for object store-related errors
 #[derive(Debug, Snafu)]
@@ -117,6 +134,9 @@ pub(crate) enum Error {
 
     #[snafu(display("Generating SAS keys while skipping signatures is not supported"))]
     SASwithSkipSignature,
+
+    #[snafu(display("Stored access policies require an access key, and are not supported by user delegation SAS"))]
+    SASwithPolicyNotSupported,
 }
 
 impl From<Error> for crate::Error {
@@ -137,6 +157,7 @@ impl From<Error> for crate::Error {
 #[derive(Debug)]
 pub(crate) struct AzureConfig {
     pub account: String,
+    pub version: String,
     pub container: String,
     pub credentials: AzureCredentialProvider,
     pub retry_config: RetryConfig,
@@ -144,6 +165,9 @@ pub(crate) struct AzureConfig {
     pub is_emulator: bool,
     pub skip_signature: bool,
     pub disable_tagging: bool,
+    pub access_tier: Option<AccessTier>,
+    pub multipart_block_size: usize,
+    pub multipart_concurrency: usize,
     pub client_options: ClientOptions,
 }
 
@@ -181,21 +205,53 @@ impl<'a> PutRequest<'a> {
         Self { builder, ..self }
     }
 
+    fn access_tier(self, tier: Option<AccessTier>) -> Self {
+        match tier {
+            Some(tier) => self.header(&ACCESS_TIER_HEADER, tier.as_str()),
+            None => self,
+        }
+    }
+
     fn query<T: Serialize + ?Sized + Sync>(self, query: &T) -> Self {
         let builder = self.builder.query(query);
         Self { builder, ..self }
     }
 
+    fn timeout(self, timeout: Option<Duration>) -> Self {
+        match timeout {
+            Some(timeout) => Self {
+                builder: self.builder.timeout(timeout),
+                ..self
+            },
+            None => self,
+        }
+    }
+
     async fn send(self) -> Result<Response> {
-        let credential = self.config.get_credential().await?;
-        let response = self
+        let config = self.config;
+        let credential = config.get_credential().await?;
+        let refresh = config.retry_config.refresh_credentials && credential.is_some();
+        let retry = refresh.then(|| self.builder.try_clone()).flatten();
+        let result = self
             .builder
-            .with_azure_authorization(&credential, &self.config.account)
-            .send_retry(&self.config.retry_config)
-            .await
-            .context(PutRequestSnafu {
-                path: self.path.as_ref(),
-            })?;
+            .with_azure_authorization(&credential, &config.account, &config.version)
+            .send_retry(&config.retry_config)
+            .await;
+
+        let response = match (result, retry) {
+            (Err(e), Some(builder)) if e.is_auth_failure() => {
+                config.credentials.invalidate().await;
+                let credential = config.get_credential().await?;
+                builder
+                    .with_azure_authorization(&credential, &config.account, &config.version)
+                    .send_retry(&config.retry_config)
+                    .await
+            }
+            (result, _) => result,
+        }
+        .context(PutRequestSnafu {
+            path: self.path.as_ref(),
+        })?;
 
         Ok(response)
     }
@@ -205,13 +261,24 @@ impl<'a> PutRequest<'a> {
 pub(crate) struct AzureClient {
     config: AzureConfig,
     client: ReqwestClient,
+    upload_limiter: Option<UploadLimiter>,
 }
 
 impl AzureClient {
     /// create a new instance of [AzureClient]
     pub fn new(config: AzureConfig) -> Result<Self> {
-        let client = config.client_options.client()?;
-        Ok(Self { config, client })
+        let client = config.client_options.store_client()?;
+        let upload_limiter = config.client_options.upload_limiter()?;
+        Ok(Self {
+            config,
+            client,
+            upload_limiter,
+        })
+    }
+
+    /// Returns the [`UploadLimiter`] shared by uploads to this store, if any
+    pub fn upload_limiter(&self) -> Option<&UploadLimiter> {
+        self.upload_limiter.as_ref()
     }
 
     /// Returns the config
@@ -245,13 +312,18 @@ impl AzureClient {
 
     /// Make an Azure PUT request <https://docs.microsoft.com/en-us/rest/api/storageservices/put-blob>
     pub async fn put_blob(&self, path: &Path, bytes: Bytes, opts: PutOptions) -> Result<PutResult> {
-        let builder = self.put_request(path, bytes);
+        let metadata = METADATA_LIMITS.headers(STORE, &opts.metadata)?;
+        let mut builder = self.put_request(path, bytes);
+        for (name, value) in &metadata {
+            builder = builder.header(name, value);
+        }
 
         let builder = match &opts.mode {
             PutMode::Overwrite => builder,
             PutMode::Create => builder.header(&IF_NONE_MATCH, "*"),
             PutMode::Update(v) => {
                 let etag = v.e_tag.as_ref().context(MissingETagSnafu)?;
+                self.config.client_options.check_conditional_etag(etag)?;
                 builder.header(&IF_MATCH, etag)
             }
         };
@@ -261,7 +333,11 @@ impl AzureClient {
             (tags, false) => builder.header(&TAGS_HEADER, tags),
         };
 
-        let response = builder.header(&BLOB_TYPE, "BlockBlob").send().await?;
+        let response = builder
+            .header(&BLOB_TYPE, "BlockBlob")
+            .access_tier(self.config.access_tier)
+            .send()
+            .await?;
         Ok(get_put_result(response.headers(), VERSION_HEADER).context(MetadataSnafu)?)
     }
 
@@ -275,11 +351,12 @@ impl AzureClient {
             .send()
             .await?;
 
-        Ok(PartId { content_id })
+        Ok(PartId::new(content_id, part_idx))
     }
 
     /// PUT a block list <https://learn.microsoft.com/en-us/rest/api/storageservices/put-block-list>
     pub async fn put_block_list(&self, path: &Path, parts: Vec<PartId>) -> Result<PutResult> {
+        validate_parts(STORE, &parts)?;
         let blocks = parts
             .into_iter()
             .map(|part| BlockId::from(part.content_id))
@@ -288,6 +365,8 @@ impl AzureClient {
         let response = self
             .put_request(path, BlockList { blocks }.to_xml().into())
             .query(&[("comp", "blocklist")])
+            .access_tier(self.config.access_tier)
+            .timeout(self.config.client_options.multipart_complete_timeout()?)
             .send()
             .await?;
 
@@ -295,19 +374,32 @@ impl AzureClient {
     }
 
     /// Make an Azure Delete request <https://docs.microsoft.com/en-us/rest/api/storageservices/delete-blob>
-    pub async fn delete_request<T: Serialize + ?Sized + Sync>(
+    ///
+    /// If `if_match` is provided the delete is conditional on the blob's ETag
+    ///
+    /// Deletes `version` if specified, otherwise the current version of the blob
+    /// along with any snapshots
+    pub async fn delete_request(
         &self,
         path: &Path,
-        query: &T,
+        version: Option<&str>,
+        if_match: Option<&str>,
     ) -> Result<()> {
         let credential = self.get_credential().await?;
         let url = self.config.path_url(path);
 
-        self.client
-            .request(Method::DELETE, url)
-            .query(query)
-            .header(&DELETE_SNAPSHOTS, "include")
-            .with_azure_authorization(&credential, &self.config.account)
+        let mut builder = self.client.request(Method::DELETE, url);
+        if let Some(e_tag) = if_match {
+            builder = builder.header(&IF_MATCH, e_tag);
+        }
+
+        builder = match version {
+            Some(version) => builder.query(&[("versionid", version)]),
+            None => builder.header(&DELETE_SNAPSHOTS, "include"),
+        };
+
+        builder
+            .with_azure_authorization(&credential, &self.config.account, &self.config.version)
             .send_retry(&self.config.retry_config)
             .await
             .context(DeleteRequestSnafu {
@@ -317,8 +409,146 @@ impl AzureClient {
         Ok(())
     }
 
+    /// Make an Azure Set Blob Tier request <https://learn.microsoft.com/en-us/rest/api/storageservices/set-blob-tier>
+    pub async fn set_access_tier(&self, path: &Path, tier: AccessTier) -> Result<()> {
+        tier.check_version(&self.config.version)?;
+
+        let credential = self.get_credential().await?;
+        let url = self.config.path_url(path);
+
+        self.client
+            .request(Method::PUT, url)
+            .query(&[("comp", "tier")])
+            .header(&ACCESS_TIER_HEADER, tier.as_str())
+            .header(CONTENT_LENGTH, HeaderValue::from_static("0"))
+            .with_azure_authorization(&credential, &self.config.account, &self.config.version)
+            .send_retry(&self.config.retry_config)
+            .await
+            .context(PutRequestSnafu {
+                path: path.as_ref(),
+            })?;
+
+        Ok(())
+    }
+
+    /// Make an Azure Set Blob Expiry request <https://learn.microsoft.com/en-us/rest/api/storageservices/set-blob-expiry>
+    pub async fn set_expiry(&self, path: &Path, expiry: ExpiryOption) -> Result<()> {
+        let (option, time) = expiry.headers()?;
+
+        let credential = self.get_credential().await?;
+        let url = self.config.path_url(path);
+
+        let mut builder = self
+            .client
+            .request(Method::PUT, url)
+            .query(&[("comp", "expiry")])
+            .header(&EXPIRY_OPTION_HEADER, option)
+            .header(CONTENT_LENGTH, HeaderValue::from_static("0"));
+
+        if let Some(time) = time {
+            builder = builder.header(&EXPIRY_TIME_HEADER, time);
+        }
+
+        builder
+            .with_azure_authorization(&credential, &self.config.account, &self.config.version)
+            .send_retry(&self.config.retry_config)
+            .await
+            .context(PutRequestSnafu {
+                path: path.as_ref(),
+            })?;
+
+        Ok(())
+    }
+
+    /// Returns the [`AccessTier`] of the blob at `path`, read from the `x-ms-access-tier`
+    /// header of a Get Blob Properties request
+    ///
+    /// Returns `None` if the header is absent or contains an unrecognised tier
+    pub async fn get_access_tier(&self, path: &Path) -> Result<Option<AccessTier>> {
+        let options = GetOptions {
+            head: true,
+            ..Default::default()
+        };
+        let response = self.get_request(path, options).await?;
+        let tier = response
+            .headers()
+            .get(&ACCESS_TIER_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse().ok());
+        Ok(tier)
+    }
+
+    /// Returns the [`Acl`] of the blob at `path`, read from the headers of a Get Blob
+    /// Properties request, see [`acl_from_headers`]
+    pub async fn get_acl(&self, path: &Path) -> Result<Acl> {
+        let options = GetOptions {
+            head: true,
+            ..Default::default()
+        };
+        let response = self.get_request(path, options).await?;
+        Ok(acl_from_headers(response.headers()))
+    }
+
+    /// Make a Find Blobs by Tags request scoped to the container, returning the paths of
+    /// the matching blobs and the marker of the next page if any
+    ///
+    /// <https://learn.microsoft.com/en-us/rest/api/storageservices/find-blobs-by-tags-container>
+    pub async fn find_blobs_by_tags(
+        &self,
+        query: &str,
+        marker: Option<&str>,
+    ) -> Result<(Vec<Path>, Option<String>)> {
+        let credential = self.get_credential().await?;
+        let url = self.config.path_url(&Path::default());
+
+        let mut params = vec![
+            ("restype", "container"),
+            ("comp", "blobs"),
+            ("where", query),
+        ];
+        if let Some(marker) = marker {
+            params.push(("marker", marker))
+        }
+
+        let response = self
+            .client
+            .request(Method::GET, url)
+            .query(&params)
+            .with_azure_authorization(&credential, &self.config.account, &self.config.version)
+            .send_retry(&self.config.retry_config)
+            .await
+            .context(ListRequestSnafu)?
+            .bytes()
+            .await
+            .context(ListResponseBodySnafu)?;
+
+        let response: FilterBlobsResult =
+            quick_xml::de::from_reader(response.reader()).context(InvalidListResponseSnafu)?;
+        let paths = response
+            .blobs
+            .blobs
+            .into_iter()
+            .map(|b| Ok(Path::parse(b.name)?))
+            .collect::<Result<_>>()?;
+        Ok((paths, response.next_marker.filter(|m| !m.is_empty())))
+    }
+
     /// Make an Azure Copy request <https://docs.microsoft.com/en-us/rest/api/storageservices/copy-blob>
-    pub async fn copy_request(&self, from: &Path, to: &Path, overwrite: bool) -> Result<()> {
+    ///
+    /// If `tier` is provided the copy is stored in this [`AccessTier`], any
+    /// conditions in `opts` are sent as `x-ms-source-if-*` headers
+    pub async fn copy_request(
+        &self,
+        from: &Path,
+        to: &Path,
+        overwrite: bool,
+        tier: Option<AccessTier>,
+        opts: &CopyOptions,
+    ) -> Result<()> {
+        if let Some(tier) = tier {
+            tier.check_version(&self.config.version)?;
+        }
+
         let credential = self.get_credential().await?;
         let url = self.config.path_url(to);
         let mut source = self.config.path_url(from);
@@ -339,8 +569,23 @@ impl AzureClient {
             builder = builder.header(IF_NONE_MATCH, "*");
         }
 
+        if let Some(tier) = tier {
+            builder = builder.header(&ACCESS_TIER_HEADER, tier.as_str());
+        }
+
+        if let Some(etag) = &opts.if_match {
+            builder = builder.header(&SOURCE_IF_MATCH, etag);
+        }
+
+        if let Some(date) = opts.if_unmodified_since {
+            builder = builder.header(
+                &SOURCE_IF_UNMODIFIED_SINCE,
+                date.format(RFC1123_FMT).to_string(),
+            );
+        }
+
         builder
-            .with_azure_authorization(&credential, &self.config.account)
+            .with_azure_authorization(&credential, &self.config.account, &self.config.version)
             .send_retry(&self.config.retry_config)
             .await
             .map_err(|err| err.error(STORE, from.to_string()))?;
@@ -348,6 +593,22 @@ impl AzureClient {
         Ok(())
     }
 
+    /// Make a Get Container Properties request
+    /// <https://learn.microsoft.com/en-us/rest/api/storageservices/get-container-properties>
+    pub async fn container_properties(&self) -> Result<()> {
+        let credential = self.get_credential().await?;
+        let url = self.config.path_url(&Path::default());
+
+        self.client
+            .request(Method::HEAD, url)
+            .query(&[("restype", "container")])
+            .with_azure_authorization(&credential, &self.config.account, &self.config.version)
+            .send_retry(&self.config.retry_config)
+            .await
+            .map_err(|e| e.error(STORE, self.config.container.clone()))?;
+        Ok(())
+    }
+
     /// Make a Get User Delegation Key request
     /// <https://docs.microsoft.com/en-us/rest/api/storageservices/get-user-delegation-key>
     async fn get_user_delegation_key(
@@ -373,7 +634,7 @@ impl AzureClient {
             .request(Method::POST, url)
             .body(body)
             .query(&[("restype", "service"), ("comp", "userdelegationkey")])
-            .with_azure_authorization(&credential, &self.config.account)
+            .with_azure_authorization(&credential, &self.config.account, &self.config.version)
             .send_retry(&self.config.retry_config)
             .await
             .context(DelegationKeyRequestSnafu)?
@@ -421,6 +682,27 @@ impl AzureClient {
         }
     }
 
+    /// Create an [`AzureSigner`] for generating service SAS URLs that reference the
+    /// stored access policy `policy_id`
+    ///
+    /// Only the account key can sign such URLs, as user delegation SAS does not
+    /// support stored access policies
+    pub async fn policy_signer(&self, policy_id: &str) -> Result<AzureSigner> {
+        let credential = self.get_credential().await?;
+        match credential.as_deref() {
+            // The start and expiry are provided by the policy
+            Some(AzureCredential::AccessKey(key)) => {
+                let now = chrono::Utc::now();
+                let signer =
+                    AzureSigner::new(key.to_owned(), self.config.account.clone(), now, now, None);
+                Ok(signer.with_signed_identifier(policy_id.to_string()))
+            }
+            Some(AzureCredential::BearerToken(_)) => Err(Error::SASwithPolicyNotSupported.into()),
+            None => Err(Error::SASwithSkipSignature.into()),
+            _ => Err(Error::SASforSASNotSupported.into()),
+        }
+    }
+
     #[cfg(test)]
     pub async fn get_blob_tagging(&self, path: &Path) -> Result<Response> {
         let credential = self.get_credential().await?;
@@ -429,7 +711,7 @@ impl AzureClient {
             .client
             .request(Method::GET, url)
             .query(&[("comp", "tags")])
-            .with_azure_authorization(&credential, &self.config.account)
+            .with_azure_authorization(&credential, &self.config.account, &self.config.version)
             .send_retry(&self.config.retry_config)
             .await
             .context(GetRequestSnafu {
@@ -449,6 +731,14 @@ impl GetClient for AzureClient {
         version_header: Some(VERSION_HEADER),
     };
 
+    fn retry_config(&self) -> &RetryConfig {
+        &self.config.retry_config
+    }
+
+    fn hedge_config(&self) -> Option<&HedgeConfig> {
+        self.config.client_options.hedge_config()
+    }
+
     /// Make an Azure GET request
     /// <https://docs.microsoft.com/en-us/rest/api/storageservices/get-blob>
     /// <https://docs.microsoft.com/en-us/rest/api/storageservices/get-blob-properties>
@@ -480,7 +770,7 @@ impl GetClient for AzureClient {
 
         let response = builder
             .with_get_options(options)
-            .with_azure_authorization(&credential, &self.config.account)
+            .with_azure_authorization(&credential, &self.config.account, &self.config.version)
             .send_retry(&self.config.retry_config)
             .await
             .context(GetRequestSnafu {
@@ -503,6 +793,10 @@ impl GetClient for AzureClient {
 
 #[async_trait]
 impl ListClient for AzureClient {
+    fn list_deduplication(&self) -> Result<ListDeduplication> {
+        self.config.client_options.list_deduplication()
+    }
+
     /// Make an Azure List request <https://docs.microsoft.com/en-us/rest/api/storageservices/list-blobs>
     async fn list_request(
         &self,
@@ -536,7 +830,7 @@ impl ListClient for AzureClient {
             .client
             .request(Method::GET, url)
             .query(&query)
-            .with_azure_authorization(&credential, &self.config.account)
+            .with_azure_authorization(&credential, &self.config.account, &self.config.version)
             .send_retry(&self.config.retry_config)
             .await
             .context(ListRequestSnafu)?
@@ -548,6 +842,13 @@ impl ListClient for AzureClient {
             quick_xml::de::from_reader(response.reader()).context(InvalidListResponseSnafu)?;
         let token = response.next_marker.take();
 
+        if self.config.client_options.hide_directory_markers()? {
+            response
+                .blobs
+                .blobs
+                .retain(|b| !(b.properties.content_length == 0 && b.name.ends_with(DELIMITER)));
+        }
+
         Ok((to_list_result(response, prefix)?, token))
     }
 }
@@ -596,6 +897,28 @@ fn to_list_result(value: ListResultInternal, prefix: Option<&str>) -> Result<Lis
     })
 }
 
+/// Raw / internal response from find blobs by tags requests
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct FilterBlobsResult {
+    blobs: FilterBlobs,
+    next_marker: Option<String>,
+}
+
+/// Collection of blobs returned from find blobs by tags requests
+#[derive(Debug, Deserialize)]
+struct FilterBlobs {
+    #[serde(rename = "Blob", default)]
+    blobs: Vec<FilterBlob>,
+}
+
+/// A blob returned from find blobs by tags requests
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct FilterBlob {
+    name: String,
+}
+
 /// Collection of blobs and potentially shared prefixes returned from list requests.
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
 #[serde(rename_all = "PascalCase")]
@@ -629,12 +952,14 @@ impl TryFrom<Blob> for ObjectMeta {
     type Error = crate::Error;
 
     fn try_from(value: Blob) -> Result<Self> {
+        let checksum = value.properties.checksum();
         Ok(Self {
             location: Path::parse(value.name)?,
             last_modified: value.properties.last_modified,
             size: value.properties.content_length as usize,
             e_tag: value.properties.e_tag,
-            version: None, // For consistency with S3 and GCP which don't include this
+            version: value.version_id,
+            checksum,
         })
     }
 }
@@ -657,6 +982,20 @@ struct BlobProperties {
     pub content_language: Option<String>,
     #[serde(rename = "Etag")]
     pub e_tag: Option<String>,
+    #[serde(rename = "Content-MD5")]
+    pub content_md5: Option<String>,
+}
+
+impl BlobProperties {
+    /// Returns the [`ObjectChecksum`] of the blob's `Content-MD5`, if set
+    fn checksum(&self) -> Option<ObjectChecksum> {
+        let md5 = self.content_md5.as_deref().filter(|x| !x.is_empty())?;
+        let digest = BASE64_STANDARD.decode(md5).ok()?;
+        Some(ObjectChecksum {
+            algorithm: ChecksumAlgorithm::Md5,
+            digest,
+        })
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -45,7 +45,8 @@ use url::Url;
 
 use super::client::UserDelegationKey;
 
-static AZURE_VERSION: HeaderValue = HeaderValue::from_static("2023-11-03");
+/// The default storage service version sent in the `x-ms-version` header
+pub(crate) const AZURE_VERSION: &str = "2023-11-03";
 static VERSION: HeaderName = HeaderName::from_static("x-ms-version");
 pub(crate) static BLOB_TYPE: HeaderName = HeaderName::from_static("x-ms-blob-type");
 pub(crate) static DELETE_SNAPSHOTS: HeaderName = HeaderName::from_static("x-ms-delete-snapshots");
@@ -148,6 +149,7 @@ pub(crate) struct AzureSigner {
     end: DateTime<Utc>,
     account: String,
     delegation_key: Option<UserDelegationKey>,
+    signed_identifier: Option<String>,
 }
 
 impl AzureSigner {
@@ -164,6 +166,20 @@ impl AzureSigner {
             start,
             end,
             delegation_key,
+            signed_identifier: None,
+        }
+    }
+
+    /// Reference the [stored access policy] with the given `id`, which provides the
+    /// permissions, start and expiry of the signature in place of those of this signer
+    ///
+    /// Stored access policies are only supported by service SAS
+    ///
+    /// [stored access policy]: https://learn.microsoft.com/en-us/rest/api/storageservices/define-stored-access-policy
+    pub fn with_signed_identifier(self, id: String) -> Self {
+        Self {
+            signed_identifier: Some(id),
+            ..self
         }
     }
 
@@ -177,7 +193,14 @@ impl AzureSigner {
                 &self.end,
                 delegation_key,
             ),
-            None => string_to_sign_service_sas(url, method, &self.account, &self.start, &self.end),
+            None => string_to_sign_service_sas(
+                url,
+                method,
+                &self.account,
+                &self.start,
+                &self.end,
+                self.signed_identifier.as_deref(),
+            ),
         };
         let auth = hmac_sha256(&self.signing_key.0, str_to_sign);
         url.query_pairs_mut().extend_pairs(query_pairs);
@@ -187,16 +210,16 @@ impl AzureSigner {
     }
 }
 
-fn add_date_and_version_headers(request: &mut Request) {
+fn add_date_and_version_headers(request: &mut Request, version: &str) {
     // rfc2822 string should never contain illegal characters
     let date = Utc::now();
     let date_str = date.format(RFC1123_FMT).to_string();
     // we formatted the data string ourselves, so unwrapping should be fine
     let date_val = HeaderValue::from_str(&date_str).unwrap();
     request.headers_mut().insert(DATE, date_val);
-    request
-        .headers_mut()
-        .insert(&VERSION, AZURE_VERSION.clone());
+    // the version is validated when the store is built
+    let version = HeaderValue::from_str(version).expect("valid version");
+    request.headers_mut().insert(&VERSION, version);
 }
 
 /// Authorize a [`Request`] with an [`AzureAuthorizer`]
@@ -204,6 +227,7 @@ fn add_date_and_version_headers(request: &mut Request) {
 pub struct AzureAuthorizer<'a> {
     credential: &'a AzureCredential,
     account: &'a str,
+    version: &'a str,
 }
 
 impl<'a> AzureAuthorizer<'a> {
@@ -212,12 +236,20 @@ impl<'a> AzureAuthorizer<'a> {
         AzureAuthorizer {
             credential,
             account,
+            version: AZURE_VERSION,
         }
     }
 
+    /// Set the storage service version sent in the `x-ms-version` header
+    ///
+    /// Defaults to the version this crate has been tested against
+    pub fn with_version(self, version: &'a str) -> Self {
+        Self { version, ..self }
+    }
+
     /// Authorize `request`
     pub fn authorize(&self, request: &mut Request) {
-        add_date_and_version_headers(request);
+        add_date_and_version_headers(request, self.version);
 
         match self.credential {
             AzureCredential::AccessKey(key) => {
@@ -259,6 +291,7 @@ pub(crate) trait CredentialExt {
         self,
         credential: &Option<impl Deref<Target = AzureCredential>>,
         account: &str,
+        version: &str,
     ) -> Self;
 }
 
@@ -267,16 +300,19 @@ impl CredentialExt for RequestBuilder {
         self,
         credential: &Option<impl Deref<Target = AzureCredential>>,
         account: &str,
+        version: &str,
     ) -> Self {
         let (client, request) = self.build_split();
         let mut request = request.expect("request valid");
 
         match credential.as_deref() {
             Some(credential) => {
-                AzureAuthorizer::new(credential, account).authorize(&mut request);
+                AzureAuthorizer::new(credential, account)
+                    .with_version(version)
+                    .authorize(&mut request);
             }
             None => {
-                add_date_and_version_headers(&mut request);
+                add_date_and_version_headers(&mut request, version);
             }
         }
 
@@ -354,16 +390,32 @@ fn string_to_sign_sas(
 
 /// Create a string to be signed for authorization via [service sas].
 ///
+/// If `signed_identifier` is provided, the signature references the [stored access policy]
+/// with that id, and the permissions, start and expiry provided by the policy are omitted
+///
 /// [service sas]: https://learn.microsoft.com/en-us/rest/api/storageservices/create-service-sas#version-2020-12-06-and-later
+/// [stored access policy]: https://learn.microsoft.com/en-us/rest/api/storageservices/define-stored-access-policy
 fn string_to_sign_service_sas(
     u: &Url,
     method: &Method,
     account: &str,
     start: &DateTime<Utc>,
     end: &DateTime<Utc>,
+    signed_identifier: Option<&str>,
 ) -> (String, HashMap<&'static str, String>) {
-    let (signed_resource, signed_permissions, signed_start, signed_expiry, canonicalized_resource) =
-        string_to_sign_sas(u, method, account, start, end);
+    let (
+        signed_resource,
+        mut signed_permissions,
+        mut signed_start,
+        mut signed_expiry,
+        canonicalized_resource,
+    ) = string_to_sign_sas(u, method, account, start, end);
+
+    if signed_identifier.is_some() {
+        signed_permissions.clear();
+        signed_start.clear();
+        signed_expiry.clear();
+    }
 
     let string_to_sign = format!(
         "{}\n{}\n{}\n{}\n{}\n{}\n{}\n{}\n{}\n{}\n{}\n{}\n{}\n{}\n{}\n{}",
@@ -371,26 +423,33 @@ fn string_to_sign_service_sas(
         signed_start,
         signed_expiry,
         canonicalized_resource,
-        "",                               // signed identifier
-        "",                               // signed ip
-        "",                               // signed protocol
-        &AZURE_VERSION.to_str().unwrap(), // signed version
-        signed_resource,                  // signed resource
-        "",                               // signed snapshot time
-        "",                               // signed encryption scope
-        "",                               // rscc - response header: Cache-Control
-        "",                               // rscd - response header: Content-Disposition
-        "",                               // rsce - response header: Content-Encoding
-        "",                               // rscl - response header: Content-Language
-        "",                               // rsct - response header: Content-Type
+        signed_identifier.unwrap_or_default(), // signed identifier
+        "",                                    // signed ip
+        "",                                    // signed protocol
+        AZURE_VERSION,                         // signed version
+        signed_resource,                       // signed resource
+        "",                                    // signed snapshot time
+        "",                                    // signed encryption scope
+        "",                                    // rscc - response header: Cache-Control
+        "",                                    // rscd - response header: Content-Disposition
+        "",                                    // rsce - response header: Content-Encoding
+        "",                                    // rscl - response header: Content-Language
+        "",                                    // rsct - response header: Content-Type
     );
 
     let mut pairs = HashMap::new();
-    pairs.insert("sv", AZURE_VERSION.to_str().unwrap().to_string());
-    pairs.insert("sp", signed_permissions);
-    pairs.insert("st", signed_start);
-    pairs.insert("se", signed_expiry);
+    pairs.insert("sv", AZURE_VERSION.to_string());
     pairs.insert("sr", signed_resource);
+    match signed_identifier {
+        Some(id) => {
+            pairs.insert("si", id.to_string());
+        }
+        None => {
+            pairs.insert("sp", signed_permissions);
+            pairs.insert("st", signed_start);
+            pairs.insert("se", signed_expiry);
+        }
+    }
 
     (string_to_sign, pairs)
 }
@@ -426,7 +485,7 @@ fn string_to_sign_user_delegation_sas(
         "",                               // signed correlation id
         "",                               // signed ip
         "",                               // signed protocol
-        &AZURE_VERSION.to_str().unwrap(), // signed version
+        AZURE_VERSION, // signed version
         signed_resource,                  // signed resource
         "",                               // signed snapshot time
         "",                               // signed encryption scope
@@ -438,7 +497,7 @@ fn string_to_sign_user_delegation_sas(
     );
 
     let mut pairs = HashMap::new();
-    pairs.insert("sv", AZURE_VERSION.to_str().unwrap().to_string());
+    pairs.insert("sv", AZURE_VERSION.to_string());
     pairs.insert("sp", signed_permissions);
     pairs.insert("st", signed_start);
     pairs.insert("se", signed_expiry);
@@ -615,7 +674,7 @@ impl TokenProvider for ClientSecretOAuthProvider {
                 ("scope", AZURE_STORAGE_SCOPE),
                 ("grant_type", "client_credentials"),
             ])
-            .send_retry(retry)
+            .send_retry_credential(retry)
             .await
             .context(TokenRequestSnafu)?
             .json()
@@ -723,7 +782,7 @@ impl TokenProvider for ImdsManagedIdentityProvider {
         };
 
         let response: ImdsTokenResponse = builder
-            .send_retry(retry)
+            .send_retry_credential(retry)
             .await
             .context(TokenRequestSnafu)?
             .json()
@@ -797,7 +856,7 @@ impl TokenProvider for WorkloadIdentityOAuthProvider {
                 ("scope", AZURE_STORAGE_SCOPE),
                 ("grant_type", "client_credentials"),
             ])
-            .send_retry(retry)
+            .send_retry_credential(retry)
             .await
             .context(TokenRequestSnafu)?
             .json()
@@ -925,6 +984,10 @@ impl CredentialProvider for AzureCliCredential {
     async fn get_credential(&self) -> crate::Result<Arc<Self::Credential>> {
         Ok(self.cache.get_or_insert_with(|| self.fetch_token()).await?)
     }
+
+    async fn invalidate(&self) {
+        self.cache.clear().await
+    }
 }
 
 #[cfg(test)]
@@ -938,6 +1001,7 @@ mod tests {
     use super::*;
     use crate::azure::MicrosoftAzureBuilder;
     use crate::client::mock_server::MockServer;
+    use crate::client::{credential_retry_config, TokenCredentialProvider};
     use crate::{ObjectStore, Path};
 
     #[tokio::test]
@@ -997,6 +1061,109 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_managed_identity_retry() {
+        let server = MockServer::new();
+        let endpoint = server.url();
+
+        let retry = RetryConfig::default();
+        let capped = credential_retry_config(&retry);
+        assert_eq!(capped.max_retries, 3);
+        assert_eq!(capped.retry_timeout, Duration::from_secs(10));
+        assert_eq!(capped.backoff.max_backoff, Duration::from_secs(1));
+
+        let provider = TokenCredentialProvider::new(
+            ImdsManagedIdentityProvider::new(
+                None,
+                None,
+                None,
+                Some(format!("{endpoint}/metadata/identity/oauth2/token")),
+            ),
+            Client::new(),
+            retry,
+        );
+
+        // A transient error is retried
+        server.push(
+            Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .body(Body::empty())
+                .unwrap(),
+        );
+        server.push(Response::new(Body::from(
+            r#"{
+                "access_token": "TOKEN",
+                "expires_in": "3599",
+                "expires_on": "1506484173",
+                "not_before": "1506480273",
+                "resource": "https://management.azure.com/",
+                "token_type": "Bearer"
+            }"#,
+        )));
+
+        let credential = provider.get_credential().await.unwrap();
+        assert_eq!(
+            credential.as_ref(),
+            &AzureCredential::BearerToken("TOKEN".into())
+        );
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_sas_token() {
+        let server = MockServer::new();
+
+        let store = MicrosoftAzureBuilder::new()
+            .with_account("account")
+            .with_container_name("container")
+            .with_endpoint(server.url().to_string())
+            .with_allow_http(true)
+            .with_sas_token("?sv=2021-10-04&se=2999-01-01T00%3A00%3A00Z&sp=rcwl&sig=a%2Bb")
+            .build()
+            .unwrap();
+
+        server.push_fn(|req| {
+            assert_eq!(req.uri().path(), "/container/file");
+            let query = req.uri().query().unwrap();
+            assert!(query.contains("sv=2021-10-04"), "{query}");
+            assert!(query.contains("sp=rcwl"), "{query}");
+            assert!(query.contains("sig=a%2Bb"), "{query}");
+            assert!(req.headers().get(AUTHORIZATION).is_none());
+            Response::new(Body::empty())
+        });
+
+        store.delete(&Path::from("file")).await.unwrap();
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_azure_version() {
+        let server = MockServer::new();
+
+        let builder = MicrosoftAzureBuilder::new()
+            .with_account("account")
+            .with_container_name("container")
+            .with_endpoint(server.url().to_string())
+            .with_allow_http(true)
+            .with_skip_signature(true);
+
+        let store = builder.clone().build().unwrap();
+        server.push_fn(|req| {
+            assert_eq!(req.headers().get(&VERSION).unwrap(), AZURE_VERSION);
+            Response::new(Body::empty())
+        });
+        store.delete(&Path::from("file")).await.unwrap();
+
+        let store = builder.with_azure_version("2021-08-06").build().unwrap();
+        server.push_fn(|req| {
+            assert_eq!(req.headers().get(&VERSION).unwrap(), "2021-08-06");
+            Response::new(Body::empty())
+        });
+        store.delete(&Path::from("file")).await.unwrap();
+
+        server.shutdown().await;
+    }
+
     #[tokio::test]
     async fn test_workload_identity() {
         let server = MockServer::new();
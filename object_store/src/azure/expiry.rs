@@ -0,0 +1,122 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use crate::azure::credential::RFC1123_FMT;
+use chrono::{DateTime, Utc};
+use snafu::Snafu;
+use std::time::Duration;
+
+#[derive(Debug, Snafu)]
+enum Error {
+    #[snafu(display("Relative expiry must be at least one millisecond, got {:?}", duration))]
+    RelativeTooShort { duration: Duration },
+
+    #[snafu(display("Absolute expiry {} is in the past", time))]
+    AbsoluteInPast { time: DateTime<Utc> },
+}
+
+impl From<Error> for crate::Error {
+    fn from(source: Error) -> Self {
+        Self::Generic {
+            store: super::STORE,
+            source: Box::new(source),
+        }
+    }
+}
+
+/// When a blob should be automatically deleted, see [`MicrosoftAzure::set_expiry`]
+///
+/// <https://learn.microsoft.com/en-us/rest/api/storageservices/set-blob-expiry>
+///
+/// [`MicrosoftAzure::set_expiry`]: super::MicrosoftAzure::set_expiry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExpiryOption {
+    /// Expire the blob after the given duration from now
+    RelativeToNow(Duration),
+    /// Expire the blob after the given duration from its creation time
+    RelativeToCreation(Duration),
+    /// Expire the blob at the given time
+    Absolute(DateTime<Utc>),
+    /// Remove any existing expiry from the blob
+    NeverExpire,
+}
+
+impl ExpiryOption {
+    /// Returns the values of the `x-ms-expiry-option` and `x-ms-expiry-time` headers
+    ///
+    /// Relative expiries are expressed in milliseconds, and absolute expiries as RFC 1123 dates
+    pub(crate) fn headers(&self) -> crate::Result<(&'static str, Option<String>)> {
+        Ok(match self {
+            Self::RelativeToNow(d) => ("RelativeToNow", Some(relative_millis(d)?)),
+            Self::RelativeToCreation(d) => ("RelativeToCreation", Some(relative_millis(d)?)),
+            Self::Absolute(time) => {
+                if *time <= Utc::now() {
+                    return Err(Error::AbsoluteInPast { time: *time }.into());
+                }
+                ("Absolute", Some(time.format(RFC1123_FMT).to_string()))
+            }
+            Self::NeverExpire => ("NeverExpire", None),
+        })
+    }
+}
+
+fn relative_millis(duration: &Duration) -> crate::Result<String> {
+    match duration.as_millis() {
+        0 => Err(Error::RelativeTooShort {
+            duration: *duration,
+        }
+        .into()),
+        millis => Ok(millis.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_expiry_headers() {
+        let option = ExpiryOption::RelativeToNow(Duration::from_secs(60));
+        assert_eq!(
+            option.headers().unwrap(),
+            ("RelativeToNow", Some("60000".into()))
+        );
+
+        let option = ExpiryOption::RelativeToCreation(Duration::from_millis(1500));
+        let expected = ("RelativeToCreation", Some("1500".into()));
+        assert_eq!(option.headers().unwrap(), expected);
+
+        let time = Utc.with_ymd_and_hms(2999, 1, 2, 3, 4, 5).unwrap();
+        let option = ExpiryOption::Absolute(time);
+        let expected = ("Absolute", Some("Wed, 02 Jan 2999 03:04:05 GMT".into()));
+        assert_eq!(option.headers().unwrap(), expected);
+
+        assert_eq!(
+            ExpiryOption::NeverExpire.headers().unwrap(),
+            ("NeverExpire", None)
+        );
+
+        let option = ExpiryOption::RelativeToNow(Duration::from_micros(10));
+        let err = option.headers().unwrap_err().to_string();
+        assert!(err.contains("at least one millisecond"), "{err}");
+
+        let time = Utc.with_ymd_and_hms(2000, 1, 1, 0, 0, 0).unwrap();
+        let err = ExpiryOption::Absolute(time).headers().unwrap_err();
+        assert!(err.to_string().contains("in the past"), "{err}");
+    }
+}
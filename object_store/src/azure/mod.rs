@@ -30,12 +30,13 @@ use crate::{
     multipart::{MultiPartStore, PartId, PutPart, WriteMultiPart},
     path::Path,
     signer::Signer,
-    GetOptions, GetResult, ListResult, MultipartId, ObjectMeta, ObjectStore, PutOptions, PutResult,
-    Result,
+    Acl, CopyMode, CopyOptions, DeleteOptions, GetOptions, GetResult, ListPage, ListResult,
+    MultipartId, ObjectMeta, ObjectStore, PutOptions, PutResult, Result,
 };
 use async_trait::async_trait;
 use bytes::Bytes;
 use futures::stream::BoxStream;
+use futures::{StreamExt, TryStreamExt};
 use reqwest::Method;
 use std::fmt::Debug;
 use std::sync::Arc;
@@ -45,20 +46,33 @@ use url::Url;
 
 use crate::client::get::GetClientExt;
 use crate::client::list::ListClientExt;
+use crate::client::pagination::stream_paginated;
 use crate::client::CredentialProvider;
+use crate::util::unsupported_path;
 pub use credential::{authority_hosts, AzureAccessKey, AzureAuthorizer};
 
+mod acl;
 mod builder;
 mod client;
 mod credential;
+mod expiry;
+mod tier;
 
 /// [`CredentialProvider`] for [`MicrosoftAzure`]
 pub type AzureCredentialProvider = Arc<dyn CredentialProvider<Credential = AzureCredential>>;
 pub use builder::{AzureConfigKey, MicrosoftAzureBuilder};
 pub use credential::AzureCredential;
+pub use expiry::ExpiryOption;
+pub use tier::AccessTier;
 
 const STORE: &str = "MicrosoftAzure";
 
+/// The maximum length of a blob name in characters
+const MAX_BLOB_NAME_CHARS: usize = 1024;
+
+/// The maximum number of path segments in a blob name
+const MAX_BLOB_NAME_SEGMENTS: usize = 254;
+
 /// Interface for [Microsoft Azure Blob Storage](https://azure.microsoft.com/en-us/services/storage/blobs/).
 #[derive(Debug)]
 pub struct MicrosoftAzure {
@@ -71,6 +85,100 @@ impl MicrosoftAzure {
         &self.client.config().credentials
     }
 
+    /// Moves the blob at `location` to the provided [`AccessTier`]
+    ///
+    /// Rehydrating a blob from [`AccessTier::Archive`] may take several hours, during
+    /// which the blob cannot be read.
+    ///
+    /// [`AccessTier::Cold`] requires a storage service version of `2021-12-02` or later,
+    /// see [`MicrosoftAzureBuilder::with_azure_version`]
+    ///
+    /// <https://learn.microsoft.com/en-us/rest/api/storageservices/set-blob-tier>
+    pub async fn set_access_tier(&self, location: &Path, tier: AccessTier) -> Result<()> {
+        self.client.set_access_tier(location, tier).await
+    }
+
+    /// Copy a blob from one path to another, storing the copy in the provided [`AccessTier`]
+    ///
+    /// This performs the copy and tier transition in a single request, avoiding
+    /// the copy first being written to, and billed at, the default tier.
+    ///
+    /// <https://learn.microsoft.com/en-us/rest/api/storageservices/copy-blob>
+    pub async fn copy_with_access_tier(
+        &self,
+        from: &Path,
+        to: &Path,
+        tier: AccessTier,
+    ) -> Result<()> {
+        self.validate_path(to)?;
+        self.client
+            .copy_request(from, to, true, Some(tier), &CopyOptions::default())
+            .await
+    }
+
+    /// Sets when the blob at `location` will be automatically deleted
+    ///
+    /// This is only supported for accounts with a hierarchical namespace enabled,
+    /// and can be used to ensure temporary data is cleaned up
+    ///
+    /// <https://learn.microsoft.com/en-us/rest/api/storageservices/set-blob-expiry>
+    pub async fn set_expiry(&self, location: &Path, expiry: ExpiryOption) -> Result<()> {
+        self.client.set_expiry(location, expiry).await
+    }
+
+    /// Returns the [`AccessTier`] of the blob at `location`
+    ///
+    /// Returns `None` if the tier is not reported, for example for blobs in premium
+    /// storage accounts
+    pub async fn access_tier(&self, location: &Path) -> Result<Option<AccessTier>> {
+        self.client.get_access_tier(location).await
+    }
+
+    /// Find the blobs in the container whose index tags match `query`, for example
+    /// `"project" = 'apollo' AND "status" = 'final'`
+    ///
+    /// Tag names are enclosed in double quotes, and values in single quotes. Index tags
+    /// are set with [`PutOptions::tags`](crate::PutOptions::tags), and are only
+    /// searchable once indexed by the service, which may take a short while after upload.
+    ///
+    /// As the service only returns the names of matching blobs, the [`ObjectMeta`] of each
+    /// is fetched with a separate request
+    ///
+    /// <https://learn.microsoft.com/en-us/rest/api/storageservices/find-blobs-by-tags-container>
+    pub fn find_blobs_by_tags(&self, query: &str) -> BoxStream<'_, Result<ObjectMeta>> {
+        let client = self.client.as_ref();
+        stream_paginated(query.to_string(), move |query, marker| async move {
+            let (paths, marker) = client.find_blobs_by_tags(&query, marker.as_deref()).await?;
+            Ok((paths, query, marker))
+        })
+        .map_ok(|paths| futures::stream::iter(paths.into_iter().map(Ok)))
+        .try_flatten()
+        .map_ok(move |path| async move { self.head(&path).await })
+        .try_buffered(8)
+        .boxed()
+    }
+
+    /// Create a [Service SAS] URL for the resource at `path` that references the
+    /// [stored access policy] with the given `policy_id`
+    ///
+    /// Unlike [`Signer::signed_url`], the permissions, start and expiry of the URL are
+    /// provided by the policy, and so are omitted from the URL. This allows the URL to be
+    /// revoked, or its permissions changed, by modifying or deleting the policy on the
+    /// container. The policy must therefore specify both the permissions and expiry.
+    ///
+    /// This requires the store to be configured with an access key, as stored access
+    /// policies are not supported by user delegation SAS.
+    ///
+    /// [Service SAS]: https://learn.microsoft.com/en-us/rest/api/storageservices/create-service-sas
+    /// [stored access policy]: https://learn.microsoft.com/en-us/rest/api/storageservices/define-stored-access-policy
+    pub async fn signed_url_with_policy(&self, path: &Path, policy_id: &str) -> Result<Url> {
+        let mut url = self.path_url(path);
+        let signer = self.client.policy_signer(policy_id).await?;
+        // The permissions are provided by the policy, and so the method is ignored
+        signer.sign(&Method::GET, &mut url)?;
+        Ok(url)
+    }
+
     /// Create a full URL to the resource specified by `path` with this instance's configuration.
     fn path_url(&self, path: &Path) -> url::Url {
         self.client.config().path_url(path)
@@ -91,6 +199,7 @@ impl std::fmt::Display for MicrosoftAzure {
 #[async_trait]
 impl ObjectStore for MicrosoftAzure {
     async fn put_opts(&self, location: &Path, bytes: Bytes, opts: PutOptions) -> Result<PutResult> {
+        self.validate_path(location)?;
         self.client.put_blob(location, bytes, opts).await
     }
 
@@ -98,11 +207,19 @@ impl ObjectStore for MicrosoftAzure {
         &self,
         location: &Path,
     ) -> Result<(MultipartId, Box<dyn AsyncWrite + Unpin + Send>)> {
+        self.validate_path(location)?;
         let inner = AzureMultiPartUpload {
             client: Arc::clone(&self.client),
             location: location.to_owned(),
         };
-        Ok((String::new(), Box::new(WriteMultiPart::new(inner, 8))))
+        let config = self.client.config();
+        let writer = WriteMultiPart::new(inner, config.multipart_concurrency)
+            .with_part_size(config.multipart_block_size);
+        let writer = match self.client.upload_limiter() {
+            Some(limiter) => writer.with_upload_limiter(limiter.clone()),
+            None => writer,
+        };
+        Ok((String::new(), Box::new(writer)))
     }
 
     async fn abort_multipart(&self, _location: &Path, _multipart_id: &MultipartId) -> Result<()> {
@@ -116,7 +233,15 @@ impl ObjectStore for MicrosoftAzure {
     }
 
     async fn delete(&self, location: &Path) -> Result<()> {
-        self.client.delete_request(location, &()).await
+        self.client.delete_request(location, None, None).await
+    }
+
+    async fn delete_opts(&self, location: &Path, opts: DeleteOptions) -> Result<()> {
+        let version = opts.version.as_deref();
+        let if_match = opts.if_match.as_deref();
+        self.client
+            .delete_request(location, version, if_match)
+            .await
     }
 
     fn list(&self, prefix: Option<&Path>) -> BoxStream<'_, Result<ObjectMeta>> {
@@ -127,12 +252,66 @@ impl ObjectStore for MicrosoftAzure {
         self.client.list_with_delimiter(prefix).await
     }
 
+    fn list_with_delimiter_stream(
+        &self,
+        prefix: Option<&Path>,
+    ) -> BoxStream<'_, Result<ListResult>> {
+        self.client.list_paginated(prefix, true, None)
+    }
+
+    fn list_from_token(
+        &self,
+        prefix: Option<&Path>,
+        token: Option<&str>,
+    ) -> BoxStream<'_, Result<ListPage>> {
+        self.client.list_from_token(prefix, token)
+    }
+
+    async fn probe(&self) -> Result<()> {
+        self.client.container_properties().await
+    }
+
+    async fn get_acl(&self, location: &Path) -> Result<Acl> {
+        self.client.get_acl(location).await
+    }
+
     async fn copy(&self, from: &Path, to: &Path) -> Result<()> {
-        self.client.copy_request(from, to, true).await
+        self.validate_path(to)?;
+        self.client
+            .copy_request(from, to, true, None, &CopyOptions::default())
+            .await
+    }
+
+    async fn copy_opts(&self, from: &Path, to: &Path, opts: CopyOptions) -> Result<()> {
+        self.validate_path(to)?;
+        let overwrite = opts.mode == CopyMode::Overwrite;
+        self.client
+            .copy_request(from, to, overwrite, None, &opts)
+            .await
     }
 
     async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
-        self.client.copy_request(from, to, false).await
+        self.validate_path(to)?;
+        self.client
+            .copy_request(from, to, false, None, &CopyOptions::default())
+            .await
+    }
+
+    fn object_url(&self, location: &Path) -> Result<Url> {
+        Ok(self.path_url(location))
+    }
+
+    fn validate_path(&self, location: &Path) -> Result<()> {
+        // https://learn.microsoft.com/en-us/rest/api/storageservices/naming-and-referencing-containers--blobs--and-metadata#blob-names
+        if location.as_ref().chars().count() > MAX_BLOB_NAME_CHARS {
+            let reason = format!("blob name exceeds {MAX_BLOB_NAME_CHARS} characters");
+            return Err(unsupported_path(location, STORE, reason));
+        }
+        if location.parts().count() > MAX_BLOB_NAME_SEGMENTS {
+            let reason = format!("blob name exceeds {MAX_BLOB_NAME_SEGMENTS} path segments");
+            return Err(unsupported_path(location, STORE, reason));
+        }
+        Ok(())
     }
 }
 
@@ -265,6 +444,7 @@ mod tests {
         copy_if_not_exists(&integration).await;
         stream_get(&integration).await;
         put_opts(&integration, true).await;
+        delete_opts(&integration).await;
         multipart(&integration, &integration).await;
         signing(&integration).await;
 
@@ -308,6 +488,55 @@ mod tests {
         assert_eq!(data, loaded);
     }
 
+    #[tokio::test]
+    async fn azure_test_signed_url_with_policy() {
+        use base64::prelude::BASE64_STANDARD;
+        use base64::Engine;
+        use std::collections::HashMap;
+
+        let store = MicrosoftAzureBuilder::new()
+            .with_account("account")
+            .with_container_name("container")
+            .with_access_key(BASE64_STANDARD.encode("secret"))
+            .build()
+            .unwrap();
+
+        let url = store
+            .signed_url_with_policy(&Path::from("file"), "policy-1")
+            .await
+            .unwrap();
+        assert_eq!(url.path(), "/container/file");
+
+        let query: HashMap<_, _> = url.query_pairs().into_owned().collect();
+        assert_eq!(query["si"], "policy-1");
+        assert_eq!(query["sr"], "b");
+        // Provided by the policy
+        for key in ["sp", "st", "se"] {
+            assert!(!query.contains_key(key), "{key}");
+        }
+
+        // The signed identifier is part of the signature
+        let string_to_sign = format!(
+            "\n\n\n/blob/account/container/file\npolicy-1\n\n\n{}\nb\n\n\n\n\n\n\n",
+            query["sv"]
+        );
+        let expected = crate::util::hmac_sha256("secret", string_to_sign);
+        assert_eq!(query["sig"], BASE64_STANDARD.encode(expected));
+
+        // Stored access policies are not supported by user delegation SAS
+        let store = MicrosoftAzureBuilder::new()
+            .with_account("account")
+            .with_container_name("container")
+            .with_bearer_token_authorization("token")
+            .build()
+            .unwrap();
+        let err = store
+            .signed_url_with_policy(&Path::from("file"), "policy-1")
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("user delegation"), "{err}");
+    }
+
     #[test]
     fn azure_test_config_get_value() {
         let azure_client_id = "object_store:fake_access_key_id".to_string();
@@ -333,4 +562,613 @@ mod tests {
             azure_storage_token
         );
     }
+
+    #[tokio::test]
+    async fn azure_test_skip_signature() {
+        use crate::client::mock_server::MockServer;
+        use hyper::{Body, Response};
+
+        let server = MockServer::new();
+        // Skipping signatures takes precedence over configured credentials
+        let store = MicrosoftAzureBuilder::new()
+            .with_account("account")
+            .with_container_name("container")
+            .with_endpoint(server.url().to_string())
+            .with_allow_http(true)
+            .with_access_key("aGVsbG8=")
+            .with_skip_signature(true)
+            .build()
+            .unwrap();
+
+        server.push_fn(|req| {
+            assert!(req.headers().get("authorization").is_none());
+            Response::builder()
+                .header("etag", "\"1\"")
+                .header("last-modified", "Thu, 01 Jan 1970 00:00:00 GMT")
+                .body(Body::from("data"))
+                .unwrap()
+        });
+        let path = Path::from("file");
+        let data = store.get(&path).await.unwrap().bytes().await.unwrap();
+        assert_eq!(data.as_ref(), b"data");
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn azure_test_get_acl() {
+        use crate::client::mock_server::MockServer;
+        use crate::{AclGrant, AclGrantee, AclPermission};
+        use hyper::{Body, Response};
+
+        let server = MockServer::new();
+        let store = MicrosoftAzureBuilder::new()
+            .with_account("account")
+            .with_container_name("container")
+            .with_endpoint(server.url().to_string())
+            .with_allow_http(true)
+            .with_skip_signature(true)
+            .build()
+            .unwrap();
+        let path = Path::from("file");
+
+        server.push_fn(|req| {
+            assert_eq!(req.method(), "HEAD");
+            Response::builder()
+                .header("x-ms-owner", "owner-oid")
+                .header("x-ms-group", "group-oid")
+                .header("x-ms-permissions", "rw-r-----+")
+                .header(
+                    "x-ms-acl",
+                    "user::rw-,user:reader-oid:r--,group::r--,mask::r--,other::---,default:user::rwx",
+                )
+                .body(Body::empty())
+                .unwrap()
+        });
+        let acl = store.get_acl(&path).await.unwrap();
+        assert_eq!(acl.owner.as_deref(), Some("owner-oid"));
+        let user = |id: &str| AclGrantee::User(id.to_string());
+        assert_eq!(
+            acl.grants,
+            vec![
+                AclGrant::new(user("owner-oid"), AclPermission::Read),
+                AclGrant::new(user("owner-oid"), AclPermission::Write),
+                AclGrant::new(user("reader-oid"), AclPermission::Read),
+                AclGrant::new(
+                    AclGrantee::Group("group-oid".to_string()),
+                    AclPermission::Read
+                ),
+            ]
+        );
+
+        // Without x-ms-acl the permissions are used
+        server.push(
+            Response::builder()
+                .header("x-ms-owner", "owner-oid")
+                .header("x-ms-permissions", "rw-r--r--")
+                .body(Body::empty())
+                .unwrap(),
+        );
+        let acl = store.get_acl(&path).await.unwrap();
+        assert_eq!(acl.grants.len(), 3);
+        assert_eq!(acl.grants[2].grantee, AclGrantee::AuthenticatedUsers);
+
+        // Accounts without a hierarchical namespace return neither
+        server.push(Response::new(Body::empty()));
+        assert_eq!(store.get_acl(&path).await.unwrap(), Default::default());
+
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn azure_test_access_tier() {
+        use crate::client::mock_server::MockServer;
+        use hyper::{Body, Response};
+
+        let server = MockServer::new();
+        let builder = MicrosoftAzureBuilder::new()
+            .with_account("account")
+            .with_container_name("container")
+            .with_endpoint(server.url().to_string())
+            .with_allow_http(true)
+            .with_skip_signature(true)
+            .with_access_tier(AccessTier::Cold);
+
+        // Cold is not supported by versions prior to 2021-12-02
+        let err = builder.clone().with_azure_version("2021-08-06").build();
+        assert!(err.is_err());
+
+        let store = builder.build().unwrap();
+        let path = Path::from("file");
+
+        server.push_fn(|req| {
+            assert_eq!(req.headers().get("x-ms-access-tier").unwrap(), "Cold");
+            Response::builder()
+                .header("etag", "\"1\"")
+                .body(Body::empty())
+                .unwrap()
+        });
+        store.put(&path, "data".into()).await.unwrap();
+
+        server.push_fn(|req| {
+            assert_eq!(req.uri().query(), Some("comp=tier"));
+            assert_eq!(req.headers().get("x-ms-access-tier").unwrap(), "Cool");
+            Response::new(Body::empty())
+        });
+        store
+            .set_access_tier(&path, AccessTier::Cool)
+            .await
+            .unwrap();
+
+        server.push_fn(|req| {
+            assert_eq!(req.method(), "HEAD");
+            Response::builder()
+                .header("x-ms-access-tier", "Cold")
+                .body(Body::empty())
+                .unwrap()
+        });
+        let tier = store.access_tier(&path).await.unwrap();
+        assert_eq!(tier, Some(AccessTier::Cold));
+
+        server.push(Response::new(Body::empty()));
+        assert_eq!(store.access_tier(&path).await.unwrap(), None);
+
+        server.shutdown().await;
+
+        let store = MicrosoftAzureBuilder::new()
+            .with_account("account")
+            .with_container_name("container")
+            .with_access_key("")
+            .with_azure_version("2021-08-06")
+            .build()
+            .unwrap();
+        let err = store.set_access_tier(&path, AccessTier::Cold).await;
+        assert!(matches!(err, Err(crate::Error::NotSupported { .. })));
+    }
+
+    #[tokio::test]
+    async fn azure_test_set_expiry() {
+        use crate::client::mock_server::MockServer;
+        use hyper::{Body, Response};
+
+        let server = MockServer::new();
+        let store = MicrosoftAzureBuilder::new()
+            .with_account("account")
+            .with_container_name("container")
+            .with_endpoint(server.url().to_string())
+            .with_allow_http(true)
+            .with_skip_signature(true)
+            .build()
+            .unwrap();
+        let path = Path::from("scratch");
+
+        server.push_fn(|req| {
+            assert_eq!(req.method(), "PUT");
+            assert_eq!(req.uri().path(), "/container/scratch");
+            assert_eq!(req.uri().query(), Some("comp=expiry"));
+            let headers = req.headers();
+            assert_eq!(headers.get("x-ms-expiry-option").unwrap(), "RelativeToNow");
+            assert_eq!(headers.get("x-ms-expiry-time").unwrap(), "30000");
+            Response::new(Body::empty())
+        });
+        let expiry = ExpiryOption::RelativeToNow(Duration::from_secs(30));
+        store.set_expiry(&path, expiry).await.unwrap();
+
+        server.push_fn(|req| {
+            let headers = req.headers();
+            assert_eq!(headers.get("x-ms-expiry-option").unwrap(), "NeverExpire");
+            assert!(headers.get("x-ms-expiry-time").is_none());
+            Response::new(Body::empty())
+        });
+        store
+            .set_expiry(&path, ExpiryOption::NeverExpire)
+            .await
+            .unwrap();
+
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn azure_test_delete_version() {
+        use crate::client::mock_server::MockServer;
+        use hyper::{Body, Response};
+
+        let server = MockServer::new();
+        let store = MicrosoftAzureBuilder::new()
+            .with_account("account")
+            .with_container_name("container")
+            .with_endpoint(server.url().to_string())
+            .with_allow_http(true)
+            .with_skip_signature(true)
+            .build()
+            .unwrap();
+
+        server.push_fn(|req| {
+            assert_eq!(req.method(), "DELETE");
+            assert_eq!(req.uri().path(), "/container/file");
+            assert_eq!(req.uri().query(), Some("versionid=v1"));
+            assert!(req.headers().get("x-ms-delete-snapshots").is_none());
+            Response::new(Body::empty())
+        });
+        let options = DeleteOptions {
+            version: Some("v1".to_string()),
+            ..Default::default()
+        };
+        store
+            .delete_opts(&Path::from("file"), options)
+            .await
+            .unwrap();
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn azure_test_copy_with_access_tier() {
+        use crate::client::mock_server::MockServer;
+        use hyper::{Body, Response, StatusCode};
+
+        let server = MockServer::new();
+        let store = MicrosoftAzureBuilder::new()
+            .with_account("account")
+            .with_container_name("container")
+            .with_endpoint(server.url().to_string())
+            .with_allow_http(true)
+            .with_skip_signature(true)
+            .build()
+            .unwrap();
+
+        server.push_fn(|req| {
+            assert_eq!(req.method(), "PUT");
+            assert_eq!(req.uri().path(), "/container/to");
+            let headers = req.headers();
+            assert!(headers.get("x-ms-copy-source").is_some());
+            assert_eq!(headers.get("x-ms-access-tier").unwrap(), "Cool");
+            Response::builder()
+                .status(StatusCode::ACCEPTED)
+                .body(Body::empty())
+                .unwrap()
+        });
+
+        let from = Path::from("from");
+        let to = Path::from("to");
+        store
+            .copy_with_access_tier(&from, &to, AccessTier::Cool)
+            .await
+            .unwrap();
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn azure_test_get_version() {
+        use crate::client::mock_server::MockServer;
+        use futures::TryStreamExt;
+        use hyper::{Body, Response};
+
+        let server = MockServer::new();
+        let store = MicrosoftAzureBuilder::new()
+            .with_account("account")
+            .with_container_name("container")
+            .with_endpoint(server.url().to_string())
+            .with_allow_http(true)
+            .with_skip_signature(true)
+            .build()
+            .unwrap();
+
+        server.push(Response::new(Body::from(
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<EnumerationResults>
+    <Blobs>
+        <Blob>
+            <Name>file</Name>
+            <VersionId>2024-01-01T00:00:00.0000000Z</VersionId>
+            <Properties>
+                <Last-Modified>Mon, 01 Jan 2024 00:00:00 GMT</Last-Modified>
+                <Etag>0x1</Etag>
+                <Content-Length>3</Content-Length>
+                <Content-Type>text/plain</Content-Type>
+            </Properties>
+        </Blob>
+    </Blobs>
+    <NextMarker />
+</EnumerationResults>"#,
+        )));
+        let listed = store.list(None).try_collect::<Vec<_>>().await.unwrap();
+        assert_eq!(listed.len(), 1);
+        let version = listed[0].version.clone().unwrap();
+        assert_eq!(version, "2024-01-01T00:00:00.0000000Z");
+
+        server.push_fn(|req| {
+            assert_eq!(req.method(), "GET");
+            assert_eq!(req.uri().path(), "/container/file");
+            assert_eq!(
+                req.uri().query(),
+                Some("versionid=2024-01-01T00%3A00%3A00.0000000Z")
+            );
+            Response::builder()
+                .header("etag", "0x1")
+                .header("last-modified", "Mon, 01 Jan 2024 00:00:00 GMT")
+                .header("x-ms-version-id", "2024-01-01T00:00:00.0000000Z")
+                .body(Body::from("old"))
+                .unwrap()
+        });
+        let options = GetOptions {
+            version: Some(version.clone()),
+            ..Default::default()
+        };
+        let result = store.get_opts(&listed[0].location, options).await.unwrap();
+        assert_eq!(result.meta.version, Some(version));
+        assert_eq!(result.bytes().await.unwrap().as_ref(), b"old");
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn azure_test_checksum() {
+        use crate::client::mock_server::MockServer;
+        use crate::{ChecksumAlgorithm, ObjectChecksum};
+        use base64::prelude::BASE64_STANDARD;
+        use base64::Engine;
+        use futures::TryStreamExt;
+        use hyper::{Body, Response};
+
+        let server = MockServer::new();
+        let store = MicrosoftAzureBuilder::new()
+            .with_account("account")
+            .with_container_name("container")
+            .with_endpoint(server.url().to_string())
+            .with_allow_http(true)
+            .with_skip_signature(true)
+            .build()
+            .unwrap();
+
+        let md5 = "XrY7u+Ae7tCTyyK7j1rNww==";
+        let expected = Some(ObjectChecksum {
+            algorithm: ChecksumAlgorithm::Md5,
+            digest: BASE64_STANDARD.decode(md5).unwrap(),
+        });
+
+        server.push(Response::new(Body::from(format!(
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<EnumerationResults>
+    <Blobs>
+        <Blob>
+            <Name>file</Name>
+            <Properties>
+                <Last-Modified>Mon, 01 Jan 2024 00:00:00 GMT</Last-Modified>
+                <Etag>0x1</Etag>
+                <Content-Length>11</Content-Length>
+                <Content-Type>text/plain</Content-Type>
+                <Content-MD5>{md5}</Content-MD5>
+            </Properties>
+        </Blob>
+        <Blob>
+            <Name>other</Name>
+            <Properties>
+                <Last-Modified>Mon, 01 Jan 2024 00:00:00 GMT</Last-Modified>
+                <Etag>0x2</Etag>
+                <Content-Length>11</Content-Length>
+                <Content-Type>text/plain</Content-Type>
+                <Content-MD5 />
+            </Properties>
+        </Blob>
+    </Blobs>
+    <NextMarker />
+</EnumerationResults>"#
+        ))));
+        let listed = store.list(None).try_collect::<Vec<_>>().await.unwrap();
+        assert_eq!(listed[0].checksum, expected);
+        assert_eq!(listed[1].checksum, None);
+
+        server.push_fn(move |req| {
+            assert_eq!(req.method(), "HEAD");
+            Response::builder()
+                .header("etag", "0x1")
+                .header("last-modified", "Mon, 01 Jan 2024 00:00:00 GMT")
+                .header("content-length", "11")
+                .header("content-md5", md5)
+                .body(Body::empty())
+                .unwrap()
+        });
+        let meta = store.head(&Path::from("file")).await.unwrap();
+        assert_eq!(meta.checksum, expected);
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn azure_test_weak_etag() {
+        use crate::client::mock_server::MockServer;
+        use crate::{ClientOptions, PutMode, UpdateVersion};
+        use hyper::{Body, Response};
+
+        let server = MockServer::new();
+        let store = MicrosoftAzureBuilder::new()
+            .with_account("account")
+            .with_container_name("container")
+            .with_endpoint(server.url().to_string())
+            .with_client_options(ClientOptions::new().with_reject_weak_etags(true))
+            .with_allow_http(true)
+            .with_skip_signature(true)
+            .build()
+            .unwrap();
+
+        server.push_fn(|_| {
+            Response::builder()
+                .header("etag", " w/\"0x1\"")
+                .header("last-modified", "Mon, 01 Jan 2024 00:00:00 GMT")
+                .header("content-length", "5")
+                .body(Body::empty())
+                .unwrap()
+        });
+        let path = Path::from("file");
+        let meta = store.head(&path).await.unwrap();
+        assert_eq!(meta.e_tag.as_deref(), Some("W/\"0x1\""));
+
+        let mode = PutMode::Update(UpdateVersion {
+            e_tag: meta.e_tag,
+            version: None,
+        });
+        let err = store
+            .put_opts(&path, "hello".into(), mode.into())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, crate::Error::NotSupported { .. }), "{err}");
+        assert!(
+            err.to_string()
+                .contains("weak ETag W/\"0x1\" cannot be used for a conditional write"),
+            "{err}"
+        );
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn azure_test_find_blobs_by_tags() {
+        use crate::client::mock_server::MockServer;
+        use crate::{PutOptions, TagSet};
+        use futures::TryStreamExt;
+        use hyper::{Body, Request, Response};
+        use std::collections::HashMap;
+
+        let server = MockServer::new();
+        let store = MicrosoftAzureBuilder::new()
+            .with_account("account")
+            .with_container_name("container")
+            .with_endpoint(server.url().to_string())
+            .with_allow_http(true)
+            .with_skip_signature(true)
+            .build()
+            .unwrap();
+
+        let mut tags = TagSet::default();
+        tags.push("project", "apollo");
+        for name in ["a", "b"] {
+            server.push_fn(|req| {
+                assert_eq!(req.headers()["x-ms-tags"], "project=apollo");
+                Response::builder()
+                    .header("etag", "0x1")
+                    .body(Body::empty())
+                    .unwrap()
+            });
+            let opts = PutOptions {
+                tags: tags.clone(),
+                ..Default::default()
+            };
+            store
+                .put_opts(&Path::from(name), "hello".into(), opts)
+                .await
+                .unwrap();
+        }
+
+        // Pages and properties may be requested concurrently
+        let handler = |req: Request<Body>| {
+            let query: HashMap<_, _> =
+                url::form_urlencoded::parse(req.uri().query().unwrap_or_default().as_bytes())
+                    .into_owned()
+                    .collect();
+            if query.get("comp").map(String::as_str) != Some("blobs") {
+                return Response::builder()
+                    .header("etag", "0x1")
+                    .header("last-modified", "Mon, 01 Jan 2024 00:00:00 GMT")
+                    .header("content-length", "5")
+                    .body(Body::empty())
+                    .unwrap();
+            }
+            assert_eq!(query["where"], r#""project" = 'apollo'"#);
+            let (name, marker) = match query.get("marker") {
+                None => ("a", "<NextMarker>page2</NextMarker>"),
+                Some(m) => {
+                    assert_eq!(m, "page2");
+                    ("b", "<NextMarker />")
+                }
+            };
+            Response::new(Body::from(format!(
+                r#"<?xml version="1.0" encoding="utf-8"?>
+<EnumerationResults>
+    <Where>"project" = 'apollo'</Where>
+    <Blobs>
+        <Blob>
+            <Name>{name}</Name>
+            <ContainerName>container</ContainerName>
+            <Tags><TagSet><Tag><Key>project</Key><Value>apollo</Value></Tag></TagSet></Tags>
+        </Blob>
+    </Blobs>
+    {marker}
+</EnumerationResults>"#
+            )))
+        };
+        for _ in 0..4 {
+            server.push_fn(handler);
+        }
+
+        let found = store
+            .find_blobs_by_tags(r#""project" = 'apollo'"#)
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        let names: Vec<_> = found.iter().map(|m| m.location.as_ref()).collect();
+        assert_eq!(names, vec!["a", "b"]);
+        assert!(found.iter().all(|m| m.size == 5));
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn azure_test_put_version() {
+        use crate::client::mock_server::MockServer;
+        use hyper::{Body, Response};
+
+        let server = MockServer::new();
+        let store = MicrosoftAzureBuilder::new()
+            .with_account("account")
+            .with_container_name("container")
+            .with_endpoint(server.url().to_string())
+            .with_allow_http(true)
+            .with_skip_signature(true)
+            .build()
+            .unwrap();
+
+        let versioned = |version: &'static str| {
+            move |_| {
+                Response::builder()
+                    .header("etag", "0x1")
+                    .header("x-ms-version-id", version)
+                    .body(Body::empty())
+                    .unwrap()
+            }
+        };
+
+        let path = Path::from("file");
+        server.push_fn(versioned("2024-01-01T00:00:00.0000000Z"));
+        let result = store.put(&path, "hello".into()).await.unwrap();
+        assert_eq!(result.version.unwrap(), "2024-01-01T00:00:00.0000000Z");
+
+        server.push_fn(|_| Response::new(Body::empty()));
+        server.push_fn(versioned("2024-01-02T00:00:00.0000000Z"));
+        let id = store.create_multipart(&path).await.unwrap();
+        let part = store.put_part(&path, &id, 0, "hello".into()).await.unwrap();
+        let result = store.complete_multipart(&path, &id, vec![part]).await;
+        assert_eq!(
+            result.unwrap().version.unwrap(),
+            "2024-01-02T00:00:00.0000000Z"
+        );
+        server.shutdown().await;
+    }
+
+    #[test]
+    fn azure_test_validate_path() {
+        let store = MicrosoftAzureBuilder::new()
+            .with_account("account")
+            .with_container_name("container")
+            .with_access_key("")
+            .build()
+            .unwrap();
+
+        store.validate_path(&Path::from("foo/bar.parquet")).unwrap();
+        store.validate_path(&Path::from("foo/.bar")).unwrap();
+        store.validate_path(&Path::from("foo/bar.")).unwrap();
+
+        let path = Path::from_iter(std::iter::repeat("a").take(255));
+        let err = store.validate_path(&path).unwrap_err();
+        assert!(err.to_string().contains("254 path segments"), "{err}");
+
+        let err = store
+            .validate_path(&Path::from("a".repeat(1025)))
+            .unwrap_err();
+        assert!(err.to_string().contains("1024 characters"), "{err}");
+    }
 }
@@ -0,0 +1,124 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use crate::config::Parse;
+use std::str::FromStr;
+
+/// The access tier of a blob
+///
+/// <https://learn.microsoft.com/en-us/azure/storage/blobs/access-tiers-overview>
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessTier {
+    /// Optimized for frequently accessed data
+    Hot,
+    /// Optimized for infrequently accessed data stored for at least 30 days
+    Cool,
+    /// Optimized for rarely accessed data stored for at least 90 days
+    ///
+    /// Requires a storage service version of `2021-12-02` or later
+    Cold,
+    /// Offline tier for rarely accessed data stored for at least 180 days
+    Archive,
+}
+
+impl AccessTier {
+    /// Returns the value of the `x-ms-access-tier` header for this tier
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Hot => "Hot",
+            Self::Cool => "Cool",
+            Self::Cold => "Cold",
+            Self::Archive => "Archive",
+        }
+    }
+
+    /// Returns the earliest storage service version that supports this tier
+    fn min_version(&self) -> Option<&'static str> {
+        match self {
+            Self::Cold => Some("2021-12-02"),
+            Self::Hot | Self::Cool | Self::Archive => None,
+        }
+    }
+
+    /// Returns an error if this tier is not supported by the storage service `version`
+    pub(crate) fn check_version(&self, version: &str) -> crate::Result<()> {
+        match self.min_version() {
+            // Versions are dates of the form YYYY-MM-DD and so order lexicographically
+            Some(min) if version < min => Err(crate::Error::NotSupported {
+                source: format!(
+                    "access tier {self} requires storage service version {min} or later, got {version}"
+                )
+                .into(),
+            }),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl std::fmt::Display for AccessTier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for AccessTier {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "hot" => Ok(Self::Hot),
+            "cool" => Ok(Self::Cool),
+            "cold" => Ok(Self::Cold),
+            "archive" => Ok(Self::Archive),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Parse for AccessTier {
+    fn parse(v: &str) -> crate::Result<Self> {
+        v.parse().map_err(|_| crate::Error::Generic {
+            store: "Config",
+            source: format!("\"{v}\" is not a valid access tier").into(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_access_tier() {
+        for tier in [
+            AccessTier::Hot,
+            AccessTier::Cool,
+            AccessTier::Cold,
+            AccessTier::Archive,
+        ] {
+            assert_eq!(tier.as_str().parse::<AccessTier>().unwrap(), tier);
+            tier.check_version("2023-11-03").unwrap();
+        }
+        assert_eq!("cold".parse::<AccessTier>().unwrap(), AccessTier::Cold);
+        assert!("frozen".parse::<AccessTier>().is_err());
+
+        AccessTier::Cool.check_version("2019-12-12").unwrap();
+        AccessTier::Cold.check_version("2021-12-02").unwrap();
+        let err = AccessTier::Cold.check_version("2021-08-06").unwrap_err();
+        assert!(err.to_string().contains("2021-12-02"), "{err}");
+    }
+}
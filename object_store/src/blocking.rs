@@ -0,0 +1,232 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Utilities for performing blocking IO, for integration with synchronous code
+//!
+//! [`BlockingReader`] and [`BlockingWriter`] wrap the async [`BufReader`] and
+//! [`BufWriter`], driving them to completion on a tokio runtime with [`Handle::block_on`].
+//!
+//! # Re-entrancy
+//!
+//! Blocking on a future from within an async context is not permitted, and the
+//! methods of these types will **panic** if called from a thread that is running
+//! a tokio runtime, such as from within a task spawned on it. Instead they should be
+//! used from a thread not managed by the runtime, for example one spawned with
+//! [`std::thread::spawn`], or within [`tokio::task::spawn_blocking`].
+//!
+//! The runtime must also be able to make progress whilst the calling thread is blocked,
+//! in particular a `current_thread` runtime cannot drive IO or timers whilst another
+//! thread blocks on its [`Handle`], and so a `multi_thread` runtime should be used
+//! with stores that perform network IO.
+//!
+//! ```
+//! # use object_store::blocking::BlockingReader;
+//! # use object_store::memory::InMemory;
+//! # use object_store::path::Path;
+//! # use object_store::ObjectStore;
+//! # use std::io::Read;
+//! # use std::sync::Arc;
+//! # let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+//! let store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+//! let path = Path::from("data.csv");
+//! let handle = runtime.handle().clone();
+//!
+//! let meta = handle.block_on(async {
+//!     store.put(&path, "a,b\n1,2\n".into()).await.unwrap();
+//!     store.head(&path).await.unwrap()
+//! });
+//!
+//! // Pass the reader to a synchronous parser
+//! let mut reader = BlockingReader::new(handle, store, &meta);
+//! let mut out = String::new();
+//! reader.read_to_string(&mut out).unwrap();
+//! assert_eq!(out, "a,b\n1,2\n");
+//! ```
+//!
+//! [`BufReader`]: crate::buffered::BufReader
+//! [`BufWriter`]: crate::buffered::BufWriter
+
+use crate::buffered::{BufReader, BufWriter};
+use crate::path::Path;
+use crate::{ObjectMeta, ObjectStore};
+use std::io::{BufRead, Read, Seek, SeekFrom, Write};
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use tokio::runtime::Handle;
+
+/// A blocking reader implementing [`Read`], [`BufRead`] and [`Seek`]
+///
+/// See the [module level documentation](self) for the re-entrancy caveats
+#[derive(Debug)]
+pub struct BlockingReader {
+    handle: Handle,
+    inner: BufReader,
+}
+
+impl BlockingReader {
+    /// Create a new [`BlockingReader`] from the provided [`ObjectMeta`] and [`ObjectStore`],
+    /// performing IO on the runtime of `handle`
+    pub fn new(handle: Handle, store: Arc<dyn ObjectStore>, meta: &ObjectMeta) -> Self {
+        Self::from_reader(handle, BufReader::new(store, meta))
+    }
+
+    /// Create a new [`BlockingReader`] from the provided [`ObjectMeta`], [`ObjectStore`],
+    /// and `capacity`, performing IO on the runtime of `handle`
+    pub fn with_capacity(
+        handle: Handle,
+        store: Arc<dyn ObjectStore>,
+        meta: &ObjectMeta,
+        capacity: usize,
+    ) -> Self {
+        Self::from_reader(handle, BufReader::with_capacity(store, meta, capacity))
+    }
+
+    /// Create a new [`BlockingReader`] from the provided [`BufReader`]
+    pub fn from_reader(handle: Handle, inner: BufReader) -> Self {
+        Self { handle, inner }
+    }
+
+    /// Returns the underlying [`BufReader`]
+    pub fn into_inner(self) -> BufReader {
+        self.inner
+    }
+}
+
+impl Read for BlockingReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.handle.block_on(self.inner.read(buf))
+    }
+}
+
+impl BufRead for BlockingReader {
+    fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+        self.handle.block_on(self.inner.fill_buf())
+    }
+
+    fn consume(&mut self, amt: usize) {
+        std::pin::Pin::new(&mut self.inner).consume(amt)
+    }
+}
+
+impl Seek for BlockingReader {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        self.handle.block_on(self.inner.seek(pos))
+    }
+}
+
+/// A blocking writer implementing [`Write`]
+///
+/// As with [`BufWriter`], the object is only written once [`BlockingWriter::shutdown`]
+/// is called, and dropping the writer without doing so will discard the written data.
+///
+/// See the [module level documentation](self) for the re-entrancy caveats
+#[derive(Debug)]
+pub struct BlockingWriter {
+    handle: Handle,
+    inner: BufWriter,
+}
+
+impl BlockingWriter {
+    /// Create a new [`BlockingWriter`] from the provided [`ObjectStore`] and [`Path`],
+    /// performing IO on the runtime of `handle`
+    pub fn new(handle: Handle, store: Arc<dyn ObjectStore>, path: Path) -> Self {
+        Self::from_writer(handle, BufWriter::new(store, path))
+    }
+
+    /// Create a new [`BlockingWriter`] from the provided [`ObjectStore`], [`Path`]
+    /// and `capacity`, performing IO on the runtime of `handle`
+    pub fn with_capacity(
+        handle: Handle,
+        store: Arc<dyn ObjectStore>,
+        path: Path,
+        capacity: usize,
+    ) -> Self {
+        Self::from_writer(handle, BufWriter::with_capacity(store, path, capacity))
+    }
+
+    /// Create a new [`BlockingWriter`] from the provided [`BufWriter`]
+    pub fn from_writer(handle: Handle, inner: BufWriter) -> Self {
+        Self { handle, inner }
+    }
+
+    /// Complete the write, uploading any buffered data
+    ///
+    /// The writer must not be written to after calling this method
+    pub fn shutdown(&mut self) -> std::io::Result<()> {
+        self.handle.block_on(self.inner.shutdown())
+    }
+}
+
+impl Write for BlockingWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.handle.block_on(self.inner.write(buf))
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.handle.block_on(self.inner.flush())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::InMemory;
+    use bytes::Bytes;
+
+    #[test]
+    fn test_blocking_read_write() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let handle = runtime.handle().clone();
+        let store = Arc::new(InMemory::new()) as Arc<dyn ObjectStore>;
+
+        let existent = Path::from("exists.txt");
+        let data: Bytes = b"12345678".iter().cycle().copied().take(4096).collect();
+        handle.block_on(store.put(&existent, data.clone())).unwrap();
+        let meta = handle.block_on(store.head(&existent)).unwrap();
+
+        let mut reader =
+            BlockingReader::with_capacity(handle.clone(), Arc::clone(&store), &meta, 100);
+        let mut out = Vec::new();
+        assert_eq!(reader.read_to_end(&mut out).unwrap(), data.len());
+        assert_eq!(out, data);
+
+        assert_eq!(reader.seek(SeekFrom::Start(1020)).unwrap(), 1020);
+        let mut buf = [0; 8];
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"56781234");
+
+        reader.seek(SeekFrom::End(-3)).unwrap();
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        assert_eq!(line, "678");
+
+        let written = Path::from("written.txt");
+        let mut writer =
+            BlockingWriter::with_capacity(handle.clone(), Arc::clone(&store), written.clone(), 30);
+        for _ in 0..10 {
+            writer.write_all(b"hello world\n").unwrap();
+        }
+        writer.flush().unwrap();
+        writer.shutdown().unwrap();
+
+        let result = handle.block_on(store.get(&written)).unwrap();
+        let bytes = handle.block_on(result.bytes()).unwrap();
+        assert_eq!(bytes, b"hello world\n".repeat(10));
+    }
+}
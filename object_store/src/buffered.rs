@@ -18,16 +18,20 @@
 //! Utilities for performing tokio-style buffered IO
 
 use crate::path::Path;
-use crate::{MultipartId, ObjectMeta, ObjectStore};
+use crate::{GetOptions, GetRange, MultipartId, ObjectMeta, ObjectStore};
 use bytes::Bytes;
 use futures::future::{BoxFuture, FutureExt};
-use futures::ready;
+use futures::stream::BoxStream;
+use futures::{ready, Stream, StreamExt};
 use std::cmp::Ordering;
+use std::future::Future;
 use std::io::{Error, ErrorKind, SeekFrom};
 use std::pin::Pin;
 use std::sync::Arc;
 use std::task::{Context, Poll};
-use tokio::io::{AsyncBufRead, AsyncRead, AsyncSeek, AsyncWrite, AsyncWriteExt, ReadBuf};
+use tokio::io::{
+    AsyncBufRead, AsyncRead, AsyncReadExt, AsyncSeek, AsyncWrite, AsyncWriteExt, ReadBuf,
+};
 
 /// The default buffer size used by [`BufReader`]
 pub const DEFAULT_BUFFER_SIZE: usize = 1024 * 1024;
@@ -242,7 +246,7 @@ enum BufWriterState {
 impl BufWriter {
     /// Create a new [`BufWriter`] from the provided [`ObjectStore`] and [`Path`]
     pub fn new(store: Arc<dyn ObjectStore>, path: Path) -> Self {
-        Self::with_capacity(store, path, 10 * 1024 * 1024)
+        Self::with_capacity(store, path, DEFAULT_WRITE_CAPACITY)
     }
 
     /// Create a new [`BufWriter`] from the provided [`ObjectStore`], [`Path`] and `capacity`
@@ -337,6 +341,296 @@ impl AsyncWrite for BufWriter {
     }
 }
 
+/// The default capacity used by [`BufWriter`] and [`put_reader`]
+pub(crate) const DEFAULT_WRITE_CAPACITY: usize = 10 * 1024 * 1024;
+
+/// Upload the contents of `reader` to `location`
+///
+/// See [`put_reader_with_capacity`], this uses a capacity of 10 MiB
+pub async fn put_reader<R>(
+    store: &dyn ObjectStore,
+    location: &Path,
+    reader: R,
+    len: Option<u64>,
+) -> crate::Result<()>
+where
+    R: AsyncRead + Unpin,
+{
+    put_reader_with_capacity(store, location, reader, len, DEFAULT_WRITE_CAPACITY).await
+}
+
+/// Upload the contents of `reader` to `location`, without buffering the entire payload
+///
+/// If `len` is known to be at most `capacity`, or if `reader` reaches EOF before yielding
+/// `capacity` bytes, the data is uploaded with a single [`ObjectStore::put`].
+///
+/// Otherwise, the data is streamed using [`ObjectStore::put_multipart`], buffering at most
+/// `capacity` bytes plus any buffering performed by the multipart upload itself. Should an
+/// error occur, the multipart upload is aborted with [`ObjectStore::abort_multipart`].
+///
+/// If `len` is provided and `reader` yields a different number of bytes, an error is returned
+/// and no object is written
+pub async fn put_reader_with_capacity<R>(
+    store: &dyn ObjectStore,
+    location: &Path,
+    mut reader: R,
+    len: Option<u64>,
+    capacity: usize,
+) -> crate::Result<()>
+where
+    R: AsyncRead + Unpin,
+{
+    let limit = match len {
+        Some(len) if len <= capacity as u64 => len as usize,
+        _ => capacity,
+    };
+
+    // Read one byte beyond the limit to detect if the reader is longer than expected
+    let mut buffer = Vec::with_capacity(limit.saturating_add(1));
+    (&mut reader)
+        .take(limit as u64 + 1)
+        .read_to_end(&mut buffer)
+        .await
+        .map_err(|e| from_io_error(e, "PutReader"))?;
+
+    let single = match len {
+        Some(len) => len <= capacity as u64,
+        None => buffer.len() < capacity,
+    };
+
+    if single {
+        check_length(len, buffer.len() as u64)?;
+        store.put(location, buffer.into()).await?;
+        return Ok(());
+    }
+
+    let (id, mut writer) = store.put_multipart(location).await?;
+    let upload = async {
+        writer.write_all(&buffer).await?;
+        let copied = tokio::io::copy(&mut reader, &mut writer).await?;
+        check_length(len, buffer.len() as u64 + copied)?;
+        writer.shutdown().await
+    };
+
+    match upload.await {
+        Ok(()) => Ok(()),
+        Err(e) => {
+            let _ = store.abort_multipart(location, &id).await;
+            Err(from_io_error(e, "PutReader"))
+        }
+    }
+}
+
+/// Upload the contents of `stream` to `location`, without buffering the entire payload
+///
+/// Incoming [`Bytes`] are accumulated into a buffer of `part_size` bytes, which is written
+/// to an [`ObjectStore::put_multipart`] upload once full. The upload in turn splits this
+/// data into parts according to the configuration of `store`, uploading them as they fill.
+///
+/// `stream` is not polled whilst a write is in progress, and so should part uploads fall
+/// behind, backpressure is propagated to the producer of `stream`.
+///
+/// If `stream` ends before yielding `part_size` bytes, the data is uploaded with a single
+/// [`ObjectStore::put`]. Should `stream` or the upload return an error, the multipart upload
+/// is aborted with [`ObjectStore::abort_multipart`] and the error returned.
+pub async fn put_multipart_stream<S>(
+    store: &dyn ObjectStore,
+    location: &Path,
+    stream: S,
+    part_size: usize,
+) -> crate::Result<()>
+where
+    S: Stream<Item = crate::Result<Bytes>>,
+{
+    futures::pin_mut!(stream);
+    let part_size = part_size.max(1);
+
+    let mut buffer = Vec::with_capacity(part_size);
+    while buffer.len() < part_size {
+        match stream.next().await.transpose()? {
+            Some(bytes) => buffer.extend_from_slice(&bytes),
+            None => {
+                store.put(location, buffer.into()).await?;
+                return Ok(());
+            }
+        }
+    }
+
+    let (id, mut writer) = store.put_multipart(location).await?;
+    let io_error = |e| from_io_error(e, "PutMultipartStream");
+    let upload = async {
+        loop {
+            if buffer.len() >= part_size {
+                writer.write_all(&buffer).await.map_err(io_error)?;
+                buffer.clear();
+            }
+            match stream.next().await.transpose()? {
+                Some(bytes) => buffer.extend_from_slice(&bytes),
+                None => break,
+            }
+        }
+        writer.write_all(&buffer).await.map_err(io_error)?;
+        writer.shutdown().await.map_err(io_error)
+    };
+
+    match upload.await {
+        Ok(()) => Ok(()),
+        Err(e) => {
+            let _ = store.abort_multipart(location, &id).await;
+            Err(e)
+        }
+    }
+}
+
+/// The maximum number of times [`get_into`] will resume a failed download
+const GET_INTO_MAX_RESUMES: usize = 3;
+
+/// Download the object at `location` into `writer`, returning the number of bytes written
+///
+/// Data is written to `writer` as it is received, without buffering the entire object
+/// in memory, and waiting for `writer` to accept each chunk before reading the next.
+///
+/// Should the response body fail part way through, the download will be resumed from the
+/// current offset using a range request, conditional on the [`ObjectMeta::e_tag`] to ensure
+/// the object has not been modified. If the store does not return an e-tag, or does not
+/// support range requests, the original error is returned.
+///
+/// `writer` is flushed but not shut down on completion
+pub async fn get_into<W>(
+    store: &dyn ObjectStore,
+    location: &Path,
+    mut writer: W,
+) -> crate::Result<u64>
+where
+    W: AsyncWrite + Unpin,
+{
+    let result = store.get(location).await?;
+    let e_tag = result.meta.e_tag.clone();
+    let mut stream = result.into_stream();
+
+    let mut written = 0;
+    let mut resumes = 0;
+    while let Some(next) = stream.next().await {
+        let bytes = match next {
+            Ok(bytes) => bytes,
+            Err(e) if resumes >= GET_INTO_MAX_RESUMES || e_tag.is_none() => return Err(e),
+            Err(e) => {
+                resumes += 1;
+                let options = GetOptions {
+                    if_match: e_tag.clone(),
+                    range: Some(GetRange::Offset(written)),
+                    ..Default::default()
+                };
+                stream = match store.get_opts(location, options).await {
+                    Ok(r) if r.range.start == written => r.into_stream(),
+                    _ => return Err(e),
+                };
+                continue;
+            }
+        };
+
+        writer
+            .write_all(&bytes)
+            .await
+            .map_err(|e| from_io_error(e, "GetInto"))?;
+        written += bytes.len();
+    }
+
+    writer
+        .flush()
+        .await
+        .map_err(|e| from_io_error(e, "GetInto"))?;
+    Ok(written as u64)
+}
+
+/// Download the objects at `paths` concurrently, writing each to the [`AsyncWrite`]
+/// returned by `sink`
+///
+/// At most `concurrency` objects are downloaded at once, each using [`get_into`]. The
+/// returned stream yields the path and result of each download as it completes, in
+/// completion order, along with the number of bytes written on success. A failure to
+/// download one object does not abort the remainder of the batch.
+///
+/// Dropping the returned stream cancels any in-flight downloads
+///
+/// ```
+/// # use object_store::buffered::download_objects;
+/// # use object_store::memory::InMemory;
+/// # use object_store::path::Path;
+/// # use futures::StreamExt;
+/// # async fn example() {
+/// let store = InMemory::new();
+/// let paths = vec![Path::from("a.parquet"), Path::from("b.parquet")];
+/// let results: Vec<_> = download_objects(&store, paths, 8, |_| async { Ok(tokio::io::sink()) })
+///     .collect()
+///     .await;
+/// for (path, result) in results {
+///     match result {
+///         Ok(bytes) => println!("downloaded {bytes} bytes from {path}"),
+///         Err(e) => println!("failed to download {path}: {e}"),
+///     }
+/// }
+/// # }
+/// ```
+pub fn download_objects<'a, I, F, Fut, W>(
+    store: &'a dyn ObjectStore,
+    paths: I,
+    concurrency: usize,
+    sink: F,
+) -> BoxStream<'a, (Path, crate::Result<u64>)>
+where
+    I: IntoIterator<Item = Path>,
+    I::IntoIter: Send + 'a,
+    F: Fn(&Path) -> Fut + Send + Sync + 'a,
+    Fut: Future<Output = crate::Result<W>> + Send + 'a,
+    W: AsyncWrite + Unpin + Send + 'a,
+{
+    let sink = Arc::new(sink);
+    futures::stream::iter(paths)
+        .map(move |path| {
+            let sink = Arc::clone(&sink);
+            async move {
+                let result = async {
+                    let writer = sink(&path).await?;
+                    get_into(store, &path, writer).await
+                };
+                let result = result.await;
+                (path, result)
+            }
+        })
+        .buffer_unordered(concurrency.max(1))
+        .boxed()
+}
+
+/// Returns an error if `expected` is provided and not equal to `actual`
+fn check_length(expected: Option<u64>, actual: u64) -> crate::Result<()> {
+    let msg = match expected {
+        Some(expected) if actual > expected => {
+            format!("Expected {expected} bytes from reader, got more")
+        }
+        Some(expected) if actual < expected => {
+            format!("Expected {expected} bytes from reader, got {actual}")
+        }
+        _ => return Ok(()),
+    };
+    Err(crate::Error::Generic {
+        store: "PutReader",
+        source: msg.into(),
+    })
+}
+
+/// Converts a [`std::io::Error`] to a [`crate::Error`], unwrapping it if it
+/// wraps a [`crate::Error`]
+fn from_io_error(e: Error, store: &'static str) -> crate::Error {
+    if e.get_ref().map(|e| e.is::<crate::Error>()).unwrap_or(false) {
+        return *e.into_inner().unwrap().downcast().unwrap();
+    }
+    crate::Error::Generic {
+        store,
+        source: Box::new(e),
+    }
+}
+
 /// Port of standardised function as requires Rust 1.66
 ///
 /// <https://github.com/rust-lang/rust/pull/87601/files#diff-b9390ee807a1dae3c3128dce36df56748ad8d23c6e361c0ebba4d744bf6efdb9R1533>
@@ -352,6 +646,10 @@ mod tests {
     use super::*;
     use crate::memory::InMemory;
     use crate::path::Path;
+    use crate::{GetResult, GetResultPayload, ListResult, PutOptions, PutResult, Result};
+    use async_trait::async_trait;
+    use futures::stream::BoxStream;
+    use std::sync::atomic::{AtomicUsize, Ordering::SeqCst};
     use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncSeekExt};
 
     #[tokio::test]
@@ -459,4 +757,268 @@ mod tests {
 
         assert_eq!(store.head(&path).await.unwrap().size, 40);
     }
+
+    #[tokio::test]
+    async fn test_put_reader() {
+        let store = InMemory::new();
+        let path = Path::from("file.txt");
+        let data: Vec<u8> = (0..100).collect();
+
+        for (size, len) in [
+            (0, None),
+            (25, None),
+            (30, None),
+            (31, None),
+            (100, None),
+            (25, Some(25)),
+            (30, Some(30)),
+            (100, Some(100)),
+        ] {
+            let reader = &data[..size];
+            put_reader_with_capacity(&store, &path, reader, len, 30)
+                .await
+                .unwrap();
+
+            let read = store.get(&path).await.unwrap().bytes().await.unwrap();
+            assert_eq!(&read, &data[..size], "{size} {len:?}");
+        }
+
+        let path = Path::from("mismatch.txt");
+        for (size, len, expected) in [
+            (25, 20, "Expected 20 bytes from reader, got more"),
+            (20, 25, "Expected 25 bytes from reader, got 20"),
+            (100, 50, "Expected 50 bytes from reader, got more"),
+            (50, 100, "Expected 100 bytes from reader, got 50"),
+        ] {
+            let reader = &data[..size];
+            let err = put_reader_with_capacity(&store, &path, reader, Some(len), 30)
+                .await
+                .unwrap_err()
+                .to_string();
+            assert!(err.contains(expected), "{err}");
+
+            let err = store.head(&path).await.unwrap_err();
+            assert!(matches!(err, crate::Error::NotFound { .. }), "{err}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_put_multipart_stream() {
+        let store = InMemory::new();
+        let path = Path::from("file.txt");
+        let data: Vec<u8> = (0..100).collect();
+
+        for (size, chunk) in [(0, 7), (20, 7), (30, 7), (31, 7), (100, 7), (100, 45)] {
+            // A lazy stream yielding `chunk` bytes at a time
+            let produced = AtomicUsize::new(0);
+            let stream = futures::stream::unfold(0, |offset| {
+                let produced = &produced;
+                let data = &data;
+                async move {
+                    (offset < size).then(|| {
+                        let end = (offset + chunk).min(size);
+                        produced.fetch_add(end - offset, SeqCst);
+                        (Ok(Bytes::copy_from_slice(&data[offset..end])), end)
+                    })
+                }
+            });
+
+            put_multipart_stream(&store, &path, stream, 30)
+                .await
+                .unwrap();
+            assert_eq!(produced.load(SeqCst), size);
+
+            let read = store.get(&path).await.unwrap().bytes().await.unwrap();
+            assert_eq!(&read, &data[..size], "{size} {chunk}");
+        }
+
+        let path = Path::from("error.txt");
+        for size in [20, 100] {
+            let stream = futures::stream::iter(data[..size].chunks(10))
+                .map(|c| Ok(Bytes::copy_from_slice(c)))
+                .chain(futures::stream::once(async {
+                    Err(crate::Error::Generic {
+                        store: "test",
+                        source: "stream failed".into(),
+                    })
+                }));
+
+            let err = put_multipart_stream(&store, &path, stream, 30)
+                .await
+                .unwrap_err();
+            assert!(err.to_string().contains("stream failed"), "{err}");
+
+            let err = store.head(&path).await.unwrap_err();
+            assert!(matches!(err, crate::Error::NotFound { .. }), "{err}");
+        }
+    }
+
+    /// An [`ObjectStore`] that fails the response body of the first `failures` requests
+    /// part way through
+    #[derive(Debug)]
+    struct FlakyStore {
+        inner: InMemory,
+        failures: AtomicUsize,
+    }
+
+    impl std::fmt::Display for FlakyStore {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "FlakyStore({})", self.inner)
+        }
+    }
+
+    #[async_trait]
+    impl ObjectStore for FlakyStore {
+        async fn put_opts(
+            &self,
+            location: &Path,
+            bytes: Bytes,
+            opts: PutOptions,
+        ) -> Result<PutResult> {
+            self.inner.put_opts(location, bytes, opts).await
+        }
+
+        async fn put_multipart(&self, location: &Path) -> Result<MultipartResult> {
+            self.inner.put_multipart(location).await
+        }
+
+        async fn abort_multipart(&self, location: &Path, id: &MultipartId) -> Result<()> {
+            self.inner.abort_multipart(location, id).await
+        }
+
+        async fn get_opts(&self, location: &Path, options: GetOptions) -> Result<GetResult> {
+            let result = self.inner.get_opts(location, options).await?;
+            let (meta, range) = (result.meta.clone(), result.range.clone());
+            let body = result.bytes().await?;
+            let fail = self
+                .failures
+                .fetch_update(SeqCst, SeqCst, |x| x.checked_sub(1))
+                .is_ok();
+
+            // Return the body in chunks, failing half way through if requested
+            let chunks: Vec<_> = body.chunks(1024).map(|c| body.slice_ref(c)).collect();
+            let take = if fail { chunks.len() / 2 } else { chunks.len() };
+            let stream = futures::stream::iter(chunks.into_iter().take(take).map(Ok));
+            let stream = match fail {
+                true => stream
+                    .chain(futures::stream::once(async {
+                        Err(crate::Error::Generic {
+                            store: "FlakyStore",
+                            source: "connection reset".into(),
+                        })
+                    }))
+                    .boxed(),
+                false => stream.boxed(),
+            };
+            Ok(GetResult {
+                payload: GetResultPayload::Stream(stream),
+                meta,
+                range,
+            })
+        }
+
+        async fn delete(&self, location: &Path) -> Result<()> {
+            self.inner.delete(location).await
+        }
+
+        fn list(&self, prefix: Option<&Path>) -> BoxStream<'_, Result<ObjectMeta>> {
+            self.inner.list(prefix)
+        }
+
+        async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
+            self.inner.list_with_delimiter(prefix).await
+        }
+
+        async fn copy(&self, from: &Path, to: &Path) -> Result<()> {
+            self.inner.copy(from, to).await
+        }
+
+        async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+            self.inner.copy_if_not_exists(from, to).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_into() {
+        let store = FlakyStore {
+            inner: InMemory::new(),
+            failures: AtomicUsize::new(0),
+        };
+        let path = Path::from("large.bin");
+        let data: Bytes = (0..10 * 1024 * 1024).map(|x| (x % 251) as u8).collect();
+        store.put(&path, data.clone()).await.unwrap();
+
+        for failures in [0, 1, GET_INTO_MAX_RESUMES] {
+            store.failures.store(failures, SeqCst);
+
+            let dir = tempfile::tempdir().unwrap();
+            let file_path = dir.path().join("large.bin");
+            let file = tokio::fs::File::create(&file_path).await.unwrap();
+
+            let written = get_into(&store, &path, file).await.unwrap();
+            assert_eq!(written, data.len() as u64);
+
+            let read = tokio::fs::read(&file_path).await.unwrap();
+            assert_eq!(read.len(), data.len());
+            assert!(read == data, "content mismatch with {failures} failures");
+        }
+
+        // Exceeding the maximum number of resumes should return the error
+        store.failures.store(GET_INTO_MAX_RESUMES + 1, SeqCst);
+        let mut out = Vec::new();
+        let err = get_into(&store, &path, &mut out).await.unwrap_err();
+        assert!(err.to_string().contains("connection reset"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_download_objects() {
+        let store = InMemory::new();
+        let mut paths: Vec<_> = (0..20).map(|x| Path::from(format!("{x}.bin"))).collect();
+        for (idx, path) in paths.iter().enumerate() {
+            store
+                .put(path, vec![idx as u8; idx * 100].into())
+                .await
+                .unwrap();
+        }
+        paths.push(Path::from("missing.bin"));
+        paths.push(Path::from("unwritable.bin"));
+
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().to_path_buf();
+        let mut results: Vec<_> = download_objects(&store, paths, 4, move |path| {
+            let file_path = root.join(path.as_ref());
+            let unwritable = path.as_ref() == "unwritable.bin";
+            async move {
+                if unwritable {
+                    return Err(crate::Error::NotImplemented);
+                }
+                Ok(tokio::fs::File::create(file_path).await.unwrap())
+            }
+        })
+        .collect()
+        .await;
+        results.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+        assert_eq!(results.len(), 22);
+
+        for (path, result) in results {
+            match path.as_ref() {
+                "missing.bin" => {
+                    let err = result.unwrap_err();
+                    assert!(matches!(err, crate::Error::NotFound { .. }), "{err}");
+                }
+                "unwritable.bin" => {
+                    let err = result.unwrap_err();
+                    assert!(matches!(err, crate::Error::NotImplemented), "{err}");
+                }
+                _ => {
+                    let idx: usize = path.as_ref().strip_suffix(".bin").unwrap().parse().unwrap();
+                    assert_eq!(result.unwrap(), idx as u64 * 100);
+                    let data = tokio::fs::read(dir.path().join(path.as_ref()))
+                        .await
+                        .unwrap();
+                    assert_eq!(data, vec![idx as u8; idx * 100]);
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,517 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! An object store wrapper caching the results of requests to the wrapped store
+//!
+//! [`CachingStore`] currently supports caching [`Error::NotFound`], see
+//! [`CachingStore::with_not_found_ttl`]. This makes repeatedly checking for an object
+//! that does not yet exist, for example when polling for a completion marker, cheap.
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt::{Display, Formatter};
+use std::future::Future;
+use std::ops::Range;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::stream::BoxStream;
+use parking_lot::Mutex;
+use tokio::io::AsyncWrite;
+use url::Url;
+
+use crate::path::Path;
+use crate::{
+    Acl, CopyOptions, DeleteOptions, Error, GetOptions, GetResult, ListPage, ListResult,
+    MultipartId, ObjectMeta, ObjectStore, PutOptions, PutResult, Result,
+};
+
+/// Store wrapper that caches the results of requests to an inner store
+///
+/// By default no results are cached, see [`CachingStore::with_not_found_ttl`].
+///
+/// ```
+/// # use object_store::cache::CachingStore;
+/// # use object_store::memory::InMemory;
+/// # use std::time::Duration;
+/// // Remember that an object does not exist for up to 5 seconds
+/// let store = CachingStore::new(InMemory::new()).with_not_found_ttl(Duration::from_secs(5));
+/// ```
+#[derive(Debug)]
+pub struct CachingStore<T: ObjectStore> {
+    inner: T,
+    not_found: Option<Arc<NotFoundCache>>,
+}
+
+impl<T: ObjectStore> CachingStore<T> {
+    /// Create a new [`CachingStore`] wrapping `inner`
+    pub fn new(inner: T) -> Self {
+        Self {
+            inner,
+            not_found: None,
+        }
+    }
+
+    /// Cache [`Error::NotFound`] returned by [`ObjectStore::head`] and the get methods
+    /// for `ttl`, returning it for the same location without a request to the inner store
+    ///
+    /// The cached result for a location is invalidated by a write to that location
+    /// through this store, i.e. a put, completed multipart upload, copy or rename.
+    /// Writes made by other clients are not observed until the cached result expires,
+    /// and so `ttl` should be short.
+    ///
+    /// Gets of a specific [`GetOptions::version`] are not cached.
+    pub fn with_not_found_ttl(mut self, ttl: Duration) -> Self {
+        self.not_found = Some(Arc::new(NotFoundCache::new(ttl)));
+        self
+    }
+
+    /// Performs a read of `location` with `f`, consulting the not found cache
+    async fn read<R, F>(&self, location: &Path, f: F) -> Result<R>
+    where
+        F: Future<Output = Result<R>> + Send,
+    {
+        let cache = match &self.not_found {
+            Some(cache) => cache,
+            None => return f.await,
+        };
+        if cache.contains(location) {
+            return Err(Error::NotFound {
+                path: location.to_string(),
+                source: Box::new(CachedNotFound),
+            });
+        }
+
+        let generation = cache.generation();
+        let r = f.await;
+        if let Err(Error::NotFound { .. }) = &r {
+            cache.insert(location, generation);
+        }
+        r
+    }
+
+    /// Invalidates any cached result for `location`, following a write to it
+    fn invalidate(&self, location: &Path) {
+        if let Some(cache) = &self.not_found {
+            cache.invalidate(location)
+        }
+    }
+}
+
+impl<T: ObjectStore> Display for CachingStore<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "CachingStore({})", self.inner)
+    }
+}
+
+#[async_trait]
+impl<T: ObjectStore> ObjectStore for CachingStore<T> {
+    async fn put(&self, location: &Path, bytes: Bytes) -> Result<PutResult> {
+        let r = self.inner.put(location, bytes).await;
+        self.invalidate(location);
+        r
+    }
+
+    async fn put_opts(&self, location: &Path, bytes: Bytes, opts: PutOptions) -> Result<PutResult> {
+        let r = self.inner.put_opts(location, bytes, opts).await;
+        self.invalidate(location);
+        r
+    }
+
+    async fn put_multipart(
+        &self,
+        location: &Path,
+    ) -> Result<(MultipartId, Box<dyn AsyncWrite + Unpin + Send>)> {
+        let (id, inner) = self.inner.put_multipart(location).await?;
+        let writer = match &self.not_found {
+            Some(cache) => Box::new(InvalidatingWriter {
+                inner,
+                cache: Arc::clone(cache),
+                location: location.clone(),
+            }),
+            None => inner,
+        };
+        Ok((id, writer))
+    }
+
+    async fn abort_multipart(&self, location: &Path, multipart_id: &MultipartId) -> Result<()> {
+        self.inner.abort_multipart(location, multipart_id).await
+    }
+
+    async fn get_opts(&self, location: &Path, options: GetOptions) -> Result<GetResult> {
+        if options.version.is_some() {
+            return self.inner.get_opts(location, options).await;
+        }
+        self.read(location, self.inner.get_opts(location, options))
+            .await
+    }
+
+    async fn get_range(&self, location: &Path, range: Range<usize>) -> Result<Bytes> {
+        self.read(location, self.inner.get_range(location, range))
+            .await
+    }
+
+    async fn get_ranges(&self, location: &Path, ranges: &[Range<usize>]) -> Result<Vec<Bytes>> {
+        self.read(location, self.inner.get_ranges(location, ranges))
+            .await
+    }
+
+    async fn head(&self, location: &Path) -> Result<ObjectMeta> {
+        self.read(location, self.inner.head(location)).await
+    }
+
+    async fn delete(&self, location: &Path) -> Result<()> {
+        self.inner.delete(location).await
+    }
+
+    async fn delete_opts(&self, location: &Path, opts: DeleteOptions) -> Result<()> {
+        self.inner.delete_opts(location, opts).await
+    }
+
+    fn delete_stream<'a>(
+        &'a self,
+        locations: BoxStream<'a, Result<Path>>,
+    ) -> BoxStream<'a, Result<Path>> {
+        self.inner.delete_stream(locations)
+    }
+
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'_, Result<ObjectMeta>> {
+        self.inner.list(prefix)
+    }
+
+    fn list_with_offset(
+        &self,
+        prefix: Option<&Path>,
+        offset: &Path,
+    ) -> BoxStream<'_, Result<ObjectMeta>> {
+        self.inner.list_with_offset(prefix, offset)
+    }
+
+    fn list_range(&self, start: &Path, end: &Path) -> BoxStream<'_, Result<ObjectMeta>> {
+        self.inner.list_range(start, end)
+    }
+
+    fn list_from_token(
+        &self,
+        prefix: Option<&Path>,
+        token: Option<&str>,
+    ) -> BoxStream<'_, Result<ListPage>> {
+        self.inner.list_from_token(prefix, token)
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
+        self.inner.list_with_delimiter(prefix).await
+    }
+
+    fn list_with_delimiter_stream(
+        &self,
+        prefix: Option<&Path>,
+    ) -> BoxStream<'_, Result<ListResult>> {
+        self.inner.list_with_delimiter_stream(prefix)
+    }
+
+    async fn copy(&self, from: &Path, to: &Path) -> Result<()> {
+        let r = self.inner.copy(from, to).await;
+        self.invalidate(to);
+        r
+    }
+
+    async fn copy_opts(&self, from: &Path, to: &Path, opts: CopyOptions) -> Result<()> {
+        let r = self.inner.copy_opts(from, to, opts).await;
+        self.invalidate(to);
+        r
+    }
+
+    async fn rename(&self, from: &Path, to: &Path) -> Result<()> {
+        let r = self.inner.rename(from, to).await;
+        self.invalidate(to);
+        r
+    }
+
+    async fn probe(&self) -> Result<()> {
+        self.inner.probe().await
+    }
+
+    async fn get_acl(&self, location: &Path) -> Result<Acl> {
+        self.inner.get_acl(location).await
+    }
+
+    async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        let r = self.inner.copy_if_not_exists(from, to).await;
+        self.invalidate(to);
+        r
+    }
+
+    async fn rename_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        let r = self.inner.rename_if_not_exists(from, to).await;
+        self.invalidate(to);
+        r
+    }
+
+    fn object_url(&self, location: &Path) -> Result<Url> {
+        self.inner.object_url(location)
+    }
+
+    fn validate_path(&self, location: &Path) -> Result<()> {
+        self.inner.validate_path(location)
+    }
+}
+
+/// The source of [`Error::NotFound`] returned from the cache of a [`CachingStore`]
+#[derive(Debug)]
+struct CachedNotFound;
+
+impl Display for CachedNotFound {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("object not found (cached)")
+    }
+}
+
+impl std::error::Error for CachedNotFound {}
+
+/// The locations for which [`Error::NotFound`] is cached
+#[derive(Debug)]
+struct NotFoundCache {
+    ttl: Duration,
+    state: Mutex<NotFoundState>,
+}
+
+#[derive(Debug, Default)]
+struct NotFoundState {
+    /// The expiry of the cached result for each location
+    entries: HashMap<Path, Instant>,
+    /// The inserted entries in expiry order, used to evict expired entries
+    expiry: VecDeque<(Path, Instant)>,
+    /// Incremented by each invalidation, so that the result of a read that raced
+    /// with a write is not cached
+    generation: u64,
+}
+
+impl NotFoundCache {
+    fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            state: Default::default(),
+        }
+    }
+
+    fn generation(&self) -> u64 {
+        self.state.lock().generation
+    }
+
+    fn contains(&self, location: &Path) -> bool {
+        let state = self.state.lock();
+        matches!(state.entries.get(location), Some(e) if *e > Instant::now())
+    }
+
+    /// Caches a not found result for `location`, provided there has been no
+    /// invalidation since `generation`
+    fn insert(&self, location: &Path, generation: u64) {
+        let mut state = self.state.lock();
+        if state.generation != generation {
+            return;
+        }
+
+        let now = Instant::now();
+        while matches!(state.expiry.front(), Some((_, e)) if *e <= now) {
+            let (path, expires) = state.expiry.pop_front().unwrap();
+            // The entry may have been invalidated and inserted again since
+            if state.entries.get(&path) == Some(&expires) {
+                state.entries.remove(&path);
+            }
+        }
+
+        let expires = now + self.ttl;
+        state.entries.insert(location.clone(), expires);
+        state.expiry.push_back((location.clone(), expires));
+    }
+
+    fn invalidate(&self, location: &Path) {
+        let mut state = self.state.lock();
+        state.generation += 1;
+        state.entries.remove(location);
+    }
+}
+
+/// An [`AsyncWrite`] invalidating the cached result for `location` once the
+/// upload is completed by [`AsyncWrite::poll_shutdown`]
+struct InvalidatingWriter {
+    inner: Box<dyn AsyncWrite + Unpin + Send>,
+    cache: Arc<NotFoundCache>,
+    location: Path,
+}
+
+impl AsyncWrite for InvalidatingWriter {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        let r = Pin::new(&mut self.inner).poll_shutdown(cx);
+        if r.is_ready() {
+            self.cache.invalidate(&self.location);
+        }
+        r
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::InMemory;
+    use crate::tests::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::io::AsyncWriteExt;
+
+    #[tokio::test]
+    async fn caching_store_test() {
+        let integration =
+            CachingStore::new(InMemory::new()).with_not_found_ttl(Duration::from_secs(60));
+
+        put_get_delete_list(&integration).await;
+        get_opts(&integration).await;
+        list_uses_directories_correctly(&integration).await;
+        list_with_delimiter(&integration).await;
+        rename_and_copy(&integration).await;
+        copy_if_not_exists(&integration).await;
+        stream_get(&integration).await;
+    }
+
+    /// An [`ObjectStore`] counting the number of [`ObjectStore::head`] requests
+    #[derive(Debug, Default)]
+    struct CountingStore {
+        inner: InMemory,
+        heads: AtomicUsize,
+    }
+
+    impl Display for CountingStore {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            write!(f, "CountingStore({})", self.inner)
+        }
+    }
+
+    #[async_trait]
+    impl ObjectStore for CountingStore {
+        async fn put_opts(
+            &self,
+            location: &Path,
+            bytes: Bytes,
+            opts: PutOptions,
+        ) -> Result<PutResult> {
+            self.inner.put_opts(location, bytes, opts).await
+        }
+
+        async fn put_multipart(
+            &self,
+            location: &Path,
+        ) -> Result<(MultipartId, Box<dyn AsyncWrite + Unpin + Send>)> {
+            self.inner.put_multipart(location).await
+        }
+
+        async fn abort_multipart(&self, location: &Path, id: &MultipartId) -> Result<()> {
+            self.inner.abort_multipart(location, id).await
+        }
+
+        async fn get_opts(&self, location: &Path, options: GetOptions) -> Result<GetResult> {
+            self.inner.get_opts(location, options).await
+        }
+
+        async fn head(&self, location: &Path) -> Result<ObjectMeta> {
+            self.heads.fetch_add(1, Ordering::SeqCst);
+            self.inner.head(location).await
+        }
+
+        async fn delete(&self, location: &Path) -> Result<()> {
+            self.inner.delete(location).await
+        }
+
+        fn list(&self, prefix: Option<&Path>) -> BoxStream<'_, Result<ObjectMeta>> {
+            self.inner.list(prefix)
+        }
+
+        async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
+            self.inner.list_with_delimiter(prefix).await
+        }
+
+        async fn copy(&self, from: &Path, to: &Path) -> Result<()> {
+            self.inner.copy(from, to).await
+        }
+
+        async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+            self.inner.copy_if_not_exists(from, to).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_not_found_cache() {
+        let ttl = Duration::from_millis(200);
+        let store = CachingStore::new(CountingStore::default()).with_not_found_ttl(ttl);
+        let heads = || store.inner.heads.load(Ordering::SeqCst);
+        let marker = Path::from("job/_SUCCESS");
+
+        // Repeated checks within the TTL only request the inner store once
+        for _ in 0..5 {
+            let err = store.head(&marker).await.unwrap_err();
+            assert!(matches!(err, Error::NotFound { .. }), "{err}");
+        }
+        assert_eq!(heads(), 1);
+
+        // Once expired, the inner store is requested again
+        tokio::time::sleep(ttl).await;
+        store.head(&marker).await.unwrap_err();
+        assert_eq!(heads(), 2);
+
+        // A put invalidates the cached result immediately
+        store.put(&marker, Bytes::new()).await.unwrap();
+        store.head(&marker).await.unwrap();
+        assert_eq!(heads(), 3);
+
+        // As does completing a multipart upload
+        let upload = Path::from("job/part-0");
+        store.head(&upload).await.unwrap_err();
+        let (_, mut writer) = store.put_multipart(&upload).await.unwrap();
+        writer.write_all(b"data").await.unwrap();
+        store.head(&upload).await.unwrap_err();
+        assert_eq!(heads(), 4);
+        writer.shutdown().await.unwrap();
+        store.head(&upload).await.unwrap();
+        assert_eq!(heads(), 5);
+
+        // Other locations are unaffected
+        let other = Path::from("other");
+        store.head(&other).await.unwrap_err();
+        store.head(&other).await.unwrap_err();
+        assert_eq!(heads(), 6);
+
+        // Without a TTL nothing is cached
+        let store = CachingStore::new(CountingStore::default());
+        store.head(&marker).await.unwrap_err();
+        store.head(&marker).await.unwrap_err();
+        assert_eq!(store.inner.heads.load(Ordering::SeqCst), 2);
+    }
+}
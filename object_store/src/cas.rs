@@ -0,0 +1,209 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Atomic compare-and-swap writes built on [`PutMode`]
+//!
+//! These allow applications to safely coordinate concurrent updates to an object,
+//! for example to maintain a consistent metadata file or build a lock. Support for
+//! conditional writes varies by store, see [`PutMode`] for details.
+
+use bytes::Bytes;
+use snafu::Snafu;
+
+use crate::path::Path;
+use crate::{ObjectStore, PutMode, PutResult, UpdateVersion};
+
+/// A specialized `Error` for [`cas_put`]
+#[derive(Debug, Snafu)]
+pub enum Error {
+    /// The object was modified, or already existed, since `expected_etag` was observed
+    #[snafu(display("Compare-and-swap conflict for object at location {path}: {source}"))]
+    Conflict {
+        /// The location of the object
+        path: String,
+        /// The underlying [`Error::Precondition`](crate::Error::Precondition) or
+        /// [`Error::AlreadyExists`](crate::Error::AlreadyExists)
+        source: crate::Error,
+    },
+
+    /// Any other error returned by the store
+    #[snafu(display("{source}"), context(false))]
+    Store {
+        /// The underlying error
+        source: crate::Error,
+    },
+}
+
+impl From<Error> for crate::Error {
+    fn from(value: Error) -> Self {
+        match value {
+            Error::Conflict { source, .. } | Error::Store { source } => source,
+        }
+    }
+}
+
+/// Write `bytes` to `location` only if the object is unchanged since it was observed
+/// with `expected_etag`
+///
+/// * If `expected_etag` is `None` the object is created, using [`PutMode::Create`], only
+///   if no object exists at `location`
+/// * Otherwise the object is overwritten, using [`PutMode::Update`], only if its current
+///   e-tag matches `expected_etag`
+///
+/// On success the [`PutResult`] contains the e-tag of the new object, to be provided as
+/// `expected_etag` to any subsequent update. Otherwise, if the precondition was not met,
+/// [`Error::Conflict`] is returned, and the caller should typically re-read the object
+/// and retry.
+///
+/// When updating, the object is first read with [`ObjectStore::head`] to determine its
+/// [`ObjectMeta::version`](crate::ObjectMeta::version), as some stores, such as
+/// [`GoogleCloudStorage`](crate::gcp::GoogleCloudStorage), perform conditional updates
+/// based on the version rather than the e-tag. The update remains atomic, as the write
+/// is still conditional on the object being unchanged since this read.
+///
+/// Stores that do not support the requested [`PutMode`] return [`Error::Store`], for
+/// example [`AmazonS3`](crate::aws::AmazonS3) requires
+/// [`S3ConditionalPut`](crate::aws::S3ConditionalPut) to be configured.
+pub async fn cas_put(
+    store: &dyn ObjectStore,
+    location: &Path,
+    expected_etag: Option<&str>,
+    bytes: Bytes,
+) -> Result<PutResult, Error> {
+    let mode = match expected_etag {
+        None => PutMode::Create,
+        Some(expected) => {
+            let meta = match store.head(location).await {
+                Ok(meta) => meta,
+                Err(source @ crate::Error::NotFound { .. }) => {
+                    return Err(Error::Conflict {
+                        path: location.to_string(),
+                        source,
+                    })
+                }
+                Err(source) => return Err(Error::Store { source }),
+            };
+            if meta.e_tag.as_deref() != Some(expected) {
+                let current = meta.e_tag.as_deref().unwrap_or("<none>");
+                return Err(Error::Conflict {
+                    path: location.to_string(),
+                    source: crate::Error::Precondition {
+                        path: location.to_string(),
+                        source: format!("{current} does not match {expected}").into(),
+                    },
+                });
+            }
+            PutMode::Update(UpdateVersion {
+                e_tag: Some(expected.to_string()),
+                version: meta.version,
+            })
+        }
+    };
+
+    match store.put_opts(location, bytes, mode.into()).await {
+        Ok(result) => Ok(result),
+        Err(source @ crate::Error::Precondition { .. })
+        | Err(source @ crate::Error::AlreadyExists { .. }) => Err(Error::Conflict {
+            path: location.to_string(),
+            source,
+        }),
+        Err(source) => Err(Error::Store { source }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::InMemory;
+
+    #[tokio::test]
+    async fn test_cas_put() {
+        let store = InMemory::new();
+        let path = Path::from("counter");
+
+        let created = cas_put(&store, &path, None, "0".into()).await.unwrap();
+        let err = cas_put(&store, &path, None, "1".into()).await.unwrap_err();
+        assert!(matches!(err, Error::Conflict { .. }), "{err}");
+
+        let e_tag = created.e_tag.unwrap();
+        let updated = cas_put(&store, &path, Some(&e_tag), "1".into())
+            .await
+            .unwrap();
+        assert_ne!(updated.e_tag.as_deref(), Some(e_tag.as_str()));
+
+        // The previous e-tag is now stale
+        let err = cas_put(&store, &path, Some(&e_tag), "2".into())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::Conflict { .. }), "{err}");
+        assert!(matches!(
+            crate::Error::from(err),
+            crate::Error::Precondition { .. }
+        ));
+
+        let read = store.get(&path).await.unwrap().bytes().await.unwrap();
+        assert_eq!(read.as_ref(), b"1");
+
+        // Updating a deleted object conflicts
+        store.delete(&path).await.unwrap();
+        let e_tag = updated.e_tag.unwrap();
+        let err = cas_put(&store, &path, Some(&e_tag), "2".into())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::Conflict { .. }), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_cas_put_concurrent() {
+        const WRITERS: usize = 8;
+        const INCREMENTS: usize = 10;
+
+        let store = InMemory::new();
+        let path = Path::from("counter");
+        cas_put(&store, &path, None, "0".into()).await.unwrap();
+
+        let increment = || async {
+            let mut conflicts = 0;
+            for _ in 0..INCREMENTS {
+                loop {
+                    let result = store.get(&path).await.unwrap();
+                    let e_tag = result.meta.e_tag.clone().unwrap();
+                    let bytes = result.bytes().await.unwrap();
+                    let value: usize = std::str::from_utf8(&bytes).unwrap().parse().unwrap();
+
+                    // Allow other writers to interleave between the read and the write
+                    tokio::task::yield_now().await;
+
+                    let new = Bytes::from((value + 1).to_string());
+                    match cas_put(&store, &path, Some(&e_tag), new).await {
+                        Ok(_) => break,
+                        Err(Error::Conflict { .. }) => conflicts += 1,
+                        Err(e) => panic!("{e}"),
+                    }
+                }
+            }
+            conflicts
+        };
+
+        let conflicts = futures::future::join_all((0..WRITERS).map(|_| increment())).await;
+        assert!(conflicts.iter().sum::<usize>() > 0);
+
+        let read = store.get(&path).await.unwrap().bytes().await.unwrap();
+        let expected = (WRITERS * INCREMENTS).to_string();
+        assert_eq!(read.as_ref(), expected.as_bytes());
+    }
+}
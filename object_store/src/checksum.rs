@@ -0,0 +1,63 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+/// The algorithm of an [`ObjectChecksum`]
+///
+/// Variants are ordered from cheapest to most expensive to compute
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChecksumAlgorithm {
+    /// CRC-32C (Castagnoli)
+    Crc32c,
+    /// CRC-32 (IEEE)
+    Crc32,
+    /// SHA-256
+    Sha256,
+    /// MD5
+    Md5,
+}
+
+impl ChecksumAlgorithm {
+    /// Returns the lowercase name of this algorithm, e.g. `crc32c`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Crc32c => "crc32c",
+            Self::Crc32 => "crc32",
+            Self::Sha256 => "sha256",
+            Self::Md5 => "md5",
+        }
+    }
+}
+
+/// A checksum of the contents of an object, as computed and stored by the store
+///
+/// Stores represent checksums differently, with the digest normalized to its raw
+/// bytes, CRCs being big-endian. Where a store provides several checksums the
+/// cheapest to compute is used, and checksums of multipart objects, which are
+/// checksums of the part checksums, are omitted as they can't be recomputed
+/// from the object contents.
+///
+/// * S3: `x-amz-checksum-*` if the object was uploaded with a checksum, returned by
+///   head requests, and get requests with [`GetOptions::verify_checksum`](crate::GetOptions::verify_checksum)
+/// * GCS: the `crc32c` or `md5` of `x-goog-hash`
+/// * Azure: `Content-MD5`, if set on upload
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ObjectChecksum {
+    /// The [`ChecksumAlgorithm`]
+    pub algorithm: ChecksumAlgorithm,
+    /// The raw bytes of the digest
+    pub digest: Vec<u8>,
+}
@@ -26,11 +26,12 @@ use bytes::{BufMut, Bytes, BytesMut};
 use futures::stream::BoxStream;
 use futures::StreamExt;
 use tokio::io::AsyncWrite;
+use url::Url;
 
 use crate::path::Path;
 use crate::{
-    GetOptions, GetResult, GetResultPayload, ListResult, ObjectMeta, ObjectStore, PutOptions,
-    PutResult,
+    Acl, CopyOptions, DeleteOptions, GetOptions, GetResult, GetResultPayload, ListPage, ListResult,
+    ObjectMeta, ObjectStore, PutOptions, PutResult,
 };
 use crate::{MultipartId, Result};
 
@@ -144,6 +145,10 @@ impl ObjectStore for ChunkedStore {
         self.inner.delete(location).await
     }
 
+    async fn delete_opts(&self, location: &Path, opts: DeleteOptions) -> Result<()> {
+        self.inner.delete_opts(location, opts).await
+    }
+
     fn list(&self, prefix: Option<&Path>) -> BoxStream<'_, Result<ObjectMeta>> {
         self.inner.list(prefix)
     }
@@ -156,17 +161,56 @@ impl ObjectStore for ChunkedStore {
         self.inner.list_with_offset(prefix, offset)
     }
 
+    fn list_range(&self, start: &Path, end: &Path) -> BoxStream<'_, Result<ObjectMeta>> {
+        self.inner.list_range(start, end)
+    }
+
+    fn list_from_token(
+        &self,
+        prefix: Option<&Path>,
+        token: Option<&str>,
+    ) -> BoxStream<'_, Result<ListPage>> {
+        self.inner.list_from_token(prefix, token)
+    }
+
     async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
         self.inner.list_with_delimiter(prefix).await
     }
 
+    fn list_with_delimiter_stream(
+        &self,
+        prefix: Option<&Path>,
+    ) -> BoxStream<'_, Result<ListResult>> {
+        self.inner.list_with_delimiter_stream(prefix)
+    }
+
     async fn copy(&self, from: &Path, to: &Path) -> Result<()> {
         self.inner.copy(from, to).await
     }
 
+    async fn copy_opts(&self, from: &Path, to: &Path, opts: CopyOptions) -> Result<()> {
+        self.inner.copy_opts(from, to, opts).await
+    }
+
+    async fn probe(&self) -> Result<()> {
+        self.inner.probe().await
+    }
+
+    async fn get_acl(&self, location: &Path) -> Result<Acl> {
+        self.inner.get_acl(location).await
+    }
+
     async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
         self.inner.copy_if_not_exists(from, to).await
     }
+
+    fn object_url(&self, location: &Path) -> Result<Url> {
+        self.inner.object_url(location)
+    }
+
+    fn validate_path(&self, location: &Path) -> Result<()> {
+        self.inner.validate_path(location)
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,243 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Verification of downloaded objects against checksums returned by the store
+
+use crate::path::Path;
+use crate::{ChecksumAlgorithm as Algorithm, ObjectChecksum, Result};
+use base64::prelude::BASE64_STANDARD;
+use base64::Engine;
+use bytes::Bytes;
+use futures::stream::BoxStream;
+use futures::StreamExt;
+use md5::{Digest, Md5};
+use reqwest::header::HeaderMap;
+use ring::digest;
+
+/// CRC-32 (IEEE) reversed polynomial
+const CRC32_POLY: u32 = 0xEDB88320;
+/// CRC-32C (Castagnoli) reversed polynomial
+const CRC32C_POLY: u32 = 0x82F63B78;
+
+impl Algorithm {
+    pub(crate) fn hasher(&self) -> Hasher {
+        match self {
+            Self::Crc32c => Hasher::Crc(Box::new(Crc32::new(CRC32C_POLY))),
+            Self::Crc32 => Hasher::Crc(Box::new(Crc32::new(CRC32_POLY))),
+            Self::Sha256 => Hasher::Sha256(digest::Context::new(&digest::SHA256)),
+            Self::Md5 => Hasher::Md5(Md5::new()),
+        }
+    }
+
+    /// Returns the digest of `data` computed with this algorithm
+    pub(crate) fn digest(&self, data: &[u8]) -> Vec<u8> {
+        let mut hasher = self.hasher();
+        hasher.update(data);
+        hasher.finish()
+    }
+}
+
+/// A table-driven implementation of a reflected 32-bit CRC
+pub(crate) struct Crc32 {
+    table: [u32; 256],
+    crc: u32,
+}
+
+impl Crc32 {
+    fn new(poly: u32) -> Self {
+        let mut table = [0; 256];
+        for (i, entry) in table.iter_mut().enumerate() {
+            let mut crc = i as u32;
+            for _ in 0..8 {
+                crc = match crc & 1 {
+                    1 => (crc >> 1) ^ poly,
+                    _ => crc >> 1,
+                };
+            }
+            *entry = crc;
+        }
+        Self { table, crc: !0 }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        for b in data {
+            self.crc = self.table[((self.crc ^ *b as u32) & 0xFF) as usize] ^ (self.crc >> 8);
+        }
+    }
+}
+
+/// An incremental hasher for an [`Algorithm`]
+pub(crate) enum Hasher {
+    Crc(Box<Crc32>),
+    Sha256(digest::Context),
+    Md5(Md5),
+}
+
+impl Hasher {
+    pub(crate) fn update(&mut self, data: &[u8]) {
+        match self {
+            Self::Crc(c) => c.update(data),
+            Self::Sha256(c) => c.update(data),
+            Self::Md5(c) => c.update(data),
+        }
+    }
+
+    pub(crate) fn finish(self) -> Vec<u8> {
+        match self {
+            // CRCs are transmitted as big-endian bytes
+            Self::Crc(c) => (!c.crc).to_be_bytes().to_vec(),
+            Self::Sha256(c) => c.finish().as_ref().to_vec(),
+            Self::Md5(c) => c.finalize().to_vec(),
+        }
+    }
+}
+
+impl ObjectChecksum {
+    /// Extracts the checksum of the entire object from the response `headers`, if any
+    ///
+    /// Supports `x-amz-checksum-*` (S3), `x-goog-hash` (GCS), and `Content-MD5`, or
+    /// `x-ms-blob-content-md5` for an Azure range request, preferring the cheapest
+    /// algorithm to compute when several are present
+    pub(crate) fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        let mut found = vec![];
+        let mut push = |algorithm, value: &str| {
+            // Checksums of multipart objects are checksums of the part checksums,
+            // suffixed with the number of parts, and cannot be verified
+            if value.contains('-') {
+                return;
+            }
+            if let Ok(digest) = BASE64_STANDARD.decode(value.trim()) {
+                found.push(Self { algorithm, digest })
+            }
+        };
+
+        for (name, algorithm) in [
+            ("x-amz-checksum-crc32c", Algorithm::Crc32c),
+            ("x-amz-checksum-crc32", Algorithm::Crc32),
+            ("x-amz-checksum-sha256", Algorithm::Sha256),
+            ("content-md5", Algorithm::Md5),
+            ("x-ms-blob-content-md5", Algorithm::Md5),
+        ] {
+            if let Some(v) = headers.get(name).and_then(|v| v.to_str().ok()) {
+                push(algorithm, v)
+            }
+        }
+
+        // e.g. `x-goog-hash: crc32c=n03x6A==,md5=Ojk9c3dhfxgoKVVHYwFbHQ==`
+        let goog = headers.get_all("x-goog-hash").iter();
+        for v in goog.filter_map(|v| v.to_str().ok()) {
+            for (k, v) in v.split(',').filter_map(|x| x.trim().split_once('=')) {
+                match k {
+                    "crc32c" => push(Algorithm::Crc32c, v),
+                    "md5" => push(Algorithm::Md5, v),
+                    _ => {}
+                }
+            }
+        }
+
+        found.into_iter().min_by_key(|x| x.algorithm as u8)
+    }
+
+    /// Wraps `stream` of `len` bytes, verifying it matches this checksum
+    ///
+    /// The error is returned in place of the final chunk of the stream
+    pub(crate) fn verify_stream(
+        self,
+        location: Path,
+        len: usize,
+        stream: BoxStream<'static, Result<Bytes>>,
+    ) -> BoxStream<'static, Result<Bytes>> {
+        let mut hasher = Some(self.algorithm.hasher());
+        let mut remaining = len;
+        stream
+            .map(move |r| {
+                let bytes = r?;
+                if let Some(h) = hasher.as_mut() {
+                    h.update(&bytes);
+                }
+                remaining = remaining.saturating_sub(bytes.len());
+                if remaining == 0 {
+                    if let Some(h) = hasher.take() {
+                        self.check(&location, h.finish())?;
+                    }
+                }
+                Ok(bytes)
+            })
+            .boxed()
+    }
+
+    /// Returns [`crate::Error::ChecksumMismatch`] if `actual` does not match this checksum
+    pub(crate) fn check(&self, location: &Path, actual: Vec<u8>) -> Result<()> {
+        if actual == self.digest {
+            return Ok(());
+        }
+        Err(crate::Error::ChecksumMismatch {
+            path: location.to_string(),
+            source: format!(
+                "expected {} {}, got {}",
+                self.algorithm.as_str(),
+                BASE64_STANDARD.encode(&self.digest),
+                BASE64_STANDARD.encode(actual)
+            )
+            .into(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_digests() {
+        let data = b"123456789";
+        let crc32 = Algorithm::Crc32.digest(data);
+        assert_eq!(crc32, 0xCBF43926_u32.to_be_bytes());
+        let crc32c = Algorithm::Crc32c.digest(data);
+        assert_eq!(crc32c, 0xE3069283_u32.to_be_bytes());
+
+        // Chunking does not affect the result
+        let mut hasher = Algorithm::Crc32c.hasher();
+        hasher.update(b"1234");
+        hasher.update(b"56789");
+        assert_eq!(hasher.finish(), crc32c);
+
+        let md5 = BASE64_STANDARD.encode(Algorithm::Md5.digest(b"hello world"));
+        assert_eq!(md5, "XrY7u+Ae7tCTyyK7j1rNww==");
+    }
+
+    #[test]
+    fn test_from_headers() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(ObjectChecksum::from_headers(&headers), None);
+
+        headers.insert("content-md5", "XrY7u+Ae7tCTyyK7j1rNww==".parse().unwrap());
+        let expected = ObjectChecksum::from_headers(&headers).unwrap();
+        assert_eq!(expected.algorithm, Algorithm::Md5);
+
+        let goog = "crc32c=yZRlqg==,md5=XrY7u+Ae7tCTyyK7j1rNww==";
+        headers.insert("x-goog-hash", goog.parse().unwrap());
+        let expected = ObjectChecksum::from_headers(&headers).unwrap();
+        assert_eq!(expected.algorithm, Algorithm::Crc32c);
+        assert_eq!(expected.digest, BASE64_STANDARD.decode("yZRlqg==").unwrap());
+
+        // Multipart checksums are ignored
+        let mut headers = HeaderMap::new();
+        headers.insert("x-amz-checksum-crc32c", "yZRlqg==-3".parse().unwrap());
+        assert_eq!(ObjectChecksum::from_headers(&headers), None);
+    }
+}
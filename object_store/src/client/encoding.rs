@@ -0,0 +1,310 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Decoding of response bodies according to their [`Content-Encoding`]
+//!
+//! [`Content-Encoding`]: https://developer.mozilla.org/en-US/docs/Web/HTTP/Headers/Content-Encoding
+
+use crate::Result;
+use bytes::Bytes;
+use futures::stream::BoxStream;
+use reqwest::header::{HeaderMap, CONTENT_ENCODING};
+use snafu::Snafu;
+
+#[derive(Debug, Snafu)]
+enum Error {
+    #[snafu(display("Unsupported Content-Encoding \"{encoding}\""))]
+    Unsupported { encoding: String },
+
+    #[snafu(display(
+        "Decoding Content-Encoding \"{encoding}\" requires the `compression` feature"
+    ))]
+    FeatureDisabled { encoding: &'static str },
+
+    #[snafu(display(
+        "Cannot decode a range of a body with Content-Encoding \"{encoding}\", as the range \
+         applies to the encoded body"
+    ))]
+    Range { encoding: &'static str },
+
+    #[cfg(feature = "compression")]
+    #[snafu(display("Error decoding body with Content-Encoding \"{encoding}\": {source}"))]
+    Decode {
+        encoding: &'static str,
+        source: std::io::Error,
+    },
+}
+
+impl From<Error> for crate::Error {
+    fn from(source: Error) -> Self {
+        match source {
+            #[cfg(feature = "compression")]
+            Error::Decode { .. } => Self::Generic {
+                store: "ContentEncoding",
+                source: Box::new(source),
+            },
+            _ => Self::NotSupported {
+                source: Box::new(source),
+            },
+        }
+    }
+}
+
+/// A [`Content-Encoding`] of a response body
+///
+/// [`Content-Encoding`]: https://developer.mozilla.org/en-US/docs/Web/HTTP/Headers/Content-Encoding
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ContentEncoding {
+    Gzip,
+    Deflate,
+    Brotli,
+    Zstd,
+}
+
+impl ContentEncoding {
+    /// Returns the [`ContentEncoding`] of a response with `headers`, or `None` if the
+    /// body is not encoded
+    ///
+    /// Returns an error if the encoding is not supported, or if `range` is true, as a
+    /// range of an encoded body cannot be decoded
+    pub(crate) fn from_headers(headers: &HeaderMap, range: bool) -> Result<Option<Self>> {
+        let value = match headers.get(CONTENT_ENCODING) {
+            Some(v) => v.to_str().unwrap_or_default().trim().to_ascii_lowercase(),
+            None => return Ok(None),
+        };
+        let encoding = match value.as_str() {
+            "" | "identity" => return Ok(None),
+            "gzip" | "x-gzip" => Self::Gzip,
+            "deflate" => Self::Deflate,
+            "br" => Self::Brotli,
+            "zstd" => Self::Zstd,
+            _ => return Err(Error::Unsupported { encoding: value }.into()),
+        };
+        if range {
+            return Err(Error::Range {
+                encoding: encoding.as_str(),
+            }
+            .into());
+        }
+        if cfg!(not(feature = "compression")) {
+            return Err(Error::FeatureDisabled {
+                encoding: encoding.as_str(),
+            }
+            .into());
+        }
+        Ok(Some(encoding))
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Gzip => "gzip",
+            Self::Deflate => "deflate",
+            Self::Brotli => "br",
+            Self::Zstd => "zstd",
+        }
+    }
+
+    /// Decodes `input`, an encoded body, returning a stream of the decoded bytes
+    #[cfg(feature = "compression")]
+    pub(crate) fn decode(
+        self,
+        input: BoxStream<'static, Result<Bytes>>,
+    ) -> BoxStream<'static, Result<Bytes>> {
+        use futures::StreamExt;
+
+        let decoder = Decoder::new(self);
+        futures::stream::unfold(
+            (input, Some(decoder)),
+            move |(mut input, decoder)| async move {
+                let mut decoder = decoder?;
+                let decode_err = |source| Error::Decode {
+                    encoding: self.as_str(),
+                    source,
+                };
+                loop {
+                    let decoded = match input.next().await {
+                        Some(Ok(bytes)) => decoder.write(&bytes).map_err(decode_err),
+                        Some(Err(e)) => return Some((Err(e), (input, None))),
+                        None => {
+                            let result = decoder.finish().map_err(|e| decode_err(e).into());
+                            return Some((result, (input, None)));
+                        }
+                    };
+                    match decoded {
+                        // Wait for enough input to produce output
+                        Ok(bytes) if bytes.is_empty() => continue,
+                        Ok(bytes) => return Some((Ok(bytes), (input, Some(decoder)))),
+                        Err(e) => return Some((Err(e.into()), (input, None))),
+                    }
+                }
+            },
+        )
+        .filter(|r| futures::future::ready(!matches!(r, Ok(b) if b.is_empty())))
+        .boxed()
+    }
+
+    #[cfg(not(feature = "compression"))]
+    pub(crate) fn decode(
+        self,
+        _input: BoxStream<'static, Result<Bytes>>,
+    ) -> BoxStream<'static, Result<Bytes>> {
+        unreachable!("rejected by ContentEncoding::from_headers")
+    }
+}
+
+/// An incremental decoder, writing the decoded output to an in-memory buffer
+#[cfg(feature = "compression")]
+enum Decoder {
+    Gzip(flate2::write::MultiGzDecoder<Vec<u8>>),
+    Deflate(flate2::write::ZlibDecoder<Vec<u8>>),
+    Brotli(Box<brotli::DecompressorWriter<Vec<u8>>>),
+    Zstd(zstd::stream::write::Decoder<'static, Vec<u8>>),
+}
+
+#[cfg(feature = "compression")]
+impl Decoder {
+    fn new(encoding: ContentEncoding) -> Self {
+        match encoding {
+            ContentEncoding::Gzip => Self::Gzip(flate2::write::MultiGzDecoder::new(vec![])),
+            ContentEncoding::Deflate => Self::Deflate(flate2::write::ZlibDecoder::new(vec![])),
+            ContentEncoding::Brotli => {
+                Self::Brotli(Box::new(brotli::DecompressorWriter::new(vec![], 4096)))
+            }
+            ContentEncoding::Zstd => Self::Zstd(
+                zstd::stream::write::Decoder::new(vec![]).expect("zstd decoder initialization"),
+            ),
+        }
+    }
+
+    /// Decodes `data`, returning any output produced
+    fn write(&mut self, data: &[u8]) -> std::io::Result<Bytes> {
+        use std::io::Write;
+
+        let out = match self {
+            Self::Gzip(d) => {
+                d.write_all(data)?;
+                d.get_mut()
+            }
+            Self::Deflate(d) => {
+                d.write_all(data)?;
+                d.get_mut()
+            }
+            Self::Brotli(d) => {
+                d.write_all(data)?;
+                d.get_mut()
+            }
+            Self::Zstd(d) => {
+                d.write_all(data)?;
+                d.get_mut()
+            }
+        };
+        Ok(std::mem::take(out).into())
+    }
+
+    /// Completes decoding, returning any remaining output
+    fn finish(self) -> std::io::Result<Bytes> {
+        use std::io::Write;
+
+        let out = match self {
+            Self::Gzip(d) => d.finish()?,
+            Self::Deflate(d) => d.finish()?,
+            Self::Brotli(mut d) => {
+                d.close()?;
+                std::mem::take(d.get_mut())
+            }
+            Self::Zstd(mut d) => {
+                d.flush()?;
+                d.into_inner()
+            }
+        };
+        Ok(out.into())
+    }
+}
+
+#[cfg(all(test, feature = "compression"))]
+mod tests {
+    use super::*;
+    use futures::{StreamExt, TryStreamExt};
+    use reqwest::header::HeaderValue;
+    use std::io::Write;
+
+    fn encode(encoding: ContentEncoding, data: &[u8]) -> Vec<u8> {
+        match encoding {
+            ContentEncoding::Gzip => {
+                let mut e = flate2::write::GzEncoder::new(vec![], Default::default());
+                e.write_all(data).unwrap();
+                e.finish().unwrap()
+            }
+            ContentEncoding::Deflate => {
+                let mut e = flate2::write::ZlibEncoder::new(vec![], Default::default());
+                e.write_all(data).unwrap();
+                e.finish().unwrap()
+            }
+            ContentEncoding::Brotli => {
+                let mut out = vec![];
+                let mut e = brotli::CompressorWriter::new(&mut out, 4096, 5, 22);
+                e.write_all(data).unwrap();
+                drop(e);
+                out
+            }
+            ContentEncoding::Zstd => zstd::encode_all(data, 0).unwrap(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_decode() {
+        let data: Vec<u8> = (0..100_000).map(|x| (x % 251) as u8).collect();
+        for encoding in [
+            ContentEncoding::Gzip,
+            ContentEncoding::Deflate,
+            ContentEncoding::Brotli,
+            ContentEncoding::Zstd,
+        ] {
+            let mut headers = HeaderMap::new();
+            let value = HeaderValue::from_static(encoding.as_str());
+            headers.insert(CONTENT_ENCODING, value);
+            let parsed = ContentEncoding::from_headers(&headers, false).unwrap();
+            assert_eq!(parsed, Some(encoding));
+            ContentEncoding::from_headers(&headers, true).unwrap_err();
+
+            let encoded = encode(encoding, &data);
+            let chunks: Vec<_> = encoded
+                .chunks(1000)
+                .map(|c| Ok(Bytes::copy_from_slice(c)))
+                .collect();
+            let decoded: Vec<_> = encoding
+                .decode(futures::stream::iter(chunks).boxed())
+                .try_collect()
+                .await
+                .unwrap();
+            assert_eq!(decoded.concat(), data, "{encoding:?}");
+
+            // Corrupt input is an error
+            let corrupt = Bytes::from(vec![0xFF; 100]);
+            let input = futures::stream::iter([Ok(corrupt)]).boxed();
+            let r: Result<Vec<_>> = encoding.decode(input).try_collect().await;
+            r.unwrap_err();
+        }
+
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_ENCODING, HeaderValue::from_static("identity"));
+        assert_eq!(ContentEncoding::from_headers(&headers, true).unwrap(), None);
+        headers.insert(CONTENT_ENCODING, HeaderValue::from_static("compress"));
+        let err = ContentEncoding::from_headers(&headers, false).unwrap_err();
+        assert!(matches!(err, crate::Error::NotSupported { .. }), "{err}");
+    }
+}
@@ -16,17 +16,27 @@
 // under the License.
 
 use std::ops::Range;
+use std::sync::Arc;
 
-use crate::client::header::{header_meta, HeaderConfig};
+use crate::client::encoding::ContentEncoding;
+use crate::client::header::{header_meta, header_meta_with_size, HeaderConfig};
+use crate::client::retry::is_retryable_transport_error;
+use crate::client::HedgeConfig;
 use crate::path::Path;
-use crate::{GetOptions, GetRange, GetResult, GetResultPayload, Result};
+use crate::{
+    GetOptions, GetRange, GetResult, GetResultPayload, ObjectChecksum, Result, RetryConfig,
+};
 use async_trait::async_trait;
-use futures::{StreamExt, TryStreamExt};
+use bytes::Bytes;
+use futures::future::{select, Either};
+use futures::stream::{BoxStream, FuturesUnordered};
+use futures::{FutureExt, StreamExt, TryStreamExt};
 use hyper::header::CONTENT_RANGE;
 use hyper::StatusCode;
 use reqwest::header::ToStrError;
 use reqwest::Response;
 use snafu::{ensure, OptionExt, ResultExt, Snafu};
+use tracing::info;
 
 /// A client that can perform a get request
 #[async_trait]
@@ -36,6 +46,12 @@ pub trait GetClient: Send + Sync + 'static {
     /// Configure the [`HeaderConfig`] for this client
     const HEADER_CONFIG: HeaderConfig;
 
+    /// The [`RetryConfig`] for this client
+    fn retry_config(&self) -> &RetryConfig;
+
+    /// The [`HedgeConfig`] for this client, if hedging is enabled
+    fn hedge_config(&self) -> Option<&HedgeConfig>;
+
     async fn get_request(&self, path: &Path, options: GetOptions) -> Result<Response>;
 }
 
@@ -46,20 +62,196 @@ pub trait GetClientExt {
 }
 
 #[async_trait]
-impl<T: GetClient> GetClientExt for T {
+impl<T: GetClient> GetClientExt for Arc<T> {
     async fn get_opts(&self, location: &Path, options: GetOptions) -> Result<GetResult> {
         let range = options.range.clone();
+        let verify = options.verify_checksum && range.is_none() && !options.head;
         if let Some(r) = range.as_ref() {
             r.is_valid().map_err(|e| crate::Error::Generic {
                 store: T::STORE,
                 source: Box::new(e),
             })?;
         }
-        let response = self.get_request(location, options).await?;
-        get_result::<T>(location, range, response).map_err(|e| crate::Error::Generic {
-            store: T::STORE,
-            source: Box::new(e),
+        let response = hedged_get_request(self.as_ref(), location, &options).await?;
+        let encoding = match options.decompress && !options.head {
+            true => ContentEncoding::from_headers(response.headers(), range.is_some())?,
+            false => None,
+        };
+        let checksum = verify
+            .then(|| ObjectChecksum::from_headers(response.headers()))
+            .flatten();
+        let mut result =
+            get_result::<T>(location, range, response).map_err(|e| crate::Error::Generic {
+                store: T::STORE,
+                source: Box::new(e),
+            })?;
+        options.check_max_size(location, &result.range)?;
+
+        if let GetResultPayload::Stream(body) = result.payload {
+            let state = ContinuationState {
+                client: Self::clone(self),
+                location: location.clone(),
+                e_tag: result.meta.e_tag.clone(),
+                remaining: result.range.clone(),
+                options,
+                body,
+                retries: 0,
+                done: false,
+            };
+            let stream = match checksum {
+                Some(c) => {
+                    c.verify_stream(location.clone(), result.range.len(), state.into_stream())
+                }
+                None => state.into_stream(),
+            };
+            let stream = match encoding {
+                Some(encoding) => encoding.decode(stream),
+                None => stream,
+            };
+            result.payload = GetResultPayload::Stream(stream);
+        }
+        Ok(result)
+    }
+}
+
+/// Performs [`GetClient::get_request`], issuing further requests according to
+/// [`GetClient::hedge_config`] should a response not be received in time
+///
+/// Returns the first response received, cancelling any other in-flight requests
+async fn hedged_get_request<T: GetClient>(
+    client: &T,
+    location: &Path,
+    options: &GetOptions,
+) -> Result<Response> {
+    let config = match client.hedge_config() {
+        Some(config) => *config,
+        None => return client.get_request(location, options.clone()).await,
+    };
+
+    let mut in_flight = FuturesUnordered::new();
+    in_flight.push(client.get_request(location, options.clone()));
+    let mut hedges = 0;
+    loop {
+        let hedge = match hedges < config.max_hedges {
+            true => tokio::time::sleep(config.delay).boxed(),
+            false => futures::future::pending().boxed(),
+        };
+        let next = match select(in_flight.next(), hedge).await {
+            Either::Left((next, _)) => next,
+            Either::Right(_) => None,
+        };
+        match next {
+            Some(Ok(response)) => return Ok(response),
+            // A `304 Not Modified` is definitive, there is no need to wait for, and
+            // transfer the body of, any other requests
+            Some(Err(e @ crate::Error::NotModified { .. })) => return Err(e),
+            // Errors are retried by the request itself, and so only returned
+            // once no other requests remain in flight
+            Some(Err(e)) if in_flight.is_empty() => return Err(e),
+            Some(Err(e)) => info!("Hedged request for {location} failed: {e}"),
+            None => {
+                hedges += 1;
+                info!(
+                    "Hedging request for {location}, hedge {hedges} of {}",
+                    config.max_hedges
+                );
+                in_flight.push(client.get_request(location, options.clone()));
+            }
+        }
+    }
+}
+
+/// The state of a response body that is continued with a range request
+/// should it fail part way through
+struct ContinuationState<T> {
+    client: Arc<T>,
+    location: Path,
+    /// The e-tag of the object, required to ensure it has not changed between requests
+    e_tag: Option<String>,
+    /// The range of the object not yet returned
+    remaining: Range<usize>,
+    options: GetOptions,
+    body: BoxStream<'static, Result<Bytes>>,
+    retries: usize,
+    done: bool,
+}
+
+impl<T: GetClient> ContinuationState<T> {
+    fn into_stream(self) -> BoxStream<'static, Result<Bytes>> {
+        futures::stream::unfold(self, |mut s| async move {
+            if s.done {
+                return None;
+            }
+            loop {
+                match s.body.next().await {
+                    Some(Ok(bytes)) => {
+                        s.remaining.start += bytes.len();
+                        return Some((Ok(bytes), s));
+                    }
+                    None => return None,
+                    Some(Err(e)) => {
+                        let body = match is_retryable_body_error(&e) {
+                            true => s.continuation().await,
+                            false => None,
+                        };
+                        match body {
+                            Some(body) => s.body = body,
+                            None => {
+                                s.done = true;
+                                return Some((Err(e), s));
+                            }
+                        }
+                    }
+                }
+            }
         })
+        .boxed()
+    }
+
+    /// Issue a range request for the remaining bytes, returning `None` if not possible
+    async fn continuation(&mut self) -> Option<BoxStream<'static, Result<Bytes>>> {
+        // Without an e-tag we cannot guarantee the object has not been modified
+        let e_tag = self.e_tag.clone()?;
+        if self.retries >= self.client.retry_config().max_retries || self.remaining.is_empty() {
+            return None;
+        }
+        self.retries += 1;
+
+        let range = GetRange::Bounded(self.remaining.clone());
+        let options = GetOptions {
+            if_match: Some(e_tag),
+            range: Some(range.clone()),
+            ..self.options.clone()
+        };
+        info!(
+            "Continuing get of {} from byte {} after stream error, retry {}",
+            self.location, self.remaining.start, self.retries
+        );
+
+        let response = self
+            .client
+            .get_request(&self.location, options)
+            .await
+            .ok()?;
+        match get_result::<T>(&self.location, Some(range), response).ok()? {
+            GetResult {
+                payload: GetResultPayload::Stream(body),
+                ..
+            } => Some(body),
+            _ => None,
+        }
+    }
+}
+
+/// Returns true if `e`, returned reading a response body, is a transient transport
+/// error that may not recur should the body be continued with a new request
+fn is_retryable_body_error(e: &crate::Error) -> bool {
+    match e {
+        crate::Error::Generic { source, .. } => source
+            .downcast_ref::<reqwest::Error>()
+            .map(|e| is_retryable_transport_error(e, true))
+            .unwrap_or_default(),
+        _ => false,
     }
 }
 
@@ -129,10 +321,8 @@ fn get_result<T: GetClient>(
     range: Option<GetRange>,
     response: Response,
 ) -> Result<GetResult, GetResultError> {
-    let mut meta = header_meta(location, response.headers(), T::HEADER_CONFIG)?;
-
     // ensure that we receive the range we asked for
-    let range = if let Some(expected) = range {
+    let (meta, range) = if let Some(expected) = range {
         ensure!(
             response.status() == StatusCode::PARTIAL_CONTENT,
             NotPartialSnafu
@@ -146,8 +336,10 @@ fn get_result<T: GetClient>(
         let value = ContentRange::from_str(value).context(ParseContentRangeSnafu { value })?;
         let actual = value.range;
 
-        // Update size to reflect full size of object (#5272)
-        meta.size = value.size;
+        // The size is that of the full object (#5272), the Content-Length
+        // being that of the range, if present at all
+        let meta =
+            header_meta_with_size(location, response.headers(), T::HEADER_CONFIG, value.size)?;
 
         let expected = expected.as_range(meta.size)?;
 
@@ -156,9 +348,11 @@ fn get_result<T: GetClient>(
             UnexpectedRangeSnafu { expected, actual }
         );
 
-        actual
+        (meta, actual)
     } else {
-        0..meta.size
+        let meta = header_meta(location, response.headers(), T::HEADER_CONFIG)?;
+        let range = 0..meta.size;
+        (meta, range)
     };
 
     let stream = response
@@ -179,10 +373,15 @@ fn get_result<T: GetClient>(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::client::mock_server::MockServer;
     use hyper::http;
     use hyper::http::header::*;
 
-    struct TestClient {}
+    struct TestClient {
+        url: String,
+        retry_config: RetryConfig,
+        hedge_config: Option<HedgeConfig>,
+    }
 
     #[async_trait]
     impl GetClient for TestClient {
@@ -194,8 +393,36 @@ mod tests {
             version_header: None,
         };
 
-        async fn get_request(&self, _: &Path, _: GetOptions) -> Result<Response> {
-            unimplemented!()
+        fn retry_config(&self) -> &RetryConfig {
+            &self.retry_config
+        }
+
+        fn hedge_config(&self) -> Option<&HedgeConfig> {
+            self.hedge_config.as_ref()
+        }
+
+        async fn get_request(&self, path: &Path, options: GetOptions) -> Result<Response> {
+            use crate::client::GetOptionsExt;
+
+            let url = format!("{}/{}", self.url, path);
+            let response = reqwest::Client::new()
+                .get(url)
+                .with_get_options(options)
+                .send()
+                .await
+                .unwrap();
+
+            match response.status() {
+                StatusCode::PRECONDITION_FAILED => Err(crate::Error::Precondition {
+                    path: path.to_string(),
+                    source: "precondition failed".into(),
+                }),
+                StatusCode::NOT_MODIFIED => Err(crate::Error::NotModified {
+                    path: path.to_string(),
+                    source: "not modified".into(),
+                }),
+                _ => Ok(response),
+            }
         }
     }
 
@@ -316,4 +543,372 @@ mod tests {
         let err = get_result::<TestClient>(&path, Some(GetRange::Suffix(4)), resp).unwrap_err();
         assert_eq!(err.to_string(), "Requested 2..6, got 2..4");
     }
+
+    /// Returns a response with the provided headers and `content_length`, whose
+    /// body fails after `body`
+    fn truncated_response(
+        status: StatusCode,
+        headers: &[(&str, String)],
+        body: &[u8],
+        content_length: usize,
+    ) -> hyper::Response<hyper::Body> {
+        // Declare a content length greater than the body, and abort the body once
+        // the data has been flushed, closing the connection part way through
+        let (mut sender, response_body) = hyper::Body::channel();
+        sender.try_send_data(body.to_vec().into()).unwrap();
+        tokio::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            sender.abort();
+        });
+
+        let mut builder = hyper::Response::builder()
+            .status(status)
+            .header(CONTENT_LENGTH, content_length);
+        for (k, v) in headers {
+            builder = builder.header(*k, v);
+        }
+        builder.body(response_body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_get_range_meta() {
+        let server = MockServer::new();
+        let client = Arc::new(TestClient {
+            url: server.url().to_string(),
+            retry_config: Default::default(),
+            hedge_config: None,
+        });
+        let path = Path::from("test");
+
+        // A streamed response, without a Content-Length
+        server.push_fn(|req| {
+            assert_eq!(req.headers().get(RANGE).unwrap(), "bytes=10-19");
+            let (mut sender, body) = hyper::Body::channel();
+            sender.try_send_data(vec![0_u8; 10].into()).unwrap();
+            hyper::Response::builder()
+                .status(StatusCode::PARTIAL_CONTENT)
+                .header(CONTENT_RANGE, "bytes 10-19/100")
+                .header(ETAG, "\"1\"")
+                .header(LAST_MODIFIED, "Tue, 15 Nov 1994 12:45:26 GMT")
+                .body(body)
+                .unwrap()
+        });
+
+        let options = GetOptions {
+            range: Some((10..20).into()),
+            ..Default::default()
+        };
+        let result = client.get_opts(&path, options).await.unwrap();
+        assert_eq!(result.range, 10..20);
+        // The metadata of the full object is returned without a separate HEAD
+        assert_eq!(result.meta.size, 100);
+        assert_eq!(result.meta.e_tag.as_deref(), Some("\"1\""));
+        assert_eq!(result.meta.last_modified.timestamp(), 784903526);
+        server.shutdown().await;
+    }
+
+    #[cfg(feature = "compression")]
+    #[tokio::test]
+    async fn test_get_decompress() {
+        use std::io::Write;
+
+        let server = MockServer::new();
+        let client = Arc::new(TestClient {
+            url: server.url().to_string(),
+            retry_config: Default::default(),
+            hedge_config: None,
+        });
+        let path = Path::from("test.csv");
+        let data = "a,b,c\n1,2,3\n".repeat(100);
+
+        let mut encoder = flate2::write::GzEncoder::new(vec![], Default::default());
+        encoder.write_all(data.as_bytes()).unwrap();
+        let encoded = encoder.finish().unwrap();
+
+        for _ in 0..2 {
+            let encoded = encoded.clone();
+            server.push_fn(move |_| {
+                hyper::Response::builder()
+                    .header(CONTENT_ENCODING, "gzip")
+                    .header(CONTENT_LENGTH, encoded.len())
+                    .body(encoded.into())
+                    .unwrap()
+            });
+        }
+
+        let options = GetOptions {
+            decompress: true,
+            ..Default::default()
+        };
+        let result = client.get_opts(&path, options.clone()).await.unwrap();
+        // The metadata describes the encoded object
+        assert_eq!(result.meta.size, encoded.len());
+        assert_eq!(result.bytes().await.unwrap(), data.as_bytes());
+
+        // Without decompress the encoded bytes are returned
+        let result = client.get_opts(&path, GetOptions::default()).await.unwrap();
+        assert_eq!(result.bytes().await.unwrap(), encoded);
+
+        // A range of an encoded body cannot be decoded
+        server.push_fn(|_| {
+            hyper::Response::builder()
+                .status(StatusCode::PARTIAL_CONTENT)
+                .header(CONTENT_ENCODING, "gzip")
+                .header(CONTENT_RANGE, "bytes 0-9/100")
+                .body(vec![0; 10].into())
+                .unwrap()
+        });
+        let options = GetOptions {
+            range: Some((0..10).into()),
+            ..options
+        };
+        let err = client.get_opts(&path, options).await.unwrap_err();
+        assert!(matches!(err, crate::Error::NotSupported { .. }), "{err}");
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_get_continuation() {
+        let server = MockServer::new();
+        let client = Arc::new(TestClient {
+            url: server.url().to_string(),
+            retry_config: RetryConfig {
+                max_retries: 2,
+                ..Default::default()
+            },
+            hedge_config: None,
+        });
+        let data: Vec<u8> = (0..100).collect();
+        let path = Path::from("test");
+
+        // A truncated response should be transparently continued
+        let (d1, d2) = (data[..30].to_vec(), data[30..60].to_vec());
+        let d3 = data[60..].to_vec();
+        server.push(truncated_response(
+            StatusCode::OK,
+            &[("etag", "\"1\"".to_string())],
+            &d1,
+            100,
+        ));
+        server.push_fn(move |req| {
+            assert_eq!(req.headers().get(RANGE).unwrap(), "bytes=30-99");
+            assert_eq!(req.headers().get(IF_MATCH).unwrap(), "\"1\"");
+            truncated_response(
+                StatusCode::PARTIAL_CONTENT,
+                &[
+                    ("etag", "\"1\"".to_string()),
+                    ("content-range", "bytes 30-99/100".to_string()),
+                ],
+                &d2,
+                70,
+            )
+        });
+        server.push_fn(move |req| {
+            assert_eq!(req.headers().get(RANGE).unwrap(), "bytes=60-99");
+            assert_eq!(req.headers().get(IF_MATCH).unwrap(), "\"1\"");
+            hyper::Response::builder()
+                .status(StatusCode::PARTIAL_CONTENT)
+                .header("etag", "\"1\"")
+                .header("content-range", "bytes 60-99/100")
+                .body(d3.into())
+                .unwrap()
+        });
+
+        let result = client.get_opts(&path, GetOptions::default()).await.unwrap();
+        let bytes = result.bytes().await.unwrap();
+        assert_eq!(bytes.as_ref(), data.as_slice());
+
+        // Should fail if the object has changed
+        server.push(truncated_response(
+            StatusCode::OK,
+            &[("etag", "\"1\"".to_string())],
+            &data[..30],
+            100,
+        ));
+        server.push(
+            hyper::Response::builder()
+                .status(StatusCode::PRECONDITION_FAILED)
+                .body(hyper::Body::empty())
+                .unwrap(),
+        );
+        let result = client.get_opts(&path, GetOptions::default()).await.unwrap();
+        result.bytes().await.unwrap_err();
+
+        // Should fail without an e-tag
+        server.push(truncated_response(StatusCode::OK, &[], &data[..30], 100));
+        let result = client.get_opts(&path, GetOptions::default()).await.unwrap();
+        result.bytes().await.unwrap_err();
+
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_get_continuation_non_retryable() {
+        let server = MockServer::new();
+        let client = Arc::new(TestClient {
+            url: server.url().to_string(),
+            retry_config: Default::default(),
+            hedge_config: None,
+        });
+
+        // An error that is not a transient transport error is returned without continuing
+        let body = vec![
+            Ok(Bytes::from_static(b"hello")),
+            Err(crate::Error::Generic {
+                store: "TEST",
+                source: "invalid data".into(),
+            }),
+        ];
+        let state = ContinuationState {
+            client,
+            location: Path::from("test"),
+            e_tag: Some("\"1\"".to_string()),
+            remaining: 0..10,
+            options: GetOptions::default(),
+            body: futures::stream::iter(body).boxed(),
+            retries: 0,
+            done: false,
+        };
+        let results: Vec<_> = state.into_stream().collect().await;
+        assert_eq!(results.len(), 2);
+        let err = results[1].as_ref().unwrap_err();
+        assert!(err.to_string().contains("invalid data"), "{err}");
+        assert_eq!(server.connections(), 0);
+
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_verify_checksum() {
+        let server = MockServer::new();
+        let client = Arc::new(TestClient {
+            url: server.url().to_string(),
+            retry_config: Default::default(),
+            hedge_config: None,
+        });
+        let path = Path::from("test");
+        let options = GetOptions {
+            verify_checksum: true,
+            ..Default::default()
+        };
+
+        // MD5 and CRC32C of "hello world"
+        let md5 = ("content-md5", "XrY7u+Ae7tCTyyK7j1rNww==");
+        let crc32c = ("x-goog-hash", "crc32c=yZRlqg==");
+        for (header, value) in [md5, crc32c] {
+            server.push(
+                hyper::Response::builder()
+                    .header(header, value)
+                    .body("hello world".into())
+                    .unwrap(),
+            );
+            let result = client.get_opts(&path, options.clone()).await.unwrap();
+            assert_eq!(result.bytes().await.unwrap().as_ref(), b"hello world");
+
+            // Corrupted body
+            server.push(
+                hyper::Response::builder()
+                    .header(header, value)
+                    .body("hello wOrld".into())
+                    .unwrap(),
+            );
+            let result = client.get_opts(&path, options.clone()).await.unwrap();
+            let err = result.bytes().await.unwrap_err();
+            assert!(
+                matches!(err, crate::Error::ChecksumMismatch { .. }),
+                "{err}"
+            );
+
+            // Not verified unless requested
+            server.push(
+                hyper::Response::builder()
+                    .header(header, value)
+                    .body("hello wOrld".into())
+                    .unwrap(),
+            );
+            let result = client.get_opts(&path, GetOptions::default()).await;
+            assert_eq!(
+                result.unwrap().bytes().await.unwrap().as_ref(),
+                b"hello wOrld"
+            );
+        }
+
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_hedging() {
+        use std::time::{Duration, Instant};
+
+        let server = MockServer::new();
+        let client = Arc::new(TestClient {
+            url: server.url().to_string(),
+            retry_config: Default::default(),
+            hedge_config: Some(HedgeConfig {
+                delay: Duration::from_millis(50),
+                max_hedges: 2,
+            }),
+        });
+        let path = Path::from("test");
+
+        // The first request stalls, and so a hedged request is issued
+        server.push_async_fn(|_| async move {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            hyper::Response::new(hyper::Body::from("slow"))
+        });
+        server.push(hyper::Response::new(hyper::Body::from("fast")));
+
+        let start = Instant::now();
+        let result = client.get_opts(&path, Default::default()).await.unwrap();
+        let elapsed = start.elapsed();
+        assert_eq!(result.bytes().await.unwrap().as_ref(), b"fast");
+        assert!(elapsed < Duration::from_secs(2), "{elapsed:?}");
+
+        // A response received before the delay is not hedged
+        server.push(hyper::Response::new(hyper::Body::from("once")));
+        let result = client.get_opts(&path, Default::default()).await.unwrap();
+        assert_eq!(result.bytes().await.unwrap().as_ref(), b"once");
+
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_not_modified() {
+        use std::time::{Duration, Instant};
+
+        let server = MockServer::new();
+        let client = Arc::new(TestClient {
+            url: server.url().to_string(),
+            retry_config: Default::default(),
+            hedge_config: Some(HedgeConfig {
+                delay: Duration::from_millis(50),
+                max_hedges: 1,
+            }),
+        });
+        let path = Path::from("test");
+        let options = GetOptions {
+            if_none_match: Some("\"1\"".to_string()),
+            ..Default::default()
+        };
+
+        // The stalled request would return a body, but the 304 from the hedged
+        // request is returned without waiting for it
+        server.push_async_fn(|_| async move {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            hyper::Response::new(hyper::Body::from("modified"))
+        });
+        server.push(
+            hyper::Response::builder()
+                .status(StatusCode::NOT_MODIFIED)
+                .body(hyper::Body::empty())
+                .unwrap(),
+        );
+
+        let start = Instant::now();
+        let err = client.get_opts(&path, options).await.unwrap_err();
+        let elapsed = start.elapsed();
+        assert!(matches!(err, crate::Error::NotModified { .. }), "{err}");
+        assert!(elapsed < Duration::from_secs(2), "{elapsed:?}");
+        server.shutdown().await;
+    }
 }
@@ -18,7 +18,8 @@
 //! Logic for extracting ObjectMeta from headers used by AWS, GCP and Azure
 
 use crate::path::Path;
-use crate::ObjectMeta;
+use crate::util::normalize_etag;
+use crate::{ObjectChecksum, ObjectMeta};
 use chrono::{DateTime, TimeZone, Utc};
 use hyper::header::{CONTENT_LENGTH, ETAG, LAST_MODIFIED};
 use hyper::HeaderMap;
@@ -85,9 +86,11 @@ pub fn get_version(headers: &HeaderMap, version: &str) -> Result<Option<String>,
 }
 
 /// Extracts an etag from the provided [`HeaderMap`]
+///
+/// Weak ETags are returned with their `W/` prefix, see [`normalize_etag`]
 pub fn get_etag(headers: &HeaderMap) -> Result<String, Error> {
     let e_tag = headers.get(ETAG).ok_or(Error::MissingEtag)?;
-    Ok(e_tag.to_str().context(BadHeaderSnafu)?.to_string())
+    Ok(normalize_etag(e_tag.to_str().context(BadHeaderSnafu)?))
 }
 
 /// Extracts [`ObjectMeta`] from the provided [`HeaderMap`]
@@ -95,6 +98,28 @@ pub fn header_meta(
     location: &Path,
     headers: &HeaderMap,
     cfg: HeaderConfig,
+) -> Result<ObjectMeta, Error> {
+    let content_length = headers
+        .get(CONTENT_LENGTH)
+        .context(MissingContentLengthSnafu)?;
+
+    let content_length = content_length.to_str().context(BadHeaderSnafu)?;
+    let size = content_length
+        .parse()
+        .context(InvalidContentLengthSnafu { content_length })?;
+
+    header_meta_with_size(location, headers, cfg, size)
+}
+
+/// Extracts [`ObjectMeta`] for an object of `size` bytes from the provided [`HeaderMap`]
+///
+/// This is used where the size of the object is not the `Content-Length`
+/// of the response, such as for a range request
+pub fn header_meta_with_size(
+    location: &Path,
+    headers: &HeaderMap,
+    cfg: HeaderConfig,
+    size: usize,
 ) -> Result<ObjectMeta, Error> {
     let last_modified = match headers.get(LAST_MODIFIED) {
         Some(last_modified) => {
@@ -113,15 +138,6 @@ pub fn header_meta(
         Err(e) => return Err(e),
     };
 
-    let content_length = headers
-        .get(CONTENT_LENGTH)
-        .context(MissingContentLengthSnafu)?;
-
-    let content_length = content_length.to_str().context(BadHeaderSnafu)?;
-    let size = content_length
-        .parse()
-        .context(InvalidContentLengthSnafu { content_length })?;
-
     let version = match cfg.version_header.and_then(|h| headers.get(h)) {
         Some(v) => Some(v.to_str().context(BadHeaderSnafu)?.to_string()),
         None => None,
@@ -133,5 +149,6 @@ pub fn header_meta(
         version,
         size,
         e_tag,
+        checksum: ObjectChecksum::from_headers(headers),
     })
 }
@@ -18,11 +18,11 @@
 use crate::client::pagination::stream_paginated;
 use crate::path::Path;
 use crate::Result;
-use crate::{ListResult, ObjectMeta};
+use crate::{ListDeduplication, ListPage, ListResult, ObjectMeta};
 use async_trait::async_trait;
 use futures::stream::BoxStream;
-use futures::{StreamExt, TryStreamExt};
-use std::collections::BTreeSet;
+use futures::{Stream, StreamExt, TryStreamExt};
+use std::collections::{BTreeSet, HashSet, VecDeque};
 
 /// A client that can perform paginated list requests
 #[async_trait]
@@ -34,6 +34,82 @@ pub trait ListClient: Send + Sync + 'static {
         token: Option<&str>,
         offset: Option<&str>,
     ) -> Result<(ListResult, Option<String>)>;
+
+    /// The [`ListDeduplication`] to apply to listings
+    fn list_deduplication(&self) -> Result<ListDeduplication> {
+        Ok(ListDeduplication::Off)
+    }
+}
+
+/// Returns the `prefix` parameter of a list request for `prefix`
+///
+/// A non-empty prefix is terminated with [`DELIMITER`], so that it matches only whole
+/// path segments. `None` and the empty path both list the whole bucket, and so both
+/// return `None`, as a prefix of [`DELIMITER`] would match no objects.
+///
+/// [`DELIMITER`]: crate::path::DELIMITER
+pub(crate) fn list_prefix(prefix: Option<&Path>) -> Option<String> {
+    prefix
+        .filter(|x| !x.as_ref().is_empty())
+        .map(|p| format!("{}{}", p.as_ref(), crate::path::DELIMITER))
+}
+
+/// Tracks the keys returned by a listing, see [`ListDeduplication`]
+#[derive(Debug)]
+struct SeenKeys {
+    dedup: ListDeduplication,
+    seen: HashSet<Path>,
+    /// The order keys were seen in, used to evict keys for [`ListDeduplication::Bounded`]
+    order: VecDeque<Path>,
+}
+
+impl SeenKeys {
+    fn new(dedup: ListDeduplication) -> Self {
+        Self {
+            dedup,
+            seen: Default::default(),
+            order: Default::default(),
+        }
+    }
+
+    /// Records `path`, returning false if it has already been returned by this listing
+    fn insert(&mut self, path: &Path) -> bool {
+        match self.dedup {
+            ListDeduplication::Off | ListDeduplication::Bounded(0) => true,
+            ListDeduplication::Full => self.seen.insert(path.clone()),
+            ListDeduplication::Bounded(n) => {
+                if !self.seen.insert(path.clone()) {
+                    return false;
+                }
+                self.order.push_back(path.clone());
+                if self.order.len() > n {
+                    let evicted = self.order.pop_front().unwrap();
+                    self.seen.remove(&evicted);
+                }
+                true
+            }
+        }
+    }
+}
+
+/// Filters duplicate objects from `stream` according to `dedup`
+fn deduplicate<'a, S>(
+    stream: S,
+    dedup: Result<ListDeduplication>,
+) -> BoxStream<'a, Result<ObjectMeta>>
+where
+    S: Stream<Item = Result<ObjectMeta>> + Send + 'a,
+{
+    match dedup {
+        Ok(ListDeduplication::Off) => stream.boxed(),
+        Ok(dedup) => {
+            let mut seen = SeenKeys::new(dedup);
+            stream
+                .try_filter(move |o| futures::future::ready(seen.insert(&o.location)))
+                .boxed()
+        }
+        Err(e) => futures::stream::once(futures::future::ready(Err(e))).boxed(),
+    }
 }
 
 /// Extension trait for [`ListClient`] that adds common listing functionality
@@ -48,6 +124,12 @@ pub trait ListClientExt {
 
     fn list(&self, prefix: Option<&Path>) -> BoxStream<'_, Result<ObjectMeta>>;
 
+    fn list_from_token(
+        &self,
+        prefix: Option<&Path>,
+        token: Option<&str>,
+    ) -> BoxStream<'_, Result<ListPage>>;
+
     fn list_with_offset(
         &self,
         prefix: Option<&Path>,
@@ -66,9 +148,7 @@ impl<T: ListClient> ListClientExt for T {
         offset: Option<&Path>,
     ) -> BoxStream<'_, Result<ListResult>> {
         let offset = offset.map(|x| x.to_string());
-        let prefix = prefix
-            .filter(|x| !x.as_ref().is_empty())
-            .map(|p| format!("{}{}", p.as_ref(), crate::path::DELIMITER));
+        let prefix = list_prefix(prefix);
 
         stream_paginated(
             (prefix, offset),
@@ -88,10 +168,44 @@ impl<T: ListClient> ListClientExt for T {
     }
 
     fn list(&self, prefix: Option<&Path>) -> BoxStream<'_, Result<ObjectMeta>> {
-        self.list_paginated(prefix, false, None)
+        let stream = self
+            .list_paginated(prefix, false, None)
             .map_ok(|r| futures::stream::iter(r.objects.into_iter().map(Ok)))
-            .try_flatten()
-            .boxed()
+            .try_flatten();
+        deduplicate(stream, self.list_deduplication())
+    }
+
+    fn list_from_token(
+        &self,
+        prefix: Option<&Path>,
+        token: Option<&str>,
+    ) -> BoxStream<'_, Result<ListPage>> {
+        let prefix = list_prefix(prefix);
+
+        // The state is the token of the next page to fetch, or `None` once exhausted
+        let state = Some(token.map(ToString::to_string));
+        futures::stream::unfold(state, move |state| {
+            let prefix = prefix.clone();
+            async move {
+                let token = state?;
+                let r = self
+                    .list_request(prefix.as_deref(), false, token.as_deref(), None)
+                    .await;
+
+                match r {
+                    Ok((r, next_token)) => {
+                        let next_token = next_token.filter(|t| !t.is_empty());
+                        let page = ListPage {
+                            objects: r.objects,
+                            next_token: next_token.clone(),
+                        };
+                        Some((Ok(page), next_token.map(Some)))
+                    }
+                    Err(e) => Some((Err(e), None)),
+                }
+            }
+        })
+        .boxed()
     }
 
     fn list_with_offset(
@@ -99,14 +213,16 @@ impl<T: ListClient> ListClientExt for T {
         prefix: Option<&Path>,
         offset: &Path,
     ) -> BoxStream<'_, Result<ObjectMeta>> {
-        self.list_paginated(prefix, false, Some(offset))
+        let stream = self
+            .list_paginated(prefix, false, Some(offset))
             .map_ok(|r| futures::stream::iter(r.objects.into_iter().map(Ok)))
-            .try_flatten()
-            .boxed()
+            .try_flatten();
+        deduplicate(stream, self.list_deduplication())
     }
 
     async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
         let mut stream = self.list_paginated(prefix, true, None);
+        let mut seen = SeenKeys::new(self.list_deduplication()?);
 
         let mut common_prefixes = BTreeSet::new();
         let mut objects = Vec::new();
@@ -114,7 +230,11 @@ impl<T: ListClient> ListClientExt for T {
         while let Some(result) = stream.next().await {
             let response = result?;
             common_prefixes.extend(response.common_prefixes.into_iter());
-            objects.extend(response.objects.into_iter());
+            let unseen = response
+                .objects
+                .into_iter()
+                .filter(|o| seen.insert(&o.location));
+            objects.extend(unseen);
         }
 
         Ok(ListResult {
@@ -123,3 +243,174 @@ impl<T: ListClient> ListClientExt for T {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// A [`ListClient`] returning a page per request, with a common prefix and object each
+    #[derive(Default)]
+    struct MockClient {
+        requests: AtomicUsize,
+    }
+
+    const PAGES: usize = 3;
+
+    #[async_trait]
+    impl ListClient for MockClient {
+        async fn list_request(
+            &self,
+            _prefix: Option<&str>,
+            _delimiter: bool,
+            token: Option<&str>,
+            _offset: Option<&str>,
+        ) -> Result<(ListResult, Option<String>)> {
+            self.requests.fetch_add(1, Ordering::SeqCst);
+            let page: usize = token.map(|t| t.parse().unwrap()).unwrap_or_default();
+            let result = ListResult {
+                common_prefixes: vec![Path::from(format!("dir{page}"))],
+                objects: vec![ObjectMeta {
+                    location: Path::from(format!("file{page}")),
+                    last_modified: Default::default(),
+                    size: 0,
+                    e_tag: None,
+                    version: None,
+                    checksum: None,
+                }],
+            };
+            let next = (page + 1 < PAGES).then(|| (page + 1).to_string());
+            Ok((result, next))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_list_with_delimiter_paginated() {
+        let client = MockClient::default();
+        let mut stream = client.list_paginated(None, true, None);
+
+        for page in 0..PAGES {
+            let result = stream.next().await.unwrap().unwrap();
+            // Each page is yielded before the next is requested
+            assert_eq!(client.requests.load(Ordering::SeqCst), page + 1);
+            assert_eq!(
+                result.common_prefixes,
+                vec![Path::from(format!("dir{page}"))]
+            );
+            assert_eq!(
+                result.objects[0].location,
+                Path::from(format!("file{page}"))
+            );
+        }
+        assert!(stream.next().await.is_none());
+        assert_eq!(client.requests.load(Ordering::SeqCst), PAGES);
+
+        let result = client.list_with_delimiter(None).await.unwrap();
+        assert_eq!(result.common_prefixes.len(), PAGES);
+        assert_eq!(result.objects.len(), PAGES);
+    }
+
+    /// A [`ListClient`] returning overlapping pages of objects, as may occur
+    /// when listing keys under concurrent modification
+    struct DuplicateClient {
+        dedup: ListDeduplication,
+    }
+
+    #[async_trait]
+    impl ListClient for DuplicateClient {
+        async fn list_request(
+            &self,
+            _prefix: Option<&str>,
+            _delimiter: bool,
+            token: Option<&str>,
+            _offset: Option<&str>,
+        ) -> Result<(ListResult, Option<String>)> {
+            let page: usize = token.map(|t| t.parse().unwrap()).unwrap_or_default();
+            let objects = [page, page + 1]
+                .into_iter()
+                .map(|i| ObjectMeta {
+                    location: Path::from(format!("file{i}")),
+                    last_modified: Default::default(),
+                    size: 0,
+                    e_tag: None,
+                    version: None,
+                    checksum: None,
+                })
+                .collect();
+            let result = ListResult {
+                common_prefixes: vec![],
+                objects,
+            };
+            let next = (page + 1 < PAGES).then(|| (page + 1).to_string());
+            Ok((result, next))
+        }
+
+        fn list_deduplication(&self) -> Result<ListDeduplication> {
+            Ok(self.dedup)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_list_deduplication() {
+        let list = |dedup| async move {
+            let client = DuplicateClient { dedup };
+            let objects: Vec<_> = client.list(None).try_collect().await.unwrap();
+            let locations: Vec<_> = objects.into_iter().map(|o| o.location).collect();
+            let delimited = client.list_with_delimiter(None).await.unwrap();
+            assert_eq!(delimited.objects.len(), locations.len());
+            locations
+        };
+
+        let all = list(ListDeduplication::Off).await;
+        assert_eq!(all.len(), PAGES * 2);
+
+        let expected: Vec<_> = (0..=PAGES)
+            .map(|i| Path::from(format!("file{i}")))
+            .collect();
+        assert_eq!(list(ListDeduplication::Full).await, expected);
+        assert_eq!(list(ListDeduplication::Bounded(1)).await, expected);
+        assert_eq!(list(ListDeduplication::Bounded(0)).await, all);
+
+        let mut seen = SeenKeys::new(ListDeduplication::Bounded(2));
+        let (a, b, c) = (Path::from("a"), Path::from("b"), Path::from("c"));
+        assert!(seen.insert(&a));
+        assert!(seen.insert(&b));
+        assert!(!seen.insert(&a));
+        assert!(seen.insert(&c));
+        // `a` has been evicted
+        assert!(seen.insert(&a));
+        assert!(!seen.insert(&c));
+    }
+
+    #[test]
+    fn test_list_prefix() {
+        assert_eq!(list_prefix(None), None);
+        assert_eq!(list_prefix(Some(&Path::from(""))), None);
+        assert_eq!(list_prefix(Some(&Path::from("/"))), None);
+        assert_eq!(list_prefix(Some(&Path::from("a"))).unwrap(), "a/");
+        assert_eq!(list_prefix(Some(&Path::from("a/b/"))).unwrap(), "a/b/");
+    }
+
+    #[tokio::test]
+    async fn test_list_from_token() {
+        let client = MockClient::default();
+        let pages: Vec<_> = client
+            .list_from_token(None, None)
+            .try_collect()
+            .await
+            .unwrap();
+        let tokens: Vec<_> = pages.iter().map(|p| p.next_token.as_deref()).collect();
+        assert_eq!(tokens, vec![Some("1"), Some("2"), None]);
+
+        // Resume from a captured token
+        let token = pages[0].next_token.as_deref();
+        let resumed: Vec<_> = client
+            .list_from_token(None, token)
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(resumed.len(), 2);
+        assert_eq!(resumed[0].objects[0].location, Path::from("file1"));
+        assert_eq!(resumed[1].objects[0].location, Path::from("file2"));
+    }
+}
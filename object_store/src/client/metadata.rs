@@ -0,0 +1,136 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Validation of user-defined object metadata against the limits of each store
+
+use crate::Error;
+use hyper::header::{HeaderName, HeaderValue};
+use std::collections::BTreeMap;
+
+/// The limits a store places on user-defined metadata
+#[derive(Debug, Clone, Copy)]
+pub struct MetadataLimits {
+    /// The prefix of the header used to send each key, e.g. `x-amz-meta-`
+    pub header_prefix: &'static str,
+    /// The maximum total size in bytes of all keys and values
+    pub max_size: usize,
+    /// If keys must be valid C# identifiers, as required by Azure
+    pub identifier_keys: bool,
+}
+
+impl MetadataLimits {
+    /// Returns the header name and value with which to send each entry of `metadata`
+    ///
+    /// Returns an error if `metadata` exceeds these limits for `store`, or contains
+    /// a key or value that cannot be sent as a header
+    pub fn headers<'a>(
+        &self,
+        store: &'static str,
+        metadata: &'a BTreeMap<String, String>,
+    ) -> crate::Result<Vec<(HeaderName, &'a str)>> {
+        let mut headers = Vec::with_capacity(metadata.len());
+        for (key, value) in metadata {
+            let name = format!("{}{key}", self.header_prefix);
+            let name = match HeaderName::from_bytes(name.as_bytes()) {
+                Ok(name) if !key.is_empty() && (!self.identifier_keys || is_identifier(key)) => {
+                    name
+                }
+                _ => {
+                    return Err(Error::Generic {
+                        store,
+                        source: format!("invalid metadata key '{key}'").into(),
+                    })
+                }
+            };
+            if HeaderValue::from_str(value).is_err() {
+                return Err(Error::Generic {
+                    store,
+                    source: format!("invalid value for metadata key '{key}'").into(),
+                });
+            }
+            headers.push((name, value.as_str()));
+        }
+
+        let size = metadata.iter().map(|(k, v)| k.len() + v.len()).sum();
+        match size > self.max_size {
+            true => Err(Error::MetadataTooLarge {
+                store,
+                size,
+                limit: self.max_size,
+            }),
+            false => Ok(headers),
+        }
+    }
+}
+
+/// Returns true if `s` is an ASCII C# identifier
+fn is_identifier(s: &str) -> bool {
+    let mut chars = s.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_metadata() {
+        let limits = MetadataLimits {
+            header_prefix: "x-amz-meta-",
+            max_size: 2048,
+            identifier_keys: false,
+        };
+        let mut metadata = BTreeMap::new();
+        assert!(limits.headers("S3", &metadata).unwrap().is_empty());
+
+        metadata.insert("Author".to_string(), "a".repeat(2042));
+        let headers = limits.headers("S3", &metadata).unwrap();
+        assert_eq!(headers[0].0, "x-amz-meta-author");
+
+        metadata.insert("b".to_string(), String::new());
+        let err = limits.headers("S3", &metadata).unwrap_err();
+        assert!(matches!(err, Error::MetadataTooLarge { .. }), "{err}");
+        assert_eq!(
+            err.to_string(),
+            "User-defined metadata of 2049 bytes exceeds the S3 limit of 2048 bytes"
+        );
+
+        let invalid = BTreeMap::from([("not valid".to_string(), String::new())]);
+        let err = limits.headers("S3", &invalid).unwrap_err().to_string();
+        assert!(err.contains("invalid metadata key 'not valid'"), "{err}");
+
+        let value = BTreeMap::from([("key".to_string(), "line\nbreak".to_string())]);
+        let err = limits.headers("S3", &value).unwrap_err().to_string();
+        assert!(
+            err.contains("invalid value for metadata key 'key'"),
+            "{err}"
+        );
+
+        let azure = MetadataLimits {
+            header_prefix: "x-ms-meta-",
+            max_size: 8192,
+            identifier_keys: true,
+        };
+        let dashed = BTreeMap::from([("content-owner".to_string(), String::new())]);
+        limits.headers("S3", &dashed).unwrap();
+        azure.headers("MicrosoftAzure", &dashed).unwrap_err();
+        let ident = BTreeMap::from([("_content_owner1".to_string(), String::new())]);
+        azure.headers("MicrosoftAzure", &ident).unwrap();
+        assert!(!is_identifier("1key"));
+    }
+}
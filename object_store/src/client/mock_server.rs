@@ -24,6 +24,7 @@ use std::collections::VecDeque;
 use std::convert::Infallible;
 use std::future::Future;
 use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use tokio::sync::oneshot;
 use tokio::task::JoinHandle;
@@ -33,6 +34,7 @@ pub type ResponseFn = Box<dyn FnOnce(Request<Body>) -> BoxFuture<'static, Respon
 /// A mock server
 pub struct MockServer {
     responses: Arc<Mutex<VecDeque<ResponseFn>>>,
+    connections: Arc<AtomicUsize>,
     shutdown: oneshot::Sender<()>,
     handle: JoinHandle<()>,
     url: String,
@@ -43,8 +45,12 @@ impl MockServer {
         let responses: Arc<Mutex<VecDeque<ResponseFn>>> =
             Arc::new(Mutex::new(VecDeque::with_capacity(10)));
 
+        let connections = Arc::new(AtomicUsize::new(0));
+
         let r = Arc::clone(&responses);
+        let c = Arc::clone(&connections);
         let make_service = make_service_fn(move |_conn| {
+            c.fetch_add(1, Ordering::Relaxed);
             let r = Arc::clone(&r);
             async move {
                 Ok::<_, Infallible>(service_fn(move |req| {
@@ -76,6 +82,7 @@ impl MockServer {
 
         Self {
             responses,
+            connections,
             shutdown,
             handle,
             url,
@@ -87,6 +94,11 @@ impl MockServer {
         &self.url
     }
 
+    /// The number of connections accepted by the mock server
+    pub fn connections(&self) -> usize {
+        self.connections.load(Ordering::Relaxed)
+    }
+
     /// Add a response
     pub fn push(&self, response: Response<Body>) {
         self.push_fn(|_| response)
@@ -29,6 +29,10 @@ pub mod pagination;
 
 pub mod get;
 
+pub mod checksum;
+
+pub mod encoding;
+
 #[cfg(any(feature = "aws", feature = "gcp", feature = "azure"))]
 pub mod list;
 
@@ -37,11 +41,15 @@ pub mod token;
 
 pub mod header;
 
+#[cfg(any(feature = "aws", feature = "gcp", feature = "azure"))]
+pub mod metadata;
+
 #[cfg(any(feature = "aws", feature = "gcp"))]
 pub mod s3;
 
 use async_trait::async_trait;
 use std::collections::HashMap;
+use std::net::SocketAddr;
 use std::str::FromStr;
 use std::sync::Arc;
 use std::time::Duration;
@@ -50,7 +58,7 @@ use reqwest::header::{HeaderMap, HeaderValue};
 use reqwest::{Client, ClientBuilder, NoProxy, Proxy, RequestBuilder};
 use serde::{Deserialize, Serialize};
 
-use crate::config::{fmt_duration, ConfigValue};
+use crate::config::{fmt_duration, ConfigValue, Parse, REDACTED};
 use crate::path::Path;
 use crate::{GetOptions, Result};
 
@@ -81,8 +89,12 @@ pub enum ClientConfigKey {
     AllowInvalidCertificates,
     /// Timeout for only the connect phase of a Client
     ConnectTimeout,
+    /// Infer the CONTENT_TYPE of uploads from the file extension
+    ContentTypeInference,
     /// default CONTENT_TYPE for uploads
     DefaultContentType,
+    /// Omit zero-byte directory marker objects, i.e. keys ending in `/`, from listings
+    HideDirectoryMarkers,
     /// Only use http1 connections
     Http1Only,
     /// Interval for HTTP2 Ping frames should be sent to keep a connection alive.
@@ -93,6 +105,13 @@ pub enum ClientConfigKey {
     Http2KeepAliveWhileIdle,
     /// Only use http2 connections
     Http2Only,
+    /// Filter duplicate keys from listings, see [`ListDeduplication`]
+    ListDeduplication,
+    /// The maximum bytes buffered and in flight across all multipart uploads of a store
+    MaxInFlightUploadBytes,
+    /// Timeout for the request completing a multipart upload, see
+    /// [`ClientOptions::with_multipart_complete_timeout`]
+    MultipartCompleteTimeout,
     /// The pool max idle timeout
     ///
     /// This is the length of time an idle connection will be kept alive
@@ -105,6 +124,12 @@ pub enum ClientConfigKey {
     ProxyCaCertificate,
     /// List of hosts that bypass proxy
     ProxyExcludes,
+    /// Return an error if a conditional request would use a weak ETag
+    RejectWeakEtags,
+    /// Interval between TCP keepalive probes on idle connections
+    TcpKeepalive,
+    /// Disable Nagle's algorithm, i.e. set `TCP_NODELAY`, on connections
+    TcpNodelay,
     /// Request timeout
     ///
     /// The timeout is applied from when the request starts connecting until the
@@ -112,6 +137,40 @@ pub enum ClientConfigKey {
     Timeout,
     /// User-Agent header to be used by this client
     UserAgent,
+    /// Product token appended to the User-Agent header of this client
+    UserAgentSuffix,
+}
+
+impl ClientConfigKey {
+    /// All variants of [`ClientConfigKey`]
+    #[cfg(any(feature = "aws", feature = "gcp", feature = "azure"))]
+    pub(crate) const ALL: &'static [Self] = &[
+        Self::AllowHttp,
+        Self::AllowInvalidCertificates,
+        Self::ConnectTimeout,
+        Self::ContentTypeInference,
+        Self::DefaultContentType,
+        Self::HideDirectoryMarkers,
+        Self::Http1Only,
+        Self::Http2KeepAliveInterval,
+        Self::Http2KeepAliveTimeout,
+        Self::Http2KeepAliveWhileIdle,
+        Self::Http2Only,
+        Self::ListDeduplication,
+        Self::MaxInFlightUploadBytes,
+        Self::MultipartCompleteTimeout,
+        Self::PoolIdleTimeout,
+        Self::PoolMaxIdlePerHost,
+        Self::ProxyUrl,
+        Self::ProxyCaCertificate,
+        Self::ProxyExcludes,
+        Self::RejectWeakEtags,
+        Self::TcpKeepalive,
+        Self::TcpNodelay,
+        Self::Timeout,
+        Self::UserAgent,
+        Self::UserAgentSuffix,
+    ];
 }
 
 impl AsRef<str> for ClientConfigKey {
@@ -120,19 +179,28 @@ impl AsRef<str> for ClientConfigKey {
             Self::AllowHttp => "allow_http",
             Self::AllowInvalidCertificates => "allow_invalid_certificates",
             Self::ConnectTimeout => "connect_timeout",
+            Self::ContentTypeInference => "content_type_inference",
             Self::DefaultContentType => "default_content_type",
+            Self::HideDirectoryMarkers => "hide_directory_markers",
             Self::Http1Only => "http1_only",
             Self::Http2Only => "http2_only",
             Self::Http2KeepAliveInterval => "http2_keep_alive_interval",
             Self::Http2KeepAliveTimeout => "http2_keep_alive_timeout",
             Self::Http2KeepAliveWhileIdle => "http2_keep_alive_while_idle",
+            Self::ListDeduplication => "list_deduplication",
+            Self::MaxInFlightUploadBytes => "max_in_flight_upload_bytes",
+            Self::MultipartCompleteTimeout => "multipart_complete_timeout",
             Self::PoolIdleTimeout => "pool_idle_timeout",
             Self::PoolMaxIdlePerHost => "pool_max_idle_per_host",
             Self::ProxyUrl => "proxy_url",
             Self::ProxyCaCertificate => "proxy_ca_certificate",
             Self::ProxyExcludes => "proxy_excludes",
+            Self::RejectWeakEtags => "reject_weak_etags",
+            Self::TcpKeepalive => "tcp_keepalive",
+            Self::TcpNodelay => "tcp_nodelay",
             Self::Timeout => "timeout",
             Self::UserAgent => "user_agent",
+            Self::UserAgentSuffix => "user_agent_suffix",
         }
     }
 }
@@ -145,17 +213,26 @@ impl FromStr for ClientConfigKey {
             "allow_http" => Ok(Self::AllowHttp),
             "allow_invalid_certificates" => Ok(Self::AllowInvalidCertificates),
             "connect_timeout" => Ok(Self::ConnectTimeout),
+            "content_type_inference" => Ok(Self::ContentTypeInference),
             "default_content_type" => Ok(Self::DefaultContentType),
+            "hide_directory_markers" => Ok(Self::HideDirectoryMarkers),
             "http1_only" => Ok(Self::Http1Only),
             "http2_only" => Ok(Self::Http2Only),
             "http2_keep_alive_interval" => Ok(Self::Http2KeepAliveInterval),
             "http2_keep_alive_timeout" => Ok(Self::Http2KeepAliveTimeout),
             "http2_keep_alive_while_idle" => Ok(Self::Http2KeepAliveWhileIdle),
+            "list_deduplication" => Ok(Self::ListDeduplication),
+            "max_in_flight_upload_bytes" => Ok(Self::MaxInFlightUploadBytes),
+            "multipart_complete_timeout" => Ok(Self::MultipartCompleteTimeout),
             "pool_idle_timeout" => Ok(Self::PoolIdleTimeout),
             "pool_max_idle_per_host" => Ok(Self::PoolMaxIdlePerHost),
             "proxy_url" => Ok(Self::ProxyUrl),
+            "reject_weak_etags" => Ok(Self::RejectWeakEtags),
+            "tcp_keepalive" => Ok(Self::TcpKeepalive),
+            "tcp_nodelay" => Ok(Self::TcpNodelay),
             "timeout" => Ok(Self::Timeout),
             "user_agent" => Ok(Self::UserAgent),
+            "user_agent_suffix" => Ok(Self::UserAgentSuffix),
             _ => Err(super::Error::UnknownConfigurationKey {
                 store: "HTTP",
                 key: s.into(),
@@ -168,12 +245,17 @@ impl FromStr for ClientConfigKey {
 #[derive(Debug, Clone)]
 pub struct ClientOptions {
     user_agent: Option<ConfigValue<HeaderValue>>,
+    user_agent_suffix: Option<String>,
     content_type_map: HashMap<String, String>,
+    content_type_inference: ConfigValue<bool>,
     default_content_type: Option<String>,
     default_headers: Option<HeaderMap>,
+    resolve_overrides: HashMap<String, Vec<SocketAddr>>,
     proxy_url: Option<String>,
     proxy_ca_certificate: Option<String>,
     proxy_excludes: Option<String>,
+    root_certificates: Vec<String>,
+    client_tls_identity: Option<ClientTlsIdentity>,
     allow_http: ConfigValue<bool>,
     allow_insecure: ConfigValue<bool>,
     timeout: Option<ConfigValue<Duration>>,
@@ -183,8 +265,17 @@ pub struct ClientOptions {
     http2_keep_alive_interval: Option<ConfigValue<Duration>>,
     http2_keep_alive_timeout: Option<ConfigValue<Duration>>,
     http2_keep_alive_while_idle: ConfigValue<bool>,
+    tcp_keepalive: Option<ConfigValue<Duration>>,
+    tcp_nodelay: Option<ConfigValue<bool>>,
+    hedge_config: Option<HedgeConfig>,
+    hide_directory_markers: ConfigValue<bool>,
+    list_deduplication: ConfigValue<ListDeduplication>,
+    reject_weak_etags: ConfigValue<bool>,
+    max_in_flight_upload_bytes: Option<ConfigValue<usize>>,
+    multipart_complete_timeout: Option<ConfigValue<Duration>>,
     http1_only: ConfigValue<bool>,
     http2_only: ConfigValue<bool>,
+    http_client: Option<Client>,
 }
 
 impl Default for ClientOptions {
@@ -198,12 +289,17 @@ impl Default for ClientOptions {
         // we opt for a slightly higher default timeout of 30 seconds
         Self {
             user_agent: None,
+            user_agent_suffix: None,
             content_type_map: Default::default(),
+            content_type_inference: Default::default(),
             default_content_type: None,
             default_headers: None,
+            resolve_overrides: Default::default(),
             proxy_url: None,
             proxy_ca_certificate: None,
             proxy_excludes: None,
+            root_certificates: vec![],
+            client_tls_identity: None,
             allow_http: Default::default(),
             allow_insecure: Default::default(),
             timeout: Some(Duration::from_secs(30).into()),
@@ -213,11 +309,22 @@ impl Default for ClientOptions {
             http2_keep_alive_interval: None,
             http2_keep_alive_timeout: None,
             http2_keep_alive_while_idle: Default::default(),
+            tcp_keepalive: None,
+            tcp_nodelay: None,
+            hedge_config: None,
+            hide_directory_markers: Default::default(),
+            list_deduplication: Default::default(),
+            reject_weak_etags: Default::default(),
+            max_in_flight_upload_bytes: None,
+            // Completing a multipart upload requires the store to assemble the object,
+            // which for large objects can take far longer than any other request
+            multipart_complete_timeout: Some(Duration::from_secs(5 * 60).into()),
             // HTTP2 is known to be significantly slower than HTTP1, so we default
             // to HTTP1 for now.
             // https://github.com/apache/arrow-rs/issues/5194
             http1_only: true.into(),
             http2_only: Default::default(),
+            http_client: None,
         }
     }
 }
@@ -236,7 +343,9 @@ impl ClientOptions {
             ClientConfigKey::ConnectTimeout => {
                 self.connect_timeout = Some(ConfigValue::Deferred(value.into()))
             }
+            ClientConfigKey::ContentTypeInference => self.content_type_inference.parse(value),
             ClientConfigKey::DefaultContentType => self.default_content_type = Some(value.into()),
+            ClientConfigKey::HideDirectoryMarkers => self.hide_directory_markers.parse(value),
             ClientConfigKey::Http1Only => self.http1_only.parse(value),
             ClientConfigKey::Http2Only => self.http2_only.parse(value),
             ClientConfigKey::Http2KeepAliveInterval => {
@@ -248,6 +357,13 @@ impl ClientOptions {
             ClientConfigKey::Http2KeepAliveWhileIdle => {
                 self.http2_keep_alive_while_idle.parse(value)
             }
+            ClientConfigKey::ListDeduplication => self.list_deduplication.parse(value),
+            ClientConfigKey::MaxInFlightUploadBytes => {
+                self.max_in_flight_upload_bytes = Some(ConfigValue::Deferred(value.into()))
+            }
+            ClientConfigKey::MultipartCompleteTimeout => {
+                self.multipart_complete_timeout = Some(ConfigValue::Deferred(value.into()))
+            }
             ClientConfigKey::PoolIdleTimeout => {
                 self.pool_idle_timeout = Some(ConfigValue::Deferred(value.into()))
             }
@@ -257,10 +373,18 @@ impl ClientOptions {
             ClientConfigKey::ProxyUrl => self.proxy_url = Some(value.into()),
             ClientConfigKey::ProxyCaCertificate => self.proxy_ca_certificate = Some(value.into()),
             ClientConfigKey::ProxyExcludes => self.proxy_excludes = Some(value.into()),
+            ClientConfigKey::RejectWeakEtags => self.reject_weak_etags.parse(value),
+            ClientConfigKey::TcpKeepalive => {
+                self.tcp_keepalive = Some(ConfigValue::Deferred(value.into()))
+            }
+            ClientConfigKey::TcpNodelay => {
+                self.tcp_nodelay = Some(ConfigValue::Deferred(value.into()))
+            }
             ClientConfigKey::Timeout => self.timeout = Some(ConfigValue::Deferred(value.into())),
             ClientConfigKey::UserAgent => {
                 self.user_agent = Some(ConfigValue::Deferred(value.into()))
             }
+            ClientConfigKey::UserAgentSuffix => self.user_agent_suffix = Some(value.into()),
         }
         self
     }
@@ -271,7 +395,17 @@ impl ClientOptions {
             ClientConfigKey::AllowHttp => Some(self.allow_http.to_string()),
             ClientConfigKey::AllowInvalidCertificates => Some(self.allow_insecure.to_string()),
             ClientConfigKey::ConnectTimeout => self.connect_timeout.as_ref().map(fmt_duration),
+            ClientConfigKey::ContentTypeInference => Some(self.content_type_inference.to_string()),
             ClientConfigKey::DefaultContentType => self.default_content_type.clone(),
+            ClientConfigKey::HideDirectoryMarkers => Some(self.hide_directory_markers.to_string()),
+            ClientConfigKey::ListDeduplication => Some(self.list_deduplication.to_string()),
+            ClientConfigKey::MaxInFlightUploadBytes => self
+                .max_in_flight_upload_bytes
+                .as_ref()
+                .map(ToString::to_string),
+            ClientConfigKey::MultipartCompleteTimeout => {
+                self.multipart_complete_timeout.as_ref().map(fmt_duration)
+            }
             ClientConfigKey::Http1Only => Some(self.http1_only.to_string()),
             ClientConfigKey::Http2KeepAliveInterval => {
                 self.http2_keep_alive_interval.as_ref().map(fmt_duration)
@@ -290,29 +424,76 @@ impl ClientOptions {
             ClientConfigKey::ProxyUrl => self.proxy_url.clone(),
             ClientConfigKey::ProxyCaCertificate => self.proxy_ca_certificate.clone(),
             ClientConfigKey::ProxyExcludes => self.proxy_excludes.clone(),
+            ClientConfigKey::RejectWeakEtags => Some(self.reject_weak_etags.to_string()),
+            ClientConfigKey::TcpKeepalive => self.tcp_keepalive.as_ref().map(fmt_duration),
+            ClientConfigKey::TcpNodelay => self.tcp_nodelay.as_ref().map(ToString::to_string),
             ClientConfigKey::Timeout => self.timeout.as_ref().map(fmt_duration),
             ClientConfigKey::UserAgent => self
                 .user_agent
                 .as_ref()
                 .and_then(|v| v.get().ok())
                 .and_then(|v| v.to_str().ok().map(|s| s.to_string())),
+            ClientConfigKey::UserAgentSuffix => self.user_agent_suffix.clone(),
         }
     }
 
     /// Sets the User-Agent header to be used by this client
     ///
-    /// Default is based on the version of this crate
+    /// This replaces the default, which is based on the version of this crate,
+    /// see [`Self::with_user_agent_suffix`] to instead extend it
     pub fn with_user_agent(mut self, agent: HeaderValue) -> Self {
         self.user_agent = Some(agent.into());
         self
     }
 
+    /// Appends a product token, e.g. `my-app/1.0`, to the User-Agent header
+    ///
+    /// The token is separated by a space from the User-Agent configured by
+    /// [`Self::with_user_agent`], or the default if not set
+    pub fn with_user_agent_suffix(mut self, suffix: impl Into<String>) -> Self {
+        self.user_agent_suffix = Some(suffix.into());
+        self
+    }
+
+    /// Returns the User-Agent header to be used by this client
+    fn user_agent(&self) -> Result<HeaderValue> {
+        let agent = match &self.user_agent {
+            Some(user_agent) => user_agent.get()?,
+            None => HeaderValue::from_static(DEFAULT_USER_AGENT),
+        };
+        match &self.user_agent_suffix {
+            Some(suffix) => {
+                let mut combined = agent.as_bytes().to_vec();
+                combined.push(b' ');
+                combined.extend_from_slice(suffix.as_bytes());
+                HeaderValue::from_bytes(&combined).map_err(|_| super::Error::Generic {
+                    store: "Config",
+                    source: format!("invalid User-Agent suffix \"{suffix}\"").into(),
+                })
+            }
+            None => Ok(agent),
+        }
+    }
+
     /// Set the default CONTENT_TYPE for uploads
     pub fn with_default_content_type(mut self, mime: impl Into<String>) -> Self {
         self.default_content_type = Some(mime.into());
         self
     }
 
+    /// Infer the CONTENT_TYPE of uploads from the file extension, using a table of
+    /// common extensions, e.g. `.json` as `application/json` and `.csv` as `text/csv`
+    ///
+    /// This applies to extensions not configured with [`Self::with_content_type_for_suffix`],
+    /// with unknown extensions using [`Self::with_default_content_type`] if set,
+    /// otherwise `application/octet-stream`
+    ///
+    /// Defaults to false
+    pub fn with_content_type_inference(mut self, infer: bool) -> Self {
+        self.content_type_inference = infer.into();
+        self
+    }
+
     /// Set the CONTENT_TYPE for a given file extension
     pub fn with_content_type_for_suffix(
         mut self,
@@ -329,6 +510,84 @@ impl ClientOptions {
         self
     }
 
+    /// Resolve `host` to `addr`, instead of performing a DNS lookup
+    ///
+    /// Requests are still sent with the original hostname in the `Host` header and
+    /// TLS SNI, allowing, for example, a local emulator to impersonate a cloud endpoint.
+    /// May be called multiple times for the same `host`, with connections attempted
+    /// to each address in turn.
+    ///
+    /// Note: as DNS has no notion of ports, the port of `addr` is ignored, with
+    /// connections made to the port of the request URL
+    pub fn with_resolve(mut self, host: impl Into<String>, addr: SocketAddr) -> Self {
+        self.resolve_overrides
+            .entry(host.into())
+            .or_default()
+            .push(addr);
+        self
+    }
+
+    /// Omit directory markers from listings
+    ///
+    /// Some tools represent directories by creating a zero-byte object with a key
+    /// ending in `/`. As [`Path`] cannot represent a trailing `/`, these are otherwise
+    /// returned by [`ObjectStore::list`] and [`ObjectStore::list_with_delimiter`] as a
+    /// zero-byte object at the path of the directory. If `hide` is true, such markers
+    /// are instead filtered out of listings.
+    ///
+    /// Behaviour per backend:
+    ///
+    /// * S3 and GCS surface directory markers by default
+    /// * Azure already omits zero-byte blobs matching the listed prefix, this
+    ///   additionally omits markers for nested directories
+    /// * The HTTP store lists WebDAV collections, which are never returned as objects
+    ///
+    /// [`LocalFileSystem`](crate::local::LocalFileSystem) and
+    /// [`InMemory`](crate::memory::InMemory) are not configured with [`ClientOptions`],
+    /// and never return directory markers as a key cannot end in `/`.
+    ///
+    /// [`ObjectStore::list`]: crate::ObjectStore::list
+    /// [`ObjectStore::list_with_delimiter`]: crate::ObjectStore::list_with_delimiter
+    pub fn with_hide_directory_markers(mut self, hide: bool) -> Self {
+        self.hide_directory_markers = hide.into();
+        self
+    }
+
+    /// Filter duplicate keys from listings, see [`ListDeduplication`]
+    ///
+    /// Defaults to [`ListDeduplication::Off`]
+    pub fn with_list_deduplication(mut self, dedup: ListDeduplication) -> Self {
+        self.list_deduplication = dedup.into();
+        self
+    }
+
+    /// Return an error, instead of sending the request, if a conditional write would use
+    /// a weak ETag, i.e. one prefixed with `W/`
+    ///
+    /// Weak ETags only indicate that two representations are semantically equivalent,
+    /// and so can never satisfy the strong comparison used by `If-Match`. Without this
+    /// option such a request is sent and rejected by the store with
+    /// [`Error::Precondition`](crate::Error::Precondition), which is indistinguishable
+    /// from the object having been concurrently modified.
+    ///
+    /// Defaults to false
+    pub fn with_reject_weak_etags(mut self, reject: bool) -> Self {
+        self.reject_weak_etags = reject.into();
+        self
+    }
+
+    /// Limit the bytes buffered and in flight across all concurrent
+    /// [`ObjectStore::put_multipart`](crate::ObjectStore::put_multipart) uploads of a store,
+    /// see [`UploadLimiter`](crate::multipart::UploadLimiter)
+    ///
+    /// Each store built with these options has its own limit, shared by all its uploads
+    ///
+    /// By default this is unlimited
+    pub fn with_max_in_flight_upload_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_in_flight_upload_bytes = Some(max_bytes.into());
+        self
+    }
+
     /// Sets what protocol is allowed. If `allow_http` is :
     /// * false (default):  Only HTTPS are allowed
     /// * true:  HTTP and HTTPS are allowed
@@ -387,6 +646,39 @@ impl ClientOptions {
         self
     }
 
+    /// Add a PEM-encoded certificate to trust when verifying server certificates
+    ///
+    /// This is in addition to the system's trusted root certificates, and can be used
+    /// to trust a private certificate authority. May be called multiple times
+    pub fn with_root_certificate(mut self, certificate: impl Into<String>) -> Self {
+        self.root_certificates.push(certificate.into());
+        self
+    }
+
+    /// Sets the client certificate presented to servers that require mutual TLS
+    ///
+    /// `identity` must be PEM-encoded, containing the private key followed by
+    /// the certificate chain. PKCS#12 archives are not supported, and should
+    /// first be converted, e.g. with `openssl pkcs12 -nodes`
+    pub fn with_client_tls_identity(mut self, identity: impl Into<String>) -> Self {
+        self.client_tls_identity = Some(ClientTlsIdentity(identity.into()));
+        self
+    }
+
+    /// Sets the [`reqwest::Client`] used to make requests to the store, sharing its
+    /// connection pool with any other stores configured with the same client
+    ///
+    /// By default each store builds its own client, and therefore its own connection pool,
+    /// which wastes connections when creating many stores for the same backend, for
+    /// example with different prefixes. The shared client is used as is, and so the
+    /// options of this [`ClientOptions`] that configure the client, such as timeouts,
+    /// proxies and certificates, do not apply to it. Clients used to fetch credentials,
+    /// e.g. from an instance metadata endpoint, are still built from these options.
+    pub fn with_http_client(mut self, client: Client) -> Self {
+        self.http_client = Some(client);
+        self
+    }
+
     /// Set a list of hosts to exclude from proxy connections
     pub fn with_proxy_excludes(mut self, proxy_excludes: impl Into<String>) -> Self {
         self.proxy_excludes = Some(proxy_excludes.into());
@@ -412,6 +704,26 @@ impl ClientOptions {
         self
     }
 
+    /// Set the timeout for the request completing a multipart upload, i.e. S3 and GCS
+    /// `CompleteMultipartUpload`, and Azure `Put Block List`
+    ///
+    /// The store assembles the object from its parts when the upload is completed,
+    /// which for large objects can take much longer than uploading any one part, and
+    /// so this timeout is configured separately from [`Self::with_timeout`]
+    ///
+    /// Default is 5 minutes
+    pub fn with_multipart_complete_timeout(mut self, timeout: Duration) -> Self {
+        self.multipart_complete_timeout = Some(ConfigValue::Parsed(timeout));
+        self
+    }
+
+    /// Disables the multipart complete timeout, applying the request timeout
+    /// configured by [`Self::with_timeout`] instead
+    pub fn with_multipart_complete_timeout_disabled(mut self) -> Self {
+        self.multipart_complete_timeout = None;
+        self
+    }
+
     /// Set a timeout for only the connect phase of a Client
     ///
     /// Default is 5 seconds
@@ -476,21 +788,133 @@ impl ClientOptions {
         self
     }
 
+    /// Enable TCP keepalive, sending probes on idle connections at the given interval
+    ///
+    /// This allows connections silently dropped by intermediaries, such as load
+    /// balancers, to be detected, instead of stalling the next request until it times out
+    ///
+    /// Default is disabled
+    pub fn with_tcp_keepalive(mut self, interval: Duration) -> Self {
+        self.tcp_keepalive = Some(ConfigValue::Parsed(interval));
+        self
+    }
+
+    /// Set whether to disable Nagle's algorithm, i.e. set `TCP_NODELAY`, on connections
+    ///
+    /// Default is `true`, i.e. Nagle's algorithm is disabled
+    pub fn with_tcp_nodelay(mut self, nodelay: bool) -> Self {
+        self.tcp_nodelay = Some(nodelay.into());
+        self
+    }
+
+    /// Enable hedging of get and head requests, reducing tail latency
+    ///
+    /// If a request has not received a response after `delay`, a further identical
+    /// request is issued, up to `max_hedges` additional requests in total. The first
+    /// response received is returned, and all other in-flight requests are cancelled.
+    ///
+    /// As hedging increases the number of requests made to the store, `delay` should
+    /// typically be set to a high percentile, e.g. p95, of the observed latency.
+    ///
+    /// Default is disabled
+    pub fn with_hedging(mut self, delay: Duration, max_hedges: usize) -> Self {
+        self.hedge_config = (max_hedges > 0).then_some(HedgeConfig { delay, max_hedges });
+        self
+    }
+
+    /// Returns the [`HedgeConfig`] if hedging is enabled
+    pub(crate) fn hedge_config(&self) -> Option<&HedgeConfig> {
+        self.hedge_config.as_ref()
+    }
+
+    /// Returns true if directory markers should be omitted from listings
+    ///
+    /// See [`Self::with_hide_directory_markers`]
+    #[cfg(any(feature = "aws", feature = "gcp", feature = "azure"))]
+    pub(crate) fn hide_directory_markers(&self) -> Result<bool> {
+        self.hide_directory_markers.get()
+    }
+
+    /// Returns a new [`UploadLimiter`] if [`Self::with_max_in_flight_upload_bytes`] is set
+    ///
+    /// [`UploadLimiter`]: crate::multipart::UploadLimiter
+    #[cfg(any(feature = "aws", feature = "gcp", feature = "azure"))]
+    pub(crate) fn upload_limiter(&self) -> Result<Option<crate::multipart::UploadLimiter>> {
+        let max_bytes = match &self.max_in_flight_upload_bytes {
+            Some(v) => v.get()?,
+            None => return Ok(None),
+        };
+        if max_bytes < 1024 {
+            return Err(crate::Error::Generic {
+                store: "ClientOptions",
+                source: format!("max in-flight upload bytes of {max_bytes} is less than 1 KiB")
+                    .into(),
+            });
+        }
+        Ok(Some(crate::multipart::UploadLimiter::new(max_bytes)))
+    }
+
+    /// Returns the timeout for the request completing a multipart upload, if any
+    ///
+    /// See [`Self::with_multipart_complete_timeout`]
+    #[cfg(any(feature = "aws", feature = "gcp", feature = "azure"))]
+    pub(crate) fn multipart_complete_timeout(&self) -> Result<Option<Duration>> {
+        self.multipart_complete_timeout
+            .as_ref()
+            .map(|v| v.get())
+            .transpose()
+    }
+
+    /// Returns the [`ListDeduplication`] to apply to listings
+    #[cfg(any(feature = "aws", feature = "gcp", feature = "azure"))]
+    pub(crate) fn list_deduplication(&self) -> Result<ListDeduplication> {
+        self.list_deduplication.get()
+    }
+
+    /// Returns an error if `e_tag` is weak and [`Self::with_reject_weak_etags`] is enabled
+    #[cfg(any(feature = "aws", feature = "azure"))]
+    pub(crate) fn check_conditional_etag(&self, e_tag: &str) -> Result<()> {
+        match self.reject_weak_etags.get()? && crate::util::is_weak_etag(e_tag) {
+            true => Err(crate::Error::NotSupported {
+                source: format!(
+                    "weak ETag {e_tag} cannot be used for a conditional write as it does not \
+                     identify the exact contents of an object"
+                )
+                .into(),
+            }),
+            false => Ok(()),
+        }
+    }
+
     /// Get the mime type for the file in `path` to be uploaded
     ///
     /// Gets the file extension from `path`, and returns the
     /// mime type if it was defined initially through
     /// `ClientOptions::with_content_type_for_suffix`
     ///
+    /// Otherwise, if `ClientOptions::with_content_type_inference` is enabled,
+    /// returns the mime type of a common extension
+    ///
     /// Otherwise returns the default mime type if it was defined
-    /// earlier through `ClientOptions::with_default_content_type`
+    /// earlier through `ClientOptions::with_default_content_type`, or
+    /// `application/octet-stream` if inference is enabled
     pub fn get_content_type(&self, path: &Path) -> Option<&str> {
-        match path.extension() {
-            Some(extension) => match self.content_type_map.get(extension) {
-                Some(ct) => Some(ct.as_str()),
-                None => self.default_content_type.as_deref(),
-            },
-            None => self.default_content_type.as_deref(),
+        let extension = path.extension();
+        if let Some(ct) = extension.and_then(|e| self.content_type_map.get(e)) {
+            return Some(ct.as_str());
+        }
+
+        // An invalid value is reported by Self::client
+        let infer = self.content_type_inference.get().unwrap_or_default();
+        if infer {
+            if let Some(ct) = extension.and_then(infer_content_type) {
+                return Some(ct);
+            }
+        }
+
+        match &self.default_content_type {
+            Some(ct) => Some(ct.as_str()),
+            None => infer.then_some("application/octet-stream"),
         }
     }
 
@@ -507,18 +931,34 @@ impl ClientOptions {
             .client()
     }
 
+    /// Returns the client for requests to the store, see [`Self::with_http_client`]
+    pub(crate) fn store_client(&self) -> Result<Client> {
+        match &self.http_client {
+            Some(client) => {
+                // Validate eagerly as this is otherwise only read when uploading
+                self.content_type_inference.get()?;
+                Ok(client.clone())
+            }
+            None => self.client(),
+        }
+    }
+
     pub(crate) fn client(&self) -> Result<Client> {
         let mut builder = ClientBuilder::new();
 
-        match &self.user_agent {
-            Some(user_agent) => builder = builder.user_agent(user_agent.get()?),
-            None => builder = builder.user_agent(DEFAULT_USER_AGENT),
-        }
+        builder = builder.user_agent(self.user_agent()?);
+
+        // Validate eagerly as this is otherwise only read when uploading
+        self.content_type_inference.get()?;
 
         if let Some(headers) = &self.default_headers {
             builder = builder.default_headers(headers.clone())
         }
 
+        for (host, addrs) in &self.resolve_overrides {
+            builder = builder.resolve_to_addrs(host, addrs)
+        }
+
         if let Some(proxy) = &self.proxy_url {
             let mut proxy = Proxy::all(proxy).map_err(map_client_error)?;
 
@@ -538,6 +978,18 @@ impl ClientOptions {
             builder = builder.proxy(proxy);
         }
 
+        for certificate in &self.root_certificates {
+            let certificate = reqwest::tls::Certificate::from_pem(certificate.as_bytes())
+                .map_err(map_client_error)?;
+            builder = builder.add_root_certificate(certificate);
+        }
+
+        if let Some(identity) = &self.client_tls_identity {
+            let identity = reqwest::tls::Identity::from_pem(identity.0.as_bytes())
+                .map_err(map_client_error)?;
+            builder = builder.identity(identity);
+        }
+
         if let Some(timeout) = &self.timeout {
             builder = builder.timeout(timeout.get()?)
         }
@@ -566,6 +1018,14 @@ impl ClientOptions {
             builder = builder.http2_keep_alive_while_idle(true)
         }
 
+        if let Some(interval) = &self.tcp_keepalive {
+            builder = builder.tcp_keepalive(interval.get()?)
+        }
+
+        if let Some(nodelay) = &self.tcp_nodelay {
+            builder = builder.tcp_nodelay(nodelay.get()?)
+        }
+
         if self.http1_only.get()? {
             builder = builder.http1_only()
         }
@@ -585,6 +1045,108 @@ impl ClientOptions {
     }
 }
 
+/// Returns the CONTENT_TYPE of a common file `extension`,
+/// see [`ClientOptions::with_content_type_inference`]
+fn infer_content_type(extension: &str) -> Option<&'static str> {
+    Some(match extension.to_ascii_lowercase().as_str() {
+        "json" => "application/json",
+        "jsonl" | "ndjson" => "application/x-ndjson",
+        "csv" => "text/csv",
+        "tsv" => "text/tab-separated-values",
+        "txt" | "log" => "text/plain",
+        "html" | "htm" => "text/html",
+        "css" => "text/css",
+        "js" => "text/javascript",
+        "md" => "text/markdown",
+        "xml" => "application/xml",
+        "yaml" | "yml" => "application/yaml",
+        "parquet" | "orc" | "bin" => "application/octet-stream",
+        "avro" => "application/avro",
+        "arrow" => "application/vnd.apache.arrow.file",
+        "gz" => "application/gzip",
+        "zst" => "application/zstd",
+        "zip" => "application/zip",
+        "tar" => "application/x-tar",
+        "pdf" => "application/pdf",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "svg" => "image/svg+xml",
+        "webp" => "image/webp",
+        _ => return None,
+    })
+}
+
+/// Configuration for hedging requests, see [`ClientOptions::with_hedging`]
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct HedgeConfig {
+    /// The delay after which a further request is issued
+    pub delay: Duration,
+    /// The maximum number of additional requests
+    pub max_hedges: usize,
+}
+
+/// How duplicate keys are filtered from the results of a single listing
+///
+/// Paginated listings are not atomic, and so some stores may return the same key
+/// more than once, in different pages, when it is concurrently modified. Deduplication
+/// tracks the keys already returned by a listing, filtering any repeats.
+///
+/// This has a memory cost proportional to the number of keys tracked, typically
+/// on the order of 100 bytes per key, and so for extremely large listings
+/// [`ListDeduplication::Bounded`] can be used to only track recently returned keys.
+/// As stores return keys in lexicographical order, duplicates are typically close
+/// together, and so a modest bound is often sufficient.
+///
+/// Can be configured with [`ClientConfigKey::ListDeduplication`] using the values
+/// `off`, `full`, or the number of keys to track
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ListDeduplication {
+    /// Do not filter duplicate keys
+    #[default]
+    Off,
+    /// Track every key returned by a listing, filtering all duplicates
+    Full,
+    /// Track at most this many of the most recently returned keys
+    Bounded(usize),
+}
+
+impl std::fmt::Display for ListDeduplication {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Off => write!(f, "off"),
+            Self::Full => write!(f, "full"),
+            Self::Bounded(n) => write!(f, "{n}"),
+        }
+    }
+}
+
+impl Parse for ListDeduplication {
+    fn parse(v: &str) -> Result<Self> {
+        match v.to_ascii_lowercase().as_str() {
+            "off" | "false" => Ok(Self::Off),
+            "full" | "true" => Ok(Self::Full),
+            _ => match usize::parse(v) {
+                Ok(n) => Ok(Self::Bounded(n)),
+                Err(_) => Err(crate::Error::Generic {
+                    store: "Config",
+                    source: format!("failed to parse \"{v}\" as ListDeduplication").into(),
+                }),
+            },
+        }
+    }
+}
+
+/// A PEM-encoded client certificate and private key, redacted from [`Debug`] output
+#[derive(Clone)]
+struct ClientTlsIdentity(String);
+
+impl std::fmt::Debug for ClientTlsIdentity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "ClientTlsIdentity({REDACTED})")
+    }
+}
+
 pub trait GetOptionsExt {
     fn with_get_options(self, options: GetOptions) -> Self;
 }
@@ -626,6 +1188,13 @@ pub trait CredentialProvider: std::fmt::Debug + Send + Sync {
 
     /// Return a credential
     async fn get_credential(&self) -> Result<Arc<Self::Credential>>;
+
+    /// Discard any cached credential, so that the next call to
+    /// [`Self::get_credential`] fetches a new one
+    ///
+    /// Called when a request is rejected with an authentication error, see
+    /// [`crate::RetryConfig::refresh_credentials`]. The default implementation does nothing
+    async fn invalidate(&self) {}
 }
 
 /// A static set of credentials
@@ -659,7 +1228,7 @@ where
 mod cloud {
     use super::*;
     use crate::client::token::{TemporaryToken, TokenCache};
-    use crate::RetryConfig;
+    use crate::{BackoffConfig, RetryConfig};
 
     /// A [`CredentialProvider`] that uses [`Client`] to fetch temporary tokens
     #[derive(Debug)]
@@ -670,12 +1239,45 @@ mod cloud {
         cache: TokenCache<Arc<T::Credential>>,
     }
 
+    /// The maximum number of times to retry fetching a credential
+    const CREDENTIAL_MAX_RETRIES: usize = 3;
+
+    /// The maximum backoff between attempts to fetch a credential
+    const CREDENTIAL_MAX_BACKOFF: Duration = Duration::from_secs(1);
+
+    /// The maximum length of time to spend retrying a credential fetch
+    const CREDENTIAL_RETRY_TIMEOUT: Duration = Duration::from_secs(10);
+
+    /// Returns `retry` capped to the limits for fetching credentials
+    ///
+    /// Credential fetches block every request made by a store, and so fail faster
+    /// than requests to the store itself
+    pub(crate) fn credential_retry_config(retry: &RetryConfig) -> RetryConfig {
+        RetryConfig {
+            backoff: BackoffConfig {
+                max_backoff: retry.backoff.max_backoff.min(CREDENTIAL_MAX_BACKOFF),
+                ..retry.backoff.clone()
+            },
+            max_retries: retry.max_retries.min(CREDENTIAL_MAX_RETRIES),
+            retry_timeout: retry.retry_timeout.min(CREDENTIAL_RETRY_TIMEOUT),
+            budget: retry.budget.clone(),
+            max_non_idempotent_retries: retry.max_non_idempotent_retries,
+            non_idempotent_backoff: retry.non_idempotent_backoff.clone(),
+            request_id_header: retry.request_id_header.clone(),
+            refresh_credentials: retry.refresh_credentials,
+        }
+    }
+
     impl<T: TokenProvider> TokenCredentialProvider<T> {
+        /// Create a new [`TokenCredentialProvider`]
+        ///
+        /// Token fetches are retried according to `retry`, capped to at most 3 retries
+        /// within 10 seconds. Refused connections are not retried
         pub fn new(inner: T, client: Client, retry: RetryConfig) -> Self {
             Self {
                 inner,
                 client,
-                retry,
+                retry: credential_retry_config(&retry),
                 cache: Default::default(),
             }
         }
@@ -697,6 +1299,10 @@ mod cloud {
                 .get_or_insert_with(|| self.inner.fetch_token(&self.client, &self.retry))
                 .await
         }
+
+        async fn invalidate(&self) {
+            self.cache.clear().await
+        }
     }
 
     #[async_trait]
@@ -734,6 +1340,8 @@ mod tests {
         let pool_max_idle_per_host = "94".to_string();
         let proxy_url = "https://fake_proxy_url".to_string();
         let timeout = "95 seconds".to_string();
+        let tcp_keepalive = "96 seconds".to_string();
+        let tcp_nodelay = "false".to_string();
         let user_agent = "object_store:fake_user_agent".to_string();
 
         let options = HashMap::from([
@@ -759,6 +1367,8 @@ mod tests {
             ("pool_max_idle_per_host", pool_max_idle_per_host.clone()),
             ("proxy_url", proxy_url.clone()),
             ("timeout", timeout.clone()),
+            ("tcp_keepalive", tcp_keepalive.clone()),
+            ("tcp_nodelay", tcp_nodelay.clone()),
             ("user_agent", user_agent.clone()),
         ]);
 
@@ -845,6 +1455,18 @@ mod tests {
             builder.get_config_value(&ClientConfigKey::Timeout).unwrap(),
             timeout
         );
+        assert_eq!(
+            builder
+                .get_config_value(&ClientConfigKey::TcpKeepalive)
+                .unwrap(),
+            tcp_keepalive
+        );
+        assert_eq!(
+            builder
+                .get_config_value(&ClientConfigKey::TcpNodelay)
+                .unwrap(),
+            tcp_nodelay
+        );
         assert_eq!(
             builder
                 .get_config_value(&ClientConfigKey::UserAgent)
@@ -852,4 +1474,328 @@ mod tests {
             user_agent
         );
     }
+
+    #[tokio::test]
+    async fn test_user_agent() {
+        use crate::client::mock_server::MockServer;
+        use hyper::{header::USER_AGENT, Body, Response};
+
+        let server = MockServer::new();
+        let expected = [
+            DEFAULT_USER_AGENT.to_string(),
+            format!("{DEFAULT_USER_AGENT} my-app/1.0"),
+            "custom/2.0".to_string(),
+            "custom/2.0 my-app/1.0".to_string(),
+        ];
+        for agent in expected.clone() {
+            server.push_fn(move |r| {
+                assert_eq!(r.headers().get(USER_AGENT).unwrap(), agent.as_str());
+                Response::new(Body::empty())
+            });
+        }
+
+        let custom = HeaderValue::from_static("custom/2.0");
+        let options = [
+            ClientOptions::new(),
+            ClientOptions::new().with_user_agent_suffix("my-app/1.0"),
+            ClientOptions::new().with_user_agent(custom.clone()),
+            ClientOptions::new()
+                .with_config(ClientConfigKey::UserAgent, "custom/2.0")
+                .with_config(ClientConfigKey::UserAgentSuffix, "my-app/1.0"),
+        ];
+        for options in options {
+            let client = options.with_allow_http(true).client().unwrap();
+            client.get(server.url()).send().await.unwrap();
+        }
+        server.shutdown().await;
+
+        let err = ClientOptions::new()
+            .with_user_agent_suffix("bad\nsuffix")
+            .client()
+            .unwrap_err();
+        assert!(err.to_string().contains("User-Agent suffix"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_tcp_options() {
+        use crate::client::mock_server::MockServer;
+        use hyper::{Body, Response};
+
+        let server = MockServer::new();
+        server.push(Response::new(Body::empty()));
+
+        let options = ClientOptions::new()
+            .with_allow_http(true)
+            .with_tcp_keepalive(Duration::from_secs(30))
+            .with_tcp_nodelay(false);
+        assert_eq!(
+            options.get_config_value(&ClientConfigKey::TcpKeepalive),
+            Some("30s".to_string())
+        );
+        assert_eq!(
+            options.get_config_value(&ClientConfigKey::TcpNodelay),
+            Some("false".to_string())
+        );
+
+        let client = options.client().unwrap();
+        client.get(server.url()).send().await.unwrap();
+        server.shutdown().await;
+
+        // Invalid values are reported when building the client
+        for (key, value) in [
+            (ClientConfigKey::TcpKeepalive, "forever"),
+            (ClientConfigKey::TcpNodelay, "maybe"),
+        ] {
+            let options = ClientOptions::new().with_config(key, value);
+            options.client().unwrap_err();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resolve() {
+        use crate::client::mock_server::MockServer;
+        use hyper::{header::HOST, Body, Response};
+
+        let server = MockServer::new();
+        let url: url::Url = server.url().parse().unwrap();
+        let port = url.port().unwrap();
+        let host = format!("bucket.s3.example.invalid:{port}");
+
+        let expected = host.clone();
+        server.push_fn(move |r| {
+            assert_eq!(r.headers().get(HOST).unwrap(), expected.as_str());
+            Response::new(Body::empty())
+        });
+
+        let client = ClientOptions::new()
+            .with_allow_http(true)
+            .with_resolve("bucket.s3.example.invalid", ([127, 0, 0, 1], 0).into())
+            .client()
+            .unwrap();
+        client.get(format!("http://{host}/")).send().await.unwrap();
+        server.shutdown().await;
+    }
+
+    /// A PEM-encoded certificate or key from the `tests/tls` directory
+    macro_rules! tls_file {
+        ($name:literal) => {
+            include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/tls/", $name))
+        };
+    }
+
+    /// Spawns a server on localhost requiring clients to present a certificate
+    /// signed by the test CA, returning its port
+    async fn mtls_server() -> u16 {
+        use base64::prelude::BASE64_STANDARD;
+        use base64::Engine;
+        use hyper::server::conn::Http;
+        use hyper::service::service_fn;
+        use hyper::{Body, Response};
+        use tokio_rustls::rustls::server::AllowAnyAuthenticatedClient;
+        use tokio_rustls::rustls::{Certificate, PrivateKey, RootCertStore, ServerConfig};
+
+        let der = |pem: &str| {
+            let b64: String = pem.lines().filter(|l| !l.starts_with("-----")).collect();
+            BASE64_STANDARD.decode(b64).unwrap()
+        };
+
+        let mut roots = RootCertStore::empty();
+        roots.add(&Certificate(der(tls_file!("ca.pem")))).unwrap();
+        let config = ServerConfig::builder()
+            .with_safe_defaults()
+            .with_client_cert_verifier(AllowAnyAuthenticatedClient::new(roots).boxed())
+            .with_single_cert(
+                vec![Certificate(der(tls_file!("server.pem")))],
+                PrivateKey(der(tls_file!("server.key"))),
+            )
+            .unwrap();
+        let acceptor = tokio_rustls::TlsAcceptor::from(Arc::new(config));
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                let acceptor = acceptor.clone();
+                tokio::spawn(async move {
+                    if let Ok(stream) = acceptor.accept(stream).await {
+                        let service = service_fn(|_| async {
+                            Ok::<_, hyper::Error>(Response::new(Body::from("mtls")))
+                        });
+                        let _ = Http::new().serve_connection(stream, service).await;
+                    }
+                });
+            }
+        });
+        port
+    }
+
+    #[tokio::test]
+    async fn test_retry_tls_handshake() {
+        use crate::client::retry::{Error, RetryExt};
+        use crate::{BackoffConfig, RetryConfig};
+        use base64::prelude::BASE64_STANDARD;
+        use base64::Engine;
+        use hyper::server::conn::Http;
+        use hyper::service::service_fn;
+        use hyper::{Body, Response};
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use tokio_rustls::rustls::{Certificate, PrivateKey, ServerConfig};
+
+        let der = |pem: &str| {
+            let b64: String = pem.lines().filter(|l| !l.starts_with("-----")).collect();
+            BASE64_STANDARD.decode(b64).unwrap()
+        };
+        let config = ServerConfig::builder()
+            .with_safe_defaults()
+            .with_no_client_auth()
+            .with_single_cert(
+                vec![Certificate(der(tls_file!("server.pem")))],
+                PrivateKey(der(tls_file!("server.key"))),
+            )
+            .unwrap();
+        let acceptor = tokio_rustls::TlsAcceptor::from(Arc::new(config));
+
+        // Resets the first two connections during the TLS handshake
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let accepted = Arc::new(AtomicUsize::new(0));
+        let captured = Arc::clone(&accepted);
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                if captured.fetch_add(1, Ordering::SeqCst) < 2 {
+                    // A zero linger sends a RST, rather than a FIN, on close
+                    #[allow(deprecated)]
+                    stream.set_linger(Some(Duration::ZERO)).unwrap();
+                    drop(stream);
+                    continue;
+                }
+                let acceptor = acceptor.clone();
+                tokio::spawn(async move {
+                    if let Ok(stream) = acceptor.accept(stream).await {
+                        let service = service_fn(|_| async {
+                            Ok::<_, hyper::Error>(Response::new(Body::from("tls")))
+                        });
+                        let _ = Http::new().serve_connection(stream, service).await;
+                    }
+                });
+            }
+        });
+
+        let url = format!("https://localhost:{port}/");
+        let retry = RetryConfig {
+            backoff: BackoffConfig {
+                init_backoff: Duration::from_millis(1),
+                max_backoff: Duration::from_millis(10),
+                base: 2.,
+            },
+            max_retries: 3,
+            retry_timeout: Duration::from_secs(10),
+            ..Default::default()
+        };
+        let options = ClientOptions::new()
+            .with_resolve("localhost", ([127, 0, 0, 1], 0).into())
+            .with_root_certificate(tls_file!("ca.pem"));
+        let client = options.client().unwrap();
+
+        let response = client.get(&url).send_retry(&retry).await.unwrap();
+        assert_eq!(response.text().await.unwrap(), "tls");
+        assert_eq!(accepted.load(Ordering::SeqCst), 3);
+
+        // An untrusted certificate is not retried
+        let untrusted = ClientOptions::new().with_resolve("localhost", ([127, 0, 0, 1], 0).into());
+        let err = untrusted
+            .client()
+            .unwrap()
+            .get(&url)
+            .send_retry(&retry)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::Reqwest { retries: 0, .. }), "{err}");
+        assert_eq!(accepted.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn test_mtls() {
+        let port = mtls_server().await;
+        let url = format!("https://localhost:{port}/");
+        let options = ClientOptions::new()
+            .with_resolve("localhost", ([127, 0, 0, 1], 0).into())
+            .with_root_certificate(tls_file!("ca.pem"));
+
+        // The server certificate is signed by an untrusted CA
+        let untrusted = ClientOptions::new().with_resolve("localhost", ([127, 0, 0, 1], 0).into());
+        untrusted
+            .client()
+            .unwrap()
+            .get(&url)
+            .send()
+            .await
+            .unwrap_err();
+
+        // No client certificate is presented
+        options
+            .client()
+            .unwrap()
+            .get(&url)
+            .send()
+            .await
+            .unwrap_err();
+
+        let identity = format!("{}{}", tls_file!("client.key"), tls_file!("client.pem"));
+        let options = options.with_client_tls_identity(identity);
+        assert!(!format!("{options:?}").contains("PRIVATE KEY"));
+
+        let response = options.client().unwrap().get(&url).send().await.unwrap();
+        assert_eq!(response.text().await.unwrap(), "mtls");
+
+        // Invalid PEM is reported when building the client
+        let err = ClientOptions::new()
+            .with_client_tls_identity("not a certificate")
+            .client()
+            .unwrap_err();
+        assert!(err.to_string().contains("HTTP client"), "{err}");
+    }
+
+    #[test]
+    fn client_test_content_type_inference() {
+        let csv = Path::from("data/file.csv");
+        let unknown = Path::from("data/file.unknown");
+
+        let options = ClientOptions::new();
+        assert_eq!(options.get_content_type(&csv), None);
+
+        let options = options.with_config(ClientConfigKey::ContentTypeInference, "true");
+        options.client().unwrap();
+        assert_eq!(options.get_content_type(&csv), Some("text/csv"));
+        assert_eq!(
+            options.get_content_type(&Path::from("FILE.JSON")),
+            Some("application/json")
+        );
+        assert_eq!(
+            options.get_content_type(&unknown),
+            Some("application/octet-stream")
+        );
+        assert_eq!(
+            options.get_content_type(&Path::from("data/file")),
+            Some("application/octet-stream")
+        );
+
+        // Explicitly configured content types take precedence
+        let options = options
+            .with_content_type_for_suffix("csv", "text/plain")
+            .with_default_content_type("application/x-custom");
+        assert_eq!(options.get_content_type(&csv), Some("text/plain"));
+        assert_eq!(
+            options.get_content_type(&unknown),
+            Some("application/x-custom")
+        );
+
+        let err = ClientOptions::new()
+            .with_config(ClientConfigKey::ContentTypeInference, "maybe")
+            .client()
+            .unwrap_err();
+        assert!(err.to_string().contains("failed to parse"), "{err}");
+    }
 }
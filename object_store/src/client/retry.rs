@@ -20,23 +20,139 @@
 use crate::client::backoff::{Backoff, BackoffConfig};
 use futures::future::BoxFuture;
 use futures::FutureExt;
-use reqwest::header::LOCATION;
-use reqwest::{Response, StatusCode};
+use parking_lot::Mutex;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, LOCATION};
+use reqwest::{Request, Response, StatusCode};
+use serde::Deserialize;
 use snafu::Error as SnafuError;
 use snafu::Snafu;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
-use tracing::info;
+use tracing::{debug, info, Instrument};
+
+/// The headers used by the supported stores to identify a request, in order of preference
+const REQUEST_ID_HEADERS: [&str; 4] = [
+    "x-amz-request-id",
+    "x-ms-request-id",
+    "x-guploader-uploadid",
+    "x-request-id",
+];
+
+/// Returns the id the server assigned to the request from the response `headers`
+fn server_request_id(headers: &HeaderMap) -> Option<String> {
+    REQUEST_ID_HEADERS.iter().find_map(|h| {
+        let value = headers.get(*h)?.to_str().ok()?;
+        Some(value.to_string())
+    })
+}
+
+/// Returns a new random (version 4) UUID to identify a request
+fn new_client_request_id() -> String {
+    let mut bytes: [u8; 16] = rand::random();
+    bytes[6] = (bytes[6] & 0x0F) | 0x40;
+    bytes[8] = (bytes[8] & 0x3F) | 0x80;
+    let hex: String = bytes.iter().map(|b| format!("{b:02x}")).collect();
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+/// The header S3 uses to report the region of a bucket when redirecting a request
+const BUCKET_REGION: &str = "x-amz-bucket-region";
+
+/// Details of an error response returned by a store, as requested when raising
+/// a support case with the cloud provider
+///
+/// See [`Error::server_details`](crate::Error::server_details)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ServerErrorDetails {
+    /// The id the server assigned to the request, e.g. from `x-amz-request-id`,
+    /// `x-ms-request-id` or, for GCS, `x-guploader-uploadid`
+    pub request_id: Option<String>,
+    /// The id the client attached to the request, if configured with
+    /// [`RetryConfig::request_id_header`]
+    pub client_request_id: Option<String>,
+    /// The backend-specific error code, e.g. `NoSuchKey` or `BlobNotFound`
+    pub code: Option<String>,
+    /// The error message returned by the server
+    pub message: Option<String>,
+}
+
+/// The XML error body returned by S3, Azure and the GCS XML API
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct XmlErrorResponse {
+    code: Option<String>,
+    message: Option<String>,
+    request_id: Option<String>,
+}
+
+impl ServerErrorDetails {
+    /// Extract the details from the headers and body of an error response
+    pub(crate) fn new(headers: &HeaderMap, body: Option<&str>) -> Self {
+        let mut details = Self {
+            request_id: server_request_id(headers),
+            ..Default::default()
+        };
+
+        let body = match body.map(|b| b.trim_start_matches('\u{feff}').trim()) {
+            Some(body) => body,
+            None => return details,
+        };
+
+        if body.starts_with('<') {
+            if let Ok(xml) = quick_xml::de::from_str::<XmlErrorResponse>(body) {
+                details.code = xml.code;
+                details.message = xml.message;
+                details.request_id = details.request_id.or(xml.request_id);
+            }
+        } else if let Ok(json) = serde_json::from_str::<serde_json::Value>(body) {
+            // GCS JSON API errors are of the form {"error": {"code": 404, "message": ..,
+            // "errors": [{"reason": "notFound"}]}}, Azure Data Lake errors use a string code
+            let error = &json["error"];
+            details.code = error["code"]
+                .as_str()
+                .or_else(|| error["errors"][0]["reason"].as_str())
+                .or_else(|| error["status"].as_str())
+                .map(ToString::to_string);
+            details.message = error["message"].as_str().map(ToString::to_string);
+        }
+        details
+    }
+}
+
+/// Error codes returned alongside a 403 that indicate the credentials were rejected,
+/// as opposed to the request being denied by an access policy
+#[cfg(any(feature = "aws", feature = "gcp", feature = "azure"))]
+const AUTH_FAILURE_CODES: &[&str] = &[
+    // S3
+    "ExpiredToken",
+    "InvalidToken",
+    "TokenRefreshRequired",
+    // Azure
+    "AuthenticationFailed",
+    "InvalidAuthenticationInfo",
+];
 
 /// Retry request error
 #[derive(Debug, Snafu)]
 pub enum Error {
     #[snafu(display("Received redirect without LOCATION, this normally indicates an incorrectly configured region"))]
-    BareRedirect,
+    BareRedirect {
+        /// The region of the bucket as reported by the `x-amz-bucket-region` header
+        region: Option<String>,
+    },
 
     #[snafu(display("Client error with status {status}: {}", body.as_deref().unwrap_or("No Body")))]
     Client {
         status: StatusCode,
         body: Option<String>,
+        details: Box<ServerErrorDetails>,
     },
 
     #[snafu(display("Error after {retries} retries in {elapsed:?}, max_retries:{max_retries}, retry_timeout:{retry_timeout:?}, source:{source}"))]
@@ -53,17 +169,46 @@ impl Error {
     /// Returns the status code associated with this error if any
     pub fn status(&self) -> Option<StatusCode> {
         match self {
-            Self::BareRedirect => None,
+            Self::BareRedirect { .. } => None,
             Self::Client { status, .. } => Some(*status),
             Self::Reqwest { source, .. } => source.status(),
         }
     }
 
+    /// Returns true if this error indicates the credentials used to authorize the
+    /// request were rejected, and so the request may succeed with refreshed credentials
+    #[cfg(any(feature = "aws", feature = "gcp", feature = "azure"))]
+    pub(crate) fn is_auth_failure(&self) -> bool {
+        match self {
+            Self::Client {
+                status, details, ..
+            } => match *status {
+                StatusCode::UNAUTHORIZED => true,
+                StatusCode::FORBIDDEN => details
+                    .code
+                    .as_deref()
+                    .map(|code| AUTH_FAILURE_CODES.contains(&code))
+                    .unwrap_or_default(),
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
     /// Returns the error body if any
     pub fn body(&self) -> Option<&str> {
         match self {
             Self::Client { body, .. } => body.as_deref(),
-            Self::BareRedirect => None,
+            Self::BareRedirect { .. } => None,
+            Self::Reqwest { .. } => None,
+        }
+    }
+
+    /// Returns the details of the server's error response if any
+    pub fn details(&self) -> Option<&ServerErrorDetails> {
+        match self {
+            Self::Client { details, .. } => Some(details),
+            Self::BareRedirect { .. } => None,
             Self::Reqwest { .. } => None,
         }
     }
@@ -86,6 +231,13 @@ impl Error {
                 path,
                 source: Box::new(self),
             },
+            Some(StatusCode::UNAUTHORIZED) => crate::Error::Unauthenticated {
+                source: Box::new(self),
+            },
+            Some(StatusCode::FORBIDDEN) => crate::Error::PermissionDenied {
+                path,
+                source: Box::new(self),
+            },
             _ => crate::Error::Generic {
                 store,
                 source: Box::new(self),
@@ -124,13 +276,22 @@ pub type Result<T, E = Error> = std::result::Result<T, E>;
 /// The following categories of error will be retried:
 ///
 /// * 5xx server errors
-/// * Connection errors
+/// * Transient connection errors, such as a temporary DNS resolution failure or a
+///   connection reset during the TLS handshake
 /// * Dropped connections
 /// * Timeouts for [safe] / read-only requests
 ///
 /// Requests will be retried up to some limit, using exponential
 /// backoff with jitter. See [`BackoffConfig`] for more information
 ///
+/// Writes, i.e. requests without a [safe] method such as puts, deletes and posts, may
+/// be applied more than once if retried, and so are retried up to a separate, lower,
+/// limit. Reads, i.e. `GET`, `HEAD` and `OPTIONS` requests, are retried up to
+/// [`RetryConfig::max_retries`]. See [`RetryConfig::max_non_idempotent_retries`]
+///
+/// Connection errors that will not resolve on retry, such as an invalid certificate
+/// or a hostname that does not exist, are returned immediately
+///
 /// [safe]: https://datatracker.ietf.org/doc/html/rfc7231#section-4.2.1
 #[derive(Debug, Clone)]
 pub struct RetryConfig {
@@ -154,6 +315,53 @@ pub struct RetryConfig {
     /// below 5 minutes to avoid errors due to expired credentials
     /// and/or request payloads
     pub retry_timeout: Duration,
+
+    /// An optional [`RetryBudget`] shared by all requests made with this configuration
+    ///
+    /// Defaults to `None`, in which case each request is retried independently
+    pub budget: Option<RetryBudget>,
+
+    /// The maximum number of times to retry a request that is not idempotent,
+    /// i.e. a write such as a put
+    ///
+    /// If the response to such a request is lost, for example due to a dropped
+    /// connection, a retry may apply the request a second time, potentially
+    /// overwriting a concurrent write, or fail because the first attempt succeeded.
+    /// These requests are therefore retried fewer times by default.
+    ///
+    /// This is capped to [`Self::max_retries`], and defaults to 3
+    pub max_non_idempotent_retries: usize,
+
+    /// The backoff configuration for requests that are not idempotent
+    ///
+    /// Defaults to `None`, in which case [`Self::backoff`] is used
+    pub non_idempotent_backoff: Option<BackoffConfig>,
+
+    /// If set, each request is sent with a unique client-generated id in this header,
+    /// unless the request already has a value for it
+    ///
+    /// The id is reused by any retries of the request, and allows correlating the
+    /// logs of the client with those of other services. It is recorded in a
+    /// `request` tracing span alongside the id assigned by the server, and in
+    /// [`ServerErrorDetails::client_request_id`].
+    ///
+    /// The header is added after the request is signed, and so must not be one
+    /// covered by the signature, such as an `x-amz-` header for S3 or, with
+    /// shared key authorization, an `x-ms-` header for Azure. A custom header such as
+    /// `x-client-request-id` should be used instead.
+    ///
+    /// Defaults to `None`
+    pub request_id_header: Option<HeaderName>,
+
+    /// If a request fails with an authentication error, such as a 401 or a 403
+    /// reporting an expired token, discard any cached credentials and retry the
+    /// request once with freshly fetched credentials
+    ///
+    /// This handles a cached credential becoming invalid before its reported expiry,
+    /// for example due to clock skew or early revocation.
+    ///
+    /// Defaults to `true`
+    pub refresh_credentials: bool,
 }
 
 impl Default for RetryConfig {
@@ -162,129 +370,448 @@ impl Default for RetryConfig {
             backoff: Default::default(),
             max_retries: 10,
             retry_timeout: Duration::from_secs(3 * 60),
+            budget: None,
+            max_non_idempotent_retries: 3,
+            non_idempotent_backoff: None,
+            request_id_header: None,
+            refresh_credentials: true,
+        }
+    }
+}
+
+/// The maximum number of retries a [`RetryBudget`] can accumulate
+const BUDGET_CAPACITY: f64 = 100.;
+
+/// Caps the number of retries to a fraction of the number of requests
+///
+/// Without a budget, during a widespread outage every request is retried up to
+/// [`RetryConfig::max_retries`] times, multiplying the load on an already struggling
+/// service. A [`RetryBudget`] instead limits retries, across all requests sharing it,
+/// to `ratio` of the requests made, plus `min_retries_per_second` to allow retries
+/// when the request rate is low. Once the budget is exhausted, requests fail
+/// immediately instead of being retried.
+///
+/// Retries can accumulate up to a limit of 100, allowing short bursts of failures to
+/// be retried. Clones share the same budget.
+///
+/// See <https://sre.google/sre-book/handling-overload/#deciding-to-retry>
+///
+/// ```
+/// # use object_store::{RetryBudget, RetryConfig};
+/// // Allow retrying 10% of requests, and at least one request per second
+/// let retry = RetryConfig {
+///     budget: Some(RetryBudget::new(0.1, 1.)),
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Debug, Clone)]
+pub struct RetryBudget {
+    ratio: f64,
+    min_retries_per_second: f64,
+    state: Arc<Mutex<BudgetState>>,
+}
+
+#[derive(Debug)]
+struct BudgetState {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl RetryBudget {
+    /// Create a new [`RetryBudget`] permitting `ratio` retries per request, and
+    /// at least `min_retries_per_second`
+    ///
+    /// The budget starts full, with one second's worth of `min_retries_per_second`
+    pub fn new(ratio: f64, min_retries_per_second: f64) -> Self {
+        let ratio = ratio.max(0.);
+        let min_retries_per_second = min_retries_per_second.max(0.);
+        Self {
+            ratio,
+            min_retries_per_second,
+            state: Arc::new(Mutex::new(BudgetState {
+                tokens: min_retries_per_second.min(BUDGET_CAPACITY),
+                last_refill: Instant::now(),
+            })),
+        }
+    }
+
+    /// Returns the number of retries currently available
+    pub fn available(&self) -> usize {
+        let mut state = self.state.lock();
+        self.refill(&mut state);
+        state.tokens as usize
+    }
+
+    fn refill(&self, state: &mut BudgetState) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(state.last_refill).as_secs_f64();
+        state.tokens = (state.tokens + elapsed * self.min_retries_per_second).min(BUDGET_CAPACITY);
+        state.last_refill = now;
+    }
+
+    /// Record an initial request, adding `ratio` to the budget
+    fn deposit(&self) {
+        let mut state = self.state.lock();
+        state.tokens = (state.tokens + self.ratio).min(BUDGET_CAPACITY);
+    }
+
+    /// Attempt to withdraw a retry from the budget, returning false if exhausted
+    fn withdraw(&self) -> bool {
+        let mut state = self.state.lock();
+        self.refill(&mut state);
+        match state.tokens >= 1. {
+            true => {
+                state.tokens -= 1.;
+                true
+            }
+            false => false,
+        }
+    }
+}
+
+/// Returns true if `budget`, if any, permits another retry
+fn budget_permits_retry(budget: Option<&RetryBudget>) -> bool {
+    match budget {
+        Some(budget) if !budget.withdraw() => {
+            info!("Retry budget exhausted, not retrying request");
+            false
         }
+        _ => true,
     }
 }
 
 pub trait RetryExt {
     /// Dispatch a request with the given retry configuration
     ///
-    /// # Panic
-    ///
-    /// This will panic if the request body is a stream
+    /// A request with a stream body can't be replayed, and so is sent once without retrying
     fn send_retry(self, config: &RetryConfig) -> BoxFuture<'static, Result<Response>>;
+
+    /// Dispatch a request for a credential with the given retry configuration
+    ///
+    /// Unlike [`Self::send_retry`] a refused connection is not retried, as this indicates
+    /// the credential endpoint is not available, for example an instance metadata
+    /// endpoint when not running on a cloud VM
+    #[cfg(any(feature = "aws", feature = "gcp", feature = "azure"))]
+    fn send_retry_credential(self, config: &RetryConfig) -> BoxFuture<'static, Result<Response>>;
 }
 
 impl RetryExt for reqwest::RequestBuilder {
     fn send_retry(self, config: &RetryConfig) -> BoxFuture<'static, Result<Response>> {
-        let mut backoff = Backoff::new(&config.backoff);
-        let max_retries = config.max_retries;
-        let retry_timeout = config.retry_timeout;
-
-        let (client, req) = self.build_split();
-        let req = req.expect("request must be valid");
-
-        async move {
-            let mut retries = 0;
-            let now = Instant::now();
-
-            loop {
-                let s = req.try_clone().expect("request body must be cloneable");
-                match client.execute(s).await {
-                    Ok(r) => match r.error_for_status_ref() {
-                        Ok(_) if r.status().is_success() => return Ok(r),
-                        Ok(r) if r.status() == StatusCode::NOT_MODIFIED => {
-                            return Err(Error::Client {
+        send_retry(self, config, true, Idempotency::Method)
+    }
+
+    #[cfg(any(feature = "aws", feature = "gcp", feature = "azure"))]
+    fn send_retry_credential(self, config: &RetryConfig) -> BoxFuture<'static, Result<Response>> {
+        // Credential requests do not modify any state, regardless of method
+        send_retry(self, config, false, Idempotency::Always)
+    }
+}
+
+/// Returns true if the transport error `e` is likely transient, and so a request that
+/// failed with it may be retried, where `safe` indicates the request has a safe method
+/// and so may also be retried after a timeout
+pub(crate) fn is_retryable_transport_error(e: &reqwest::Error, safe: bool) -> bool {
+    let mut do_retry = false;
+    if safe && e.is_timeout() {
+        do_retry = true
+    } else if let Some(source) = e.source() {
+        if let Some(e) = source.downcast_ref::<hyper::Error>() {
+            if e.is_connect() || e.is_closed() || e.is_incomplete_message() || is_interrupted(e) {
+                do_retry = true;
+            }
+        }
+    }
+    if e.is_connect() {
+        if let Some(transient) = is_transient_connect_error(e) {
+            do_retry = transient;
+        }
+    }
+    do_retry
+}
+
+/// Returns true if `e` was caused by the connection being interrupted, for example
+/// reset or closed part way through a response body
+fn is_interrupted(e: &hyper::Error) -> bool {
+    use std::io::ErrorKind;
+    e.source()
+        .and_then(|e| e.downcast_ref::<std::io::Error>())
+        .map(|e| {
+            matches!(
+                e.kind(),
+                ErrorKind::UnexpectedEof
+                    | ErrorKind::ConnectionReset
+                    | ErrorKind::ConnectionAborted
+                    | ErrorKind::BrokenPipe
+            )
+        })
+        .unwrap_or_default()
+}
+
+/// Returns true if `e` was caused by the remote refusing the connection
+fn is_connection_refused(e: &reqwest::Error) -> bool {
+    let mut source = e.source();
+    while let Some(e) = source {
+        if let Some(e) = e.downcast_ref::<std::io::Error>() {
+            return e.kind() == std::io::ErrorKind::ConnectionRefused;
+        }
+        source = e.source();
+    }
+    false
+}
+
+/// Windows Sockets error codes for hostname resolution failures, which unlike other
+/// platforms are reported as OS errors, and so can be classified without their message
+///
+/// <https://learn.microsoft.com/en-us/windows/win32/winsock/windows-sockets-error-codes-2>
+#[cfg(windows)]
+mod wsa {
+    /// `WSAHOST_NOT_FOUND`
+    pub const HOST_NOT_FOUND: i32 = 11001;
+    /// `WSATRY_AGAIN`
+    pub const TRY_AGAIN: i32 = 11002;
+    /// `WSANO_DATA`
+    pub const NO_DATA: i32 = 11004;
+}
+
+/// Hostname resolution failures that indicate a temporary condition, i.e. `EAI_AGAIN`
+///
+/// On unix platforms the standard library reports `getaddrinfo` failures as an
+/// [`std::io::Error`] of an uncategorized kind, formatted from `gai_strerror`, and so
+/// they can only be told apart by their message. These are the messages of glibc,
+/// musl, macOS and Windows respectively, and are matched case-insensitively.
+///
+/// Messages not recognised, for example because they are localized, are not
+/// classified, in which case the error is retried as any other connection error
+const TRANSIENT_DNS_ERRORS: &[&str] = &[
+    "temporary failure in name resolution",
+    "failed to lookup address information: try again",
+    "temporary error during hostname resolution",
+];
+
+/// Hostname resolution failures that indicate the host does not exist, i.e.
+/// `EAI_NONAME`, see [`TRANSIENT_DNS_ERRORS`]
+const PERMANENT_DNS_ERRORS: &[&str] = &[
+    "name or service not known",
+    "failed to lookup address information: name does not resolve",
+    "nodename nor servname provided",
+    "no such host is known",
+    "no address associated with hostname",
+];
+
+/// Classifies a connection error from the [`std::io::Error`] that caused it, returning
+/// `Some(true)` if it is transient and `Some(false)` if retrying will not help
+///
+/// Returns `None` if the error was not caused by a recognised [`std::io::Error`]
+fn is_transient_connect_error(e: &(dyn std::error::Error + 'static)) -> Option<bool> {
+    use std::io::ErrorKind;
+
+    let mut source = Some(e);
+    while let Some(e) = source {
+        if let Some(e) = e.downcast_ref::<std::io::Error>() {
+            // The connector may wrap the underlying error in another std::io::Error
+            if let Some(inner) = e.get_ref().and_then(|e| is_transient_connect_error(e)) {
+                return Some(inner);
+            }
+            #[cfg(windows)]
+            match e.raw_os_error() {
+                Some(wsa::TRY_AGAIN) => return Some(true),
+                Some(wsa::HOST_NOT_FOUND | wsa::NO_DATA) => return Some(false),
+                _ => {}
+            }
+            return match e.kind() {
+                ErrorKind::ConnectionReset
+                | ErrorKind::ConnectionAborted
+                | ErrorKind::ConnectionRefused
+                | ErrorKind::BrokenPipe
+                | ErrorKind::UnexpectedEof
+                | ErrorKind::TimedOut
+                | ErrorKind::Interrupted => Some(true),
+                // TLS errors, such as an invalid certificate, are reported as InvalidData
+                ErrorKind::InvalidData => Some(false),
+                // Hostname resolution failures have no specific kind, see TRANSIENT_DNS_ERRORS
+                _ => {
+                    let msg = e.to_string().to_ascii_lowercase();
+                    if TRANSIENT_DNS_ERRORS.iter().any(|x| msg.contains(x)) {
+                        Some(true)
+                    } else if PERMANENT_DNS_ERRORS.iter().any(|x| msg.contains(x)) {
+                        Some(false)
+                    } else {
+                        None
+                    }
+                }
+            };
+        }
+        source = e.source();
+    }
+    None
+}
+
+/// How to determine if a request is idempotent, and so which retry limit applies
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Idempotency {
+    /// Reads, i.e. requests with a safe method, are idempotent and writes are not
+    Method,
+    /// The request does not modify any state, regardless of its method
+    Always,
+}
+
+impl Idempotency {
+    /// Returns true if `req` can be retried without risk of it being applied more
+    /// than once
+    fn is_idempotent(self, req: &reqwest::Request) -> bool {
+        match self {
+            Self::Method => req.method().is_safe(),
+            Self::Always => true,
+        }
+    }
+}
+
+fn send_retry(
+    builder: reqwest::RequestBuilder,
+    config: &RetryConfig,
+    retry_refused: bool,
+    idempotency: Idempotency,
+) -> BoxFuture<'static, Result<Response>> {
+    let (client, req) = builder.build_split();
+    let mut req = req.expect("request must be valid");
+
+    let client_request_id = config.request_id_header.as_ref().map(|name| {
+        let existing = req.headers().get(name).and_then(|v| v.to_str().ok());
+        match existing {
+            Some(id) => id.to_string(),
+            None => {
+                let id = new_client_request_id();
+                let value = HeaderValue::from_str(&id).expect("valid header value");
+                req.headers_mut().insert(name.clone(), value);
+                id
+            }
+        }
+    });
+    let traced = client_request_id.is_some();
+    let span = match &client_request_id {
+        Some(id) => {
+            tracing::info_span!("request", client_request_id = %id, server_request_id = tracing::field::Empty)
+        }
+        None => tracing::Span::none(),
+    };
+    let details = move |headers: &HeaderMap, body: Option<&str>| {
+        Box::new(ServerErrorDetails {
+            client_request_id: client_request_id.clone(),
+            ..ServerErrorDetails::new(headers, body)
+        })
+    };
+
+    let (backoff, max_retries) = match idempotency.is_idempotent(&req) {
+        true => (&config.backoff, config.max_retries),
+        false => (
+            config
+                .non_idempotent_backoff
+                .as_ref()
+                .unwrap_or(&config.backoff),
+            config.max_non_idempotent_retries.min(config.max_retries),
+        ),
+    };
+    // A request with a body that can't be replayed, such as a stream, is sent once
+    let max_retries = match req.try_clone() {
+        Some(_) => max_retries,
+        None => 0,
+    };
+    let safe = req.method().is_safe();
+    let mut req = Some(req);
+    let mut backoff = Backoff::new(backoff);
+    let retry_timeout = config.retry_timeout;
+    let budget = config.budget.clone();
+    if let Some(budget) = &budget {
+        budget.deposit();
+    }
+
+    async move {
+        let mut retries = 0;
+        let now = Instant::now();
+
+        loop {
+            let s = match req.as_ref().and_then(Request::try_clone) {
+                Some(s) => s,
+                None => req.take().expect("request without a cloneable body is not retried"),
+            };
+            let result = client.execute(s).await;
+            if let (true, Ok(r)) = (traced, &result) {
+                let span = tracing::Span::current();
+                if let Some(id) = server_request_id(r.headers()) {
+                    span.record("server_request_id", id.as_str());
+                }
+                debug!("Received response with status {}", r.status());
+            }
+            match result {
+                Ok(r) => match r.error_for_status_ref() {
+                    Ok(_) if r.status().is_success() => return Ok(r),
+                    Ok(r) if r.status() == StatusCode::NOT_MODIFIED => {
+                        return Err(Error::Client {
+                            body: None,
+                            status: StatusCode::NOT_MODIFIED,
+                            details: details(r.headers(), None),
+                        })
+                    }
+                    Ok(r) => {
+                        let is_bare_redirect = r.status().is_redirection() && !r.headers().contains_key(LOCATION);
+                        return match is_bare_redirect {
+                            true => Err(Error::BareRedirect {
+                                region: r
+                                    .headers()
+                                    .get(BUCKET_REGION)
+                                    .and_then(|v| v.to_str().ok())
+                                    .map(ToString::to_string),
+                            }),
+                            // Not actually sure if this is reachable, but here for completeness
+                            false => Err(Error::Client {
                                 body: None,
-                                status: StatusCode::NOT_MODIFIED,
+                                status: r.status(),
+                                details: details(r.headers(), None),
                             })
                         }
-                        Ok(r) => {
-                            let is_bare_redirect = r.status().is_redirection() && !r.headers().contains_key(LOCATION);
-                            return match is_bare_redirect {
-                                true => Err(Error::BareRedirect),
-                                // Not actually sure if this is reachable, but here for completeness
-                                false => Err(Error::Client {
-                                    body: None,
-                                    status: r.status(),
-                                })
-                            }
-                        }
-                        Err(e) => {
-                            let status = r.status();
-                            if retries == max_retries
-                                || now.elapsed() > retry_timeout
-                                || !status.is_server_error() {
-
-                                return Err(match status.is_client_error() {
-                                    true => match r.text().await {
-                                        Ok(body) => {
-                                            Error::Client {
-                                                body: Some(body).filter(|b| !b.is_empty()),
-                                                status,
-                                            }
-                                        }
-                                        Err(e) => {
-                                            Error::Reqwest {
-                                                retries,
-                                                max_retries,
-                                                elapsed: now.elapsed(),
-                                                retry_timeout,
-                                                source: e,
-                                            }
+                    }
+                    Err(e) => {
+                        let status = r.status();
+                        if retries == max_retries
+                            || now.elapsed() > retry_timeout
+                            || !status.is_server_error()
+                            || !budget_permits_retry(budget.as_ref()) {
+
+                            let headers = r.headers().clone();
+                            return Err(match status.is_client_error() {
+                                true => match r.text().await {
+                                    Ok(body) => {
+                                        let body = Some(body).filter(|b| !b.is_empty());
+                                        Error::Client {
+                                            details: details(&headers, body.as_deref()),
+                                            body,
+                                            status,
                                         }
                                     }
-                                    false => Error::Reqwest {
-                                        retries,
-                                        max_retries,
-                                        elapsed: now.elapsed(),
-                                        retry_timeout,
-                                        source: e,
+                                    Err(e) => {
+                                        Error::Reqwest {
+                                            retries,
+                                            max_retries,
+                                            elapsed: now.elapsed(),
+                                            retry_timeout,
+                                            source: e,
+                                        }
                                     }
-                                });
-                            }
-
-                            let sleep = backoff.next();
-                            retries += 1;
-                            info!(
-                                "Encountered server error, backing off for {} seconds, retry {} of {}: {}",
-                                sleep.as_secs_f32(),
-                                retries,
-                                max_retries,
-                                e,
-                            );
-                            tokio::time::sleep(sleep).await;
-                        }
-                    },
-                    Err(e) =>
-                    {
-                        let mut do_retry = false;
-                        if req.method().is_safe() && e.is_timeout() {
-                            do_retry = true
-                        } else if let Some(source) = e.source() {
-                            if let Some(e) = source.downcast_ref::<hyper::Error>() {
-                                if e.is_connect() || e.is_closed() || e.is_incomplete_message() {
-                                    do_retry = true;
                                 }
-                            }
+                                false => Error::Reqwest {
+                                    retries,
+                                    max_retries,
+                                    elapsed: now.elapsed(),
+                                    retry_timeout,
+                                    source: e,
+                                }
+                            });
                         }
 
-                        if retries == max_retries
-                            || now.elapsed() > retry_timeout
-                            || !do_retry {
-
-                            return Err(Error::Reqwest {
-                                retries,
-                                max_retries,
-                                elapsed: now.elapsed(),
-                                retry_timeout,
-                                source: e,
-                            })
-                        }
                         let sleep = backoff.next();
                         retries += 1;
                         info!(
-                            "Encountered transport error backing off for {} seconds, retry {} of {}: {}", 
+                            "Encountered server error, backing off for {} seconds, retry {} of {}: {}",
                             sleep.as_secs_f32(),
                             retries,
                             max_retries,
@@ -292,20 +819,54 @@ impl RetryExt for reqwest::RequestBuilder {
                         );
                         tokio::time::sleep(sleep).await;
                     }
+                },
+                Err(e) =>
+                {
+                    let mut do_retry = is_retryable_transport_error(&e, safe);
+                    if !retry_refused && is_connection_refused(&e) {
+                        do_retry = false;
+                    }
+
+                    if retries == max_retries
+                        || now.elapsed() > retry_timeout
+                        || !do_retry
+                        || !budget_permits_retry(budget.as_ref()) {
+
+                        return Err(Error::Reqwest {
+                            retries,
+                            max_retries,
+                            elapsed: now.elapsed(),
+                            retry_timeout,
+                            source: e,
+                        })
+                    }
+                    let sleep = backoff.next();
+                    retries += 1;
+                    info!(
+                        "Encountered transport error backing off for {} seconds, retry {} of {}: {}", 
+                        sleep.as_secs_f32(),
+                        retries,
+                        max_retries,
+                        e,
+                    );
+                    tokio::time::sleep(sleep).await;
                 }
             }
         }
-        .boxed()
     }
+    .instrument(span)
+    .boxed()
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::client::backoff::BackoffConfig;
     use crate::client::mock_server::MockServer;
-    use crate::client::retry::{Error, RetryExt};
+    use crate::client::retry::{Error, RetryBudget, RetryExt, ServerErrorDetails};
     use crate::RetryConfig;
     use hyper::header::LOCATION;
     use hyper::{Body, Response};
+    use reqwest::header::HeaderMap;
     use reqwest::{Client, Method, StatusCode};
     use std::time::Duration;
 
@@ -317,6 +878,7 @@ mod tests {
             backoff: Default::default(),
             max_retries: 2,
             retry_timeout: Duration::from_secs(1000),
+            ..Default::default()
         };
 
         let client = Client::builder()
@@ -433,7 +995,7 @@ mod tests {
         );
 
         let e = do_request().await.unwrap_err();
-        assert!(matches!(e, Error::BareRedirect));
+        assert!(matches!(e, Error::BareRedirect { region: None }));
         assert_eq!(e.to_string(), "Received redirect without LOCATION, this normally indicates an incorrectly configured region");
 
         // Gives up after the retrying the specified number of times
@@ -490,7 +1052,434 @@ mod tests {
             "{e}"
         );
 
+        // Does not retry request with a stream body
+        mock.push(
+            Response::builder()
+                .status(StatusCode::BAD_GATEWAY)
+                .body(Body::empty())
+                .unwrap(),
+        );
+        let stream = futures::stream::iter([Ok::<_, std::io::Error>("foo")]);
+        let res = client
+            .request(Method::PUT, mock.url())
+            .body(reqwest::Body::wrap_stream(stream))
+            .send_retry(&retry);
+        let e = res.await.unwrap_err();
+        assert_eq!(e.status(), Some(StatusCode::BAD_GATEWAY));
+        assert!(e.to_string().contains("Error after 0 retries in"), "{e}");
+
+        // Maps client errors of request with a stream body
+        mock.push(
+            Response::builder()
+                .status(StatusCode::PRECONDITION_FAILED)
+                .body(Body::empty())
+                .unwrap(),
+        );
+        let stream = futures::stream::iter([Ok::<_, std::io::Error>("foo")]);
+        let res = client
+            .request(Method::PUT, mock.url())
+            .body(reqwest::Body::wrap_stream(stream))
+            .send_retry(&retry);
+        let e = res.await.unwrap_err().error("test", "foo".to_string());
+        assert!(matches!(e, crate::Error::Precondition { .. }), "{e}");
+
         // Shutdown
         mock.shutdown().await
     }
+
+    #[cfg(any(feature = "aws", feature = "gcp", feature = "azure"))]
+    #[tokio::test]
+    async fn test_retry_credential() {
+        let retry = RetryConfig {
+            backoff: Default::default(),
+            max_retries: 2,
+            retry_timeout: Duration::from_secs(1000),
+            ..Default::default()
+        };
+        let client = Client::new();
+
+        // Refused connections are retried for data requests, but not credentials
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        drop(listener);
+
+        let e = client.get(&url).send_retry(&retry).await.unwrap_err();
+        assert!(matches!(e, Error::Reqwest { retries: 2, .. }), "{e}");
+
+        let request = client.get(&url).send_retry_credential(&retry);
+        let e = request.await.unwrap_err();
+        assert!(matches!(e, Error::Reqwest { retries: 0, .. }), "{e}");
+
+        // Server errors are retried
+        let mock = MockServer::new();
+        mock.push(
+            Response::builder()
+                .status(StatusCode::SERVICE_UNAVAILABLE)
+                .body(Body::empty())
+                .unwrap(),
+        );
+        mock.push(Response::new(Body::from("token")));
+        let request = client.get(mock.url()).send_retry_credential(&retry);
+        let r = request.await.unwrap();
+        assert_eq!(r.text().await.unwrap(), "token");
+
+        mock.shutdown().await
+    }
+
+    #[tokio::test]
+    async fn test_retry_budget() {
+        let mock = MockServer::new();
+        let budget = RetryBudget::new(0.1, 0.);
+        let retry = RetryConfig {
+            backoff: BackoffConfig {
+                init_backoff: Duration::from_millis(1),
+                max_backoff: Duration::from_millis(10),
+                base: 2.,
+            },
+            max_retries: 10,
+            retry_timeout: Duration::from_secs(1000),
+            budget: Some(budget.clone()),
+            ..Default::default()
+        };
+        let client = Client::new();
+        let error = || {
+            Response::builder()
+                .status(StatusCode::SERVICE_UNAVAILABLE)
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        // Each successful request deposits a tenth of a retry
+        for _ in 0..20 {
+            mock.push(Response::new(Body::empty()));
+            client.get(mock.url()).send_retry(&retry).await.unwrap();
+        }
+        assert_eq!(budget.available(), 2);
+
+        // A sustained outage is only retried until the budget is exhausted,
+        // despite max_retries permitting many more retries
+        for _ in 0..10 {
+            mock.push(error());
+        }
+        let e = client.get(mock.url()).send_retry(&retry).await.unwrap_err();
+        assert!(matches!(e, Error::Reqwest { retries: 2, .. }), "{e}");
+        assert_eq!(budget.available(), 0);
+
+        // Subsequent requests fail fast
+        let e = client.get(mock.url()).send_retry(&retry).await.unwrap_err();
+        assert!(matches!(e, Error::Reqwest { retries: 0, .. }), "{e}");
+
+        // Under concurrent load, retries are capped to a fraction of requests
+        for _ in 0..100 {
+            mock.push(error());
+        }
+        let requests = (0..50).map(|_| client.get(mock.url()).send_retry(&retry));
+        let results = futures::future::join_all(requests).await;
+        let retries: usize = results
+            .into_iter()
+            .map(|r| match r.unwrap_err() {
+                Error::Reqwest { retries, .. } => retries,
+                e => panic!("{e}"),
+            })
+            .sum();
+        assert!(retries <= 5, "{retries}");
+
+        // A minimum rate of retries is permitted regardless of request rate
+        let budget = RetryBudget::new(0., 50.);
+        for _ in 0..50 {
+            assert!(budget.withdraw());
+        }
+        assert!(!budget.withdraw());
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(budget.available() >= 4, "{}", budget.available());
+
+        mock.shutdown().await
+    }
+
+    #[tokio::test]
+    async fn test_retry_idempotency() {
+        let mock = MockServer::new();
+        let backoff = BackoffConfig {
+            init_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(10),
+            base: 2.,
+        };
+        let retry = RetryConfig {
+            backoff,
+            max_retries: 5,
+            max_non_idempotent_retries: 1,
+            ..Default::default()
+        };
+        let client = Client::new();
+
+        let failures = |n: usize| {
+            for _ in 0..n {
+                mock.push(
+                    Response::builder()
+                        .status(StatusCode::BAD_GATEWAY)
+                        .body(Body::empty())
+                        .unwrap(),
+                );
+            }
+        };
+        let retries = |e: Error| match e {
+            Error::Reqwest { retries, .. } => retries,
+            e => panic!("{e}"),
+        };
+
+        // Under identical failures, a get is retried more than an unconditional put
+        failures(6);
+        let e = client.get(mock.url()).send_retry(&retry).await.unwrap_err();
+        assert_eq!(retries(e), 5);
+
+        failures(6);
+        let e = client.put(mock.url()).send_retry(&retry).await.unwrap_err();
+        assert_eq!(retries(e), 1);
+
+        // A read is retried past the remaining failures
+        client.get(mock.url()).send_retry(&retry).await.unwrap();
+
+        failures(5);
+        client.head(mock.url()).send_retry(&retry).await.unwrap();
+
+        // All writes, conditional or not, use the lower limit
+        let writes = [
+            client.put(mock.url()).header("If-None-Match", "*"),
+            client.put(mock.url()).header("If-Match", "\"1\""),
+            client.delete(mock.url()),
+            client.post(mock.url()),
+        ];
+        for write in writes {
+            failures(2);
+            let e = write.send_retry(&retry).await.unwrap_err();
+            assert_eq!(retries(e), 1);
+        }
+
+        // Credential requests are retried up to max_retries regardless of method
+        #[cfg(any(feature = "aws", feature = "gcp", feature = "azure"))]
+        {
+            failures(5);
+            let post = client.post(mock.url());
+            post.send_retry_credential(&retry).await.unwrap();
+        }
+
+        // The cap for non-idempotent requests never exceeds max_retries
+        let retry = RetryConfig {
+            max_retries: 0,
+            ..retry
+        };
+        failures(1);
+        let e = client
+            .post(mock.url())
+            .send_retry(&retry)
+            .await
+            .unwrap_err();
+        assert_eq!(retries(e), 0);
+
+        mock.shutdown().await
+    }
+
+    #[tokio::test]
+    async fn test_client_request_id() {
+        use hyper::Request;
+        use parking_lot::Mutex;
+        use std::sync::Arc;
+
+        let mock = MockServer::new();
+        let retry = RetryConfig {
+            backoff: BackoffConfig {
+                init_backoff: Duration::from_millis(1),
+                ..Default::default()
+            },
+            request_id_header: Some("x-client-request-id".parse().unwrap()),
+            ..Default::default()
+        };
+        let client = Client::new();
+
+        let ids = Arc::new(Mutex::new(vec![]));
+        let record = |status: StatusCode| {
+            let ids = Arc::clone(&ids);
+            move |req: Request<Body>| {
+                let id = req.headers()["x-client-request-id"].to_str().unwrap();
+                ids.lock().push(id.to_string());
+                Response::builder()
+                    .status(status)
+                    .header("x-amz-request-id", "server-id")
+                    .body(Body::empty())
+                    .unwrap()
+            }
+        };
+
+        // Retries of a request reuse its id
+        mock.push_fn(record(StatusCode::INTERNAL_SERVER_ERROR));
+        mock.push_fn(record(StatusCode::OK));
+        let do_request = || client.request(Method::GET, mock.url()).send_retry(&retry);
+        do_request().await.unwrap();
+
+        // Whereas each request has a unique id, recorded in the error details
+        mock.push_fn(record(StatusCode::FORBIDDEN));
+        let e = do_request().await.unwrap_err();
+        let details = e.details().unwrap();
+        assert_eq!(details.request_id.as_deref(), Some("server-id"));
+
+        let ids = ids.lock().clone();
+        assert_eq!(ids.len(), 3);
+        assert_eq!(ids[0], ids[1]);
+        assert_ne!(ids[1], ids[2]);
+        assert_eq!(details.client_request_id.as_ref(), Some(&ids[2]));
+        assert_eq!(ids[0].len(), 36);
+        assert_eq!(ids[0].as_bytes()[14], b'4');
+
+        // An existing value is preserved
+        mock.push_fn(|req| {
+            assert_eq!(req.headers()["x-client-request-id"], "my-id");
+            Response::new(Body::empty())
+        });
+        client
+            .request(Method::GET, mock.url())
+            .header("x-client-request-id", "my-id")
+            .send_retry(&retry)
+            .await
+            .unwrap();
+
+        // No header is attached by default
+        mock.push_fn(|req| {
+            assert!(!req.headers().contains_key("x-client-request-id"));
+            Response::new(Body::empty())
+        });
+        client
+            .request(Method::GET, mock.url())
+            .send_retry(&RetryConfig::default())
+            .await
+            .unwrap();
+
+        mock.shutdown().await
+    }
+
+    #[tokio::test]
+    async fn test_server_error_details() {
+        let mock = MockServer::new();
+        let retry = RetryConfig::default();
+        let client = Client::new();
+
+        mock.push(
+            Response::builder()
+                .status(StatusCode::NOT_FOUND)
+                .header("x-amz-request-id", "4442587FB7D0A2F9")
+                .body(Body::from(
+                    r#"<?xml version="1.0" encoding="UTF-8"?>
+<Error>
+  <Code>NoSuchKey</Code>
+  <Message>The resource you requested does not exist</Message>
+  <Resource>/bucket/file</Resource>
+  <RequestId>4442587FB7D0A2F9</RequestId>
+</Error>"#,
+                ))
+                .unwrap(),
+        );
+        let e = client
+            .request(Method::GET, mock.url())
+            .send_retry(&retry)
+            .await
+            .unwrap_err()
+            .error("S3", "file".to_string());
+        assert!(matches!(e, crate::Error::NotFound { .. }));
+        let details = e.server_details().unwrap();
+        assert_eq!(details.request_id.as_deref(), Some("4442587FB7D0A2F9"));
+        assert_eq!(details.code.as_deref(), Some("NoSuchKey"));
+        assert_eq!(
+            details.message.as_deref(),
+            Some("The resource you requested does not exist")
+        );
+
+        let mut headers = HeaderMap::new();
+        headers.insert("x-ms-request-id", "b2c5a4e8-601e".parse().unwrap());
+        let body = "\u{feff}<?xml version=\"1.0\" encoding=\"utf-8\"?><Error><Code>BlobNotFound</Code><Message>The specified blob does not exist.</Message></Error>";
+        let details = ServerErrorDetails::new(&headers, Some(body));
+        assert_eq!(details.request_id.as_deref(), Some("b2c5a4e8-601e"));
+        assert_eq!(details.code.as_deref(), Some("BlobNotFound"));
+        assert_eq!(
+            details.message.as_deref(),
+            Some("The specified blob does not exist.")
+        );
+
+        let mut headers = HeaderMap::new();
+        headers.insert("x-guploader-uploadid", "ADPycdv".parse().unwrap());
+        let body = r#"{"error": {"code": 403, "message": "Access denied.", "errors": [{"message": "Access denied.", "domain": "global", "reason": "forbidden"}]}}"#;
+        let details = ServerErrorDetails::new(&headers, Some(body));
+        assert_eq!(details.request_id.as_deref(), Some("ADPycdv"));
+        assert_eq!(details.code.as_deref(), Some("forbidden"));
+        assert_eq!(details.message.as_deref(), Some("Access denied."));
+
+        let details = ServerErrorDetails::new(&HeaderMap::new(), Some("cupcakes"));
+        assert_eq!(details, ServerErrorDetails::default());
+
+        mock.shutdown().await
+    }
+
+    #[test]
+    fn test_transient_connect_error() {
+        use super::is_transient_connect_error;
+        use std::io::{Error, ErrorKind};
+
+        let dns = |msg: &str| Error::new(ErrorKind::Other, format!("dns error: {msg}"));
+        let cases = [
+            (Error::from(ErrorKind::ConnectionReset), Some(true)),
+            (Error::from(ErrorKind::UnexpectedEof), Some(true)),
+            (
+                Error::new(ErrorKind::InvalidData, "invalid peer certificate"),
+                Some(false),
+            ),
+            (
+                dns("failed to lookup address information: Temporary failure in name resolution"),
+                Some(true),
+            ),
+            (
+                dns("failed to lookup address information: Name or service not known"),
+                Some(false),
+            ),
+            // musl
+            (
+                dns("failed to lookup address information: Try again"),
+                Some(true),
+            ),
+            (
+                dns("failed to lookup address information: Name does not resolve"),
+                Some(false),
+            ),
+            // macOS
+            (
+                dns("failed to lookup address information: nodename nor servname provided, or not known"),
+                Some(false),
+            ),
+            // Windows
+            (
+                dns("No such host is known. (os error 11001)"),
+                Some(false),
+            ),
+            (
+                dns("This is usually a temporary error during hostname resolution and means that the local server did not receive a response from an authoritative server. (os error 11002)"),
+                Some(true),
+            ),
+            // Unrecognised, e.g. localized, messages are not classified
+            (
+                dns("failed to lookup address information: Nom ou service inconnu"),
+                None,
+            ),
+            (dns("something else"), None),
+        ];
+        for (e, expected) in cases {
+            assert_eq!(is_transient_connect_error(&e), expected, "{e}");
+        }
+
+        // The io::Error may be wrapped by other errors
+        let nested = Error::new(ErrorKind::Other, Error::from(ErrorKind::InvalidData));
+        assert_eq!(is_transient_connect_error(&nested), Some(false));
+
+        let wrapped = crate::Error::Generic {
+            store: "test",
+            source: Box::new(Error::from(ErrorKind::ConnectionReset)),
+        };
+        assert_eq!(is_transient_connect_error(&wrapped), Some(true));
+    }
 }
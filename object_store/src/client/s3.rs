@@ -17,7 +17,7 @@
 //! The list and multipart API used by both GCS and S3
 
 use crate::multipart::PartId;
-use crate::path::Path;
+use crate::path::{Path, DELIMITER};
 use crate::{ListResult, ObjectMeta, Result};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
@@ -31,6 +31,13 @@ pub struct ListResponse {
     pub common_prefixes: Vec<ListPrefix>,
     #[serde(default)]
     pub next_continuation_token: Option<String>,
+    #[serde(default)]
+    pub is_truncated: bool,
+    /// Only returned by [ListObjects] (V1) with a delimiter
+    ///
+    /// [ListObjects]: https://docs.aws.amazon.com/AmazonS3/latest/API/API_ListObjects.html
+    #[serde(default)]
+    pub next_marker: Option<String>,
 }
 
 impl TryFrom<ListResponse> for ListResult {
@@ -72,6 +79,33 @@ pub struct ListContents {
     pub e_tag: Option<String>,
 }
 
+impl ListResponse {
+    /// Removes zero-byte directory markers, i.e. keys ending in `/`, from the contents
+    pub fn remove_directory_markers(&mut self) {
+        self.contents
+            .retain(|c| !(c.size == 0 && c.key.ends_with(DELIMITER)))
+    }
+
+    /// Returns the marker of the next page of a [ListObjects] (V1) response, if any
+    ///
+    /// This is `NextMarker` if provided, otherwise the greatest key or common prefix
+    /// of this page, and so must be called before removing any of the contents
+    ///
+    /// [ListObjects]: https://docs.aws.amazon.com/AmazonS3/latest/API/API_ListObjects.html
+    #[cfg(feature = "aws")]
+    pub fn next_marker(&mut self) -> Option<String> {
+        if !self.is_truncated {
+            return None;
+        }
+        if let Some(marker) = self.next_marker.take() {
+            return Some(marker);
+        }
+        let key = self.contents.last().map(|c| c.key.as_str());
+        let prefix = self.common_prefixes.last().map(|p| p.prefix.as_str());
+        key.max(prefix).map(ToString::to_string)
+    }
+}
+
 impl TryFrom<ListContents> for ObjectMeta {
     type Error = crate::Error;
 
@@ -82,16 +116,126 @@ impl TryFrom<ListContents> for ObjectMeta {
             size: value.size,
             e_tag: value.e_tag,
             version: None,
+            checksum: None,
         })
     }
 }
 
+#[cfg(feature = "aws")]
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ListMultipartUploadsResult {
+    #[serde(default, rename = "Upload")]
+    pub uploads: Vec<MultipartUpload>,
+    #[serde(default)]
+    pub is_truncated: bool,
+    #[serde(default)]
+    pub next_key_marker: Option<String>,
+    #[serde(default)]
+    pub next_upload_id_marker: Option<String>,
+}
+
+#[cfg(feature = "aws")]
+impl ListMultipartUploadsResult {
+    /// Returns the key and upload ID markers of the next page, if any
+    fn next_markers(&mut self) -> Option<(String, String)> {
+        if !self.is_truncated {
+            return None;
+        }
+        let key = self.next_key_marker.take()?;
+        Some((key, self.next_upload_id_marker.take().unwrap_or_default()))
+    }
+}
+
+#[cfg(feature = "aws")]
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct MultipartUpload {
+    pub key: String,
+    pub upload_id: String,
+    pub initiated: DateTime<Utc>,
+}
+
+/// Aborts the multipart uploads initiated before `older_than`, returning the number aborted
+///
+/// Each page of uploads is fetched with `list`, given the key and upload ID markers of
+/// the previous page, and each upload aborted with `abort`. Uploads to keys that are
+/// not valid [`Path`] cannot be aborted with `abort`, and are skipped.
+#[cfg(feature = "aws")]
+pub async fn abort_incomplete_uploads<L, LF, A, AF>(
+    list: L,
+    abort: A,
+    older_than: DateTime<Utc>,
+) -> Result<usize>
+where
+    L: Fn(Option<(String, String)>) -> LF,
+    LF: std::future::Future<Output = Result<ListMultipartUploadsResult>>,
+    A: Fn(Path, crate::MultipartId) -> AF,
+    AF: std::future::Future<Output = Result<()>>,
+{
+    let mut markers = None;
+    let mut aborted = 0;
+    loop {
+        let mut page = list(markers.take()).await?;
+        for upload in std::mem::take(&mut page.uploads) {
+            if upload.initiated >= older_than {
+                continue;
+            }
+            let path = match Path::parse(upload.key) {
+                Ok(path) => path,
+                Err(_) => continue,
+            };
+            match abort(path, upload.upload_id).await {
+                Ok(()) => aborted += 1,
+                // Completed or aborted concurrently
+                Err(crate::Error::NotFound { .. }) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        match page.next_markers() {
+            Some(next) => markers = Some(next),
+            None => return Ok(aborted),
+        }
+    }
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "PascalCase")]
 pub struct InitiateMultipartUploadResult {
     pub upload_id: String,
 }
 
+#[cfg(feature = "aws")]
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct CopyPartResult {
+    #[serde(rename = "ETag")]
+    pub e_tag: String,
+}
+
+#[cfg(feature = "aws")]
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Tagging {
+    pub tag_set: TagList,
+}
+
+#[cfg(feature = "aws")]
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct TagList {
+    #[serde(default)]
+    pub tag: Vec<Tag>,
+}
+
+#[cfg(feature = "aws")]
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Tag {
+    pub key: String,
+    pub value: String,
+}
+
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "PascalCase")]
 pub struct CompleteMultipartUpload {
@@ -102,10 +246,9 @@ impl From<Vec<PartId>> for CompleteMultipartUpload {
     fn from(value: Vec<PartId>) -> Self {
         let part = value
             .into_iter()
-            .enumerate()
-            .map(|(part_number, part)| MultipartPart {
+            .map(|part| MultipartPart {
+                part_number: part.part_idx() + 1,
                 e_tag: part.content_id,
-                part_number: part_number + 1,
             })
             .collect();
         Self { part }
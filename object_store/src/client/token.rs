@@ -77,4 +77,9 @@ impl<T: Clone + Send> TokenCache<T> {
 
         Ok(token)
     }
+
+    /// Discard the cached token, if any
+    pub async fn clear(&self) {
+        *self.cache.lock().await = None;
+    }
 }
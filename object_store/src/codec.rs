@@ -0,0 +1,472 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! An object store wrapper mapping application keys to backend-safe keys
+
+use std::fmt::{Debug, Display, Formatter};
+use std::ops::Range;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::stream::BoxStream;
+use futures::{StreamExt, TryStreamExt};
+use snafu::Snafu;
+use tokio::io::AsyncWrite;
+use url::Url;
+
+use crate::path::{Path, DELIMITER};
+use crate::{
+    Acl, CopyOptions, DeleteOptions, GetOptions, GetResult, ListPage, ListResult, MultipartId,
+    ObjectMeta, ObjectStore, PutOptions, PutResult, Result,
+};
+
+#[derive(Debug, Snafu)]
+enum Error {
+    #[snafu(display("Key segment \"{segment}\" is not a valid encoding: {reason}"))]
+    InvalidEncoding { segment: String, reason: String },
+}
+
+impl From<Error> for crate::Error {
+    fn from(source: Error) -> Self {
+        Self::Generic {
+            store: "KeyCodecStore",
+            source: Box::new(source),
+        }
+    }
+}
+
+/// A reversible mapping between the path segments of application keys, and the
+/// path segments stored in a backend
+///
+/// The mapping is applied to each segment of a [`Path`] individually, preserving
+/// the hierarchy of keys, and therefore prefix listing and [`ObjectStore::list_with_delimiter`].
+///
+/// Implementations must be bijective, i.e. `decode(encode(s)) == s` for all segments,
+/// and `encode` must not produce a segment containing [`DELIMITER`] or that is
+/// empty, `.` or `..`. [`KeyCodecStore`] additionally checks that `encode(decode(s)) == s`
+/// for all keys read from the backend, returning an error for keys not produced by `encode`.
+pub trait KeyCodec: Debug + Send + Sync + 'static {
+    /// Encode a segment of an application key into the segment stored in the backend
+    fn encode(&self, segment: &str) -> String;
+
+    /// Decode a segment stored in the backend, the inverse of [`Self::encode`]
+    fn decode(&self, segment: &str) -> Result<String>;
+}
+
+/// A [`KeyCodec`] that percent-encodes a configurable set of characters, along with
+/// `%` itself so that the encoding is reversible
+///
+/// ```
+/// # use object_store::codec::{KeyCodec, PercentKeyCodec};
+/// let codec = PercentKeyCodec::default();
+/// assert_eq!(codec.encode("a b:c"), "a%20b%3Ac");
+/// assert_eq!(codec.decode("a%20b%3Ac").unwrap(), "a b:c");
+/// ```
+#[derive(Debug, Clone)]
+pub struct PercentKeyCodec {
+    reserved: Vec<char>,
+}
+
+impl Default for PercentKeyCodec {
+    /// Percent-encode spaces and colons, which are rejected or mishandled by some
+    /// backends and filesystems
+    fn default() -> Self {
+        Self::new([' ', ':'])
+    }
+}
+
+impl PercentKeyCodec {
+    /// Create a new [`PercentKeyCodec`] that percent-encodes `reserved` and `%`
+    pub fn new(reserved: impl IntoIterator<Item = char>) -> Self {
+        let mut reserved: Vec<_> = reserved.into_iter().collect();
+        reserved.push('%');
+        Self { reserved }
+    }
+}
+
+impl KeyCodec for PercentKeyCodec {
+    fn encode(&self, segment: &str) -> String {
+        let mut out = String::with_capacity(segment.len());
+        for c in segment.chars() {
+            match self.reserved.contains(&c) {
+                true => {
+                    let mut buf = [0; 4];
+                    for b in c.encode_utf8(&mut buf).bytes() {
+                        out.push_str(&format!("%{b:02X}"));
+                    }
+                }
+                false => out.push(c),
+            }
+        }
+        out
+    }
+
+    fn decode(&self, segment: &str) -> Result<String> {
+        let decoded = percent_encoding::percent_decode_str(segment).decode_utf8();
+        match decoded {
+            Ok(decoded) => Ok(decoded.into_owned()),
+            Err(e) => Err(Error::InvalidEncoding {
+                segment: segment.to_string(),
+                reason: e.to_string(),
+            }
+            .into()),
+        }
+    }
+}
+
+/// Store wrapper that applies a [`KeyCodec`] to all paths handled by the store
+///
+/// Paths are encoded before being passed to the wrapped store, and paths returned
+/// by it, e.g. by [`ObjectStore::list`], are decoded back to the original keys.
+///
+/// As the wrapped store orders listings by the encoded keys, [`ObjectStore::list_with_offset`]
+/// is implemented by filtering a full listing on the decoded keys.
+///
+/// ```
+/// # use object_store::codec::{KeyCodecStore, PercentKeyCodec};
+/// # use object_store::memory::InMemory;
+/// # use object_store::path::Path;
+/// # use object_store::ObjectStore;
+/// # use futures::TryStreamExt;
+/// # use std::sync::Arc;
+/// # async fn example() -> object_store::Result<()> {
+/// let inner: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+/// let store = KeyCodecStore::new(Arc::clone(&inner), PercentKeyCodec::default());
+///
+/// store.put(&Path::from("logs/12:00 UTC"), "data".into()).await?;
+/// inner.head(&Path::from("logs/12%3A00%20UTC")).await?;
+///
+/// let listed: Vec<_> = store.list(None).map_ok(|m| m.location).try_collect().await?;
+/// assert_eq!(listed, vec![Path::from("logs/12:00 UTC")]);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct KeyCodecStore<T: ObjectStore> {
+    inner: T,
+    codec: Arc<dyn KeyCodec>,
+}
+
+impl<T: ObjectStore> Display for KeyCodecStore<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "KeyCodecStore({})", self.inner)
+    }
+}
+
+impl<T: ObjectStore> KeyCodecStore<T> {
+    /// Create a new [`KeyCodecStore`] applying `codec` to the paths of `inner`
+    pub fn new(inner: T, codec: impl KeyCodec) -> Self {
+        Self {
+            inner,
+            codec: Arc::new(codec),
+        }
+    }
+
+    /// Returns the path stored in the wrapped store for `location`
+    pub fn encode_path(&self, location: &Path) -> Result<Path> {
+        let encoded: Vec<_> = location
+            .parts()
+            .map(|part| self.codec.encode(part.as_ref()))
+            .collect();
+        Ok(Path::parse(encoded.join(DELIMITER))?)
+    }
+
+    /// Returns the application path for `location` in the wrapped store
+    pub fn decode_path(&self, location: &Path) -> Result<Path> {
+        let decoded = location
+            .parts()
+            .map(|part| {
+                let decoded = self.codec.decode(part.as_ref())?;
+                match self.codec.encode(&decoded) == part.as_ref() {
+                    true => Ok(decoded),
+                    false => Err(Error::InvalidEncoding {
+                        segment: part.as_ref().to_string(),
+                        reason: "not produced by the codec".to_string(),
+                    }
+                    .into()),
+                }
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Path::parse(decoded.join(DELIMITER))?)
+    }
+
+    fn decode_meta(&self, meta: ObjectMeta) -> Result<ObjectMeta> {
+        Ok(ObjectMeta {
+            location: self.decode_path(&meta.location)?,
+            ..meta
+        })
+    }
+
+    fn decode_list(&self, result: ListResult) -> Result<ListResult> {
+        Ok(ListResult {
+            common_prefixes: result
+                .common_prefixes
+                .iter()
+                .map(|p| self.decode_path(p))
+                .collect::<Result<_>>()?,
+            objects: result
+                .objects
+                .into_iter()
+                .map(|m| self.decode_meta(m))
+                .collect::<Result<_>>()?,
+        })
+    }
+
+    fn encode_prefix(&self, prefix: Option<&Path>) -> Result<Option<Path>> {
+        prefix.map(|p| self.encode_path(p)).transpose()
+    }
+}
+
+#[async_trait]
+impl<T: ObjectStore> ObjectStore for KeyCodecStore<T> {
+    async fn put(&self, location: &Path, bytes: Bytes) -> Result<PutResult> {
+        let location = self.encode_path(location)?;
+        self.inner.put(&location, bytes).await
+    }
+
+    async fn put_opts(&self, location: &Path, bytes: Bytes, opts: PutOptions) -> Result<PutResult> {
+        let location = self.encode_path(location)?;
+        self.inner.put_opts(&location, bytes, opts).await
+    }
+
+    async fn put_multipart(
+        &self,
+        location: &Path,
+    ) -> Result<(MultipartId, Box<dyn AsyncWrite + Unpin + Send>)> {
+        let location = self.encode_path(location)?;
+        self.inner.put_multipart(&location).await
+    }
+
+    async fn abort_multipart(&self, location: &Path, multipart_id: &MultipartId) -> Result<()> {
+        let location = self.encode_path(location)?;
+        self.inner.abort_multipart(&location, multipart_id).await
+    }
+
+    async fn get_opts(&self, location: &Path, options: GetOptions) -> Result<GetResult> {
+        let encoded = self.encode_path(location)?;
+        let mut result = self.inner.get_opts(&encoded, options).await?;
+        result.meta.location = location.clone();
+        Ok(result)
+    }
+
+    async fn get_range(&self, location: &Path, range: Range<usize>) -> Result<Bytes> {
+        let location = self.encode_path(location)?;
+        self.inner.get_range(&location, range).await
+    }
+
+    async fn get_ranges(&self, location: &Path, ranges: &[Range<usize>]) -> Result<Vec<Bytes>> {
+        let location = self.encode_path(location)?;
+        self.inner.get_ranges(&location, ranges).await
+    }
+
+    async fn head(&self, location: &Path) -> Result<ObjectMeta> {
+        let encoded = self.encode_path(location)?;
+        let meta = self.inner.head(&encoded).await?;
+        Ok(ObjectMeta {
+            location: location.clone(),
+            ..meta
+        })
+    }
+
+    async fn delete(&self, location: &Path) -> Result<()> {
+        let location = self.encode_path(location)?;
+        self.inner.delete(&location).await
+    }
+
+    async fn delete_opts(&self, location: &Path, opts: DeleteOptions) -> Result<()> {
+        let location = self.encode_path(location)?;
+        self.inner.delete_opts(&location, opts).await
+    }
+
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'_, Result<ObjectMeta>> {
+        let prefix = match self.encode_prefix(prefix) {
+            Ok(prefix) => prefix,
+            Err(e) => return futures::stream::once(async { Err(e) }).boxed(),
+        };
+        self.inner
+            .list(prefix.as_ref())
+            .and_then(|meta| futures::future::ready(self.decode_meta(meta)))
+            .boxed()
+    }
+
+    fn list_from_token(
+        &self,
+        prefix: Option<&Path>,
+        token: Option<&str>,
+    ) -> BoxStream<'_, Result<ListPage>> {
+        let prefix = match self.encode_prefix(prefix) {
+            Ok(prefix) => prefix,
+            Err(e) => return futures::stream::once(async { Err(e) }).boxed(),
+        };
+        self.inner
+            .list_from_token(prefix.as_ref(), token)
+            .and_then(|page| {
+                let objects = page
+                    .objects
+                    .into_iter()
+                    .map(|m| self.decode_meta(m))
+                    .collect::<Result<_>>();
+                let next_token = page.next_token;
+                futures::future::ready(objects.map(|objects| ListPage {
+                    objects,
+                    next_token,
+                }))
+            })
+            .boxed()
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
+        let prefix = self.encode_prefix(prefix)?;
+        let result = self.inner.list_with_delimiter(prefix.as_ref()).await?;
+        self.decode_list(result)
+    }
+
+    fn list_with_delimiter_stream(
+        &self,
+        prefix: Option<&Path>,
+    ) -> BoxStream<'_, Result<ListResult>> {
+        let prefix = match self.encode_prefix(prefix) {
+            Ok(prefix) => prefix,
+            Err(e) => return futures::stream::once(async { Err(e) }).boxed(),
+        };
+        self.inner
+            .list_with_delimiter_stream(prefix.as_ref())
+            .and_then(|result| futures::future::ready(self.decode_list(result)))
+            .boxed()
+    }
+
+    async fn copy(&self, from: &Path, to: &Path) -> Result<()> {
+        let (from, to) = (self.encode_path(from)?, self.encode_path(to)?);
+        self.inner.copy(&from, &to).await
+    }
+
+    async fn copy_opts(&self, from: &Path, to: &Path, opts: CopyOptions) -> Result<()> {
+        let (from, to) = (self.encode_path(from)?, self.encode_path(to)?);
+        self.inner.copy_opts(&from, &to, opts).await
+    }
+
+    async fn probe(&self) -> Result<()> {
+        self.inner.probe().await
+    }
+
+    async fn get_acl(&self, location: &Path) -> Result<Acl> {
+        let location = self.encode_path(location)?;
+        self.inner.get_acl(&location).await
+    }
+
+    async fn rename(&self, from: &Path, to: &Path) -> Result<()> {
+        let (from, to) = (self.encode_path(from)?, self.encode_path(to)?);
+        self.inner.rename(&from, &to).await
+    }
+
+    async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        let (from, to) = (self.encode_path(from)?, self.encode_path(to)?);
+        self.inner.copy_if_not_exists(&from, &to).await
+    }
+
+    async fn rename_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        let (from, to) = (self.encode_path(from)?, self.encode_path(to)?);
+        self.inner.rename_if_not_exists(&from, &to).await
+    }
+
+    fn object_url(&self, location: &Path) -> Result<Url> {
+        let location = self.encode_path(location)?;
+        self.inner.object_url(&location)
+    }
+
+    fn validate_path(&self, location: &Path) -> Result<()> {
+        let location = self.encode_path(location)?;
+        self.inner.validate_path(&location)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::InMemory;
+    use crate::test_util::flatten_list_stream;
+    use crate::tests::*;
+
+    #[tokio::test]
+    async fn key_codec_test() {
+        let integration = KeyCodecStore::new(InMemory::new(), PercentKeyCodec::default());
+
+        put_get_delete_list(&integration).await;
+        get_opts(&integration).await;
+        list_uses_directories_correctly(&integration).await;
+        list_with_delimiter(&integration).await;
+        rename_and_copy(&integration).await;
+        copy_if_not_exists(&integration).await;
+        stream_get(&integration).await;
+    }
+
+    #[tokio::test]
+    async fn key_codec_round_trip() {
+        let inner: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+        let codec = PercentKeyCodec::new([':', ' ', 'é']);
+        let store = KeyCodecStore::new(Arc::clone(&inner), codec);
+
+        let keys = [
+            Path::from("a:b/c d"),
+            Path::from("a:b/100%"),
+            Path::from("a:b/%3A"),
+            Path::from("a:b/café"),
+            Path::from_iter(["a:b", "x/y"]),
+        ];
+        for key in &keys {
+            store.put(key, "data".into()).await.unwrap();
+            assert_eq!(&store.head(key).await.unwrap().location, key);
+            assert_eq!(&store.get(key).await.unwrap().meta.location, key);
+        }
+
+        let stored = flatten_list_stream(&inner, None).await.unwrap();
+        for path in &stored {
+            assert!(!path.as_ref().contains([':', ' ', 'é']), "{path}");
+        }
+
+        let mut listed = flatten_list_stream(&store, None).await.unwrap();
+        listed.sort();
+        let mut expected = keys.to_vec();
+        expected.sort();
+        assert_eq!(listed, expected);
+
+        let prefix = Path::from("a:b");
+        let result = store.list_with_delimiter(None).await.unwrap();
+        assert_eq!(result.common_prefixes, vec![prefix.clone()]);
+        let result = store.list_with_delimiter(Some(&prefix)).await.unwrap();
+        assert_eq!(result.objects.len(), keys.len());
+
+        let offset = &expected[1];
+        let listed: Vec<_> = store
+            .list_with_offset(None, offset)
+            .map_ok(|m| m.location)
+            .try_collect()
+            .await
+            .unwrap();
+        assert!(listed.iter().all(|p| p > offset));
+        assert_eq!(listed.len(), expected.len() - 2);
+
+        // Keys not produced by the codec are rejected when listing
+        inner
+            .put(&Path::from("a:b/raw:key"), "data".into())
+            .await
+            .unwrap();
+        let err = flatten_list_stream(&store, None).await.unwrap_err();
+        assert!(err.to_string().contains("not a valid encoding"), "{err}");
+    }
+}
@@ -112,6 +112,38 @@ impl Parse for HeaderValue {
     }
 }
 
+/// A size in bytes, parsed with [`crate::parse_byte_size`]
+#[cfg(any(feature = "aws", feature = "gcp", feature = "azure"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct ByteSize(pub usize);
+
+#[cfg(any(feature = "aws", feature = "gcp", feature = "azure"))]
+impl Display for ByteSize {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[cfg(any(feature = "aws", feature = "gcp", feature = "azure"))]
+impl From<usize> for ByteSize {
+    fn from(value: usize) -> Self {
+        Self(value)
+    }
+}
+
+#[cfg(any(feature = "aws", feature = "gcp", feature = "azure"))]
+impl Parse for ByteSize {
+    fn parse(v: &str) -> Result<Self> {
+        let bytes = crate::parse_byte_size(v)?;
+        usize::try_from(bytes)
+            .map(Self)
+            .map_err(|_| Error::Generic {
+                store: "Config",
+                source: format!("size \"{v}\" exceeds the addressable memory").into(),
+            })
+    }
+}
+
 pub(crate) fn fmt_duration(duration: &ConfigValue<Duration>) -> String {
     match duration {
         ConfigValue::Parsed(v) => format_duration(*v).to_string(),
@@ -119,6 +151,31 @@ pub(crate) fn fmt_duration(duration: &ConfigValue<Duration>) -> String {
     }
 }
 
+/// The value reported in place of a secret by [`config_snapshot`]
+pub(crate) const REDACTED: &str = "<redacted>";
+
+/// Collects the value of each of `keys` into a map keyed by the canonical key name
+///
+/// Keys for which `get` returns `None` are omitted, and the values of keys for which
+/// `is_secret` returns `true` are replaced with [`REDACTED`] unless `include_secrets` is set
+#[cfg(any(feature = "aws", feature = "gcp", feature = "azure"))]
+pub(crate) fn config_snapshot<K: AsRef<str>>(
+    keys: impl IntoIterator<Item = K>,
+    include_secrets: bool,
+    is_secret: impl Fn(&K) -> bool,
+    get: impl Fn(&K) -> Option<String>,
+) -> std::collections::BTreeMap<String, String> {
+    keys.into_iter()
+        .filter_map(|key| {
+            let value = match !include_secrets && is_secret(&key) {
+                true => get(&key).map(|_| REDACTED.to_string())?,
+                false => get(&key)?,
+            };
+            Some((key.as_ref().to_string(), value))
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -131,4 +188,22 @@ mod tests {
         assert_eq!(Duration::parse("60 s").unwrap(), duration);
         assert_eq!(Duration::parse("60s").unwrap(), duration)
     }
+
+    #[cfg(any(feature = "aws", feature = "gcp", feature = "azure"))]
+    #[test]
+    fn test_parse_byte_size() {
+        assert_eq!(ByteSize::parse("0").unwrap(), ByteSize(0));
+        assert_eq!(ByteSize::parse("1024").unwrap(), ByteSize(1024));
+        assert_eq!(ByteSize::parse("5MB").unwrap(), ByteSize(5_000_000));
+        assert_eq!(ByteSize::parse("5MiB").unwrap(), ByteSize(5 * 1024 * 1024));
+        assert_eq!(ByteSize::parse("5 mib").unwrap(), ByteSize(5 * 1024 * 1024));
+        assert_eq!(ByteSize::parse("16KiB").unwrap(), ByteSize(16 * 1024));
+        assert_eq!(ByteSize::parse("2B").unwrap(), ByteSize(2));
+        assert_eq!(ByteSize::parse("1.5MiB").unwrap(), ByteSize(1536 * 1024));
+
+        for invalid in ["", "MiB", "-5MiB", "5XB", "5 MiB extra"] {
+            let err = ByteSize::parse(invalid).unwrap_err().to_string();
+            assert!(err.contains("Invalid size"), "{invalid}: {err}");
+        }
+    }
 }
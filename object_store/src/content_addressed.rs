@@ -0,0 +1,128 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Utilities for storing objects by the digest of their contents
+
+use bytes::Bytes;
+use ring::digest;
+
+use crate::path::Path;
+use crate::{Error, ObjectStore, Result};
+
+/// The digest algorithm used by [`put_content_addressed`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DigestAlgorithm {
+    /// SHA-256
+    #[default]
+    Sha256,
+    /// SHA-512
+    Sha512,
+}
+
+impl DigestAlgorithm {
+    /// The name of this algorithm, used as the first segment of content-addressed paths
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Sha256 => "sha256",
+            Self::Sha512 => "sha512",
+        }
+    }
+
+    fn algorithm(&self) -> &'static digest::Algorithm {
+        match self {
+            Self::Sha256 => &digest::SHA256,
+            Self::Sha512 => &digest::SHA512,
+        }
+    }
+}
+
+/// Returns the content-addressed path of `bytes` computed with `algorithm`
+///
+/// This is `{algorithm}/{hex[..2]}/{hex[2..]}` where `hex` is the lowercase hex encoded
+/// digest, e.g. `sha256/2c/f24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824`,
+/// with the two character fan-out avoiding very large listings of a single prefix
+pub fn content_addressed_path(bytes: &[u8], algorithm: DigestAlgorithm) -> Path {
+    let digest = digest::digest(algorithm.algorithm(), bytes);
+    let hex: String = digest.as_ref().iter().map(|b| format!("{b:02x}")).collect();
+    Path::from_iter([algorithm.name(), &hex[..2], &hex[2..]])
+}
+
+/// Write `bytes` to `store` at the path derived from their digest, returning this path
+///
+/// See [`content_addressed_path`] for the path layout. If an object already exists at
+/// this path, as determined by [`ObjectStore::head`], it is assumed to have identical
+/// contents and the write is skipped.
+///
+/// Note: concurrent calls with the same contents may both perform the write, however,
+/// as the contents are identical the final object is the same
+pub async fn put_content_addressed(
+    store: &dyn ObjectStore,
+    bytes: Bytes,
+    algorithm: DigestAlgorithm,
+) -> Result<Path> {
+    let path = content_addressed_path(&bytes, algorithm);
+    match store.head(&path).await {
+        Ok(_) => return Ok(path),
+        Err(Error::NotFound { .. }) => {}
+        Err(e) => return Err(e),
+    }
+    store.put(&path, bytes).await?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::InMemory;
+
+    #[tokio::test]
+    async fn test_put_content_addressed() {
+        let store = InMemory::new();
+        let data = Bytes::from_static(b"hello world");
+
+        let path = put_content_addressed(&store, data.clone(), DigestAlgorithm::default())
+            .await
+            .unwrap();
+        assert_eq!(
+            path.as_ref(),
+            "sha256/b9/4d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9"
+        );
+        let meta = store.head(&path).await.unwrap();
+
+        // Identical content yields the same path without rewriting the object
+        let repeat = put_content_addressed(&store, data.clone(), DigestAlgorithm::Sha256)
+            .await
+            .unwrap();
+        assert_eq!(repeat, path);
+        assert_eq!(store.head(&path).await.unwrap().e_tag, meta.e_tag);
+
+        let read = store.get(&path).await.unwrap().bytes().await.unwrap();
+        assert_eq!(read, data);
+
+        let other = put_content_addressed(&store, "other".into(), DigestAlgorithm::Sha256)
+            .await
+            .unwrap();
+        assert_ne!(other, path);
+
+        let sha512 = put_content_addressed(&store, data, DigestAlgorithm::Sha512)
+            .await
+            .unwrap();
+        assert!(sha512.as_ref().starts_with("sha512/"), "{sha512}");
+        assert_eq!(sha512.filename().unwrap().len(), 126);
+    }
+}
@@ -0,0 +1,690 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! An object store wrapper injecting failures, for testing
+//!
+//! [`FaultStore`] wraps another [`ObjectStore`], and can be programmed with a set of
+//! [`FaultRule`] to fail operations, inject latency or truncate the streams returned
+//! by [`ObjectStore::get`]. This is useful for exercising the error handling, retry
+//! and resumption logic of code built on top of [`ObjectStore`].
+//!
+//! Any randomness is derived from a seed, and so a given sequence of operations
+//! will encounter the same faults on every run.
+//!
+//! This module requires the `faults` feature
+
+use std::fmt::{Debug, Display, Formatter};
+use std::ops::Range;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::stream::BoxStream;
+use futures::StreamExt;
+use parking_lot::Mutex;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use tokio::io::AsyncWrite;
+use url::Url;
+
+use crate::path::Path;
+use crate::{
+    Acl, CopyOptions, DeleteOptions, Error, GetOptions, GetResult, GetResultPayload, ListPage,
+    ListResult, MultipartId, ObjectMeta, ObjectStore, PutOptions, PutResult, Result,
+};
+
+const STORE: &str = "FaultStore";
+
+/// The type of an operation performed against a [`FaultStore`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Operation {
+    /// [`ObjectStore::put_opts`]
+    Put,
+    /// [`ObjectStore::put_multipart`]
+    PutMultipart,
+    /// [`ObjectStore::abort_multipart`]
+    AbortMultipart,
+    /// [`ObjectStore::get_opts`], and the get methods built upon it
+    Get,
+    /// [`ObjectStore::head`] and [`ObjectStore::get_acl`]
+    Head,
+    /// [`ObjectStore::delete`] and [`ObjectStore::delete_opts`]
+    Delete,
+    /// [`ObjectStore::list`], [`ObjectStore::list_with_offset`] and
+    /// [`ObjectStore::list_with_delimiter`], as well as [`ObjectStore::probe`]
+    List,
+    /// [`ObjectStore::copy`] and [`ObjectStore::copy_if_not_exists`]
+    Copy,
+}
+
+/// The kind of [`Error`] returned by [`Fault::Error`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FaultError {
+    /// [`Error::Generic`]
+    Generic,
+    /// [`Error::NotFound`]
+    NotFound,
+    /// [`Error::AlreadyExists`]
+    AlreadyExists,
+    /// [`Error::Precondition`]
+    Precondition,
+    /// [`Error::NotModified`]
+    NotModified,
+    /// [`Error::NotSupported`]
+    NotSupported,
+}
+
+impl FaultError {
+    fn error(&self, op: Operation, location: Option<&Path>) -> Error {
+        let source = Box::new(InjectedFault { op });
+        let path = location.map(ToString::to_string).unwrap_or_default();
+        match self {
+            Self::Generic => Error::Generic {
+                store: STORE,
+                source,
+            },
+            Self::NotFound => Error::NotFound { path, source },
+            Self::AlreadyExists => Error::AlreadyExists { path, source },
+            Self::Precondition => Error::Precondition { path, source },
+            Self::NotModified => Error::NotModified { path, source },
+            Self::NotSupported => Error::NotSupported { source },
+        }
+    }
+}
+
+/// The source of errors injected by a [`FaultStore`]
+#[derive(Debug)]
+struct InjectedFault {
+    op: Operation,
+}
+
+impl Display for InjectedFault {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "injected fault for {:?} operation", self.op)
+    }
+}
+
+impl std::error::Error for InjectedFault {}
+
+/// A fault injected by a [`FaultRule`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    /// Fail the operation with the given kind of error
+    Error(FaultError),
+    /// Delay the operation by the given duration before performing it
+    Latency(Duration),
+    /// Return an error from the stream of a [`Operation::Get`] after the given
+    /// number of bytes, simulating a connection dropped mid-transfer
+    ///
+    /// This has no effect on other operations
+    TruncateGet(usize),
+}
+
+/// When a [`FaultRule`] applies to an operation it matches
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Trigger {
+    Always,
+    Nth(usize),
+    Every(usize),
+    Probability(f64),
+}
+
+/// A rule determining when a [`FaultStore`] injects a [`Fault`]
+///
+/// By default a rule applies to every operation, this can be restricted with
+/// [`Self::on`], and [`Self::nth`], [`Self::every`] or [`Self::with_probability`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct FaultRule {
+    fault: Fault,
+    operations: Vec<Operation>,
+    trigger: Trigger,
+    path_prefix: Option<Path>,
+}
+
+impl FaultRule {
+    /// Create a new [`FaultRule`] injecting `fault` into every operation
+    pub fn new(fault: Fault) -> Self {
+        Self {
+            fault,
+            operations: vec![],
+            trigger: Trigger::Always,
+            path_prefix: None,
+        }
+    }
+
+    /// Only apply this rule to `op`, may be called multiple times to match
+    /// several kinds of operation
+    pub fn on(mut self, op: Operation) -> Self {
+        self.operations.push(op);
+        self
+    }
+
+    /// Only apply this rule to operations on paths under `prefix`
+    ///
+    /// Operations without a path, such as listing the entire store, never match
+    pub fn with_prefix(mut self, prefix: Path) -> Self {
+        self.path_prefix = Some(prefix);
+        self
+    }
+
+    /// Only apply this rule to the `n`th matching operation, starting from 1
+    pub fn nth(mut self, n: usize) -> Self {
+        self.trigger = Trigger::Nth(n);
+        self
+    }
+
+    /// Apply this rule to every `n`th matching operation, starting from 1
+    pub fn every(mut self, n: usize) -> Self {
+        self.trigger = Trigger::Every(n.max(1));
+        self
+    }
+
+    /// Apply this rule to matching operations with the given probability, in the range
+    /// `0.0..=1.0`, using the random number generator seeded by [`FaultStore::with_seed`]
+    pub fn with_probability(mut self, probability: f64) -> Self {
+        self.trigger = Trigger::Probability(probability.clamp(0., 1.));
+        self
+    }
+
+    fn matches(&self, op: Operation, location: Option<&Path>) -> bool {
+        let op_matches = self.operations.is_empty() || self.operations.contains(&op);
+        let path_matches = match (&self.path_prefix, location) {
+            (None, _) => true,
+            (Some(prefix), Some(location)) => location.prefix_matches(prefix),
+            (Some(_), None) => false,
+        };
+        op_matches && path_matches
+    }
+}
+
+#[derive(Debug)]
+struct State {
+    rules: Vec<(FaultRule, usize)>,
+    rng: StdRng,
+}
+
+/// The faults to inject into a single operation
+#[derive(Debug, Default)]
+struct Faults {
+    error: Option<FaultError>,
+    latency: Duration,
+    truncate: Option<usize>,
+}
+
+/// Store wrapper that injects failures according to a set of [`FaultRule`]
+///
+/// ```
+/// # use std::time::Duration;
+/// # use object_store::faults::{Fault, FaultError, FaultRule, FaultStore, Operation};
+/// # use object_store::memory::InMemory;
+/// let store = FaultStore::new(InMemory::new())
+///     .with_seed(42)
+///     // Fail the third get request
+///     .with_rule(FaultRule::new(Fault::Error(FaultError::Generic)).on(Operation::Get).nth(3))
+///     // Delay 10% of all requests by 100 milliseconds
+///     .with_rule(FaultRule::new(Fault::Latency(Duration::from_millis(100))).with_probability(0.1));
+/// ```
+pub struct FaultStore<T: ObjectStore> {
+    inner: Arc<T>,
+    state: Arc<Mutex<State>>,
+}
+
+impl<T: ObjectStore> Debug for FaultStore<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FaultStore")
+            .field("inner", &self.inner)
+            .field("rules", &self.state.lock().rules)
+            .finish()
+    }
+}
+
+impl<T: ObjectStore> Display for FaultStore<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "FaultStore({})", self.inner)
+    }
+}
+
+impl<T: ObjectStore> FaultStore<T> {
+    /// Create a new [`FaultStore`] wrapping `inner`, without any rules and with a seed of 0
+    pub fn new(inner: T) -> Self {
+        Self {
+            inner: Arc::new(inner),
+            state: Arc::new(Mutex::new(State {
+                rules: vec![],
+                rng: StdRng::seed_from_u64(0),
+            })),
+        }
+    }
+
+    /// Seed the random number generator used by [`FaultRule::with_probability`]
+    pub fn with_seed(self, seed: u64) -> Self {
+        self.state.lock().rng = StdRng::seed_from_u64(seed);
+        self
+    }
+
+    /// Add a [`FaultRule`] to this store
+    pub fn with_rule(self, rule: FaultRule) -> Self {
+        self.add_rule(rule);
+        self
+    }
+
+    /// Add a [`FaultRule`] to this store, applying to subsequent operations
+    pub fn add_rule(&self, rule: FaultRule) {
+        self.state.lock().rules.push((rule, 0));
+    }
+
+    /// Remove all rules from this store
+    pub fn clear_rules(&self) {
+        self.state.lock().rules.clear();
+    }
+
+    /// Returns the wrapped store
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    /// Determine the faults to inject into an operation
+    fn faults(&self, op: Operation, location: Option<&Path>) -> Faults {
+        let mut state = self.state.lock();
+        let State { rules, rng } = &mut *state;
+
+        let mut faults = Faults::default();
+        for (rule, count) in rules.iter_mut() {
+            if !rule.matches(op, location) {
+                continue;
+            }
+            *count += 1;
+            let triggered = match rule.trigger {
+                Trigger::Always => true,
+                Trigger::Nth(n) => *count == n,
+                Trigger::Every(n) => *count % n == 0,
+                Trigger::Probability(p) => rng.gen_bool(p),
+            };
+            if !triggered {
+                continue;
+            }
+            match rule.fault {
+                Fault::Error(e) => {
+                    faults.error.get_or_insert(e);
+                }
+                Fault::Latency(d) => faults.latency += d,
+                Fault::TruncateGet(n) => {
+                    faults.truncate = Some(faults.truncate.map_or(n, |t| t.min(n)));
+                }
+            }
+        }
+        faults
+    }
+
+    /// Applies the latency and error faults for an operation, returning any truncation
+    async fn inject(&self, op: Operation, location: Option<&Path>) -> Result<Option<usize>> {
+        let faults = self.faults(op, location);
+        if !faults.latency.is_zero() {
+            tokio::time::sleep(faults.latency).await;
+        }
+        match faults.error {
+            Some(e) => Err(e.error(op, location)),
+            None => Ok(faults.truncate),
+        }
+    }
+
+    fn inject_stream<'a, S>(
+        &'a self,
+        op: Operation,
+        location: Option<&'a Path>,
+        f: impl FnOnce() -> BoxStream<'a, Result<S>> + Send + 'a,
+    ) -> BoxStream<'a, Result<S>>
+    where
+        S: Send + 'a,
+    {
+        futures::stream::once(async move {
+            match self.inject(op, location).await {
+                Ok(_) => f(),
+                Err(e) => futures::stream::once(async { Err(e) }).boxed(),
+            }
+        })
+        .flatten()
+        .boxed()
+    }
+}
+
+/// Truncate the payload of `result` after `limit` bytes
+fn truncate(result: GetResult, limit: usize) -> GetResult {
+    let meta = result.meta.clone();
+    let range = result.range.clone();
+    let path = meta.location.clone();
+    let stream = result.into_stream();
+
+    // The state is the number of bytes remaining, or `None` once the limit is exceeded
+    let stream = futures::stream::unfold(
+        (stream, Some(limit), false),
+        move |(mut stream, remaining, done)| {
+            let path = path.clone();
+            async move {
+                if done {
+                    return None;
+                }
+                let remaining = match remaining {
+                    Some(remaining) => remaining,
+                    None => {
+                        let e = FaultError::Generic.error(Operation::Get, Some(&path));
+                        return Some((Err(e), (stream, None, true)));
+                    }
+                };
+                match stream.next().await {
+                    Some(Ok(bytes)) if bytes.len() <= remaining => {
+                        let remaining = remaining - bytes.len();
+                        Some((Ok(bytes), (stream, Some(remaining), false)))
+                    }
+                    Some(Ok(bytes)) => Some((Ok(bytes.slice(..remaining)), (stream, None, false))),
+                    Some(Err(e)) => Some((Err(e), (stream, None, true))),
+                    None => None,
+                }
+            }
+        },
+    );
+
+    GetResult {
+        payload: GetResultPayload::Stream(stream.boxed()),
+        meta,
+        range,
+    }
+}
+
+#[async_trait]
+impl<T: ObjectStore> ObjectStore for FaultStore<T> {
+    async fn put_opts(&self, location: &Path, bytes: Bytes, opts: PutOptions) -> Result<PutResult> {
+        self.inject(Operation::Put, Some(location)).await?;
+        self.inner.put_opts(location, bytes, opts).await
+    }
+
+    async fn put_multipart(
+        &self,
+        location: &Path,
+    ) -> Result<(MultipartId, Box<dyn AsyncWrite + Unpin + Send>)> {
+        self.inject(Operation::PutMultipart, Some(location)).await?;
+        self.inner.put_multipart(location).await
+    }
+
+    async fn abort_multipart(&self, location: &Path, multipart_id: &MultipartId) -> Result<()> {
+        self.inject(Operation::AbortMultipart, Some(location))
+            .await?;
+        self.inner.abort_multipart(location, multipart_id).await
+    }
+
+    async fn get_opts(&self, location: &Path, options: GetOptions) -> Result<GetResult> {
+        let truncation = self.inject(Operation::Get, Some(location)).await?;
+        let result = self.inner.get_opts(location, options).await?;
+        Ok(match truncation {
+            Some(limit) => truncate(result, limit),
+            None => result,
+        })
+    }
+
+    async fn get_range(&self, location: &Path, range: Range<usize>) -> Result<Bytes> {
+        let options = GetOptions {
+            range: Some(range.into()),
+            ..Default::default()
+        };
+        self.get_opts(location, options).await?.bytes().await
+    }
+
+    async fn head(&self, location: &Path) -> Result<ObjectMeta> {
+        self.inject(Operation::Head, Some(location)).await?;
+        self.inner.head(location).await
+    }
+
+    async fn delete(&self, location: &Path) -> Result<()> {
+        self.inject(Operation::Delete, Some(location)).await?;
+        self.inner.delete(location).await
+    }
+
+    async fn delete_opts(&self, location: &Path, opts: DeleteOptions) -> Result<()> {
+        self.inject(Operation::Delete, Some(location)).await?;
+        self.inner.delete_opts(location, opts).await
+    }
+
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'_, Result<ObjectMeta>> {
+        let prefix = prefix.cloned();
+        self.inject_stream(Operation::List, None, move || {
+            self.inner.list(prefix.as_ref())
+        })
+    }
+
+    fn list_with_offset(
+        &self,
+        prefix: Option<&Path>,
+        offset: &Path,
+    ) -> BoxStream<'_, Result<ObjectMeta>> {
+        let prefix = prefix.cloned();
+        let offset = offset.clone();
+        self.inject_stream(Operation::List, None, move || {
+            self.inner.list_with_offset(prefix.as_ref(), &offset)
+        })
+    }
+
+    fn list_range(&self, start: &Path, end: &Path) -> BoxStream<'_, Result<ObjectMeta>> {
+        let (start, end) = (start.clone(), end.clone());
+        self.inject_stream(Operation::List, None, move || {
+            self.inner.list_range(&start, &end)
+        })
+    }
+
+    fn list_from_token(
+        &self,
+        prefix: Option<&Path>,
+        token: Option<&str>,
+    ) -> BoxStream<'_, Result<ListPage>> {
+        let prefix = prefix.cloned();
+        let token = token.map(ToString::to_string);
+        self.inject_stream(Operation::List, None, move || {
+            self.inner
+                .list_from_token(prefix.as_ref(), token.as_deref())
+        })
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
+        self.inject(Operation::List, prefix).await?;
+        self.inner.list_with_delimiter(prefix).await
+    }
+
+    fn list_with_delimiter_stream(
+        &self,
+        prefix: Option<&Path>,
+    ) -> BoxStream<'_, Result<ListResult>> {
+        let prefix = prefix.cloned();
+        self.inject_stream(Operation::List, None, move || {
+            self.inner.list_with_delimiter_stream(prefix.as_ref())
+        })
+    }
+
+    async fn copy(&self, from: &Path, to: &Path) -> Result<()> {
+        self.inject(Operation::Copy, Some(to)).await?;
+        self.inner.copy(from, to).await
+    }
+
+    async fn copy_opts(&self, from: &Path, to: &Path, opts: CopyOptions) -> Result<()> {
+        self.inject(Operation::Copy, Some(to)).await?;
+        self.inner.copy_opts(from, to, opts).await
+    }
+
+    async fn probe(&self) -> Result<()> {
+        self.inject(Operation::List, None).await?;
+        self.inner.probe().await
+    }
+
+    async fn get_acl(&self, location: &Path) -> Result<Acl> {
+        self.inject(Operation::Head, Some(location)).await?;
+        self.inner.get_acl(location).await
+    }
+
+    async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        self.inject(Operation::Copy, Some(to)).await?;
+        self.inner.copy_if_not_exists(from, to).await
+    }
+
+    fn object_url(&self, location: &Path) -> Result<Url> {
+        self.inner.object_url(location)
+    }
+
+    fn validate_path(&self, location: &Path) -> Result<()> {
+        self.inner.validate_path(location)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::InMemory;
+    use crate::test_util::flatten_list_stream;
+    use crate::tests::*;
+    use futures::TryStreamExt;
+    use std::time::Instant;
+
+    #[tokio::test]
+    async fn fault_store_test() {
+        let integration = FaultStore::new(InMemory::new());
+
+        put_get_delete_list(&integration).await;
+        get_opts(&integration).await;
+        list_uses_directories_correctly(&integration).await;
+        list_with_delimiter(&integration).await;
+        rename_and_copy(&integration).await;
+        copy_if_not_exists(&integration).await;
+        stream_get(&integration).await;
+    }
+
+    #[tokio::test]
+    async fn test_nth_error() {
+        let error = Fault::Error(FaultError::NotFound);
+        let store = FaultStore::new(InMemory::new())
+            .with_rule(FaultRule::new(error).on(Operation::Get).nth(2));
+
+        let path = Path::from("file");
+        store.put(&path, "data".into()).await.unwrap();
+
+        store.get(&path).await.unwrap();
+        let err = store.get(&path).await.unwrap_err();
+        assert!(matches!(err, Error::NotFound { .. }), "{err}");
+        assert!(err.to_string().contains("injected fault"), "{err}");
+        store.get(&path).await.unwrap();
+        store.head(&path).await.unwrap();
+
+        store.add_rule(FaultRule::new(Fault::Error(FaultError::Generic)).on(Operation::List));
+        assert!(flatten_list_stream(&store, None).await.is_err());
+        assert!(store.list_with_delimiter(None).await.is_err());
+        let err = store.list_from_token(None, None).next().await.unwrap();
+        assert!(err.unwrap_err().to_string().contains("injected fault"));
+        let err = store.list_with_delimiter_stream(None).next().await.unwrap();
+        assert!(err.unwrap_err().to_string().contains("injected fault"));
+
+        store.clear_rules();
+        assert_eq!(
+            flatten_list_stream(&store, None).await.unwrap(),
+            vec![path.clone()]
+        );
+        let page = store.list_from_token(None, None).next().await.unwrap();
+        assert_eq!(page.unwrap().objects[0].location, path);
+        let result = store.list_with_delimiter_stream(None).next().await.unwrap();
+        assert_eq!(result.unwrap().objects[0].location, path);
+    }
+
+    #[tokio::test]
+    async fn test_prefix() {
+        let error = Fault::Error(FaultError::Precondition);
+        let store = FaultStore::new(InMemory::new())
+            .with_rule(FaultRule::new(error).with_prefix(Path::from("bad")));
+
+        store.put(&Path::from("good/a"), "a".into()).await.unwrap();
+        let err = store.put(&Path::from("bad/a"), "a".into()).await;
+        assert!(matches!(err, Err(Error::Precondition { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_truncate_get() {
+        let store = FaultStore::new(InMemory::new());
+        let path = Path::from("file");
+        store.put(&path, Bytes::from(vec![0; 100])).await.unwrap();
+
+        store.add_rule(FaultRule::new(Fault::TruncateGet(40)).nth(1));
+        let chunks: Vec<_> = store
+            .get(&path)
+            .await
+            .unwrap()
+            .into_stream()
+            .collect()
+            .await;
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0].as_ref().unwrap().len(), 40);
+        assert!(chunks[1].is_err());
+
+        let data = store.get(&path).await.unwrap().bytes().await.unwrap();
+        assert_eq!(data.len(), 100);
+
+        // Truncating to the full length does not error
+        store.add_rule(FaultRule::new(Fault::TruncateGet(100)));
+        let result = store.get(&path).await.unwrap();
+        let chunks: Vec<_> = result.into_stream().try_collect().await.unwrap();
+        assert_eq!(chunks.iter().map(|c| c.len()).sum::<usize>(), 100);
+    }
+
+    #[tokio::test]
+    async fn test_latency() {
+        let latency = Fault::Latency(Duration::from_millis(50));
+        let store =
+            FaultStore::new(InMemory::new()).with_rule(FaultRule::new(latency).on(Operation::Put));
+
+        let start = Instant::now();
+        store.put(&Path::from("a"), "a".into()).await.unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(50));
+    }
+
+    #[tokio::test]
+    async fn test_deterministic() {
+        async fn failures(seed: u64) -> Vec<bool> {
+            let error = Fault::Error(FaultError::Generic);
+            let store = FaultStore::new(InMemory::new())
+                .with_seed(seed)
+                .with_rule(FaultRule::new(error).with_probability(0.5));
+
+            let mut failures = vec![];
+            for i in 0..64 {
+                let path = Path::from(format!("{i}"));
+                failures.push(store.put(&path, "data".into()).await.is_err());
+            }
+            failures
+        }
+
+        let a = failures(1).await;
+        assert_eq!(a, failures(1).await);
+        assert_ne!(a, failures(2).await);
+        assert!(a.iter().any(|x| *x) && a.iter().any(|x| !*x));
+
+        // Every
+        let error = Fault::Error(FaultError::Generic);
+        let store = FaultStore::new(InMemory::new())
+            .with_rule(FaultRule::new(error).on(Operation::Head).every(3));
+        let path = Path::from("file");
+        store.put(&path, "data".into()).await.unwrap();
+        let mut results = vec![];
+        for _ in 0..6 {
+            results.push(store.head(&path).await.is_ok());
+        }
+        assert_eq!(results, vec![true, true, false, true, true, false]);
+    }
+}
@@ -0,0 +1,81 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use crate::{Acl, AclGrant, AclGrantee, AclPermission};
+use serde::Deserialize;
+
+/// The owner and ACL fields of the JSON object resource
+///
+/// <https://cloud.google.com/storage/docs/json_api/v1/objects#resource>
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ObjectAclResource {
+    owner: Option<ObjectOwner>,
+    /// Absent for buckets with uniform bucket-level access
+    #[serde(default)]
+    acl: Vec<ObjectAccessControl>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ObjectOwner {
+    entity: String,
+}
+
+/// <https://cloud.google.com/storage/docs/json_api/v1/objectAccessControls#resource>
+#[derive(Debug, Deserialize)]
+struct ObjectAccessControl {
+    entity: String,
+    role: String,
+}
+
+impl From<ObjectAclResource> for Acl {
+    fn from(value: ObjectAclResource) -> Self {
+        let grants = value
+            .acl
+            .into_iter()
+            .filter_map(|control| {
+                let permission = match control.role.as_str() {
+                    "OWNER" => AclPermission::FullControl,
+                    "READER" => AclPermission::Read,
+                    "WRITER" => AclPermission::Write,
+                    _ => return None,
+                };
+                Some(AclGrant::new(grantee(control.entity), permission))
+            })
+            .collect();
+
+        Self {
+            owner: value.owner.map(|o| o.entity),
+            grants,
+        }
+    }
+}
+
+/// Returns the [`AclGrantee`] of an entity, e.g. `user-{email}` or `group-{email}`
+///
+/// <https://cloud.google.com/storage/docs/access-control/lists#scopes>
+fn grantee(entity: String) -> AclGrantee {
+    match entity.as_str() {
+        "allUsers" => AclGrantee::AllUsers,
+        "allAuthenticatedUsers" => AclGrantee::AuthenticatedUsers,
+        _ => match entity.strip_prefix("user-") {
+            Some(user) => AclGrantee::User(user.to_string()),
+            // Groups, domains and project teams
+            None => AclGrantee::Group(entity),
+        },
+    }
+}
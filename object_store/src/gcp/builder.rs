@@ -16,15 +16,24 @@
 // under the License.
 
 use crate::client::TokenCredentialProvider;
-use crate::gcp::client::{GoogleCloudStorageClient, GoogleCloudStorageConfig};
+use crate::config::{config_snapshot, ByteSize, ConfigValue};
+use crate::gcp::client::{GoogleCloudStorageClient, GoogleCloudStorageConfig, CANNED_ACLS};
 use crate::gcp::credential::{
     ApplicationDefaultCredentials, InstanceCredentialProvider, ServiceAccountCredentials,
     DEFAULT_GCS_BASE_URL,
 };
-use crate::gcp::{credential, GcpCredential, GcpCredentialProvider, GoogleCloudStorage, STORE};
-use crate::{ClientConfigKey, ClientOptions, Result, RetryConfig, StaticCredentialProvider};
+use crate::gcp::{
+    credential, GcpCredential, GcpCredentialProvider, GcsListFields, GoogleCloudStorage, STORE,
+};
+use crate::multipart::{
+    validate_multipart_config, DEFAULT_CONCURRENCY, DEFAULT_PART_SIZE, MAX_PART_SIZE, MIN_PART_SIZE,
+};
+use crate::{
+    CannedAcl, ClientConfigKey, ClientOptions, Result, RetryConfig, StaticCredentialProvider,
+};
 use serde::{Deserialize, Serialize};
 use snafu::{OptionExt, ResultExt, Snafu};
+use std::collections::BTreeMap;
 use std::str::FromStr;
 use std::sync::Arc;
 use url::Url;
@@ -107,6 +116,18 @@ pub struct GoogleCloudStorageBuilder {
     client_options: ClientOptions,
     /// Credentials
     credentials: Option<GcpCredentialProvider>,
+    /// Size of the parts uploaded by multipart uploads
+    multipart_part_size: Option<ConfigValue<ByteSize>>,
+    /// Number of parts uploaded concurrently by multipart uploads
+    multipart_concurrency: Option<ConfigValue<usize>>,
+    /// Grow the size of parts uploaded by multipart uploads
+    multipart_part_size_scaling: ConfigValue<bool>,
+    /// Skip signing requests
+    skip_signature: ConfigValue<bool>,
+    /// The fields requested when listing with the JSON API
+    list_fields: Option<ConfigValue<GcsListFields>>,
+    /// The canned ACL applied to written objects
+    canned_acl: Option<ConfigValue<CannedAcl>>,
 }
 
 /// Configuration keys for [`GoogleCloudStorageBuilder`]
@@ -155,10 +176,90 @@ pub enum GoogleConfigKey {
     /// See [`GoogleCloudStorageBuilder::with_application_credentials`].
     ApplicationCredentials,
 
+    /// The size of the parts uploaded by [`ObjectStore::put_multipart`]
+    ///
+    /// Accepts a number of bytes with an optional unit, e.g. `16MiB`.
+    /// See [`GoogleCloudStorageBuilder::with_multipart_part_size`] for details
+    ///
+    /// Supported keys:
+    /// - `google_multipart_part_size`
+    /// - `multipart_part_size`
+    ///
+    /// [`ObjectStore::put_multipart`]: crate::ObjectStore::put_multipart
+    MultipartPartSize,
+
+    /// The number of parts uploaded concurrently by [`ObjectStore::put_multipart`]
+    ///
+    /// Supported keys:
+    /// - `google_multipart_concurrency`
+    /// - `multipart_concurrency`
+    ///
+    /// [`ObjectStore::put_multipart`]: crate::ObjectStore::put_multipart
+    MultipartConcurrency,
+
+    /// Grow the size of the parts uploaded by [`ObjectStore::put_multipart`]
+    ///
+    /// See [`GoogleCloudStorageBuilder::with_multipart_part_size_scaling`] for details
+    ///
+    /// Supported keys:
+    /// - `google_multipart_part_size_scaling`
+    /// - `multipart_part_size_scaling`
+    ///
+    /// [`ObjectStore::put_multipart`]: crate::ObjectStore::put_multipart
+    MultipartPartSizeScaling,
+
+    /// Skip signing request
+    ///
+    /// See [`GoogleCloudStorageBuilder::with_skip_signature`] for details.
+    ///
+    /// Supported keys:
+    /// - `google_skip_signature`
+    /// - `skip_signature`
+    SkipSignature,
+
+    /// List objects with the JSON API, requesting only the given fields
+    ///
+    /// See [`GoogleCloudStorageBuilder::with_list_fields`] for details.
+    ///
+    /// Supported keys:
+    /// - `google_list_fields`
+    /// - `list_fields`
+    ListFields,
+
+    /// The canned ACL applied to written objects
+    ///
+    /// See [`GoogleCloudStorageBuilder::with_canned_acl`] for details.
+    ///
+    /// Supported keys:
+    /// - `google_canned_acl`
+    /// - `canned_acl`
+    CannedAcl,
+
     /// Client options
     Client(ClientConfigKey),
 }
 
+impl GoogleConfigKey {
+    /// All variants of [`GoogleConfigKey`] other than [`GoogleConfigKey::Client`]
+    const KEYS: &'static [Self] = &[
+        Self::ServiceAccount,
+        Self::ServiceAccountKey,
+        Self::Bucket,
+        Self::ApplicationCredentials,
+        Self::MultipartPartSize,
+        Self::MultipartConcurrency,
+        Self::MultipartPartSizeScaling,
+        Self::SkipSignature,
+        Self::ListFields,
+        Self::CannedAcl,
+    ];
+
+    /// Returns true if the value of this key should not be disclosed
+    fn is_secret(&self) -> bool {
+        matches!(self, Self::ServiceAccountKey)
+    }
+}
+
 impl AsRef<str> for GoogleConfigKey {
     fn as_ref(&self) -> &str {
         match self {
@@ -166,6 +267,12 @@ impl AsRef<str> for GoogleConfigKey {
             Self::ServiceAccountKey => "google_service_account_key",
             Self::Bucket => "google_bucket",
             Self::ApplicationCredentials => "google_application_credentials",
+            Self::MultipartPartSize => "google_multipart_part_size",
+            Self::MultipartConcurrency => "google_multipart_concurrency",
+            Self::MultipartPartSizeScaling => "google_multipart_part_size_scaling",
+            Self::SkipSignature => "google_skip_signature",
+            Self::ListFields => "google_list_fields",
+            Self::CannedAcl => "google_canned_acl",
             Self::Client(key) => key.as_ref(),
         }
     }
@@ -183,6 +290,16 @@ impl FromStr for GoogleConfigKey {
             "google_service_account_key" | "service_account_key" => Ok(Self::ServiceAccountKey),
             "google_bucket" | "google_bucket_name" | "bucket" | "bucket_name" => Ok(Self::Bucket),
             "google_application_credentials" => Ok(Self::ApplicationCredentials),
+            "google_multipart_part_size" | "multipart_part_size" => Ok(Self::MultipartPartSize),
+            "google_multipart_concurrency" | "multipart_concurrency" => {
+                Ok(Self::MultipartConcurrency)
+            }
+            "google_multipart_part_size_scaling" | "multipart_part_size_scaling" => {
+                Ok(Self::MultipartPartSizeScaling)
+            }
+            "google_skip_signature" | "skip_signature" => Ok(Self::SkipSignature),
+            "google_list_fields" | "list_fields" => Ok(Self::ListFields),
+            "google_canned_acl" | "canned_acl" => Ok(Self::CannedAcl),
             _ => match s.parse() {
                 Ok(key) => Ok(Self::Client(key)),
                 Err(_) => Err(Error::UnknownConfigurationKey { key: s.into() }.into()),
@@ -202,6 +319,12 @@ impl Default for GoogleCloudStorageBuilder {
             client_options: ClientOptions::new().with_allow_http(true),
             url: None,
             credentials: None,
+            multipart_part_size: None,
+            multipart_concurrency: None,
+            multipart_part_size_scaling: Default::default(),
+            skip_signature: Default::default(),
+            list_fields: None,
+            canned_acl: None,
         }
     }
 }
@@ -280,6 +403,22 @@ impl GoogleCloudStorageBuilder {
             GoogleConfigKey::ApplicationCredentials => {
                 self.application_credentials_path = Some(value.into())
             }
+            GoogleConfigKey::MultipartPartSize => {
+                self.multipart_part_size = Some(ConfigValue::Deferred(value.into()))
+            }
+            GoogleConfigKey::MultipartConcurrency => {
+                self.multipart_concurrency = Some(ConfigValue::Deferred(value.into()))
+            }
+            GoogleConfigKey::MultipartPartSizeScaling => {
+                self.multipart_part_size_scaling.parse(value)
+            }
+            GoogleConfigKey::SkipSignature => self.skip_signature.parse(value),
+            GoogleConfigKey::ListFields => {
+                self.list_fields = Some(ConfigValue::Deferred(value.into()))
+            }
+            GoogleConfigKey::CannedAcl => {
+                self.canned_acl = Some(ConfigValue::Deferred(value.into()))
+            }
             GoogleConfigKey::Client(key) => {
                 self.client_options = self.client_options.with_config(key, value)
             }
@@ -304,10 +443,65 @@ impl GoogleCloudStorageBuilder {
             GoogleConfigKey::ServiceAccountKey => self.service_account_key.clone(),
             GoogleConfigKey::Bucket => self.bucket_name.clone(),
             GoogleConfigKey::ApplicationCredentials => self.application_credentials_path.clone(),
+            GoogleConfigKey::MultipartPartSize => {
+                self.multipart_part_size.as_ref().map(ToString::to_string)
+            }
+            GoogleConfigKey::MultipartConcurrency => {
+                self.multipart_concurrency.as_ref().map(ToString::to_string)
+            }
+            GoogleConfigKey::MultipartPartSizeScaling => {
+                Some(self.multipart_part_size_scaling.to_string())
+            }
+            GoogleConfigKey::SkipSignature => Some(self.skip_signature.to_string()),
+            GoogleConfigKey::ListFields => self.list_fields.as_ref().map(ToString::to_string),
+            GoogleConfigKey::CannedAcl => self.canned_acl.as_ref().map(ToString::to_string),
             GoogleConfigKey::Client(key) => self.client_options.get_config_value(key),
         }
     }
 
+    /// Returns the configuration of this builder as key - value pairs
+    ///
+    /// This includes any values sourced from the environment by [`Self::from_env`], along
+    /// with the defaults of options that have not been set, and can be used to diagnose
+    /// why a store is not configured as expected. Keys without a value are omitted.
+    ///
+    /// Secrets, such as the service account key, are redacted,
+    /// see [`Self::config_snapshot_with_secrets`] to include them.
+    ///
+    /// # Example
+    /// ```
+    /// use object_store::gcp::GoogleCloudStorageBuilder;
+    ///
+    /// let builder = GoogleCloudStorageBuilder::new()
+    ///     .with_bucket_name("foo")
+    ///     .with_service_account_key("secret");
+    /// let snapshot = builder.config_snapshot();
+    /// assert_eq!(snapshot["google_bucket"], "foo");
+    /// assert_ne!(snapshot["google_service_account_key"], "secret");
+    /// ```
+    pub fn config_snapshot(&self) -> BTreeMap<String, String> {
+        self.snapshot(false)
+    }
+
+    /// Returns the configuration of this builder as key - value pairs, including secrets
+    ///
+    /// See [`Self::config_snapshot`]
+    pub fn config_snapshot_with_secrets(&self) -> BTreeMap<String, String> {
+        self.snapshot(true)
+    }
+
+    fn snapshot(&self, include_secrets: bool) -> BTreeMap<String, String> {
+        let client_keys = ClientConfigKey::ALL
+            .iter()
+            .map(|k| GoogleConfigKey::Client(*k));
+        config_snapshot(
+            GoogleConfigKey::KEYS.iter().copied().chain(client_keys),
+            include_secrets,
+            GoogleConfigKey::is_secret,
+            |k| self.get_config_value(k),
+        )
+    }
+
     /// Sets properties on this builder based on a URL
     ///
     /// This is a separate member function to allow fallible computation to
@@ -378,6 +572,43 @@ impl GoogleCloudStorageBuilder {
         self
     }
 
+    /// Sets the size in bytes of the parts uploaded by [`ObjectStore::put_multipart`],
+    /// defaults to 10 MiB
+    ///
+    /// This must be at least 5 MiB and at most 5 GiB. Larger parts reduce the number of requests, at the
+    /// cost of greater memory usage, and are necessary for objects with more than
+    /// 10,000 parts
+    ///
+    /// [`ObjectStore::put_multipart`]: crate::ObjectStore::put_multipart
+    pub fn with_multipart_part_size(mut self, part_size: usize) -> Self {
+        self.multipart_part_size = Some(ByteSize(part_size).into());
+        self
+    }
+
+    /// Sets the number of parts uploaded concurrently by [`ObjectStore::put_multipart`],
+    /// defaults to 8
+    ///
+    /// [`ObjectStore::put_multipart`]: crate::ObjectStore::put_multipart
+    pub fn with_multipart_concurrency(mut self, concurrency: usize) -> Self {
+        self.multipart_concurrency = Some(concurrency.into());
+        self
+    }
+
+    /// Grow the size of the parts uploaded by [`ObjectStore::put_multipart`] as the upload
+    /// progresses, allowing uploads of up to roughly 10 TiB with the default part size
+    /// whilst still using small parts for small uploads, defaults to false
+    ///
+    /// The part size starts at [`Self::with_multipart_part_size`] and doubles after every
+    /// 1,000 parts, up to the maximum part size of 5 GiB, see
+    /// [`WriteMultiPart::with_part_size_scaling`]
+    ///
+    /// [`ObjectStore::put_multipart`]: crate::ObjectStore::put_multipart
+    /// [`WriteMultiPart::with_part_size_scaling`]: crate::multipart::WriteMultiPart::with_part_size_scaling
+    pub fn with_multipart_part_size_scaling(mut self, scaling: bool) -> Self {
+        self.multipart_part_size_scaling = scaling.into();
+        self
+    }
+
     /// Set the retry configuration
     pub fn with_retry(mut self, retry_config: RetryConfig) -> Self {
         self.retry_config = retry_config;
@@ -410,6 +641,51 @@ impl GoogleCloudStorageBuilder {
         self
     }
 
+    /// Sets the [`reqwest::Client`] used to make requests, sharing its connection pool
+    /// with other stores, see [`ClientOptions::with_http_client`]
+    pub fn with_http_client(mut self, client: reqwest::Client) -> Self {
+        self.client_options = self.client_options.with_http_client(client);
+        self
+    }
+
+    /// If enabled, [`GoogleCloudStorage`] will not fetch credentials and will not sign requests
+    ///
+    /// This takes precedence over any configured credentials, with requests sent without
+    /// an `Authorization` header. This can be useful when interacting with public buckets
+    pub fn with_skip_signature(mut self, skip_signature: bool) -> Self {
+        self.skip_signature = skip_signature.into();
+        self
+    }
+
+    /// List objects with the [JSON API], requesting only the given [`GcsListFields`]
+    ///
+    /// [`GcsListFields::Minimal`] requests only the fields required to populate
+    /// [`ObjectMeta`](crate::ObjectMeta), whilst [`GcsListFields::Full`] additionally
+    /// populates its version and checksum. See [`GcsListFields`] for the differences
+    /// from the default XML API listing.
+    ///
+    /// [JSON API]: https://cloud.google.com/storage/docs/json_api/v1/objects/list
+    pub fn with_list_fields(mut self, fields: GcsListFields) -> Self {
+        self.list_fields = Some(fields.into());
+        self
+    }
+
+    /// Apply the [`CannedAcl`] to written objects, sent as the `x-goog-acl` header,
+    /// the XML API equivalent of the JSON API `predefinedAcl` parameter
+    ///
+    /// This applies to puts without [`PutOptions::acl`], and to multipart uploads,
+    /// which have no per-request options. Building the store fails if the ACL is not
+    /// supported by GCS, such as [`CannedAcl::PublicReadWrite`]
+    ///
+    /// Buckets with [uniform bucket-level access] reject writes specifying an ACL
+    ///
+    /// [`PutOptions::acl`]: crate::PutOptions::acl
+    /// [uniform bucket-level access]: https://cloud.google.com/storage/docs/uniform-bucket-level-access
+    pub fn with_canned_acl(mut self, acl: CannedAcl) -> Self {
+        self.canned_acl = Some(acl.into());
+        self
+    }
+
     /// Configure a connection to Google Cloud Storage, returning a
     /// new [`GoogleCloudStorage`] and consuming `self`
     pub fn build(mut self) -> Result<GoogleCloudStorage> {
@@ -446,7 +722,13 @@ impl GoogleCloudStorageBuilder {
             .and_then(|c| c.gcs_base_url.clone())
             .unwrap_or_else(|| DEFAULT_GCS_BASE_URL.to_string());
 
-        let credentials = if let Some(credentials) = self.credentials {
+        let skip_signature = self.skip_signature.get()?;
+        let credentials = if skip_signature {
+            // Never consulted, see GoogleCloudStorageConfig::get_credential
+            Arc::new(StaticCredentialProvider::new(GcpCredential {
+                bearer: "".to_string(),
+            })) as _
+        } else if let Some(credentials) = self.credentials {
             credentials
         } else if disable_oauth {
             Arc::new(StaticCredentialProvider::new(GcpCredential {
@@ -483,12 +765,39 @@ impl GoogleCloudStorageBuilder {
             )) as _
         };
 
+        let multipart_part_size = match self.multipart_part_size {
+            Some(size) => size.get()?.0,
+            None => DEFAULT_PART_SIZE,
+        };
+        let multipart_concurrency = match self.multipart_concurrency {
+            Some(concurrency) => concurrency.get()?,
+            None => DEFAULT_CONCURRENCY,
+        };
+        validate_multipart_config(
+            STORE,
+            multipart_part_size,
+            MIN_PART_SIZE,
+            MAX_PART_SIZE,
+            multipart_concurrency,
+        )?;
+
+        let canned_acl = match self.canned_acl {
+            Some(acl) => Some(acl.get()?.check(STORE, CANNED_ACLS)?),
+            None => None,
+        };
+
         let config = GoogleCloudStorageConfig {
             base_url: gcs_base_url,
             credentials,
             bucket_name,
             retry_config: self.retry_config,
             client_options: self.client_options,
+            multipart_part_size,
+            multipart_concurrency,
+            multipart_part_size_scaling: self.multipart_part_size_scaling.get()?,
+            skip_signature,
+            list_fields: self.list_fields.map(|x| x.get()).transpose()?,
+            canned_acl,
         };
 
         Ok(GoogleCloudStorage {
@@ -518,6 +827,29 @@ mod tests {
             .unwrap_err();
     }
 
+    #[test]
+    fn gcs_test_validate_path() {
+        use crate::{path::Path, ObjectStore};
+
+        let store = GoogleCloudStorageBuilder::new()
+            .with_service_account_key(FAKE_KEY)
+            .with_bucket_name("foo")
+            .build()
+            .unwrap();
+
+        store.validate_path(&Path::from("foo/bar.parquet")).unwrap();
+        store.validate_path(&Path::from(".well-known/foo")).unwrap();
+
+        let path = Path::from(".well-known/acme-challenge/foo");
+        let err = store.validate_path(&path).unwrap_err();
+        assert!(err.to_string().contains("acme-challenge"), "{err}");
+
+        let err = store
+            .validate_path(&Path::from("a".repeat(1025)))
+            .unwrap_err();
+        assert!(err.to_string().contains("1024 bytes"), "{err}");
+    }
+
     #[test]
     fn gcs_test_config_from_map() {
         let google_service_account = "object_store:fake_service_account".to_string();
@@ -600,6 +932,46 @@ mod tests {
         );
     }
 
+    #[test]
+    fn gcs_test_multipart_config() {
+        let builder = GoogleCloudStorageBuilder::new().with_bucket_name("bucket");
+
+        let store = builder
+            .clone()
+            .with_config("google_multipart_part_size".parse().unwrap(), "32MiB")
+            .with_config("multipart_concurrency".parse().unwrap(), "2")
+            .build()
+            .unwrap();
+        let config = store.client.config();
+        assert_eq!(config.multipart_part_size, 32 * 1024 * 1024);
+        assert_eq!(config.multipart_concurrency, 2);
+        assert!(!config.multipart_part_size_scaling);
+
+        let store = builder
+            .clone()
+            .with_config(GoogleConfigKey::MultipartPartSizeScaling, "true")
+            .build()
+            .unwrap();
+        assert!(store.client.config().multipart_part_size_scaling);
+
+        let store = builder
+            .clone()
+            .with_config(GoogleConfigKey::MultipartPartSize, "5GiB")
+            .build()
+            .unwrap();
+        assert_eq!(
+            store.client.config().multipart_part_size,
+            5 * 1024 * 1024 * 1024
+        );
+
+        for size in ["5MB", "0", "1.5MiB", "6GiB"] {
+            let b = builder
+                .clone()
+                .with_config(GoogleConfigKey::MultipartPartSize, size);
+            assert!(b.build().is_err(), "{size}");
+        }
+    }
+
     #[test]
     fn gcs_test_urls() {
         let mut builder = GoogleCloudStorageBuilder::new();
@@ -612,6 +984,25 @@ mod tests {
         builder.parse_url("mailto://bucket/path").unwrap_err();
     }
 
+    #[test]
+    fn gcs_test_config_snapshot() {
+        let builder = GoogleCloudStorageBuilder::new()
+            .with_bucket_name("bucket")
+            .with_service_account_key(FAKE_KEY);
+
+        let snapshot = builder.config_snapshot();
+        assert_eq!(snapshot["google_bucket"], "bucket");
+        assert_eq!(snapshot["allow_http"], "true");
+        assert_eq!(
+            snapshot["google_service_account_key"],
+            crate::config::REDACTED
+        );
+        assert!(!snapshot.contains_key("google_service_account"));
+
+        let snapshot = builder.config_snapshot_with_secrets();
+        assert_eq!(snapshot["google_service_account_key"], FAKE_KEY);
+    }
+
     #[test]
     fn gcs_test_service_account_key_only() {
         let _ = GoogleCloudStorageBuilder::new()
@@ -18,18 +18,23 @@
 use crate::client::get::GetClient;
 use crate::client::header::{get_put_result, get_version, HeaderConfig};
 use crate::client::list::ListClient;
+use crate::client::metadata::MetadataLimits;
 use crate::client::retry::RetryExt;
 use crate::client::s3::{
     CompleteMultipartUpload, CompleteMultipartUploadResult, InitiateMultipartUploadResult,
     ListResponse,
 };
-use crate::client::GetOptionsExt;
-use crate::gcp::{GcpCredential, GcpCredentialProvider, STORE};
-use crate::multipart::PartId;
+use crate::client::{GetOptionsExt, HedgeConfig};
+use crate::gcp::acl::ObjectAclResource;
+use crate::gcp::credential::CredentialExt;
+use crate::gcp::hold::{ObjectHolds, ObjectHoldsResource, ObjectPatch};
+use crate::gcp::list::JsonListResponse;
+use crate::gcp::{GcpCredential, GcpCredentialProvider, GcsListFields, STORE};
+use crate::multipart::{validate_parts, PartId, UploadLimiter};
 use crate::path::{Path, DELIMITER};
 use crate::{
-    ClientOptions, GetOptions, ListResult, MultipartId, PutMode, PutOptions, PutResult, Result,
-    RetryConfig,
+    Acl, CannedAcl, ClientOptions, GetOptions, ListDeduplication, ListResult, MultipartId, PutMode,
+    PutOptions, PutResult, Result, RetryConfig,
 };
 use async_trait::async_trait;
 use bytes::{Buf, Bytes};
@@ -43,6 +48,28 @@ use std::sync::Arc;
 const VERSION_HEADER: &str = "x-goog-generation";
 
 static VERSION_MATCH: HeaderName = HeaderName::from_static("x-goog-if-generation-match");
+static ACL_HEADER: HeaderName = HeaderName::from_static("x-goog-acl");
+
+/// The [`CannedAcl`] supported by GCS for objects
+///
+/// <https://cloud.google.com/storage/docs/access-control/lists#predefined-acl>
+pub(crate) const CANNED_ACLS: &[CannedAcl] = &[
+    CannedAcl::Private,
+    CannedAcl::PublicRead,
+    CannedAcl::AuthenticatedRead,
+    CannedAcl::BucketOwnerRead,
+    CannedAcl::BucketOwnerFullControl,
+    CannedAcl::ProjectPrivate,
+];
+
+/// The limits on user-defined metadata
+///
+/// <https://cloud.google.com/storage/quotas#objects>
+const METADATA_LIMITS: MetadataLimits = MetadataLimits {
+    header_prefix: "x-goog-meta-",
+    max_size: 8 * 1024,
+    identifier_keys: false,
+};
 
 #[derive(Debug, Snafu)]
 enum Error {
@@ -55,6 +82,9 @@ enum Error {
     #[snafu(display("Got invalid list response: {}", source))]
     InvalidListResponse { source: quick_xml::de::DeError },
 
+    #[snafu(display("Got invalid JSON list response: {}", source))]
+    InvalidJsonListResponse { source: serde_json::Error },
+
     #[snafu(display("Error performing get request {}: {}", path, source))]
     GetRequest {
         source: crate::client::retry::Error,
@@ -103,9 +133,29 @@ enum Error {
     InvalidMultipartResponse { source: quick_xml::de::DeError },
 }
 
+/// GCS returns `403 Forbidden`, with a message referencing the hold or retention,
+/// for operations blocked by an object hold or retention
+fn is_retention_error(e: &crate::client::retry::Error) -> bool {
+    e.status() == Some(StatusCode::FORBIDDEN)
+        && e.body()
+            .map(|b| {
+                let b = b.to_ascii_lowercase();
+                b.contains(" hold") || b.contains("retention")
+            })
+            .unwrap_or_default()
+}
+
 impl From<Error> for crate::Error {
     fn from(err: Error) -> Self {
         match err {
+            Error::DeleteRequest { source, path } | Error::PutRequest { source, path }
+                if is_retention_error(&source) =>
+            {
+                Self::Retained {
+                    path,
+                    source: Box::new(source),
+                }
+            }
             Error::GetRequest { source, path }
             | Error::DeleteRequest { source, path }
             | Error::PutRequest { source, path } => source.error(STORE, path),
@@ -128,6 +178,29 @@ pub struct GoogleCloudStorageConfig {
     pub retry_config: RetryConfig,
 
     pub client_options: ClientOptions,
+
+    pub multipart_part_size: usize,
+
+    pub multipart_concurrency: usize,
+
+    pub multipart_part_size_scaling: bool,
+
+    pub skip_signature: bool,
+
+    pub list_fields: Option<GcsListFields>,
+
+    pub canned_acl: Option<CannedAcl>,
+}
+
+impl GoogleCloudStorageConfig {
+    /// Returns the credential to authorize requests with, or `None` if requests
+    /// should be sent anonymously
+    async fn get_credential(&self) -> Result<Option<Arc<GcpCredential>>> {
+        Ok(match self.skip_signature {
+            false => Some(self.credentials.get_credential().await?),
+            true => None,
+        })
+    }
 }
 
 /// A builder for a put request allowing customisation of the headers and query string
@@ -149,15 +222,29 @@ impl<'a> PutRequest<'a> {
     }
 
     async fn send(self) -> Result<PutResult> {
-        let credential = self.config.credentials.get_credential().await?;
-        let response = self
+        let credential = self.config.get_credential().await?;
+        let refresh = self.config.retry_config.refresh_credentials && credential.is_some();
+        let retry = refresh.then(|| self.builder.try_clone()).flatten();
+        let result = self
             .builder
-            .bearer_auth(&credential.bearer)
+            .with_bearer_auth(credential.as_deref())
             .send_retry(&self.config.retry_config)
-            .await
-            .context(PutRequestSnafu {
-                path: self.path.as_ref(),
-            })?;
+            .await;
+
+        let response = match (result, retry) {
+            (Err(e), Some(builder)) if e.is_auth_failure() => {
+                self.config.credentials.invalidate().await;
+                let credential = self.config.get_credential().await?;
+                builder
+                    .with_bearer_auth(credential.as_deref())
+                    .send_retry(&self.config.retry_config)
+                    .await
+            }
+            (result, _) => result,
+        }
+        .context(PutRequestSnafu {
+            path: self.path.as_ref(),
+        })?;
 
         Ok(get_put_result(response.headers(), VERSION_HEADER).context(MetadataSnafu)?)
     }
@@ -173,11 +260,14 @@ pub struct GoogleCloudStorageClient {
 
     // TODO: Hook this up in tests
     max_list_results: Option<String>,
+
+    upload_limiter: Option<UploadLimiter>,
 }
 
 impl GoogleCloudStorageClient {
     pub fn new(config: GoogleCloudStorageConfig) -> Result<Self> {
-        let client = config.client_options.client()?;
+        let client = config.client_options.store_client()?;
+        let upload_limiter = config.client_options.upload_limiter()?;
         let bucket_name_encoded =
             percent_encode(config.bucket_name.as_bytes(), NON_ALPHANUMERIC).to_string();
 
@@ -186,15 +276,85 @@ impl GoogleCloudStorageClient {
             client,
             bucket_name_encoded,
             max_list_results: None,
+            upload_limiter,
         })
     }
 
+    /// Returns the [`UploadLimiter`] shared by uploads to this store, if any
+    pub fn upload_limiter(&self) -> Option<&UploadLimiter> {
+        self.upload_limiter.as_ref()
+    }
+
     pub fn config(&self) -> &GoogleCloudStorageConfig {
         &self.config
     }
 
-    async fn get_credential(&self) -> Result<Arc<GcpCredential>> {
-        self.config.credentials.get_credential().await
+    async fn get_credential(&self) -> Result<Option<Arc<GcpCredential>>> {
+        self.config.get_credential().await
+    }
+
+    /// Perform a list request with the JSON API, requesting only `fields`
+    ///
+    /// <https://cloud.google.com/storage/docs/json_api/v1/objects/list>
+    async fn json_list_request(
+        &self,
+        fields: GcsListFields,
+        prefix: Option<&str>,
+        delimiter: bool,
+        page_token: Option<&str>,
+        offset: Option<&str>,
+    ) -> Result<(ListResult, Option<String>)> {
+        let credential = self.get_credential().await?;
+        let url = format!(
+            "{}/storage/v1/b/{}/o",
+            self.config.base_url, self.bucket_name_encoded
+        );
+
+        let mut query = Vec::with_capacity(6);
+        query.push(("fields", fields.fields()));
+        if delimiter {
+            query.push(("delimiter", DELIMITER))
+        }
+
+        if let Some(prefix) = &prefix {
+            query.push(("prefix", prefix))
+        }
+
+        if let Some(page_token) = page_token {
+            query.push(("pageToken", page_token))
+        }
+
+        if let Some(max_results) = &self.max_list_results {
+            query.push(("maxResults", max_results))
+        }
+
+        if let Some(offset) = offset {
+            query.push(("startOffset", offset))
+        }
+
+        let response = self
+            .client
+            .request(Method::GET, url)
+            .query(&query)
+            .with_bearer_auth(credential.as_deref())
+            .send_retry(&self.config.retry_config)
+            .await
+            .context(ListRequestSnafu)?
+            .bytes()
+            .await
+            .context(ListResponseBodySnafu)?;
+
+        let mut response: JsonListResponse =
+            serde_json::from_slice(&response).context(InvalidJsonListResponseSnafu)?;
+        if self.config.client_options.hide_directory_markers()? {
+            response.remove_directory_markers();
+        }
+        if let Some(offset) = offset {
+            response.remove_offset(offset);
+        }
+
+        let token = response.next_page_token.take();
+        Ok((response.try_into()?, token))
     }
 
     pub fn object_url(&self, path: &Path) -> String {
@@ -205,6 +365,92 @@ impl GoogleCloudStorageClient {
         )
     }
 
+    /// The JSON API URL of the object at `path`
+    fn json_object_url(&self, path: &Path) -> String {
+        let encoded = utf8_percent_encode(path.as_ref(), NON_ALPHANUMERIC);
+        format!(
+            "{}/storage/v1/b/{}/o/{}",
+            self.config.base_url, self.bucket_name_encoded, encoded
+        )
+    }
+
+    /// Perform a patch request <https://cloud.google.com/storage/docs/json_api/v1/objects/patch>
+    ///
+    /// `override_unlocked_retention` is required to remove or shorten an unlocked retention
+    pub async fn patch_object(
+        &self,
+        path: &Path,
+        patch: &ObjectPatch,
+        override_unlocked_retention: bool,
+    ) -> Result<()> {
+        let credential = self.get_credential().await?;
+        let url = self.json_object_url(path);
+
+        let mut builder = self.client.request(Method::PATCH, url);
+        if override_unlocked_retention {
+            builder = builder.query(&[("overrideUnlockedRetention", "true")]);
+        }
+
+        builder
+            .with_bearer_auth(credential.as_deref())
+            .json(patch)
+            .send_retry(&self.config.retry_config)
+            .await
+            .map_err(|e| e.error(STORE, path.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Fetch the holds and retention of an object <https://cloud.google.com/storage/docs/json_api/v1/objects/get>
+    pub async fn object_holds(&self, path: &Path) -> Result<ObjectHolds> {
+        let credential = self.get_credential().await?;
+        let url = self.json_object_url(path);
+
+        let response = self
+            .client
+            .request(Method::GET, url)
+            .query(&[("fields", "temporaryHold,eventBasedHold,retention")])
+            .with_bearer_auth(credential.as_deref())
+            .send_retry(&self.config.retry_config)
+            .await
+            .map_err(|e| e.error(STORE, path.to_string()))?;
+
+        let resource: ObjectHoldsResource =
+            response
+                .json()
+                .await
+                .map_err(|source| crate::Error::Generic {
+                    store: STORE,
+                    source: Box::new(source),
+                })?;
+        Ok(resource.into())
+    }
+
+    /// Fetch the owner and ACL of an object <https://cloud.google.com/storage/docs/json_api/v1/objects/get>
+    pub async fn get_acl(&self, path: &Path) -> Result<Acl> {
+        let credential = self.get_credential().await?;
+        let url = self.json_object_url(path);
+
+        let response = self
+            .client
+            .request(Method::GET, url)
+            .query(&[("projection", "full"), ("fields", "owner,acl")])
+            .with_bearer_auth(credential.as_deref())
+            .send_retry(&self.config.retry_config)
+            .await
+            .map_err(|e| e.error(STORE, path.to_string()))?;
+
+        let resource: ObjectAclResource =
+            response
+                .json()
+                .await
+                .map_err(|source| crate::Error::Generic {
+                    store: STORE,
+                    source: Box::new(source),
+                })?;
+        Ok(resource.into())
+    }
+
     /// Perform a put request <https://cloud.google.com/storage/docs/xml-api/put-object-upload>
     ///
     /// Returns the new ETag
@@ -232,7 +478,18 @@ impl GoogleCloudStorageClient {
     }
 
     pub async fn put(&self, path: &Path, data: Bytes, opts: PutOptions) -> Result<PutResult> {
-        let builder = self.put_request(path, data);
+        let metadata = METADATA_LIMITS.headers(STORE, &opts.metadata)?;
+        let acl = opts
+            .acl
+            .map(|acl| acl.check(STORE, CANNED_ACLS))
+            .transpose()?;
+        let mut builder = self.put_request(path, data);
+        for (name, value) in &metadata {
+            builder = builder.header(name, value);
+        }
+        if let Some(acl) = acl.or(self.config.canned_acl) {
+            builder = builder.header(&ACL_HEADER, acl.as_str());
+        }
 
         let builder = match &opts.mode {
             PutMode::Overwrite => builder,
@@ -267,9 +524,7 @@ impl GoogleCloudStorageClient {
         ];
         let result = self.put_request(path, data).query(query).send().await?;
 
-        Ok(PartId {
-            content_id: result.e_tag.unwrap(),
-        })
+        Ok(PartId::new(result.e_tag.unwrap(), part_idx))
     }
 
     /// Initiate a multi-part upload <https://cloud.google.com/storage/docs/xml-api/post-object-multipart>
@@ -283,13 +538,18 @@ impl GoogleCloudStorageClient {
             .get_content_type(path)
             .unwrap_or("application/octet-stream");
 
-        let response = self
+        let mut builder = self
             .client
             .request(Method::POST, &url)
-            .bearer_auth(&credential.bearer)
+            .with_bearer_auth(credential.as_deref())
             .header(header::CONTENT_TYPE, content_type)
             .header(header::CONTENT_LENGTH, "0")
-            .query(&[("uploads", "")])
+            .query(&[("uploads", "")]);
+        if let Some(acl) = self.config.canned_acl {
+            builder = builder.header(&ACL_HEADER, acl.as_str());
+        }
+
+        let response = builder
             .send_retry(&self.config.retry_config)
             .await
             .context(PutRequestSnafu {
@@ -310,7 +570,7 @@ impl GoogleCloudStorageClient {
 
         self.client
             .request(Method::DELETE, &url)
-            .bearer_auth(&credential.bearer)
+            .with_bearer_auth(credential.as_deref())
             .header(header::CONTENT_TYPE, "application/octet-stream")
             .header(header::CONTENT_LENGTH, "0")
             .query(&[("uploadId", multipart_id)])
@@ -329,6 +589,7 @@ impl GoogleCloudStorageClient {
         multipart_id: &MultipartId,
         completed_parts: Vec<PartId>,
     ) -> Result<PutResult> {
+        validate_parts(STORE, &completed_parts)?;
         let upload_id = multipart_id.clone();
         let url = self.object_url(path);
 
@@ -342,12 +603,18 @@ impl GoogleCloudStorageClient {
             // https://github.com/tafia/quick-xml/issues/350
             .replace("&quot;", "\"");
 
-        let response = self
+        let mut builder = self
             .client
             .request(Method::POST, &url)
-            .bearer_auth(&credential.bearer)
+            .with_bearer_auth(credential.as_deref())
             .query(&[("uploadId", upload_id)])
-            .body(data)
+            .body(data);
+
+        if let Some(timeout) = self.config.client_options.multipart_complete_timeout()? {
+            builder = builder.timeout(timeout);
+        }
+
+        let response = builder
             .send_retry(&self.config.retry_config)
             .await
             .context(CompleteMultipartRequestSnafu)?;
@@ -369,13 +636,28 @@ impl GoogleCloudStorageClient {
     }
 
     /// Perform a delete request <https://cloud.google.com/storage/docs/xml-api/delete-object>
-    pub async fn delete_request(&self, path: &Path) -> Result<()> {
+    ///
+    /// Deletes `version` if provided, otherwise the live version of the object. If
+    /// `if_generation_match` is provided the delete is conditional on the object's generation
+    pub async fn delete_request(
+        &self,
+        path: &Path,
+        version: Option<&str>,
+        if_generation_match: Option<&str>,
+    ) -> Result<()> {
         let credential = self.get_credential().await?;
         let url = self.object_url(path);
 
-        let builder = self.client.request(Method::DELETE, url);
+        let mut builder = self.client.request(Method::DELETE, url);
+        if let Some(version) = version {
+            builder = builder.query(&[("generation", version)]);
+        }
+        if let Some(generation) = if_generation_match {
+            builder = builder.header(&VERSION_MATCH, generation);
+        }
+
         builder
-            .bearer_auth(&credential.bearer)
+            .with_bearer_auth(credential.as_deref())
             .send_retry(&self.config.retry_config)
             .await
             .context(DeleteRequestSnafu {
@@ -385,8 +667,34 @@ impl GoogleCloudStorageClient {
         Ok(())
     }
 
+    /// List at most a single object, verifying the credentials can read the bucket
+    ///
+    /// Unlike a bucket HEAD this only requires `storage.objects.list`
+    /// <https://cloud.google.com/storage/docs/xml-api/get-bucket-list>
+    pub async fn probe_request(&self) -> Result<()> {
+        let credential = self.get_credential().await?;
+        let url = format!("{}/{}", self.config.base_url, self.bucket_name_encoded);
+
+        self.client
+            .request(Method::GET, url)
+            .query(&[("list-type", "2"), ("max-keys", "1")])
+            .with_bearer_auth(credential.as_deref())
+            .send_retry(&self.config.retry_config)
+            .await
+            .map_err(|e| e.error(STORE, self.config.bucket_name.clone()))?;
+        Ok(())
+    }
+
     /// Perform a copy request <https://cloud.google.com/storage/docs/xml-api/put-object-copy>
-    pub async fn copy_request(&self, from: &Path, to: &Path, if_not_exists: bool) -> Result<()> {
+    ///
+    /// If `storage_class` is provided the copy is stored with this storage class
+    pub async fn copy_request(
+        &self,
+        from: &Path,
+        to: &Path,
+        if_not_exists: bool,
+        storage_class: Option<&str>,
+    ) -> Result<()> {
         let credential = self.get_credential().await?;
         let url = self.object_url(to);
 
@@ -402,8 +710,12 @@ impl GoogleCloudStorageClient {
             builder = builder.header(&VERSION_MATCH, 0);
         }
 
+        if let Some(storage_class) = storage_class {
+            builder = builder.header("x-goog-storage-class", storage_class);
+        }
+
         builder
-            .bearer_auth(&credential.bearer)
+            .with_bearer_auth(credential.as_deref())
             // Needed if reqwest is compiled with native-tls instead of rustls-tls
             // See https://github.com/apache/arrow-rs/pull/3921
             .header(header::CONTENT_LENGTH, 0)
@@ -430,6 +742,14 @@ impl GetClient for GoogleCloudStorageClient {
         version_header: Some(VERSION_HEADER),
     };
 
+    fn retry_config(&self) -> &RetryConfig {
+        &self.config.retry_config
+    }
+
+    fn hedge_config(&self) -> Option<&HedgeConfig> {
+        self.config.client_options.hedge_config()
+    }
+
     /// Perform a get request <https://cloud.google.com/storage/docs/xml-api/get-object-download>
     async fn get_request(&self, path: &Path, options: GetOptions) -> Result<Response> {
         let credential = self.get_credential().await?;
@@ -446,11 +766,8 @@ impl GetClient for GoogleCloudStorageClient {
             request = request.query(&[("generation", version)]);
         }
 
-        if !credential.bearer.is_empty() {
-            request = request.bearer_auth(&credential.bearer);
-        }
-
         let response = request
+            .with_bearer_auth(credential.as_deref())
             .with_get_options(options)
             .send_retry(&self.config.retry_config)
             .await
@@ -464,6 +781,10 @@ impl GetClient for GoogleCloudStorageClient {
 
 #[async_trait]
 impl ListClient for GoogleCloudStorageClient {
+    fn list_deduplication(&self) -> Result<ListDeduplication> {
+        self.config.client_options.list_deduplication()
+    }
+
     /// Perform a list request <https://cloud.google.com/storage/docs/xml-api/get-bucket-list>
     async fn list_request(
         &self,
@@ -472,6 +793,12 @@ impl ListClient for GoogleCloudStorageClient {
         page_token: Option<&str>,
         offset: Option<&str>,
     ) -> Result<(ListResult, Option<String>)> {
+        if let Some(fields) = self.config.list_fields {
+            return self
+                .json_list_request(fields, prefix, delimiter, page_token, offset)
+                .await;
+        }
+
         let credential = self.get_credential().await?;
         let url = format!("{}/{}", self.config.base_url, self.bucket_name_encoded);
 
@@ -501,7 +828,7 @@ impl ListClient for GoogleCloudStorageClient {
             .client
             .request(Method::GET, url)
             .query(&query)
-            .bearer_auth(&credential.bearer)
+            .with_bearer_auth(credential.as_deref())
             .send_retry(&self.config.retry_config)
             .await
             .context(ListRequestSnafu)?
@@ -511,6 +838,9 @@ impl ListClient for GoogleCloudStorageClient {
 
         let mut response: ListResponse =
             quick_xml::de::from_reader(response.reader()).context(InvalidListResponseSnafu)?;
+        if self.config.client_options.hide_directory_markers()? {
+            response.remove_directory_markers();
+        }
 
         let token = response.next_continuation_token.take();
         Ok((response.try_into()?, token))
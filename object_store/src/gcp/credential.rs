@@ -24,7 +24,7 @@ use async_trait::async_trait;
 use base64::prelude::BASE64_URL_SAFE_NO_PAD;
 use base64::Engine;
 use futures::TryFutureExt;
-use reqwest::{Client, Method};
+use reqwest::{Client, Method, RequestBuilder};
 use ring::signature::RsaKeyPair;
 use serde::Deserialize;
 use snafu::{ResultExt, Snafu};
@@ -91,6 +91,25 @@ pub struct GcpCredential {
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
 
+/// Extension trait for authorizing requests with a [`GcpCredential`]
+pub trait CredentialExt {
+    /// Attach `credential` as a bearer token, if any
+    ///
+    /// Requests are sent unauthenticated if `credential` is `None` or has an empty token
+    fn with_bearer_auth(self, credential: Option<&GcpCredential>) -> Self;
+}
+
+impl CredentialExt for RequestBuilder {
+    fn with_bearer_auth(self, credential: Option<&GcpCredential>) -> Self {
+        match credential {
+            Some(credential) if !credential.bearer.is_empty() => {
+                self.bearer_auth(&credential.bearer)
+            }
+            _ => self,
+        }
+    }
+}
+
 #[derive(Debug, Default, serde::Serialize)]
 pub struct JwtHeader<'a> {
     /// The type of JWS: it can only be "JWT" here
@@ -333,7 +352,7 @@ async fn make_metadata_request(
         .request(Method::GET, url)
         .header("Metadata-Flavor", "Google")
         .query(&[("audience", "https://www.googleapis.com/oauth2/v4/token")])
-        .send_retry(retry)
+        .send_retry_credential(retry)
         .await
         .context(TokenRequestSnafu)?
         .json()
@@ -441,7 +460,7 @@ impl TokenProvider for AuthorizedUserCredentials {
                 ("client_secret", &self.client_secret),
                 ("refresh_token", &self.refresh_token),
             ])
-            .send_retry(retry)
+            .send_retry_credential(retry)
             .await
             .context(TokenRequestSnafu)?
             .json::<TokenResponse>()
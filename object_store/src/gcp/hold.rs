@@ -0,0 +1,106 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use crate::{Retention, RetentionMode};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// The holds and retention configuration of an object
+///
+/// Whilst an object has an active hold or retention, it can't be overwritten
+/// or deleted, with such requests returning [`Error::Retained`](crate::Error::Retained)
+///
+/// <https://cloud.google.com/storage/docs/object-holds>
+/// <https://cloud.google.com/storage/docs/object-lock>
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ObjectHolds {
+    /// If a temporary hold is placed on the object
+    pub temporary_hold: bool,
+    /// If an event-based hold is placed on the object
+    pub event_based_hold: bool,
+    /// The [`Retention`] configuration of the object, if any
+    pub retention: Option<Retention>,
+}
+
+/// The JSON representation of an object's retention
+///
+/// <https://cloud.google.com/storage/docs/json_api/v1/objects#resource>
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ObjectRetention {
+    mode: String,
+    retain_until_time: DateTime<Utc>,
+}
+
+impl From<&Retention> for ObjectRetention {
+    fn from(value: &Retention) -> Self {
+        let mode = match value.mode {
+            RetentionMode::Governance => "Unlocked",
+            RetentionMode::Compliance => "Locked",
+        };
+        Self {
+            mode: mode.to_string(),
+            retain_until_time: value.retain_until,
+        }
+    }
+}
+
+impl From<ObjectRetention> for Retention {
+    fn from(value: ObjectRetention) -> Self {
+        let mode = match value.mode.as_str() {
+            "Locked" => RetentionMode::Compliance,
+            _ => RetentionMode::Governance,
+        };
+        Self::new(mode, value.retain_until_time)
+    }
+}
+
+/// The holds and retention fields of the JSON object resource
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ObjectHoldsResource {
+    #[serde(default)]
+    temporary_hold: bool,
+    #[serde(default)]
+    event_based_hold: bool,
+    retention: Option<ObjectRetention>,
+}
+
+impl From<ObjectHoldsResource> for ObjectHolds {
+    fn from(value: ObjectHoldsResource) -> Self {
+        Self {
+            temporary_hold: value.temporary_hold,
+            event_based_hold: value.event_based_hold,
+            retention: value.retention.map(Into::into),
+        }
+    }
+}
+
+/// The body of an object patch request, fields left as `None` are unmodified
+///
+/// <https://cloud.google.com/storage/docs/json_api/v1/objects/patch>
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ObjectPatch {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temporary_hold: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event_based_hold: Option<bool>,
+    /// `Some(None)` clears the retention
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retention: Option<Option<ObjectRetention>>,
+}
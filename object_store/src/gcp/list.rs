@@ -0,0 +1,245 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use crate::config::Parse;
+use crate::path::{Path, DELIMITER};
+use crate::{ChecksumAlgorithm, ListResult, ObjectChecksum, ObjectMeta, Result};
+use base64::prelude::BASE64_STANDARD;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::str::FromStr;
+
+/// The object fields requested when listing with the [JSON API]
+///
+/// By default [`GoogleCloudStorage`] lists objects with the [XML API], which always
+/// returns the full object listing. The JSON API instead accepts a [`fields`] parameter
+/// restricting the response to the fields needed to populate [`ObjectMeta`], reducing
+/// the size of the listing response.
+///
+/// Note: the `etag` of an object returned by the JSON API is not the same as the
+/// `ETag` returned by the XML API used by [`ObjectStore::get`] and [`ObjectStore::head`],
+/// and so the [`ObjectMeta::e_tag`] of a listed object cannot be used in a conditional
+/// request, see [`GetOptions::if_match`]. Use [`ObjectMeta::version`], populated
+/// by [`GcsListFields::Full`], instead.
+///
+/// [XML API]: https://cloud.google.com/storage/docs/xml-api/get-bucket-list
+/// [JSON API]: https://cloud.google.com/storage/docs/json_api/v1/objects/list
+/// [`fields`]: https://cloud.google.com/storage/docs/json_api#partial-response
+/// [`GoogleCloudStorage`]: crate::gcp::GoogleCloudStorage
+/// [`ObjectStore::get`]: crate::ObjectStore::get
+/// [`ObjectStore::head`]: crate::ObjectStore::head
+/// [`GetOptions::if_match`]: crate::GetOptions::if_match
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GcsListFields {
+    /// Request only the name, size, update time and etag of each object
+    Minimal,
+    /// Additionally request the generation and checksums of each object, populating
+    /// [`ObjectMeta::version`] and [`ObjectMeta::checksum`]
+    Full,
+}
+
+impl GcsListFields {
+    /// Returns the value of the `fields` query parameter
+    pub(crate) fn fields(&self) -> &'static str {
+        match self {
+            Self::Minimal => "nextPageToken,prefixes,items(name,size,updated,etag)",
+            Self::Full => {
+                "nextPageToken,prefixes,items(name,size,updated,etag,generation,md5Hash,crc32c)"
+            }
+        }
+    }
+
+    /// Returns the name of this field set
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Minimal => "minimal",
+            Self::Full => "full",
+        }
+    }
+}
+
+impl std::fmt::Display for GcsListFields {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for GcsListFields {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "minimal" => Ok(Self::Minimal),
+            "full" => Ok(Self::Full),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Parse for GcsListFields {
+    fn parse(v: &str) -> Result<Self> {
+        v.parse().map_err(|_| crate::Error::Generic {
+            store: "Config",
+            source: format!("\"{v}\" is not a valid list field set").into(),
+        })
+    }
+}
+
+/// A response from the JSON list API
+///
+/// <https://cloud.google.com/storage/docs/json_api/v1/objects/list#response>
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct JsonListResponse {
+    #[serde(default)]
+    pub items: Vec<JsonObject>,
+    #[serde(default)]
+    pub prefixes: Vec<String>,
+    pub next_page_token: Option<String>,
+}
+
+/// The subset of the JSON object resource requested by [`GcsListFields`]
+///
+/// <https://cloud.google.com/storage/docs/json_api/v1/objects#resource>
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct JsonObject {
+    pub name: String,
+    /// Integers are serialized as strings by the JSON API
+    pub size: String,
+    pub updated: DateTime<Utc>,
+    pub etag: Option<String>,
+    pub generation: Option<String>,
+    pub md5_hash: Option<String>,
+    pub crc32c: Option<String>,
+}
+
+impl JsonListResponse {
+    /// Removes zero-byte directory markers, i.e. names ending in `/`, from the items
+    pub fn remove_directory_markers(&mut self) {
+        self.items
+            .retain(|c| !(c.size == "0" && c.name.ends_with(DELIMITER)))
+    }
+
+    /// Removes the object named `offset`, as the JSON API `startOffset` is inclusive
+    /// whereas list offsets are exclusive
+    pub fn remove_offset(&mut self, offset: &str) {
+        self.items.retain(|c| c.name != offset)
+    }
+}
+
+impl TryFrom<JsonListResponse> for ListResult {
+    type Error = crate::Error;
+
+    fn try_from(value: JsonListResponse) -> Result<Self> {
+        let common_prefixes = value
+            .prefixes
+            .into_iter()
+            .map(|x| Ok(Path::parse(x)?))
+            .collect::<Result<_>>()?;
+
+        let objects = value
+            .items
+            .into_iter()
+            .map(TryFrom::try_from)
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            common_prefixes,
+            objects,
+        })
+    }
+}
+
+impl TryFrom<JsonObject> for ObjectMeta {
+    type Error = crate::Error;
+
+    fn try_from(value: JsonObject) -> Result<Self> {
+        let size = value.size.parse().map_err(|_| crate::Error::Generic {
+            store: super::STORE,
+            source: format!("invalid size \"{}\" for object {}", value.size, value.name).into(),
+        })?;
+
+        // The checksums of composite objects are checksums of the component
+        // checksums, and so only crc32c is provided for such objects
+        let checksum = [
+            (ChecksumAlgorithm::Crc32c, &value.crc32c),
+            (ChecksumAlgorithm::Md5, &value.md5_hash),
+        ]
+        .into_iter()
+        .find_map(|(algorithm, v)| {
+            let digest = BASE64_STANDARD.decode(v.as_ref()?).ok()?;
+            Some(ObjectChecksum { algorithm, digest })
+        });
+
+        Ok(Self {
+            location: Path::parse(value.name)?,
+            last_modified: value.updated,
+            size,
+            e_tag: value.etag,
+            version: value.generation,
+            checksum,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_list_response() {
+        let body = r#"{
+            "nextPageToken": "token",
+            "prefixes": ["a/b/"],
+            "items": [
+                {"name": "a/", "size": "0", "updated": "2023-01-01T00:00:00.000Z", "etag": "CAE="},
+                {
+                    "name": "a/c",
+                    "size": "9",
+                    "updated": "2023-01-02T00:00:00.000Z",
+                    "etag": "CAI=",
+                    "generation": "1700000000000000",
+                    "md5Hash": "JfnnlDI7RTiF9RgfG2JNCw==",
+                    "crc32c": "4waSgw=="
+                }
+            ]
+        }"#;
+        let mut response: JsonListResponse = serde_json::from_str(body).unwrap();
+        assert_eq!(response.next_page_token.as_deref(), Some("token"));
+        response.remove_directory_markers();
+
+        let result = ListResult::try_from(response).unwrap();
+        assert_eq!(result.common_prefixes, vec![Path::from("a/b")]);
+        assert_eq!(result.objects.len(), 1);
+
+        let meta = &result.objects[0];
+        assert_eq!(meta.location, Path::from("a/c"));
+        assert_eq!(meta.size, 9);
+        assert_eq!(meta.e_tag.as_deref(), Some("CAI="));
+        assert_eq!(meta.version.as_deref(), Some("1700000000000000"));
+        let checksum = meta.checksum.as_ref().unwrap();
+        assert_eq!(checksum.algorithm, ChecksumAlgorithm::Crc32c);
+        assert_eq!(checksum.digest, 0xE3069283_u32.to_be_bytes().to_vec());
+
+        for fields in [GcsListFields::Minimal, GcsListFields::Full] {
+            assert_eq!(fields.as_str().parse::<GcsListFields>().unwrap(), fields);
+        }
+        assert!("all".parse::<GcsListFields>().is_err());
+    }
+}
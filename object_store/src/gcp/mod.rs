@@ -30,6 +30,19 @@
 //! consider implementing automatic clean up of unused parts that are older than one
 //! week.
 //!
+//! ## XML API
+//!
+//! Object reads, writes, listing, copies and multipart uploads all use the
+//! [XML API](https://cloud.google.com/storage/docs/xml-api/overview), which shares its
+//! request and response formats with S3, and so listings are parsed with the same code
+//! as for S3. The
+//! [JSON API](https://cloud.google.com/storage/docs/json_api) is only used for operations
+//! without an XML equivalent, such as managing object holds and retention.
+//!
+//! Requests are authenticated with OAuth 2.0 bearer tokens, signing with
+//! [HMAC keys](https://cloud.google.com/storage/docs/authentication/hmackeys) is not
+//! currently supported.
+//!
 //! ## Using HTTP/2
 //!
 //! Google Cloud Storage supports both HTTP/2 and HTTP/1. HTTP/1 is used by default
@@ -40,29 +53,48 @@ use std::sync::Arc;
 
 use crate::client::CredentialProvider;
 use crate::{
-    multipart::{PartId, PutPart, WriteMultiPart},
+    multipart::{PartId, PutPart, WriteMultiPart, MAX_PART_SIZE},
     path::Path,
-    GetOptions, GetResult, ListResult, MultipartId, ObjectMeta, ObjectStore, PutOptions, PutResult,
-    Result,
+    Acl, DeleteOptions, GetOptions, GetResult, ListPage, ListResult, MultipartId, ObjectMeta,
+    ObjectStore, PutOptions, PutResult, Result, Retention,
 };
 use async_trait::async_trait;
 use bytes::Bytes;
 use client::GoogleCloudStorageClient;
 use futures::stream::BoxStream;
+use hold::ObjectPatch;
 use tokio::io::AsyncWrite;
+use url::Url;
 
 use crate::client::get::GetClientExt;
 use crate::client::list::ListClientExt;
 use crate::multipart::MultiPartStore;
+use crate::util::{self, check_precondition, unsupported_path, url_with_path};
 pub use builder::{GoogleCloudStorageBuilder, GoogleConfigKey};
 pub use credential::GcpCredential;
+pub use hold::ObjectHolds;
+pub use list::GcsListFields;
 
+mod acl;
 mod builder;
 mod client;
 mod credential;
+mod hold;
+mod list;
 
 const STORE: &str = "GCS";
 
+/// The maximum length of an object name in bytes
+const MAX_KEY_BYTES: usize = 1024;
+
+/// Object names may not start with this prefix
+const ACME_CHALLENGE_PREFIX: &str = ".well-known/acme-challenge/";
+
+/// The maximum number of parts in a multipart upload
+///
+/// <https://cloud.google.com/storage/quotas#requests>
+const MAX_PARTS: usize = 10_000;
+
 /// [`CredentialProvider`] for [`GoogleCloudStorage`]
 pub type GcpCredentialProvider = Arc<dyn CredentialProvider<Credential = GcpCredential>>;
 
@@ -87,6 +119,87 @@ impl GoogleCloudStorage {
     pub fn credentials(&self) -> &GcpCredentialProvider {
         &self.client.config().credentials
     }
+
+    /// Copy an object from one path to another, storing the copy with `storage_class`,
+    /// e.g. `NEARLINE` or `ARCHIVE`
+    ///
+    /// This performs the copy and storage class transition in a single request,
+    /// without the copy first being written to the default storage class.
+    ///
+    /// <https://cloud.google.com/storage/docs/storage-classes>
+    pub async fn copy_with_storage_class(
+        &self,
+        from: &Path,
+        to: &Path,
+        storage_class: &str,
+    ) -> Result<()> {
+        self.validate_path(to)?;
+        if reqwest::header::HeaderValue::from_str(storage_class).is_err() {
+            return Err(crate::Error::Generic {
+                store: STORE,
+                source: format!("invalid storage class {storage_class:?}").into(),
+            });
+        }
+        self.client
+            .copy_request(from, to, false, Some(storage_class))
+            .await
+    }
+
+    /// Place, or remove, a temporary hold on the object at `location`
+    ///
+    /// Whilst a hold is in place the object can't be overwritten or deleted
+    ///
+    /// <https://cloud.google.com/storage/docs/object-holds>
+    pub async fn set_temporary_hold(&self, location: &Path, hold: bool) -> Result<()> {
+        let patch = ObjectPatch {
+            temporary_hold: Some(hold),
+            ..Default::default()
+        };
+        self.client.patch_object(location, &patch, false).await
+    }
+
+    /// Place, or remove, an event-based hold on the object at `location`
+    ///
+    /// Whilst a hold is in place the object can't be overwritten or deleted. Unlike
+    /// a temporary hold, releasing an event-based hold resets the object's time in
+    /// the bucket for the purposes of a bucket retention policy
+    ///
+    /// <https://cloud.google.com/storage/docs/object-holds>
+    pub async fn set_event_based_hold(&self, location: &Path, hold: bool) -> Result<()> {
+        let patch = ObjectPatch {
+            event_based_hold: Some(hold),
+            ..Default::default()
+        };
+        self.client.patch_object(location, &patch, false).await
+    }
+
+    /// Set, or with `None` remove, the [`Retention`] of the object at `location`
+    ///
+    /// Requires the bucket to have object retention enabled. A retention in
+    /// [`RetentionMode::Compliance`], a `Locked` retention in GCS, can't be
+    /// removed or shortened.
+    ///
+    /// [`RetentionMode::Compliance`]: crate::RetentionMode::Compliance
+    ///
+    /// <https://cloud.google.com/storage/docs/object-lock>
+    pub async fn set_retention(
+        &self,
+        location: &Path,
+        retention: Option<&Retention>,
+    ) -> Result<()> {
+        let patch = ObjectPatch {
+            retention: Some(retention.map(Into::into)),
+            ..Default::default()
+        };
+        self.client
+            .patch_object(location, &patch, retention.is_none())
+            .await
+    }
+
+    /// Returns the current [`ObjectHolds`] of the object at `location`
+    pub async fn object_holds(&self, location: &Path) -> Result<ObjectHolds> {
+        self.client.object_holds(location).await
+    }
 }
 
 struct GCSMultipartUpload {
@@ -111,11 +224,19 @@ impl PutPart for GCSMultipartUpload {
             .await?;
         Ok(())
     }
+
+    /// Abort a multipart upload <https://cloud.google.com/storage/docs/xml-api/delete-multipart>
+    async fn abort(&self) -> Result<()> {
+        self.client
+            .multipart_cleanup(&self.path, &self.multipart_id)
+            .await
+    }
 }
 
 #[async_trait]
 impl ObjectStore for GoogleCloudStorage {
     async fn put_opts(&self, location: &Path, bytes: Bytes, opts: PutOptions) -> Result<PutResult> {
+        self.validate_path(location)?;
         self.client.put(location, bytes, opts).await
     }
 
@@ -123,6 +244,7 @@ impl ObjectStore for GoogleCloudStorage {
         &self,
         location: &Path,
     ) -> Result<(MultipartId, Box<dyn AsyncWrite + Unpin + Send>)> {
+        self.validate_path(location)?;
         let upload_id = self.client.multipart_initiate(location).await?;
 
         let inner = GCSMultipartUpload {
@@ -131,7 +253,21 @@ impl ObjectStore for GoogleCloudStorage {
             multipart_id: upload_id.clone(),
         };
 
-        Ok((upload_id, Box::new(WriteMultiPart::new(inner, 8))))
+        let config = self.client.config();
+        let writer = WriteMultiPart::new(inner, config.multipart_concurrency)
+            .with_part_size(config.multipart_part_size);
+        let writer = match config.multipart_part_size_scaling {
+            true => {
+                let max_part_size = usize::try_from(MAX_PART_SIZE).unwrap_or(usize::MAX);
+                writer.with_part_size_scaling(MAX_PARTS, max_part_size)
+            }
+            false => writer,
+        };
+        let writer = match self.client.upload_limiter() {
+            Some(limiter) => writer.with_upload_limiter(limiter.clone()),
+            None => writer,
+        };
+        Ok((upload_id, Box::new(writer)))
     }
 
     async fn abort_multipart(&self, location: &Path, multipart_id: &MultipartId) -> Result<()> {
@@ -147,7 +283,29 @@ impl ObjectStore for GoogleCloudStorage {
     }
 
     async fn delete(&self, location: &Path) -> Result<()> {
-        self.client.delete_request(location).await
+        self.client.delete_request(location, None, None).await
+    }
+
+    async fn delete_opts(&self, location: &Path, opts: DeleteOptions) -> Result<()> {
+        let version = opts.version.as_deref();
+        let expected = match &opts.if_match {
+            Some(expected) => expected,
+            None => return self.client.delete_request(location, version, None).await,
+        };
+
+        // The XML API does not support If-Match on DELETE, instead check the ETag
+        // and make the delete conditional on the generation it corresponds to
+        let options = GetOptions {
+            head: true,
+            version: opts.version.clone(),
+            ..Default::default()
+        };
+        let meta = self.get_opts(location, options).await?.meta;
+        check_precondition(location, meta.e_tag.as_deref(), expected)?;
+        let generation = meta.version.as_deref();
+        self.client
+            .delete_request(location, version, generation)
+            .await
     }
 
     fn list(&self, prefix: Option<&Path>) -> BoxStream<'_, Result<ObjectMeta>> {
@@ -162,16 +320,70 @@ impl ObjectStore for GoogleCloudStorage {
         self.client.list_with_offset(prefix, offset)
     }
 
+    fn list_range(&self, start: &Path, end: &Path) -> BoxStream<'_, Result<ObjectMeta>> {
+        let location = start.clone();
+        let head = Box::pin(async move { self.head(&location).await });
+        util::list_range(start, end, head, self.client.list_with_offset(None, start))
+    }
+
     async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
         self.client.list_with_delimiter(prefix).await
     }
 
+    fn list_with_delimiter_stream(
+        &self,
+        prefix: Option<&Path>,
+    ) -> BoxStream<'_, Result<ListResult>> {
+        self.client.list_paginated(prefix, true, None)
+    }
+
+    fn list_from_token(
+        &self,
+        prefix: Option<&Path>,
+        token: Option<&str>,
+    ) -> BoxStream<'_, Result<ListPage>> {
+        self.client.list_from_token(prefix, token)
+    }
+
+    async fn probe(&self) -> Result<()> {
+        self.client.probe_request().await
+    }
+
+    async fn get_acl(&self, location: &Path) -> Result<Acl> {
+        self.client.get_acl(location).await
+    }
+
     async fn copy(&self, from: &Path, to: &Path) -> Result<()> {
-        self.client.copy_request(from, to, false).await
+        self.validate_path(to)?;
+        self.client.copy_request(from, to, false, None).await
     }
 
     async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
-        self.client.copy_request(from, to, true).await
+        self.validate_path(to)?;
+        self.client.copy_request(from, to, true, None).await
+    }
+
+    fn object_url(&self, location: &Path) -> Result<Url> {
+        let bucket = &self.client.config().bucket_name;
+        let url =
+            Url::parse(&format!("gs://{bucket}")).map_err(|source| crate::Error::Generic {
+                store: STORE,
+                source: Box::new(source),
+            })?;
+        Ok(url_with_path(url, location))
+    }
+
+    fn validate_path(&self, location: &Path) -> Result<()> {
+        // https://cloud.google.com/storage/docs/objects#naming
+        if location.as_ref().len() > MAX_KEY_BYTES {
+            let reason = format!("object name exceeds {MAX_KEY_BYTES} bytes");
+            return Err(unsupported_path(location, STORE, reason));
+        }
+        if location.as_ref().starts_with(ACME_CHALLENGE_PREFIX) {
+            let reason = format!("object name starts with \"{ACME_CHALLENGE_PREFIX}\"");
+            return Err(unsupported_path(location, STORE, reason));
+        }
+        Ok(())
     }
 }
 
@@ -237,6 +449,7 @@ mod test {
             // Fake GCS server doesn't currently honor preconditions
             get_opts(&integration).await;
             put_opts(&integration, true).await;
+            delete_opts(&integration).await;
         }
     }
 
@@ -322,4 +535,535 @@ mod test {
             err
         )
     }
+
+    #[tokio::test]
+    async fn gcs_test_skip_signature() {
+        use crate::client::mock_server::MockServer;
+        use hyper::{Body, Response};
+
+        let server = MockServer::new();
+        // The private key is invalid, and so would fail to build if credentials were loaded
+        let key = format!(
+            r#"{{"private_key": "invalid", "private_key_id": "id", "client_email": "email", "gcs_base_url": "{}"}}"#,
+            server.url()
+        );
+        let store = GoogleCloudStorageBuilder::new()
+            .with_bucket_name("bucket")
+            .with_service_account_key(key)
+            .with_config("skip_signature".parse().unwrap(), "true")
+            .build()
+            .unwrap();
+
+        // Only the object request is expected, with no token request preceding it
+        server.push_fn(|req| {
+            assert_eq!(req.uri().path(), "/bucket/file");
+            assert!(req.headers().get("authorization").is_none());
+            Response::builder()
+                .header("etag", "\"1\"")
+                .header("last-modified", "Thu, 01 Jan 1970 00:00:00 GMT")
+                .body(Body::from("data"))
+                .unwrap()
+        });
+        let data = store.get(&Path::from("file")).await.unwrap();
+        assert_eq!(data.bytes().await.unwrap().as_ref(), b"data");
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn gcs_test_delete_version() {
+        use crate::client::mock_server::MockServer;
+        use hyper::{Body, Response};
+
+        let server = MockServer::new();
+        let key = format!(
+            r#"{{"private_key": "invalid", "private_key_id": "id", "client_email": "email", "gcs_base_url": "{}"}}"#,
+            server.url()
+        );
+        let store = GoogleCloudStorageBuilder::new()
+            .with_bucket_name("bucket")
+            .with_service_account_key(key)
+            .with_skip_signature(true)
+            .build()
+            .unwrap();
+
+        server.push_fn(|req| {
+            assert_eq!(req.method(), "DELETE");
+            assert_eq!(req.uri().path(), "/bucket/file");
+            assert_eq!(req.uri().query(), Some("generation=1"));
+            assert!(req.headers().get("x-goog-if-generation-match").is_none());
+            Response::new(Body::empty())
+        });
+        let options = DeleteOptions {
+            version: Some("1".to_string()),
+            ..Default::default()
+        };
+        store
+            .delete_opts(&Path::from("file"), options)
+            .await
+            .unwrap();
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn gcs_test_copy_with_storage_class() {
+        use crate::client::mock_server::MockServer;
+        use hyper::{Body, Response};
+
+        let server = MockServer::new();
+        let key = format!(
+            r#"{{"private_key": "invalid", "private_key_id": "id", "client_email": "email", "gcs_base_url": "{}"}}"#,
+            server.url()
+        );
+        let store = GoogleCloudStorageBuilder::new()
+            .with_bucket_name("bucket")
+            .with_service_account_key(key)
+            .with_skip_signature(true)
+            .build()
+            .unwrap();
+
+        server.push_fn(|req| {
+            assert_eq!(req.method(), "PUT");
+            assert_eq!(req.headers()["x-goog-storage-class"], "NEARLINE");
+            Response::new(Body::empty())
+        });
+        let from = Path::from("from");
+        let to = Path::from("to");
+        store
+            .copy_with_storage_class(&from, &to, "NEARLINE")
+            .await
+            .unwrap();
+
+        // An invalid storage class is rejected without making a request
+        let err = store
+            .copy_with_storage_class(&from, &to, "NEARLINE\n")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, crate::Error::Generic { .. }), "{err}");
+        assert!(err.to_string().contains("invalid storage class"), "{err}");
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn gcs_test_canned_acl() {
+        use crate::client::mock_server::MockServer;
+        use crate::{CannedAcl, PutOptions};
+        use hyper::{Body, Response};
+
+        let server = MockServer::new();
+        let key = format!(
+            r#"{{"private_key": "invalid", "private_key_id": "id", "client_email": "email", "gcs_base_url": "{}"}}"#,
+            server.url()
+        );
+        let builder = GoogleCloudStorageBuilder::new()
+            .with_bucket_name("bucket")
+            .with_service_account_key(key)
+            .with_skip_signature(true);
+
+        let err = builder
+            .clone()
+            .with_canned_acl(CannedAcl::PublicReadWrite)
+            .build()
+            .unwrap_err();
+        assert!(matches!(err, crate::Error::NotSupported { .. }), "{err}");
+
+        let store = builder
+            .with_config("canned_acl".parse().unwrap(), "projectPrivate")
+            .build()
+            .unwrap();
+        let path = Path::from("file");
+
+        server.push_fn(|req| {
+            assert_eq!(req.method(), "PUT");
+            assert_eq!(req.headers()["x-goog-acl"], "public-read");
+            Response::builder()
+                .header("etag", "\"1\"")
+                .body(Body::empty())
+                .unwrap()
+        });
+        let opts = PutOptions {
+            acl: Some(CannedAcl::PublicRead),
+            ..Default::default()
+        };
+        store.put_opts(&path, "data".into(), opts).await.unwrap();
+
+        server.push_fn(|req| {
+            assert_eq!(req.method(), "POST");
+            assert_eq!(req.uri().query(), Some("uploads="));
+            assert_eq!(req.headers()["x-goog-acl"], "project-private");
+            let body = "<InitiateMultipartUploadResult><UploadId>upload</UploadId></InitiateMultipartUploadResult>";
+            Response::new(Body::from(body))
+        });
+        let id = MultiPartStore::create_multipart(&store, &path)
+            .await
+            .unwrap();
+        assert_eq!(id, "upload");
+
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn gcs_test_get_acl() {
+        use crate::client::mock_server::MockServer;
+        use crate::{AclGrant, AclGrantee, AclPermission};
+        use hyper::{Body, Response};
+
+        let server = MockServer::new();
+        let key = format!(
+            r#"{{"private_key": "invalid", "private_key_id": "id", "client_email": "email", "gcs_base_url": "{}"}}"#,
+            server.url()
+        );
+        let store = GoogleCloudStorageBuilder::new()
+            .with_bucket_name("bucket")
+            .with_service_account_key(key)
+            .with_skip_signature(true)
+            .build()
+            .unwrap();
+
+        server.push_fn(|req| {
+            assert_eq!(req.method(), "GET");
+            assert_eq!(req.uri().path(), "/storage/v1/b/bucket/o/dir%2Ffile");
+            assert_eq!(
+                req.uri().query(),
+                Some("projection=full&fields=owner%2Cacl")
+            );
+            let body = r#"{
+                "owner": {"entity": "user-owner@example.com"},
+                "acl": [
+                    {"entity": "user-owner@example.com", "role": "OWNER"},
+                    {"entity": "project-viewers-123", "role": "READER"},
+                    {"entity": "allUsers", "role": "READER"}
+                ]
+            }"#;
+            Response::new(Body::from(body))
+        });
+
+        let acl = store.get_acl(&Path::from("dir/file")).await.unwrap();
+        assert_eq!(acl.owner.as_deref(), Some("user-owner@example.com"));
+        assert_eq!(
+            acl.grants,
+            vec![
+                AclGrant::new(
+                    AclGrantee::User("owner@example.com".to_string()),
+                    AclPermission::FullControl
+                ),
+                AclGrant::new(
+                    AclGrantee::Group("project-viewers-123".to_string()),
+                    AclPermission::Read
+                ),
+                AclGrant::new(AclGrantee::AllUsers, AclPermission::Read),
+            ]
+        );
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn gcs_test_holds() {
+        use crate::client::mock_server::MockServer;
+        use crate::{Error, RetentionMode};
+        use chrono::{TimeZone, Utc};
+        use hyper::{Body, Response, StatusCode};
+
+        let server = MockServer::new();
+        let key = format!(
+            r#"{{"private_key": "invalid", "private_key_id": "id", "client_email": "email", "gcs_base_url": "{}"}}"#,
+            server.url()
+        );
+        let store = GoogleCloudStorageBuilder::new()
+            .with_bucket_name("bucket")
+            .with_service_account_key(key)
+            .with_skip_signature(true)
+            .build()
+            .unwrap();
+
+        let path = Path::from("dir/file");
+        server.push_async_fn(|req| async move {
+            assert_eq!(req.method(), "PATCH");
+            assert_eq!(req.uri().path(), "/storage/v1/b/bucket/o/dir%2Ffile");
+            assert_eq!(req.uri().query(), None);
+            let body = hyper::body::to_bytes(req.into_body()).await.unwrap();
+            assert_eq!(body.as_ref(), br#"{"temporaryHold":true}"#);
+            Response::new(Body::from("{}"))
+        });
+        store.set_temporary_hold(&path, true).await.unwrap();
+
+        server.push_fn(|req| {
+            assert_eq!(req.method(), "GET");
+            assert_eq!(req.uri().path(), "/storage/v1/b/bucket/o/dir%2Ffile");
+            Response::new(Body::from(
+                r#"{"temporaryHold": true, "retention": {"mode": "Locked", "retainUntilTime": "2030-01-01T00:00:00Z"}}"#,
+            ))
+        });
+        let holds = store.object_holds(&path).await.unwrap();
+        let retain_until = Utc.with_ymd_and_hms(2030, 1, 1, 0, 0, 0).unwrap();
+        let expected = ObjectHolds {
+            temporary_hold: true,
+            event_based_hold: false,
+            retention: Some(Retention::new(RetentionMode::Compliance, retain_until)),
+        };
+        assert_eq!(holds, expected);
+
+        // A delete blocked by the hold returns a typed error
+        server.push_fn(|req| {
+            assert_eq!(req.method(), "DELETE");
+            Response::builder()
+                .status(StatusCode::FORBIDDEN)
+                .body(Body::from(
+                    "<Error><Code>AccessDenied</Code><Details>Object 'dir/file' is under active Temporary hold and cannot be deleted, overwritten or archived until hold is removed.</Details></Error>",
+                ))
+                .unwrap()
+        });
+        let err = store.delete(&path).await.unwrap_err();
+        assert!(matches!(err, Error::Retained { .. }), "{err}");
+
+        // Removing the retention overrides the unlocked retention
+        server.push_async_fn(|req| async move {
+            assert_eq!(req.uri().query(), Some("overrideUnlockedRetention=true"));
+            let body = hyper::body::to_bytes(req.into_body()).await.unwrap();
+            assert_eq!(body.as_ref(), br#"{"retention":null}"#);
+            Response::new(Body::from("{}"))
+        });
+        store.set_retention(&path, None).await.unwrap();
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn gcs_test_get_version() {
+        use crate::client::mock_server::MockServer;
+        use hyper::{Body, Response};
+
+        let server = MockServer::new();
+        let key = format!(
+            r#"{{"private_key": "invalid", "private_key_id": "id", "client_email": "email", "gcs_base_url": "{}"}}"#,
+            server.url()
+        );
+        let store = GoogleCloudStorageBuilder::new()
+            .with_bucket_name("bucket")
+            .with_service_account_key(key)
+            .with_skip_signature(true)
+            .build()
+            .unwrap();
+
+        for method in ["GET", "HEAD"] {
+            server.push_fn(move |req| {
+                assert_eq!(req.method(), method);
+                assert_eq!(req.uri().path(), "/bucket/file");
+                assert_eq!(req.uri().query(), Some("generation=1700000000000000"));
+                Response::builder()
+                    .header("etag", "\"1\"")
+                    .header("last-modified", "Thu, 01 Jan 1970 00:00:00 GMT")
+                    .header("x-goog-generation", "1700000000000000")
+                    .body(Body::from("old"))
+                    .unwrap()
+            });
+        }
+
+        let path = Path::from("file");
+        let options = GetOptions {
+            version: Some("1700000000000000".to_string()),
+            ..Default::default()
+        };
+        let result = store.get_opts(&path, options.clone()).await.unwrap();
+        assert_eq!(result.meta.version.as_deref(), Some("1700000000000000"));
+        assert_eq!(result.bytes().await.unwrap().as_ref(), b"old");
+
+        let head = GetOptions {
+            head: true,
+            ..options
+        };
+        let meta = store.get_opts(&path, head).await.unwrap().meta;
+        assert_eq!(meta.version.as_deref(), Some("1700000000000000"));
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn gcs_test_list_fields() {
+        use crate::client::mock_server::MockServer;
+        use futures::TryStreamExt;
+        use hyper::{Body, Response};
+
+        let server = MockServer::new();
+        let key = format!(
+            r#"{{"private_key": "invalid", "private_key_id": "id", "client_email": "email", "gcs_base_url": "{}"}}"#,
+            server.url()
+        );
+        let builder = GoogleCloudStorageBuilder::new()
+            .with_bucket_name("bucket")
+            .with_service_account_key(key)
+            .with_skip_signature(true);
+
+        let store = builder
+            .clone()
+            .with_config("list_fields".parse().unwrap(), "minimal")
+            .build()
+            .unwrap();
+
+        server.push_fn(|req| {
+            assert_eq!(req.uri().path(), "/storage/v1/b/bucket/o");
+            let query: std::collections::HashMap<_, _> = url::form_urlencoded::parse(req.uri().query().unwrap().as_bytes())
+                .into_owned()
+                .collect();
+            assert_eq!(
+                query["fields"],
+                "nextPageToken,prefixes,items(name,size,updated,etag)"
+            );
+            assert_eq!(query["delimiter"], "/");
+            assert_eq!(query["prefix"], "a/");
+            let body = r#"{
+                "prefixes": ["a/b/"],
+                "items": [{"name": "a/c", "size": "9", "updated": "2023-01-02T00:00:00.000Z", "etag": "CAI="}]
+            }"#;
+            Response::new(Body::from(body))
+        });
+
+        let result = store
+            .list_with_delimiter(Some(&Path::from("a")))
+            .await
+            .unwrap();
+        assert_eq!(result.common_prefixes, vec![Path::from("a/b")]);
+        assert_eq!(result.objects.len(), 1);
+        let meta = &result.objects[0];
+        assert_eq!(meta.location, Path::from("a/c"));
+        assert_eq!(meta.size, 9);
+        assert_eq!(meta.e_tag.as_deref(), Some("CAI="));
+        assert_eq!(meta.last_modified.to_rfc3339(), "2023-01-02T00:00:00+00:00");
+        assert!(meta.version.is_none());
+
+        let store = builder
+            .with_list_fields(GcsListFields::Full)
+            .build()
+            .unwrap();
+        server.push_fn(|req| {
+            let query: std::collections::HashMap<_, _> = url::form_urlencoded::parse(req.uri().query().unwrap().as_bytes())
+                .into_owned()
+                .collect();
+            assert!(query["fields"].contains("generation,md5Hash,crc32c"));
+            assert_eq!(query["startOffset"], "a");
+            // startOffset is inclusive
+            let body = r#"{"items": [
+                {"name": "a", "size": "1", "updated": "2023-01-01T00:00:00.000Z", "generation": "1"},
+                {"name": "b", "size": "2", "updated": "2023-01-01T00:00:00.000Z", "generation": "2"}
+            ]}"#;
+            Response::new(Body::from(body))
+        });
+
+        let objects: Vec<_> = store
+            .list_with_offset(None, &Path::from("a"))
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(objects.len(), 1);
+        assert_eq!(objects[0].location, Path::from("b"));
+        assert_eq!(objects[0].version.as_deref(), Some("2"));
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn gcs_test_xml_api() {
+        use crate::client::mock_server::MockServer;
+        use futures::TryStreamExt;
+        use hyper::{Body, Response};
+
+        let server = MockServer::new();
+        let key = format!(
+            r#"{{"private_key": "invalid", "private_key_id": "id", "client_email": "email", "gcs_base_url": "{}"}}"#,
+            server.url()
+        );
+        let store = GoogleCloudStorageBuilder::new()
+            .with_bucket_name("bucket")
+            .with_service_account_key(key)
+            .with_skip_signature(true)
+            .build()
+            .unwrap();
+
+        server.push_fn(|req| {
+            assert_eq!(req.method(), "GET");
+            assert_eq!(req.uri().path(), "/bucket");
+            let query = req.uri().query().unwrap();
+            assert!(query.contains("list-type=2"), "{query}");
+            assert!(query.contains("prefix=dir%2F"), "{query}");
+            Response::new(Body::from(
+                r#"<?xml version='1.0' encoding='UTF-8'?>
+<ListBucketResult xmlns="http://doc.s3.amazonaws.com/2006-03-01">
+  <Name>bucket</Name>
+  <Prefix>dir/</Prefix>
+  <KeyCount>1</KeyCount>
+  <MaxKeys>1000</MaxKeys>
+  <IsTruncated>false</IsTruncated>
+  <Contents>
+    <Key>dir/file</Key>
+    <Generation>1700000000000000</Generation>
+    <MetaGeneration>1</MetaGeneration>
+    <LastModified>2023-11-14T22:13:20.000Z</LastModified>
+    <ETag>"5d41402abc4b2a76b9719d911017c592"</ETag>
+    <Size>5</Size>
+  </Contents>
+</ListBucketResult>"#,
+            ))
+        });
+        let objects: Vec<_> = store
+            .list(Some(&Path::from("dir")))
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(objects.len(), 1);
+        assert_eq!(objects[0].location, Path::from("dir/file"));
+        assert_eq!(objects[0].size, 5);
+        assert_eq!(
+            objects[0].e_tag.as_deref(),
+            Some("\"5d41402abc4b2a76b9719d911017c592\"")
+        );
+
+        server.push_fn(|req| {
+            assert_eq!(req.method(), "GET");
+            assert_eq!(req.uri().path(), "/bucket/dir%2Ffile");
+            Response::builder()
+                .header("etag", "\"5d41402abc4b2a76b9719d911017c592\"")
+                .header("last-modified", "Tue, 14 Nov 2023 22:13:20 GMT")
+                .body(Body::from("hello"))
+                .unwrap()
+        });
+        let result = store.get(&objects[0].location).await.unwrap();
+        assert_eq!(result.meta, objects[0]);
+        assert_eq!(result.bytes().await.unwrap().as_ref(), b"hello");
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn gcs_test_checksum() {
+        use crate::client::mock_server::MockServer;
+        use crate::{ChecksumAlgorithm, ObjectChecksum};
+        use hyper::{Body, Response};
+
+        let server = MockServer::new();
+        let key = format!(
+            r#"{{"private_key": "invalid", "private_key_id": "id", "client_email": "email", "gcs_base_url": "{}"}}"#,
+            server.url()
+        );
+        let store = GoogleCloudStorageBuilder::new()
+            .with_bucket_name("bucket")
+            .with_service_account_key(key)
+            .with_skip_signature(true)
+            .build()
+            .unwrap();
+
+        server.push_fn(|req| {
+            assert_eq!(req.method(), "HEAD");
+            Response::builder()
+                .header("etag", "\"1\"")
+                .header("last-modified", "Thu, 01 Jan 1970 00:00:00 GMT")
+                .header("content-length", "9")
+                .header("x-goog-hash", "crc32c=4waSgw==")
+                .header("x-goog-hash", "md5=JfnnlDI7RTiF9RgfG2JNCw==")
+                .body(Body::empty())
+                .unwrap()
+        });
+        let meta = store.head(&Path::from("file")).await.unwrap();
+        // The cheapest checksum to compute is preferred
+        let expected = ObjectChecksum {
+            algorithm: ChecksumAlgorithm::Crc32c,
+            digest: 0xE3069283_u32.to_be_bytes().to_vec(),
+        };
+        assert_eq!(meta.checksum, Some(expected));
+        server.shutdown().await;
+    }
 }
@@ -0,0 +1,511 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Utility for listing objects matching a glob pattern
+//!
+//! ```
+//! # use object_store::glob::Glob;
+//! # use object_store::memory::InMemory;
+//! # use object_store::ObjectStore;
+//! # use futures::TryStreamExt;
+//! # async fn example() -> object_store::Result<()> {
+//! let store = InMemory::new();
+//! let glob = Glob::new("data/*/year=2024/*.parquet")?;
+//! let objects: Vec<_> = glob.list(&store).try_collect().await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::str::FromStr;
+
+use futures::stream::BoxStream;
+use futures::{StreamExt, TryStreamExt};
+use snafu::{ensure, ResultExt, Snafu};
+
+use crate::path::{InvalidPart, Path, PathPart, DELIMITER};
+use crate::{ObjectMeta, ObjectStore, Result};
+
+#[derive(Debug, Snafu)]
+enum Error {
+    #[snafu(display("Glob \"{}\" contains an empty path segment", glob))]
+    EmptySegment { glob: String },
+
+    #[snafu(display("Glob \"{}\" contains an unterminated character class", glob))]
+    UnterminatedClass { glob: String },
+
+    #[snafu(display("Glob \"{}\" contains a trailing escape character", glob))]
+    TrailingEscape { glob: String },
+
+    #[snafu(display("Glob \"{}\" contains \"**\" not forming a whole path segment", glob))]
+    InvalidRecursive { glob: String },
+
+    #[snafu(display("Glob \"{}\" contains an invalid path segment: {}", glob, source))]
+    InvalidSegment { glob: String, source: InvalidPart },
+}
+
+impl From<Error> for super::Error {
+    fn from(err: Error) -> Self {
+        Self::Generic {
+            store: "Glob",
+            source: Box::new(err),
+        }
+    }
+}
+
+/// A single token of a [`Pattern`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    /// Matches exactly this character
+    Char(char),
+    /// `?` matches any single character
+    Any,
+    /// `*` matches any sequence of characters, including the empty sequence
+    Star,
+    /// `[...]` matches any character in (or if negated, not in) the inclusive ranges
+    Class {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+}
+
+impl Token {
+    fn matches(&self, c: char) -> bool {
+        match self {
+            Self::Char(x) => *x == c,
+            Self::Any => true,
+            Self::Star => unreachable!(),
+            Self::Class { negated, ranges } => {
+                ranges.iter().any(|(lo, hi)| (*lo..=*hi).contains(&c)) != *negated
+            }
+        }
+    }
+}
+
+/// A pattern matching a single path segment
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Pattern(Vec<Token>);
+
+impl Pattern {
+    /// Returns true if this pattern matches `s`
+    fn matches(&self, s: &str) -> bool {
+        let chars: Vec<char> = s.chars().collect();
+        let tokens = &self.0;
+
+        // Iterative wildcard matching, backtracking to the most recent `*`
+        let (mut t, mut c) = (0, 0);
+        let mut backtrack = None;
+        while c < chars.len() {
+            match tokens.get(t) {
+                Some(Token::Star) => {
+                    backtrack = Some((t, c));
+                    t += 1;
+                }
+                Some(token) if token.matches(chars[c]) => {
+                    t += 1;
+                    c += 1;
+                }
+                _ => match backtrack {
+                    Some((star_t, star_c)) => {
+                        backtrack = Some((star_t, star_c + 1));
+                        t = star_t + 1;
+                        c = star_c + 1;
+                    }
+                    None => return false,
+                },
+            }
+        }
+        tokens[t..].iter().all(|t| matches!(t, Token::Star))
+    }
+}
+
+/// A segment of a [`Glob`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    /// A segment without wildcards
+    Literal(String),
+    /// A segment containing wildcards
+    Pattern(Pattern),
+    /// `**` matches zero or more path segments
+    Recursive,
+}
+
+impl Segment {
+    fn parse(glob: &str, segment: &str) -> Result<Self, Error> {
+        ensure!(!segment.is_empty(), EmptySegmentSnafu { glob });
+        if segment == "**" {
+            return Ok(Self::Recursive);
+        }
+        ensure!(!segment.contains("**"), InvalidRecursiveSnafu { glob });
+
+        let mut tokens = vec![];
+        let mut chars = segment.chars();
+        while let Some(c) = chars.next() {
+            let token = match c {
+                '?' => Token::Any,
+                '*' => Token::Star,
+                '\\' => Token::Char(chars.next().ok_or_else(|| Error::TrailingEscape {
+                    glob: glob.to_string(),
+                })?),
+                '[' => parse_class(glob, &mut chars)?,
+                c => Token::Char(c),
+            };
+            tokens.push(token);
+        }
+
+        match tokens.iter().all(|t| matches!(t, Token::Char(_))) {
+            true => {
+                let s: String = tokens
+                    .into_iter()
+                    .map(|t| match t {
+                        Token::Char(c) => c,
+                        _ => unreachable!(),
+                    })
+                    .collect();
+                PathPart::parse(&s).context(InvalidSegmentSnafu { glob })?;
+                Ok(Self::Literal(s))
+            }
+            false => Ok(Self::Pattern(Pattern(tokens))),
+        }
+    }
+
+    fn matches(&self, part: &str) -> bool {
+        match self {
+            Self::Literal(l) => l == part,
+            Self::Pattern(p) => p.matches(part),
+            Self::Recursive => true,
+        }
+    }
+}
+
+/// Parses a character class, with the opening `[` already consumed
+fn parse_class(glob: &str, chars: &mut std::str::Chars<'_>) -> Result<Token, Error> {
+    let unterminated = || Error::UnterminatedClass {
+        glob: glob.to_string(),
+    };
+
+    let mut negated = false;
+    let mut ranges = vec![];
+    let mut first = true;
+    loop {
+        let c = chars.next().ok_or_else(unterminated)?;
+        match c {
+            '!' | '^' if first && !negated => {
+                negated = true;
+                continue;
+            }
+            // A `]` immediately following the opening `[` is a literal
+            ']' if !(first && ranges.is_empty()) => break,
+            _ => {}
+        }
+        first = false;
+
+        let lo = match c {
+            '\\' => chars.next().ok_or_else(unterminated)?,
+            c => c,
+        };
+
+        // Look ahead for a range such as `a-z`
+        let mut lookahead = chars.clone();
+        match (lookahead.next(), lookahead.next()) {
+            (Some('-'), Some(hi)) if hi != ']' => {
+                *chars = lookahead;
+                ranges.push((lo, hi));
+            }
+            _ => ranges.push((lo, lo)),
+        }
+    }
+    Ok(Token::Class { negated, ranges })
+}
+
+/// Returns the [`PathPart`] for a literal [`Segment`]
+///
+/// Unlike [`PathPart::from`] this does not percent-encode, as the glob is matched
+/// against the raw object keys returned by the store
+fn literal_part(literal: &str) -> PathPart<'_> {
+    PathPart::parse(literal).expect("validated in Segment::parse")
+}
+
+/// Returns true if `segments` match the entirety of `parts`
+fn matches_parts(segments: &[Segment], parts: &[PathPart<'_>]) -> bool {
+    match segments.split_first() {
+        None => parts.is_empty(),
+        Some((Segment::Recursive, rest)) => {
+            (0..=parts.len()).any(|skip| matches_parts(rest, &parts[skip..]))
+        }
+        Some((segment, rest)) => match parts.split_first() {
+            Some((part, remaining)) => {
+                segment.matches(part.as_ref()) && matches_parts(rest, remaining)
+            }
+            None => false,
+        },
+    }
+}
+
+/// A glob pattern that can be matched against [`Path`]
+///
+/// A glob is split on [`DELIMITER`] into segments, each of which may contain
+///
+/// * `?` matching any single character
+/// * `*` matching any sequence of characters
+/// * `[abc]`, `[a-z]` matching any character in the set, or not in the set if
+///   the class starts with `!` or `^`
+/// * `\` escaping the following character
+///
+/// Additionally a segment consisting solely of `**` matches zero or more segments.
+///
+/// Wildcards never match across a [`DELIMITER`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Glob {
+    segments: Vec<Segment>,
+}
+
+impl Glob {
+    /// Parse a [`Glob`] from the provided string
+    pub fn new(glob: &str) -> Result<Self> {
+        let segments = glob
+            .split(DELIMITER)
+            .map(|s| Segment::parse(glob, s))
+            .collect::<Result<_, _>>()?;
+        Ok(Self { segments })
+    }
+
+    /// Returns the longest [`Path`] prefix of this glob without any wildcards
+    ///
+    /// Any path matching this glob will be contained within this prefix
+    pub fn prefix(&self) -> Path {
+        self.segments
+            .iter()
+            .map_while(|s| match s {
+                Segment::Literal(l) => Some(literal_part(l)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Returns true if `path` matches this glob
+    pub fn matches(&self, path: &Path) -> bool {
+        let parts: Vec<_> = path.parts().collect();
+        matches_parts(&self.segments, &parts)
+    }
+
+    /// List the objects in `store` matching this glob
+    ///
+    /// Segments without wildcards are used as a listing prefix, and segments
+    /// containing wildcards are resolved with [`ObjectStore::list_with_delimiter`],
+    /// only descending into common prefixes that can match. This avoids listing
+    /// the entire store for a narrow pattern.
+    ///
+    /// Once a `**` segment is encountered, all objects under the current prefix
+    /// are listed and filtered client-side.
+    ///
+    /// The order of the returned [`ObjectMeta`] is not guaranteed
+    pub fn list<'a>(&'a self, store: &'a dyn ObjectStore) -> BoxStream<'a, Result<ObjectMeta>> {
+        // A queue of listing prefixes along with the index of the next segment
+        let queue = vec![(Path::default(), 0_usize)];
+        futures::stream::try_unfold(queue, move |mut queue| async move {
+            let (mut prefix, mut idx) = match queue.pop() {
+                Some(x) => x,
+                None => return Ok(None),
+            };
+
+            // Extend the prefix with any literal segments
+            while let Some(Segment::Literal(l)) = self.segments.get(idx) {
+                prefix = prefix
+                    .parts()
+                    .chain(std::iter::once(literal_part(l)))
+                    .collect();
+                idx += 1;
+            }
+
+            let stream = match self.segments.get(idx) {
+                // The glob has no wildcards remaining, look for an exact match
+                None => match store.head(&prefix).await {
+                    Ok(meta) => futures::stream::iter([Ok(meta)]).boxed(),
+                    Err(crate::Error::NotFound { .. }) => futures::stream::empty().boxed(),
+                    Err(e) => return Err(e),
+                },
+                Some(Segment::Recursive) => {
+                    let rest = &self.segments[idx..];
+                    let depth = prefix.parts().count();
+                    store
+                        .list(Some(&prefix))
+                        .try_filter(move |meta| {
+                            let parts: Vec<_> = meta.location.parts().skip(depth).collect();
+                            futures::future::ready(matches_parts(rest, &parts))
+                        })
+                        .boxed()
+                }
+                Some(segment) => {
+                    let is_last = idx + 1 == self.segments.len();
+                    let list = store.list_with_delimiter(Some(&prefix)).await?;
+                    for p in list.common_prefixes.into_iter().rev() {
+                        if p.filename().map(|f| segment.matches(f)).unwrap_or(false) {
+                            queue.push((p, idx + 1));
+                        }
+                    }
+
+                    let objects = list.objects.into_iter().filter(move |o| {
+                        is_last && o.location.filename().map(|f| segment.matches(f)) == Some(true)
+                    });
+                    futures::stream::iter(objects.map(Ok)).boxed()
+                }
+            };
+            Ok(Some((stream, queue)))
+        })
+        .try_flatten()
+        .boxed()
+    }
+}
+
+impl FromStr for Glob {
+    type Err = super::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::new(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::InMemory;
+    use bytes::Bytes;
+
+    #[test]
+    fn test_pattern() {
+        let cases = [
+            ("*", "", true),
+            ("*", "foo", true),
+            ("*.parquet", "a.parquet", true),
+            ("*.parquet", "a.parquet.tmp", false),
+            ("a*b*c", "aXbYc", true),
+            ("a*b*c", "abcb", false),
+            ("?", "a", true),
+            ("?", "ab", false),
+            ("f?o", "foo", true),
+            ("[abc]", "b", true),
+            ("[abc]", "d", false),
+            ("[a-c]x", "bx", true),
+            ("[!a-c]x", "bx", false),
+            ("[^a-c]x", "dx", true),
+            ("[]]", "]", true),
+            ("[a-]", "-", true),
+            ("\\*", "*", true),
+            ("\\*", "a", false),
+        ];
+
+        for (glob, s, expected) in cases {
+            let segment = Segment::parse(glob, glob).unwrap();
+            assert_eq!(segment.matches(s), expected, "{glob} {s}");
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let glob = Glob::new("data/*/year=2024/*.parquet").unwrap();
+        assert_eq!(glob.prefix().as_ref(), "data");
+
+        let glob = Glob::new("data/year=2024/file").unwrap();
+        assert_eq!(glob.prefix().as_ref(), "data/year=2024/file");
+
+        let glob = Glob::new("**/*.parquet").unwrap();
+        assert_eq!(glob.prefix().as_ref(), "");
+
+        Glob::new("data//file").unwrap_err();
+        Glob::new("data/[abc").unwrap_err();
+        Glob::new("data/foo\\").unwrap_err();
+        Glob::new("data/a**").unwrap_err();
+    }
+
+    #[test]
+    fn test_matches() {
+        let cases = [
+            ("a/*/c", "a/b/c", true),
+            ("a/*/c", "a/b/d/c", false),
+            ("a/**/c", "a/c", true),
+            ("a/**/c", "a/b/d/c", true),
+            ("a/**", "a/b/d/c", true),
+            ("a/**", "b/c", false),
+            ("**", "a/b", true),
+            ("**/*.parquet", "a/b.parquet", true),
+            ("**/*.parquet", "a/b.csv", false),
+        ];
+
+        for (glob, path, expected) in cases {
+            let g = Glob::new(glob).unwrap();
+            assert_eq!(g.matches(&Path::from(path)), expected, "{glob} {path}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_list() {
+        let store = InMemory::new();
+        let paths = [
+            "data/a/year=2024/1.parquet",
+            "data/a/year=2024/2.csv",
+            "data/a/year=2023/1.parquet",
+            "data/b/year=2024/1.parquet",
+            "data/b/year=2024/nested/1.parquet",
+            "data/c.parquet",
+            "data/d*.parquet",
+            "other/a/year=2024/1.parquet",
+        ];
+        for p in paths {
+            store
+                .put(&Path::parse(p).unwrap(), Bytes::new())
+                .await
+                .unwrap();
+        }
+
+        let cases = [
+            (
+                "data/*/year=2024/*.parquet",
+                vec!["data/a/year=2024/1.parquet", "data/b/year=2024/1.parquet"],
+            ),
+            ("data/?.parquet", vec!["data/c.parquet"]),
+            (
+                "data/[!b]/year=*/1.parquet",
+                vec!["data/a/year=2023/1.parquet", "data/a/year=2024/1.parquet"],
+            ),
+            (
+                "data/**/1.parquet",
+                vec![
+                    "data/a/year=2023/1.parquet",
+                    "data/a/year=2024/1.parquet",
+                    "data/b/year=2024/1.parquet",
+                    "data/b/year=2024/nested/1.parquet",
+                ],
+            ),
+            ("*/a/year=2024/2.csv", vec!["data/a/year=2024/2.csv"]),
+            ("data/c.parquet", vec!["data/c.parquet"]),
+            ("data/d.parquet", vec![]),
+            ("data/d\\*.parquet", vec!["data/d*.parquet"]),
+        ];
+
+        for (glob, expected) in cases {
+            let glob = Glob::new(glob).unwrap();
+            let mut actual: Vec<_> = glob
+                .list(&store)
+                .map_ok(|m| m.location.to_string())
+                .try_collect()
+                .await
+                .unwrap();
+            actual.sort_unstable();
+            assert_eq!(actual, expected, "{glob:?}");
+        }
+    }
+}
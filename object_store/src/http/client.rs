@@ -18,9 +18,9 @@
 use crate::client::get::GetClient;
 use crate::client::header::HeaderConfig;
 use crate::client::retry::{self, RetryConfig, RetryExt};
-use crate::client::GetOptionsExt;
+use crate::client::{GetOptionsExt, HedgeConfig};
 use crate::path::{Path, DELIMITER};
-use crate::util::deserialize_rfc1123;
+use crate::util::{deserialize_rfc1123, normalize_etag};
 use crate::{ClientOptions, GetOptions, ObjectMeta, Result};
 use async_trait::async_trait;
 use bytes::{Buf, Bytes};
@@ -91,7 +91,7 @@ pub struct Client {
 
 impl Client {
     pub fn new(url: Url, client_options: ClientOptions, retry_config: RetryConfig) -> Result<Self> {
-        let client = client_options.client()?;
+        let client = client_options.store_client()?;
         Ok(Self {
             url,
             retry_config,
@@ -104,7 +104,7 @@ impl Client {
         &self.url
     }
 
-    fn path_url(&self, location: &Path) -> Url {
+    pub fn path_url(&self, location: &Path) -> Url {
         let mut url = self.url.clone();
         url.path_segments_mut().unwrap().extend(location.parts());
         url
@@ -284,6 +284,14 @@ impl GetClient for Client {
         version_header: None,
     };
 
+    fn retry_config(&self) -> &RetryConfig {
+        &self.retry_config
+    }
+
+    fn hedge_config(&self) -> Option<&HedgeConfig> {
+        self.client_options.hedge_config()
+    }
+
     async fn get_request(&self, path: &Path, options: GetOptions) -> Result<Response> {
         let url = self.path_url(path);
         let method = match options.head {
@@ -379,8 +387,9 @@ impl MultiStatusResponse {
             location: self.path(base_url)?,
             last_modified,
             size: self.size()?,
-            e_tag: self.prop_stat.prop.e_tag.clone(),
+            e_tag: self.prop_stat.prop.e_tag.as_deref().map(normalize_etag),
             version: None,
+            checksum: None,
         })
     }
 
@@ -28,9 +28,15 @@
 //!
 //! Multipart uploads are not currently supported
 //!
+//! Some servers return weak ETags, prefixed with `W/`, for example when compressing
+//! responses on the fly. These are reported in [`ObjectMeta::e_tag`](crate::ObjectMeta::e_tag)
+//! as returned, but cannot be used for conditional writes or [`GetOptions::if_match`]
+//!
 //! [rfc2518]: https://datatracker.ietf.org/doc/html/rfc2518
 //! [WebDAV]: https://en.wikipedia.org/wiki/WebDAV
 
+use std::sync::Arc;
+
 use async_trait::async_trait;
 use bytes::Bytes;
 use futures::stream::BoxStream;
@@ -44,6 +50,7 @@ use crate::client::get::GetClientExt;
 use crate::client::header::get_etag;
 use crate::http::client::Client;
 use crate::path::Path;
+use crate::util::range_prefix;
 use crate::{
     ClientConfigKey, ClientOptions, GetOptions, GetResult, ListResult, MultipartId, ObjectMeta,
     ObjectStore, PutMode, PutOptions, PutResult, Result, RetryConfig,
@@ -85,7 +92,7 @@ impl From<Error> for crate::Error {
 /// See [`crate::http`] for more information
 #[derive(Debug)]
 pub struct HttpStore {
-    client: Client,
+    client: Arc<Client>,
 }
 
 impl std::fmt::Display for HttpStore {
@@ -157,6 +164,15 @@ impl ObjectStore for HttpStore {
         .boxed()
     }
 
+    fn list_range(&self, start: &Path, end: &Path) -> BoxStream<'_, Result<ObjectMeta>> {
+        // Objects are not listed in lexicographic order
+        let prefix = range_prefix(start, end);
+        let (start, end) = (start.clone(), end.clone());
+        self.list(prefix.as_ref())
+            .try_filter(move |f| futures::future::ready(f.location >= start && f.location < end))
+            .boxed()
+    }
+
     async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
         let status = self.client.list(prefix, "1").await?;
         let prefix_len = prefix.map(|p| p.as_ref().len()).unwrap_or(0);
@@ -196,6 +212,10 @@ impl ObjectStore for HttpStore {
     async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
         self.client.copy(from, to, false).await
     }
+
+    fn object_url(&self, location: &Path) -> Result<Url> {
+        Ok(self.client.path_url(location))
+    }
 }
 
 /// Configure a connection to a generic HTTP server
@@ -236,13 +256,20 @@ impl HttpBuilder {
         self
     }
 
+    /// Sets the [`reqwest::Client`] used to make requests, sharing its connection pool
+    /// with other stores, see [`ClientOptions::with_http_client`]
+    pub fn with_http_client(mut self, client: reqwest::Client) -> Self {
+        self.client_options = self.client_options.with_http_client(client);
+        self
+    }
+
     /// Build an [`HttpStore`] with the configured options
     pub fn build(self) -> Result<HttpStore> {
         let url = self.url.context(MissingUrlSnafu)?;
         let parsed = Url::parse(&url).context(UnableToParseUrlSnafu { url })?;
 
         Ok(HttpStore {
-            client: Client::new(parsed, self.client_options, self.retry_config)?,
+            client: Arc::new(Client::new(parsed, self.client_options, self.retry_config)?),
         })
     }
 }
@@ -100,6 +100,11 @@
 //! * Allows for functionality not native to filesystems, such as operation preconditions
 //! and atomic multipart uploads
 //!
+//! Synchronous consumers that cannot drive the async APIs can use the blocking [`Read`],
+//! [`Seek`] and [`Write`](std::io::Write) adapters in the `blocking` module (requires the `blocking` feature).
+//! Consumers running on a single-threaded runtime, or within a [`LocalSet`](tokio::task::LocalSet),
+//! can use [`LocalObjectStore`](spawn::LocalObjectStore) to perform IO on a dedicated runtime.
+//!
 //! [`BufReader`]: buffered::BufReader
 //!
 //! # Adapters
@@ -109,6 +114,8 @@
 //!
 //! * Rate Throttling: [`ThrottleConfig`](throttle::ThrottleConfig)
 //! * Concurrent Request Limit: [`LimitStore`](limit::LimitStore)
+//! * Not Found Caching: [`CachingStore`](cache::CachingStore)
+//! * Operation logging: [`LoggingStore`](logging::LoggingStore) (requires the `logging` feature)
 //!
 //! # Configuration System
 //!
@@ -328,7 +335,7 @@
 //! ```
 //! # use std::collections::btree_map::Entry;
 //! # use std::collections::HashMap;
-//! # use object_store::{GetOptions, GetResult, ObjectStore, Result, Error};
+//! # use object_store::{ConditionalGetResult, GetOptions, GetResult, ObjectStore, Result, Error};
 //! # use std::sync::Arc;
 //! # use std::time::{Duration, Instant};
 //! # use bytes::Bytes;
@@ -359,10 +366,9 @@
 //!                         if_none_match: Some(e.e_tag.clone()),
 //!                         ..GetOptions::default()
 //!                     };
-//!                     match self.store.get_opts(&path, opts).await {
-//!                         Ok(d) => e.data = d.bytes().await?,
-//!                         Err(Error::NotModified { .. }) => {} // Data has not changed
-//!                         Err(e) => return Err(e),
+//!                     match self.store.get_if_modified(&path, opts).await? {
+//!                         ConditionalGetResult::Modified(d) => e.data = d.bytes().await?,
+//!                         ConditionalGetResult::NotModified => {} // Data has not changed
 //!                     };
 //!                     e.refreshed_at = Instant::now();
 //!                     e.data.clone()
@@ -460,22 +466,40 @@ compile_error!("Features 'gcp', 'aws', 'azure', 'http' are not supported on wasm
 pub mod aws;
 #[cfg(feature = "azure")]
 pub mod azure;
+#[cfg(feature = "blocking")]
+pub mod blocking;
 pub mod buffered;
+pub mod cache;
+pub mod cas;
 #[cfg(not(target_arch = "wasm32"))]
 pub mod chunked;
+pub mod codec;
+#[cfg(feature = "content-addressed")]
+pub mod content_addressed;
 pub mod delimited;
+#[cfg(feature = "faults")]
+pub mod faults;
 #[cfg(feature = "gcp")]
 pub mod gcp;
+pub mod glob;
 #[cfg(feature = "http")]
 pub mod http;
 pub mod limit;
 #[cfg(not(target_arch = "wasm32"))]
 pub mod local;
+pub mod lock;
+#[cfg(feature = "logging")]
+pub mod logging;
 pub mod memory;
+pub mod migrate;
+pub mod mirror;
+pub mod overlay;
 pub mod path;
 pub mod prefix;
+pub mod sharded;
 #[cfg(feature = "cloud")]
 pub mod signer;
+pub mod spawn;
 pub mod throttle;
 
 #[cfg(feature = "cloud")]
@@ -483,15 +507,22 @@ mod client;
 
 #[cfg(feature = "cloud")]
 pub use client::{
-    backoff::BackoffConfig, retry::RetryConfig, ClientConfigKey, ClientOptions, CredentialProvider,
+    backoff::BackoffConfig, retry::RetryBudget, retry::RetryConfig, retry::ServerErrorDetails,
+    ClientConfigKey, ClientOptions, CredentialProvider, ListDeduplication,
     StaticCredentialProvider,
 };
 
 #[cfg(feature = "cloud")]
 mod config;
 
+mod acl;
+mod checksum;
+mod retention;
 mod tags;
 
+pub use acl::{Acl, AclGrant, AclGrantee, AclPermission, CannedAcl};
+pub use checksum::{ChecksumAlgorithm, ObjectChecksum};
+pub use retention::{Retention, RetentionMode};
 pub use tags::TagSet;
 
 pub mod multipart;
@@ -504,18 +535,25 @@ pub use util::GetRange;
 use crate::path::Path;
 #[cfg(not(target_arch = "wasm32"))]
 use crate::util::maybe_spawn_blocking;
-pub use crate::util::{coalesce_ranges, collect_bytes, OBJECT_STORE_COALESCE_DEFAULT};
+pub use crate::util::{
+    coalesce_ranges, collect_bytes, copy_prefix, head_many, list_many, list_many_sorted,
+    list_parallel, list_parallel_sorted, parse_byte_size, put_if_changed, rename_prefix,
+    sub_prefixes, PrefixCopyResult, ALPHANUMERIC_ALPHABET, HEX_ALPHABET,
+    OBJECT_STORE_COALESCE_DEFAULT,
+};
 use async_trait::async_trait;
 use bytes::Bytes;
 use chrono::{DateTime, Utc};
 use futures::{stream::BoxStream, StreamExt, TryStreamExt};
 use snafu::Snafu;
+use std::collections::BTreeMap;
 use std::fmt::{Debug, Formatter};
 #[cfg(not(target_arch = "wasm32"))]
 use std::io::{Read, Seek, SeekFrom};
 use std::ops::Range;
 use std::sync::Arc;
 use tokio::io::AsyncWrite;
+use url::Url;
 
 /// An alias for a dynamically dispatched object store implementation.
 pub type DynObjectStore = dyn ObjectStore;
@@ -589,6 +627,24 @@ pub trait ObjectStore: std::fmt::Display + Send + Sync + Debug + 'static {
     /// Perform a get request with options
     async fn get_opts(&self, location: &Path, options: GetOptions) -> Result<GetResult>;
 
+    /// Perform a conditional get request, returning [`ConditionalGetResult::NotModified`]
+    /// instead of [`Error::NotModified`] if the object has not changed
+    ///
+    /// This is typically used with [`GetOptions::if_none_match`] or
+    /// [`GetOptions::if_modified_since`] to revalidate a cached copy of an object,
+    /// in which case a `304 Not Modified` response is returned without any body
+    async fn get_if_modified(
+        &self,
+        location: &Path,
+        options: GetOptions,
+    ) -> Result<ConditionalGetResult> {
+        match self.get_opts(location, options).await {
+            Ok(r) => Ok(ConditionalGetResult::Modified(r)),
+            Err(Error::NotModified { .. }) => Ok(ConditionalGetResult::NotModified),
+            Err(e) => Err(e),
+        }
+    }
+
     /// Return the bytes that are stored at the specified location
     /// in the given byte range.
     ///
@@ -624,6 +680,25 @@ pub trait ObjectStore: std::fmt::Display + Send + Sync + Debug + 'static {
     /// Delete the object at the specified location.
     async fn delete(&self, location: &Path) -> Result<()>;
 
+    /// Delete the object at the specified location with the provided [`DeleteOptions`]
+    ///
+    /// If [`DeleteOptions::if_match`] is set and does not match the current
+    /// [`ObjectMeta::e_tag`] of the object, returns [`Error::Precondition`] and the
+    /// object is not deleted.
+    ///
+    /// Stores that support conditional deletes, such as S3, Azure and GCS, perform this
+    /// check atomically. The default implementation instead calls [`ObjectStore::head`]
+    /// followed by [`ObjectStore::delete`], and so may delete an object that was modified
+    /// between the two requests.
+    async fn delete_opts(&self, location: &Path, opts: DeleteOptions) -> Result<()> {
+        util::check_delete_version(&opts)?;
+        if let Some(expected) = &opts.if_match {
+            let meta = self.head(location).await?;
+            util::check_precondition(location, meta.e_tag.as_deref(), expected)?;
+        }
+        self.delete(location).await
+    }
+
     /// Delete all the objects at the specified locations
     ///
     /// When supported, this method will use bulk operations that delete more
@@ -683,7 +758,7 @@ pub trait ObjectStore: std::fmt::Display + Send + Sync + Debug + 'static {
     /// List all the objects with the given prefix.
     ///
     /// Prefixes are evaluated on a path segment basis, i.e. `foo/bar/` is a prefix of `foo/bar/x` but not of
-    /// `foo/bar_baz/x`.
+    /// `foo/bar_baz/x`. A prefix of `None` and the empty path are equivalent, listing all objects.
     ///
     /// Note: the order of returned [`ObjectMeta`] is not guaranteed
     fn list(&self, prefix: Option<&Path>) -> BoxStream<'_, Result<ObjectMeta>>;
@@ -705,19 +780,127 @@ pub trait ObjectStore: std::fmt::Display + Send + Sync + Debug + 'static {
             .boxed()
     }
 
+    /// List all the objects with a location in the range `[start, end)`
+    ///
+    /// Locations are compared lexicographically by their UTF-8 bytes, as by the [`Ord`]
+    /// implementation of [`Path`], and so a store can be split into disjoint ranges
+    /// listed in parallel, for example by separate workers.
+    ///
+    /// The default implementation lists the objects after `start` with
+    /// [`ObjectStore::list_with_offset`], under the path segments shared by `start` and
+    /// `end`, and stops listing once a location reaches `end`. The object at `start`
+    /// is fetched with [`ObjectStore::head`]. This requires [`ObjectStore::list`] to
+    /// return objects in lexicographic order, stores that do not, such as
+    /// [`LocalFileSystem`](local::LocalFileSystem), instead filter the listing.
+    ///
+    /// Note: the order of returned [`ObjectMeta`] is not guaranteed
+    fn list_range(&self, start: &Path, end: &Path) -> BoxStream<'_, Result<ObjectMeta>> {
+        let prefix = util::range_prefix(start, end);
+        let location = start.clone();
+        let head = Box::pin(async move { self.head(&location).await });
+        let after = self.list_with_offset(prefix.as_ref(), start);
+        util::list_range(start, end, head, after)
+    }
+
     /// List objects with the given prefix and an implementation specific
     /// delimiter. Returns common prefixes (directories) in addition to object
     /// metadata.
     ///
     /// Prefixes are evaluated on a path segment basis, i.e. `foo/bar/` is a prefix of `foo/bar/x` but not of
-    /// `foo/bar_baz/x`.
+    /// `foo/bar_baz/x`. A prefix of `None` and the empty path are equivalent, listing the
+    /// objects and common prefixes at the root.
     async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult>;
 
+    /// List all the objects with the given prefix, starting from a continuation
+    /// token previously returned in [`ListPage::next_token`]
+    ///
+    /// If `token` is `None` the listing starts from the beginning. Each [`ListPage`]
+    /// contains the token from which the remainder of the listing can be resumed,
+    /// allowing long-running listings to be checkpointed and resumed, for example
+    /// after a restart. Tokens should be treated as opaque, are validated by the
+    /// underlying store, and are only valid for the same store and `prefix`.
+    ///
+    /// Stores with native continuation tokens, such as S3, Azure and GCS, return
+    /// these directly. The default implementation returns [`Error::NotImplemented`].
+    fn list_from_token(
+        &self,
+        prefix: Option<&Path>,
+        token: Option<&str>,
+    ) -> BoxStream<'_, Result<ListPage>> {
+        let _ = (prefix, token);
+        futures::stream::once(async { Err(Error::NotImplemented) }).boxed()
+    }
+
+    /// List objects with the given prefix and an implementation specific
+    /// delimiter, yielding each page of results as it is received
+    ///
+    /// This is equivalent to [`ObjectStore::list_with_delimiter`], but avoids buffering
+    /// the entire listing in memory, which can be significant for prefixes containing
+    /// large numbers of objects or common prefixes. Each [`ListResult`] contains a
+    /// subset of the objects and common prefixes, and a common prefix may be repeated
+    /// across pages.
+    ///
+    /// The default implementation yields the result of [`ObjectStore::list_with_delimiter`]
+    /// as a single page
+    fn list_with_delimiter_stream(
+        &self,
+        prefix: Option<&Path>,
+    ) -> BoxStream<'_, Result<ListResult>> {
+        let prefix = prefix.cloned();
+        futures::stream::once(async move { self.list_with_delimiter(prefix.as_ref()).await })
+            .boxed()
+    }
+
     /// Copy an object from one path to another in the same object store.
     ///
     /// If there exists an object at the destination, it will be overwritten.
     async fn copy(&self, from: &Path, to: &Path) -> Result<()>;
 
+    /// Copy an object from one path to another with the provided [`CopyOptions`]
+    ///
+    /// If the conditions of [`CopyOptions`] are not satisfied by the source object,
+    /// returns [`Error::Precondition`] and the object is not copied. This allows copying
+    /// exactly the object previously inspected, e.g. with [`ObjectStore::head`].
+    ///
+    /// S3 and Azure perform this check atomically. The default implementation instead
+    /// calls [`ObjectStore::head`] followed by [`ObjectStore::copy`], and so may copy an
+    /// object that was modified between the two requests.
+    ///
+    /// With [`CopyMode::Create`] the destination is created as by
+    /// [`ObjectStore::copy_if_not_exists`], with the same atomicity guarantees.
+    async fn copy_opts(&self, from: &Path, to: &Path, opts: CopyOptions) -> Result<()> {
+        let mode = opts.mode;
+        util::check_copy_source(self, from, opts).await?;
+        match mode {
+            CopyMode::Overwrite => self.copy(from, to).await,
+            CopyMode::Create => self.copy_if_not_exists(from, to).await,
+        }
+    }
+
+    /// Check that the store is reachable and the configured credentials are valid
+    ///
+    /// This does not require a known object, making it suitable for readiness checks.
+    /// Stores override this with their cheapest request, by default it lists at most
+    /// a single object.
+    ///
+    /// Rejected credentials are returned as [`Error::Unauthenticated`] and
+    /// insufficient permissions as [`Error::PermissionDenied`], connectivity
+    /// failures are returned as [`Error::Generic`]
+    async fn probe(&self) -> Result<()> {
+        self.list(None).next().await.transpose()?;
+        Ok(())
+    }
+
+    /// Return the access control list of the object at `location`
+    ///
+    /// This is a best-effort normalization of the store's access control model,
+    /// see [`Acl`]. Stores without per-object access control, such as the local
+    /// filesystem, return [`Error::NotImplemented`], as does the default implementation.
+    async fn get_acl(&self, location: &Path) -> Result<Acl> {
+        let _ = location;
+        Err(Error::NotImplemented)
+    }
+
     /// Move an object from one path to another in the same object store.
     ///
     /// By default, this is implemented as a copy and then delete source. It may not
@@ -731,11 +914,20 @@ pub trait ObjectStore: std::fmt::Display + Send + Sync + Debug + 'static {
 
     /// Copy an object from one path to another, only if destination is empty.
     ///
-    /// Will return an error if the destination already has an object.
+    /// Will return [`Error::AlreadyExists`] if the destination already has an object.
     ///
-    /// Performs an atomic operation if the underlying object storage supports it.
-    /// If atomic operations are not supported by the underlying object storage (like S3)
-    /// it will return an error.
+    /// Stores only implement this where the check and the copy can be performed as a
+    /// single atomic operation, such that of several concurrent copies to the same
+    /// destination exactly one succeeds, returning [`Error::NotSupported`] otherwise:
+    ///
+    /// | Store | Mechanism |
+    /// |-------|-----------|
+    /// | `AmazonS3` | configured by `S3CopyIfNotExists`, e.g. `If-None-Match: *` on multipart completion |
+    /// | `GoogleCloudStorage` | `x-goog-if-generation-match: 0` |
+    /// | `MicrosoftAzure` | `If-None-Match: *` |
+    /// | `HttpStore` | WebDAV `Overwrite: F`, atomic only if the server's `COPY` is |
+    /// | [`LocalFileSystem`](local::LocalFileSystem) | a hard link, which fails if the destination exists |
+    /// | [`InMemory`](memory::InMemory) | a single write lock |
     async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> Result<()>;
 
     /// Move an object from one path to another in the same object store.
@@ -745,6 +937,42 @@ pub trait ObjectStore: std::fmt::Display + Send + Sync + Debug + 'static {
         self.copy_if_not_exists(from, to).await?;
         self.delete(from).await
     }
+
+    /// Returns a URL identifying the object at `location`
+    ///
+    /// Where possible this is the canonical URL of the object, e.g. `s3://bucket/key`,
+    /// which [`parse_url`] will resolve back to an equivalent store and `location`.
+    /// Characters in `location` that are not permitted in a URL path are percent-encoded.
+    ///
+    /// ```
+    /// # use object_store::{memory::InMemory, path::Path, ObjectStore};
+    /// let store = InMemory::new();
+    /// let url = store.object_url(&Path::from("foo bar/baz")).unwrap();
+    /// assert_eq!(url.as_str(), "memory:///foo%20bar/baz");
+    ///
+    /// let (_, path) = object_store::parse_url(&url).unwrap();
+    /// assert_eq!(path, Path::from("foo bar/baz"));
+    /// ```
+    ///
+    /// The default implementation returns [`Error::NotImplemented`]
+    fn object_url(&self, location: &Path) -> Result<Url> {
+        let _ = location;
+        Err(Error::NotImplemented)
+    }
+
+    /// Returns an error if `location` is not a valid object key for this store
+    ///
+    /// [`Path`] enforces only the constraints common to all stores, individual
+    /// stores may impose additional restrictions, e.g. on the length of a key.
+    /// Stores will validate the destination of puts, multipart uploads, copies and
+    /// renames before issuing the request, failing fast instead of surfacing an opaque
+    /// error from the service.
+    ///
+    /// The default implementation accepts all paths
+    fn validate_path(&self, location: &Path) -> Result<()> {
+        let _ = location;
+        Ok(())
+    }
 }
 
 macro_rules! as_ref_impl {
@@ -787,6 +1015,14 @@ macro_rules! as_ref_impl {
                 self.as_ref().get_opts(location, options).await
             }
 
+            async fn get_if_modified(
+                &self,
+                location: &Path,
+                options: GetOptions,
+            ) -> Result<ConditionalGetResult> {
+                self.as_ref().get_if_modified(location, options).await
+            }
+
             async fn get_range(&self, location: &Path, range: Range<usize>) -> Result<Bytes> {
                 self.as_ref().get_range(location, range).await
             }
@@ -807,6 +1043,10 @@ macro_rules! as_ref_impl {
                 self.as_ref().delete(location).await
             }
 
+            async fn delete_opts(&self, location: &Path, opts: DeleteOptions) -> Result<()> {
+                self.as_ref().delete_opts(location, opts).await
+            }
+
             fn delete_stream<'a>(
                 &'a self,
                 locations: BoxStream<'a, Result<Path>>,
@@ -826,14 +1066,45 @@ macro_rules! as_ref_impl {
                 self.as_ref().list_with_offset(prefix, offset)
             }
 
+            fn list_range(&self, start: &Path, end: &Path) -> BoxStream<'_, Result<ObjectMeta>> {
+                self.as_ref().list_range(start, end)
+            }
+
             async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
                 self.as_ref().list_with_delimiter(prefix).await
             }
 
+            fn list_from_token(
+                &self,
+                prefix: Option<&Path>,
+                token: Option<&str>,
+            ) -> BoxStream<'_, Result<ListPage>> {
+                self.as_ref().list_from_token(prefix, token)
+            }
+
+            fn list_with_delimiter_stream(
+                &self,
+                prefix: Option<&Path>,
+            ) -> BoxStream<'_, Result<ListResult>> {
+                self.as_ref().list_with_delimiter_stream(prefix)
+            }
+
             async fn copy(&self, from: &Path, to: &Path) -> Result<()> {
                 self.as_ref().copy(from, to).await
             }
 
+            async fn copy_opts(&self, from: &Path, to: &Path, opts: CopyOptions) -> Result<()> {
+                self.as_ref().copy_opts(from, to, opts).await
+            }
+
+            async fn probe(&self) -> Result<()> {
+                self.as_ref().probe().await
+            }
+
+            async fn get_acl(&self, location: &Path) -> Result<Acl> {
+                self.as_ref().get_acl(location).await
+            }
+
             async fn rename(&self, from: &Path, to: &Path) -> Result<()> {
                 self.as_ref().rename(from, to).await
             }
@@ -845,6 +1116,14 @@ macro_rules! as_ref_impl {
             async fn rename_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
                 self.as_ref().rename_if_not_exists(from, to).await
             }
+
+            fn object_url(&self, location: &Path) -> Result<Url> {
+                self.as_ref().object_url(location)
+            }
+
+            fn validate_path(&self, location: &Path) -> Result<()> {
+                self.as_ref().validate_path(location)
+            }
         }
     };
 }
@@ -863,6 +1142,16 @@ pub struct ListResult {
     pub objects: Vec<ObjectMeta>,
 }
 
+/// A page of results returned by [`ObjectStore::list_from_token`]
+#[derive(Debug)]
+pub struct ListPage {
+    /// Object metadata for this page of the listing
+    pub objects: Vec<ObjectMeta>,
+    /// An opaque token from which to resume the listing after this page, or `None`
+    /// if this is the last page
+    pub next_token: Option<String>,
+}
+
 /// The metadata that describes an object.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ObjectMeta {
@@ -874,14 +1163,22 @@ pub struct ObjectMeta {
     pub size: usize,
     /// The unique identifier for the object
     ///
+    /// Some stores, in particular HTTP servers, may return a weak ETag, prefixed with `W/`.
+    /// These are preserved as returned, and can be used with [`GetOptions::if_none_match`],
+    /// but will never satisfy [`GetOptions::if_match`] or a conditional write.
+    ///
     /// <https://datatracker.ietf.org/doc/html/rfc9110#name-etag>
     pub e_tag: Option<String>,
     /// A version indicator for this object
     pub version: Option<String>,
+    /// The checksum of the object's contents computed by the store, if any
+    ///
+    /// See [`ObjectChecksum`] for the checksums provided by each store
+    pub checksum: Option<ObjectChecksum>,
 }
 
 /// Options for a get request, such as range
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub struct GetOptions {
     /// Request will succeed if the `ObjectMeta::e_tag` matches
     /// otherwise returning [`Error::Precondition`]
@@ -927,11 +1224,44 @@ pub struct GetOptions {
     /// <https://datatracker.ietf.org/doc/html/rfc9110#name-range>
     pub range: Option<GetRange>,
     /// Request a particular object version
+    ///
+    /// This is an opaque identifier as returned in [`ObjectMeta::version`] or
+    /// [`PutResult::version`], sent as the `versionId` query parameter for S3, the
+    /// `generation` for GCS, and the `versionid` for Azure
     pub version: Option<String>,
     /// Request transfer of no content
     ///
     /// <https://datatracker.ietf.org/doc/html/rfc9110#name-head>
     pub head: bool,
+    /// Request will fail with [`Error::TooLarge`] if the number of bytes to be returned,
+    /// i.e. the size of the object or the requested range, exceeds this limit
+    ///
+    /// This is checked before any of the body is read, and can be used to guard against
+    /// accidentally buffering an enormous object in memory, e.g. with [`GetResult::bytes`]
+    pub max_size: Option<usize>,
+    /// Verify the returned bytes against the checksum of the object provided by the
+    /// store, returning [`Error::ChecksumMismatch`] in place of the final chunk of the
+    /// payload should they differ
+    ///
+    /// This uses the `x-amz-checksum-*` headers for S3, requiring the object to have
+    /// been uploaded with a checksum, the `x-goog-hash` header for GCS, and `Content-MD5`
+    /// for Azure and HTTP stores. It is ignored for range requests, objects without a
+    /// stored checksum, and stores that do not provide checksums
+    pub verify_checksum: bool,
+
+    /// Decode the returned bytes according to the `Content-Encoding` of the response,
+    /// supporting `gzip`, `deflate`, `br` and `zstd`
+    ///
+    /// Without this, the bytes of an object stored with a `Content-Encoding` are
+    /// returned as stored, i.e. still encoded. Note that [`GetResult::meta`] and
+    /// [`GetResult::range`] continue to describe the encoded object, and so the
+    /// size of the decoded bytes will generally differ from [`ObjectMeta::size`].
+    ///
+    /// Returns [`Error::NotSupported`] for a range request of an encoded object, as
+    /// the range applies to the encoded bytes, or for an unsupported encoding.
+    /// Decoding requires the `compression` feature, and only applies to the HTTP-based
+    /// stores, as other stores do not record a `Content-Encoding`.
+    pub decompress: bool,
 }
 
 impl GetOptions {
@@ -944,7 +1274,9 @@ impl GetOptions {
         let last_modified = meta.last_modified;
 
         if let Some(m) = &self.if_match {
-            if m != "*" && m.split(',').map(str::trim).all(|x| x != etag) {
+            // If-Match uses the strong comparison, and so weak ETags never match
+            let strong = !util::is_weak_etag(etag);
+            if m != "*" && m.split(',').map(str::trim).all(|x| !strong || x != etag) {
                 return Err(Error::Precondition {
                     path: meta.location.to_string(),
                     source: format!("{etag} does not match {m}").into(),
@@ -960,7 +1292,13 @@ impl GetOptions {
         }
 
         if let Some(m) = &self.if_none_match {
-            if m == "*" || m.split(',').map(str::trim).any(|x| x == etag) {
+            // If-None-Match uses the weak comparison, ignoring any weak indicator
+            let weak = util::strip_weak_etag(etag);
+            if m == "*"
+                || m.split(',')
+                    .map(str::trim)
+                    .any(|x| util::strip_weak_etag(x) == weak)
+            {
                 return Err(Error::NotModified {
                     path: meta.location.to_string(),
                     source: format!("{etag} matches {m}").into(),
@@ -976,6 +1314,18 @@ impl GetOptions {
         }
         Ok(())
     }
+
+    /// Returns an error if `range` exceeds [`Self::max_size`]
+    fn check_max_size(&self, location: &Path, range: &Range<usize>) -> Result<()> {
+        match self.max_size {
+            Some(limit) if !self.head && range.len() > limit => Err(Error::TooLarge {
+                path: location.to_string(),
+                size: range.len(),
+                limit,
+            }),
+            _ => Ok(()),
+        }
+    }
 }
 
 /// Result for a get request
@@ -984,11 +1334,36 @@ pub struct GetResult {
     /// The [`GetResultPayload`]
     pub payload: GetResultPayload,
     /// The [`ObjectMeta`] for this object
+    ///
+    /// This is populated from the response to the get request itself, avoiding
+    /// the need for a separate [`ObjectStore::head`] request. For a range request,
+    /// [`ObjectMeta::size`] is the size of the full object, not of [`Self::range`]
     pub meta: ObjectMeta,
     /// The range of bytes returned by this request
     pub range: Range<usize>,
 }
 
+/// Result for a conditional get request, see [`ObjectStore::get_if_modified`]
+#[derive(Debug)]
+pub enum ConditionalGetResult {
+    /// The object has changed, and its new contents are returned
+    Modified(GetResult),
+    /// The object has not changed, and any cached copy can be used
+    ///
+    /// No response body is transferred in this case
+    NotModified,
+}
+
+impl ConditionalGetResult {
+    /// Returns the [`GetResult`] if the object was modified
+    pub fn modified(self) -> Option<GetResult> {
+        match self {
+            Self::Modified(r) => Some(r),
+            Self::NotModified => None,
+        }
+    }
+}
+
 /// The kind of a [`GetResult`]
 ///
 /// This special cases the case of a local file, as some systems may
@@ -1088,6 +1463,9 @@ pub enum PutMode {
 pub struct UpdateVersion {
     /// The unique identifier for the newly created object
     ///
+    /// This must be a strong ETag, as a weak ETag never matches the current object,
+    /// see `ClientOptions::with_reject_weak_etags`
+    ///
     /// <https://datatracker.ietf.org/doc/html/rfc9110#name-etag>
     pub e_tag: Option<String>,
     /// A version indicator for the newly created object
@@ -1112,6 +1490,34 @@ pub struct PutOptions {
     ///
     /// Implementations that don't support object tagging should ignore this
     pub tags: TagSet,
+    /// Provide a [`Retention`] configuration for this object, preventing it
+    /// from being overwritten or deleted until it expires
+    ///
+    /// Requires the bucket to have S3 Object Lock enabled. Implementations that
+    /// don't support object retention should ignore this
+    pub retention: Option<Retention>,
+    /// Place a legal hold on this object, preventing it from being overwritten or
+    /// deleted until the hold is removed, independently of any [`Retention`]
+    ///
+    /// Requires the bucket to have S3 Object Lock enabled. Implementations that
+    /// don't support legal holds should ignore this
+    pub legal_hold: bool,
+    /// User-defined metadata to store alongside this object
+    ///
+    /// Stores limit the total size of keys and values, 2 KiB for S3 and 8 KiB for
+    /// GCS and Azure, returning [`Error::MetadataTooLarge`] before sending the request
+    /// should this be exceeded. Keys must be valid header names, and for Azure valid
+    /// C# identifiers, i.e. consist of letters, digits and underscores.
+    ///
+    /// Implementations that don't support user-defined metadata should ignore this
+    pub metadata: BTreeMap<String, String>,
+    /// Apply a [`CannedAcl`] to this object, sent as the `x-amz-acl` header for S3
+    /// and the `x-goog-acl` header for GCS, the XML API equivalent of `predefinedAcl`
+    ///
+    /// Takes precedence over any default canned ACL configured on the store. Azure
+    /// controls access with the public access level of the container instead, and
+    /// like other implementations that don't support object ACLs ignores this
+    pub acl: Option<CannedAcl>,
 }
 
 impl From<PutMode> for PutOptions {
@@ -1132,6 +1538,57 @@ impl From<TagSet> for PutOptions {
     }
 }
 
+/// Options for a delete request
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct DeleteOptions {
+    /// Request will succeed if the `ObjectMeta::e_tag` matches
+    /// otherwise returning [`Error::Precondition`]
+    ///
+    /// See [`ObjectStore::delete_opts`] for details on atomicity
+    pub if_match: Option<String>,
+    /// Permanently delete a specific object version, as returned in
+    /// [`ObjectMeta::version`] or [`PutResult::version`]
+    ///
+    /// Without a version, deleting an object in a versioned S3 bucket only adds a
+    /// delete marker, with prior versions retained and still readable with
+    /// [`GetOptions::version`]. Deleting a specific version instead permanently
+    /// removes it, and is necessary to erase data. The version is sent as the
+    /// `versionId` query parameter for S3, the `generation` for GCS, and the
+    /// `versionid` for Azure.
+    ///
+    /// Stores without versioning return [`Error::NotSupported`]
+    pub version: Option<String>,
+}
+
+/// Options for a copy request
+///
+/// The conditions apply to the source of the copy, see [`ObjectStore::copy_opts`]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct CopyOptions {
+    /// Request will succeed if the `ObjectMeta::e_tag` of the source matches
+    /// otherwise returning [`Error::Precondition`]
+    ///
+    /// This is sent as `x-amz-copy-source-if-match` for S3, and
+    /// `x-ms-source-if-match` for Azure
+    pub if_match: Option<String>,
+    /// Request will succeed if the source has not been modified since
+    /// otherwise returning [`Error::Precondition`]
+    pub if_unmodified_since: Option<DateTime<Utc>>,
+    /// How to handle an existing object at the destination
+    pub mode: CopyMode,
+}
+
+/// Configure how a copy handles an existing object at the destination
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CopyMode {
+    /// Overwrite any object present at the destination, as [`ObjectStore::copy`]
+    #[default]
+    Overwrite,
+    /// Return [`Error::AlreadyExists`] if an object exists at the destination, as
+    /// [`ObjectStore::copy_if_not_exists`]
+    Create,
+}
+
 /// Result for a put request
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct PutResult {
@@ -1140,6 +1597,13 @@ pub struct PutResult {
     /// <https://datatracker.ietf.org/doc/html/rfc9110#name-etag>
     pub e_tag: Option<String>,
     /// A version indicator for the newly created object
+    ///
+    /// This is populated from the response to the put or multipart completion, i.e.
+    /// `x-amz-version-id` for S3, the object generation for GCS and `x-ms-version-id`
+    /// for Azure, and can be passed to [`GetOptions::version`] to read back exactly
+    /// the object that was written.
+    ///
+    /// This is `None` for stores, or buckets, without versioning enabled
     pub version: Option<String>,
 }
 
@@ -1194,6 +1658,57 @@ pub enum Error {
         source: Box<dyn std::error::Error + Send + Sync + 'static>,
     },
 
+    #[snafu(display(
+        "Object at location {} of {} bytes exceeds the maximum size of {} bytes",
+        path,
+        size,
+        limit
+    ))]
+    TooLarge {
+        path: String,
+        size: usize,
+        limit: usize,
+    },
+
+    #[snafu(display(
+        "User-defined metadata of {} bytes exceeds the {} limit of {} bytes",
+        size,
+        store,
+        limit
+    ))]
+    MetadataTooLarge {
+        store: &'static str,
+        size: usize,
+        limit: usize,
+    },
+
+    #[snafu(display("Checksum mismatch for object at location {}: {}", path, source))]
+    ChecksumMismatch {
+        path: String,
+        source: Box<dyn std::error::Error + Send + Sync + 'static>,
+    },
+
+    #[snafu(display(
+        "Object at location {} is protected by a retention policy or hold: {}",
+        path,
+        source
+    ))]
+    Retained {
+        path: String,
+        source: Box<dyn std::error::Error + Send + Sync + 'static>,
+    },
+
+    #[snafu(display("The provided credentials were rejected: {}", source))]
+    Unauthenticated {
+        source: Box<dyn std::error::Error + Send + Sync + 'static>,
+    },
+
+    #[snafu(display("Permission denied for path {}: {}", path, source))]
+    PermissionDenied {
+        path: String,
+        source: Box<dyn std::error::Error + Send + Sync + 'static>,
+    },
+
     #[snafu(display("Operation not yet implemented."))]
     NotImplemented,
 
@@ -1201,10 +1716,34 @@ pub enum Error {
     UnknownConfigurationKey { store: &'static str, key: String },
 }
 
+#[cfg(feature = "cloud")]
+impl Error {
+    /// Returns the request id, error code and message of the server response that
+    /// caused this error, if any
+    ///
+    /// These are typically requested when raising a support case with the cloud provider
+    pub fn server_details(&self) -> Option<&ServerErrorDetails> {
+        let mut source = std::error::Error::source(self);
+        while let Some(e) = source {
+            if let Some(details) = e
+                .downcast_ref::<client::retry::Error>()
+                .and_then(|e| e.details())
+            {
+                return Some(details);
+            }
+            source = e.source();
+        }
+        None
+    }
+}
+
 impl From<Error> for std::io::Error {
     fn from(e: Error) -> Self {
         let kind = match &e {
             Error::NotFound { .. } => std::io::ErrorKind::NotFound,
+            Error::Unauthenticated { .. } | Error::PermissionDenied { .. } => {
+                std::io::ErrorKind::PermissionDenied
+            }
             _ => std::io::ErrorKind::Other,
         };
         Self::new(kind, e)
@@ -1623,6 +2162,29 @@ mod tests {
             assert_eq!(actual, expected, "{prefix:?} - {offset:?}");
         }
 
+        let cases = [
+            (Path::from(""), Path::from("b")),
+            (Path::from("a/a.file"), Path::from("a/b.file")),
+            (Path::from("a/a"), Path::from("aa/a.file")),
+            (Path::from("a/😀"), Path::from("ab")),
+            (Path::from("aa"), Path::from("a")),
+            (Path::from("a/b.file"), Path::from("a/b.file")),
+        ];
+
+        for (start, end) in cases {
+            let s = storage.list_range(&start, &end);
+            let mut actual: Vec<_> = s.map_ok(|x| x.location).try_collect().await.unwrap();
+            actual.sort_unstable();
+
+            let expected: Vec<_> = files
+                .iter()
+                .filter(|x| **x >= start && **x < end)
+                .cloned()
+                .collect();
+
+            assert_eq!(actual, expected, "[{start:?}, {end:?})");
+        }
+
         // Test bulk delete
         let paths = vec![
             Path::from("a/a.file"),
@@ -1683,6 +2245,37 @@ mod tests {
         storage.put(&path, "foo".into()).await.unwrap();
         let meta = storage.head(&path).await.unwrap();
 
+        let options = GetOptions {
+            max_size: Some(3),
+            ..GetOptions::default()
+        };
+        let bytes = storage.get_opts(&path, options).await.unwrap();
+        assert_eq!(bytes.bytes().await.unwrap().as_ref(), b"foo");
+
+        let options = GetOptions {
+            max_size: Some(2),
+            ..GetOptions::default()
+        };
+        match storage.get_opts(&path, options).await {
+            Err(Error::TooLarge { size, limit, .. }) => assert_eq!((size, limit), (3, 2)),
+            r => panic!("expected TooLarge, got {r:?}"),
+        }
+
+        let options = GetOptions {
+            max_size: Some(2),
+            range: Some((1..3).into()),
+            ..GetOptions::default()
+        };
+        let bytes = storage.get_opts(&path, options).await.unwrap();
+        assert_eq!(bytes.bytes().await.unwrap().as_ref(), b"oo");
+
+        let options = GetOptions {
+            max_size: Some(0),
+            head: true,
+            ..GetOptions::default()
+        };
+        storage.get_opts(&path, options).await.unwrap();
+
         let options = GetOptions {
             if_unmodified_since: Some(meta.last_modified),
             ..GetOptions::default()
@@ -1746,14 +2339,19 @@ mod tests {
             if_none_match: Some(tag.clone()),
             ..GetOptions::default()
         };
-        let err = storage.get_opts(&path, options).await.unwrap_err();
+        let err = storage.get_opts(&path, options.clone()).await.unwrap_err();
         assert!(matches!(err, Error::NotModified { .. }), "{err}");
+        let result = storage.get_if_modified(&path, options).await.unwrap();
+        assert!(matches!(result, ConditionalGetResult::NotModified));
 
         let options = GetOptions {
             if_none_match: Some("invalid".to_string()),
             ..GetOptions::default()
         };
-        storage.get_opts(&path, options).await.unwrap();
+        storage.get_opts(&path, options.clone()).await.unwrap();
+        let result = storage.get_if_modified(&path, options).await.unwrap();
+        let data = result.modified().unwrap().bytes().await.unwrap();
+        assert_eq!(data.as_ref(), b"foo");
 
         let result = storage.put(&path, "test".into()).await.unwrap();
         let new_tag = result.e_tag.unwrap();
@@ -1899,6 +2497,33 @@ mod tests {
         assert_eq!(v, NUM_WORKERS * NUM_INCREMENTS);
     }
 
+    pub(crate) async fn delete_opts(storage: &dyn ObjectStore) {
+        let path = Path::from("delete_opts");
+        let meta = storage.put(&path, "a".into()).await.unwrap();
+        let e_tag = meta.e_tag.unwrap();
+
+        let mismatch = DeleteOptions {
+            if_match: Some("invalid".to_string()),
+            ..Default::default()
+        };
+        let err = storage.delete_opts(&path, mismatch).await.unwrap_err();
+        assert!(matches!(err, Error::Precondition { .. }), "{err}");
+        storage.head(&path).await.unwrap();
+
+        let matching = DeleteOptions {
+            if_match: Some(e_tag),
+            ..Default::default()
+        };
+        storage.delete_opts(&path, matching).await.unwrap();
+        let err = storage.head(&path).await.unwrap_err();
+        assert!(matches!(err, Error::NotFound { .. }), "{err}");
+
+        storage
+            .delete_opts(&path, DeleteOptions::default())
+            .await
+            .ok();
+    }
+
     /// Returns a chunk of length `chunk_length`
     fn get_chunk(chunk_length: usize) -> Bytes {
         let mut data = vec![0_u8; chunk_length];
@@ -1979,6 +2604,10 @@ mod tests {
         }
         drop(writer);
 
+        // Dropping the writer must not complete the upload
+        let err = storage.head(&location).await.unwrap_err();
+        assert!(matches!(err, crate::Error::NotFound { .. }), "{err}");
+
         storage
             .abort_multipart(&location, &upload_id)
             .await
@@ -2065,6 +2694,23 @@ mod tests {
             storage.put(f, data).await.unwrap();
         }
 
+        // ==================== check: list the root with and without an empty prefix ====================
+        let root = Path::from("");
+        let mut expected = files.clone();
+        expected.sort_unstable();
+        for prefix in [None, Some(&root)] {
+            let mut content_list = flatten_list_stream(storage, prefix).await.unwrap();
+            content_list.sort_unstable();
+            assert_eq!(content_list, expected, "{prefix:?}");
+
+            let result = storage.list_with_delimiter(prefix).await.unwrap();
+            // Local stores may also return empty directories left by previous tests
+            let mydb = Path::from("mydb");
+            assert!(result.common_prefixes.contains(&mydb), "{prefix:?}");
+            assert_eq!(result.objects.len(), 1, "{prefix:?}");
+            assert_eq!(result.objects[0].location, files[0], "{prefix:?}");
+        }
+
         // ==================== check: prefix-list `mydb/wb` (directory) ====================
         let prefix = Path::from("mydb/wb");
 
@@ -2082,6 +2728,23 @@ mod tests {
         assert_eq!(object.location, expected_location);
         assert_eq!(object.size, data.len());
 
+        // ==================== check: streaming prefix-list `mydb/wb` matches ====================
+        let pages: Vec<_> = storage
+            .list_with_delimiter_stream(Some(&prefix))
+            .try_collect()
+            .await
+            .unwrap();
+        let mut common_prefixes: Vec<_> = pages
+            .iter()
+            .flat_map(|p| p.common_prefixes.iter().cloned())
+            .collect();
+        common_prefixes.sort_unstable();
+        common_prefixes.dedup();
+        assert_eq!(common_prefixes, result.common_prefixes);
+        let objects: Vec<_> = pages.iter().flat_map(|p| &p.objects).collect();
+        assert_eq!(objects.len(), 1);
+        assert_eq!(objects[0].location, expected_location);
+
         // ==================== check: prefix-list `mydb/wb/000/000/001` (partial filename doesn't match) ====================
         let prefix = Path::from("mydb/wb/000/000/001");
 
@@ -2142,8 +2805,32 @@ mod tests {
         assert!(result.is_err());
         assert!(matches!(result.unwrap_err(), crate::Error::NotFound { .. }));
 
+        // copy_opts() with CopyMode::Create errors if destination already exists
+        let opts = CopyOptions {
+            mode: CopyMode::Create,
+            ..Default::default()
+        };
+        storage.put(&path1, contents1.clone()).await.unwrap();
+        let err = storage
+            .copy_opts(&path1, &path2, opts.clone())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, crate::Error::AlreadyExists { .. }), "{err}");
+
+        // Only one of two racing copies to the same destination succeeds
+        let path3 = Path::from("not_exists_nested/test3");
+        let (a, b) = futures::join!(
+            storage.copy_if_not_exists(&path1, &path3),
+            storage.copy_opts(&path2, &path3, opts)
+        );
+        assert!(a.is_ok() != b.is_ok(), "{a:?} {b:?}");
+        let err = a.err().or(b.err()).unwrap();
+        assert!(matches!(err, crate::Error::AlreadyExists { .. }), "{err}");
+
         // Clean up
+        storage.delete(&path1).await.unwrap();
         storage.delete(&path2).await.unwrap();
+        storage.delete(&path3).await.unwrap();
     }
 
     pub(crate) async fn copy_if_not_exists(storage: &DynObjectStore) {
@@ -2335,6 +3022,7 @@ mod tests {
             size: 100,
             e_tag: Some("123".to_string()),
             version: None,
+            checksum: None,
         };
 
         let mut options = GetOptions::default();
@@ -18,8 +18,9 @@
 //! An object store that limits the maximum concurrency of the wrapped implementation
 
 use crate::{
-    BoxStream, GetOptions, GetResult, GetResultPayload, ListResult, MultipartId, ObjectMeta,
-    ObjectStore, Path, PutOptions, PutResult, Result, StreamExt,
+    Acl, BoxStream, CopyOptions, DeleteOptions, GetOptions, GetResult, GetResultPayload, ListPage,
+    ListResult, MultipartId, ObjectMeta, ObjectStore, Path, PutOptions, PutResult, Result,
+    StreamExt,
 };
 use async_trait::async_trait;
 use bytes::Bytes;
@@ -31,6 +32,7 @@ use std::sync::Arc;
 use std::task::{Context, Poll};
 use tokio::io::AsyncWrite;
 use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use url::Url;
 
 /// Store wrapper that wraps an inner store and limits the maximum number of concurrent
 /// object store operations. Where each call to an [`ObjectStore`] member function is
@@ -126,6 +128,11 @@ impl<T: ObjectStore> ObjectStore for LimitStore<T> {
         self.inner.delete(location).await
     }
 
+    async fn delete_opts(&self, location: &Path, opts: DeleteOptions) -> Result<()> {
+        let _permit = self.semaphore.acquire().await.unwrap();
+        self.inner.delete_opts(location, opts).await
+    }
+
     fn delete_stream<'a>(
         &'a self,
         locations: BoxStream<'a, Result<Path>>,
@@ -160,21 +167,79 @@ impl<T: ObjectStore> ObjectStore for LimitStore<T> {
         fut.into_stream().flatten().boxed()
     }
 
+    fn list_range(&self, start: &Path, end: &Path) -> BoxStream<'_, Result<ObjectMeta>> {
+        let (start, end) = (start.clone(), end.clone());
+        let fut = Arc::clone(&self.semaphore)
+            .acquire_owned()
+            .map(move |permit| {
+                let s = self.inner.list_range(&start, &end);
+                PermitWrapper::new(s, permit.unwrap())
+            });
+        fut.into_stream().flatten().boxed()
+    }
+
+    fn list_from_token(
+        &self,
+        prefix: Option<&Path>,
+        token: Option<&str>,
+    ) -> BoxStream<'_, Result<ListPage>> {
+        let prefix = prefix.cloned();
+        let token = token.map(ToString::to_string);
+        let fut = Arc::clone(&self.semaphore)
+            .acquire_owned()
+            .map(move |permit| {
+                let s = self
+                    .inner
+                    .list_from_token(prefix.as_ref(), token.as_deref());
+                PermitWrapper::new(s, permit.unwrap())
+            });
+        fut.into_stream().flatten().boxed()
+    }
+
     async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
         let _permit = self.semaphore.acquire().await.unwrap();
         self.inner.list_with_delimiter(prefix).await
     }
 
+    fn list_with_delimiter_stream(
+        &self,
+        prefix: Option<&Path>,
+    ) -> BoxStream<'_, Result<ListResult>> {
+        let prefix = prefix.cloned();
+        let fut = Arc::clone(&self.semaphore)
+            .acquire_owned()
+            .map(move |permit| {
+                let s = self.inner.list_with_delimiter_stream(prefix.as_ref());
+                PermitWrapper::new(s, permit.unwrap())
+            });
+        fut.into_stream().flatten().boxed()
+    }
+
     async fn copy(&self, from: &Path, to: &Path) -> Result<()> {
         let _permit = self.semaphore.acquire().await.unwrap();
         self.inner.copy(from, to).await
     }
 
+    async fn copy_opts(&self, from: &Path, to: &Path, opts: CopyOptions) -> Result<()> {
+        let _permit = self.semaphore.acquire().await.unwrap();
+        self.inner.copy_opts(from, to, opts).await
+    }
+
     async fn rename(&self, from: &Path, to: &Path) -> Result<()> {
         let _permit = self.semaphore.acquire().await.unwrap();
         self.inner.rename(from, to).await
     }
 
+    async fn probe(&self) -> Result<()> {
+        let _permit = self.semaphore.acquire().await.unwrap();
+        self.inner.probe().await
+    }
+
+    async fn get_acl(&self, location: &Path) -> Result<Acl> {
+        let _permit = self.semaphore.acquire().await.unwrap();
+        self.inner.get_acl(location).await
+    }
+
     async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
         let _permit = self.semaphore.acquire().await.unwrap();
         self.inner.copy_if_not_exists(from, to).await
@@ -184,6 +249,14 @@ impl<T: ObjectStore> ObjectStore for LimitStore<T> {
         let _permit = self.semaphore.acquire().await.unwrap();
         self.inner.rename_if_not_exists(from, to).await
     }
+
+    fn object_url(&self, location: &Path) -> Result<Url> {
+        self.inner.object_url(location)
+    }
+
+    fn validate_path(&self, location: &Path) -> Result<()> {
+        self.inner.validate_path(location)
+    }
 }
 
 fn permit_get_result(r: GetResult, permit: OwnedSemaphorePermit) -> GetResult {
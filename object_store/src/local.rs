@@ -18,8 +18,9 @@
 //! An object store implementation for a local filesystem
 use crate::{
     maybe_spawn_blocking,
+    multipart::{validate_parts, MultiPartStore, PartId},
     path::{absolute_path_to_url, Path},
-    util::InvalidGetRange,
+    util::{range_prefix, url_with_path, InvalidGetRange},
     GetOptions, GetResult, GetResultPayload, ListResult, MultipartId, ObjectMeta, ObjectStore,
     PutMode, PutOptions, PutResult, Result,
 };
@@ -207,6 +208,10 @@ impl From<Error> for super::Error {
 /// sequences, such as unpaired UTF-16 surrogates, and [`LocalFileSystem`] will error on
 /// encountering such sequences.
 ///
+/// On Windows, where `\` is also a path separator, a `\` within a [`Path`] segment is
+/// normalized to the [`Path`] delimiter. A location such as `a\b/c` therefore refers to
+/// the same file as `a/b/c`, and will be listed as `a/b/c`.
+///
 /// Finally, filenames matching the regex `/.*#\d+/`, e.g. `foo.parquet#123`, are not supported
 /// by [`LocalFileSystem`] as they are used to provide atomic writes. Such files will be ignored
 /// for listing operations, and attempting to address such a file will error.
@@ -299,13 +304,26 @@ impl Config {
 
     /// Return an absolute filesystem path of the given location
     fn prefix_to_filesystem(&self, location: &Path) -> Result<PathBuf> {
+        let parts = location.parts();
+
+        // `\` is a path separator on Windows, split it out explicitly so that
+        // the resulting segments are consistent with those returned by listing
+        #[cfg(target_family = "windows")]
+        let parts = parts.flat_map(|part| {
+            part.as_ref()
+                .split(ENCODED_BACKSLASH)
+                .filter(|s| !s.is_empty())
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+        });
+
         let mut url = self.root.clone();
         url.path_segments_mut()
             .expect("url path")
             // technically not necessary as Path ignores empty segments
             // but avoids creating paths with "//" which look odd in error messages.
             .pop_if_empty()
-            .extend(location.parts());
+            .extend(parts);
 
         url.to_file_path()
             .map_err(|_| Error::InvalidUrl { url }.into())
@@ -320,6 +338,10 @@ impl Config {
     }
 }
 
+/// The percent-encoded form of `\` within a [`PathPart`](crate::path::PathPart)
+#[cfg(target_family = "windows")]
+const ENCODED_BACKSLASH: &str = "%5C";
+
 fn is_valid_file_path(path: &Path) -> bool {
     match path.filename() {
         Some(p) => match p.split_once('#') {
@@ -430,10 +452,11 @@ impl ObjectStore for LocalFileSystem {
             let meta = convert_metadata(metadata, location)?;
             options.check_preconditions(&meta)?;
 
-            let range = match options.range {
+            let range = match &options.range {
                 Some(r) => r.as_range(meta.size).context(InvalidRangeSnafu)?,
                 None => 0..meta.size,
             };
+            options.check_max_size(&meta.location, &range)?;
 
             Ok(GetResult {
                 payload: GetResultPayload::File(file, path),
@@ -547,6 +570,15 @@ impl ObjectStore for LocalFileSystem {
         .boxed()
     }
 
+    fn list_range(&self, start: &Path, end: &Path) -> BoxStream<'_, Result<ObjectMeta>> {
+        // Objects are not listed in lexicographic order
+        let prefix = range_prefix(start, end);
+        let (start, end) = (start.clone(), end.clone());
+        self.list(prefix.as_ref())
+            .try_filter(move |f| futures::future::ready(f.location >= start && f.location < end))
+            .boxed()
+    }
+
     async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
         let config = Arc::clone(&self.config);
 
@@ -664,6 +696,146 @@ impl ObjectStore for LocalFileSystem {
         })
         .await
     }
+
+    fn object_url(&self, location: &Path) -> Result<Url> {
+        Ok(url_with_path(self.config.root.clone(), location))
+    }
+
+    fn validate_path(&self, location: &Path) -> Result<()> {
+        self.config.path_to_filesystem(location).map(|_| ())
+    }
+}
+
+/// Each upload is staged as a manifest file `{dest}#{id}`, listing the staged file of
+/// each part, allowing an upload to be resumed by another [`LocalFileSystem`] instance
+#[async_trait]
+impl MultiPartStore for LocalFileSystem {
+    async fn create_multipart(&self, location: &Path) -> Result<MultipartId> {
+        let dest = self.config.path_to_filesystem(location)?;
+        maybe_spawn_blocking(move || Ok(new_staged_upload(&dest)?.1)).await
+    }
+
+    async fn put_part(
+        &self,
+        location: &Path,
+        id: &MultipartId,
+        part_idx: usize,
+        data: Bytes,
+    ) -> Result<PartId> {
+        let dest = self.config.path_to_filesystem(location)?;
+        let manifest = staged_upload_path(&dest, id);
+        maybe_spawn_blocking(move || {
+            let mut m = OpenOptions::new()
+                .append(true)
+                .open(&manifest)
+                .map_err(|source| match source.kind() {
+                    ErrorKind::NotFound => Error::NotFound {
+                        path: manifest.clone(),
+                        source,
+                    },
+                    _ => Error::UnableToOpenFile {
+                        path: manifest.clone(),
+                        source,
+                    },
+                })?;
+
+            let (mut file, suffix) = new_staged_upload(&dest)?;
+            if let Err(source) = file.write_all(&data) {
+                let _ = std::fs::remove_file(staged_upload_path(&dest, &suffix));
+                return Err(Error::UnableToCopyDataToFile { source }.into());
+            }
+            writeln!(m, "{suffix}").context(UnableToCopyDataToFileSnafu)?;
+
+            Ok(PartId::new(suffix, part_idx))
+        })
+        .await
+    }
+
+    async fn complete_multipart(
+        &self,
+        location: &Path,
+        id: &MultipartId,
+        parts: Vec<PartId>,
+    ) -> Result<PutResult> {
+        validate_parts("LocalFileSystem", &parts)?;
+        let dest = self.config.path_to_filesystem(location)?;
+        let manifest = staged_upload_path(&dest, id);
+        maybe_spawn_blocking(move || {
+            let staged = read_manifest(&manifest)?;
+            let mut file = OpenOptions::new()
+                .write(true)
+                .truncate(true)
+                .open(&manifest)
+                .context(UnableToOpenFileSnafu { path: &manifest })?;
+
+            for part in &parts {
+                let path = staged_upload_path(&dest, &part.content_id);
+                let mut part = File::open(&path).context(UnableToOpenFileSnafu { path })?;
+                io::copy(&mut part, &mut file).context(UnableToCopyDataToFileSnafu)?;
+            }
+            let metadata = file.metadata().map_err(|e| Error::Metadata {
+                source: e.into(),
+                path: manifest.to_string_lossy().to_string(),
+            })?;
+            std::mem::drop(file);
+            std::fs::rename(&manifest, &dest).context(UnableToRenameFileSnafu)?;
+
+            for suffix in staged {
+                let _ = std::fs::remove_file(staged_upload_path(&dest, &suffix));
+            }
+            Ok(PutResult {
+                e_tag: Some(get_etag(&metadata)),
+                version: None,
+            })
+        })
+        .await
+    }
+
+    async fn abort_multipart(&self, location: &Path, id: &MultipartId) -> Result<()> {
+        let dest = self.config.path_to_filesystem(location)?;
+        let manifest = staged_upload_path(&dest, id);
+        maybe_spawn_blocking(move || {
+            let staged = match read_manifest(&manifest) {
+                Ok(staged) => staged,
+                Err(crate::Error::NotFound { .. }) => return Ok(()), // Already aborted
+                Err(e) => return Err(e),
+            };
+            for suffix in staged {
+                let _ = std::fs::remove_file(staged_upload_path(&dest, &suffix));
+            }
+            match std::fs::remove_file(&manifest) {
+                Ok(_) => Ok(()),
+                Err(source) => match source.kind() {
+                    ErrorKind::NotFound => Ok(()),
+                    _ => Err(Error::UnableToDeleteFile {
+                        path: manifest,
+                        source,
+                    }
+                    .into()),
+                },
+            }
+        })
+        .await
+    }
+}
+
+/// Returns the suffixes of the staged parts listed in the upload manifest at `path`
+fn read_manifest(path: &std::path::Path) -> Result<Vec<String>> {
+    let contents = std::fs::read_to_string(path).map_err(|source| match source.kind() {
+        ErrorKind::NotFound => Error::NotFound {
+            path: path.to_path_buf(),
+            source,
+        },
+        _ => Error::UnableToReadBytes {
+            path: path.to_path_buf(),
+            source,
+        },
+    })?;
+    Ok(contents
+        .lines()
+        .filter(|l| !l.is_empty() && l.bytes().all(|b| b.is_ascii_digit()))
+        .map(ToString::to_string)
+        .collect())
 }
 
 /// Creates the parent directories of `path` or returns an error based on `source` if no parent
@@ -673,7 +845,33 @@ fn create_parent_dirs(path: &std::path::Path, source: io::Error) -> Result<()> {
         source,
     })?;
 
-    std::fs::create_dir_all(parent).context(UnableToCreateDirSnafu { path: parent })?;
+    create_dir_all(parent).context(UnableToCreateDirSnafu { path: parent })?;
+    Ok(())
+}
+
+/// Creates the directory `path` and any missing ancestors
+///
+/// Unlike [`std::fs::create_dir_all`] this is iterative, and so does not recurse
+/// once per missing ancestor of a deeply nested `path`
+fn create_dir_all(path: &std::path::Path) -> io::Result<()> {
+    let mut missing = vec![];
+    let mut current = Some(path);
+    while let Some(dir) = current {
+        if dir.as_os_str().is_empty() || dir.is_dir() {
+            break;
+        }
+        missing.push(dir);
+        current = dir.parent();
+    }
+
+    for dir in missing.into_iter().rev() {
+        match std::fs::create_dir(dir) {
+            Ok(()) => {}
+            // Another writer may have concurrently created the directory
+            Err(e) if e.kind() == ErrorKind::AlreadyExists && dir.is_dir() => {}
+            Err(e) => return Err(e),
+        }
+    }
     Ok(())
 }
 
@@ -1030,6 +1228,7 @@ fn convert_metadata(metadata: Metadata, location: Path) -> Result<ObjectMeta> {
         size,
         e_tag: Some(get_etag(&metadata)),
         version: None,
+        checksum: None,
     })
 }
 
@@ -1115,6 +1314,64 @@ mod tests {
         copy_if_not_exists(&integration).await;
         stream_get(&integration).await;
         put_opts(&integration, false).await;
+        delete_opts(&integration).await;
+        multipart(&integration, &integration).await;
+    }
+
+    #[tokio::test]
+    async fn multipart_resume() {
+        let root = TempDir::new().unwrap();
+        let path = Path::from("dir/resumed");
+
+        // Stage the first part, persisting the upload and part ids
+        let integration = LocalFileSystem::new_with_prefix(root.path()).unwrap();
+        let id = integration.create_multipart(&path).await.unwrap();
+        let first = integration
+            .put_part(&path, &id, 0, "hello ".into())
+            .await
+            .unwrap();
+        let (part_idx, content_id) = (first.part_idx(), first.content_id);
+        // Staged parts are not visible
+        assert!(flatten_list_stream(&integration, None)
+            .await
+            .unwrap()
+            .is_empty());
+        drop(integration);
+
+        // A new instance can continue and complete the upload
+        let integration = LocalFileSystem::new_with_prefix(root.path()).unwrap();
+        let second = integration
+            .put_part(&path, &id, 1, "world".into())
+            .await
+            .unwrap();
+        let first = PartId::new(content_id, part_idx);
+        integration
+            .complete_multipart(&path, &id, vec![first, second])
+            .await
+            .unwrap();
+
+        let data = integration.get(&path).await.unwrap().bytes().await.unwrap();
+        assert_eq!(data.as_ref(), b"hello world");
+        // All staged files are removed
+        let files = std::fs::read_dir(root.path().join("dir")).unwrap().count();
+        assert_eq!(files, 1);
+
+        // Aborting removes any staged parts
+        let id = integration.create_multipart(&path).await.unwrap();
+        integration
+            .put_part(&path, &id, 0, "data".into())
+            .await
+            .unwrap();
+        MultiPartStore::abort_multipart(&integration, &path, &id)
+            .await
+            .unwrap();
+        let files = std::fs::read_dir(root.path().join("dir")).unwrap().count();
+        assert_eq!(files, 1);
+        let err = integration
+            .put_part(&path, &id, 1, "data".into())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, crate::Error::NotFound { .. }), "{err}");
     }
 
     #[test]
@@ -1266,6 +1523,70 @@ mod tests {
         fs.list_with_delimiter(None).await.unwrap();
     }
 
+    #[tokio::test]
+    #[cfg(target_family = "windows")]
+    async fn test_windows_separators() {
+        let root = TempDir::new().unwrap();
+        let integration = LocalFileSystem::new_with_prefix(root.path()).unwrap();
+
+        let location = Path::from("a/b/c");
+        integration.put(&location, "data".into()).await.unwrap();
+        assert!(root.path().join("a").join("b").join("c").is_file());
+        check_list(&integration, None, &["a/b/c"]).await;
+
+        // A backslash within a segment is normalized to a separator
+        let backslash = Path::from_iter(["a\\b", "c"]);
+        assert_eq!(backslash.as_ref(), "a%5Cb/c");
+        let read = integration.get(&backslash).await.unwrap().bytes().await;
+        assert_eq!(read.unwrap().as_ref(), b"data");
+
+        let other = Path::from_iter(["d\\e", "f"]);
+        integration.put(&other, "other".into()).await.unwrap();
+        assert!(root.path().join("d").join("e").join("f").is_file());
+        check_list(&integration, None, &["a/b/c", "d/e/f"]).await;
+
+        let listed = Path::from("d/e/f");
+        let read = integration.get(&listed).await.unwrap().bytes().await;
+        assert_eq!(read.unwrap().as_ref(), b"other");
+
+        let prefix = Path::from_iter(["d\\e"]);
+        check_list(&integration, Some(&prefix), &["d/e/f"]).await;
+
+        // Mixed separators in the filesystem path are normalized
+        let mixed = format!("{}\\a/b\\c", root.path().display());
+        let path = Path::from_filesystem_path(mixed).unwrap();
+        assert!(path.as_ref().ends_with("/a/b/c"), "{path}");
+    }
+
+    #[tokio::test]
+    async fn test_deep_path() {
+        let root = TempDir::new().unwrap();
+        let integration = LocalFileSystem::new_with_prefix(root.path()).unwrap();
+
+        let raw = vec!["a"; 900].join("/");
+        let location = Path::parse(&raw).unwrap();
+        assert_eq!(location.parts().count(), 900);
+        assert_eq!(location.as_ref(), raw);
+
+        integration.put(&location, "data".into()).await.unwrap();
+        let read = integration.get(&location).await.unwrap().bytes().await;
+        assert_eq!(read.unwrap().as_ref(), b"data");
+
+        check_list(&integration, None, &[raw.as_str()]).await;
+        let prefix = Path::from_iter(vec!["a"; 450]);
+        check_list(&integration, Some(&prefix), &[raw.as_str()]).await;
+
+        let parent = Path::from_iter(vec!["a"; 899]);
+        let sibling = parent.child("b");
+        integration.put(&sibling, "other".into()).await.unwrap();
+        let result = integration.list_with_delimiter(Some(&parent)).await;
+        let mut objects = result.unwrap().objects;
+        objects.sort_unstable_by(|a, b| a.location.cmp(&b.location));
+        assert_eq!(objects.len(), 2);
+        assert_eq!(objects[0].location, location);
+        assert_eq!(objects[1].location, sibling);
+    }
+
     async fn check_list(integration: &LocalFileSystem, prefix: Option<&Path>, expected: &[&str]) {
         let result: Vec<_> = integration.list(prefix).try_collect().await.unwrap();
 
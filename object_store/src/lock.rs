@@ -0,0 +1,318 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! An advisory lock built on conditional object operations
+//!
+//! # Consistency
+//!
+//! [`ObjectLock`] provides mutual exclusion between processes that coordinate through
+//! the same lock object, under the following assumptions:
+//!
+//! * The store provides atomic conditional creates, updates and deletes, see [`PutMode`]
+//!   and [`DeleteOptions`]. Stores lacking support will return an error, for example
+//!   [`AmazonS3`](crate::aws::AmazonS3) requires
+//!   [`S3ConditionalPut`](crate::aws::S3ConditionalPut) to be configured
+//! * The clocks of all contenders are loosely synchronised, as an expired lease is
+//!   determined from the expiry time written by its holder
+//! * A holder renews its lease well before it expires, and stops relying on the lock
+//!   if renewal fails or the lease has expired
+//!
+//! As with any lease-based lock, a holder that is paused for longer than its lease,
+//! for example by a long garbage collection, may briefly overlap with a new holder.
+//! Applications requiring strict exclusion should additionally fence their writes, for
+//! example with [`cas_put`].
+//!
+//! [`PutMode`]: crate::PutMode
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+
+use crate::cas::{self, cas_put};
+use crate::path::Path;
+use crate::{DeleteOptions, Error, ObjectStore, Result};
+
+/// The default lease duration of an [`ObjectLock`]
+const DEFAULT_LEASE: Duration = Duration::from_secs(30);
+
+const STORE: &str = "ObjectLock";
+
+/// An advisory lock represented by an object in an [`ObjectStore`]
+///
+/// The lock object contains the id of its owner, and the time at which its lease
+/// expires, after which it may be taken over by another contender. This allows
+/// recovering from holders that crashed without releasing the lock.
+///
+/// See the [module docs](self) for the consistency assumptions
+///
+/// ```
+/// # use std::sync::Arc;
+/// # use object_store::{lock::ObjectLock, memory::InMemory, path::Path};
+/// # async fn example() -> object_store::Result<()> {
+/// let store = Arc::new(InMemory::new());
+/// let lock = ObjectLock::new(store, Path::from("locks/job"), "worker-1");
+/// if let Some(lease) = lock.try_acquire().await? {
+///     // ... perform work, periodically calling `lock.renew(&lease)`
+///     lock.release(lease).await?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ObjectLock {
+    store: Arc<dyn ObjectStore>,
+    location: Path,
+    owner: String,
+    lease: Duration,
+}
+
+/// A lease on an [`ObjectLock`], returned on successfully acquiring the lock
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LockLease {
+    e_tag: String,
+    expires: DateTime<Utc>,
+}
+
+impl LockLease {
+    /// The time at which this lease expires, unless renewed
+    pub fn expires(&self) -> DateTime<Utc> {
+        self.expires
+    }
+
+    /// Returns true if this lease has expired
+    pub fn is_expired(&self) -> bool {
+        Utc::now() >= self.expires
+    }
+}
+
+impl ObjectLock {
+    /// Create a new [`ObjectLock`] stored at `location`, identifying this contender
+    /// by `owner`
+    pub fn new(store: Arc<dyn ObjectStore>, location: Path, owner: impl Into<String>) -> Self {
+        Self {
+            store,
+            location,
+            owner: owner.into(),
+            lease: DEFAULT_LEASE,
+        }
+    }
+
+    /// Set the duration of the lease granted on acquiring or renewing the lock
+    ///
+    /// Defaults to 30 seconds
+    pub fn with_lease(mut self, lease: Duration) -> Self {
+        self.lease = lease;
+        self
+    }
+
+    /// Attempt to acquire the lock, returning `None` if it is held by another contender
+    ///
+    /// A lock whose lease has expired is taken over
+    pub async fn try_acquire(&self) -> Result<Option<LockLease>> {
+        let (body, expires) = self.lock_body()?;
+        match cas_put(self.store.as_ref(), &self.location, None, body).await {
+            Ok(r) => return Ok(Some(self.lease(r.e_tag, expires)?)),
+            Err(cas::Error::Conflict { .. }) => {}
+            Err(e) => return Err(e.into()),
+        }
+
+        let result = match self.store.get(&self.location).await {
+            Ok(result) => result,
+            // Released since the create was attempted
+            Err(Error::NotFound { .. }) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let e_tag = result.meta.e_tag.clone();
+        let (_, current_expiry) = parse_lock_body(&result.bytes().await?)?;
+        let e_tag = match e_tag {
+            Some(e_tag) if Utc::now() >= current_expiry => e_tag,
+            _ => return Ok(None),
+        };
+
+        let (body, expires) = self.lock_body()?;
+        match cas_put(self.store.as_ref(), &self.location, Some(&e_tag), body).await {
+            Ok(r) => Ok(Some(self.lease(r.e_tag, expires)?)),
+            Err(cas::Error::Conflict { .. }) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Acquire the lock, attempting to acquire it every `poll_interval` until successful
+    pub async fn acquire(&self, poll_interval: Duration) -> Result<LockLease> {
+        loop {
+            if let Some(lease) = self.try_acquire().await? {
+                return Ok(lease);
+            }
+            tokio::time::sleep(poll_interval).await;
+        }
+    }
+
+    /// Extend `lease`, returning the renewed [`LockLease`]
+    ///
+    /// Returns [`Error::Precondition`] if the lock is no longer held by `lease`,
+    /// for example because it expired and was taken over by another contender
+    pub async fn renew(&self, lease: &LockLease) -> Result<LockLease> {
+        let (body, expires) = self.lock_body()?;
+        let r = cas_put(
+            self.store.as_ref(),
+            &self.location,
+            Some(&lease.e_tag),
+            body,
+        )
+        .await?;
+        self.lease(r.e_tag, expires)
+    }
+
+    /// Release the lock held by `lease`
+    ///
+    /// Returns [`Error::Precondition`] if the lock is no longer held by `lease`
+    pub async fn release(&self, lease: LockLease) -> Result<()> {
+        let opts = DeleteOptions {
+            if_match: Some(lease.e_tag),
+            ..Default::default()
+        };
+        self.store.delete_opts(&self.location, opts).await
+    }
+
+    /// Returns the contents of the lock object for a new lease, and its expiry
+    fn lock_body(&self) -> Result<(Bytes, DateTime<Utc>)> {
+        let invalid = || Error::Generic {
+            store: STORE,
+            source: format!("lease duration {:?} is out of range", self.lease).into(),
+        };
+        let lease = chrono::Duration::from_std(self.lease).map_err(|_| invalid())?;
+        let expires = Utc::now().checked_add_signed(lease).ok_or_else(invalid)?;
+        let body = format!("{}\n{}", self.owner, expires.to_rfc3339());
+        Ok((body.into(), expires))
+    }
+
+    fn lease(&self, e_tag: Option<String>, expires: DateTime<Utc>) -> Result<LockLease> {
+        let e_tag = e_tag.ok_or_else(|| Error::Generic {
+            store: STORE,
+            source: "store did not return an e-tag for the lock object".into(),
+        })?;
+        Ok(LockLease { e_tag, expires })
+    }
+}
+
+/// Parses the owner and expiry from the contents of a lock object
+fn parse_lock_body(body: &[u8]) -> Result<(&str, DateTime<Utc>)> {
+    let invalid = || Error::Generic {
+        store: STORE,
+        source: "invalid lock object".into(),
+    };
+    let body = std::str::from_utf8(body).map_err(|_| invalid())?;
+    let (owner, expires) = body.rsplit_once('\n').ok_or_else(invalid)?;
+    let expires = DateTime::parse_from_rfc3339(expires).map_err(|_| invalid())?;
+    Ok((owner, expires.with_timezone(&Utc)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::InMemory;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+    #[tokio::test]
+    async fn test_lock() {
+        let store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+        let location = Path::from("lock");
+        let a = ObjectLock::new(Arc::clone(&store), location.clone(), "a");
+        let b = ObjectLock::new(Arc::clone(&store), location.clone(), "b");
+
+        let lease = a.try_acquire().await.unwrap().unwrap();
+        assert!(!lease.is_expired());
+        assert!(b.try_acquire().await.unwrap().is_none());
+
+        let body = store.get(&location).await.unwrap().bytes().await.unwrap();
+        let (owner, expires) = parse_lock_body(&body).unwrap();
+        assert_eq!(owner, "a");
+        assert_eq!(expires, lease.expires());
+
+        let renewed = a.renew(&lease).await.unwrap();
+        assert_ne!(renewed, lease);
+        let err = a.renew(&lease).await.unwrap_err();
+        assert!(matches!(err, Error::Precondition { .. }), "{err}");
+        assert!(b.try_acquire().await.unwrap().is_none());
+
+        a.release(renewed).await.unwrap();
+        let lease = b.try_acquire().await.unwrap().unwrap();
+        assert!(a.try_acquire().await.unwrap().is_none());
+        b.release(lease).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_lock_expiry() {
+        let store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+        let location = Path::from("lock");
+        let a = ObjectLock::new(Arc::clone(&store), location.clone(), "a")
+            .with_lease(Duration::from_millis(1));
+        let b = ObjectLock::new(Arc::clone(&store), location, "b");
+
+        // Simulate a crashed holder that never renews or releases its lease
+        let stale = a.try_acquire().await.unwrap().unwrap();
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert!(stale.is_expired());
+
+        let lease = b.try_acquire().await.unwrap().unwrap();
+        let err = a.renew(&stale).await.unwrap_err();
+        assert!(matches!(err, Error::Precondition { .. }), "{err}");
+        let err = a.release(stale).await.unwrap_err();
+        assert!(matches!(err, Error::Precondition { .. }), "{err}");
+
+        b.release(lease).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_lock_lease_overflow() {
+        let store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+        let lock = ObjectLock::new(store, Path::from("lock"), "a").with_lease(Duration::MAX);
+        let err = lock.try_acquire().await.unwrap_err();
+        assert!(matches!(err, Error::Generic { .. }), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_lock_mutual_exclusion() {
+        let store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+        let location = Path::from("lock");
+        let held = AtomicBool::new(false);
+        let completed = AtomicUsize::new(0);
+
+        let contend = |owner: &'static str| {
+            let lock = ObjectLock::new(Arc::clone(&store), location.clone(), owner);
+            let (held, completed) = (&held, &completed);
+            async move {
+                for _ in 0..10 {
+                    let lease = lock.acquire(Duration::from_millis(1)).await.unwrap();
+                    assert!(
+                        !held.swap(true, Ordering::SeqCst),
+                        "lock held by two contenders"
+                    );
+                    tokio::task::yield_now().await;
+                    held.store(false, Ordering::SeqCst);
+                    lock.release(lease).await.unwrap();
+                    completed.fetch_add(1, Ordering::SeqCst);
+                }
+            }
+        };
+
+        futures::future::join(contend("a"), contend("b")).await;
+        assert_eq!(completed.load(Ordering::SeqCst), 20);
+    }
+}
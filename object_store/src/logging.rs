@@ -0,0 +1,541 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! An object store wrapper logging each operation, for diagnostics
+//!
+//! [`LoggingStore`] wraps another [`ObjectStore`], and logs each operation performed
+//! against it using the [`log`] facade, with the target `object_store::logging`.
+//!
+//! Each log record includes the operation, the paths involved, the number of bytes
+//! transferred, any requested range, how long the operation took, and the resulting
+//! error if any. The contents of objects are never logged.
+//!
+//! When the configured level is not enabled for the target, operations are forwarded
+//! directly to the wrapped store without measuring or formatting anything.
+//!
+//! This module requires the `logging` feature
+
+use std::fmt::{Arguments, Display, Formatter};
+use std::ops::Range;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Instant;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::stream::BoxStream;
+use futures::{Stream, StreamExt};
+use log::Level;
+use tokio::io::AsyncWrite;
+use url::Url;
+
+use crate::path::Path;
+use crate::{
+    Acl, CopyOptions, DeleteOptions, GetOptions, GetResult, ListPage, ListResult, MultipartId,
+    ObjectMeta, ObjectStore, PutOptions, PutResult, Result,
+};
+
+/// Store wrapper that logs each operation performed against the wrapped store
+///
+/// ```
+/// # use object_store::logging::LoggingStore;
+/// # use object_store::memory::InMemory;
+/// let store = LoggingStore::new(InMemory::new()).with_level(log::Level::Info);
+/// ```
+#[derive(Debug)]
+pub struct LoggingStore<T: ObjectStore> {
+    inner: Arc<T>,
+    level: Level,
+}
+
+impl<T: ObjectStore> Display for LoggingStore<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "LoggingStore({})", self.inner)
+    }
+}
+
+impl<T: ObjectStore> LoggingStore<T> {
+    /// Create a new [`LoggingStore`] wrapping `inner`, logging at [`Level::Debug`]
+    pub fn new(inner: T) -> Self {
+        Self {
+            inner: Arc::new(inner),
+            level: Level::Debug,
+        }
+    }
+
+    /// Set the [`Level`] at which operations are logged
+    pub fn with_level(mut self, level: Level) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// Returns the wrapped store
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    fn enabled(&self) -> bool {
+        log::log_enabled!(self.level)
+    }
+
+    /// Logs the outcome of the operation described by `op`, started at `start`
+    fn log<R>(&self, start: Instant, op: Arguments<'_>, result: &Result<R>) {
+        let elapsed = start.elapsed();
+        match result {
+            Ok(_) => log::log!(self.level, "{op} succeeded in {elapsed:?}"),
+            Err(e) => log::log!(self.level, "{op} failed in {elapsed:?}: {e}"),
+        }
+    }
+
+    /// Wraps `stream`, logging the number of items returned once it completes
+    fn log_stream<'a, S: 'a>(
+        &self,
+        op: String,
+        stream: BoxStream<'a, Result<S>>,
+    ) -> BoxStream<'a, Result<S>> {
+        LoggedStream {
+            inner: stream,
+            op,
+            level: self.level,
+            start: Instant::now(),
+            count: 0,
+            done: false,
+        }
+        .boxed()
+    }
+}
+
+/// A stream that logs once it completes, or is dropped before completion
+struct LoggedStream<'a, S> {
+    inner: BoxStream<'a, Result<S>>,
+    op: String,
+    level: Level,
+    start: Instant,
+    count: usize,
+    done: bool,
+}
+
+impl<'a, S> Stream for LoggedStream<'a, S> {
+    type Item = Result<S>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let next = futures::ready!(self.inner.poll_next_unpin(cx));
+        let (op, level, elapsed) = (&self.op, self.level, self.start.elapsed());
+        match &next {
+            Some(Ok(_)) => self.count += 1,
+            Some(Err(e)) => log::log!(level, "{op} failed in {elapsed:?}: {e}"),
+            None if !self.done => {
+                let count = self.count;
+                log::log!(level, "{op} returned {count} items in {elapsed:?}");
+                self.done = true;
+            }
+            None => {}
+        }
+        Poll::Ready(next)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, S> Drop for LoggedStream<'a, S> {
+    fn drop(&mut self) {
+        if !self.done {
+            let (op, count, elapsed) = (&self.op, self.count, self.start.elapsed());
+            log::log!(
+                self.level,
+                "{op} abandoned after {count} items in {elapsed:?}"
+            );
+        }
+    }
+}
+
+#[async_trait]
+impl<T: ObjectStore> ObjectStore for LoggingStore<T> {
+    async fn put_opts(&self, location: &Path, bytes: Bytes, opts: PutOptions) -> Result<PutResult> {
+        if !self.enabled() {
+            return self.inner.put_opts(location, bytes, opts).await;
+        }
+        let (start, len) = (Instant::now(), bytes.len());
+        let r = self.inner.put_opts(location, bytes, opts).await;
+        self.log(start, format_args!("put {location} ({len} bytes)"), &r);
+        r
+    }
+
+    async fn put_multipart(
+        &self,
+        location: &Path,
+    ) -> Result<(MultipartId, Box<dyn AsyncWrite + Unpin + Send>)> {
+        if !self.enabled() {
+            return self.inner.put_multipart(location).await;
+        }
+        let start = Instant::now();
+        let r = self.inner.put_multipart(location).await;
+        self.log(start, format_args!("put_multipart {location}"), &r);
+        r
+    }
+
+    async fn abort_multipart(&self, location: &Path, multipart_id: &MultipartId) -> Result<()> {
+        if !self.enabled() {
+            return self.inner.abort_multipart(location, multipart_id).await;
+        }
+        let start = Instant::now();
+        let r = self.inner.abort_multipart(location, multipart_id).await;
+        self.log(
+            start,
+            format_args!("abort_multipart {location} ({multipart_id})"),
+            &r,
+        );
+        r
+    }
+
+    async fn get_opts(&self, location: &Path, options: GetOptions) -> Result<GetResult> {
+        if !self.enabled() {
+            return self.inner.get_opts(location, options).await;
+        }
+        let start = Instant::now();
+        let (head, range) = (options.head, options.range.clone());
+        let r = self.inner.get_opts(location, options).await;
+        let op = if head { "head" } else { "get" };
+        let requested = match &range {
+            Some(range) => format!(" {range}"),
+            None => String::new(),
+        };
+        match &r {
+            Ok(result) => {
+                let (elapsed, returned) = (start.elapsed(), &result.range);
+                let len = returned.end - returned.start;
+                log::log!(
+                    self.level,
+                    "{op} {location}{requested} returned {len} bytes ({}-{} of {}) in {elapsed:?}",
+                    returned.start,
+                    returned.end,
+                    result.meta.size
+                )
+            }
+            Err(_) => self.log(start, format_args!("{op} {location}{requested}"), &r),
+        }
+        r
+    }
+
+    async fn get_range(&self, location: &Path, range: Range<usize>) -> Result<Bytes> {
+        if !self.enabled() {
+            return self.inner.get_range(location, range).await;
+        }
+        let start = Instant::now();
+        let r = self.inner.get_range(location, range.clone()).await;
+        self.log(
+            start,
+            format_args!("get_range {location} {}-{}", range.start, range.end),
+            &r,
+        );
+        r
+    }
+
+    async fn get_ranges(&self, location: &Path, ranges: &[Range<usize>]) -> Result<Vec<Bytes>> {
+        if !self.enabled() {
+            return self.inner.get_ranges(location, ranges).await;
+        }
+        let start = Instant::now();
+        let r = self.inner.get_ranges(location, ranges).await;
+        let len: usize = ranges.iter().map(|r| r.end.saturating_sub(r.start)).sum();
+        let count = ranges.len();
+        self.log(
+            start,
+            format_args!("get_ranges {location} ({count} ranges, {len} bytes)"),
+            &r,
+        );
+        r
+    }
+
+    async fn head(&self, location: &Path) -> Result<ObjectMeta> {
+        if !self.enabled() {
+            return self.inner.head(location).await;
+        }
+        let start = Instant::now();
+        let r = self.inner.head(location).await;
+        self.log(start, format_args!("head {location}"), &r);
+        r
+    }
+
+    async fn delete(&self, location: &Path) -> Result<()> {
+        if !self.enabled() {
+            return self.inner.delete(location).await;
+        }
+        let start = Instant::now();
+        let r = self.inner.delete(location).await;
+        self.log(start, format_args!("delete {location}"), &r);
+        r
+    }
+
+    async fn delete_opts(&self, location: &Path, opts: DeleteOptions) -> Result<()> {
+        if !self.enabled() {
+            return self.inner.delete_opts(location, opts).await;
+        }
+        let start = Instant::now();
+        let r = self.inner.delete_opts(location, opts).await;
+        self.log(start, format_args!("delete {location}"), &r);
+        r
+    }
+
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'_, Result<ObjectMeta>> {
+        let s = self.inner.list(prefix);
+        match self.enabled() {
+            true => self.log_stream(format!("list {}", display_prefix(prefix)), s),
+            false => s,
+        }
+    }
+
+    fn list_with_offset(
+        &self,
+        prefix: Option<&Path>,
+        offset: &Path,
+    ) -> BoxStream<'_, Result<ObjectMeta>> {
+        let s = self.inner.list_with_offset(prefix, offset);
+        match self.enabled() {
+            true => {
+                let op = format!("list {} after {offset}", display_prefix(prefix));
+                self.log_stream(op, s)
+            }
+            false => s,
+        }
+    }
+
+    fn list_range(&self, start: &Path, end: &Path) -> BoxStream<'_, Result<ObjectMeta>> {
+        let s = self.inner.list_range(start, end);
+        match self.enabled() {
+            true => self.log_stream(format!("list range [{start}, {end})"), s),
+            false => s,
+        }
+    }
+
+    fn list_from_token(
+        &self,
+        prefix: Option<&Path>,
+        token: Option<&str>,
+    ) -> BoxStream<'_, Result<ListPage>> {
+        let s = self.inner.list_from_token(prefix, token);
+        match self.enabled() {
+            true => {
+                let op = format!("list pages {}", display_prefix(prefix));
+                self.log_stream(op, s)
+            }
+            false => s,
+        }
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
+        if !self.enabled() {
+            return self.inner.list_with_delimiter(prefix).await;
+        }
+        let start = Instant::now();
+        let r = self.inner.list_with_delimiter(prefix).await;
+        let prefix = display_prefix(prefix);
+        match &r {
+            Ok(result) => log::log!(
+                self.level,
+                "list_with_delimiter {prefix} returned {} objects and {} prefixes in {:?}",
+                result.objects.len(),
+                result.common_prefixes.len(),
+                start.elapsed()
+            ),
+            Err(_) => self.log(start, format_args!("list_with_delimiter {prefix}"), &r),
+        }
+        r
+    }
+
+    fn list_with_delimiter_stream(
+        &self,
+        prefix: Option<&Path>,
+    ) -> BoxStream<'_, Result<ListResult>> {
+        let s = self.inner.list_with_delimiter_stream(prefix);
+        match self.enabled() {
+            true => {
+                let op = format!("list_with_delimiter pages {}", display_prefix(prefix));
+                self.log_stream(op, s)
+            }
+            false => s,
+        }
+    }
+
+    async fn copy(&self, from: &Path, to: &Path) -> Result<()> {
+        if !self.enabled() {
+            return self.inner.copy(from, to).await;
+        }
+        let start = Instant::now();
+        let r = self.inner.copy(from, to).await;
+        self.log(start, format_args!("copy {from} to {to}"), &r);
+        r
+    }
+
+    async fn copy_opts(&self, from: &Path, to: &Path, opts: CopyOptions) -> Result<()> {
+        if !self.enabled() {
+            return self.inner.copy_opts(from, to, opts).await;
+        }
+        let start = Instant::now();
+        let r = self.inner.copy_opts(from, to, opts).await;
+        self.log(start, format_args!("copy {from} to {to}"), &r);
+        r
+    }
+
+    async fn rename(&self, from: &Path, to: &Path) -> Result<()> {
+        if !self.enabled() {
+            return self.inner.rename(from, to).await;
+        }
+        let start = Instant::now();
+        let r = self.inner.rename(from, to).await;
+        self.log(start, format_args!("rename {from} to {to}"), &r);
+        r
+    }
+
+    async fn probe(&self) -> Result<()> {
+        if !self.enabled() {
+            return self.inner.probe().await;
+        }
+        let start = Instant::now();
+        let r = self.inner.probe().await;
+        self.log(start, format_args!("probe"), &r);
+        r
+    }
+
+    async fn get_acl(&self, location: &Path) -> Result<Acl> {
+        if !self.enabled() {
+            return self.inner.get_acl(location).await;
+        }
+        let start = Instant::now();
+        let r = self.inner.get_acl(location).await;
+        self.log(start, format_args!("get_acl {location}"), &r);
+        r
+    }
+
+    async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        if !self.enabled() {
+            return self.inner.copy_if_not_exists(from, to).await;
+        }
+        let start = Instant::now();
+        let r = self.inner.copy_if_not_exists(from, to).await;
+        self.log(start, format_args!("copy_if_not_exists {from} to {to}"), &r);
+        r
+    }
+
+    async fn rename_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        if !self.enabled() {
+            return self.inner.rename_if_not_exists(from, to).await;
+        }
+        let start = Instant::now();
+        let r = self.inner.rename_if_not_exists(from, to).await;
+        self.log(
+            start,
+            format_args!("rename_if_not_exists {from} to {to}"),
+            &r,
+        );
+        r
+    }
+
+    fn object_url(&self, location: &Path) -> Result<Url> {
+        self.inner.object_url(location)
+    }
+
+    fn validate_path(&self, location: &Path) -> Result<()> {
+        self.inner.validate_path(location)
+    }
+}
+
+fn display_prefix(prefix: Option<&Path>) -> &str {
+    match prefix {
+        Some(p) if !p.as_ref().is_empty() => p.as_ref(),
+        _ => "/",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::InMemory;
+    use crate::test_util::flatten_list_stream;
+    use crate::tests::*;
+    use log::{LevelFilter, Log, Metadata, Record};
+    use parking_lot::Mutex;
+
+    /// Records messages logged by this module at [`Level::Info`] or above
+    struct TestLogger(Mutex<Vec<String>>);
+
+    impl Log for TestLogger {
+        fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+            metadata.target() == "object_store::logging" && metadata.level() <= Level::Info
+        }
+
+        fn log(&self, record: &Record<'_>) {
+            if self.enabled(record.metadata()) {
+                self.0.lock().push(record.args().to_string())
+            }
+        }
+
+        fn flush(&self) {}
+    }
+
+    static LOGGER: TestLogger = TestLogger(parking_lot::const_mutex(vec![]));
+
+    #[tokio::test]
+    async fn logging_store_test() {
+        let integration = LoggingStore::new(InMemory::new());
+
+        put_get_delete_list(&integration).await;
+        get_opts(&integration).await;
+        list_uses_directories_correctly(&integration).await;
+        list_with_delimiter(&integration).await;
+        rename_and_copy(&integration).await;
+        copy_if_not_exists(&integration).await;
+        stream_get(&integration).await;
+    }
+
+    #[tokio::test]
+    async fn test_log_records() {
+        log::set_logger(&LOGGER).unwrap();
+        log::set_max_level(LevelFilter::Trace);
+
+        let store = LoggingStore::new(InMemory::new()).with_level(Level::Info);
+        let path = Path::from("foo/bar");
+        store.put(&path, Bytes::from("secret data")).await.unwrap();
+        store.get_range(&path, 1..4).await.unwrap();
+        let options = GetOptions {
+            range: Some((2..).into()),
+            ..Default::default()
+        };
+        store.get_opts(&path, options).await.unwrap();
+        store.head(&Path::from("missing")).await.unwrap_err();
+        flatten_list_stream(&store, Some(&Path::from("foo")))
+            .await
+            .unwrap();
+
+        // Disabled levels are not logged
+        let quiet = LoggingStore::new(InMemory::new()).with_level(Level::Debug);
+        quiet.put(&path, Bytes::from("data")).await.unwrap();
+
+        let records = std::mem::take(&mut *LOGGER.0.lock());
+        assert_eq!(records.len(), 5, "{records:?}");
+        assert!(records[0].starts_with("put foo/bar (11 bytes) succeeded"));
+        assert!(records[1].starts_with("get_range foo/bar 1-4 succeeded"));
+        assert!(records[2].starts_with("get foo/bar bytes=2- returned 9 bytes (2-11 of 11)"));
+        assert!(records[3].starts_with("head missing failed"));
+        assert!(records[3].contains("not found"), "{}", records[3]);
+        assert!(records[4].starts_with("list foo returned 1 items"));
+        assert!(records.iter().all(|r| !r.contains("secret")));
+    }
+}
@@ -16,12 +16,14 @@
 // under the License.
 
 //! An in-memory object store implementation
-use crate::util::InvalidGetRange;
+use crate::multipart::{validate_parts, MultiPartStore, PartId};
+use crate::util::check_precondition;
+use crate::util::{url_with_path, InvalidGetRange};
 use crate::{
-    path::Path, GetRange, GetResult, GetResultPayload, ListResult, ObjectMeta, ObjectStore,
-    PutMode, PutOptions, PutResult, Result, UpdateVersion,
+    path::Path, GetRange, GetResult, GetResultPayload, ListPage, ListResult, ObjectMeta,
+    ObjectStore, PutMode, PutOptions, PutResult, Result, UpdateVersion,
 };
-use crate::{GetOptions, MultipartId};
+use crate::{DeleteOptions, GetOptions, MultipartId};
 use async_trait::async_trait;
 use bytes::Bytes;
 use chrono::{DateTime, Utc};
@@ -30,12 +32,17 @@ use parking_lot::RwLock;
 use snafu::{OptionExt, ResultExt, Snafu};
 use std::collections::BTreeMap;
 use std::collections::BTreeSet;
+use std::collections::HashMap;
 use std::io;
-use std::ops::Range;
+use std::ops::{Bound, Range};
 use std::pin::Pin;
 use std::sync::Arc;
 use std::task::Poll;
 use tokio::io::AsyncWrite;
+use url::Url;
+
+/// The maximum number of objects in a page returned by [`ObjectStore::list_from_token`]
+const LIST_PAGE_SIZE: usize = 1000;
 
 /// A specialized `Error` for in-memory object store-related errors
 #[derive(Debug, Snafu)]
@@ -52,6 +59,12 @@ enum Error {
 
     #[snafu(display("ETag required for conditional update"))]
     MissingETag,
+
+    #[snafu(display("Multipart upload not found: {id}"))]
+    UploadNotFound { id: String },
+
+    #[snafu(display("Missing part at index: {part}"))]
+    MissingPart { part: usize },
 }
 
 impl From<Error> for super::Error {
@@ -65,6 +78,10 @@ impl From<Error> for super::Error {
                 path: path.into(),
                 source: source.into(),
             },
+            Error::UploadNotFound { ref id } => Self::NotFound {
+                path: id.into(),
+                source: source.into(),
+            },
             _ => Self::Generic {
                 store: "InMemory",
                 source: Box::new(source),
@@ -101,6 +118,13 @@ impl Entry {
 struct Storage {
     next_etag: usize,
     map: BTreeMap<Path, Entry>,
+    uploads: HashMap<usize, PartStorage>,
+}
+
+/// The parts of a [`MultiPartStore`] upload, by part index
+#[derive(Debug, Default, Clone)]
+struct PartStorage {
+    parts: Vec<Option<Bytes>>,
 }
 
 type SharedStorage = Arc<RwLock<Storage>>;
@@ -114,6 +138,24 @@ impl Storage {
         etag
     }
 
+    fn upload_mut(&mut self, id: &MultipartId) -> Result<&mut PartStorage> {
+        let parts = id
+            .parse()
+            .ok()
+            .and_then(|x| self.uploads.get_mut(&x))
+            .context(UploadNotFoundSnafu { id })?;
+        Ok(parts)
+    }
+
+    fn remove_upload(&mut self, id: &MultipartId) -> Result<PartStorage> {
+        let parts = id
+            .parse()
+            .ok()
+            .and_then(|x| self.uploads.remove(&x))
+            .context(UploadNotFoundSnafu { id })?;
+        Ok(parts)
+    }
+
     fn overwrite(&mut self, location: &Path, entry: Entry) {
         self.map.insert(location.clone(), entry);
     }
@@ -211,16 +253,18 @@ impl ObjectStore for InMemory {
             size: entry.data.len(),
             e_tag: Some(e_tag),
             version: None,
+            checksum: None,
         };
         options.check_preconditions(&meta)?;
 
-        let (range, data) = match options.range {
+        let (range, data) = match &options.range {
             Some(range) => {
                 let r = range.as_range(entry.data.len()).context(RangeSnafu)?;
                 (r.clone(), entry.data.slice(r))
             }
             None => (0..entry.data.len(), entry.data),
         };
+        options.check_max_size(location, &range)?;
         let stream = futures::stream::once(futures::future::ready(Ok(data)));
 
         Ok(GetResult {
@@ -253,6 +297,7 @@ impl ObjectStore for InMemory {
             size: entry.data.len(),
             e_tag: Some(entry.e_tag.to_string()),
             version: None,
+            checksum: None,
         })
     }
 
@@ -261,6 +306,19 @@ impl ObjectStore for InMemory {
         Ok(())
     }
 
+    async fn delete_opts(&self, location: &Path, opts: DeleteOptions) -> Result<()> {
+        crate::util::check_delete_version(&opts)?;
+        let mut storage = self.storage.write();
+        if let Some(expected) = &opts.if_match {
+            let entry = storage.map.get(location).context(NoDataInMemorySnafu {
+                path: location.to_string(),
+            })?;
+            check_precondition(location, Some(&entry.e_tag.to_string()), expected)?;
+        }
+        storage.map.remove(location);
+        Ok(())
+    }
+
     fn list(&self, prefix: Option<&Path>) -> BoxStream<'_, Result<ObjectMeta>> {
         let root = Path::default();
         let prefix = prefix.unwrap_or(&root);
@@ -283,6 +341,7 @@ impl ObjectStore for InMemory {
                     size: value.data.len(),
                     e_tag: Some(value.e_tag.to_string()),
                     version: None,
+                    checksum: None,
                 })
             })
             .collect();
@@ -290,6 +349,32 @@ impl ObjectStore for InMemory {
         futures::stream::iter(values).boxed()
     }
 
+    /// The memory implementation returns pages of up to 1,000 objects, using the
+    /// location of the last object in each page as the continuation token
+    fn list_from_token(
+        &self,
+        prefix: Option<&Path>,
+        token: Option<&str>,
+    ) -> BoxStream<'_, Result<ListPage>> {
+        let prefix = prefix.cloned().unwrap_or_default();
+        let after = match token.map(Path::parse).transpose() {
+            Ok(after) => after,
+            Err(e) => return futures::stream::once(async { Err(e.into()) }).boxed(),
+        };
+
+        // The state is the location after which to list, or `None` once exhausted
+        futures::stream::unfold(Some(after), move |state| {
+            let page = state.map(|after| self.list_page(&prefix, after.as_ref()));
+            async move {
+                let page = page?;
+                let last = page.objects.last().map(|x| x.location.clone());
+                let next = page.next_token.as_ref().map(|_| last);
+                Some((Ok(page), next))
+            }
+        })
+        .boxed()
+    }
+
     /// The memory implementation returns all results, as opposed to the cloud
     /// versions which limit their results to 1k or more because of API
     /// limitations.
@@ -328,6 +413,7 @@ impl ObjectStore for InMemory {
                     size: v.data.len(),
                     e_tag: Some(v.e_tag.to_string()),
                     version: None,
+                    checksum: None,
                 };
                 objects.push(object);
             }
@@ -357,6 +443,70 @@ impl ObjectStore for InMemory {
         storage.insert(to, entry.data);
         Ok(())
     }
+
+    fn object_url(&self, location: &Path) -> Result<Url> {
+        Ok(url_with_path(Url::parse("memory:///").unwrap(), location))
+    }
+}
+
+#[async_trait]
+impl MultiPartStore for InMemory {
+    async fn create_multipart(&self, _path: &Path) -> Result<MultipartId> {
+        let mut storage = self.storage.write();
+        let etag = storage.next_etag;
+        storage.next_etag += 1;
+        storage.uploads.insert(etag, Default::default());
+        Ok(etag.to_string())
+    }
+
+    async fn put_part(
+        &self,
+        _path: &Path,
+        id: &MultipartId,
+        part_idx: usize,
+        data: Bytes,
+    ) -> Result<PartId> {
+        let mut storage = self.storage.write();
+        let upload = storage.upload_mut(id)?;
+        if part_idx >= upload.parts.len() {
+            upload.parts.resize(part_idx + 1, None);
+        }
+        upload.parts[part_idx] = Some(data);
+        Ok(PartId::new(String::new(), part_idx))
+    }
+
+    async fn complete_multipart(
+        &self,
+        path: &Path,
+        id: &MultipartId,
+        parts: Vec<PartId>,
+    ) -> Result<PutResult> {
+        validate_parts("InMemory", &parts)?;
+        let mut storage = self.storage.write();
+        let upload = storage.remove_upload(id)?;
+
+        let mut buf = Vec::new();
+        for part in &parts {
+            let data = upload
+                .parts
+                .get(part.part_idx())
+                .and_then(|x| x.as_ref())
+                .context(MissingPartSnafu {
+                    part: part.part_idx(),
+                })?;
+            buf.extend_from_slice(data);
+        }
+        let etag = storage.insert(path, buf.into());
+        Ok(PutResult {
+            e_tag: Some(etag.to_string()),
+            version: None,
+        })
+    }
+
+    async fn abort_multipart(&self, _path: &Path, id: &MultipartId) -> Result<()> {
+        self.storage.write().remove_upload(id)?;
+        Ok(())
+    }
 }
 
 impl InMemory {
@@ -379,6 +529,47 @@ impl InMemory {
         self.fork()
     }
 
+    /// Returns a page of the objects under `prefix` with locations after `after`
+    fn list_page(&self, prefix: &Path, after: Option<&Path>) -> ListPage {
+        let start = match after {
+            Some(after) if after >= prefix => Bound::Excluded(after),
+            _ => Bound::Included(prefix),
+        };
+
+        let storage = self.storage.read();
+        let mut objects: Vec<_> = storage
+            .map
+            .range::<Path, _>((start, Bound::Unbounded))
+            .take_while(|(key, _)| key.as_ref().starts_with(prefix.as_ref()))
+            .filter(|(key, _)| {
+                key.prefix_match(prefix)
+                    .map(|mut x| x.next().is_some())
+                    .unwrap_or(false)
+            })
+            .take(LIST_PAGE_SIZE + 1)
+            .map(|(key, value)| ObjectMeta {
+                location: key.clone(),
+                last_modified: value.last_modified,
+                size: value.data.len(),
+                e_tag: Some(value.e_tag.to_string()),
+                version: None,
+                checksum: None,
+            })
+            .collect();
+
+        let next_token = match objects.len() > LIST_PAGE_SIZE {
+            true => {
+                objects.truncate(LIST_PAGE_SIZE);
+                objects.last().map(|x| x.location.to_string())
+            }
+            false => None,
+        };
+        ListPage {
+            objects,
+            next_token,
+        }
+    }
+
     async fn entry(&self, location: &Path) -> Result<Entry> {
         let storage = self.storage.read();
         let value = storage
@@ -432,6 +623,59 @@ mod tests {
 
     use crate::tests::*;
 
+    #[tokio::test]
+    async fn list_from_token() {
+        use futures::TryStreamExt;
+
+        let integration = InMemory::new();
+        let mut expected = vec![];
+        for i in 0..2500 {
+            let location = Path::from(format!("dir/{i:04}"));
+            integration.put(&location, Bytes::new()).await.unwrap();
+            expected.push(location);
+        }
+        integration
+            .put(&Path::from("other"), Bytes::new())
+            .await
+            .unwrap();
+
+        let prefix = Path::from("dir");
+        let pages: Vec<_> = integration
+            .list_from_token(Some(&prefix), None)
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(pages.len(), 3);
+        assert!(pages[2].next_token.is_none());
+        let listed: Vec<_> = pages
+            .iter()
+            .flat_map(|p| &p.objects)
+            .map(|o| &o.location)
+            .collect();
+        assert_eq!(listed, expected.iter().collect::<Vec<_>>());
+
+        // Resume from a captured token
+        let token = pages[0].next_token.clone().unwrap();
+        let resumed: Vec<_> = integration
+            .list_from_token(Some(&prefix), Some(&token))
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        let resumed: Vec<_> = resumed
+            .into_iter()
+            .flat_map(|p| p.objects)
+            .map(|o| o.location)
+            .collect();
+        assert_eq!(resumed, expected[1000..]);
+
+        let err = integration
+            .list_from_token(Some(&prefix), Some("//invalid"))
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap_err();
+        assert!(matches!(err, crate::Error::InvalidPath { .. }), "{err}");
+    }
+
     #[tokio::test]
     async fn in_memory_test() {
         let integration = InMemory::new();
@@ -444,6 +688,50 @@ mod tests {
         copy_if_not_exists(&integration).await;
         stream_get(&integration).await;
         put_opts(&integration, true).await;
+        delete_opts(&integration).await;
+        multipart(&integration, &integration).await;
+    }
+
+    #[tokio::test]
+    async fn copy_opts_stale_etag() {
+        let integration = InMemory::new();
+        let from = Path::from("from");
+        let to = Path::from("to");
+        let result = integration.put(&from, "data".into()).await.unwrap();
+
+        let opts = crate::CopyOptions {
+            if_match: Some("stale".to_string()),
+            ..Default::default()
+        };
+        let err = integration.copy_opts(&from, &to, opts).await.unwrap_err();
+        assert!(matches!(err, crate::Error::Precondition { .. }), "{err}");
+        let err = integration.head(&to).await.unwrap_err();
+        assert!(matches!(err, crate::Error::NotFound { .. }), "{err}");
+
+        let opts = crate::CopyOptions {
+            if_match: result.e_tag,
+            ..Default::default()
+        };
+        integration.copy_opts(&from, &to, opts).await.unwrap();
+        assert_eq!(
+            integration.get(&to).await.unwrap().bytes().await.unwrap(),
+            "data"
+        );
+    }
+
+    #[tokio::test]
+    async fn delete_version_not_supported() {
+        let integration = InMemory::new();
+        let path = Path::from("file");
+        integration.put(&path, "data".into()).await.unwrap();
+
+        let options = DeleteOptions {
+            version: Some("1".to_string()),
+            ..Default::default()
+        };
+        let err = integration.delete_opts(&path, options).await.unwrap_err();
+        assert!(matches!(err, crate::Error::NotSupported { .. }), "{err}");
+        integration.head(&path).await.unwrap();
     }
 
     #[tokio::test]
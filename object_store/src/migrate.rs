@@ -0,0 +1,285 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Utilities for migrating objects between stores
+
+use futures::TryStreamExt;
+use tokio::io::AsyncWriteExt;
+
+use crate::buffered::{get_into, DEFAULT_WRITE_CAPACITY};
+use crate::path::Path;
+use crate::{Error, ObjectMeta, ObjectStore, Result};
+
+/// The outcome of synchronising a single object with [`sync`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncOutcome {
+    /// The object was copied to the destination
+    Copied,
+    /// The object was skipped as it already exists unchanged at the destination
+    Skipped,
+}
+
+/// The number of objects processed by [`sync`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SyncSummary {
+    /// The number of objects copied
+    pub copied: usize,
+    /// The number of objects skipped as they were unchanged
+    pub skipped: usize,
+    /// The number of objects that could not be copied
+    pub failed: usize,
+}
+
+/// Copy all objects under `src_prefix` in `src` to the same relative path under
+/// `dst_prefix` in `dst`
+///
+/// The source is listed with [`ObjectStore::list`], and up to `concurrency` objects
+/// are copied at once. `progress` is called with the source path and outcome of each
+/// object as it completes, with the totals returned once the listing is exhausted.
+///
+/// An object is skipped if it exists in the destination with the same size and either:
+///
+/// * The same [`ObjectMeta::e_tag`]
+/// * A [`ObjectMeta::last_modified`] no earlier than that of the source, i.e. it was
+///   written by a previous sync. This is necessary as e-tags are not in general
+///   comparable across stores, or preserved by [`ObjectStore::copy`]
+///
+/// Re-running a sync will therefore only copy objects that are new or have changed.
+///
+/// If `src` and `dst` are the same store, objects are copied server-side with
+/// [`ObjectStore::copy`]. Otherwise they are downloaded and re-uploaded, using
+/// [`ObjectStore::put_multipart`] to avoid buffering large objects in memory.
+///
+/// A failure to copy an individual object is reported to `progress` and counted in
+/// [`SyncSummary::failed`], whereas a failure to list `src` aborts the sync.
+///
+/// Objects in `dst` that do not exist in `src` are left untouched.
+pub async fn sync<F>(
+    src: &dyn ObjectStore,
+    src_prefix: Option<&Path>,
+    dst: &dyn ObjectStore,
+    dst_prefix: Option<&Path>,
+    concurrency: usize,
+    mut progress: F,
+) -> Result<SyncSummary>
+where
+    F: FnMut(&Path, &Result<SyncOutcome>),
+{
+    let same_store = std::ptr::eq(
+        src as *const dyn ObjectStore as *const u8,
+        dst as *const dyn ObjectStore as *const u8,
+    );
+
+    let mut results = src
+        .list(src_prefix)
+        .map_ok(|meta| async move {
+            let to = destination(&meta.location, src_prefix, dst_prefix);
+            let result = sync_object(src, dst, same_store, &meta, &to).await;
+            Ok((meta.location, result))
+        })
+        .try_buffer_unordered(concurrency.max(1));
+
+    let mut summary = SyncSummary::default();
+    while let Some((location, result)) = results.try_next().await? {
+        match &result {
+            Ok(SyncOutcome::Copied) => summary.copied += 1,
+            Ok(SyncOutcome::Skipped) => summary.skipped += 1,
+            Err(_) => summary.failed += 1,
+        }
+        progress(&location, &result);
+    }
+    Ok(summary)
+}
+
+/// Returns the destination path for `location`
+fn destination(location: &Path, src_prefix: Option<&Path>, dst_prefix: Option<&Path>) -> Path {
+    let relative = src_prefix.and_then(|p| location.prefix_match(p));
+    let dst_parts = dst_prefix.into_iter().flat_map(|p| p.parts());
+    match relative {
+        Some(relative) => dst_parts.chain(relative).collect(),
+        None => dst_parts.chain(location.parts()).collect(),
+    }
+}
+
+async fn sync_object(
+    src: &dyn ObjectStore,
+    dst: &dyn ObjectStore,
+    same_store: bool,
+    meta: &ObjectMeta,
+    to: &Path,
+) -> Result<SyncOutcome> {
+    match dst.head(to).await {
+        Ok(existing) if is_unchanged(meta, &existing) => return Ok(SyncOutcome::Skipped),
+        Ok(_) | Err(Error::NotFound { .. }) => {}
+        Err(e) => return Err(e),
+    }
+
+    if same_store {
+        src.copy(&meta.location, to).await?;
+        return Ok(SyncOutcome::Copied);
+    }
+
+    if meta.size <= DEFAULT_WRITE_CAPACITY {
+        let bytes = src.get(&meta.location).await?.bytes().await?;
+        dst.put(to, bytes).await?;
+        return Ok(SyncOutcome::Copied);
+    }
+
+    let (id, mut writer) = dst.put_multipart(to).await?;
+    let upload = async {
+        get_into(src, &meta.location, &mut writer).await?;
+        writer.shutdown().await.map_err(|e| Error::Generic {
+            store: "Sync",
+            source: Box::new(e),
+        })
+    };
+    match upload.await {
+        Ok(()) => Ok(SyncOutcome::Copied),
+        Err(e) => {
+            let _ = dst.abort_multipart(to, &id).await;
+            Err(e)
+        }
+    }
+}
+
+fn is_unchanged(src: &ObjectMeta, dst: &ObjectMeta) -> bool {
+    let same_e_tag = src.e_tag.is_some() && src.e_tag == dst.e_tag;
+    src.size == dst.size && (same_e_tag || dst.last_modified >= src.last_modified)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::InMemory;
+    use crate::test_util::flatten_list_stream;
+    use bytes::Bytes;
+
+    #[test]
+    fn test_destination() {
+        let location = Path::from("a/b/c");
+        let a = Path::from("a");
+        let x = Path::from("x/y");
+        assert_eq!(destination(&location, None, None), location);
+        assert_eq!(destination(&location, Some(&a), None), Path::from("b/c"));
+        assert_eq!(
+            destination(&location, None, Some(&x)),
+            Path::from("x/y/a/b/c")
+        );
+        assert_eq!(
+            destination(&location, Some(&a), Some(&x)),
+            Path::from("x/y/b/c")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sync() {
+        let src = InMemory::new();
+        let dst = InMemory::new();
+        for i in 0..10 {
+            let path = Path::from(format!("src/{i}"));
+            src.put(&path, Bytes::from(format!("data{i}")))
+                .await
+                .unwrap();
+        }
+        src.put(&Path::from("other"), "other".into()).await.unwrap();
+
+        let src_prefix = Path::from("src");
+        let dst_prefix = Path::from("dst");
+        let mut seen = vec![];
+        let summary = sync(
+            &src,
+            Some(&src_prefix),
+            &dst,
+            Some(&dst_prefix),
+            4,
+            |p, r| {
+                assert_eq!(r.as_ref().unwrap(), &SyncOutcome::Copied);
+                seen.push(p.clone());
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(summary.copied, 10);
+        assert_eq!(summary.skipped, 0);
+        assert_eq!(summary.failed, 0);
+        assert_eq!(seen.len(), 10);
+
+        let mut listed = flatten_list_stream(&dst, None).await.unwrap();
+        listed.sort_unstable();
+        let expected: Vec<_> = (0..10).map(|i| Path::from(format!("dst/{i}"))).collect();
+        assert_eq!(listed, expected);
+        let data = dst.get(&Path::from("dst/3")).await.unwrap();
+        assert_eq!(data.bytes().await.unwrap(), "data3");
+
+        // Re-running skips unchanged objects
+        src.put(&Path::from("src/3"), "changed".into())
+            .await
+            .unwrap();
+        let summary = sync(
+            &src,
+            Some(&src_prefix),
+            &dst,
+            Some(&dst_prefix),
+            4,
+            |_, _| {},
+        )
+        .await
+        .unwrap();
+        assert_eq!(summary.copied, 1);
+        assert_eq!(summary.skipped, 9);
+        let data = dst.get(&Path::from("dst/3")).await.unwrap();
+        assert_eq!(data.bytes().await.unwrap(), "changed");
+
+        // A destination object of the same size, written after the source, is not
+        // copied even though its e-tag differs
+        let (src_path, dst_path) = (Path::from("newer/a"), Path::from("dst_newer/a"));
+        src.put(&src_path, "aaaa".into()).await.unwrap();
+        dst.put(&dst_path, "bbbb".into()).await.unwrap();
+        let (src_meta, dst_meta) = (
+            src.head(&src_path).await.unwrap(),
+            dst.head(&dst_path).await.unwrap(),
+        );
+        assert_ne!(src_meta.e_tag, dst_meta.e_tag);
+        assert!(dst_meta.last_modified >= src_meta.last_modified);
+
+        let summary = sync(
+            &src,
+            Some(&Path::from("newer")),
+            &dst,
+            Some(&Path::from("dst_newer")),
+            4,
+            |_, r| assert_eq!(r.as_ref().unwrap(), &SyncOutcome::Skipped),
+        )
+        .await
+        .unwrap();
+        assert_eq!(summary.copied, 0);
+        assert_eq!(summary.skipped, 1);
+        let data = dst.get(&dst_path).await.unwrap();
+        assert_eq!(data.bytes().await.unwrap(), "bbbb");
+
+        // Syncing within a store uses server-side copy
+        let prefix = Path::from("copy");
+        let summary = sync(&src, Some(&src_prefix), &src, Some(&prefix), 4, |_, _| {})
+            .await
+            .unwrap();
+        assert_eq!(summary.copied, 10);
+        let summary = sync(&src, Some(&src_prefix), &src, Some(&prefix), 4, |_, _| {})
+            .await
+            .unwrap();
+        assert_eq!(summary.skipped, 10);
+    }
+}
@@ -0,0 +1,676 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! An object store that mirrors writes to one or more secondary stores
+
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::future::Future;
+use std::io;
+use std::ops::Range;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::ready;
+use futures::stream::BoxStream;
+use parking_lot::Mutex;
+use tokio::io::AsyncWrite;
+use url::Url;
+
+use crate::path::Path;
+use crate::{
+    Acl, CopyOptions, DeleteOptions, Error, GetOptions, GetResult, ListPage, ListResult,
+    MultipartId, ObjectMeta, ObjectStore, PutMode, PutOptions, PutResult, Result,
+};
+
+/// Determines how [`MirrorStore`] handles failures writing to a secondary store
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MirrorPolicy {
+    /// Writes are applied to the secondary stores before returning, and any failure
+    /// is returned to the caller
+    ///
+    /// Note: the write will have already been applied to the primary store
+    #[default]
+    Required,
+    /// Writes are applied to the secondary stores before returning, but failures
+    /// are logged and otherwise ignored
+    BestEffort,
+    /// Writes are applied to the secondary stores in a background task, with
+    /// failures logged and otherwise ignored
+    ///
+    /// This requires a running tokio runtime, and multipart uploads are instead
+    /// mirrored as per [`MirrorPolicy::BestEffort`]
+    Background,
+}
+
+/// Secondary multipart uploads keyed by the location and id of the primary upload
+type Uploads = Arc<Mutex<HashMap<(Path, MultipartId), Vec<(usize, MultipartId)>>>>;
+
+/// Store wrapper that mirrors writes from a primary store to one or more secondary stores
+///
+/// All reads, including listing, are served by the primary store. Writes, i.e. puts,
+/// multipart uploads, deletes and copies, are first applied to the primary store and,
+/// if successful, then to all secondary stores in accordance with the [`MirrorPolicy`].
+///
+/// This can be used to maintain a replica of a store, or to live-migrate between stores,
+/// in which case deleting an object that does not exist in a secondary store is not an
+/// error.
+///
+/// Conditional requests, such as [`PutMode::Create`], are only evaluated against the
+/// primary store, with the secondary stores unconditionally overwritten. All other
+/// [`PutOptions`], such as tags, metadata and ACLs, are applied to every store.
+#[derive(Debug, Clone)]
+pub struct MirrorStore {
+    primary: Arc<dyn ObjectStore>,
+    secondaries: Vec<Arc<dyn ObjectStore>>,
+    policy: MirrorPolicy,
+    uploads: Uploads,
+}
+
+impl Display for MirrorStore {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "MirrorStore({}", self.primary)?;
+        for secondary in &self.secondaries {
+            write!(f, ", {secondary}")?;
+        }
+        write!(f, ")")
+    }
+}
+
+impl MirrorStore {
+    /// Create a new [`MirrorStore`] mirroring writes to `primary` to `secondaries`
+    pub fn new(primary: Arc<dyn ObjectStore>, secondaries: Vec<Arc<dyn ObjectStore>>) -> Self {
+        Self {
+            primary,
+            secondaries,
+            policy: Default::default(),
+            uploads: Default::default(),
+        }
+    }
+
+    /// Set the [`MirrorPolicy`], defaults to [`MirrorPolicy::Required`]
+    pub fn with_policy(mut self, policy: MirrorPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Returns the primary store
+    pub fn primary(&self) -> &Arc<dyn ObjectStore> {
+        &self.primary
+    }
+
+    /// Returns the secondary stores
+    pub fn secondaries(&self) -> &[Arc<dyn ObjectStore>] {
+        &self.secondaries
+    }
+
+    /// Applies `f` to all secondary stores in accordance with the [`MirrorPolicy`]
+    async fn mirror<F, Fut>(&self, op: &'static str, location: &Path, f: F) -> Result<()>
+    where
+        F: Fn(Arc<dyn ObjectStore>) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        if self.secondaries.is_empty() {
+            return Ok(());
+        }
+
+        let policy = self.policy;
+        let secondaries = self.secondaries.clone();
+        let location = location.clone();
+        let run = async move {
+            let futures = secondaries.iter().map(|s| f(Arc::clone(s)));
+            let results = futures::future::join_all(futures).await;
+            for (secondary, result) in secondaries.iter().zip(results) {
+                if let Err(e) = result {
+                    if policy == MirrorPolicy::Required {
+                        return Err(e);
+                    }
+                    warn_failure(op, &location, secondary, &e);
+                }
+            }
+            Ok(())
+        };
+
+        match policy {
+            MirrorPolicy::Background => {
+                tokio::spawn(run);
+                Ok(())
+            }
+            _ => run.await,
+        }
+    }
+}
+
+fn warn_failure(op: &str, location: &Path, secondary: &dyn Display, e: &dyn Display) {
+    tracing::warn!("failed to mirror {op} of {location} to {secondary}: {e}");
+}
+
+#[async_trait]
+impl ObjectStore for MirrorStore {
+    async fn put_opts(&self, location: &Path, bytes: Bytes, opts: PutOptions) -> Result<PutResult> {
+        // Conditions are only evaluated against the primary store
+        let mirrored = PutOptions {
+            mode: PutMode::Overwrite,
+            ..opts.clone()
+        };
+        let result = self.primary.put_opts(location, bytes.clone(), opts).await?;
+
+        let path = location.clone();
+        self.mirror("put", location, move |s| {
+            let (path, bytes, opts) = (path.clone(), bytes.clone(), mirrored.clone());
+            async move { s.put_opts(&path, bytes, opts).await.map(|_| ()) }
+        })
+        .await?;
+        Ok(result)
+    }
+
+    async fn put_multipart(
+        &self,
+        location: &Path,
+    ) -> Result<(MultipartId, Box<dyn AsyncWrite + Unpin + Send>)> {
+        let (id, primary) = self.primary.put_multipart(location).await?;
+
+        let mut ids = Vec::with_capacity(self.secondaries.len());
+        let mut secondaries = Vec::with_capacity(self.secondaries.len());
+        for (idx, store) in self.secondaries.iter().enumerate() {
+            match store.put_multipart(location).await {
+                Ok((id, writer)) => {
+                    ids.push((idx, id));
+                    secondaries.push(SecondaryWriter {
+                        store: Arc::clone(store),
+                        writer,
+                        written: 0,
+                        shutdown: false,
+                    });
+                }
+                Err(e) if self.policy == MirrorPolicy::Required => {
+                    // Abort any uploads already created
+                    let _ = self.primary.abort_multipart(location, &id).await;
+                    for (idx, id) in ids {
+                        let _ = self.secondaries[idx].abort_multipart(location, &id).await;
+                    }
+                    return Err(e);
+                }
+                Err(e) => warn_failure("put_multipart", location, store, &e),
+            }
+        }
+
+        let key = (location.clone(), id.clone());
+        self.uploads.lock().insert(key.clone(), ids);
+
+        let writer = MirrorWriter {
+            primary,
+            secondaries,
+            pending: Bytes::new(),
+            required: self.policy == MirrorPolicy::Required,
+            location: location.clone(),
+            uploads: Arc::clone(&self.uploads),
+            key,
+        };
+        Ok((id, Box::new(writer)))
+    }
+
+    async fn abort_multipart(&self, location: &Path, multipart_id: &MultipartId) -> Result<()> {
+        self.primary.abort_multipart(location, multipart_id).await?;
+
+        let key = (location.clone(), multipart_id.clone());
+        let ids = self.uploads.lock().remove(&key).unwrap_or_default();
+        for (idx, id) in ids {
+            let secondary = &self.secondaries[idx];
+            if let Err(e) = secondary.abort_multipart(location, &id).await {
+                if self.policy == MirrorPolicy::Required {
+                    return Err(e);
+                }
+                warn_failure("abort_multipart", location, secondary, &e);
+            }
+        }
+        Ok(())
+    }
+
+    async fn get_opts(&self, location: &Path, options: GetOptions) -> Result<GetResult> {
+        self.primary.get_opts(location, options).await
+    }
+
+    async fn get_range(&self, location: &Path, range: Range<usize>) -> Result<Bytes> {
+        self.primary.get_range(location, range).await
+    }
+
+    async fn head(&self, location: &Path) -> Result<ObjectMeta> {
+        self.primary.head(location).await
+    }
+
+    async fn delete(&self, location: &Path) -> Result<()> {
+        self.delete_opts(location, DeleteOptions::default()).await
+    }
+
+    /// Note: any precondition is only evaluated against the primary store, and as versions
+    /// are specific to a store, deleting a [`DeleteOptions::version`] is not mirrored
+    async fn delete_opts(&self, location: &Path, opts: DeleteOptions) -> Result<()> {
+        let versioned = opts.version.is_some();
+        self.primary.delete_opts(location, opts).await?;
+        if versioned {
+            return Ok(());
+        }
+
+        let path = location.clone();
+        self.mirror("delete", location, move |s| {
+            let path = path.clone();
+            async move {
+                match s.delete(&path).await {
+                    Ok(()) | Err(Error::NotFound { .. }) => Ok(()),
+                    Err(e) => Err(e),
+                }
+            }
+        })
+        .await
+    }
+
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'_, Result<ObjectMeta>> {
+        self.primary.list(prefix)
+    }
+
+    fn list_with_offset(
+        &self,
+        prefix: Option<&Path>,
+        offset: &Path,
+    ) -> BoxStream<'_, Result<ObjectMeta>> {
+        self.primary.list_with_offset(prefix, offset)
+    }
+
+    fn list_range(&self, start: &Path, end: &Path) -> BoxStream<'_, Result<ObjectMeta>> {
+        self.primary.list_range(start, end)
+    }
+
+    fn list_from_token(
+        &self,
+        prefix: Option<&Path>,
+        token: Option<&str>,
+    ) -> BoxStream<'_, Result<ListPage>> {
+        self.primary.list_from_token(prefix, token)
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
+        self.primary.list_with_delimiter(prefix).await
+    }
+
+    fn list_with_delimiter_stream(
+        &self,
+        prefix: Option<&Path>,
+    ) -> BoxStream<'_, Result<ListResult>> {
+        self.primary.list_with_delimiter_stream(prefix)
+    }
+
+    async fn copy(&self, from: &Path, to: &Path) -> Result<()> {
+        self.primary.copy(from, to).await?;
+
+        let (src, dst) = (from.clone(), to.clone());
+        self.mirror("copy", to, move |s| {
+            let (src, dst) = (src.clone(), dst.clone());
+            async move { s.copy(&src, &dst).await }
+        })
+        .await
+    }
+
+    async fn copy_opts(&self, from: &Path, to: &Path, opts: CopyOptions) -> Result<()> {
+        self.primary.copy_opts(from, to, opts).await?;
+
+        let (src, dst) = (from.clone(), to.clone());
+        self.mirror("copy", to, move |s| {
+            let (src, dst) = (src.clone(), dst.clone());
+            async move { s.copy(&src, &dst).await }
+        })
+        .await
+    }
+
+    async fn probe(&self) -> Result<()> {
+        self.primary.probe().await?;
+        futures::future::try_join_all(self.secondaries.iter().map(|s| s.probe())).await?;
+        Ok(())
+    }
+
+    async fn get_acl(&self, location: &Path) -> Result<Acl> {
+        self.primary.get_acl(location).await
+    }
+
+    async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        self.primary.copy_if_not_exists(from, to).await?;
+
+        let (src, dst) = (from.clone(), to.clone());
+        self.mirror("copy", to, move |s| {
+            let (src, dst) = (src.clone(), dst.clone());
+            async move { s.copy(&src, &dst).await }
+        })
+        .await
+    }
+
+    fn object_url(&self, location: &Path) -> Result<Url> {
+        self.primary.object_url(location)
+    }
+
+    fn validate_path(&self, location: &Path) -> Result<()> {
+        self.primary.validate_path(location)?;
+        for secondary in &self.secondaries {
+            secondary.validate_path(location)?;
+        }
+        Ok(())
+    }
+}
+
+struct SecondaryWriter {
+    store: Arc<dyn ObjectStore>,
+    writer: Box<dyn AsyncWrite + Unpin + Send>,
+    /// The number of bytes of [`MirrorWriter::pending`] written
+    written: usize,
+    shutdown: bool,
+}
+
+/// An [`AsyncWrite`] that tees writes to the primary upload to the secondary uploads
+struct MirrorWriter {
+    primary: Box<dyn AsyncWrite + Unpin + Send>,
+    secondaries: Vec<SecondaryWriter>,
+    /// Data accepted by the primary upload but not yet by all secondary uploads
+    pending: Bytes,
+    required: bool,
+    location: Path,
+    uploads: Uploads,
+    key: (Path, MultipartId),
+}
+
+impl MirrorWriter {
+    /// Calls `f` for each secondary, returning [`Poll::Pending`] if any are pending
+    ///
+    /// Secondaries that fail are removed if not [`MirrorPolicy::Required`]
+    fn poll_secondaries<F>(&mut self, op: &str, mut f: F) -> Poll<io::Result<()>>
+    where
+        F: FnMut(&mut SecondaryWriter, &Bytes) -> Poll<io::Result<()>>,
+    {
+        let mut pending = false;
+        let mut idx = 0;
+        while idx < self.secondaries.len() {
+            match f(&mut self.secondaries[idx], &self.pending) {
+                Poll::Ready(Ok(())) => {}
+                Poll::Pending => pending = true,
+                Poll::Ready(Err(e)) if self.required => return Poll::Ready(Err(e)),
+                Poll::Ready(Err(e)) => {
+                    let secondary = self.secondaries.remove(idx);
+                    warn_failure(op, &self.location, &secondary.store, &e);
+                    continue;
+                }
+            }
+            idx += 1;
+        }
+        match pending {
+            true => Poll::Pending,
+            false => Poll::Ready(Ok(())),
+        }
+    }
+
+    /// Writes [`Self::pending`] to all secondaries
+    fn poll_drain(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        ready!(self.poll_secondaries("put_multipart", |s, pending| {
+            while s.written < pending.len() {
+                let buf = &pending[s.written..];
+                match ready!(Pin::new(&mut s.writer).poll_write(cx, buf)) {
+                    Ok(0) => return Poll::Ready(Err(io::ErrorKind::WriteZero.into())),
+                    Ok(n) => s.written += n,
+                    Err(e) => return Poll::Ready(Err(e)),
+                }
+            }
+            Poll::Ready(Ok(()))
+        }))?;
+
+        self.pending = Bytes::new();
+        self.secondaries.iter_mut().for_each(|s| s.written = 0);
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncWrite for MirrorWriter {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        ready!(self.poll_drain(cx))?;
+        let n = ready!(Pin::new(&mut self.primary).poll_write(cx, buf))?;
+        if !self.secondaries.is_empty() {
+            self.pending = Bytes::copy_from_slice(&buf[..n]);
+        }
+        Poll::Ready(Ok(n))
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        ready!(self.poll_drain(cx))?;
+        ready!(Pin::new(&mut self.primary).poll_flush(cx))?;
+        self.poll_secondaries("put_multipart", |s, _| {
+            Pin::new(&mut s.writer).poll_flush(cx)
+        })
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        ready!(self.poll_drain(cx))?;
+        ready!(self.poll_secondaries("put_multipart", |s, _| {
+            if !s.shutdown {
+                ready!(Pin::new(&mut s.writer).poll_shutdown(cx))?;
+                s.shutdown = true;
+            }
+            Poll::Ready(Ok(()))
+        }))?;
+        ready!(Pin::new(&mut self.primary).poll_shutdown(cx))?;
+        self.uploads.lock().remove(&self.key);
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::local::LocalFileSystem;
+    use crate::memory::InMemory;
+    use crate::tests::*;
+    use tokio::io::AsyncWriteExt;
+
+    fn failing_store(dir: &tempfile::TempDir) -> Arc<dyn ObjectStore> {
+        // A root that is a file causes all writes to fail
+        let root = dir.path().join("file");
+        std::fs::write(&root, "").unwrap();
+        Arc::new(LocalFileSystem::new_with_prefix(root).unwrap())
+    }
+
+    #[tokio::test]
+    async fn mirror_test() {
+        let integration = MirrorStore::new(
+            Arc::new(InMemory::new()),
+            vec![Arc::new(InMemory::new()), Arc::new(InMemory::new())],
+        );
+
+        put_get_delete_list(&integration).await;
+        get_opts(&integration).await;
+        list_uses_directories_correctly(&integration).await;
+        list_with_delimiter(&integration).await;
+        rename_and_copy(&integration).await;
+        copy_if_not_exists(&integration).await;
+        stream_get(&integration).await;
+    }
+
+    #[tokio::test]
+    async fn test_mirror_writes() {
+        let primary: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+        let secondary: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+        let store = MirrorStore::new(Arc::clone(&primary), vec![Arc::clone(&secondary)]);
+
+        let a = Path::from("a");
+        let b = Path::from("b");
+        let c = Path::from("c");
+        store.put(&a, "data".into()).await.unwrap();
+        store.copy(&a, &b).await.unwrap();
+        for s in [&primary, &secondary] {
+            assert_eq!(s.get(&a).await.unwrap().bytes().await.unwrap(), "data");
+            assert_eq!(s.get(&b).await.unwrap().bytes().await.unwrap(), "data");
+        }
+
+        let (_, mut writer) = store.put_multipart(&c).await.unwrap();
+        writer.write_all(&[1; 1024]).await.unwrap();
+        writer.write_all(&[2; 1024]).await.unwrap();
+        writer.shutdown().await.unwrap();
+        for s in [&primary, &secondary] {
+            let data = s.get(&c).await.unwrap().bytes().await.unwrap();
+            assert_eq!(data.len(), 2048);
+            assert_eq!(&data[1023..1025], &[1, 2]);
+        }
+
+        store.delete(&a).await.unwrap();
+        for s in [&primary, &secondary] {
+            let err = s.head(&a).await.unwrap_err();
+            assert!(matches!(err, Error::NotFound { .. }), "{err}");
+        }
+
+        // Missing from secondary is not an error
+        primary.put(&a, "data".into()).await.unwrap();
+        store.delete(&a).await.unwrap();
+    }
+
+    /// Records the [`PutOptions`] of each put to an [`InMemory`] store
+    #[derive(Debug, Default)]
+    struct RecordingStore {
+        inner: InMemory,
+        puts: Mutex<Vec<PutOptions>>,
+    }
+
+    impl Display for RecordingStore {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            write!(f, "RecordingStore")
+        }
+    }
+
+    #[async_trait]
+    impl ObjectStore for RecordingStore {
+        async fn put_opts(
+            &self,
+            location: &Path,
+            bytes: Bytes,
+            opts: PutOptions,
+        ) -> Result<PutResult> {
+            self.puts.lock().push(opts.clone());
+            self.inner.put_opts(location, bytes, opts).await
+        }
+
+        async fn put_multipart(
+            &self,
+            location: &Path,
+        ) -> Result<(MultipartId, Box<dyn AsyncWrite + Unpin + Send>)> {
+            self.inner.put_multipart(location).await
+        }
+
+        async fn abort_multipart(&self, location: &Path, id: &MultipartId) -> Result<()> {
+            self.inner.abort_multipart(location, id).await
+        }
+
+        async fn get_opts(&self, location: &Path, options: GetOptions) -> Result<GetResult> {
+            self.inner.get_opts(location, options).await
+        }
+
+        async fn delete(&self, location: &Path) -> Result<()> {
+            self.inner.delete(location).await
+        }
+
+        fn list(&self, prefix: Option<&Path>) -> BoxStream<'_, Result<ObjectMeta>> {
+            self.inner.list(prefix)
+        }
+
+        async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
+            self.inner.list_with_delimiter(prefix).await
+        }
+
+        async fn copy(&self, from: &Path, to: &Path) -> Result<()> {
+            self.inner.copy(from, to).await
+        }
+
+        async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+            self.inner.copy_if_not_exists(from, to).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_mirror_put_options() {
+        let primary: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+        let secondary = Arc::new(RecordingStore::default());
+        let store = MirrorStore::new(primary, vec![Arc::clone(&secondary) as _]);
+
+        let mut tags = crate::TagSet::default();
+        tags.push("key", "value");
+        let opts = PutOptions {
+            mode: PutMode::Create,
+            tags,
+            metadata: [("owner".to_string(), "test".to_string())].into(),
+            acl: Some(crate::CannedAcl::Private),
+            ..Default::default()
+        };
+        let path = Path::from("a");
+        store
+            .put_opts(&path, "data".into(), opts.clone())
+            .await
+            .unwrap();
+
+        // All options other than the mode are applied to the secondary
+        let expected = PutOptions {
+            mode: PutMode::Overwrite,
+            ..opts
+        };
+        assert_eq!(*secondary.puts.lock(), vec![expected]);
+    }
+
+    #[tokio::test]
+    async fn test_mirror_policy() {
+        let dir = tempfile::tempdir().unwrap();
+        let location = Path::from("a");
+
+        let primary: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+        let secondary = failing_store(&dir);
+        let store = MirrorStore::new(Arc::clone(&primary), vec![Arc::clone(&secondary)]);
+        store.put(&location, "data".into()).await.unwrap_err();
+        assert!(store.put_multipart(&location).await.is_err());
+
+        for policy in [MirrorPolicy::BestEffort, MirrorPolicy::Background] {
+            let primary: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+            let working: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+            let secondaries = vec![Arc::clone(&secondary), Arc::clone(&working)];
+            let store = MirrorStore::new(Arc::clone(&primary), secondaries).with_policy(policy);
+
+            store.put(&location, "data".into()).await.unwrap();
+            primary.head(&location).await.unwrap();
+
+            let (_, mut writer) = store.put_multipart(&Path::from("b")).await.unwrap();
+            writer.write_all(b"data").await.unwrap();
+            writer.shutdown().await.unwrap();
+            primary.head(&Path::from("b")).await.unwrap();
+            working.head(&Path::from("b")).await.unwrap();
+
+            // Wait for any background mirroring to complete
+            for _ in 0..100 {
+                if working.head(&location).await.is_ok() {
+                    break;
+                }
+                tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            }
+            working.head(&location).await.unwrap();
+        }
+    }
+}
@@ -23,12 +23,255 @@
 
 use async_trait::async_trait;
 use bytes::Bytes;
-use futures::{stream::FuturesUnordered, Future, StreamExt};
+use chrono::{DateTime, Utc};
+use futures::future::BoxFuture;
+use futures::{stream::FuturesUnordered, Future, FutureExt, StreamExt};
 use std::{io, pin::Pin, sync::Arc, task::Poll};
 use tokio::io::AsyncWrite;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
 
 use crate::path::Path;
+#[cfg(feature = "cloud")]
+use crate::ObjectChecksum;
 use crate::{MultipartId, PutResult, Result};
+#[cfg(feature = "cloud")]
+use base64::{prelude::BASE64_STANDARD, Engine};
+
+/// The default size of the parts uploaded by [`WriteMultiPart`]
+///
+/// Minimum size of 5 MiB
+/// <https://docs.aws.amazon.com/AmazonS3/latest/userguide/qfacts.html>
+/// <https://cloud.google.com/storage/quotas#requests>
+pub(crate) const DEFAULT_PART_SIZE: usize = 10 * 1024 * 1024;
+
+/// The default number of parts uploaded concurrently by [`WriteMultiPart`]
+#[cfg(any(feature = "aws", feature = "gcp", feature = "azure"))]
+pub(crate) const DEFAULT_CONCURRENCY: usize = 8;
+
+/// The minimum size of all but the last part of a multipart upload for most stores
+#[cfg(any(feature = "aws", feature = "gcp"))]
+pub(crate) const MIN_PART_SIZE: usize = 5 * 1024 * 1024;
+
+/// The maximum size of a part of a multipart upload for most stores
+///
+/// <https://docs.aws.amazon.com/AmazonS3/latest/userguide/qfacts.html>
+/// <https://cloud.google.com/storage/quotas#requests>
+#[cfg(any(feature = "aws", feature = "gcp"))]
+pub(crate) const MAX_PART_SIZE: u64 = 5 * 1024 * 1024 * 1024;
+
+/// Returns an error if `part_size` is not within `min_part_size..=max_part_size` or
+/// `concurrency` is zero
+#[cfg(any(feature = "aws", feature = "gcp", feature = "azure"))]
+pub(crate) fn validate_multipart_config(
+    store: &'static str,
+    part_size: usize,
+    min_part_size: usize,
+    max_part_size: u64,
+    concurrency: usize,
+) -> Result<()> {
+    if part_size == 0 || part_size < min_part_size {
+        return Err(crate::Error::Generic {
+            store,
+            source: format!(
+                "multipart part size of {part_size} bytes is less than the minimum of {} bytes",
+                min_part_size.max(1)
+            )
+            .into(),
+        });
+    }
+    if part_size as u64 > max_part_size {
+        return Err(crate::Error::Generic {
+            store,
+            source: format!(
+                "multipart part size of {part_size} bytes exceeds the maximum of {max_part_size} bytes"
+            )
+            .into(),
+        });
+    }
+    if concurrency == 0 {
+        return Err(crate::Error::Generic {
+            store,
+            source: "multipart concurrency must be at least 1".into(),
+        });
+    }
+    Ok(())
+}
+
+/// Returns an error if `parts` are not in strictly increasing order of index, e.g.
+/// because a part is duplicated or out of order
+///
+/// Gaps in the part indices are permitted, as not all parts need be included in the
+/// completed upload. This allows malformed uploads to be rejected before issuing the
+/// completion request
+pub(crate) fn validate_parts(store: &'static str, parts: &[PartId]) -> Result<()> {
+    for (position, pair) in parts.windows(2).enumerate() {
+        let (prev, part) = (pair[0].part_idx, pair[1].part_idx);
+        if part > prev {
+            continue;
+        }
+        let position = position + 1;
+        let source = if part == prev {
+            format!("duplicate multipart part {part} at position {position}")
+        } else {
+            format!("multipart parts out of order, found part {part} after part {prev} at position {position}")
+        };
+        return Err(crate::Error::Generic {
+            store,
+            source: source.into(),
+        });
+    }
+    Ok(())
+}
+
+/// The expected checksums of the parts and assembled object of a multipart upload,
+/// see [`WriteMultiPart::with_checksum_manifest`]
+///
+/// This allows reproducible uploads, where the checksums are known ahead of time, to
+/// detect any corruption of the data before the upload is completed
+#[cfg(feature = "cloud")]
+#[derive(Debug, Clone)]
+pub struct ChecksumManifest {
+    location: Path,
+    parts: Vec<ObjectChecksum>,
+    object: Option<ObjectChecksum>,
+}
+
+#[cfg(feature = "cloud")]
+impl ChecksumManifest {
+    /// Create a new, empty, [`ChecksumManifest`] for an upload to `location`, which
+    /// is reported by any [`Error::ChecksumMismatch`](crate::Error::ChecksumMismatch)
+    pub fn new(location: Path) -> Self {
+        Self {
+            location,
+            parts: vec![],
+            object: None,
+        }
+    }
+
+    /// Expect the upload to consist of exactly `parts.len()` parts, with the part
+    /// `part_idx` having the checksum `parts[part_idx]`
+    ///
+    /// The part boundaries are those of the [`WriteMultiPart`], and so depend on its
+    /// configured part size
+    pub fn with_parts(mut self, parts: Vec<ObjectChecksum>) -> Self {
+        self.parts = parts;
+        self
+    }
+
+    /// Expect the assembled object to have the checksum `checksum`
+    pub fn with_object(mut self, checksum: ObjectChecksum) -> Self {
+        self.object = Some(checksum);
+        self
+    }
+}
+
+/// Verifies the data of a [`WriteMultiPart`] against a [`ChecksumManifest`]
+#[cfg(feature = "cloud")]
+struct ManifestVerifier {
+    manifest: ChecksumManifest,
+    /// The hasher of the assembled object, updated in part order
+    hasher: Option<crate::client::checksum::Hasher>,
+    /// The index of the next part expected by `hasher`
+    next_part: usize,
+    /// The first mismatch, if any, after which all verification fails
+    failure: Option<String>,
+}
+
+#[cfg(feature = "cloud")]
+impl ManifestVerifier {
+    fn new(manifest: ChecksumManifest) -> Self {
+        let hasher = manifest.object.as_ref().map(|c| c.algorithm.hasher());
+        Self {
+            manifest,
+            hasher,
+            next_part: 0,
+            failure: None,
+        }
+    }
+
+    /// Records the result of a verification, returning the first mismatch encountered
+    fn record(&mut self, result: Result<()>) -> Result<()> {
+        if self.failure.is_none() {
+            self.failure = match result {
+                Ok(()) => None,
+                Err(crate::Error::ChecksumMismatch { source, .. }) => Some(source.to_string()),
+                Err(e) => Some(e.to_string()),
+            };
+        }
+        match &self.failure {
+            Some(msg) => Err(self.mismatch(msg.clone())),
+            None => Ok(()),
+        }
+    }
+
+    fn mismatch(&self, msg: String) -> crate::Error {
+        crate::Error::ChecksumMismatch {
+            path: self.manifest.location.to_string(),
+            source: msg.into(),
+        }
+    }
+
+    /// Verifies the part `part_idx`, which must be provided in order
+    fn verify_part(&mut self, part_idx: usize, data: &[u8]) -> Result<()> {
+        let result = self.check_part(part_idx, data);
+        self.record(result)
+    }
+
+    /// Verifies the assembled object of `num_parts` parts
+    ///
+    /// Once verification has failed, this returns the same error on every call
+    fn verify_object(&mut self, num_parts: usize) -> Result<()> {
+        let result = match self.failure {
+            Some(_) => Ok(()),
+            None => self.check_object(num_parts),
+        };
+        self.record(result)
+    }
+
+    fn check_part(&mut self, part_idx: usize, data: &[u8]) -> Result<()> {
+        // The assembled object cannot be verified if earlier parts were skipped,
+        // as is the case for a resumed upload
+        if part_idx != self.next_part {
+            self.hasher = None;
+        }
+        self.next_part = part_idx + 1;
+        if let Some(hasher) = self.hasher.as_mut() {
+            hasher.update(data);
+        }
+        if self.manifest.parts.is_empty() {
+            return Ok(());
+        }
+        match self.manifest.parts.get(part_idx) {
+            Some(expected) => match expected.algorithm.digest(data) {
+                actual if actual == expected.digest => Ok(()),
+                actual => Err(self.mismatch(format!(
+                    "expected {} {} for part {part_idx}, got {}",
+                    expected.algorithm.as_str(),
+                    BASE64_STANDARD.encode(&expected.digest),
+                    BASE64_STANDARD.encode(actual)
+                ))),
+            },
+            None => Err(self.mismatch(format!(
+                "expected {} parts, got part {part_idx}",
+                self.manifest.parts.len()
+            ))),
+        }
+    }
+
+    fn check_object(&mut self, num_parts: usize) -> Result<()> {
+        let expected_parts = self.manifest.parts.len();
+        if expected_parts != 0 && expected_parts != num_parts {
+            let msg = format!("expected {expected_parts} parts, got {num_parts}");
+            return Err(self.mismatch(msg));
+        }
+        match (&self.manifest.object, self.hasher.take()) {
+            (Some(expected), Some(hasher)) => {
+                expected.check(&self.manifest.location, hasher.finish())
+            }
+            _ => Ok(()),
+        }
+    }
+}
 
 type BoxedTryFuture<T> = Pin<Box<dyn Future<Output = Result<T, io::Error>> + Send>>;
 
@@ -43,6 +286,14 @@ pub trait PutPart: Send + Sync + 'static {
     ///
     /// `completed_parts` is in order of part number
     async fn complete(&self, completed_parts: Vec<PartId>) -> Result<()>;
+
+    /// Abort the upload, discarding any parts uploaded so far
+    ///
+    /// The default implementation does nothing, and is suitable for stores that
+    /// automatically discard uncommitted parts
+    async fn abort(&self) -> Result<()> {
+        Ok(())
+    }
 }
 
 /// Represents a part of a file that has been successfully uploaded in a multipart upload process.
@@ -50,12 +301,103 @@ pub trait PutPart: Send + Sync + 'static {
 pub struct PartId {
     /// Id of this part
     pub content_id: String,
+    /// The zero-based index of this part within the upload
+    part_idx: usize,
+}
+
+impl PartId {
+    /// Create a new [`PartId`] for the part `part_idx` with the id `content_id`
+    pub fn new(content_id: impl Into<String>, part_idx: usize) -> Self {
+        Self {
+            content_id: content_id.into(),
+            part_idx,
+        }
+    }
+
+    /// The zero-based index of this part within the upload
+    pub fn part_idx(&self) -> usize {
+        self.part_idx
+    }
+}
+
+/// The granularity, in bytes, with which [`UploadLimiter`] tracks capacity
+const LIMITER_UNIT: usize = 1024;
+
+/// Limits the total bytes buffered and in flight across all the [`WriteMultiPart`]
+/// sharing it, see [`WriteMultiPart::with_upload_limiter`]
+///
+/// Each writer reserves the size of a part from the limiter before buffering data for it,
+/// and releases the reservation once that part has been uploaded. Writers therefore wait
+/// for capacity, instead of buffering more data, once the limit is reached.
+///
+/// Capacity is tracked in whole KiB, with each reservation rounded up. A part larger than
+/// the limit reserves the entire limit, and so such uploads proceed one part at a time.
+///
+/// Cloning an [`UploadLimiter`] shares the same limit
+#[derive(Debug, Clone)]
+pub struct UploadLimiter {
+    semaphore: Arc<Semaphore>,
+    max_bytes: usize,
+}
+
+impl UploadLimiter {
+    /// Create a new [`UploadLimiter`] allowing at most `max_bytes` to be buffered or
+    /// in flight at any time
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_bytes` is less than 1 KiB
+    pub fn new(max_bytes: usize) -> Self {
+        let permits = (max_bytes / LIMITER_UNIT).min(Semaphore::MAX_PERMITS);
+        assert_ne!(permits, 0, "upload limit must be at least 1 KiB");
+        Self {
+            semaphore: Arc::new(Semaphore::new(permits)),
+            max_bytes,
+        }
+    }
+
+    /// Returns the maximum number of bytes that can be buffered or in flight
+    pub fn max_bytes(&self) -> usize {
+        self.max_bytes
+    }
+
+    /// Returns the number of bytes not currently reserved by a writer
+    pub fn available_bytes(&self) -> usize {
+        self.semaphore.available_permits() * LIMITER_UNIT
+    }
+
+    /// Reserve capacity for a part of `size` bytes
+    fn reserve(&self, size: usize) -> BoxFuture<'static, OwnedSemaphorePermit> {
+        let max = (self.max_bytes / LIMITER_UNIT).min(Semaphore::MAX_PERMITS);
+        let permits = ((size + LIMITER_UNIT - 1) / LIMITER_UNIT)
+            .clamp(1, max)
+            .min(u32::MAX as usize);
+        let semaphore = Arc::clone(&self.semaphore);
+        async move {
+            let permit = semaphore.acquire_many_owned(permits as u32).await;
+            permit.expect("semaphore is never closed")
+        }
+        .boxed()
+    }
 }
 
 /// Wrapper around a [`PutPart`] that implements [`AsyncWrite`]
 ///
-/// Data will be uploaded in fixed size chunks of 10 MiB in parallel,
-/// up to the configured maximum concurrency
+/// Data will be uploaded in fixed size chunks, of 10 MiB by default, in parallel,
+/// up to the configured maximum concurrency. Alternatively the part size can grow as
+/// the upload progresses, see [`WriteMultiPart::with_part_size_scaling`]
+///
+/// # Cancellation
+///
+/// Part uploads are driven by polling this writer, and are not spawned onto a runtime.
+/// Dropping a [`WriteMultiPart`] therefore cancels any outstanding part uploads, and the
+/// upload will never be completed unless [`AsyncWrite::poll_shutdown`] previously
+/// returned [`Poll::Ready`]. Dropping the writer whilst shutdown is in progress may or
+/// may not complete the upload, depending on whether the completion request had already
+/// been received by the store.
+///
+/// Parts uploaded before the writer was dropped are not automatically cleaned up, and
+/// may incur storage charges, use [`WriteMultiPart::abort`] to discard them
 pub struct WriteMultiPart<T: PutPart> {
     inner: Arc<T>,
     /// A list of completed parts, in sequential order.
@@ -71,10 +413,22 @@ pub struct WriteMultiPart<T: PutPart> {
     /// While S3 and Minio support variable part sizes, R2 requires they all be
     /// exactly the same size.
     part_size: usize,
+    /// If set, the part size grows so as to fit the upload in this many parts, up to
+    /// the maximum part size
+    part_size_scaling: Option<(usize, usize)>,
     /// Index of current part
     current_part_idx: usize,
     /// The completion task
     completion_task: Option<BoxedTryFuture<()>>,
+    /// If set, limits the bytes buffered and in flight across writers
+    limiter: Option<UploadLimiter>,
+    /// The capacity reserved from `limiter` for `current_buffer`
+    reservation: Option<OwnedSemaphorePermit>,
+    /// An in-progress reservation from `limiter`
+    pending_reservation: Option<BoxFuture<'static, OwnedSemaphorePermit>>,
+    /// If set, verifies the parts and assembled object against a [`ChecksumManifest`]
+    #[cfg(feature = "cloud")]
+    verifier: Option<ManifestVerifier>,
 }
 
 impl<T: PutPart> WriteMultiPart<T> {
@@ -86,21 +440,229 @@ impl<T: PutPart> WriteMultiPart<T> {
             tasks: FuturesUnordered::new(),
             max_concurrency,
             current_buffer: Vec::new(),
-            // TODO: Should self vary by provider?
-            // TODO: Should we automatically increase then when part index gets large?
-
-            // Minimum size of 5 MiB
-            // https://docs.aws.amazon.com/AmazonS3/latest/userguide/qfacts.html
-            // https://cloud.google.com/storage/quotas#requests
-            part_size: 10 * 1024 * 1024,
+            part_size: DEFAULT_PART_SIZE,
+            part_size_scaling: None,
             current_part_idx: 0,
             completion_task: None,
+            limiter: None,
+            reservation: None,
+            pending_reservation: None,
+            #[cfg(feature = "cloud")]
+            verifier: None,
+        }
+    }
+
+    /// Verify the parts and assembled object against the expected checksums of `manifest`
+    ///
+    /// Each part is verified before it is uploaded, and the assembled object before the
+    /// upload is completed, failing the write or shutdown respectively with
+    /// [`Error::ChecksumMismatch`](crate::Error::ChecksumMismatch) should either diverge.
+    /// The upload is then not completed, and should be discarded with [`Self::abort`]
+    #[cfg(feature = "cloud")]
+    pub fn with_checksum_manifest(mut self, manifest: ChecksumManifest) -> Self {
+        self.verifier = Some(ManifestVerifier::new(manifest));
+        self
+    }
+
+    /// Verifies the part `part_idx` against the [`ChecksumManifest`], if any
+    #[cfg(feature = "cloud")]
+    fn verify_part(&mut self, part_idx: usize, data: &[u8]) -> Result<()> {
+        match self.verifier.as_mut() {
+            Some(v) => v.verify_part(part_idx, data),
+            None => Ok(()),
+        }
+    }
+
+    #[cfg(not(feature = "cloud"))]
+    fn verify_part(&mut self, _part_idx: usize, _data: &[u8]) -> Result<()> {
+        Ok(())
+    }
+
+    /// Verifies the assembled object of `num_parts` against the [`ChecksumManifest`], if any
+    #[cfg(feature = "cloud")]
+    fn verify_object(&mut self, num_parts: usize) -> Result<()> {
+        match self.verifier.as_mut() {
+            Some(v) => v.verify_object(num_parts),
+            None => Ok(()),
+        }
+    }
+
+    #[cfg(not(feature = "cloud"))]
+    fn verify_object(&mut self, _num_parts: usize) -> Result<()> {
+        Ok(())
+    }
+
+    /// Bound the bytes buffered and in flight by this writer, and any others sharing
+    /// the same [`UploadLimiter`]
+    ///
+    /// This allows many concurrent uploads without the risk of exhausting memory
+    pub fn with_upload_limiter(mut self, limiter: UploadLimiter) -> Self {
+        self.limiter = Some(limiter);
+        self
+    }
+
+    /// Sets the size of each part, defaults to 10 MiB
+    ///
+    /// Most stores require all parts excluding the last to be at least 5 MiB
+    ///
+    /// # Panics
+    ///
+    /// Panics if `part_size` is zero
+    pub fn with_part_size(mut self, part_size: usize) -> Self {
+        assert_ne!(part_size, 0, "part size must be non-zero");
+        self.part_size = part_size;
+        self
+    }
+
+    /// Grow the size of each part as the upload progresses, so that uploads of unknown
+    /// size can use small parts when small, without limiting the total size of the
+    /// upload to `max_parts` times the part size
+    ///
+    /// The part size starts at the size configured by [`Self::with_part_size`], and
+    /// doubles after every `max_parts / 10` parts, i.e. parts `0..1000` use the initial
+    /// size, parts `1000..2000` twice that, and so on for a `max_parts` of 10,000.
+    /// An upload can therefore reach `max_parts / 10 * 1023` times the initial part size,
+    /// roughly 10 TiB for 10 MiB initial parts, before exceeding `max_parts`, with the
+    /// final parts 512 times the initial size.
+    ///
+    /// Parts never exceed `max_part_size`, which should be the maximum part size of the
+    /// store. As whole parts are buffered in memory, up to the configured concurrency
+    /// times the part size may be buffered, consider bounding this with
+    /// [`Self::with_upload_limiter`]
+    ///
+    /// This should not be used with stores that require all parts to be the same size,
+    /// such as Cloudflare R2
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_parts` or `max_part_size` is zero
+    pub fn with_part_size_scaling(mut self, max_parts: usize, max_part_size: usize) -> Self {
+        assert_ne!(max_parts, 0, "max parts must be non-zero");
+        assert_ne!(max_part_size, 0, "max part size must be non-zero");
+        self.part_size_scaling = Some((max_parts, max_part_size));
+        self
+    }
+
+    /// Resume an interrupted upload, whose parts `completed` have already been uploaded,
+    /// from the byte `offset` of the source data
+    ///
+    /// `completed` must be the contiguous parts `0..n` of the upload, and `offset` their
+    /// total size, i.e. the position in the source data of the first byte to write to
+    /// this writer. The part size, see [`Self::with_part_size`] and
+    /// [`Self::with_part_size_scaling`], must therefore match that of the interrupted
+    /// upload, and be configured before calling this method.
+    ///
+    /// Subsequent writes are uploaded starting from part `n`, and the completed parts are
+    /// included when the upload is completed by [`AsyncWrite::poll_shutdown`]. As their
+    /// data is not available, a checksum manifest can only verify the remaining parts,
+    /// and not the assembled object.
+    ///
+    /// An upload of a [`MultiPartStore`] can be resumed with [`MultiPartStoreUpload`]
+    ///
+    /// This should be called before any data is written
+    pub fn resume_from(mut self, completed: Vec<PartId>, offset: u64) -> Result<Self> {
+        if let Some((expected, part)) = completed
+            .iter()
+            .enumerate()
+            .find(|(expected, part)| part.part_idx != *expected)
+        {
+            return Err(crate::Error::Generic {
+                store: "WriteMultiPart",
+                source: format!(
+                    "completed parts missing or out of order, expected part {expected} but found part {}",
+                    part.part_idx
+                )
+                .into(),
+            });
+        }
+        let expected: u64 = (0..completed.len())
+            .map(|idx| self.part_size_at(idx) as u64)
+            .sum();
+        if offset != expected {
+            return Err(crate::Error::Generic {
+                store: "WriteMultiPart",
+                source: format!(
+                    "resume offset {offset} does not match the {expected} bytes of the {} completed parts",
+                    completed.len()
+                )
+                .into(),
+            });
+        }
+        self.current_part_idx = completed.len();
+        self.completed_parts = completed.into_iter().map(Some).collect();
+        Ok(self)
+    }
+
+    /// Returns the size of the part `part_idx`
+    fn part_size_at(&self, part_idx: usize) -> usize {
+        match self.part_size_scaling {
+            Some((max_parts, max_part_size)) => {
+                let doublings = part_idx / (max_parts / 10).max(1);
+                let scale = 1_usize.checked_shl(doublings.min(9) as u32).unwrap_or(1);
+                self.part_size.saturating_mul(scale).min(max_part_size)
+            }
+            None => self.part_size,
+        }
+    }
+
+    /// Returns the size of the current part
+    fn current_part_size(&self) -> usize {
+        self.part_size_at(self.current_part_idx)
+    }
+
+    /// Abort the upload, cancelling any outstanding part uploads and then
+    /// discarding any parts already uploaded, see [`PutPart::abort`]
+    ///
+    /// Aborting an upload that has already been completed by
+    /// [`AsyncWrite::poll_shutdown`] results in implementation-defined behaviour
+    pub async fn abort(self) -> Result<()> {
+        let Self { inner, tasks, .. } = self;
+        // Dropping the tasks cancels any in-flight requests
+        drop(tasks);
+        inner.abort().await
+    }
+
+    /// Reserve capacity for the current buffer from the limiter, if any
+    fn poll_reserve(mut self: Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> Poll<()> {
+        if self.reservation.is_some() {
+            return Poll::Ready(());
         }
+        let size = self.current_part_size();
+        let this = &mut *self;
+        let fut = match (&this.limiter, &mut this.pending_reservation) {
+            (None, _) => return Poll::Ready(()),
+            (Some(_), Some(fut)) => fut,
+            (Some(limiter), pending) => pending.insert(limiter.reserve(size)),
+        };
+        let permit = futures::ready!(fut.poll_unpin(cx));
+        self.pending_reservation = None;
+        self.reservation = Some(permit);
+        Poll::Ready(())
+    }
+
+    /// Upload the current buffer as the part `part_idx`, releasing its reservation
+    /// from the limiter once complete
+    fn submit_part(mut self: Pin<&mut Self>, part_idx: usize) {
+        let out_buffer = std::mem::take(&mut self.current_buffer);
+        // Parts are submitted in order, as required to verify the assembled object
+        let verified = self.verify_part(part_idx, &out_buffer);
+        let reservation = self.reservation.take();
+        let inner = Arc::clone(&self.inner);
+        self.tasks.push(Box::pin(async move {
+            verified?;
+            let upload_part = inner.put_part(out_buffer, part_idx).await?;
+            drop(reservation);
+            Ok((part_idx, upload_part))
+        }));
     }
 
     // Add data to the current buffer, returning the number of bytes added
     fn add_to_buffer(mut self: Pin<&mut Self>, buf: &[u8], offset: usize) -> usize {
-        let remaining_capacity = self.part_size - self.current_buffer.len();
+        if self.current_buffer.capacity() == 0 && offset < buf.len() {
+            let size = self.current_part_size();
+            self.current_buffer.reserve(size);
+        }
+        let remaining_capacity = self.current_part_size() - self.current_buffer.len();
         let to_copy = std::cmp::min(remaining_capacity, buf.len() - offset);
         self.current_buffer
             .extend_from_slice(&buf[offset..offset + to_copy]);
@@ -138,13 +700,8 @@ impl<T: PutPart> WriteMultiPart<T> {
 
         // If current_buffer is not empty, see if it can be submitted
         if !self.current_buffer.is_empty() && self.tasks.len() < self.max_concurrency {
-            let out_buffer: Vec<u8> = std::mem::take(&mut self.current_buffer);
-            let inner = Arc::clone(&self.inner);
             let part_idx = self.current_part_idx;
-            self.tasks.push(Box::pin(async move {
-                let upload_part = inner.put_part(out_buffer, part_idx).await?;
-                Ok((part_idx, upload_part))
-            }));
+            self.as_mut().submit_part(part_idx);
         }
 
         self.as_mut().poll_tasks(cx)?;
@@ -170,24 +727,23 @@ impl<T: PutPart> AsyncWrite for WriteMultiPart<T> {
         let mut offset = 0;
 
         loop {
+            // Wait for capacity from the limiter before buffering more data
+            if offset < buf.len() && self.as_mut().poll_reserve(cx).is_pending() {
+                break;
+            }
+
             // Fill up current buffer
             offset += self.as_mut().add_to_buffer(buf, offset);
 
             // If we don't have a full buffer or we have too many tasks, break
-            if self.current_buffer.len() < self.part_size
+            if self.current_buffer.len() < self.current_part_size()
                 || self.tasks.len() >= self.max_concurrency
             {
                 break;
             }
 
-            let new_buffer = Vec::with_capacity(self.part_size);
-            let out_buffer = std::mem::replace(&mut self.current_buffer, new_buffer);
-            let inner = Arc::clone(&self.inner);
             let part_idx = self.current_part_idx;
-            self.tasks.push(Box::pin(async move {
-                let upload_part = inner.put_part(out_buffer, part_idx).await?;
-                Ok((part_idx, upload_part))
-            }));
+            self.as_mut().submit_part(part_idx);
             self.current_part_idx += 1;
 
             // We need to poll immediately after adding to setup waker
@@ -228,29 +784,33 @@ impl<T: PutPart> AsyncWrite for WriteMultiPart<T> {
             Poll::Ready(res) => res?,
         };
 
-        // If shutdown task is not set, set it
-        let parts = std::mem::take(&mut self.completed_parts);
-        let parts = parts
-            .into_iter()
-            .enumerate()
-            .map(|(idx, part)| {
-                part.ok_or_else(|| {
-                    io::Error::new(
-                        io::ErrorKind::Other,
-                        format!("Missing information for upload part {idx}"),
-                    )
+        // If shutdown task is not set, set it, leaving the completed parts in place
+        // so that should this fail, any subsequent shutdown fails likewise
+        if self.completion_task.is_none() {
+            let num_parts = self.completed_parts.len();
+            self.verify_object(num_parts)?;
+            let parts = self
+                .completed_parts
+                .iter()
+                .enumerate()
+                .map(|(idx, part)| {
+                    part.clone().ok_or_else(|| {
+                        io::Error::new(
+                            io::ErrorKind::Other,
+                            format!("Missing information for upload part {idx}"),
+                        )
+                    })
                 })
-            })
-            .collect::<Result<_, _>>()?;
+                .collect::<Result<Vec<_>, _>>()?;
 
-        let inner = Arc::clone(&self.inner);
-        let completion_task = self.completion_task.get_or_insert_with(|| {
-            Box::pin(async move {
+            let inner = Arc::clone(&self.inner);
+            self.completion_task = Some(Box::pin(async move {
                 inner.complete(parts).await?;
                 Ok(())
-            })
-        });
+            }));
+        }
 
+        let completion_task = self.completion_task.as_mut().expect("completion task");
         Pin::new(completion_task).poll(cx)
     }
 }
@@ -263,7 +823,9 @@ impl<T: PutPart> std::fmt::Debug for WriteMultiPart<T> {
             .field("max_concurrency", &self.max_concurrency)
             .field("current_buffer", &self.current_buffer)
             .field("part_size", &self.part_size)
+            .field("part_size_scaling", &self.part_size_scaling)
             .field("current_part_idx", &self.current_part_idx)
+            .field("limiter", &self.limiter)
             .finish()
     }
 }
@@ -271,11 +833,27 @@ impl<T: PutPart> std::fmt::Debug for WriteMultiPart<T> {
 /// A low-level interface for interacting with multipart upload APIs
 ///
 /// Most use-cases should prefer [`ObjectStore::put_multipart`] as this is supported by more
-/// backends, including [`LocalFileSystem`], and automatically handles uploading fixed
-/// size parts of sufficient size in parallel
+/// backends, and automatically handles uploading fixed size parts of sufficient size in parallel
+///
+/// # Resuming Uploads
+///
+/// As the [`MultipartId`] and the public fields of each [`PartId`] fully identify an
+/// upload, they can be persisted and used by another process, or another instance of
+/// the store, to upload further parts and complete the upload:
+///
+/// * S3 and GCS uploads remain resumable until they are completed or aborted, subject to
+///   any bucket lifecycle rules for incomplete multipart uploads
+/// * Azure blocks remain resumable for 7 days, after which uncommitted blocks are discarded
+/// * [`LocalFileSystem`] stages parts alongside the destination, and so uploads remain
+///   resumable until completed or aborted
+/// * [`InMemory`] uploads are only resumable by the same store instance
+///
+/// An upload can also be resumed from the end of its completed parts by a
+/// [`WriteMultiPart`], see [`MultiPartStoreUpload`] and [`WriteMultiPart::resume_from`].
 ///
 /// [`ObjectStore::put_multipart`]: crate::ObjectStore::put_multipart
 /// [`LocalFileSystem`]: crate::local::LocalFileSystem
+/// [`InMemory`]: crate::memory::InMemory
 #[async_trait]
 pub trait MultiPartStore: Send + Sync + 'static {
     /// Creates a new multipart upload, returning the [`MultipartId`]
@@ -315,4 +893,490 @@ pub trait MultiPartStore: Send + Sync + 'static {
 
     /// Aborts a multipart upload
     async fn abort_multipart(&self, path: &Path, id: &MultipartId) -> Result<()>;
+
+    /// Aborts the incomplete multipart uploads to locations under `prefix` that were
+    /// created before `older_than`, returning the number of uploads aborted
+    ///
+    /// The parts of uploads orphaned by a process that failed mid-upload are otherwise
+    /// retained, and for S3 billed, until the upload is aborted or removed by a bucket
+    /// lifecycle rule. An upload may be in progress, and so `older_than` should
+    /// allow for the longest expected upload.
+    ///
+    /// The default implementation does nothing, returning 0. This is the case for Azure,
+    /// as uncommitted blocks are discarded automatically after 7 days, and for GCS.
+    async fn abort_incomplete_uploads(
+        &self,
+        prefix: Option<&Path>,
+        older_than: DateTime<Utc>,
+    ) -> Result<usize> {
+        let _ = (prefix, older_than);
+        Ok(0)
+    }
+}
+
+/// A [`PutPart`] that uploads to an existing multipart upload of a [`MultiPartStore`]
+///
+/// This allows an upload created with [`MultiPartStore::create_multipart`], possibly
+/// by another process, to be written with a [`WriteMultiPart`], and in particular to be
+/// resumed with [`WriteMultiPart::resume_from`]
+///
+/// ```
+/// # use object_store::memory::InMemory;
+/// # use object_store::multipart::{MultiPartStore, MultiPartStoreUpload, WriteMultiPart};
+/// # use object_store::path::Path;
+/// # use std::sync::Arc;
+/// # async fn example() -> object_store::Result<()> {
+/// let store = Arc::new(InMemory::new());
+/// let path = Path::from("file");
+/// let id = store.create_multipart(&path).await?;
+/// // The parts uploaded before the upload was interrupted, and their total size
+/// # let (completed, offset) = (vec![], 0);
+/// let upload = MultiPartStoreUpload::new(store, path, id);
+/// let writer = WriteMultiPart::new(upload, 8).resume_from(completed, offset)?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct MultiPartStoreUpload {
+    store: Arc<dyn MultiPartStore>,
+    path: Path,
+    id: MultipartId,
+}
+
+impl std::fmt::Debug for MultiPartStoreUpload {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MultiPartStoreUpload")
+            .field("path", &self.path)
+            .field("id", &self.id)
+            .finish()
+    }
+}
+
+impl MultiPartStoreUpload {
+    /// Create a new [`MultiPartStoreUpload`] for the upload `id` to `path` in `store`
+    pub fn new(store: Arc<dyn MultiPartStore>, path: Path, id: MultipartId) -> Self {
+        Self { store, path, id }
+    }
+}
+
+#[async_trait]
+impl PutPart for MultiPartStoreUpload {
+    async fn put_part(&self, buf: Vec<u8>, part_idx: usize) -> Result<PartId> {
+        self.store
+            .put_part(&self.path, &self.id, part_idx, buf.into())
+            .await
+    }
+
+    async fn complete(&self, completed_parts: Vec<PartId>) -> Result<()> {
+        self.store
+            .complete_multipart(&self.path, &self.id, completed_parts)
+            .await?;
+        Ok(())
+    }
+
+    async fn abort(&self) -> Result<()> {
+        self.store.abort_multipart(&self.path, &self.id).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+    use tokio::io::AsyncWriteExt;
+
+    #[derive(Debug, Default)]
+    struct Counts {
+        started: AtomicUsize,
+        cancelled: AtomicUsize,
+        completed: AtomicUsize,
+        aborted: AtomicUsize,
+        /// The size of each uploaded part, by part index
+        sizes: parking_lot::Mutex<Vec<(usize, usize)>>,
+        /// The content ids of the parts the upload was completed with
+        parts: parking_lot::Mutex<Vec<String>>,
+    }
+
+    /// Records the cancellation of a part upload when dropped before completion
+    struct Guard<'a>(&'a Counts, bool);
+
+    impl<'a> Drop for Guard<'a> {
+        fn drop(&mut self) {
+            if !self.1 {
+                self.0.cancelled.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+    }
+
+    struct MockUpload {
+        counts: Arc<Counts>,
+        /// If part uploads should never complete
+        stall: bool,
+    }
+
+    #[async_trait]
+    impl PutPart for MockUpload {
+        async fn put_part(&self, buf: Vec<u8>, part_idx: usize) -> Result<PartId> {
+            self.counts.started.fetch_add(1, Ordering::SeqCst);
+            self.counts.sizes.lock().push((part_idx, buf.len()));
+            let mut guard = Guard(&self.counts, false);
+            if self.stall {
+                futures::future::pending::<()>().await;
+            }
+            guard.1 = true;
+            Ok(PartId {
+                content_id: part_idx.to_string(),
+                part_idx,
+            })
+        }
+
+        async fn complete(&self, completed_parts: Vec<PartId>) -> Result<()> {
+            self.counts.completed.fetch_add(1, Ordering::SeqCst);
+            *self.counts.parts.lock() = completed_parts.into_iter().map(|p| p.content_id).collect();
+            Ok(())
+        }
+
+        async fn abort(&self) -> Result<()> {
+            self.counts.aborted.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    fn mock_writer(stall: bool) -> (Arc<Counts>, WriteMultiPart<MockUpload>) {
+        let counts = Arc::new(Counts::default());
+        let upload = MockUpload {
+            counts: Arc::clone(&counts),
+            stall,
+        };
+        (counts, WriteMultiPart::new(upload, 2).with_part_size(10))
+    }
+
+    #[tokio::test]
+    async fn test_drop_cancels() {
+        let (counts, mut writer) = mock_writer(true);
+        writer.write_all(&[0; 25]).await.unwrap();
+        assert_eq!(counts.started.load(Ordering::SeqCst), 2);
+
+        drop(writer);
+        assert_eq!(counts.cancelled.load(Ordering::SeqCst), 2);
+        assert_eq!(counts.completed.load(Ordering::SeqCst), 0);
+        assert_eq!(counts.aborted.load(Ordering::SeqCst), 0);
+
+        // Dropping whilst shutdown is waiting on parts does not complete
+        let (counts, mut writer) = mock_writer(true);
+        writer.write_all(&[0; 25]).await.unwrap();
+        let shutdown = tokio::time::timeout(Duration::from_millis(10), writer.shutdown());
+        shutdown.await.unwrap_err();
+        drop(writer);
+        assert_eq!(counts.cancelled.load(Ordering::SeqCst), 2);
+        assert_eq!(counts.completed.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_abort() {
+        let (counts, mut writer) = mock_writer(true);
+        writer.write_all(&[0; 25]).await.unwrap();
+
+        writer.abort().await.unwrap();
+        assert_eq!(counts.cancelled.load(Ordering::SeqCst), 2);
+        assert_eq!(counts.completed.load(Ordering::SeqCst), 0);
+        assert_eq!(counts.aborted.load(Ordering::SeqCst), 1);
+
+        let (counts, mut writer) = mock_writer(false);
+        writer.write_all(&[0; 25]).await.unwrap();
+        writer.shutdown().await.unwrap();
+        assert_eq!(counts.started.load(Ordering::SeqCst), 3);
+        assert_eq!(counts.cancelled.load(Ordering::SeqCst), 0);
+        assert_eq!(counts.completed.load(Ordering::SeqCst), 1);
+        assert_eq!(counts.aborted.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_resume_from() {
+        let part = |part_idx: usize| PartId {
+            content_id: format!("previous{part_idx}"),
+            part_idx,
+        };
+        let data = [0; 25];
+
+        // Parts 0 and 1 were uploaded before the upload was interrupted
+        let (counts, writer) = mock_writer(false);
+        let mut writer = writer.resume_from(vec![part(0), part(1)], 20).unwrap();
+        writer.write_all(&data[20..]).await.unwrap();
+        writer.shutdown().await.unwrap();
+        assert_eq!(*counts.sizes.lock(), vec![(2, 5)]);
+        assert_eq!(counts.completed.load(Ordering::SeqCst), 1);
+        assert_eq!(*counts.parts.lock(), vec!["previous0", "previous1", "2"]);
+
+        // The offset must match the size of the completed parts
+        let (_, writer) = mock_writer(false);
+        let err = writer.resume_from(vec![part(0), part(1)], 25).unwrap_err();
+        assert!(err.to_string().contains("resume offset 25"), "{err}");
+
+        // As must the parts be contiguous
+        let (_, writer) = mock_writer(false);
+        let err = writer.resume_from(vec![part(0), part(2)], 20).unwrap_err();
+        assert!(err.to_string().contains("missing or out of order"), "{err}");
+
+        // Part sizes account for scaling
+        let (counts, writer) = mock_writer(false);
+        let writer = writer.with_part_size_scaling(10, usize::MAX);
+        let parts = (0..3).map(part).collect();
+        let mut writer = writer.resume_from(parts, 10 + 20 + 40).unwrap();
+        writer.write_all(&[0; 100]).await.unwrap();
+        writer.shutdown().await.unwrap();
+        assert_eq!(*counts.sizes.lock(), vec![(3, 80), (4, 20)]);
+        assert_eq!(counts.parts.lock().len(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_resume_multipart_store() {
+        use crate::memory::InMemory;
+        use crate::ObjectStore;
+
+        let store = Arc::new(InMemory::new());
+        let path = Path::from("file");
+        let data: Vec<u8> = (0..25).collect();
+
+        // Parts 0 and 1 were uploaded before the upload was interrupted
+        let id = store.create_multipart(&path).await.unwrap();
+        let mut parts = vec![];
+        for (idx, chunk) in data[..20].chunks(10).enumerate() {
+            let part = store.put_part(&path, &id, idx, Bytes::copy_from_slice(chunk));
+            parts.push(part.await.unwrap());
+        }
+
+        let upload = MultiPartStoreUpload::new(Arc::clone(&store) as _, path.clone(), id);
+        let writer = WriteMultiPart::new(upload, 2).with_part_size(10);
+        let mut writer = writer.resume_from(parts, 20).unwrap();
+        writer.write_all(&data[20..]).await.unwrap();
+        writer.shutdown().await.unwrap();
+
+        let read = store.get(&path).await.unwrap().bytes().await.unwrap();
+        assert_eq!(read.as_ref(), data.as_slice());
+    }
+
+    #[cfg(feature = "cloud")]
+    #[tokio::test]
+    async fn test_checksum_manifest() {
+        use crate::ChecksumAlgorithm;
+
+        let data: Vec<u8> = (0..25).collect();
+        let checksum = |algorithm: ChecksumAlgorithm, data: &[u8]| ObjectChecksum {
+            algorithm,
+            digest: algorithm.digest(data),
+        };
+        let parts: Vec<_> = data
+            .chunks(10)
+            .map(|c| checksum(ChecksumAlgorithm::Crc32c, c))
+            .collect();
+        let object = checksum(ChecksumAlgorithm::Sha256, &data);
+        let manifest = ChecksumManifest::new(Path::from("file"))
+            .with_parts(parts.clone())
+            .with_object(object.clone());
+
+        let (counts, writer) = mock_writer(false);
+        let mut writer = writer.with_checksum_manifest(manifest.clone());
+        writer.write_all(&data).await.unwrap();
+        writer.shutdown().await.unwrap();
+        assert_eq!(counts.completed.load(Ordering::SeqCst), 1);
+
+        let assert_mismatch = |e: io::Error| {
+            let e = e.into_inner().unwrap().downcast::<crate::Error>().unwrap();
+            assert!(matches!(*e, crate::Error::ChecksumMismatch { .. }), "{e}");
+            e.to_string()
+        };
+
+        // A part that diverges fails before it is uploaded
+        let mut wrong = parts.clone();
+        wrong[1] = checksum(ChecksumAlgorithm::Crc32c, b"corrupt");
+        let (counts, writer) = mock_writer(false);
+        let manifest = manifest.clone().with_parts(wrong);
+        let mut writer = writer.with_checksum_manifest(manifest);
+        let err = match writer.write_all(&data).await {
+            Ok(_) => writer.shutdown().await.unwrap_err(),
+            Err(e) => e,
+        };
+        let msg = assert_mismatch(err);
+        assert!(msg.contains("for part 1"), "{msg}");
+        // Shutting down again fails likewise, and does not complete the upload
+        let msg = assert_mismatch(writer.shutdown().await.unwrap_err());
+        assert!(msg.contains("for part 1"), "{msg}");
+        assert_eq!(counts.completed.load(Ordering::SeqCst), 0);
+        let sizes = counts.sizes.lock().clone();
+        assert!(sizes.iter().all(|(idx, _)| *idx != 1), "{sizes:?}");
+
+        // An assembled object that diverges is not completed
+        let (counts, writer) = mock_writer(false);
+        let manifest = ChecksumManifest::new(Path::from("file"))
+            .with_object(checksum(ChecksumAlgorithm::Sha256, b"corrupt"));
+        let mut writer = writer.with_checksum_manifest(manifest);
+        writer.write_all(&data).await.unwrap();
+        assert_mismatch(writer.shutdown().await.unwrap_err());
+        assert_mismatch(writer.shutdown().await.unwrap_err());
+        assert_eq!(counts.completed.load(Ordering::SeqCst), 0);
+
+        // As is an upload with a different number of parts
+        let (counts, writer) = mock_writer(false);
+        let manifest = ChecksumManifest::new(Path::from("file")).with_parts(parts.clone());
+        let mut writer = writer.with_checksum_manifest(manifest);
+        writer.write_all(&data[..15]).await.unwrap();
+        assert_mismatch(writer.shutdown().await.unwrap_err());
+        assert_eq!(counts.completed.load(Ordering::SeqCst), 0);
+
+        // A resumed upload verifies only the remaining parts
+        let (counts, writer) = mock_writer(false);
+        let completed = (0..2)
+            .map(|part_idx| PartId {
+                content_id: part_idx.to_string(),
+                part_idx,
+            })
+            .collect();
+        let manifest = ChecksumManifest::new(Path::from("file"))
+            .with_parts(parts)
+            .with_object(object);
+        let mut writer = writer
+            .with_checksum_manifest(manifest)
+            .resume_from(completed, 20)
+            .unwrap();
+        writer.write_all(&data[20..]).await.unwrap();
+        writer.shutdown().await.unwrap();
+        assert_eq!(counts.completed.load(Ordering::SeqCst), 1);
+    }
+
+    /// Tracks the maximum total size of parts being uploaded concurrently
+    #[derive(Debug, Default)]
+    struct InFlight {
+        current: AtomicUsize,
+        max: AtomicUsize,
+    }
+
+    struct TrackedUpload(Arc<InFlight>);
+
+    #[async_trait]
+    impl PutPart for TrackedUpload {
+        async fn put_part(&self, buf: Vec<u8>, part_idx: usize) -> Result<PartId> {
+            let current = self.0.current.fetch_add(buf.len(), Ordering::SeqCst) + buf.len();
+            self.0.max.fetch_max(current, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(1)).await;
+            self.0.current.fetch_sub(buf.len(), Ordering::SeqCst);
+            Ok(PartId {
+                content_id: part_idx.to_string(),
+                part_idx,
+            })
+        }
+
+        async fn complete(&self, _completed_parts: Vec<PartId>) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_upload_limiter() {
+        let part_size = 2048;
+        let limiter = UploadLimiter::new(3 * part_size);
+        let in_flight = Arc::new(InFlight::default());
+
+        let uploads = (0..8).map(|_| {
+            let upload = TrackedUpload(Arc::clone(&in_flight));
+            let mut writer = WriteMultiPart::new(upload, 4)
+                .with_part_size(part_size)
+                .with_upload_limiter(limiter.clone());
+            async move {
+                for _ in 0..10 {
+                    writer.write_all(&[0; 1000]).await.unwrap();
+                }
+                writer.shutdown().await.unwrap();
+            }
+        });
+        futures::future::join_all(uploads).await;
+
+        let max = in_flight.max.load(Ordering::SeqCst);
+        assert!(max <= 3 * part_size, "{max}");
+        assert!(max >= part_size, "{max}");
+        assert_eq!(in_flight.current.load(Ordering::SeqCst), 0);
+        // All reservations are released
+        assert_eq!(limiter.available_bytes(), limiter.max_bytes());
+
+        // Parts larger than the limit are uploaded one at a time
+        let limiter = UploadLimiter::new(1024);
+        let in_flight = Arc::new(InFlight::default());
+        let upload = TrackedUpload(Arc::clone(&in_flight));
+        let mut writer = WriteMultiPart::new(upload, 4)
+            .with_part_size(part_size)
+            .with_upload_limiter(limiter.clone());
+        writer.write_all(&[0; 10_000]).await.unwrap();
+        writer.shutdown().await.unwrap();
+        assert_eq!(in_flight.max.load(Ordering::SeqCst), part_size);
+        assert_eq!(limiter.available_bytes(), 1024);
+    }
+
+    #[tokio::test]
+    async fn test_part_size_scaling() {
+        let (counts, writer) = mock_writer(false);
+        // Part size doubles every 2 parts
+        let mut writer = writer.with_part_size_scaling(20, usize::MAX);
+        writer.write_all(&[0; 100]).await.unwrap();
+        writer.shutdown().await.unwrap();
+
+        let mut sizes = counts.sizes.lock().clone();
+        sizes.sort_unstable();
+        let sizes: Vec<_> = sizes.into_iter().map(|(_, size)| size).collect();
+        assert_eq!(sizes, vec![10, 10, 20, 20, 40]);
+        assert_eq!(counts.completed.load(Ordering::SeqCst), 1);
+
+        // Scaling is capped at 512 times the initial part size
+        let (_, writer) = mock_writer(false);
+        let mut writer = writer.with_part_size_scaling(10, usize::MAX);
+        writer.current_part_idx = 20;
+        assert_eq!(writer.current_part_size(), 5120);
+
+        // And never exceeds the maximum part size
+        let (counts, writer) = mock_writer(false);
+        let mut writer = writer.with_part_size_scaling(10, 25);
+        for part_idx in 0..100 {
+            writer.current_part_idx = part_idx;
+            assert!(writer.current_part_size() <= 25, "{part_idx}");
+        }
+        writer.current_part_idx = 0;
+        writer.write_all(&[0; 100]).await.unwrap();
+        writer.shutdown().await.unwrap();
+        let mut sizes = counts.sizes.lock().clone();
+        sizes.sort_unstable();
+        let sizes: Vec<_> = sizes.into_iter().map(|(_, size)| size).collect();
+        assert_eq!(sizes, vec![10, 20, 25, 25, 20]);
+    }
+
+    #[test]
+    fn test_validate_parts() {
+        let parts = |idx: &[usize]| -> Vec<PartId> {
+            idx.iter()
+                .map(|&part_idx| PartId {
+                    content_id: "etag".to_string(),
+                    part_idx,
+                })
+                .collect()
+        };
+
+        validate_parts("test", &[]).unwrap();
+        validate_parts("test", &parts(&[0, 1, 2])).unwrap();
+
+        let err = validate_parts("test", &parts(&[0, 1, 1])).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("duplicate multipart part 1 at position 2"),
+            "{err}"
+        );
+
+        let err = validate_parts("test", &parts(&[1, 0])).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("found part 0 after part 1 at position 1"),
+            "{err}"
+        );
+
+        // Gaps are permitted
+        validate_parts("test", &parts(&[0, 2, 5])).unwrap();
+        validate_parts("test", &parts(&[3])).unwrap();
+    }
 }
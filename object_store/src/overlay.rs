@@ -0,0 +1,380 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! An object store that overlays one store on top of another
+
+use std::collections::{BTreeSet, HashSet};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::stream::BoxStream;
+use futures::{StreamExt, TryStreamExt};
+use tokio::io::AsyncWrite;
+
+use crate::path::{Path, DELIMITER};
+use crate::{
+    Error, GetOptions, GetResult, ListResult, MultipartId, ObjectMeta, ObjectStore, PutOptions,
+    PutResult, Result,
+};
+
+/// The filename prefix of a whiteout marker
+const WHITEOUT_PREFIX: &str = ".wh.";
+
+/// Returns the location of the whiteout marker for `location`
+fn whiteout(location: &Path) -> Path {
+    let raw = location.as_ref();
+    let marker = match raw.rsplit_once(DELIMITER) {
+        Some((parent, filename)) => format!("{parent}{DELIMITER}{WHITEOUT_PREFIX}{filename}"),
+        None => format!("{WHITEOUT_PREFIX}{raw}"),
+    };
+    Path::parse(marker).expect("valid path")
+}
+
+/// If `location` is a whiteout marker, returns the location it hides
+fn whiteout_target(location: &Path) -> Option<Path> {
+    let filename = location.filename()?.strip_prefix(WHITEOUT_PREFIX)?;
+    let raw = location.as_ref();
+    let target = match raw.rsplit_once(DELIMITER) {
+        Some((parent, _)) => format!("{parent}{DELIMITER}{filename}"),
+        None => filename.to_string(),
+    };
+    Path::parse(target).ok()
+}
+
+/// Store wrapper that overlays a writable `overlay` store on top of a `base` store
+///
+/// This provides a union mount, useful for copy-on-write datasets or caching, where:
+///
+/// * Reads check `overlay` first, falling back to `base` if not found
+/// * Writes, including copies, go only to `overlay`, shadowing any object in `base`
+/// * Deletes remove the object from `overlay` and write a whiteout marker, an empty object
+///   alongside it with the filename prefixed by `.wh.`, hiding any object in `base`
+/// * Lists merge both stores, with objects in `overlay` shadowing those at the same
+///   location in `base`, and whiteout markers hiding objects in `base`. Each location is
+///   returned at most once, and whiteout markers themselves are never returned
+///
+/// `base` is never modified. Object names starting with `.wh.` are reserved for
+/// whiteout markers and should not otherwise be used.
+///
+/// Conditional requests, such as [`PutMode::Create`], are evaluated only against the
+/// store that would serve the request, and [`ObjectStore::copy_if_not_exists`] is not
+/// atomic. Additionally [`ObjectStore::list_with_delimiter`] may return a common prefix
+/// for which all objects in `base` have been hidden by whiteouts.
+///
+/// [`PutMode::Create`]: crate::PutMode::Create
+#[derive(Debug, Clone)]
+pub struct OverlayStore<O: ObjectStore, B: ObjectStore> {
+    overlay: O,
+    base: B,
+}
+
+impl<O: ObjectStore, B: ObjectStore> std::fmt::Display for OverlayStore<O, B> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "OverlayStore({}, {})", self.overlay, self.base)
+    }
+}
+
+impl<O: ObjectStore, B: ObjectStore> OverlayStore<O, B> {
+    /// Create a new [`OverlayStore`] with `overlay` on top of `base`
+    pub fn new(overlay: O, base: B) -> Self {
+        Self { overlay, base }
+    }
+
+    /// Returns the overlay store
+    pub fn overlay(&self) -> &O {
+        &self.overlay
+    }
+
+    /// Returns the base store
+    pub fn base(&self) -> &B {
+        &self.base
+    }
+
+    /// Returns true if `location` is hidden by a whiteout marker
+    async fn is_whiteout(&self, location: &Path) -> Result<bool> {
+        match self.overlay.head(&whiteout(location)).await {
+            Ok(_) => Ok(true),
+            Err(Error::NotFound { .. }) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Removes any whiteout marker for `location`
+    async fn remove_whiteout(&self, location: &Path) -> Result<()> {
+        match self.overlay.delete(&whiteout(location)).await {
+            Ok(()) | Err(Error::NotFound { .. }) => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn not_found(location: &Path) -> Error {
+        Error::NotFound {
+            path: location.to_string(),
+            source: "hidden by whiteout".into(),
+        }
+    }
+}
+
+#[async_trait]
+impl<O: ObjectStore, B: ObjectStore> ObjectStore for OverlayStore<O, B> {
+    async fn put_opts(&self, location: &Path, bytes: Bytes, opts: PutOptions) -> Result<PutResult> {
+        let result = self.overlay.put_opts(location, bytes, opts).await?;
+        self.remove_whiteout(location).await?;
+        Ok(result)
+    }
+
+    /// Note: any whiteout for `location` is removed when the upload is created,
+    /// exposing any object in `base` until the upload completes
+    async fn put_multipart(
+        &self,
+        location: &Path,
+    ) -> Result<(MultipartId, Box<dyn AsyncWrite + Unpin + Send>)> {
+        let upload = self.overlay.put_multipart(location).await?;
+        self.remove_whiteout(location).await?;
+        Ok(upload)
+    }
+
+    async fn abort_multipart(&self, location: &Path, multipart_id: &MultipartId) -> Result<()> {
+        self.overlay.abort_multipart(location, multipart_id).await
+    }
+
+    async fn get_opts(&self, location: &Path, options: GetOptions) -> Result<GetResult> {
+        match self.overlay.get_opts(location, options.clone()).await {
+            Err(Error::NotFound { .. }) => {}
+            r => return r,
+        }
+        if self.is_whiteout(location).await? {
+            return Err(Self::not_found(location));
+        }
+        self.base.get_opts(location, options).await
+    }
+
+    async fn delete(&self, location: &Path) -> Result<()> {
+        // Write the whiteout first to avoid briefly exposing the object in base
+        self.overlay.put(&whiteout(location), Bytes::new()).await?;
+        match self.overlay.delete(location).await {
+            Ok(()) | Err(Error::NotFound { .. }) => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'_, Result<ObjectMeta>> {
+        let prefix = prefix.cloned();
+        futures::stream::once(async move {
+            let overlay: Vec<_> = self.overlay.list(prefix.as_ref()).try_collect().await?;
+
+            // Locations in base shadowed by an object or a whiteout in overlay
+            let mut hidden = HashSet::with_capacity(overlay.len());
+            let mut objects = Vec::with_capacity(overlay.len());
+            for meta in overlay {
+                match whiteout_target(&meta.location) {
+                    Some(target) => hidden.insert(target),
+                    None => {
+                        hidden.insert(meta.location.clone());
+                        objects.push(meta);
+                        continue;
+                    }
+                };
+            }
+
+            let base = self
+                .base
+                .list(prefix.as_ref())
+                .try_filter(move |meta| futures::future::ready(!hidden.contains(&meta.location)));
+
+            Ok::<_, Error>(futures::stream::iter(objects.into_iter().map(Ok)).chain(base))
+        })
+        .try_flatten()
+        .boxed()
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
+        let (overlay, base) = futures::try_join!(
+            self.overlay.list_with_delimiter(prefix),
+            self.base.list_with_delimiter(prefix)
+        )?;
+
+        let mut hidden = HashSet::with_capacity(overlay.objects.len());
+        let mut objects = Vec::with_capacity(overlay.objects.len() + base.objects.len());
+        for meta in overlay.objects {
+            match whiteout_target(&meta.location) {
+                Some(target) => hidden.insert(target),
+                None => {
+                    hidden.insert(meta.location.clone());
+                    objects.push(meta);
+                    continue;
+                }
+            };
+        }
+        objects.extend(
+            base.objects
+                .into_iter()
+                .filter(|meta| !hidden.contains(&meta.location)),
+        );
+
+        let common_prefixes: BTreeSet<_> = overlay
+            .common_prefixes
+            .into_iter()
+            .chain(base.common_prefixes)
+            .collect();
+
+        Ok(ListResult {
+            common_prefixes: common_prefixes.into_iter().collect(),
+            objects,
+        })
+    }
+
+    async fn copy(&self, from: &Path, to: &Path) -> Result<()> {
+        match self.overlay.head(from).await {
+            Ok(_) => self.overlay.copy(from, to).await?,
+            Err(Error::NotFound { .. }) => {
+                if self.is_whiteout(from).await? {
+                    return Err(Self::not_found(from));
+                }
+                let bytes = self.base.get(from).await?.bytes().await?;
+                self.overlay.put(to, bytes).await?;
+            }
+            Err(e) => return Err(e),
+        }
+        self.remove_whiteout(to).await
+    }
+
+    async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        match self.head(to).await {
+            Ok(_) => Err(Error::AlreadyExists {
+                path: to.to_string(),
+                source: "object already exists".into(),
+            }),
+            Err(Error::NotFound { .. }) => self.copy(from, to).await,
+            Err(e) => Err(e),
+        }
+    }
+
+    fn validate_path(&self, location: &Path) -> Result<()> {
+        self.overlay.validate_path(location)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::InMemory;
+    use crate::test_util::flatten_list_stream;
+    use crate::tests::*;
+
+    #[tokio::test]
+    async fn overlay_test() {
+        let integration = OverlayStore::new(InMemory::new(), InMemory::new());
+
+        put_get_delete_list(&integration).await;
+        get_opts(&integration).await;
+        list_uses_directories_correctly(&integration).await;
+        list_with_delimiter(&integration).await;
+        rename_and_copy(&integration).await;
+        copy_if_not_exists(&integration).await;
+        stream_get(&integration).await;
+    }
+
+    #[test]
+    fn test_whiteout() {
+        let cases = [
+            ("a", ".wh.a"),
+            ("a/b", "a/.wh.b"),
+            ("a/b/c.parquet", "a/b/.wh.c.parquet"),
+        ];
+        for (path, expected) in cases {
+            let path = Path::from(path);
+            let marker = whiteout(&path);
+            assert_eq!(marker.as_ref(), expected);
+            assert_eq!(whiteout_target(&marker), Some(path.clone()));
+            assert_eq!(whiteout_target(&path), None);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_overlay_semantics() {
+        let base = InMemory::new();
+        for path in ["a/1", "a/2", "a/3", "b/1"] {
+            base.put(&Path::from(path), "base".into()).await.unwrap();
+        }
+        let store = OverlayStore::new(InMemory::new(), base);
+
+        let read = |path: &'static str| {
+            let store = &store;
+            async move {
+                let r = store.get(&Path::from(path)).await?;
+                r.bytes().await
+            }
+        };
+
+        // Writes shadow base
+        store
+            .put(&Path::from("a/2"), "overlay".into())
+            .await
+            .unwrap();
+        store
+            .put(&Path::from("a/4"), "overlay".into())
+            .await
+            .unwrap();
+        assert_eq!(read("a/1").await.unwrap(), "base");
+        assert_eq!(read("a/2").await.unwrap(), "overlay");
+        assert_eq!(read("a/4").await.unwrap(), "overlay");
+
+        // Deletes hide base without modifying it
+        store.delete(&Path::from("a/3")).await.unwrap();
+        store.delete(&Path::from("a/4")).await.unwrap();
+        let err = read("a/3").await.unwrap_err();
+        assert!(matches!(err, Error::NotFound { .. }), "{err}");
+        let err = read("a/4").await.unwrap_err();
+        assert!(matches!(err, Error::NotFound { .. }), "{err}");
+        store.base().head(&Path::from("a/3")).await.unwrap();
+
+        let mut listed = flatten_list_stream(&store, None).await.unwrap();
+        listed.sort_unstable();
+        let expected: Vec<_> = ["a/1", "a/2", "b/1"].into_iter().map(Path::from).collect();
+        assert_eq!(listed, expected);
+
+        let prefix = Path::from("a");
+        let list = store.list_with_delimiter(Some(&prefix)).await.unwrap();
+        let mut objects: Vec<_> = list.objects.into_iter().map(|m| m.location).collect();
+        objects.sort_unstable();
+        assert_eq!(objects, &expected[..2]);
+
+        let list = store.list_with_delimiter(None).await.unwrap();
+        assert_eq!(list.common_prefixes, vec![Path::from("a"), Path::from("b")]);
+
+        // Writing removes the whiteout
+        store.put(&Path::from("a/3"), "new".into()).await.unwrap();
+        assert_eq!(read("a/3").await.unwrap(), "new");
+
+        // Copies from base land in overlay
+        store
+            .copy(&Path::from("b/1"), &Path::from("b/2"))
+            .await
+            .unwrap();
+        assert_eq!(read("b/2").await.unwrap(), "base");
+        store.overlay().head(&Path::from("b/2")).await.unwrap();
+
+        // Copying a deleted object fails
+        store.delete(&Path::from("b/1")).await.unwrap();
+        let err = store
+            .copy(&Path::from("b/1"), &Path::from("b/3"))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::NotFound { .. }), "{err}");
+    }
+}
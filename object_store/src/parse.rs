@@ -81,9 +81,11 @@ impl ObjectStoreScheme {
             }
             ("http", Some(_)) => (Self::Http, url.path()),
             ("https", Some(host)) => {
-                if host.ends_with("dfs.core.windows.net")
-                    || host.ends_with("blob.core.windows.net")
-                    || host.ends_with("dfs.fabric.microsoft.com")
+                if host.ends_with("dfs.core.windows.net") || host.ends_with("blob.core.windows.net")
+                {
+                    // The first path segment is the container
+                    (Self::MicrosoftAzure, strip_bucket().unwrap_or_default())
+                } else if host.ends_with("dfs.fabric.microsoft.com")
                     || host.ends_with("blob.fabric.microsoft.com")
                 {
                     (Self::MicrosoftAzure, url.path())
@@ -121,6 +123,20 @@ macro_rules! builder_opts {
 
 /// Create an [`ObjectStore`] based on the provided `url`
 ///
+/// The store is selected based on the scheme, and for `https` URLs the host, of `url`:
+///
+/// - `file:///<path>`: [`LocalFileSystem`]
+/// - `memory:///<path>`: [`InMemory`]
+/// - `s3://<bucket>/<path>`, `s3a://<bucket>/<path>`, `https://s3.<region>.amazonaws.com/<bucket>/<path>`,
+///   `https://<bucket>.s3.<region>.amazonaws.com/<path>`: [`AmazonS3`](crate::aws::AmazonS3)
+/// - `gs://<bucket>/<path>`: [`GoogleCloudStorage`](crate::gcp::GoogleCloudStorage)
+/// - `az://<container>/<path>`, `abfs[s]://<container>@<account>.dfs.core.windows.net/<path>`,
+///   `https://<account>.blob.core.windows.net/<container>/<path>`: [`MicrosoftAzure`](crate::azure::MicrosoftAzure)
+/// - any other `http` or `https` URL: [`HttpStore`](crate::http::HttpStore)
+///
+/// An error is returned if the scheme is not recognised, or the feature for the corresponding
+/// store is not enabled
+///
 /// Returns
 /// - An [`ObjectStore`] of the corresponding type
 /// - The [`Path`] into the [`ObjectStore`] of the addressed resource
@@ -168,12 +184,7 @@ where
             builder_opts!(crate::http::HttpBuilder, url, _options)
         }
         #[cfg(not(all(feature = "aws", feature = "azure", feature = "gcp", feature = "http")))]
-        s => {
-            return Err(super::Error::Generic {
-                store: "parse_url",
-                source: format!("feature for {s:?} not enabled").into(),
-            })
-        }
+        scheme => return Err(Error::NotEnabled { scheme }.into()),
     };
 
     Ok((store, path))
@@ -232,6 +243,22 @@ mod tests {
                 "https://account.blob.core.windows.net",
                 (ObjectStoreScheme::MicrosoftAzure, ""),
             ),
+            (
+                "https://account.blob.core.windows.net/container",
+                (ObjectStoreScheme::MicrosoftAzure, ""),
+            ),
+            (
+                "https://account.blob.core.windows.net/container/path/file",
+                (ObjectStoreScheme::MicrosoftAzure, "path/file"),
+            ),
+            (
+                "https://account.dfs.core.windows.net/container/path",
+                (ObjectStoreScheme::MicrosoftAzure, "path"),
+            ),
+            (
+                "https://bucket.s3.region.amazonaws.com/path/file",
+                (ObjectStoreScheme::AmazonS3, "path/file"),
+            ),
             (
                 "gs://bucket/path",
                 (ObjectStoreScheme::GoogleCloudStorage, "path"),
@@ -299,6 +326,16 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_unrecognised_scheme() {
+        let url = Url::parse("ftp://host/path").unwrap();
+        let err = parse_url(&url).unwrap_err().to_string();
+        assert_eq!(
+            err,
+            "Generic URL error: Unable to recognise URL \"ftp://host/path\""
+        );
+    }
+
     #[test]
     fn test_url_spaces() {
         let url = Url::parse("file:///my file with spaces").unwrap();
@@ -307,6 +344,49 @@ mod tests {
         assert_eq!(path.as_ref(), "my file with spaces");
     }
 
+    #[test]
+    fn test_object_url() {
+        let location = Path::from_iter(["foo bar", "baz#?%"]);
+        let mut stores: Vec<Box<dyn ObjectStore>> = vec![Box::new(crate::memory::InMemory::new())];
+
+        #[cfg(not(target_arch = "wasm32"))]
+        stores.push(Box::new(crate::local::LocalFileSystem::new()));
+
+        #[cfg(feature = "aws")]
+        stores.push(Box::new(
+            crate::aws::AmazonS3Builder::new()
+                .with_bucket_name("bucket")
+                .with_region("us-east-1")
+                .build()
+                .unwrap(),
+        ));
+
+        #[cfg(feature = "gcp")]
+        stores.push(Box::new(
+            crate::gcp::GoogleCloudStorageBuilder::new()
+                .with_bucket_name("bucket")
+                .with_service_account_key(r#"{"private_key": "private_key", "private_key_id": "private_key_id", "client_email":"client_email", "disable_oauth":true}"#)
+                .build()
+                .unwrap(),
+        ));
+
+        #[cfg(feature = "azure")]
+        stores.push(Box::new(
+            crate::azure::MicrosoftAzureBuilder::new()
+                .with_account("account")
+                .with_container_name("container")
+                .with_access_key("")
+                .build()
+                .unwrap(),
+        ));
+
+        for store in stores {
+            let url = store.object_url(&location).unwrap();
+            let (_, path) = parse_url(&url).unwrap();
+            assert_eq!(path, location, "{url}");
+        }
+    }
+
     #[tokio::test]
     #[cfg(feature = "http")]
     async fn test_url_http() {
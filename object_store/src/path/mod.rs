@@ -20,6 +20,7 @@
 use itertools::Itertools;
 use percent_encoding::percent_decode;
 use snafu::{ensure, ResultExt, Snafu};
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt::Formatter;
 #[cfg(not(target_arch = "wasm32"))]
 use url::Url;
@@ -61,6 +62,19 @@ pub enum Error {
 
     #[snafu(display("Path {} does not start with prefix {}", path, prefix))]
     PrefixMismatch { path: String, prefix: String },
+
+    #[snafu(display("Path \"{}\" contained leading delimiter", path))]
+    LeadingDelimiter { path: String },
+
+    #[snafu(display("Path \"{}\" contained trailing delimiter", path))]
+    TrailingDelimiter { path: String },
+
+    #[snafu(display("Path \"{}\" is not supported by {}: {}", path, store, reason))]
+    Unsupported {
+        path: String,
+        store: &'static str,
+        reason: String,
+    },
 }
 
 /// A parsed path representation that can be safely written to object storage
@@ -123,6 +137,39 @@ pub enum Error {
 /// Path::parse("\x00").unwrap_err(); // ASCII control characters are disallowed
 /// ```
 ///
+/// [`Path::parse_strict`] additionally rejects leading or trailing `/`, instead of
+/// silently stripping them.
+///
+/// ```
+/// # use object_store::path::Path;
+/// assert_eq!(Path::parse_strict("foo/bar").unwrap().as_ref(), "foo/bar");
+/// Path::parse_strict("/foo/bar").unwrap_err(); // Leading delimiters are disallowed
+/// Path::parse_strict("foo/bar/").unwrap_err(); // Trailing delimiters are disallowed
+/// ```
+///
+/// Note: a [`Path`] that is valid may still be rejected by a given store, see
+/// [`ObjectStore::validate_path`](crate::ObjectStore::validate_path)
+///
+/// # Case Sensitivity
+///
+/// [`Path`] comparisons are case-sensitive, matching the semantics of S3, GCS and Azure
+/// Blob Storage. However, some backends, such as [`LocalFileSystem`] on case-insensitive
+/// filesystems or certain emulators, will treat paths differing only in case as the same
+/// object. Code written against a case-sensitive store may therefore see writes unexpectedly
+/// overwrite one another when run against such a backend.
+///
+/// [`Path::eq_ignore_case`] can be used to compare paths as such a backend would, and
+/// [`case_collisions`] to detect paths within a listing that would collide.
+///
+/// ```
+/// # use object_store::path::{case_collisions, Path};
+/// assert!(Path::from("foo/BAR").eq_ignore_case(&Path::from("FOO/bar")));
+///
+/// let paths = [Path::from("a/b"), Path::from("a/B"), Path::from("a/c")];
+/// let collisions = case_collisions(&paths);
+/// assert_eq!(collisions, vec![vec![Path::from("a/B"), Path::from("a/b")]]);
+/// ```
+///
 /// [RFC 1738]: https://www.ietf.org/rfc/rfc1738.txt
 /// [`LocalFileSystem`]: crate::local::LocalFileSystem
 #[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Ord, PartialOrd)]
@@ -156,6 +203,18 @@ impl Path {
         })
     }
 
+    /// Parse a string as a [`Path`], returning a [`Error`] if invalid,
+    /// as defined on the docstring for [`Path`]
+    ///
+    /// Unlike [`Path::parse`] this will return an error if the path contains a
+    /// leading `/` or trailing `/`
+    pub fn parse_strict(path: impl AsRef<str>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        ensure!(!path.starts_with(DELIMITER), LeadingDelimiterSnafu { path });
+        ensure!(!path.ends_with(DELIMITER), TrailingDelimiterSnafu { path });
+        Self::parse(path)
+    }
+
     #[cfg(not(target_arch = "wasm32"))]
     /// Convert a filesystem path to a [`Path`] relative to the filesystem root
     ///
@@ -276,6 +335,34 @@ impl Path {
 
         Self { raw }
     }
+
+    /// Returns true if this [`Path`] is equal to `other` ignoring case
+    ///
+    /// See [Case Sensitivity](Path#case-sensitivity)
+    pub fn eq_ignore_case(&self, other: &Self) -> bool {
+        self.raw == other.raw || self.raw.to_lowercase() == other.raw.to_lowercase()
+    }
+}
+
+/// Returns the groups of distinct paths within `paths` that are equal ignoring case
+///
+/// Each group, and the paths within it, are returned in lexicographic order.
+///
+/// See [Case Sensitivity](Path#case-sensitivity)
+pub fn case_collisions<'a>(paths: impl IntoIterator<Item = &'a Path>) -> Vec<Vec<Path>> {
+    let mut groups: BTreeMap<String, BTreeSet<&Path>> = BTreeMap::new();
+    for path in paths {
+        groups
+            .entry(path.raw.to_lowercase())
+            .or_default()
+            .insert(path);
+    }
+
+    groups
+        .into_values()
+        .filter(|g| g.len() > 1)
+        .map(|g| g.into_iter().cloned().collect())
+        .collect()
 }
 
 impl AsRef<str> for Path {
@@ -366,6 +453,37 @@ mod tests {
         assert!(matches!(err, Error::EmptySegment { .. }));
     }
 
+    #[test]
+    fn test_parse_strict() {
+        assert_eq!(Path::parse_strict("").unwrap().as_ref(), "");
+        assert_eq!(Path::parse_strict("foo").unwrap().as_ref(), "foo");
+        assert_eq!(
+            Path::parse_strict("foo/bar%2F.baz").unwrap().as_ref(),
+            "foo/bar%2F.baz"
+        );
+
+        let cases = [
+            ("/", "Path \"/\" contained leading delimiter"),
+            ("/foo", "Path \"/foo\" contained leading delimiter"),
+            ("foo/", "Path \"foo/\" contained trailing delimiter"),
+            ("foo//bar", "Path \"foo//bar\" contained empty path segment"),
+            (
+                "foo/../bar",
+                "Encountered illegal character sequence \"..\"",
+            ),
+            ("foo/./bar", "Encountered illegal character sequence \".\""),
+            (
+                "foo/\x00",
+                "Encountered illegal character sequence \"\x00\"",
+            ),
+        ];
+
+        for (path, expected) in cases {
+            let err = Path::parse_strict(path).unwrap_err().to_string();
+            assert!(err.contains(expected), "{path}: {err}");
+        }
+    }
+
     #[test]
     fn convert_raw_before_partial_eq() {
         // dir and file_name
@@ -576,4 +694,34 @@ mod tests {
         assert_eq!(c.extension(), None);
         assert_eq!(d.extension(), Some("qux"));
     }
+
+    #[test]
+    fn case_insensitive() {
+        let a = Path::from("foo/Bar.parquet");
+        assert!(a.eq_ignore_case(&Path::from("FOO/bar.PARQUET")));
+        assert!(a.eq_ignore_case(&a));
+        assert!(!a.eq_ignore_case(&Path::from("foo/bar.csv")));
+        let (upper, lower) = (Path::parse("ÄB").unwrap(), Path::parse("äb").unwrap());
+        assert!(upper.eq_ignore_case(&lower));
+
+        let paths = [
+            Path::from("a/b"),
+            Path::from("A/b"),
+            Path::from("a/c"),
+            Path::from("a/b"),
+            Path::from("x/Y"),
+            Path::from("x/y"),
+            Path::from("X/y"),
+        ];
+        let collisions = case_collisions(&paths);
+        assert_eq!(
+            collisions,
+            vec![
+                vec![Path::from("A/b"), Path::from("a/b")],
+                vec![Path::from("X/y"), Path::from("x/Y"), Path::from("x/y")],
+            ]
+        );
+
+        assert!(case_collisions(&[Path::from("a"), Path::from("b")]).is_empty());
+    }
 }
@@ -20,11 +20,12 @@ use bytes::Bytes;
 use futures::{stream::BoxStream, StreamExt, TryStreamExt};
 use std::ops::Range;
 use tokio::io::AsyncWrite;
+use url::Url;
 
 use crate::path::Path;
 use crate::{
-    GetOptions, GetResult, ListResult, MultipartId, ObjectMeta, ObjectStore, PutOptions, PutResult,
-    Result,
+    Acl, CopyOptions, DeleteOptions, GetOptions, GetResult, ListPage, ListResult, MultipartId,
+    ObjectMeta, ObjectStore, PutOptions, PutResult, Result,
 };
 
 #[doc(hidden)]
@@ -75,6 +76,7 @@ impl<T: ObjectStore> PrefixStore<T> {
             location: self.strip_prefix(meta.location),
             e_tag: meta.e_tag,
             version: None,
+            checksum: meta.checksum,
         }
     }
 }
@@ -134,6 +136,11 @@ impl<T: ObjectStore> ObjectStore for PrefixStore<T> {
         self.inner.delete(&full_path).await
     }
 
+    async fn delete_opts(&self, location: &Path, opts: DeleteOptions) -> Result<()> {
+        let full_path = self.full_path(location);
+        self.inner.delete_opts(&full_path, opts).await
+    }
+
     fn list(&self, prefix: Option<&Path>) -> BoxStream<'_, Result<ObjectMeta>> {
         let prefix = self.full_path(prefix.unwrap_or(&Path::default()));
         let s = self.inner.list(Some(&prefix));
@@ -151,6 +158,34 @@ impl<T: ObjectStore> ObjectStore for PrefixStore<T> {
         s.map_ok(|meta| self.strip_meta(meta)).boxed()
     }
 
+    fn list_range(&self, start: &Path, end: &Path) -> BoxStream<'_, Result<ObjectMeta>> {
+        let (start, end) = (self.full_path(start), self.full_path(end));
+        // The range of full paths may include locations outside the prefix
+        self.inner
+            .list_range(&start, &end)
+            .try_filter(|meta| futures::future::ready(meta.location.prefix_matches(&self.prefix)))
+            .map_ok(|meta| self.strip_meta(meta))
+            .boxed()
+    }
+
+    fn list_from_token(
+        &self,
+        prefix: Option<&Path>,
+        token: Option<&str>,
+    ) -> BoxStream<'_, Result<ListPage>> {
+        let prefix = self.full_path(prefix.unwrap_or(&Path::default()));
+        let s = self.inner.list_from_token(Some(&prefix), token);
+        s.map_ok(|page| ListPage {
+            objects: page
+                .objects
+                .into_iter()
+                .map(|meta| self.strip_meta(meta))
+                .collect(),
+            next_token: page.next_token,
+        })
+        .boxed()
+    }
+
     async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
         let prefix = self.full_path(prefix.unwrap_or(&Path::default()));
         self.inner
@@ -170,18 +205,55 @@ impl<T: ObjectStore> ObjectStore for PrefixStore<T> {
             })
     }
 
+    fn list_with_delimiter_stream(
+        &self,
+        prefix: Option<&Path>,
+    ) -> BoxStream<'_, Result<ListResult>> {
+        let prefix = self.full_path(prefix.unwrap_or(&Path::default()));
+        self.inner
+            .list_with_delimiter_stream(Some(&prefix))
+            .map_ok(|lst| ListResult {
+                common_prefixes: lst
+                    .common_prefixes
+                    .into_iter()
+                    .map(|p| self.strip_prefix(p))
+                    .collect(),
+                objects: lst
+                    .objects
+                    .into_iter()
+                    .map(|meta| self.strip_meta(meta))
+                    .collect(),
+            })
+            .boxed()
+    }
+
     async fn copy(&self, from: &Path, to: &Path) -> Result<()> {
         let full_from = self.full_path(from);
         let full_to = self.full_path(to);
         self.inner.copy(&full_from, &full_to).await
     }
 
+    async fn copy_opts(&self, from: &Path, to: &Path, opts: CopyOptions) -> Result<()> {
+        let full_from = self.full_path(from);
+        let full_to = self.full_path(to);
+        self.inner.copy_opts(&full_from, &full_to, opts).await
+    }
+
     async fn rename(&self, from: &Path, to: &Path) -> Result<()> {
         let full_from = self.full_path(from);
         let full_to = self.full_path(to);
         self.inner.rename(&full_from, &full_to).await
     }
 
+    async fn probe(&self) -> Result<()> {
+        self.inner.probe().await
+    }
+
+    async fn get_acl(&self, location: &Path) -> Result<Acl> {
+        let full_path = self.full_path(location);
+        self.inner.get_acl(&full_path).await
+    }
+
     async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
         let full_from = self.full_path(from);
         let full_to = self.full_path(to);
@@ -193,6 +265,16 @@ impl<T: ObjectStore> ObjectStore for PrefixStore<T> {
         let full_to = self.full_path(to);
         self.inner.rename_if_not_exists(&full_from, &full_to).await
     }
+
+    fn object_url(&self, location: &Path) -> Result<Url> {
+        let full_path = self.full_path(location);
+        self.inner.object_url(&full_path)
+    }
+
+    fn validate_path(&self, location: &Path) -> Result<()> {
+        let full_path = self.full_path(location);
+        self.inner.validate_path(&full_path)
+    }
 }
 
 #[cfg(test)]
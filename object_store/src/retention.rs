@@ -0,0 +1,59 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use chrono::{DateTime, Utc};
+
+/// The mode of a [`Retention`] configuration
+///
+/// <https://docs.aws.amazon.com/AmazonS3/latest/userguide/object-lock.html#object-lock-retention-modes>
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RetentionMode {
+    /// The object can't be overwritten or deleted until the retention period expires,
+    /// unless the caller has special permissions to bypass or alter the retention
+    ///
+    /// This is the `GOVERNANCE` mode in S3 and an `Unlocked` retention in GCS
+    #[default]
+    Governance,
+    /// The object can't be overwritten or deleted by any user, including the
+    /// root account, until the retention period expires, nor can the retention
+    /// period be shortened
+    ///
+    /// This is the `COMPLIANCE` mode in S3 and a `Locked` retention in GCS
+    Compliance,
+}
+
+/// A write-once-read-many (WORM) retention configuration for an object
+///
+/// Until `retain_until` the object can't be overwritten or deleted, subject
+/// to the [`RetentionMode`]
+///
+/// <https://docs.aws.amazon.com/AmazonS3/latest/userguide/object-lock.html>
+/// <https://cloud.google.com/storage/docs/object-lock>
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Retention {
+    /// The [`RetentionMode`]
+    pub mode: RetentionMode,
+    /// The time until which the object is retained
+    pub retain_until: DateTime<Utc>,
+}
+
+impl Retention {
+    /// Create a new [`Retention`] with the provided `mode` and `retain_until`
+    pub fn new(mode: RetentionMode, retain_until: DateTime<Utc>) -> Self {
+        Self { mode, retain_until }
+    }
+}
@@ -0,0 +1,402 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! An object store that distributes objects across multiple backends
+
+use std::collections::BTreeSet;
+use std::fmt::{Debug, Display, Formatter};
+use std::ops::Range;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::stream::BoxStream;
+use futures::StreamExt;
+use tokio::io::AsyncWrite;
+use url::Url;
+
+use crate::path::Path;
+use crate::{
+    Acl, CopyMode, CopyOptions, DeleteOptions, GetOptions, GetResult, ListResult, MultipartId,
+    ObjectMeta, ObjectStore, PutMode, PutOptions, PutResult, Result,
+};
+
+/// A function mapping a [`Path`] to a hash, used to select a shard
+pub type ShardHasher = Arc<dyn Fn(&Path) -> u64 + Send + Sync>;
+
+/// The default [`ShardHasher`], a 64-bit FNV-1a hash of the path
+///
+/// Unlike [`std::collections::hash_map::DefaultHasher`] this is guaranteed to be
+/// stable across releases, and can therefore be used to locate persisted data
+pub fn fnv1a(location: &Path) -> u64 {
+    location
+        .as_ref()
+        .bytes()
+        .fold(0xcbf29ce484222325, |hash, b| {
+            (hash ^ b as u64).wrapping_mul(0x100000001b3)
+        })
+}
+
+/// Store wrapper that deterministically distributes objects across a list of shards
+///
+/// Each [`Path`] is hashed, and routed to the shard at index `hash % shards.len()`
+/// for all operations. [`ObjectStore::list`] and [`ObjectStore::list_with_delimiter`]
+/// fan out to all shards, merging the results.
+///
+/// The mapping from path to shard depends on both the hash function and the number
+/// and order of the shards, these must therefore not change once data has been
+/// written. Rebalancing data across a different set of shards is not supported.
+///
+/// Copies between paths that route to different shards are performed by reading
+/// the object and writing it to the destination shard.
+///
+/// ```
+/// # use std::sync::Arc;
+/// # use object_store::memory::InMemory;
+/// # use object_store::path::Path;
+/// # use object_store::sharded::ShardedStore;
+/// let store = ShardedStore::new(vec![
+///     Arc::new(InMemory::new()),
+///     Arc::new(InMemory::new()),
+/// ]);
+/// let shard = store.shard_for(&Path::from("foo/bar"));
+/// assert_eq!(shard, store.shard_for(&Path::from("foo/bar")));
+/// ```
+#[derive(Clone)]
+pub struct ShardedStore {
+    shards: Vec<Arc<dyn ObjectStore>>,
+    hasher: ShardHasher,
+}
+
+impl Debug for ShardedStore {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ShardedStore")
+            .field("shards", &self.shards)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Display for ShardedStore {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "ShardedStore(")?;
+        for (idx, shard) in self.shards.iter().enumerate() {
+            if idx != 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{shard}")?;
+        }
+        write!(f, ")")
+    }
+}
+
+impl ShardedStore {
+    /// Create a new [`ShardedStore`] routing paths with [`fnv1a`]
+    ///
+    /// # Panics
+    ///
+    /// Panics if `shards` is empty
+    pub fn new(shards: Vec<Arc<dyn ObjectStore>>) -> Self {
+        Self::new_with_hasher(shards, Arc::new(fnv1a))
+    }
+
+    /// Create a new [`ShardedStore`] routing paths with the provided [`ShardHasher`]
+    ///
+    /// # Panics
+    ///
+    /// Panics if `shards` is empty
+    pub fn new_with_hasher(shards: Vec<Arc<dyn ObjectStore>>, hasher: ShardHasher) -> Self {
+        assert!(
+            !shards.is_empty(),
+            "ShardedStore requires at least one shard"
+        );
+        Self { shards, hasher }
+    }
+
+    /// Returns the shards of this store
+    pub fn shards(&self) -> &[Arc<dyn ObjectStore>] {
+        &self.shards
+    }
+
+    /// Returns the index of the shard `location` is routed to
+    pub fn shard_for(&self, location: &Path) -> usize {
+        ((self.hasher)(location) % self.shards.len() as u64) as usize
+    }
+
+    fn shard(&self, location: &Path) -> &Arc<dyn ObjectStore> {
+        &self.shards[self.shard_for(location)]
+    }
+
+    /// Merges the results of `f` applied to each shard into a single stream
+    fn fan_out<'a, T, F>(&'a self, f: F) -> BoxStream<'a, Result<T>>
+    where
+        T: 'a,
+        F: Fn(&'a Arc<dyn ObjectStore>) -> BoxStream<'a, Result<T>>,
+    {
+        futures::stream::select_all(self.shards.iter().map(f)).boxed()
+    }
+}
+
+#[async_trait]
+impl ObjectStore for ShardedStore {
+    async fn put_opts(&self, location: &Path, bytes: Bytes, opts: PutOptions) -> Result<PutResult> {
+        self.shard(location).put_opts(location, bytes, opts).await
+    }
+
+    async fn put_multipart(
+        &self,
+        location: &Path,
+    ) -> Result<(MultipartId, Box<dyn AsyncWrite + Unpin + Send>)> {
+        self.shard(location).put_multipart(location).await
+    }
+
+    async fn abort_multipart(&self, location: &Path, multipart_id: &MultipartId) -> Result<()> {
+        self.shard(location)
+            .abort_multipart(location, multipart_id)
+            .await
+    }
+
+    async fn get_opts(&self, location: &Path, options: GetOptions) -> Result<GetResult> {
+        self.shard(location).get_opts(location, options).await
+    }
+
+    async fn get_range(&self, location: &Path, range: Range<usize>) -> Result<Bytes> {
+        self.shard(location).get_range(location, range).await
+    }
+
+    async fn head(&self, location: &Path) -> Result<ObjectMeta> {
+        self.shard(location).head(location).await
+    }
+
+    async fn delete(&self, location: &Path) -> Result<()> {
+        self.shard(location).delete(location).await
+    }
+
+    async fn delete_opts(&self, location: &Path, opts: DeleteOptions) -> Result<()> {
+        self.shard(location).delete_opts(location, opts).await
+    }
+
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'_, Result<ObjectMeta>> {
+        let prefix = prefix.cloned();
+        self.fan_out(move |shard| shard.list(prefix.as_ref()))
+    }
+
+    fn list_with_offset(
+        &self,
+        prefix: Option<&Path>,
+        offset: &Path,
+    ) -> BoxStream<'_, Result<ObjectMeta>> {
+        let prefix = prefix.cloned();
+        let offset = offset.clone();
+        self.fan_out(move |shard| shard.list_with_offset(prefix.as_ref(), &offset))
+    }
+
+    fn list_range(&self, start: &Path, end: &Path) -> BoxStream<'_, Result<ObjectMeta>> {
+        let (start, end) = (start.clone(), end.clone());
+        self.fan_out(move |shard| shard.list_range(&start, &end))
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
+        let results = futures::future::try_join_all(
+            self.shards.iter().map(|s| s.list_with_delimiter(prefix)),
+        )
+        .await?;
+
+        let mut common_prefixes = BTreeSet::new();
+        let mut objects = Vec::new();
+        for result in results {
+            common_prefixes.extend(result.common_prefixes);
+            objects.extend(result.objects);
+        }
+
+        Ok(ListResult {
+            common_prefixes: common_prefixes.into_iter().collect(),
+            objects,
+        })
+    }
+
+    fn list_with_delimiter_stream(
+        &self,
+        prefix: Option<&Path>,
+    ) -> BoxStream<'_, Result<ListResult>> {
+        let prefix = prefix.cloned();
+        // A common prefix may contain objects on several shards, and so would otherwise
+        // be returned once per shard
+        let mut seen = BTreeSet::new();
+        self.fan_out(move |shard| shard.list_with_delimiter_stream(prefix.as_ref()))
+            .map(move |result| {
+                result.map(|mut page| {
+                    page.common_prefixes.retain(|p| seen.insert(p.clone()));
+                    page
+                })
+            })
+            .boxed()
+    }
+
+    async fn copy(&self, from: &Path, to: &Path) -> Result<()> {
+        let (src, dst) = (self.shard_for(from), self.shard_for(to));
+        if src == dst {
+            return self.shards[src].copy(from, to).await;
+        }
+        let bytes = self.shards[src].get(from).await?.bytes().await?;
+        self.shards[dst].put(to, bytes).await?;
+        Ok(())
+    }
+
+    async fn copy_opts(&self, from: &Path, to: &Path, opts: CopyOptions) -> Result<()> {
+        let (src, dst) = (self.shard_for(from), self.shard_for(to));
+        if src == dst {
+            return self.shards[src].copy_opts(from, to, opts).await;
+        }
+        let mode = opts.mode;
+        crate::util::check_copy_source(self.shards[src].as_ref(), from, opts).await?;
+        match mode {
+            CopyMode::Overwrite => self.copy(from, to).await,
+            CopyMode::Create => self.copy_if_not_exists(from, to).await,
+        }
+    }
+
+    async fn probe(&self) -> Result<()> {
+        futures::future::try_join_all(self.shards.iter().map(|s| s.probe())).await?;
+        Ok(())
+    }
+
+    async fn get_acl(&self, location: &Path) -> Result<Acl> {
+        self.shard(location).get_acl(location).await
+    }
+
+    async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        let (src, dst) = (self.shard_for(from), self.shard_for(to));
+        if src == dst {
+            return self.shards[src].copy_if_not_exists(from, to).await;
+        }
+        let bytes = self.shards[src].get(from).await?.bytes().await?;
+        self.shards[dst]
+            .put_opts(to, bytes, PutMode::Create.into())
+            .await?;
+        Ok(())
+    }
+
+    fn object_url(&self, location: &Path) -> Result<Url> {
+        self.shard(location).object_url(location)
+    }
+
+    fn validate_path(&self, location: &Path) -> Result<()> {
+        self.shard(location).validate_path(location)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::InMemory;
+    use crate::test_util::flatten_list_stream;
+    use crate::tests::*;
+    use futures::TryStreamExt;
+
+    fn shards(n: usize) -> Vec<Arc<dyn ObjectStore>> {
+        (0..n).map(|_| Arc::new(InMemory::new()) as _).collect()
+    }
+
+    #[tokio::test]
+    async fn sharded_test() {
+        let integration = ShardedStore::new(shards(3));
+
+        put_get_delete_list(&integration).await;
+        get_opts(&integration).await;
+        list_uses_directories_correctly(&integration).await;
+        list_with_delimiter(&integration).await;
+        rename_and_copy(&integration).await;
+        copy_if_not_exists(&integration).await;
+        stream_get(&integration).await;
+    }
+
+    #[test]
+    fn test_fnv1a() {
+        // Hashes must be stable across releases
+        assert_eq!(fnv1a(&Path::from("")), 0xcbf29ce484222325);
+        assert_eq!(fnv1a(&Path::from("a")), 0xaf63dc4c8601ec8c);
+        assert_eq!(fnv1a(&Path::from("foobar")), 0x85944171f73967e8);
+    }
+
+    #[tokio::test]
+    async fn test_routing() {
+        let store = ShardedStore::new(shards(4));
+
+        let paths: Vec<_> = (0..32).map(|i| Path::from(format!("dir/{i}"))).collect();
+        for path in &paths {
+            let shard = store.shard_for(path);
+            assert_eq!(store.shard_for(path), shard);
+            assert_eq!(ShardedStore::new(shards(4)).shard_for(path), shard);
+
+            store.put(path, Bytes::from("data")).await.unwrap();
+            for (idx, s) in store.shards().iter().enumerate() {
+                assert_eq!(s.head(path).await.is_ok(), idx == shard, "{path}");
+            }
+        }
+
+        // Objects are spread across all shards
+        for s in store.shards() {
+            assert!(!flatten_list_stream(s.as_ref(), None)
+                .await
+                .unwrap()
+                .is_empty());
+        }
+
+        let mut listed = flatten_list_stream(&store, None).await.unwrap();
+        listed.sort_unstable();
+        let mut expected = paths.clone();
+        expected.sort_unstable();
+        assert_eq!(listed, expected);
+
+        // Custom hasher
+        let hasher: ShardHasher = Arc::new(|p: &Path| p.as_ref().len() as u64);
+        let store = ShardedStore::new_with_hasher(shards(2), hasher);
+        assert_eq!(store.shard_for(&Path::from("ab")), 0);
+        assert_eq!(store.shard_for(&Path::from("abc")), 1);
+    }
+
+    #[tokio::test]
+    async fn test_list_with_delimiter_stream_dedup() {
+        // Route objects by the length of their path, so `dir/a` and `dir/bb` are on
+        // different shards
+        let hasher: ShardHasher = Arc::new(|p: &Path| p.as_ref().len() as u64);
+        let store = ShardedStore::new_with_hasher(shards(2), hasher);
+        for path in ["dir/a", "dir/bb", "c"] {
+            store
+                .put(&Path::from(path), Bytes::from("data"))
+                .await
+                .unwrap();
+        }
+        assert_ne!(
+            store.shard_for(&Path::from("dir/a")),
+            store.shard_for(&Path::from("dir/bb"))
+        );
+
+        let pages: Vec<_> = store
+            .list_with_delimiter_stream(None)
+            .try_collect()
+            .await
+            .unwrap();
+        let prefixes: Vec<_> = pages.iter().flat_map(|p| &p.common_prefixes).collect();
+        assert_eq!(prefixes, vec![&Path::from("dir")]);
+        let objects: Vec<_> = pages.iter().flat_map(|p| &p.objects).collect();
+        assert_eq!(objects.len(), 1);
+
+        let result = store.list_with_delimiter(None).await.unwrap();
+        assert_eq!(result.common_prefixes, vec![Path::from("dir")]);
+    }
+}
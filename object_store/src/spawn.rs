@@ -0,0 +1,473 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! An object store that performs its IO on a dedicated runtime
+//!
+//! The futures and streams returned by [`ObjectStore`] are `Send`, and so can be awaited
+//! from any context, including a [`LocalSet`] or a single-threaded runtime. However, the
+//! stores perform their IO on the runtime that polls them, which must therefore have
+//! the IO and time drivers enabled, and not be blocked by long-running `!Send` tasks
+//! driven alongside the requests, such as the event loop of a UI.
+//!
+//! [`LocalObjectStore`] instead spawns every operation onto the runtime of a provided
+//! [`Handle`], typically a dedicated multi-threaded runtime, and bridges the results
+//! back over channels. The returned futures and streams only wait for these results, and
+//! so can be polled by any executor, whilst the IO makes progress independently.
+//!
+//! ```
+//! # use object_store::memory::InMemory;
+//! # use object_store::path::Path;
+//! # use object_store::spawn::LocalObjectStore;
+//! # use object_store::ObjectStore;
+//! # use std::rc::Rc;
+//! # use std::sync::Arc;
+//! // A dedicated runtime performing the IO
+//! let io_runtime = tokio::runtime::Builder::new_multi_thread()
+//!     .worker_threads(2)
+//!     .enable_all()
+//!     .build()
+//!     .unwrap();
+//! let store = LocalObjectStore::new(Arc::new(InMemory::new()), io_runtime.handle().clone());
+//!
+//! // A single-threaded runtime running `!Send` tasks
+//! let local = tokio::runtime::Builder::new_current_thread().build().unwrap();
+//! tokio::task::LocalSet::new().block_on(&local, async move {
+//!     let state = Rc::new(Path::from("data"));
+//!     store.put(&state, "hello".into()).await.unwrap();
+//!     let data = store.get(&state).await.unwrap().bytes().await.unwrap();
+//!     assert_eq!(data.as_ref(), b"hello");
+//! });
+//! ```
+//!
+//! [`LocalSet`]: tokio::task::LocalSet
+
+use crate::path::Path;
+use crate::{
+    Acl, CopyOptions, DeleteOptions, GetOptions, GetResult, GetResultPayload, ListPage, ListResult,
+    MultipartId, ObjectMeta, ObjectStore, PutOptions, PutResult, Result,
+};
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::future::BoxFuture;
+use futures::stream::BoxStream;
+use futures::{ready, FutureExt, StreamExt};
+use std::future::Future;
+use std::io::ErrorKind;
+use std::ops::Range;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tokio::io::{AsyncWrite, AsyncWriteExt, DuplexStream};
+use tokio::runtime::Handle;
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+use url::Url;
+
+/// The number of items of a stream buffered by the channel to the caller
+const STREAM_BUFFER: usize = 8;
+
+/// The number of bytes written to a multipart upload buffered for the spawned writer
+const WRITE_BUFFER: usize = 64 * 1024;
+
+/// An [`ObjectStore`] wrapper that spawns every operation onto the runtime of a [`Handle`],
+/// so that the wrapped store can be used from `!Send` contexts and other runtimes
+///
+/// See the [module level documentation](self) for details
+#[derive(Debug, Clone)]
+pub struct LocalObjectStore {
+    inner: Arc<dyn ObjectStore>,
+    handle: Handle,
+}
+
+impl std::fmt::Display for LocalObjectStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "LocalObjectStore({})", self.inner)
+    }
+}
+
+impl LocalObjectStore {
+    /// Create a new [`LocalObjectStore`] performing the operations of `inner` on the
+    /// runtime of `handle`
+    pub fn new(inner: Arc<dyn ObjectStore>, handle: Handle) -> Self {
+        Self { inner, handle }
+    }
+
+    /// Returns the wrapped [`ObjectStore`]
+    pub fn inner(&self) -> &Arc<dyn ObjectStore> {
+        &self.inner
+    }
+
+    /// Spawns the future returned by `f` and waits for its result
+    async fn spawn<T, F, Fut>(&self, f: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(Arc<dyn ObjectStore>) -> Fut,
+        Fut: Future<Output = Result<T>> + Send + 'static,
+    {
+        self.handle.spawn(f(Arc::clone(&self.inner))).await?
+    }
+
+    /// Spawns the future returned by `f`, which writes a stream to the provided
+    /// [`mpsc::Sender`], returning a stream of the items received
+    fn spawn_stream<T, F>(&self, f: F) -> BoxStream<'static, Result<T>>
+    where
+        T: Send + 'static,
+        F: FnOnce(Arc<dyn ObjectStore>, mpsc::Sender<Result<T>>) -> BoxFuture<'static, ()>,
+    {
+        let (sender, receiver) = mpsc::channel(STREAM_BUFFER);
+        self.handle.spawn(f(Arc::clone(&self.inner), sender));
+        futures::stream::unfold(receiver, |mut receiver| async move {
+            let item = receiver.recv().await?;
+            Some((item, receiver))
+        })
+        .boxed()
+    }
+}
+
+/// Sends the items of `stream` to `sender`, stopping if the receiver is dropped
+async fn forward<T>(mut stream: BoxStream<'_, Result<T>>, sender: mpsc::Sender<Result<T>>) {
+    while let Some(item) = stream.next().await {
+        if sender.send(item).await.is_err() {
+            return;
+        }
+    }
+}
+
+#[async_trait]
+impl ObjectStore for LocalObjectStore {
+    async fn put_opts(&self, location: &Path, bytes: Bytes, opts: PutOptions) -> Result<PutResult> {
+        let location = location.clone();
+        self.spawn(move |s| async move { s.put_opts(&location, bytes, opts).await })
+            .await
+    }
+
+    async fn put_multipart(
+        &self,
+        location: &Path,
+    ) -> Result<(MultipartId, Box<dyn AsyncWrite + Unpin + Send>)> {
+        let location = location.clone();
+        let (id, writer) = self
+            .spawn(move |s| async move { s.put_multipart(&location).await })
+            .await?;
+        let writer = SpawnedWriter::new(&self.handle, writer);
+        Ok((id, Box::new(writer)))
+    }
+
+    async fn abort_multipart(&self, location: &Path, multipart_id: &MultipartId) -> Result<()> {
+        let location = location.clone();
+        let id = multipart_id.clone();
+        self.spawn(move |s| async move { s.abort_multipart(&location, &id).await })
+            .await
+    }
+
+    async fn get_opts(&self, location: &Path, options: GetOptions) -> Result<GetResult> {
+        let location = location.clone();
+        let result = self
+            .spawn(move |s| async move { s.get_opts(&location, options).await })
+            .await?;
+
+        let payload = match result.payload {
+            v @ GetResultPayload::File(_, _) => v,
+            GetResultPayload::Stream(stream) => GetResultPayload::Stream(
+                self.spawn_stream(move |_, sender| forward(stream, sender).boxed()),
+            ),
+        };
+        Ok(GetResult { payload, ..result })
+    }
+
+    async fn get_range(&self, location: &Path, range: Range<usize>) -> Result<Bytes> {
+        let location = location.clone();
+        self.spawn(move |s| async move { s.get_range(&location, range).await })
+            .await
+    }
+
+    async fn get_ranges(&self, location: &Path, ranges: &[Range<usize>]) -> Result<Vec<Bytes>> {
+        let location = location.clone();
+        let ranges = ranges.to_vec();
+        self.spawn(move |s| async move { s.get_ranges(&location, &ranges).await })
+            .await
+    }
+
+    async fn head(&self, location: &Path) -> Result<ObjectMeta> {
+        let location = location.clone();
+        self.spawn(move |s| async move { s.head(&location).await })
+            .await
+    }
+
+    async fn delete(&self, location: &Path) -> Result<()> {
+        let location = location.clone();
+        self.spawn(move |s| async move { s.delete(&location).await })
+            .await
+    }
+
+    async fn delete_opts(&self, location: &Path, opts: DeleteOptions) -> Result<()> {
+        let location = location.clone();
+        self.spawn(move |s| async move { s.delete_opts(&location, opts).await })
+            .await
+    }
+
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'_, Result<ObjectMeta>> {
+        let prefix = prefix.cloned();
+        self.spawn_stream(move |s, sender| {
+            async move { forward(s.list(prefix.as_ref()), sender).await }.boxed()
+        })
+    }
+
+    fn list_with_offset(
+        &self,
+        prefix: Option<&Path>,
+        offset: &Path,
+    ) -> BoxStream<'_, Result<ObjectMeta>> {
+        let prefix = prefix.cloned();
+        let offset = offset.clone();
+        self.spawn_stream(move |s, sender| {
+            async move { forward(s.list_with_offset(prefix.as_ref(), &offset), sender).await }
+                .boxed()
+        })
+    }
+
+    fn list_range(&self, start: &Path, end: &Path) -> BoxStream<'_, Result<ObjectMeta>> {
+        let (start, end) = (start.clone(), end.clone());
+        self.spawn_stream(move |s, sender| {
+            async move { forward(s.list_range(&start, &end), sender).await }.boxed()
+        })
+    }
+
+    fn list_from_token(
+        &self,
+        prefix: Option<&Path>,
+        token: Option<&str>,
+    ) -> BoxStream<'_, Result<ListPage>> {
+        let prefix = prefix.cloned();
+        let token = token.map(ToString::to_string);
+        self.spawn_stream(move |s, sender| {
+            async move {
+                let stream = s.list_from_token(prefix.as_ref(), token.as_deref());
+                forward(stream, sender).await
+            }
+            .boxed()
+        })
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
+        let prefix = prefix.cloned();
+        self.spawn(move |s| async move { s.list_with_delimiter(prefix.as_ref()).await })
+            .await
+    }
+
+    fn list_with_delimiter_stream(
+        &self,
+        prefix: Option<&Path>,
+    ) -> BoxStream<'_, Result<ListResult>> {
+        let prefix = prefix.cloned();
+        self.spawn_stream(move |s, sender| {
+            async move { forward(s.list_with_delimiter_stream(prefix.as_ref()), sender).await }
+                .boxed()
+        })
+    }
+
+    async fn copy(&self, from: &Path, to: &Path) -> Result<()> {
+        let (from, to) = (from.clone(), to.clone());
+        self.spawn(move |s| async move { s.copy(&from, &to).await })
+            .await
+    }
+
+    async fn copy_opts(&self, from: &Path, to: &Path, opts: CopyOptions) -> Result<()> {
+        let (from, to) = (from.clone(), to.clone());
+        self.spawn(move |s| async move { s.copy_opts(&from, &to, opts).await })
+            .await
+    }
+
+    async fn rename(&self, from: &Path, to: &Path) -> Result<()> {
+        let (from, to) = (from.clone(), to.clone());
+        self.spawn(move |s| async move { s.rename(&from, &to).await })
+            .await
+    }
+
+    async fn probe(&self) -> Result<()> {
+        self.spawn(move |s| async move { s.probe().await }).await
+    }
+
+    async fn get_acl(&self, location: &Path) -> Result<Acl> {
+        let location = location.clone();
+        self.spawn(move |s| async move { s.get_acl(&location).await })
+            .await
+    }
+
+    async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        let (from, to) = (from.clone(), to.clone());
+        self.spawn(move |s| async move { s.copy_if_not_exists(&from, &to).await })
+            .await
+    }
+
+    async fn rename_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        let (from, to) = (from.clone(), to.clone());
+        self.spawn(move |s| async move { s.rename_if_not_exists(&from, &to).await })
+            .await
+    }
+
+    fn object_url(&self, location: &Path) -> Result<Url> {
+        self.inner.object_url(location)
+    }
+
+    fn validate_path(&self, location: &Path) -> Result<()> {
+        self.inner.validate_path(location)
+    }
+}
+
+/// An [`AsyncWrite`] writing to a multipart upload on a spawned task
+///
+/// Written bytes are passed to the task through a [`DuplexStream`]. As the end of the
+/// stream is also observed if the writer is dropped, the upload is only completed if
+/// signalled by [`AsyncWrite::poll_shutdown`], preserving the semantics of dropping a
+/// multipart writer without shutting it down.
+struct SpawnedWriter {
+    writer: DuplexStream,
+    complete: Option<oneshot::Sender<()>>,
+    task: Option<JoinHandle<std::io::Result<()>>>,
+}
+
+impl SpawnedWriter {
+    fn new(handle: &Handle, mut inner: Box<dyn AsyncWrite + Unpin + Send>) -> Self {
+        let (writer, mut reader) = tokio::io::duplex(WRITE_BUFFER);
+        let (complete, completed) = oneshot::channel();
+        let task = handle.spawn(async move {
+            tokio::io::copy(&mut reader, &mut inner).await?;
+            match completed.await {
+                Ok(()) => inner.shutdown().await,
+                // The writer was dropped without being shut down
+                Err(_) => Ok(()),
+            }
+        });
+        Self {
+            writer,
+            complete: Some(complete),
+            task: Some(task),
+        }
+    }
+
+    /// Polls the spawned task to completion, returning its result
+    fn poll_task(&mut self, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        let task = match self.task.as_mut() {
+            Some(task) => task,
+            None => return Poll::Ready(Ok(())),
+        };
+        let result = ready!(Pin::new(task).poll(cx));
+        self.task = None;
+        Poll::Ready(result.unwrap_or_else(|e| Err(std::io::Error::new(ErrorKind::Other, e))))
+    }
+}
+
+impl AsyncWrite for SpawnedWriter {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        match ready!(Pin::new(&mut self.writer).poll_write(cx, buf)) {
+            Ok(n) => Poll::Ready(Ok(n)),
+            // The task has stopped reading, return its error if any
+            Err(e) => match self.poll_task(cx) {
+                Poll::Ready(Err(task_err)) => Poll::Ready(Err(task_err)),
+                _ => Poll::Ready(Err(e)),
+            },
+        }
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.writer).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        if let Some(complete) = self.complete.take() {
+            let _ = complete.send(());
+        }
+        ready!(Pin::new(&mut self.writer).poll_shutdown(cx))?;
+        self.poll_task(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::InMemory;
+    use crate::tests::*;
+    use std::rc::Rc;
+    use tokio::task::LocalSet;
+
+    #[tokio::test]
+    async fn local_object_store_test() {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .enable_all()
+            .build()
+            .unwrap();
+        let store = LocalObjectStore::new(Arc::new(InMemory::new()), runtime.handle().clone());
+
+        put_get_delete_list(&store).await;
+        list_uses_directories_correctly(&store).await;
+        list_with_delimiter(&store).await;
+        rename_and_copy(&store).await;
+        copy_if_not_exists(&store).await;
+        stream_get(&store).await;
+
+        // The upload is not completed if the writer is dropped without shutdown
+        let path = Path::from("dropped");
+        let (_, mut writer) = store.put_multipart(&path).await.unwrap();
+        writer.write_all(b"data").await.unwrap();
+        drop(writer);
+        let err = store.head(&path).await.unwrap_err();
+        assert!(matches!(err, crate::Error::NotFound { .. }), "{err}");
+
+        runtime.shutdown_background();
+    }
+
+    #[test]
+    fn local_object_store_local_set() {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .enable_all()
+            .build()
+            .unwrap();
+        let store = LocalObjectStore::new(Arc::new(InMemory::new()), runtime.handle().clone());
+        let path = Path::from("file");
+        runtime
+            .block_on(store.put(&path, "hello world".into()))
+            .unwrap();
+
+        // Neither the IO nor time drivers are enabled on the local runtime
+        let local = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let set = LocalSet::new();
+        let data = set.block_on(&local, async move {
+            // A `!Send` task, holding an `Rc` across await points
+            let handle = tokio::task::spawn_local(async move {
+                let location = Rc::new(path);
+                let result = store.get(&location).await.unwrap();
+                let data = result.bytes().await.unwrap();
+                let range = store.get_range(&location, 0..5).await.unwrap();
+                assert_eq!(range.as_ref(), b"hello");
+                Rc::new(data)
+            });
+            handle.await.unwrap()
+        });
+        assert_eq!(data.as_ref().as_ref(), b"hello world");
+
+        runtime.shutdown_background();
+    }
+}
@@ -21,15 +21,16 @@ use std::ops::Range;
 use std::{convert::TryInto, sync::Arc};
 
 use crate::{
-    path::Path, GetResult, GetResultPayload, ListResult, ObjectMeta, ObjectStore, PutOptions,
-    PutResult, Result,
+    path::Path, Acl, GetResult, GetResultPayload, ListPage, ListResult, ObjectMeta, ObjectStore,
+    PutOptions, PutResult, Result,
 };
-use crate::{GetOptions, MultipartId};
+use crate::{CopyOptions, DeleteOptions, GetOptions, MultipartId};
 use async_trait::async_trait;
 use bytes::Bytes;
 use futures::{stream::BoxStream, FutureExt, StreamExt};
 use std::time::Duration;
 use tokio::io::AsyncWrite;
+use url::Url;
 
 /// Configuration settings for throttled store
 #[derive(Debug, Default, Clone, Copy)]
@@ -223,6 +224,12 @@ impl<T: ObjectStore> ObjectStore for ThrottledStore<T> {
         self.inner.delete(location).await
     }
 
+    async fn delete_opts(&self, location: &Path, opts: DeleteOptions) -> Result<()> {
+        sleep(self.config().wait_delete_per_call).await;
+
+        self.inner.delete_opts(location, opts).await
+    }
+
     fn list(&self, prefix: Option<&Path>) -> BoxStream<'_, Result<ObjectMeta>> {
         let stream = self.inner.list(prefix);
         futures::stream::once(async move {
@@ -249,6 +256,38 @@ impl<T: ObjectStore> ObjectStore for ThrottledStore<T> {
         .boxed()
     }
 
+    fn list_range(&self, start: &Path, end: &Path) -> BoxStream<'_, Result<ObjectMeta>> {
+        let stream = self.inner.list_range(start, end);
+        futures::stream::once(async move {
+            let wait_list_per_entry = self.config().wait_list_per_entry;
+            sleep(self.config().wait_list_per_call).await;
+            throttle_stream(stream, move |_| wait_list_per_entry)
+        })
+        .flatten()
+        .boxed()
+    }
+
+    fn list_from_token(
+        &self,
+        prefix: Option<&Path>,
+        token: Option<&str>,
+    ) -> BoxStream<'_, Result<ListPage>> {
+        let stream = self.inner.list_from_token(prefix, token);
+        futures::stream::once(async move {
+            let wait_list_per_entry = self.config().wait_list_per_entry;
+            sleep(self.config().wait_list_per_call).await;
+            stream.then(move |result| async move {
+                if let Ok(page) = &result {
+                    let entries_len = usize_to_u32_saturate(page.objects.len());
+                    sleep(wait_list_per_entry * entries_len).await;
+                }
+                result
+            })
+        })
+        .flatten()
+        .boxed()
+    }
+
     async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
         sleep(self.config().wait_list_with_delimiter_per_call).await;
 
@@ -262,18 +301,55 @@ impl<T: ObjectStore> ObjectStore for ThrottledStore<T> {
         }
     }
 
+    fn list_with_delimiter_stream(
+        &self,
+        prefix: Option<&Path>,
+    ) -> BoxStream<'_, Result<ListResult>> {
+        let stream = self.inner.list_with_delimiter_stream(prefix);
+        futures::stream::once(async move {
+            let wait_per_entry = self.config().wait_list_with_delimiter_per_entry;
+            sleep(self.config().wait_list_with_delimiter_per_call).await;
+            stream.then(move |result| async move {
+                if let Ok(list_result) = &result {
+                    let entries_len = usize_to_u32_saturate(list_result.objects.len());
+                    sleep(wait_per_entry * entries_len).await;
+                }
+                result
+            })
+        })
+        .flatten()
+        .boxed()
+    }
+
     async fn copy(&self, from: &Path, to: &Path) -> Result<()> {
         sleep(self.config().wait_put_per_call).await;
 
         self.inner.copy(from, to).await
     }
 
+    async fn copy_opts(&self, from: &Path, to: &Path, opts: CopyOptions) -> Result<()> {
+        sleep(self.config().wait_put_per_call).await;
+
+        self.inner.copy_opts(from, to, opts).await
+    }
+
     async fn rename(&self, from: &Path, to: &Path) -> Result<()> {
         sleep(self.config().wait_put_per_call).await;
 
         self.inner.rename(from, to).await
     }
 
+    async fn probe(&self) -> Result<()> {
+        sleep(self.config().wait_list_per_call).await;
+
+        self.inner.probe().await
+    }
+
+    async fn get_acl(&self, location: &Path) -> Result<Acl> {
+        sleep(self.config().wait_get_per_call).await;
+        self.inner.get_acl(location).await
+    }
+
     async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
         sleep(self.config().wait_put_per_call).await;
 
@@ -285,6 +361,14 @@ impl<T: ObjectStore> ObjectStore for ThrottledStore<T> {
 
         self.inner.rename_if_not_exists(from, to).await
     }
+
+    fn object_url(&self, location: &Path) -> Result<Url> {
+        self.inner.object_url(location)
+    }
+
+    fn validate_path(&self, location: &Path) -> Result<()> {
+        self.inner.validate_path(location)
+    }
 }
 
 /// Saturated `usize` to `u32` cast.
@@ -22,11 +22,15 @@ use std::{
 };
 
 use super::Result;
+use crate::path::Path;
+use crate::{CopyOptions, ObjectMeta, ObjectStore};
 use bytes::Bytes;
+use futures::stream::{BoxStream, Fuse, FusedStream};
 use futures::{stream::StreamExt, Stream, TryStreamExt};
 use snafu::Snafu;
+use url::Url;
 
-#[cfg(any(feature = "azure", feature = "http"))]
+#[cfg(any(feature = "aws", feature = "azure", feature = "http"))]
 pub static RFC1123_FMT: &str = "%a, %d %h %Y %T GMT";
 
 // deserialize dates according to rfc1123
@@ -49,6 +53,212 @@ pub(crate) fn hmac_sha256(secret: impl AsRef<[u8]>, bytes: impl AsRef<[u8]>) ->
     ring::hmac::sign(&key, bytes.as_ref())
 }
 
+/// Returns `base` with the segments of `location` appended to its path
+///
+/// Each segment is percent-encoded, such that decoding the resulting URL path
+/// yields the segments of `location`
+pub(crate) fn url_with_path(mut base: Url, location: &Path) -> Url {
+    base.path_segments_mut()
+        .expect("url path")
+        .pop_if_empty()
+        .extend(location.parts());
+    base
+}
+
+/// Returns an [`Error::InvalidPath`](crate::Error::InvalidPath) for a `location`
+/// that is not supported by `store`
+#[cfg(any(feature = "aws", feature = "gcp", feature = "azure"))]
+pub(crate) fn unsupported_path(
+    location: &Path,
+    store: &'static str,
+    reason: impl Into<String>,
+) -> crate::Error {
+    crate::Error::InvalidPath {
+        source: crate::path::Error::Unsupported {
+            path: location.to_string(),
+            store,
+            reason: reason.into(),
+        },
+    }
+}
+
+/// Returns true if `e_tag` is a weak ETag, i.e. is prefixed with `W/`
+///
+/// <https://datatracker.ietf.org/doc/html/rfc9110#name-etag>
+pub(crate) fn is_weak_etag(e_tag: &str) -> bool {
+    e_tag.starts_with("W/")
+}
+
+/// Returns `e_tag` with any weak indicator removed, for use in weak comparisons
+///
+/// <https://datatracker.ietf.org/doc/html/rfc9110#name-comparison-2>
+pub(crate) fn strip_weak_etag(e_tag: &str) -> &str {
+    e_tag.strip_prefix("W/").unwrap_or(e_tag)
+}
+
+/// Normalizes an ETag returned by a store, trimming surrounding whitespace and
+/// using the canonical `W/` prefix for weak ETags
+#[cfg(feature = "cloud")]
+pub(crate) fn normalize_etag(e_tag: &str) -> String {
+    let e_tag = e_tag.trim();
+    match e_tag.strip_prefix("w/") {
+        Some(opaque) => format!("W/{opaque}"),
+        None => e_tag.to_string(),
+    }
+}
+
+/// Returns [`crate::Error::Precondition`] if `e_tag` does not match `expected`
+///
+/// This is a strong comparison, and so a weak `e_tag` never matches
+pub(crate) fn check_precondition(
+    location: &Path,
+    e_tag: Option<&str>,
+    expected: &str,
+) -> crate::Result<()> {
+    match e_tag {
+        Some(e_tag) if e_tag == expected && !is_weak_etag(e_tag) => Ok(()),
+        e_tag => Err(crate::Error::Precondition {
+            path: location.to_string(),
+            source: format!("{} does not match {expected}", e_tag.unwrap_or("<none>")).into(),
+        }),
+    }
+}
+
+/// Returns [`crate::Error::Precondition`] if the object at `from` does not satisfy the
+/// conditions of `opts`, for use by stores that cannot copy conditionally
+pub(crate) async fn check_copy_source<T: ObjectStore + ?Sized>(
+    store: &T,
+    from: &Path,
+    opts: CopyOptions,
+) -> crate::Result<()> {
+    if opts.if_match.is_none() && opts.if_unmodified_since.is_none() {
+        return Ok(());
+    }
+    let meta = store.head(from).await?;
+    let options = crate::GetOptions {
+        if_match: opts.if_match,
+        if_unmodified_since: opts.if_unmodified_since,
+        ..Default::default()
+    };
+    options.check_preconditions(&meta)
+}
+
+/// Returns [`crate::Error::NotSupported`] if `opts` requests deleting a specific version,
+/// for use by stores without object versioning
+pub(crate) fn check_delete_version(opts: &crate::DeleteOptions) -> crate::Result<()> {
+    match opts.version {
+        Some(_) => Err(crate::Error::NotSupported {
+            source: "deleting a specific object version is not supported".into(),
+        }),
+        None => Ok(()),
+    }
+}
+
+#[derive(Debug, Snafu)]
+enum InvalidByteSize {
+    #[snafu(display(
+        "Invalid size \"{input}\", expected a number optionally followed by a unit, e.g. 16MiB"
+    ))]
+    Malformed { input: String },
+
+    #[snafu(display(
+        "Invalid size \"{input}\", unknown unit \"{unit}\", expected one of B, KB, MB, GB, TB, KiB, MiB, GiB or TiB"
+    ))]
+    UnknownUnit { input: String, unit: String },
+
+    #[snafu(display("Invalid size \"{input}\", not a whole number of bytes"))]
+    Fractional { input: String },
+
+    #[snafu(display("Invalid size \"{input}\", too large"))]
+    TooLarge { input: String },
+}
+
+impl From<InvalidByteSize> for crate::Error {
+    fn from(source: InvalidByteSize) -> Self {
+        Self::Generic {
+            store: "Config",
+            source: Box::new(source),
+        }
+    }
+}
+
+/// Parses a human-readable size in bytes, such as `1024`, `16MiB` or `2.5GB`
+///
+/// The unit is case-insensitive, and may be separated from the number by whitespace.
+/// Both SI units (`KB`, `MB`, `GB`, `TB`), which are powers of 1000, and IEC units
+/// (`KiB`, `MiB`, `GiB`, `TiB`), which are powers of 1024, are supported.
+///
+/// Single letter units such as `M` are rejected as it is ambiguous which of these is
+/// intended, as are sizes that are not a whole number of bytes.
+///
+/// ```
+/// # use object_store::parse_byte_size;
+/// assert_eq!(parse_byte_size("1024").unwrap(), 1024);
+/// assert_eq!(parse_byte_size("1KiB").unwrap(), 1024);
+/// assert_eq!(parse_byte_size("2.5MB").unwrap(), 2_500_000);
+/// assert!(parse_byte_size("10XB").is_err());
+/// ```
+pub fn parse_byte_size(s: &str) -> Result<u64> {
+    let input = s.trim();
+    let malformed = || InvalidByteSize::Malformed {
+        input: input.to_string(),
+    };
+
+    let split = input
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(input.len());
+    let (number, unit) = input.split_at(split);
+
+    let multiplier: u64 = match unit.trim_start().to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "kb" => 1_000,
+        "mb" => 1_000_000,
+        "gb" => 1_000_000_000,
+        "tb" => 1_000_000_000_000,
+        "kib" => 1 << 10,
+        "mib" => 1 << 20,
+        "gib" => 1 << 30,
+        "tib" => 1 << 40,
+        unit => {
+            return Err(InvalidByteSize::UnknownUnit {
+                input: input.to_string(),
+                unit: unit.to_string(),
+            }
+            .into())
+        }
+    };
+
+    let (whole, fraction) = number.split_once('.').unwrap_or((number, ""));
+    if whole.is_empty() || (number.contains('.') && fraction.is_empty()) {
+        return Err(malformed().into());
+    }
+    let whole: u64 = whole.parse().map_err(|_| malformed())?;
+
+    let too_large = || InvalidByteSize::TooLarge {
+        input: input.to_string(),
+    };
+    let mut bytes = whole.checked_mul(multiplier).ok_or_else(too_large)?;
+
+    if !fraction.is_empty() {
+        // Compute fraction * multiplier / 10^digits exactly, rejecting fractional bytes
+        let digits = u32::try_from(fraction.len()).map_err(|_| malformed())?;
+        let numerator: u128 = fraction.parse().map_err(|_| malformed())?;
+        let denominator = 10_u128.checked_pow(digits).ok_or_else(malformed)?;
+        let scaled = numerator
+            .checked_mul(multiplier as u128)
+            .ok_or_else(too_large)?;
+        if scaled % denominator != 0 {
+            return Err(InvalidByteSize::Fractional {
+                input: input.to_string(),
+            }
+            .into());
+        }
+        let extra = u64::try_from(scaled / denominator).map_err(|_| too_large())?;
+        bytes = bytes.checked_add(extra).ok_or_else(too_large)?;
+    }
+    Ok(bytes)
+}
+
 /// Collect a stream into [`Bytes`] avoiding copying in the event of a single chunk
 pub async fn collect_bytes<S, E>(mut stream: S, size_hint: Option<usize>) -> Result<Bytes, E>
 where
@@ -135,6 +345,451 @@ where
         .collect())
 }
 
+/// Fetches the [`ObjectMeta`] of each of `paths` from `store`, performing up to
+/// `concurrency` [`ObjectStore::head`] requests in parallel
+///
+/// Results are yielded in the order of `paths`, with an error for one path, such as
+/// [`Error::NotFound`](crate::Error::NotFound) for a missing object, not affecting
+/// the others. Where the paths to fetch are already known, this avoids listing
+/// and filtering a potentially much larger prefix.
+pub fn head_many<'a, I>(
+    store: &'a dyn ObjectStore,
+    paths: I,
+    concurrency: usize,
+) -> BoxStream<'a, Result<(Path, ObjectMeta)>>
+where
+    I: IntoIterator<Item = Path>,
+    I::IntoIter: Send + 'a,
+{
+    futures::stream::iter(paths)
+        .map(move |path| async move {
+            let meta = store.head(&path).await?;
+            Ok((path, meta))
+        })
+        .buffered(concurrency.max(1))
+        .boxed()
+}
+
+/// Writes `bytes` to `location` in `store`, unless the existing object has the same
+/// contents, returning whether a write occurred
+///
+/// The existing object is fetched with [`ObjectStore::head`], and the upload skipped
+/// if its size matches that of `bytes`, and its [`ObjectMeta::checksum`] matches the
+/// checksum of `bytes` computed with the same [`ChecksumAlgorithm`]. Where the store
+/// does not provide a checksum for the object, for example an S3 object uploaded
+/// without one, or a multipart object, the contents cannot be compared and `bytes`
+/// is always written.
+///
+/// The comparison and the write are not atomic, and so a concurrent writer may modify
+/// the object between them.
+///
+/// [`ChecksumAlgorithm`]: crate::ChecksumAlgorithm
+pub async fn put_if_changed(
+    store: &dyn ObjectStore,
+    location: &Path,
+    bytes: Bytes,
+) -> Result<bool> {
+    match store.head(location).await {
+        Ok(meta) if meta.size == bytes.len() => {
+            if let Some(checksum) = &meta.checksum {
+                if local_digest(checksum.algorithm, &bytes).as_ref() == Some(&checksum.digest) {
+                    return Ok(false);
+                }
+            }
+        }
+        Ok(_) | Err(crate::Error::NotFound { .. }) => {}
+        Err(e) => return Err(e),
+    }
+    store.put(location, bytes).await?;
+    Ok(true)
+}
+
+/// Computes the digest of `data` with `algorithm`
+#[cfg(feature = "cloud")]
+fn local_digest(algorithm: crate::ChecksumAlgorithm, data: &[u8]) -> Option<Vec<u8>> {
+    Some(algorithm.digest(data))
+}
+
+/// Only the cloud stores provide checksums, and so the hashers are only available
+/// with the `cloud` feature
+#[cfg(not(feature = "cloud"))]
+fn local_digest(_algorithm: crate::ChecksumAlgorithm, _data: &[u8]) -> Option<Vec<u8>> {
+    None
+}
+
+/// The outcome of [`copy_prefix`] or [`rename_prefix`]
+#[derive(Debug, Default)]
+pub struct PrefixCopyResult {
+    /// The source and destination of each object copied
+    pub copied: Vec<(Path, Path)>,
+    /// The source and destination of each object that could not be copied, along
+    /// with the error
+    pub failed: Vec<(Path, Path, crate::Error)>,
+}
+
+impl PrefixCopyResult {
+    /// Returns true if every object was copied
+    pub fn is_complete(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+/// Copies every object under `from` to the same relative location under `to`, performing
+/// up to `concurrency` [`ObjectStore::copy`] requests in parallel
+///
+/// Object stores have no notion of directories, and so copying a prefix requires
+/// listing it and copying each object in turn. The failure to copy an object does not
+/// prevent copying the others, and is instead reported in the returned
+/// [`PrefixCopyResult`]. An error is returned if the listing fails, in which case some
+/// objects may already have been copied.
+///
+/// Returns an error if either of `from` and `to` contains the other, as objects
+/// written to the destination could then be listed, or overwritten, as sources
+pub async fn copy_prefix(
+    store: &dyn ObjectStore,
+    from: &Path,
+    to: &Path,
+    concurrency: usize,
+) -> Result<PrefixCopyResult> {
+    transfer_prefix(store, from, to, concurrency, false).await
+}
+
+/// Renames every object under `from` to the same relative location under `to`, like
+/// [`copy_prefix`] but with [`ObjectStore::rename`]
+///
+/// Objects that fail to be renamed are reported in the returned [`PrefixCopyResult`],
+/// and are left under `from`. Stores without a native rename, such as S3, copy and then
+/// delete each object, whereas `LocalFileSystem` renames each file in place.
+pub async fn rename_prefix(
+    store: &dyn ObjectStore,
+    from: &Path,
+    to: &Path,
+    concurrency: usize,
+) -> Result<PrefixCopyResult> {
+    transfer_prefix(store, from, to, concurrency, true).await
+}
+
+async fn transfer_prefix(
+    store: &dyn ObjectStore,
+    from: &Path,
+    to: &Path,
+    concurrency: usize,
+    rename: bool,
+) -> Result<PrefixCopyResult> {
+    if from.prefix_matches(to) || to.prefix_matches(from) {
+        return Err(crate::Error::Generic {
+            store: "CopyPrefix",
+            source: format!("cannot copy prefix \"{from}\" to overlapping prefix \"{to}\"").into(),
+        });
+    }
+
+    let mut results = store
+        .list(Some(from))
+        .map(|r| async move {
+            let source = r?.location;
+            let relative = source.prefix_match(from).into_iter().flatten();
+            let dest: Path = to.parts().chain(relative).collect();
+            let result = match rename {
+                true => store.rename(&source, &dest).await,
+                false => store.copy(&source, &dest).await,
+            };
+            Ok::<_, crate::Error>((source, dest, result))
+        })
+        .buffer_unordered(concurrency.max(1));
+
+    let mut out = PrefixCopyResult::default();
+    while let Some((source, dest, result)) = results.next().await.transpose()? {
+        match result {
+            Ok(()) => out.copied.push((source, dest)),
+            Err(e) => out.failed.push((source, dest, e)),
+        }
+    }
+    Ok(out)
+}
+
+/// Returns the longest path prefix of every location in `[start, end)`, if any, with
+/// which to narrow a listing of the range, see [`ObjectStore::list_range`]
+///
+/// This is the path segments shared by `start` and `end`, excluding the last segment
+/// of either, as a location equal to a path is not listed under it as a prefix
+pub(crate) fn range_prefix(start: &Path, end: &Path) -> Option<Path> {
+    let max_parts = start.parts().count().min(end.parts().count());
+    let common: Path = start
+        .parts()
+        .zip(end.parts())
+        .take(max_parts.saturating_sub(1))
+        .take_while(|(a, b)| a == b)
+        .map(|(a, _)| a)
+        .collect();
+    (!common.as_ref().is_empty()).then_some(common)
+}
+
+/// Returns the objects with a location in `[start, end)` for a store that lists objects
+/// in lexicographic order, see [`ObjectStore::list_range`]
+///
+/// As list requests only support an exclusive offset, `head` should return the object
+/// at `start`, if any, with `after` listing the objects with a location greater than
+/// `start`, which is then stopped once a location reaches `end`
+pub(crate) fn list_range<'a>(
+    start: &Path,
+    end: &Path,
+    head: futures::future::BoxFuture<'a, Result<ObjectMeta>>,
+    after: BoxStream<'a, Result<ObjectMeta>>,
+) -> BoxStream<'a, Result<ObjectMeta>> {
+    if start >= end {
+        return futures::stream::empty().boxed();
+    }
+    let end = end.clone();
+    // The root is not an object
+    let first = match start.as_ref().is_empty() {
+        true => futures::stream::empty().boxed(),
+        false => futures::stream::once(head)
+            .filter_map(|r| async move {
+                match r {
+                    Err(crate::Error::NotFound { .. }) => None,
+                    r => Some(r),
+                }
+            })
+            .boxed(),
+    };
+    first
+        .chain(after)
+        .try_take_while(move |o| futures::future::ready(Ok(o.location < end)))
+        .boxed()
+}
+
+/// Lists the objects under each of `prefixes` in `store`, performing up to
+/// `concurrency` listings in parallel and interleaving their results into a
+/// single stream
+///
+/// Objects are yielded as they are returned by each listing, and so in no
+/// particular order. If `dedup` is true, objects listed under more than one
+/// of `prefixes`, e.g. because one prefix contains another, are only returned
+/// once, at the cost of tracking the location of every object returned.
+///
+/// See [`list_many_sorted`] to instead yield objects in lexicographic order.
+pub fn list_many<'a>(
+    store: &'a dyn ObjectStore,
+    prefixes: &'a [Path],
+    concurrency: usize,
+    dedup: bool,
+) -> BoxStream<'a, Result<ObjectMeta>> {
+    let stream = futures::stream::iter(prefixes)
+        .map(move |prefix| store.list(Some(prefix)))
+        .flatten_unordered(concurrency.max(1));
+
+    match dedup {
+        true => {
+            let mut seen = std::collections::HashSet::new();
+            stream
+                .try_filter(move |o| futures::future::ready(seen.insert(o.location.clone())))
+                .boxed()
+        }
+        false => stream.boxed(),
+    }
+}
+
+/// Lists the objects under each of `prefixes` in `store`, like [`list_many`], but
+/// yielding them in lexicographic order of their location
+///
+/// The listings of each prefix are k-way merged, and so `store` must list objects in
+/// lexicographic order, as is the case for S3, Azure, GCS and [`InMemory`], but not
+/// for `LocalFileSystem`. If `dedup` is true, objects listed under more than one of
+/// `prefixes` are only returned once, which unlike [`list_many`] only requires
+/// comparing adjacent objects.
+///
+/// An object can only be yielded once the next object of every listing is known, and
+/// so all of `prefixes` are listed at once, with each listing buffering up to a page
+/// of results. The latency to the first object is therefore that of the slowest
+/// listing, and listings progress only as fast as the merged output is consumed.
+///
+/// [`InMemory`]: crate::memory::InMemory
+pub fn list_many_sorted<'a>(
+    store: &'a dyn ObjectStore,
+    prefixes: &'a [Path],
+    dedup: bool,
+) -> BoxStream<'a, Result<ObjectMeta>> {
+    let streams = prefixes.iter().map(|p| store.list(Some(p))).collect();
+    merge_sorted(streams, dedup)
+}
+
+/// Merges `streams`, each yielding objects in lexicographic order of location, into a
+/// single stream in lexicographic order, skipping repeated locations if `dedup` is true
+fn merge_sorted<'a>(
+    streams: Vec<BoxStream<'a, Result<ObjectMeta>>>,
+    dedup: bool,
+) -> BoxStream<'a, Result<ObjectMeta>> {
+    type Source<'a> = Fuse<BoxStream<'a, Result<ObjectMeta>>>;
+
+    struct MergeState<'a> {
+        /// Each source stream, along with its next object if already fetched
+        sources: Vec<(Source<'a>, Option<ObjectMeta>)>,
+        /// The location of the last object yielded
+        last: Option<Path>,
+    }
+
+    let state = MergeState {
+        sources: streams.into_iter().map(|s| (s.fuse(), None)).collect(),
+        last: None,
+    };
+
+    futures::stream::unfold(state, move |mut state| async move {
+        loop {
+            // Fetch the next object of every source concurrently
+            let fetch = state
+                .sources
+                .iter_mut()
+                .filter(|(_, next)| next.is_none())
+                .map(|(stream, next)| async move {
+                    match stream.next().await {
+                        Some(Ok(object)) => *next = Some(object),
+                        Some(Err(e)) => return Err(e),
+                        None => {}
+                    }
+                    Ok(())
+                });
+            let fetched = futures::future::join_all(fetch).await;
+            state
+                .sources
+                .retain(|(stream, next)| next.is_some() || !stream.is_terminated());
+            if let Some(e) = fetched.into_iter().find_map(|r| r.err()) {
+                return Some((Err(e), state));
+            }
+
+            let (idx, _) = state
+                .sources
+                .iter()
+                .enumerate()
+                .filter_map(|(idx, (_, next))| Some((idx, next.as_ref()?)))
+                .min_by(|(_, a), (_, b)| a.location.cmp(&b.location))?;
+            let object = state.sources[idx].1.take().unwrap();
+
+            if dedup {
+                if state.last.as_ref() == Some(&object.location) {
+                    continue;
+                }
+                state.last = Some(object.location.clone());
+            }
+            return Some((Ok(object), state));
+        }
+    })
+    .boxed()
+}
+
+/// Hexadecimal characters, for use with [`sub_prefixes`]
+pub const HEX_ALPHABET: &str = "0123456789abcdef";
+
+/// Digits and ASCII letters, for use with [`sub_prefixes`]
+pub const ALPHANUMERIC_ALPHABET: &str =
+    "0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+/// Returns a [`Path`] under `prefix` for each character of `alphabet`, e.g. `prefix/0`,
+/// `prefix/1`, etc..., for use as the split points of [`list_parallel`]
+///
+/// Keys are frequently distributed evenly across such sub-prefixes, for example where
+/// they begin with a hash or UUID, with [`HEX_ALPHABET`] and [`ALPHANUMERIC_ALPHABET`]
+/// suitable for most such key schemes
+pub fn sub_prefixes(prefix: Option<&Path>, alphabet: &str) -> Vec<Path> {
+    let prefix = prefix.cloned().unwrap_or_default();
+    alphabet
+        .chars()
+        .map(|c| prefix.child(c.to_string().as_str()))
+        .collect()
+}
+
+/// Lists the objects under `prefix` in `store`, partitioning the key space at
+/// `split_points` and performing up to `concurrency` listings in parallel
+///
+/// Each partition is listed with [`ObjectStore::list_with_offset`] starting from one
+/// split point, keeping only the objects up to the next. Together the partitions cover
+/// every key, so the results are the same as [`ObjectStore::list`], but yielded in no
+/// particular order. Split points are typically generated by [`sub_prefixes`].
+///
+/// As [`ObjectStore::list`] does not guarantee any order, and some stores such as
+/// [`LocalFileSystem`] list objects in an arbitrary order, a partition can't stop at
+/// the first object past its end, and so each listing continues to the end of `prefix`.
+/// This therefore only reduces the latency to the first results, at the cost of
+/// additional requests, and for stores that list in lexicographic order
+/// [`list_parallel_sorted`], which stops each listing at the end of its partition,
+/// should be preferred.
+///
+/// [`LocalFileSystem`]: crate::local::LocalFileSystem
+pub fn list_parallel<'a>(
+    store: &'a dyn ObjectStore,
+    prefix: Option<&'a Path>,
+    split_points: &[Path],
+    concurrency: usize,
+) -> BoxStream<'a, Result<ObjectMeta>> {
+    futures::stream::iter(list_partitions(store, prefix, split_points, false))
+        .flatten_unordered(concurrency.max(1))
+        .boxed()
+}
+
+/// Lists the objects under `prefix` in `store`, like [`list_parallel`], but yielding
+/// them in lexicographic order of their location
+///
+/// As with [`list_many_sorted`], `store` must list objects in lexicographic order, as
+/// is the case for S3, GCS, Azure and [`InMemory`], and the partitions are k-way merged.
+/// Each listing stops at the first object past the end of its partition, and so for
+/// stores that list in an arbitrary order, such as [`LocalFileSystem`], objects will be
+/// missing from the results. As the partitions are disjoint, and only the
+/// first is consumed until exhausted, every other partition is listed at once but stalls
+/// after buffering its first page of results, and so this provides little speedup over
+/// [`ObjectStore::list`] beyond prefetching. It is intended for consumers that
+/// require sorted input, e.g. for range partitioning, when combined with other
+/// parallel listings.
+///
+/// [`InMemory`]: crate::memory::InMemory
+/// [`LocalFileSystem`]: crate::local::LocalFileSystem
+pub fn list_parallel_sorted<'a>(
+    store: &'a dyn ObjectStore,
+    prefix: Option<&'a Path>,
+    split_points: &[Path],
+) -> BoxStream<'a, Result<ObjectMeta>> {
+    merge_sorted(list_partitions(store, prefix, split_points, true), false)
+}
+
+/// Returns a listing of each partition of the keys under `prefix`, partitioned at
+/// `split_points`, where `sorted` indicates `store` lists objects in lexicographic order,
+/// and so each listing can stop at the first object past the end of its partition
+fn list_partitions<'a>(
+    store: &'a dyn ObjectStore,
+    prefix: Option<&'a Path>,
+    split_points: &[Path],
+    sorted: bool,
+) -> Vec<BoxStream<'a, Result<ObjectMeta>>> {
+    let mut split_points = split_points.to_vec();
+    split_points.sort_unstable();
+    split_points.dedup();
+
+    // Partition `i` contains the keys in `(start, end]`, with unbounded first and last partitions
+    let starts = std::iter::once(None).chain(split_points.iter().cloned().map(Some));
+    let ends = split_points
+        .iter()
+        .cloned()
+        .map(Some)
+        .chain(std::iter::once(None));
+    starts
+        .zip(ends)
+        .map(move |(start, end)| {
+            let stream = match &start {
+                Some(start) => store.list_with_offset(prefix, start),
+                None => store.list(prefix),
+            };
+            let in_range =
+                move |o: &ObjectMeta| end.as_ref().map_or(true, |end| &o.location <= end);
+            match sorted {
+                true => stream
+                    .try_take_while(move |o| futures::future::ready(Ok(in_range(o))))
+                    .boxed(),
+                false => stream
+                    .try_filter(move |o| futures::future::ready(in_range(o)))
+                    .boxed(),
+            }
+        })
+        .collect()
+}
+
 /// Returns a sorted list of ranges that cover `ranges`
 fn merge_ranges(ranges: &[Range<usize>], coalesce: usize) -> Vec<Range<usize>> {
     if ranges.is_empty() {
@@ -443,4 +1098,321 @@ mod tests {
         let range = GetRange::Offset(1);
         assert_eq!(range.as_range(2).unwrap(), 1..2);
     }
+
+    #[test]
+    fn test_parse_byte_size() {
+        assert_eq!(parse_byte_size("0").unwrap(), 0);
+        assert_eq!(parse_byte_size("1024").unwrap(), 1024);
+        assert_eq!(parse_byte_size("1KiB").unwrap(), 1024);
+        assert_eq!(parse_byte_size("1kib").unwrap(), 1024);
+        assert_eq!(parse_byte_size("2.5MB").unwrap(), 2_500_000);
+        assert_eq!(parse_byte_size("2.5 mb").unwrap(), 2_500_000);
+        assert_eq!(parse_byte_size(" 16MiB ").unwrap(), 16 * 1024 * 1024);
+        assert_eq!(parse_byte_size("0.5KiB").unwrap(), 512);
+        assert_eq!(parse_byte_size("1TiB").unwrap(), 1 << 40);
+        assert_eq!(parse_byte_size("3B").unwrap(), 3);
+
+        let err = parse_byte_size("10XB").unwrap_err().to_string();
+        assert!(err.contains("unknown unit \"xb\""), "{err}");
+
+        // Single letter units are ambiguous
+        let err = parse_byte_size("5M").unwrap_err().to_string();
+        assert!(err.contains("unknown unit"), "{err}");
+
+        let err = parse_byte_size("1.5B").unwrap_err().to_string();
+        assert!(err.contains("not a whole number of bytes"), "{err}");
+
+        let err = parse_byte_size("100000000TB").unwrap_err().to_string();
+        assert!(err.contains("too large"), "{err}");
+
+        for malformed in ["", "MB", ".5MB", "5.MB", "1.2.3MB"] {
+            assert!(parse_byte_size(malformed).is_err(), "{malformed}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_head_many() {
+        use crate::memory::InMemory;
+
+        let store = InMemory::new();
+        for i in 0..5 {
+            let data = vec![0; i];
+            let path = Path::from(format!("file{i}"));
+            store.put(&path, data.into()).await.unwrap();
+        }
+
+        let mut paths: Vec<_> = (0..5).map(|i| Path::from(format!("file{i}"))).collect();
+        paths.insert(2, Path::from("missing"));
+
+        let results: Vec<_> = head_many(&store, paths.clone(), 3).collect().await;
+        assert_eq!(results.len(), 6);
+        for (result, path) in results.into_iter().zip(&paths) {
+            match result {
+                Ok((p, meta)) => {
+                    assert_eq!(&p, path);
+                    assert_eq!(&meta.location, path);
+                    assert_eq!(meta.size, p.as_ref()[4..].parse::<usize>().unwrap());
+                }
+                Err(Error::NotFound { .. }) => assert_eq!(path.as_ref(), "missing"),
+                Err(e) => panic!("{e}"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_range_prefix() {
+        let prefix = |start: &str, end: &str| {
+            range_prefix(&Path::from(start), &Path::from(end)).map(|p| p.to_string())
+        };
+        assert_eq!(prefix("a/b/c", "a/b/d").as_deref(), Some("a/b"));
+        assert_eq!(prefix("a/bc", "a/bd").as_deref(), Some("a"));
+        assert_eq!(prefix("a/b/c", "b").as_deref(), None);
+        assert_eq!(prefix("", "a/b").as_deref(), None);
+        // `a/b!` is within `[a/b, a/b/c)` but not under the prefix `a/b`
+        assert_eq!(prefix("a/b", "a/b/c").as_deref(), Some("a"));
+        assert_eq!(prefix("a/b/c", "a/b").as_deref(), Some("a"));
+    }
+
+    #[tokio::test]
+    async fn test_list_range_offset() {
+        let store = crate::memory::InMemory::new();
+        for path in ["a/b", "a/b!", "a/b/c", "a/b/d", "a/c", "b"] {
+            store.put(&Path::from(path), "data".into()).await.unwrap();
+        }
+        let range = |start: &str, end: &str| {
+            store
+                .list_range(&Path::from(start), &Path::from(end))
+                .map_ok(|x| x.location.to_string())
+                .try_collect::<Vec<_>>()
+        };
+        assert_eq!(
+            range("a/b", "a/b/d").await.unwrap(),
+            ["a/b", "a/b!", "a/b/c"]
+        );
+        assert_eq!(
+            range("a/b!", "a/c").await.unwrap(),
+            ["a/b!", "a/b/c", "a/b/d"]
+        );
+        assert_eq!(range("", "a/b!").await.unwrap(), ["a/b"]);
+        assert!(range("b", "a").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_list_many() {
+        use crate::memory::InMemory;
+
+        let store = InMemory::new();
+        let mut expected = vec![];
+        for prefix in ["a", "b", "c", "d"] {
+            for i in 0..3 {
+                let path = Path::from(format!("{prefix}/{i}"));
+                store.put(&path, "data".into()).await.unwrap();
+                if prefix != "d" {
+                    expected.push(path);
+                }
+            }
+        }
+
+        let prefixes = ["a", "b", "c"].map(Path::from);
+        let mut listed: Vec<_> = list_many(&store, &prefixes, 2, false)
+            .map_ok(|o| o.location)
+            .try_collect()
+            .await
+            .unwrap();
+        listed.sort_unstable();
+        assert_eq!(listed, expected);
+
+        // Overlapping prefixes
+        let prefixes = ["a", "b", "a"].map(Path::from);
+        let listed: Vec<_> = list_many(&store, &prefixes, 3, false)
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(listed.len(), 9);
+
+        let mut listed: Vec<_> = list_many(&store, &prefixes, 3, true)
+            .map_ok(|o| o.location)
+            .try_collect()
+            .await
+            .unwrap();
+        listed.sort_unstable();
+        assert_eq!(listed, expected[..6]);
+    }
+
+    #[tokio::test]
+    async fn test_copy_prefix() {
+        use crate::memory::InMemory;
+
+        let store = InMemory::new();
+        for path in ["a/1", "a/2", "a/nested/3", "ab/4"] {
+            store.put(&Path::from(path), path.into()).await.unwrap();
+        }
+
+        async fn list(store: &InMemory, prefix: &str) -> Vec<String> {
+            let prefix = Path::from(prefix);
+            let mut listed: Vec<_> = store
+                .list(Some(&prefix))
+                .map_ok(|x| x.location.to_string())
+                .try_collect()
+                .await
+                .unwrap();
+            listed.sort_unstable();
+            listed
+        }
+
+        let result = copy_prefix(&store, &Path::from("a"), &Path::from("b/c"), 2)
+            .await
+            .unwrap();
+        assert!(result.is_complete());
+        assert_eq!(result.copied.len(), 3);
+        assert_eq!(
+            list(&store, "b/c").await,
+            ["b/c/1", "b/c/2", "b/c/nested/3"]
+        );
+        assert_eq!(list(&store, "a").await, ["a/1", "a/2", "a/nested/3"]);
+        let copied = store.get(&Path::from("b/c/nested/3")).await.unwrap();
+        assert_eq!(copied.bytes().await.unwrap().as_ref(), b"a/nested/3");
+
+        let result = rename_prefix(&store, &Path::from("a"), &Path::from("d"), 2)
+            .await
+            .unwrap();
+        assert_eq!(result.copied.len(), 3);
+        assert_eq!(list(&store, "d").await, ["d/1", "d/2", "d/nested/3"]);
+        assert!(list(&store, "a").await.is_empty());
+        assert_eq!(list(&store, "ab").await, ["ab/4"]);
+
+        for (from, to) in [("d", "d/e"), ("d/e", "d"), ("d", "d")] {
+            let err = copy_prefix(&store, &Path::from(from), &Path::from(to), 2)
+                .await
+                .unwrap_err();
+            assert!(err.to_string().contains("overlapping"), "{err}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_list_sorted() {
+        use crate::memory::InMemory;
+
+        let store = InMemory::new();
+        for key in ["a/0", "a/b/1", "a/c", "b/0", "b/a", "c/0", "c/1/2", "d/0"] {
+            store.put(&Path::from(key), "data".into()).await.unwrap();
+        }
+        async fn list(s: BoxStream<'_, Result<ObjectMeta>>) -> Result<Vec<String>> {
+            s.map_ok(|o| o.location.to_string()).try_collect().await
+        }
+
+        let prefixes = ["c", "a", "b", "a/b"].map(Path::from);
+        let listed = list(list_many_sorted(&store, &prefixes, false))
+            .await
+            .unwrap();
+        let expected = ["a/0", "a/b/1", "a/b/1", "a/c", "b/0", "b/a", "c/0", "c/1/2"];
+        assert_eq!(listed, expected);
+
+        let listed = list(list_many_sorted(&store, &prefixes, true))
+            .await
+            .unwrap();
+        let expected = ["a/0", "a/b/1", "a/c", "b/0", "b/a", "c/0", "c/1/2"];
+        assert_eq!(listed, expected);
+
+        let split_points = sub_prefixes(None, "dcba");
+        let listed = list(list_parallel_sorted(&store, None, &split_points))
+            .await
+            .unwrap();
+        let mut expected = listed.clone();
+        expected.sort_unstable();
+        assert_eq!(listed, expected);
+        assert_eq!(listed.len(), 8);
+
+        // Errors from any source are returned
+        let err = || futures::stream::iter([Err(crate::Error::NotImplemented)]).boxed();
+        let streams = vec![store.list(None), err()];
+        let results: Vec<_> = merge_sorted(streams, false).collect().await;
+        assert!(matches!(results[0], Err(crate::Error::NotImplemented)));
+        assert_eq!(results.len(), 9);
+    }
+
+    #[tokio::test]
+    async fn test_list_parallel() {
+        use crate::memory::InMemory;
+
+        let store = InMemory::new();
+        let keys = [
+            "data/0a",
+            "data/0b",
+            "data/3",
+            "data/4",
+            "data/4/nested",
+            "data/7f",
+            "data/a",
+            "data/ff",
+            "data/Z",
+            "data/_",
+            "other/1",
+        ];
+        for key in keys {
+            store.put(&Path::from(key), "data".into()).await.unwrap();
+        }
+
+        let prefix = Path::from("data");
+        let mut expected: Vec<_> = store
+            .list(Some(&prefix))
+            .map_ok(|o| o.location)
+            .try_collect()
+            .await
+            .unwrap();
+        expected.sort_unstable();
+        assert_eq!(expected.len(), 10);
+
+        for alphabet in [HEX_ALPHABET, ALPHANUMERIC_ALPHABET, ""] {
+            let split_points = sub_prefixes(Some(&prefix), alphabet);
+            let mut listed: Vec<_> = list_parallel(&store, Some(&prefix), &split_points, 4)
+                .map_ok(|o| o.location)
+                .try_collect()
+                .await
+                .unwrap();
+            listed.sort_unstable();
+            assert_eq!(listed, expected, "{alphabet}");
+        }
+
+        let split_points = sub_prefixes(None, HEX_ALPHABET);
+        assert_eq!(split_points[10], Path::from("a"));
+        let listed: Vec<_> = list_parallel(&store, None, &split_points, 4)
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(listed.len(), keys.len());
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[tokio::test]
+    async fn test_list_parallel_unordered() {
+        use crate::local::LocalFileSystem;
+
+        // LocalFileSystem lists objects in directory order, not lexicographic order
+        let root = tempfile::TempDir::new().unwrap();
+        let store = LocalFileSystem::new_with_prefix(root.path()).unwrap();
+        for i in (0..256_u32).rev() {
+            let location = Path::from(format!("data/{:02x}", i.wrapping_mul(167) % 256));
+            store.put(&location, "data".into()).await.unwrap();
+        }
+
+        let prefix = Path::from("data");
+        let mut expected: Vec<_> = store
+            .list(Some(&prefix))
+            .map_ok(|o| o.location)
+            .try_collect()
+            .await
+            .unwrap();
+        expected.sort_unstable();
+        assert_eq!(expected.len(), 256);
+
+        let split_points = sub_prefixes(Some(&prefix), HEX_ALPHABET);
+        let mut listed: Vec<_> = list_parallel(&store, Some(&prefix), &split_points, 4)
+            .map_ok(|o| o.location)
+            .try_collect()
+            .await
+            .unwrap();
+        listed.sort_unstable();
+        assert_eq!(listed, expected);
+    }
 }